@@ -1,6 +1,11 @@
 use std::env;
 
 fn main() {
+    // 无头模式（未启用 gui feature）不需要 Tauri 构建步骤
+    if env::var("CARGO_FEATURE_GUI").is_err() {
+        return;
+    }
+
     // Workaround for tauri-build 2.5.3 compatibility issue
     // tauri-build expects DEP_TAURI_DEV env var from tauri crate
     // Set it before calling tauri_build::build()
@@ -0,0 +1,268 @@
+//! 局域网访问控制
+//!
+//! 局域网共享模式下反代服务监听所有网卡，按默认拒绝策略放行：本机、白名单 IP / 网段、
+//! 携带已登记设备令牌（`x-device-token`）的客户端。被拒绝的连接写入审计日志，
+//! 可通过 `/api/admin/access` 查看。Admin API 始终只允许本机访问。
+
+use std::collections::VecDeque;
+use std::net::{IpAddr, SocketAddr};
+
+use axum::{
+    body::Body,
+    extract::ConnectInfo,
+    http::{Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use chrono::{DateTime, Utc};
+use ipnet::IpNet;
+use lazy_static::lazy_static;
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+
+use crate::anthropic::types::ErrorResponse;
+use crate::common::auth::constant_time_eq;
+use crate::logs::LOG_COLLECTOR;
+use crate::model::config::LanAccessConfig;
+
+/// 设备令牌请求头
+pub const DEVICE_TOKEN_HEADER: &str = "x-device-token";
+
+/// 审计日志保留的拒绝记录数
+const MAX_REJECTIONS: usize = 200;
+
+/// 被拒绝的连接
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RejectedConnection {
+    pub ip: String,
+    pub path: String,
+    pub reason: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// 白名单条目（单个 IP 或网段）
+fn parse_network(entry: &str) -> Option<IpNet> {
+    let entry = entry.trim();
+    entry
+        .parse::<IpNet>()
+        .ok()
+        .or_else(|| entry.parse::<IpAddr>().ok().map(IpNet::from))
+}
+
+/// 访问控制器
+pub struct AccessControl {
+    config: RwLock<LanAccessConfig>,
+    networks: RwLock<Vec<IpNet>>,
+    rejections: Mutex<VecDeque<RejectedConnection>>,
+}
+
+impl AccessControl {
+    pub fn new() -> Self {
+        Self {
+            config: RwLock::new(LanAccessConfig::default()),
+            networks: RwLock::new(Vec::new()),
+            rejections: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// 替换访问控制配置（立即生效）
+    pub fn set_config(&self, config: LanAccessConfig) {
+        *self.networks.write() = config.allowed_ips.iter().filter_map(|e| parse_network(e)).collect();
+        *self.config.write() = config;
+    }
+
+    /// 是否启用局域网共享
+    pub fn enabled(&self) -> bool {
+        self.config.read().enabled
+    }
+
+    /// 检查客户端是否允许访问，拒绝时返回原因
+    ///
+    /// `admin` 表示访问 Admin API：无论白名单如何都只允许本机
+    pub fn check(&self, ip: IpAddr, device_token: Option<&str>, admin: bool) -> Result<(), &'static str> {
+        let config = self.config.read();
+        if !config.enabled || is_loopback(ip) {
+            return Ok(());
+        }
+        if admin {
+            return Err("Admin API 只允许本机访问");
+        }
+        if self.networks.read().iter().any(|net| net.contains(&ip)) {
+            return Ok(());
+        }
+        match device_token {
+            Some(token) if config.devices.iter().any(|d| constant_time_eq(&d.token, token)) => Ok(()),
+            Some(_) => Err("设备令牌无效"),
+            None => Err("不在白名单中"),
+        }
+    }
+
+    /// 记录被拒绝的连接
+    pub fn record_rejection(&self, ip: IpAddr, path: &str, reason: &str) {
+        tracing::warn!("[访问控制] 拒绝 {} 访问 {}: {}", ip, path, reason);
+        LOG_COLLECTOR.add_log("WARN", &format!("🚫 拒绝 {} 访问 {}: {}", ip, path, reason));
+
+        let mut rejections = self.rejections.lock();
+        if rejections.len() >= MAX_REJECTIONS {
+            rejections.pop_front();
+        }
+        rejections.push_back(RejectedConnection {
+            ip: ip.to_string(),
+            path: path.to_string(),
+            reason: reason.to_string(),
+            timestamp: Utc::now(),
+        });
+    }
+
+    /// 最近被拒绝的连接（最新的在前）
+    pub fn rejections(&self) -> Vec<RejectedConnection> {
+        self.rejections.lock().iter().rev().cloned().collect()
+    }
+
+    /// 清空审计日志
+    pub fn clear_rejections(&self) {
+        self.rejections.lock().clear();
+    }
+}
+
+impl Default for AccessControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+lazy_static! {
+    /// 全局访问控制器
+    pub static ref ACCESS_CONTROL: AccessControl = AccessControl::new();
+}
+
+/// 本机地址（含 IPv4 映射的 IPv6 回环地址）
+fn is_loopback(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V6(v6) => v6.is_loopback() || v6.to_ipv4_mapped().is_some_and(|v4| v4.is_loopback()),
+        IpAddr::V4(v4) => v4.is_loopback(),
+    }
+}
+
+/// 生成设备令牌
+pub fn generate_device_token() -> String {
+    format!("kgd-{}", uuid::Uuid::new_v4().simple())
+}
+
+/// 校验访问控制配置，并为未填写令牌的设备生成令牌
+pub fn prepare(config: &mut LanAccessConfig) -> Result<(), String> {
+    for entry in &config.allowed_ips {
+        if parse_network(entry).is_none() {
+            return Err(format!("无效的 IP 或网段: {}", entry));
+        }
+    }
+    for device in &mut config.devices {
+        if device.name.trim().is_empty() {
+            return Err("设备名称不能为空".to_string());
+        }
+        if device.token.trim().is_empty() {
+            device.token = generate_device_token();
+        }
+    }
+    Ok(())
+}
+
+/// 访问控制中间件
+pub async fn access_control_middleware(request: Request<Body>, next: Next) -> Response {
+    if !ACCESS_CONTROL.enabled() {
+        return next.run(request).await;
+    }
+
+    let Some(ip) = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())
+    else {
+        // 路由未通过 into_make_service_with_connect_info 启动时无法得知来源，拒绝而不是放行
+        tracing::error!("访问控制无法获取客户端地址，已拒绝请求: {}", request.uri().path());
+        return (
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse::new(
+                "permission_error",
+                "Access denied: client address unavailable",
+            )),
+        )
+            .into_response();
+    };
+
+    let path = request.uri().path().to_string();
+    let device_token = request
+        .headers()
+        .get(DEVICE_TOKEN_HEADER)
+        .and_then(|v| v.to_str().ok());
+
+    match ACCESS_CONTROL.check(ip, device_token, path.starts_with("/api/admin")) {
+        Ok(()) => next.run(request).await,
+        Err(reason) => {
+            ACCESS_CONTROL.record_rejection(ip, &path, reason);
+            (
+                StatusCode::FORBIDDEN,
+                Json(ErrorResponse::new(
+                    "permission_error",
+                    format!("Access denied for {}", ip),
+                )),
+            )
+                .into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::config::LanDevice;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_default_deny_with_allowlist_and_device_tokens() {
+        let access = AccessControl::new();
+        let mut config = LanAccessConfig {
+            enabled: true,
+            allowed_ips: vec!["192.168.1.0/24".to_string(), "10.0.0.5".to_string()],
+            devices: vec![LanDevice {
+                name: "laptop".to_string(),
+                token: String::new(),
+            }],
+        };
+        prepare(&mut config).unwrap();
+        let token = config.devices[0].token.clone();
+        assert!(token.starts_with("kgd-"));
+        access.set_config(config);
+
+        assert!(access.check(ip("127.0.0.1"), None, true).is_ok());
+        assert!(access.check(ip("::ffff:127.0.0.1"), None, false).is_ok());
+        assert!(access.check(ip("192.168.1.77"), None, false).is_ok());
+        assert!(access.check(ip("10.0.0.5"), None, false).is_ok());
+        assert!(access.check(ip("10.0.0.6"), None, false).is_err());
+        assert!(access.check(ip("10.0.0.6"), Some("wrong"), false).is_err());
+        assert!(access.check(ip("10.0.0.6"), Some(&token), false).is_ok());
+        // Admin API 只允许本机
+        assert!(access.check(ip("192.168.1.77"), Some(&token), true).is_err());
+    }
+
+    #[test]
+    fn test_disabled_allows_all_and_rejections_are_bounded() {
+        let access = AccessControl::new();
+        assert!(access.check(ip("8.8.8.8"), None, true).is_ok());
+
+        for _ in 0..MAX_REJECTIONS + 5 {
+            access.record_rejection(ip("8.8.8.8"), "/v1/messages", "不在白名单中");
+        }
+        assert_eq!(access.rejections().len(), MAX_REJECTIONS);
+
+        let mut invalid = LanAccessConfig {
+            allowed_ips: vec!["192.168.1.0/33".to_string()],
+            ..LanAccessConfig::default()
+        };
+        assert!(prepare(&mut invalid).is_err());
+    }
+}
@@ -0,0 +1,49 @@
+//! 危险操作的二次确认
+//!
+//! 重置/恢复系统机器码等不可撤销的操作需先以 `dryRun=true` 预览，预览结果中带有一次性确认令牌，
+//! 真正执行时须携带该令牌。令牌与操作及预览时计划写入的值绑定，只能使用一次，过期作废。
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+
+/// 确认令牌有效期
+pub const TOKEN_TTL: Duration = Duration::from_secs(300);
+
+struct Pending {
+    operation: &'static str,
+    value: String,
+    issued_at: Instant,
+}
+
+lazy_static! {
+    static ref PENDING: Mutex<HashMap<String, Pending>> = Mutex::new(HashMap::new());
+}
+
+/// 为操作签发确认令牌，`value` 为执行时要写入的值
+pub fn issue(operation: &'static str, value: String) -> String {
+    let token = uuid::Uuid::new_v4().simple().to_string();
+    let mut pending = PENDING.lock();
+    pending.retain(|_, p| p.issued_at.elapsed() <= TOKEN_TTL);
+    pending.insert(
+        token.clone(),
+        Pending {
+            operation,
+            value,
+            issued_at: Instant::now(),
+        },
+    );
+    token
+}
+
+/// 核销确认令牌，返回预览时计划写入的值（令牌无效、过期或操作不符时返回 None）
+pub fn take(token: &str, operation: &str) -> Option<String> {
+    let mut pending = PENDING.lock();
+    let entry = pending.remove(token)?;
+    if entry.operation != operation || entry.issued_at.elapsed() > TOKEN_TTL {
+        return None;
+    }
+    Some(entry.value)
+}
@@ -4,6 +4,8 @@ use std::fmt;
 
 use axum::http::StatusCode;
 
+use crate::error_code::ErrorCode;
+
 use super::types::AdminErrorResponse;
 
 /// Admin 服务错误类型
@@ -51,13 +53,16 @@ impl AdminServiceError {
     /// 转换为 API 错误响应
     pub fn into_response(self) -> AdminErrorResponse {
         match &self {
-            AdminServiceError::NotFound { .. } => AdminErrorResponse::not_found(self.to_string()),
+            AdminServiceError::NotFound { .. } => {
+                AdminErrorResponse::not_found(self.to_string()).with_code(ErrorCode::CredentialNotFound)
+            }
             AdminServiceError::UpstreamError(_) => AdminErrorResponse::api_error(self.to_string()),
             AdminServiceError::InternalError(_) => {
                 AdminErrorResponse::internal_error(self.to_string())
             }
             AdminServiceError::InvalidCredential(_) => {
                 AdminErrorResponse::invalid_request(self.to_string())
+                    .with_code(ErrorCode::CredentialInvalid)
             }
         }
     }
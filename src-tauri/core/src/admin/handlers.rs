@@ -0,0 +1,2897 @@
+//! Admin API HTTP 处理器
+
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    response::IntoResponse,
+};
+
+use super::{
+    middleware::AdminState,
+    service::Redaction,
+    types::{AddCredentialRequest, AdminErrorResponse, RevealQuery, SetDisabledRequest, SuccessResponse},
+};
+use crate::common::redact::mask_secret;
+use crate::group_budgets::GROUP_BUDGETS;
+use crate::proxy_lifecycle::ProxyState;
+
+/// 查看明文凭证时携带 Admin API Key 的请求头
+pub const REVEAL_KEY_HEADER: &str = "x-reveal-key";
+
+/// GET /api/admin/credentials
+/// 获取所有凭证状态
+/// 默认遮蔽 Token；`?reveal=true` 且 `x-reveal-key` 请求头为 Admin API Key 时返回明文
+pub async fn get_all_credentials(
+    State(state): State<AdminState>,
+    Query(query): Query<RevealQuery>,
+    headers: axum::http::HeaderMap,
+) -> impl IntoResponse {
+    use crate::common::auth::constant_time_eq;
+
+    let redaction = if query.reveal {
+        let authorized = headers
+            .get(REVEAL_KEY_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|key| constant_time_eq(key, &state.admin_api_key.read()));
+        if !authorized {
+            let error = AdminErrorResponse::new(
+                "permission_error",
+                format!("查看明文凭证需要在 {} 请求头中提供 Admin API Key", REVEAL_KEY_HEADER),
+            );
+            return (axum::http::StatusCode::FORBIDDEN, Json(error)).into_response();
+        }
+        Redaction::Revealed
+    } else {
+        Redaction::Redacted
+    };
+
+    Json(state.service.get_all_credentials(redaction)).into_response()
+}
+
+/// POST /api/admin/credentials/:id/disabled
+/// 设置凭证禁用状态
+pub async fn set_credential_disabled(
+    State(state): State<AdminState>,
+    Path(id): Path<u64>,
+    Json(payload): Json<SetDisabledRequest>,
+) -> impl IntoResponse {
+    match state.service.set_disabled(id, payload.disabled) {
+        Ok(_) => {
+            let action = if payload.disabled { "禁用" } else { "启用" };
+            Json(SuccessResponse::new(format!("凭证 #{} 已{}", id, action))).into_response()
+        }
+        Err(e) => (e.status_code(), Json(e.into_response())).into_response(),
+    }
+}
+
+/// POST /api/admin/credentials/:id/reset
+/// 重置失败计数并重新启用
+pub async fn reset_failure_count(
+    State(state): State<AdminState>,
+    Path(id): Path<u64>,
+) -> impl IntoResponse {
+    match state.service.reset_and_enable(id) {
+        Ok(_) => Json(SuccessResponse::new(format!(
+            "凭证 #{} 已重置并启用",
+            id
+        )))
+        .into_response(),
+        Err(e) => (e.status_code(), Json(e.into_response())).into_response(),
+    }
+}
+
+/// POST /api/admin/credentials/:id/machine-id
+/// 为凭证重新生成随机 machineId
+pub async fn regenerate_credential_machine_id(
+    State(state): State<AdminState>,
+    Path(id): Path<u64>,
+) -> impl IntoResponse {
+    match state.service.regenerate_machine_id(id) {
+        Ok(machine_id) => Json(super::types::RegenerateMachineIdResponse {
+            success: true,
+            message: format!("凭证 #{} 已重新生成 machineId", id),
+            machine_id,
+        })
+        .into_response(),
+        Err(e) => (e.status_code(), Json(e.into_response())).into_response(),
+    }
+}
+
+/// GET /api/admin/credentials/:id/balance
+/// 获取指定凭证的余额
+pub async fn get_credential_balance(
+    State(state): State<AdminState>,
+    Path(id): Path<u64>,
+) -> impl IntoResponse {
+    match state.service.get_balance(id).await {
+        Ok(response) => Json(response).into_response(),
+        Err(e) => (e.status_code(), Json(e.into_response())).into_response(),
+    }
+}
+
+/// GET /api/admin/credentials/:id/usage-history
+/// 获取指定凭证的额度快照时间线（可选 `?hours=N` 只返回最近 N 小时）
+pub async fn get_credential_usage_history(
+    State(state): State<AdminState>,
+    Path(id): Path<u64>,
+    Query(query): Query<super::types::UsageHistoryQuery>,
+) -> impl IntoResponse {
+    match state.service.get_usage_history(id, query.hours) {
+        Ok(response) => Json(response).into_response(),
+        Err(e) => (e.status_code(), Json(e.into_response())).into_response(),
+    }
+}
+
+/// POST /api/admin/credentials
+/// 添加新凭证
+pub async fn add_credential(
+    State(state): State<AdminState>,
+    Json(payload): Json<AddCredentialRequest>,
+) -> impl IntoResponse {
+    match state.service.add_credential(payload).await {
+        Ok(response) => Json(response).into_response(),
+        Err(e) => (e.status_code(), Json(e.into_response())).into_response(),
+    }
+}
+
+/// PUT /api/admin/credentials/:id
+/// 编辑凭证（认证方式、OIDC Client、代理、分组、替换 refreshToken）
+pub async fn update_credential(
+    State(state): State<AdminState>,
+    Path(id): Path<u64>,
+    Json(payload): Json<super::types::UpdateCredentialRequest>,
+) -> impl IntoResponse {
+    if let Some(auth_method) = &payload.auth_method {
+        if !["social", "idc", "builder-id"].contains(&auth_method.as_str()) {
+            let error = super::types::AdminErrorResponse::invalid_request(format!("不支持的认证方式: {}", auth_method));
+            return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+        }
+    }
+    if let Some(group_id) = &payload.group_id {
+        let config = state.config.lock();
+        if !config.groups.iter().any(|g| &g.id == group_id) {
+            let error = super::types::AdminErrorResponse::not_found(format!("分组 '{}' 不存在", group_id));
+            return (axum::http::StatusCode::NOT_FOUND, Json(error)).into_response();
+        }
+    }
+
+    match state.service.update_credential(id, payload).await {
+        Ok(()) => Json(SuccessResponse::new(format!("凭证 #{} 已更新", id))).into_response(),
+        Err(e) => (e.status_code(), Json(e.into_response())).into_response(),
+    }
+}
+
+/// DELETE /api/admin/credentials/:id
+/// 删除凭证
+pub async fn delete_credential(
+    State(state): State<AdminState>,
+    Path(id): Path<u64>,
+) -> impl IntoResponse {
+    match state.service.delete_credential(id) {
+        Ok(_) => Json(SuccessResponse::new(format!("凭证 #{} 已删除", id))).into_response(),
+        Err(e) => (e.status_code(), Json(e.into_response())).into_response(),
+    }
+}
+
+/// POST /api/admin/credentials/:id/refresh
+/// 刷新单个凭证（刷新 Token + 更新余额）
+pub async fn refresh_credential(
+    State(state): State<AdminState>,
+    Path(id): Path<u64>,
+) -> impl IntoResponse {
+    match state.service.refresh_credential(id).await {
+        Ok(response) => Json(response).into_response(),
+        Err(e) => (e.status_code(), Json(e.into_response())).into_response(),
+    }
+}
+
+/// POST /api/admin/credentials/refresh-all
+/// 批量刷新凭证（支持指定 ID 列表）
+pub async fn refresh_all_credentials(
+    State(state): State<AdminState>,
+    Json(payload): Json<super::types::RefreshBatchRequest>,
+) -> impl IntoResponse {
+    match state.service.refresh_credentials(payload.ids.unwrap_or_default()).await {
+        Ok(response) => Json(response).into_response(),
+        Err(e) => (e.status_code(), Json(e.into_response())).into_response(),
+    }
+}
+
+/// POST /api/admin/credentials/import
+/// 批量导入凭证
+pub async fn import_credentials(
+    State(state): State<AdminState>,
+    Json(payload): Json<super::types::ImportCredentialsRequest>,
+) -> impl IntoResponse {
+    let group_rules = state.config.lock().group_rules.clone();
+    match state.service.import_credentials(payload.credentials, &group_rules).await {
+        Ok(response) => Json(response).into_response(),
+        Err(e) => (e.status_code(), Json(e.into_response())).into_response(),
+    }
+}
+
+/// POST /api/admin/credentials/import-text
+/// 从粘贴的纯文本（每行一个 refreshToken）或 CSV 批量导入凭证
+pub async fn import_credentials_text(
+    State(state): State<AdminState>,
+    Json(payload): Json<super::types::ImportTextRequest>,
+) -> impl IntoResponse {
+    let items = match super::text_import::parse_import_text(&payload.text, payload.format, &payload.group_id) {
+        Ok(items) => items,
+        Err(e) => {
+            let error = super::types::AdminErrorResponse::invalid_request(e);
+            return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+        }
+    };
+    if items.is_empty() {
+        let error = super::types::AdminErrorResponse::invalid_request("未找到可导入的凭证");
+        return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+
+    let group_rules = state.config.lock().group_rules.clone();
+    match state.service.import_credentials(items, &group_rules).await {
+        Ok(response) => Json(response).into_response(),
+        Err(e) => (e.status_code(), Json(e.into_response())).into_response(),
+    }
+}
+
+/// POST /api/admin/credentials/validate
+/// 批量验证凭证（刷新 Token 并查询额度），不添加到凭证列表
+pub async fn validate_credentials(
+    State(state): State<AdminState>,
+    Json(payload): Json<super::types::ValidateCredentialsRequest>,
+) -> impl IntoResponse {
+    Json(state.service.validate_credentials(payload.credentials).await)
+}
+
+/// GET /api/admin/logs
+/// 获取运行日志，`?groupId=` 只返回该分组的请求/响应日志
+pub async fn get_logs(Query(query): Query<super::types::LogsQuery>) -> impl IntoResponse {
+    use crate::logs::LOG_COLLECTOR;
+    let logs = match query.group_id.as_deref() {
+        Some(group_id) => LOG_COLLECTOR.get_logs_for_group(group_id),
+        None => LOG_COLLECTOR.get_logs(),
+    };
+    Json(serde_json::json!({
+        "logs": logs,
+        "total": logs.len()
+    }))
+}
+
+/// GET /api/admin/events
+/// 实时事件流（SSE）：新日志、凭证状态变化、反代启停
+///
+/// 连接建立后先推送一次当前反代状态
+pub async fn admin_events(State(state): State<AdminState>) -> impl IntoResponse {
+    use crate::events::{AdminEvent, EVENT_BUS};
+    use axum::response::sse::{Event, KeepAlive, Sse};
+    use futures::{StreamExt, stream};
+    use tokio::sync::broadcast::error::RecvError;
+
+    let initial = AdminEvent::Proxy {
+        status: state.proxy.snapshot(),
+    };
+    let updates = stream::unfold(EVENT_BUS.subscribe(), |mut rx| async move {
+        let event = match rx.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(skipped)) => AdminEvent::Lagged { skipped },
+            Err(RecvError::Closed) => return None,
+        };
+        Some((event, rx))
+    });
+
+    let events = stream::once(async { initial })
+        .chain(updates)
+        .map(|event| Event::default().json_data(&event));
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// GET /api/admin/log-level
+/// 获取当前日志过滤器
+pub async fn get_log_level() -> impl IntoResponse {
+    Json(super::types::LogLevelResponse {
+        filter: crate::log_level::current_filter(),
+    })
+}
+
+/// POST /api/admin/log-level
+/// 运行时调整日志级别（替换全局级别与模块指令，重启后恢复默认）
+pub async fn set_log_level(
+    Json(payload): Json<super::types::SetLogLevelRequest>,
+) -> impl IntoResponse {
+    match crate::log_level::set_filter(&payload.level, &payload.modules) {
+        Ok(filter) => Json(super::types::LogLevelResponse {
+            filter: Some(filter),
+        })
+        .into_response(),
+        Err(msg) => {
+            let error = super::types::AdminErrorResponse::invalid_request(msg);
+            (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response()
+        }
+    }
+}
+
+/// POST /api/admin/logs/clear
+/// 清空日志
+pub async fn clear_logs() -> impl IntoResponse {
+    use crate::logs::LOG_COLLECTOR;
+    LOG_COLLECTOR.clear();
+    Json(super::types::SuccessResponse::new("日志已清空"))
+}
+
+/// GET /api/admin/logs/files
+/// 列出有日志文件的日期
+pub async fn get_log_files() -> impl IntoResponse {
+    use crate::logs::LOG_FILE_SINK;
+
+    Json(super::types::LogFilesResponse {
+        directory: LOG_FILE_SINK.dir().map(|d| d.display().to_string()),
+        days: LOG_FILE_SINK.days(),
+    })
+}
+
+/// GET /api/admin/logs/archive/{date}
+/// 下载某一天的日志归档（JSON Lines，gzip 压缩）
+pub async fn download_log_archive(Path(date): Path<String>) -> impl IntoResponse {
+    use crate::logs::LOG_FILE_SINK;
+
+    let Ok(day) = chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d") else {
+        let error = super::types::AdminErrorResponse::invalid_request(format!(
+            "日期格式无效: {}（应为 YYYY-MM-DD）",
+            date
+        ));
+        return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+    };
+
+    match LOG_FILE_SINK.archive(day) {
+        Ok(Some(archive)) => (
+            [
+                (axum::http::header::CONTENT_TYPE, "application/gzip".to_string()),
+                (
+                    axum::http::header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"kiro-gateway-{}.jsonl.gz\"", day),
+                ),
+            ],
+            archive,
+        )
+            .into_response(),
+        Ok(None) => {
+            let error = super::types::AdminErrorResponse::not_found(format!("{} 没有日志文件", day));
+            (axum::http::StatusCode::NOT_FOUND, Json(error)).into_response()
+        }
+        Err(e) => {
+            let error = super::types::AdminErrorResponse::internal_error(format!("打包日志失败: {}", e));
+            (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// GET /api/admin/config
+/// 获取当前配置
+pub async fn get_config(State(state): State<AdminState>) -> impl IntoResponse {
+    use crate::model::config::Config;
+    use super::types::GetConfigResponse;
+    
+    // 获取配置文件路径
+    let config_path = state.config_path.as_path();
+    
+    match Config::load(config_path) {
+        Ok(config) => {
+            let response = GetConfigResponse {
+                host: config.host,
+                listen_hosts: config.listen_hosts,
+                port: config.port,
+                proxy_port: config.proxy_port,
+                api_key: config.api_key,
+                region: config.region,
+                auto_refresh_enabled: config.auto_refresh_enabled,
+                auto_refresh_interval_minutes: config.auto_refresh_interval_minutes,
+                locked_model: config.locked_model,
+                machine_id_backup: config.machine_id_backup,
+                maintenance_windows: config.maintenance_windows,
+                routing_strategy: config.routing_strategy,
+                proxy_drain_timeout_secs: config.proxy_drain_timeout_secs,
+                max_concurrent_per_credential: config.max_concurrent_per_credential,
+                request_queue_size: config.request_queue_size,
+                request_queue_timeout_secs: config.request_queue_timeout_secs,
+                tls: config.tls,
+                cors: config.cors,
+                autostart: config.autostart,
+            };
+            Json(serde_json::json!(response)).into_response()
+        }
+        Err(e) => {
+            let error = super::types::AdminErrorResponse::internal_error(format!("读取配置失败: {}", e));
+            (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// 配置脱敏：隐藏主密钥和租户 Key
+fn masked_config(config: &crate::model::config::Config) -> serde_json::Value {
+    let mut value = serde_json::to_value(config).unwrap_or_default();
+    for field in ["apiKey", "adminApiKey"] {
+        if let Some(api_key) = value.get_mut(field) {
+            if let Some(key) = api_key.as_str() {
+                *api_key = serde_json::Value::String(mask_secret(key));
+            }
+        }
+    }
+    if let Some(keys) = value.get_mut("apiKeys").and_then(|v| v.as_array_mut()) {
+        for entry in keys {
+            if let Some(key) = entry.get_mut("key") {
+                if let Some(k) = key.as_str() {
+                    *key = serde_json::Value::String(mask_secret(k));
+                }
+            }
+        }
+    }
+    value
+}
+
+/// GET /api/admin/config/effective
+/// 获取实际生效的配置（内存配置 + 运行时状态），用于排查设置未生效的问题
+pub async fn get_effective_config(State(state): State<AdminState>) -> impl IntoResponse {
+    use crate::model::config::Config;
+    use super::types::{EffectiveConfigResponse, EffectiveRuntime};
+
+    let config = state.config.lock().clone();
+
+    // 比较顶层字段，找出内存配置与文件不一致的部分
+    let live = serde_json::to_value(&config).unwrap_or_default();
+    let differs_from_file = match Config::load(state.config_path.as_path()) {
+        Ok(file_config) => {
+            let file = serde_json::to_value(&file_config).unwrap_or_default();
+            live.as_object()
+                .map(|fields| {
+                    fields
+                        .iter()
+                        .filter(|(k, v)| file.get(k.as_str()) != Some(*v))
+                        .map(|(k, _)| k.clone())
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+        Err(e) => {
+            tracing::warn!("读取配置文件失败: {}", e);
+            Vec::new()
+        }
+    };
+
+    let snapshot = state.proxy.snapshot();
+    let runtime = EffectiveRuntime {
+        active_group_id: state.token_manager.get_active_group(),
+        standalone_proxy: state.proxy.is_standalone(),
+        proxy_state: snapshot.state,
+        proxy_bound_port: snapshot.port,
+        log_filter: crate::log_level::current_filter(),
+        maintenance_allowed: crate::maintenance::is_maintenance_allowed(&config.maintenance_windows),
+        locked_model: crate::model_lock::get_locked_model(),
+    };
+
+    let env = ["RUST_LOG"]
+        .into_iter()
+        .filter_map(|name| std::env::var(name).ok().map(|v| (name.to_string(), v)))
+        .collect();
+
+    Json(EffectiveConfigResponse {
+        config: masked_config(&config),
+        differs_from_file,
+        runtime,
+        env,
+    })
+}
+
+/// POST /api/admin/config
+/// 更新配置
+pub async fn update_config(
+    State(state): State<AdminState>,
+    Json(payload): Json<super::types::UpdateConfigRequest>,
+) -> impl IntoResponse {
+    use crate::events::{AdminEvent, EVENT_BUS};
+    use crate::model::config::Config;
+    use super::types::SuccessResponse;
+    
+    let config_path = state.config_path.as_path();
+    
+    // 先读取现有配置
+    let mut config = match Config::load(config_path) {
+        Ok(c) => c,
+        Err(e) => {
+            let error = super::types::AdminErrorResponse::internal_error(format!("读取配置失败: {}", e));
+            return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    };
+    
+    // 更新字段
+    if let Some(host) = payload.host {
+        match crate::listen::normalize_host(&host) {
+            Ok(host) => config.host = host,
+            Err(msg) => {
+                let error = super::types::AdminErrorResponse::invalid_request(msg);
+                return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+            }
+        }
+    }
+    if let Some(listen_hosts) = payload.listen_hosts {
+        match crate::listen::normalize_hosts(&listen_hosts) {
+            Ok(hosts) => config.listen_hosts = hosts,
+            Err(msg) => {
+                let error = super::types::AdminErrorResponse::invalid_request(msg);
+                return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+            }
+        }
+    }
+    if let Some(port) = payload.port {
+        config.port = port;
+    }
+    if let Some(proxy_port) = payload.proxy_port {
+        config.proxy_port = proxy_port;
+    }
+    if let Some(api_key) = payload.api_key {
+        config.api_key = Some(api_key);
+    }
+    if let Some(region) = payload.region {
+        config.region = region;
+    }
+    if let Some(auto_refresh_enabled) = payload.auto_refresh_enabled {
+        config.auto_refresh_enabled = auto_refresh_enabled;
+    }
+    if let Some(auto_refresh_interval_minutes) = payload.auto_refresh_interval_minutes {
+        config.auto_refresh_interval_minutes = auto_refresh_interval_minutes;
+    }
+    if let Some(locked_model) = payload.locked_model {
+        config.locked_model = if locked_model.is_empty() { None } else { Some(locked_model) };
+    }
+    if let Some(maintenance_windows) = payload.maintenance_windows {
+        if let Err(msg) = crate::maintenance::validate_windows(&maintenance_windows) {
+            let error = super::types::AdminErrorResponse::invalid_request(msg);
+            return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+        }
+        config.maintenance_windows = maintenance_windows;
+    }
+    if let Some(routing_strategy) = payload.routing_strategy {
+        config.routing_strategy = routing_strategy;
+    }
+    if let Some(proxy_drain_timeout_secs) = payload.proxy_drain_timeout_secs {
+        config.proxy_drain_timeout_secs = proxy_drain_timeout_secs;
+    }
+    if let Some(max_concurrent_per_credential) = payload.max_concurrent_per_credential {
+        config.max_concurrent_per_credential = max_concurrent_per_credential;
+    }
+    if let Some(request_queue_size) = payload.request_queue_size {
+        config.request_queue_size = request_queue_size;
+    }
+    if let Some(request_queue_timeout_secs) = payload.request_queue_timeout_secs {
+        config.request_queue_timeout_secs = request_queue_timeout_secs;
+    }
+    if let Some(tls) = payload.tls {
+        config.tls = tls;
+    }
+    if let Some(cors) = payload.cors {
+        match crate::cors::normalize(&cors) {
+            Ok(cors) => config.cors = cors,
+            Err(msg) => {
+                let error = super::types::AdminErrorResponse::invalid_request(msg);
+                return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+            }
+        }
+    }
+    let autostart_changed = payload.autostart.is_some_and(|a| a != config.autostart);
+    if let Some(autostart) = payload.autostart {
+        config.autostart = autostart;
+    }
+    // machine_id_backup 应通过 backup API 设置，不通过 updateConfig
+    
+    // 保存设置
+    match config.save(config_path) {
+        Ok(_) => {
+            tracing::info!("设置已更新并保存到: {:?}", config_path);
+            if autostart_changed {
+                // 桌面应用收到事件后注册或移除自启项
+                EVENT_BUS.publish(AdminEvent::Autostart { enabled: config.autostart });
+            }
+            Json(SuccessResponse::new("设置已保存（需要重启服务生效）")).into_response()
+        }
+        Err(e) => {
+            let error = super::types::AdminErrorResponse::internal_error(format!("保存设置失败: {}", e));
+            (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+// ============ 机器码管理 API ============
+
+/// GET /api/admin/machine-id
+/// 获取当前机器码信息（从Windows注册表读取）及备份历史
+pub async fn get_machine_id(State(state): State<AdminState>) -> impl IntoResponse {
+    use crate::model::config::Config;
+    
+    // 从注册表读取机器码
+    let machine_id = get_system_machine_guid();
+    
+    // 从配置文件读取备份
+    let config = Config::load(state.config_path.as_path()).ok();
+    let machine_id_backup = config.as_ref().and_then(|c| c.machine_id_backup.clone());
+    let machine_id_backups = config.map(|c| c.machine_id_backup_history()).unwrap_or_default();
+    
+    Json(serde_json::json!({
+        "machineId": machine_id,
+        "machineIdBackup": machine_id_backup,
+        "machineIdBackups": machine_id_backups
+    })).into_response()
+}
+
+/// 从 Windows 注册表读取 MachineGuid
+#[cfg(windows)]
+fn get_system_machine_guid() -> Option<String> {
+    use winreg::enums::*;
+    use winreg::RegKey;
+    
+    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+    match hklm.open_subkey("SOFTWARE\\Microsoft\\Cryptography") {
+        Ok(key) => {
+            match key.get_value::<String, _>("MachineGuid") {
+                Ok(guid) => Some(guid),
+                Err(_) => None,
+            }
+        }
+        Err(_) => None,
+    }
+}
+
+/// 从 macOS 获取 Kiro 应用的机器码 (从 storage.json 读取)
+#[cfg(target_os = "macos")]
+fn get_system_machine_guid() -> Option<String> {
+    use std::fs;
+    
+    // Kiro 配置路径: ~/Library/Application Support/Kiro/User/globalStorage/storage.json
+    let home = dirs::home_dir()?;
+    let storage_path = home.join("Library/Application Support/Kiro/User/globalStorage/storage.json");
+    
+    if !storage_path.exists() {
+        return None;
+    }
+    
+    let content = fs::read_to_string(&storage_path).ok()?;
+    let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+    
+    json.get("telemetry.machineId")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+}
+
+/// 从 Linux 获取 Kiro 应用的机器码 (从 storage.json 读取)
+#[cfg(target_os = "linux")]
+fn get_system_machine_guid() -> Option<String> {
+    use std::fs;
+    
+    // Kiro 配置路径: ~/.config/Kiro/User/globalStorage/storage.json
+    let home = dirs::home_dir()?;
+    let storage_path = home.join(".config/Kiro/User/globalStorage/storage.json");
+    
+    if !storage_path.exists() {
+        return None;
+    }
+    
+    let content = fs::read_to_string(&storage_path).ok()?;
+    let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+    
+    json.get("telemetry.machineId")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+}
+
+/// 其他平台不支持
+#[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+fn get_system_machine_guid() -> Option<String> {
+    None
+}
+
+/// 备份当前机器码到配置文件
+pub async fn backup_machine_id(State(state): State<AdminState>) -> impl IntoResponse {
+    // 从注册表读取当前机器码
+    let current_guid = match get_system_machine_guid() {
+        Some(guid) => guid,
+        None => {
+            let error = super::types::AdminErrorResponse::invalid_request("无法读取系统机器码");
+            return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+        }
+    };
+
+    if let Err(e) = append_machine_id_backup(&state.config_path, current_guid) {
+        let error = super::types::AdminErrorResponse::internal_error(format!("保存设置失败: {}", e));
+        return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+    }
+    Json(SuccessResponse::new("机器码已备份")).into_response()
+}
+
+/// 把机器码追加到配置文件中的备份历史
+fn append_machine_id_backup(config_path: &std::path::Path, machine_id: String) -> anyhow::Result<()> {
+    use crate::model::config::Config;
+
+    let mut config = Config::load(config_path)?;
+    config.push_machine_id_backup(machine_id);
+    config.save(config_path)
+}
+
+/// POST /api/admin/machine-id/restore
+/// 从备份恢复机器码到注册表
+/// `?index=N` 选择备份（0 为最近一次）；`?dryRun=true` 仅预览并签发确认令牌，
+/// 实际写入须携带 `?confirmToken=`
+pub async fn restore_machine_id(
+    State(state): State<AdminState>,
+    Query(query): Query<super::types::MachineIdWriteQuery>,
+) -> impl IntoResponse {
+    use crate::model::config::Config;
+    
+    let config = match Config::load(state.config_path.as_path()) {
+        Ok(c) => c,
+        Err(e) => {
+            let error = super::types::AdminErrorResponse::internal_error(format!("读取配置失败: {}", e));
+            return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    };
+    
+    let Some(backup) = config.machine_id_backup_history().into_iter().nth(query.index) else {
+        let error = super::types::AdminErrorResponse::invalid_request("没有可用的机器码备份");
+        return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+    };
+    write_machine_id(&state.config_path, "restore", backup.machine_id, query, "机器码已恢复（重启系统后生效）")
+}
+
+/// POST /api/admin/machine-id/reset
+/// 重置机器码（生成新的 UUID 写入注册表）
+/// `?dryRun=true` 仅预览并签发确认令牌，实际写入须携带 `?confirmToken=`
+pub async fn reset_machine_id(
+    State(state): State<AdminState>,
+    Query(query): Query<super::types::MachineIdWriteQuery>,
+) -> impl IntoResponse {
+    let new_guid = uuid::Uuid::new_v4().to_string().to_uppercase();
+    write_machine_id(&state.config_path, "reset", new_guid, query, "机器码已重置（重启系统后生效）")
+}
+
+/// 预览或写入系统机器码；写入前自动把当前机器码追加到备份历史
+///
+/// 实际写入的值以签发令牌时预览的值为准，保证确认的就是写入的内容。
+fn write_machine_id(
+    config_path: &std::path::Path,
+    operation: &'static str,
+    planned: String,
+    query: super::types::MachineIdWriteQuery,
+    message: &str,
+) -> axum::response::Response {
+    let current = get_system_machine_guid();
+
+    if query.dry_run {
+        let confirm_token = super::confirmation::issue(operation, planned.clone());
+        return Json(super::types::MachineIdPreviewResponse {
+            dry_run: true,
+            operation,
+            current_machine_id: current,
+            new_machine_id: planned,
+            confirm_token,
+            expires_in_secs: super::confirmation::TOKEN_TTL.as_secs(),
+        })
+        .into_response();
+    }
+
+    let new_guid = match query
+        .confirm_token
+        .as_deref()
+        .and_then(|token| super::confirmation::take(token, operation))
+    {
+        Some(value) => value,
+        None => {
+            let error = super::types::AdminErrorResponse::invalid_request(
+                "缺少或无效的确认令牌，请先以 dryRun=true 预览后携带 confirmToken 再执行",
+            );
+            return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+        }
+    };
+
+    if let Some(current) = current {
+        if let Err(e) = append_machine_id_backup(config_path, current) {
+            let error = super::types::AdminErrorResponse::internal_error(format!("备份当前机器码失败: {}", e));
+            return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    }
+
+    match set_system_machine_guid(&new_guid) {
+        Ok(_) => Json(SuccessResponse::new(message)).into_response(),
+        Err(e) => {
+            let error = super::types::AdminErrorResponse::internal_error(format!("写入注册表失败: {}。请以管理员身份运行程序。", e));
+            (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// 设置 Windows 注册表中的 MachineGuid
+#[cfg(windows)]
+fn set_system_machine_guid(guid: &str) -> Result<(), String> {
+    use winreg::enums::*;
+    use winreg::RegKey;
+    
+    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+    match hklm.open_subkey_with_flags("SOFTWARE\\Microsoft\\Cryptography", KEY_SET_VALUE) {
+        Ok(key) => {
+            match key.set_value("MachineGuid", &guid) {
+                Ok(_) => Ok(()),
+                Err(e) => Err(format!("{}", e)),
+            }
+        }
+        Err(e) => Err(format!("{}", e)),
+    }
+}
+
+/// macOS: 修改 Kiro 应用的 storage.json 中的 telemetry.machineId（应用级别）
+#[cfg(target_os = "macos")]
+fn set_system_machine_guid(guid: &str) -> Result<(), String> {
+    use std::fs;
+    
+    // Kiro 配置路径: ~/Library/Application Support/Kiro/User/globalStorage/storage.json
+    let home = dirs::home_dir().ok_or("无法获取用户目录")?;
+    let storage_path = home.join("Library/Application Support/Kiro/User/globalStorage/storage.json");
+    
+    // 读取现有配置
+    let mut json: serde_json::Value = if storage_path.exists() {
+        let content = fs::read_to_string(&storage_path)
+            .map_err(|e| format!("读取配置失败: {}", e))?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("解析配置失败: {}", e))?
+    } else {
+        // 创建目录
+        if let Some(parent) = storage_path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("创建目录失败: {}", e))?;
+        }
+        serde_json::json!({})
+    };
+    
+    // 更新 telemetry.machineId
+    json["telemetry.machineId"] = serde_json::json!(guid);
+    
+    // 写回配置
+    let content = serde_json::to_string_pretty(&json)
+        .map_err(|e| format!("序列化配置失败: {}", e))?;
+    fs::write(&storage_path, content)
+        .map_err(|e| format!("写入配置失败: {}", e))?;
+    
+    Ok(())
+}
+
+/// Linux: 修改 Kiro 应用的 storage.json 中的 telemetry.machineId（应用级别）
+#[cfg(target_os = "linux")]
+fn set_system_machine_guid(guid: &str) -> Result<(), String> {
+    use std::fs;
+    
+    // Kiro 配置路径: ~/.config/Kiro/User/globalStorage/storage.json
+    let home = dirs::home_dir().ok_or("无法获取用户目录")?;
+    let storage_path = home.join(".config/Kiro/User/globalStorage/storage.json");
+    
+    // 读取现有配置
+    let mut json: serde_json::Value = if storage_path.exists() {
+        let content = fs::read_to_string(&storage_path)
+            .map_err(|e| format!("读取配置失败: {}", e))?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("解析配置失败: {}", e))?
+    } else {
+        // 创建目录
+        if let Some(parent) = storage_path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("创建目录失败: {}", e))?;
+        }
+        serde_json::json!({})
+    };
+    
+    // 更新 telemetry.machineId
+    json["telemetry.machineId"] = serde_json::json!(guid);
+    
+    // 写回配置
+    let content = serde_json::to_string_pretty(&json)
+        .map_err(|e| format!("序列化配置失败: {}", e))?;
+    fs::write(&storage_path, content)
+        .map_err(|e| format!("写入配置失败: {}", e))?;
+    
+    Ok(())
+}
+
+/// 其他平台不支持
+#[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+fn set_system_machine_guid(_guid: &str) -> Result<(), String> {
+    Err("当前平台不支持修改机器码".to_string())
+}
+
+// ============ 批量操作 API ============
+
+/// DELETE /api/admin/credentials/batch
+/// 批量删除凭证
+pub async fn batch_delete_credentials(
+    State(state): State<AdminState>,
+    Json(payload): Json<super::types::BatchDeleteRequest>,
+) -> impl IntoResponse {
+    let mut deleted = 0;
+    let mut failed = 0;
+    
+    for id in payload.ids {
+        match state.service.delete_credential(id) {
+            Ok(_) => deleted += 1,
+            Err(_) => failed += 1,
+        }
+    }
+    
+    Json(serde_json::json!({
+        "success": true,
+        "deleted": deleted,
+        "failed": failed,
+        "message": format!("成功删除 {} 个凭证，{} 个失败", deleted, failed)
+    }))
+}
+
+/// POST /api/admin/credentials/dedupe
+/// 合并指向同一账号的重复凭证（`?dryRun=true` 仅预览，`?refresh=false` 跳过账号信息查询）
+pub async fn dedupe_credentials(
+    State(state): State<AdminState>,
+    Query(query): Query<super::types::DedupeQuery>,
+) -> impl IntoResponse {
+    match state.service.dedupe_credentials(query.dry_run, query.refresh).await {
+        Ok(response) => Json(response).into_response(),
+        Err(e) => (e.status_code(), Json(e.into_response())).into_response(),
+    }
+}
+
+/// POST /api/admin/credentials/export
+/// 导出凭证（支持完整数据、仅 token 或口令加密的凭证包）
+pub async fn export_credentials(
+    State(state): State<AdminState>,
+    Json(payload): Json<super::types::ExportCredentialsRequest>,
+) -> impl IntoResponse {
+    // 获取要导出的凭证 ID
+    let ids: Vec<u64> = payload.ids;
+    
+    // 获取完整凭证数据
+    let credentials = state.service.get_credentials_for_export(&ids);
+
+    if let Some(password) = payload.password {
+        return export_credentials_encrypted(&credentials, &password);
+    }
+    
+    // 根据导出类型返回不同格式
+    match payload.export_type.as_deref() {
+        Some("tokens_only") => {
+            // 仅导出 refreshToken
+            let tokens: Vec<serde_json::Value> = credentials
+                .iter()
+                .filter_map(|c| {
+                    c.refresh_token.as_ref().map(|token| {
+                        serde_json::json!({
+                            "refreshToken": token
+                        })
+                    })
+                })
+                .collect();
+            
+            Json(serde_json::json!({
+                "success": true,
+                "type": "tokens_only",
+                "count": tokens.len(),
+                "credentials": tokens
+            })).into_response()
+        }
+        _ => {
+            // 导出完整数据（格式与 z-kiro 一致）
+            let export_data: Vec<serde_json::Value> = credentials
+                .iter()
+                .map(|c| {
+                    serde_json::json!({
+                        "accessToken": c.access_token,
+                        "refreshToken": c.refresh_token,
+                        "profileArn": c.profile_arn,
+                        "expiresAt": c.expires_at,
+                        "authMethod": c.auth_method.as_deref().unwrap_or("social")
+                    })
+                })
+                .collect();
+            
+            Json(serde_json::json!({
+                "success": true,
+                "type": "full",
+                "count": export_data.len(),
+                "credentials": export_data
+            })).into_response()
+        }
+    }
+}
+
+/// 导出口令加密的凭证包（Base64），可在另一个网关通过 import-encrypted 导入
+fn export_credentials_encrypted(credentials: &[crate::kiro::model::credentials::KiroCredentials], password: &str) -> axum::response::Response {
+    use base64::Engine;
+    use crate::backup::{self, CredentialExport, MIN_PASSWORD_LEN};
+
+    if password.chars().count() < MIN_PASSWORD_LEN {
+        let error = super::types::AdminErrorResponse::invalid_request(format!(
+            "导出口令至少 {} 个字符",
+            MIN_PASSWORD_LEN
+        ));
+        return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+
+    let export = CredentialExport::new(credentials);
+    match backup::encrypt_credentials(&export, password) {
+        Ok(data) => {
+            tracing::info!("已导出加密凭证包（{} 个凭证）", export.credentials.len());
+            Json(serde_json::json!({
+                "success": true,
+                "type": "encrypted",
+                "count": export.credentials.len(),
+                "data": base64::engine::general_purpose::STANDARD.encode(data)
+            }))
+            .into_response()
+        }
+        Err(e) => {
+            let error = super::types::AdminErrorResponse::internal_error(format!("导出凭证失败: {}", e));
+            (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// POST /api/admin/credentials/import-encrypted
+/// 导入口令加密的凭证包
+pub async fn import_encrypted_credentials(
+    State(state): State<AdminState>,
+    Json(payload): Json<super::types::ImportEncryptedCredentialsRequest>,
+) -> impl IntoResponse {
+    use base64::Engine;
+    use super::types::ImportCredentialItem;
+
+    let export = base64::engine::general_purpose::STANDARD
+        .decode(payload.data.trim())
+        .map_err(|e| anyhow::anyhow!("凭证包不是有效的 Base64: {}", e))
+        .and_then(|data| crate::backup::decrypt_credentials(&data, &payload.password));
+    let export = match export {
+        Ok(export) => export,
+        Err(e) => {
+            let error = super::types::AdminErrorResponse::invalid_request(e.to_string());
+            return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+        }
+    };
+
+    let items = export
+        .credentials
+        .into_iter()
+        .map(|c| ImportCredentialItem {
+            refresh_token: c.refresh_token,
+            auth_method: c.auth_method,
+            client_id: c.client_id,
+            client_secret: c.client_secret,
+            group_id: payload.group_id.clone(),
+        })
+        .collect();
+
+    let group_rules = state.config.lock().group_rules.clone();
+    match state.service.import_credentials(items, &group_rules).await {
+        Ok(response) => Json(response).into_response(),
+        Err(e) => (e.status_code(), Json(e.into_response())).into_response(),
+    }
+}
+
+// ============ 模型锁定 API ============
+
+/// GET /api/admin/config/model
+/// 获取当前锁定的模型
+pub async fn get_locked_model(State(state): State<AdminState>) -> impl IntoResponse {
+    use crate::model::config::Config;
+    
+    let config_path = state.config_path.as_path();
+    match Config::load(config_path) {
+        Ok(config) => Json(serde_json::json!({
+            "lockedModel": config.locked_model
+        })).into_response(),
+        Err(e) => {
+            let error = super::types::AdminErrorResponse::internal_error(format!("读取配置失败: {}", e));
+            (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// GET /api/admin/config/model/status
+/// 获取模型锁定监控状态（监控方式、最近一次写入时间、恢复次数）
+pub async fn get_model_lock_status() -> impl IntoResponse {
+    Json(crate::model_lock::status())
+}
+
+/// POST /api/admin/config/model
+/// 设置或取消锁定模型
+pub async fn set_locked_model(
+    State(state): State<AdminState>,
+    Json(payload): Json<super::types::SetLockedModelRequest>,
+) -> impl IntoResponse {
+    use crate::model::config::Config;
+    
+    let config_path = state.config_path.as_path();
+    let mut config = match Config::load(config_path) {
+        Ok(c) => c,
+        Err(e) => {
+            let error = super::types::AdminErrorResponse::internal_error(format!("读取配置失败: {}", e));
+            return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    };
+    
+    config.locked_model = payload.model.clone();
+    
+    if let Err(e) = config.save(config_path) {
+        let error = super::types::AdminErrorResponse::internal_error(format!("保存设置失败: {}", e));
+        return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+    }
+    
+    // 更新模型锁定监控器
+    crate::model_lock::set_locked_model(payload.model.clone());
+    
+    let msg = if config.locked_model.is_some() {
+        format!("模型已锁定为: {}", config.locked_model.as_ref().unwrap())
+    } else {
+        "模型锁定已取消".to_string()
+    };
+    
+    Json(SuccessResponse::new(msg)).into_response()
+}
+
+/// GET /api/admin/kiro/profiles
+/// 列出 Kiro 客户端的所有 Profile 及锁定选择
+pub async fn get_kiro_profiles() -> impl IntoResponse {
+    let status = crate::model_lock::status();
+    Json(super::types::KiroProfilesResponse {
+        profiles: crate::model_lock::list_profiles(),
+        selected: status.profiles,
+        settings_paths: status.settings_paths,
+    })
+}
+
+/// POST /api/admin/kiro/profiles
+/// 选择模型锁定作用的 Profile（已锁定模型时立即写入）
+pub async fn set_kiro_profiles(
+    State(state): State<AdminState>,
+    Json(payload): Json<super::types::SetKiroProfilesRequest>,
+) -> impl IntoResponse {
+    let known = crate::model_lock::list_profiles();
+    let mut profiles: Vec<String> = Vec::new();
+    for id in payload.profiles {
+        if !known.iter().any(|p| p.id == id) {
+            let error = AdminErrorResponse::not_found(format!("Kiro Profile 不存在: {}", id));
+            return (axum::http::StatusCode::NOT_FOUND, Json(error)).into_response();
+        }
+        if !profiles.contains(&id) {
+            profiles.push(id);
+        }
+    }
+
+    {
+        let mut config = state.config.lock();
+        config.kiro_profiles = profiles.clone();
+        if let Err(e) = config.save(state.config_path.as_path()) {
+            let error = AdminErrorResponse::internal_error(format!("保存设置失败: {}", e));
+            return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    }
+    crate::model_lock::set_profiles(profiles);
+
+    get_kiro_profiles().await.into_response()
+}
+
+// ============ 本地账号 API ============
+
+/// GET /api/admin/credentials/local
+/// 获取本地 Kiro 客户端凭证信息
+pub async fn get_local_credential() -> impl IntoResponse {
+    use super::local_account;
+    
+    match local_account::read_local_credential() {
+        Ok(cred) => Json(serde_json::json!({
+            "success": true,
+            "hasCredential": true,
+            "refreshToken": cred.refresh_token,
+            "authMethod": cred.auth_method,
+            "expiresAt": cred.expires_at
+        })).into_response(),
+        Err(e) => Json(serde_json::json!({
+            "success": false,
+            "hasCredential": false,
+            "error": e.to_string()
+        })).into_response()
+    }
+}
+
+/// POST /api/admin/credentials/import-local
+/// 导入本地 Kiro 客户端凭证
+pub async fn import_local_credential(
+    State(state): State<AdminState>,
+) -> impl IntoResponse {
+    use super::local_account;
+    use super::types::AddCredentialRequest;
+    
+    // 读取本地凭证
+    let local_cred = match local_account::read_local_credential() {
+        Ok(c) => c,
+        Err(e) => {
+            let error = super::types::AdminErrorResponse::invalid_request(format!("读取本地凭证失败: {}", e));
+            return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+        }
+    };
+    
+    let refresh_token = match local_cred.refresh_token {
+        Some(t) => t,
+        None => {
+            let error = super::types::AdminErrorResponse::invalid_request("本地凭证没有 refreshToken");
+            return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+        }
+    };
+    
+    // 构建添加请求
+    let req = AddCredentialRequest {
+        refresh_token,
+        auth_method: local_cred.auth_method.unwrap_or_else(|| "social".to_string()),
+        client_id: None,
+        client_secret: None,
+    };
+    
+    // 调用现有的添加逻辑
+    match state.service.add_credential(req).await {
+        Ok(response) => Json(response).into_response(),
+        Err(e) => (e.status_code(), Json(e.into_response())).into_response(),
+    }
+}
+
+/// 已添加凭证的 refreshToken 集合（用于标记已导入）
+fn existing_refresh_tokens(state: &AdminState) -> std::collections::HashSet<String> {
+    state
+        .service
+        .get_all_credentials(Redaction::Revealed)
+        .credentials
+        .into_iter()
+        .filter_map(|c| c.refresh_token)
+        .collect()
+}
+
+/// GET /api/admin/credentials/discover
+/// 扫描 SSO 缓存目录，列出所有可导入的凭证
+pub async fn discover_credentials(State(state): State<AdminState>) -> impl IntoResponse {
+    use super::local_account;
+    use super::types::{DiscoverCredentialsResponse, DiscoveredCredentialItem};
+
+    let Some(dir) = local_account::get_sso_cache_dir() else {
+        let error = super::types::AdminErrorResponse::internal_error("无法获取用户目录");
+        return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+    };
+
+    let existing = existing_refresh_tokens(&state);
+    let credentials = local_account::discover_in(&dir)
+        .into_iter()
+        .map(|c| DiscoveredCredentialItem {
+            already_imported: existing.contains(&c.refresh_token),
+            refresh_token_preview: mask_secret(&c.refresh_token),
+            has_client_registration: c.client_id.is_some() && c.client_secret.is_some(),
+            file: c.file,
+            auth_method: c.auth_method,
+            provider: c.provider,
+            start_url: c.start_url,
+            region: c.region,
+            expires_at: c.expires_at,
+        })
+        .collect();
+
+    Json(DiscoverCredentialsResponse {
+        directory: dir.display().to_string(),
+        credentials,
+    })
+    .into_response()
+}
+
+/// POST /api/admin/credentials/discover/import
+/// 批量导入 SSO 缓存目录中发现的凭证
+///
+/// 按文件名重新读取缓存，refreshToken 不经过前端
+pub async fn import_discovered_credentials(
+    State(state): State<AdminState>,
+    Json(payload): Json<super::types::ImportDiscoveredRequest>,
+) -> impl IntoResponse {
+    use super::local_account;
+    use super::types::ImportCredentialItem;
+
+    if payload.files.is_empty() {
+        let error = super::types::AdminErrorResponse::invalid_request("未选择要导入的凭证");
+        return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+
+    let discovered = match local_account::discover_credentials() {
+        Ok(d) => d,
+        Err(e) => {
+            let error = super::types::AdminErrorResponse::internal_error(format!("扫描 SSO 缓存失败: {}", e));
+            return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    };
+
+    let mut items = Vec::with_capacity(payload.files.len());
+    for file in &payload.files {
+        let Some(cred) = discovered.iter().find(|c| &c.file == file) else {
+            let error = super::types::AdminErrorResponse::not_found(format!("SSO 缓存中未找到凭证文件: {}", file));
+            return (axum::http::StatusCode::NOT_FOUND, Json(error)).into_response();
+        };
+        items.push(ImportCredentialItem {
+            refresh_token: cred.refresh_token.clone(),
+            auth_method: cred.auth_method.clone(),
+            client_id: cred.client_id.clone(),
+            client_secret: cred.client_secret.clone(),
+            group_id: payload.group_id.clone(),
+        });
+    }
+
+    let group_rules = state.config.lock().group_rules.clone();
+    match state.service.import_credentials(items, &group_rules).await {
+        Ok(response) => Json(response).into_response(),
+        Err(e) => (e.status_code(), Json(e.into_response())).into_response(),
+    }
+}
+
+/// POST /api/admin/credentials/:id/switch
+/// 切换到指定账号（写入本地凭证文件）
+pub async fn switch_to_credential(
+    State(state): State<AdminState>,
+    Path(id): Path<u64>,
+) -> impl IntoResponse {
+    use super::local_account::{self, LocalKiroCredential};
+    
+    // 获取凭证的完整信息
+    let snapshot = state.service.get_all_credentials(Redaction::Revealed);
+    let cred = snapshot.credentials.iter().find(|c| c.id == id);
+    
+    if cred.is_none() {
+        let error = super::types::AdminErrorResponse::not_found(format!("凭证 #{} 不存在", id))
+            .with_code(crate::error_code::ErrorCode::CredentialNotFound);
+        return (axum::http::StatusCode::NOT_FOUND, Json(error)).into_response();
+    }
+    
+    let cred = cred.unwrap();
+    
+    // 构建本地凭证
+    let local_cred = LocalKiroCredential {
+        access_token: cred.access_token.clone(),
+        refresh_token: cred.refresh_token.clone(),
+        profile_arn: cred.profile_arn.clone(),
+        expires_at: cred.expires_at.clone(),
+        auth_method: cred.auth_method.clone(),
+        provider: Some("Google".to_string()),
+    };
+    
+    match local_account::write_local_credential(&local_cred) {
+        Ok(_) => Json(SuccessResponse::new(format!("已切换到凭证 #{}", id))).into_response(),
+        Err(e) => {
+            let error = super::types::AdminErrorResponse::internal_error(format!("写入本地凭证失败: {}", e));
+            (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// POST /api/admin/credentials/switch-next
+/// 切换到下一个可用凭证（反代使用）
+pub async fn switch_to_next_credential(
+    State(state): State<AdminState>,
+) -> impl IntoResponse {
+    if state.token_manager.switch_to_next() {
+        // 获取新的当前凭证信息
+        let snapshot = state.service.get_all_credentials(Redaction::Redacted);
+        let current_id = snapshot.current_id;
+        let current_cred = snapshot.credentials.iter().find(|c| c.id == current_id);
+        
+        let msg = if let Some(cred) = current_cred {
+            format!("已切换到凭证 #{} ({})", current_id, cred.email.as_deref().unwrap_or("-"))
+        } else {
+            format!("已切换到凭证 #{}", current_id)
+        };
+        
+        Json(serde_json::json!({
+            "success": true,
+            "message": msg,
+            "currentId": current_id
+        })).into_response()
+    } else {
+        let error = super::types::AdminErrorResponse::invalid_request("没有可用的凭证可切换");
+        (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response()
+    }
+}
+
+// ============ 分组管理 ============
+
+/// GET /api/admin/groups
+/// 获取所有分组
+pub async fn get_groups(State(state): State<AdminState>) -> impl IntoResponse {
+    use super::types::{GroupInfo, GroupsResponse};
+    
+    let config = state.config.lock();
+    let credentials = state.service.get_all_credentials(Redaction::Redacted);
+    
+    // 统计每个分组的凭证数量
+    let groups: Vec<GroupInfo> = config.groups.iter().map(|g| {
+        let count = credentials.credentials.iter()
+            .filter(|c| c.group_id == g.id)
+            .count() as u32;
+        GroupInfo {
+            id: g.id.clone(),
+            name: g.name.clone(),
+            credential_count: count,
+            budget: g.budget.clone(),
+            budget_status: GROUP_BUDGETS.status(&g.id),
+        }
+    }).collect();
+    
+    Json(GroupsResponse {
+        groups,
+        active_group_id: config.active_group_id.clone(),
+        fallback_group_id: config.fallback_group_id.clone(),
+        failover_group_id: state.token_manager.failover_group(),
+    })
+}
+
+/// POST /api/admin/groups
+/// 添加分组
+pub async fn add_group(
+    State(state): State<AdminState>,
+    Json(payload): Json<super::types::AddGroupRequest>,
+) -> impl IntoResponse {
+    use crate::model::config::GroupConfig;
+    
+    // 生成唯一 ID
+    let group_id = format!("group_{}", chrono::Utc::now().timestamp_millis());
+    
+    {
+        let mut config = state.config.lock();
+        config.groups.push(GroupConfig {
+            id: group_id.clone(),
+            name: payload.name.clone(),
+            budget: None,
+        });
+        
+        // 保存设置
+        if let Err(e) = config.save(state.config_path.as_path()) {
+            let error = super::types::AdminErrorResponse::internal_error(format!("保存设置失败: {}", e));
+            return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    }
+    
+    Json(SuccessResponse::new(format!("分组 '{}' 创建成功", payload.name))).into_response()
+}
+
+/// DELETE /api/admin/groups/:id
+/// 删除分组
+pub async fn delete_group(
+    State(state): State<AdminState>,
+    Path(group_id): Path<String>,
+) -> impl IntoResponse {
+    // 不能删除默认分组
+    if group_id == "default" {
+        let error = super::types::AdminErrorResponse::invalid_request("不能删除默认分组".to_string());
+        return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+    
+    // 检查是否有凭证在该分组下
+    let credentials = state.service.get_all_credentials(Redaction::Redacted);
+    let has_credentials = credentials.credentials.iter().any(|c| c.group_id == group_id);
+    if has_credentials {
+        let error = super::types::AdminErrorResponse::invalid_request("该分组下还有凭证，无法删除".to_string());
+        return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+    if state.config.lock().group_listeners.iter().any(|l| l.group_id == group_id) {
+        let error = super::types::AdminErrorResponse::invalid_request("该分组配置了分组反代实例，请先移除".to_string());
+        return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+    
+    {
+        let mut config = state.config.lock();
+        
+        // 找到并删除分组
+        if let Some(pos) = config.groups.iter().position(|g| g.id == group_id) {
+            config.groups.remove(pos);
+            
+            // 如果删除的是当前活跃分组，重置为空（使用所有）
+            if config.active_group_id.as_ref() == Some(&group_id) {
+                config.active_group_id = None;
+            }
+            if config.fallback_group_id.as_ref() == Some(&group_id) {
+                config.fallback_group_id = None;
+                state.token_manager.set_fallback_group(None);
+            }
+
+            // 移除指向该分组的自动分组规则
+            config.group_rules.retain(|r| r.group_id != group_id);
+            
+            // 保存设置
+            if let Err(e) = config.save(state.config_path.as_path()) {
+                let error = super::types::AdminErrorResponse::internal_error(format!("保存设置失败: {}", e));
+                return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+            }
+            GROUP_BUDGETS.set_groups(&config.groups);
+            GROUP_BUDGETS.remove_usage(&group_id);
+        } else {
+            let error = super::types::AdminErrorResponse::not_found(format!("分组 '{}' 不存在", group_id));
+            return (axum::http::StatusCode::NOT_FOUND, Json(error)).into_response();
+        }
+    }
+    
+    Json(SuccessResponse::new("分组已删除".to_string())).into_response()
+}
+
+/// PUT /api/admin/groups/:id
+/// 重命名分组
+pub async fn rename_group(
+    State(state): State<AdminState>,
+    Path(group_id): Path<String>,
+    Json(payload): Json<super::types::RenameGroupRequest>,
+) -> impl IntoResponse {
+    // 不能重命名默认分组
+    if group_id == "default" {
+        let error = super::types::AdminErrorResponse::invalid_request("不能重命名默认分组".to_string());
+        return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+    
+    {
+        let mut config = state.config.lock();
+        
+        // 找到并重命名分组
+        if let Some(group) = config.groups.iter_mut().find(|g| g.id == group_id) {
+            group.name = payload.name.clone();
+            
+            // 保存设置
+            if let Err(e) = config.save(state.config_path.as_path()) {
+                let error = super::types::AdminErrorResponse::internal_error(format!("保存设置失败: {}", e));
+                return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+            }
+        } else {
+            let error = super::types::AdminErrorResponse::not_found(format!("分组 '{}' 不存在", group_id));
+            return (axum::http::StatusCode::NOT_FOUND, Json(error)).into_response();
+        }
+    }
+    
+    Json(SuccessResponse::new(format!("分组已重命名为 '{}'", payload.name))).into_response()
+}
+
+/// PUT /api/admin/groups/:id/budget
+/// 设置分组月度预算（null 取消限制）
+pub async fn set_group_budget(
+    State(state): State<AdminState>,
+    Path(group_id): Path<String>,
+    Json(payload): Json<super::types::SetGroupBudgetRequest>,
+) -> impl IntoResponse {
+    if let Some(budget) = &payload.budget {
+        if budget.monthly_tokens == Some(0) || budget.monthly_requests == Some(0) {
+            let error = super::types::AdminErrorResponse::invalid_request("预算上限必须大于 0".to_string());
+            return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+        }
+        if budget.monthly_tokens.is_none() && budget.monthly_requests.is_none() {
+            let error = super::types::AdminErrorResponse::invalid_request(
+                "至少需要设置 monthlyTokens 或 monthlyRequests".to_string(),
+            );
+            return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+        }
+    }
+
+    {
+        let mut config = state.config.lock();
+        let Some(group) = config.groups.iter_mut().find(|g| g.id == group_id) else {
+            let error = super::types::AdminErrorResponse::not_found(format!("分组 '{}' 不存在", group_id));
+            return (axum::http::StatusCode::NOT_FOUND, Json(error)).into_response();
+        };
+        group.budget = payload.budget.clone();
+
+        if let Err(e) = config.save(state.config_path.as_path()) {
+            let error = super::types::AdminErrorResponse::internal_error(format!("保存设置失败: {}", e));
+            return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+        GROUP_BUDGETS.set_groups(&config.groups);
+    }
+
+    let msg = match payload.budget {
+        Some(_) => format!("分组 '{}' 预算已更新", group_id),
+        None => format!("已取消分组 '{}' 的预算限制", group_id),
+    };
+    Json(SuccessResponse::new(msg)).into_response()
+}
+
+/// POST /api/admin/groups/active
+/// 设置活跃分组（反代使用的分组）
+pub async fn set_active_group(
+    State(state): State<AdminState>,
+    Json(payload): Json<super::types::SetActiveGroupRequest>,
+) -> impl IntoResponse {
+    if let Some(resp) = apply_active_group(&state, payload.group_id.clone()) {
+        return resp;
+    }
+    
+    let msg = match payload.group_id {
+        Some(gid) => format!("已切换到分组 '{}'", gid),
+        None => "已切换到全部".to_string(),
+    };
+    Json(SuccessResponse::new(msg)).into_response()
+}
+
+/// POST /api/admin/groups/fallback
+/// 设置备用分组（立即生效）：活跃分组的凭证全部耗尽或禁用时自动转移到该分组
+pub async fn set_fallback_group(
+    State(state): State<AdminState>,
+    Json(payload): Json<super::types::SetFallbackGroupRequest>,
+) -> impl IntoResponse {
+    if let Some(resp) = ensure_group_exists(&state, payload.group_id.as_deref()) {
+        return resp;
+    }
+
+    let mut config = state.config.lock();
+    config.fallback_group_id = payload.group_id.clone();
+    if let Err(e) = config.save(state.config_path.as_path()) {
+        let error = AdminErrorResponse::internal_error(format!("保存设置失败: {}", e));
+        return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+    }
+    state.token_manager.set_fallback_group(payload.group_id.clone());
+
+    let msg = match payload.group_id {
+        Some(gid) => format!("备用分组已设置为 '{}'", gid),
+        None => "已取消备用分组".to_string(),
+    };
+    Json(SuccessResponse::new(msg)).into_response()
+}
+
+/// 校验分组是否存在（None 表示全部，始终有效），不存在时返回错误响应
+fn ensure_group_exists(
+    state: &AdminState,
+    group_id: Option<&str>,
+) -> Option<axum::response::Response> {
+    let gid = group_id?;
+    if state.config.lock().groups.iter().any(|g| g.id == gid) {
+        return None;
+    }
+    let error = super::types::AdminErrorResponse::not_found(format!("分组 '{}' 不存在", gid));
+    Some((axum::http::StatusCode::NOT_FOUND, Json(error)).into_response())
+}
+
+/// 切换活跃分组：写入配置、保存并同步到 token_manager，失败时返回错误响应
+fn apply_active_group(
+    state: &AdminState,
+    group_id: Option<String>,
+) -> Option<axum::response::Response> {
+    if let Some(resp) = ensure_group_exists(state, group_id.as_deref()) {
+        return Some(resp);
+    }
+    {
+        let mut config = state.config.lock();
+        config.active_group_id = group_id.clone();
+        
+        // 保存设置
+        if let Err(e) = config.save(state.config_path.as_path()) {
+            let error = super::types::AdminErrorResponse::internal_error(format!("保存设置失败: {}", e));
+            return Some((axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response());
+        }
+    }
+    
+    // 同步更新 token_manager 的活跃分组
+    state.token_manager.set_active_group(group_id);
+    None
+}
+
+/// POST /api/admin/credentials/:id/group
+/// 设置凭证分组
+pub async fn set_credential_group(
+    State(state): State<AdminState>,
+    Path(id): Path<u64>,
+    Json(payload): Json<super::types::SetCredentialGroupRequest>,
+) -> impl IntoResponse {
+    // 验证分组是否存在
+    {
+        let config = state.config.lock();
+        if !config.groups.iter().any(|g| g.id == payload.group_id) {
+            let error = super::types::AdminErrorResponse::not_found(format!("分组 '{}' 不存在", payload.group_id));
+            return (axum::http::StatusCode::NOT_FOUND, Json(error)).into_response();
+        }
+    }
+    
+    // 更新凭证分组
+    match state.token_manager.set_group(id, &payload.group_id) {
+        Ok(_) => Json(SuccessResponse::new(format!("凭证 #{} 已移动到分组 '{}'", id, payload.group_id))).into_response(),
+        Err(e) => {
+            let error = super::types::AdminErrorResponse::internal_error(e.to_string());
+            (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+// ============ 代理服务控制 API ============
+
+/// 组装反代服务状态
+fn proxy_status(state: &AdminState) -> super::types::ProxyStatusResponse {
+    // 先获取配置值，释放锁
+    let (host, proxy_port, active_group_id) = {
+        let config = state.config.lock();
+        (config.host.clone(), config.proxy_port, config.active_group_id.clone())
+    };
+    
+    let snapshot = state.proxy.snapshot();
+    super::types::ProxyStatusResponse {
+        running: snapshot.state.accepts_requests(),
+        state: snapshot.state,
+        since: snapshot.since.to_rfc3339(),
+        last_error: snapshot.last_error,
+        host,
+        port: proxy_port,
+        bound_port: snapshot.port,
+        bound_addresses: snapshot.addresses,
+        active_group_id,
+        active_streams: crate::metrics::STREAM_METRICS.active_streams(),
+        drain_deadline: snapshot.drain_deadline.map(|d| d.to_rfc3339()),
+    }
+}
+
+/// GET /api/admin/proxy/status
+/// 获取代理服务状态
+pub async fn get_proxy_status(
+    State(state): State<AdminState>,
+) -> impl IntoResponse {
+    Json(proxy_status(&state))
+}
+
+/// 保存反代开机自启设置（仅双端口模式）
+fn save_proxy_auto_start(state: &AdminState, enabled: bool) {
+    if !state.proxy.is_standalone() {
+        return;
+    }
+    let mut config = state.config.lock();
+    config.proxy_auto_start = enabled;
+    if let Err(e) = config.save(state.config_path.as_path()) {
+        tracing::warn!("保存设置失败: {}", e);
+    }
+}
+
+/// POST /api/admin/proxy
+/// 启动/停止/重启反代服务，启动或重启时可同时切换服务分组
+///
+/// 返回操作完成后的生命周期状态与实际绑定端口
+pub async fn proxy_action(
+    State(state): State<AdminState>,
+    Json(payload): Json<super::types::ProxyActionRequest>,
+) -> impl IntoResponse {
+    use super::types::{AdminErrorResponse, ProxyAction, ProxyActionResponse};
+    use axum::http::StatusCode;
+
+    let conflict = |msg: String| {
+        (StatusCode::CONFLICT, Json(AdminErrorResponse::new("conflict", msg))).into_response()
+    };
+
+    if let Some(group_id) = &payload.group_id {
+        if payload.action == ProxyAction::Stop {
+            let error = AdminErrorResponse::invalid_request("停止操作不支持切换分组");
+            return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+        }
+        if let Some(resp) = ensure_group_exists(&state, group_id.as_deref()) {
+            return resp;
+        }
+    }
+
+    let result = match payload.action {
+        ProxyAction::Stop => {
+            state.proxy.stop();
+            save_proxy_auto_start(&state, false);
+            Ok("反代服务已停止")
+        }
+        ProxyAction::Start => {
+            let current = state.proxy.state();
+            if current.is_active() {
+                if payload.group_id.is_some() {
+                    return conflict("反代服务运行中，切换分组请使用 restart".to_string());
+                }
+                if current == ProxyState::Draining {
+                    return conflict("反代服务正在停止，请稍后再试".to_string());
+                }
+                return Json(ProxyActionResponse {
+                    message: "反代服务已在运行中".to_string(),
+                    status: proxy_status(&state),
+                })
+                .into_response();
+            }
+            if let Some(group_id) = payload.group_id.clone() {
+                if let Some(resp) = apply_active_group(&state, group_id) {
+                    return resp;
+                }
+            }
+            state.proxy.start().await.map(|_| "反代服务已启动")
+        }
+        ProxyAction::Restart => {
+            let mut group_error = None;
+            let result = state
+                .proxy
+                .restart(|| {
+                    if let Some(group_id) = payload.group_id.clone() {
+                        group_error = apply_active_group(&state, group_id);
+                    }
+                })
+                .await;
+            if let Some(resp) = group_error {
+                return resp;
+            }
+            result.map(|_| "反代服务已重启")
+        }
+    };
+
+    match result {
+        Ok(msg) => {
+            if payload.action != ProxyAction::Stop {
+                // 启动时重新选择当前分组的凭证
+                state.token_manager.refresh_credential_selection();
+                save_proxy_auto_start(&state, true);
+            }
+            Json(ProxyActionResponse {
+                message: msg.to_string(),
+                status: proxy_status(&state),
+            })
+            .into_response()
+        }
+        Err(e) if state.proxy.state() == ProxyState::Draining => conflict(e.to_string()),
+        Err(e) => {
+            let error = AdminErrorResponse::internal_error(format!("启动失败: {}", e));
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// GET /api/admin/version
+/// 获取版本信息
+pub async fn get_version() -> impl IntoResponse {
+    Json(serde_json::json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "name": crate::PRODUCT_NAME
+    }))
+}
+
+/// GET /api/admin/metrics
+/// 获取运行指标（流式响应按结束原因计数）
+pub async fn get_metrics() -> impl IntoResponse {
+    use crate::metrics::STREAM_METRICS;
+    Json(serde_json::json!({
+        "streamTerminations": STREAM_METRICS.snapshot()
+    }))
+}
+
+/// GET /api/admin/stats/realtime
+/// 获取最近 1/5/15 分钟的吞吐、延迟和错误率
+pub async fn get_realtime_stats() -> impl IntoResponse {
+    Json(crate::metrics::REALTIME_STATS.snapshot())
+}
+
+// ============ 租户 API Key 管理 ============
+
+/// GET /api/admin/apikeys
+/// 获取租户 API Key 列表及本月用量
+pub async fn get_api_keys(State(state): State<AdminState>) -> impl IntoResponse {
+    use crate::api_keys::API_KEY_REGISTRY;
+    use super::types::{ApiKeyItem, ApiKeysResponse};
+
+    let mut usage = API_KEY_REGISTRY.usage_snapshot();
+    let config = state.config.lock();
+    let api_keys = config
+        .api_keys
+        .iter()
+        .map(|k| ApiKeyItem {
+            id: k.id.clone(),
+            name: k.name.clone(),
+            key_preview: mask_secret(&k.key),
+            enabled: k.enabled,
+            allowed_models: k.allowed_models.clone(),
+            rate_limit_rpm: k.rate_limit_rpm,
+            monthly_token_quota: k.monthly_token_quota,
+            request_transform: k.request_transform.clone(),
+            usage: usage.remove(&k.id),
+        })
+        .collect();
+
+    Json(ApiKeysResponse { api_keys })
+}
+
+/// POST /api/admin/apikeys
+/// 添加租户 API Key
+pub async fn add_api_key(
+    State(state): State<AdminState>,
+    Json(payload): Json<super::types::AddApiKeyRequest>,
+) -> impl IntoResponse {
+    use crate::api_keys::API_KEY_REGISTRY;
+    use crate::model::config::ApiKeyConfig;
+    use super::types::AddApiKeyResponse;
+
+    let name = payload.name.trim().to_string();
+    if name.is_empty() {
+        let error = super::types::AdminErrorResponse::invalid_request("名称不能为空");
+        return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+    if let Some(Err(msg)) = payload.request_transform.as_ref().map(crate::request_transform::validate) {
+        let error = super::types::AdminErrorResponse::invalid_request(msg);
+        return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+
+    let key = payload
+        .key
+        .filter(|k| !k.trim().is_empty())
+        .unwrap_or_else(|| format!("sk-kiro-{}", uuid::Uuid::new_v4().simple()));
+    let id = format!("key_{}", chrono::Utc::now().timestamp_millis());
+
+    {
+        let mut config = state.config.lock();
+        let duplicated = config.api_key.as_deref() == Some(key.as_str())
+            || config.api_keys.iter().any(|k| k.key == key);
+        if duplicated {
+            let error = super::types::AdminErrorResponse::invalid_request("该 Key 已存在");
+            return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+        }
+
+        config.api_keys.push(ApiKeyConfig {
+            id: id.clone(),
+            name: name.clone(),
+            key: key.clone(),
+            enabled: true,
+            allowed_models: payload.allowed_models,
+            rate_limit_rpm: payload.rate_limit_rpm.filter(|v| *v > 0),
+            monthly_token_quota: payload.monthly_token_quota.filter(|v| *v > 0),
+            request_transform: payload.request_transform.filter(|t| !t.is_empty()),
+        });
+
+        if let Err(e) = config.save(state.config_path.as_path()) {
+            let error = super::types::AdminErrorResponse::internal_error(format!("保存设置失败: {}", e));
+            return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+        API_KEY_REGISTRY.set_keys(config.api_keys.clone());
+    }
+
+    Json(AddApiKeyResponse {
+        success: true,
+        message: format!("API Key '{}' 创建成功", name),
+        id,
+        key,
+    })
+    .into_response()
+}
+
+/// PUT /api/admin/apikeys/:id
+/// 更新租户 API Key
+pub async fn update_api_key(
+    State(state): State<AdminState>,
+    Path(id): Path<String>,
+    Json(payload): Json<super::types::UpdateApiKeyRequest>,
+) -> impl IntoResponse {
+    use crate::api_keys::API_KEY_REGISTRY;
+
+    if let Some(Err(msg)) = payload.request_transform.as_ref().map(crate::request_transform::validate) {
+        let error = super::types::AdminErrorResponse::invalid_request(msg);
+        return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+
+    let mut config = state.config.lock();
+    let Some(entry) = config.api_keys.iter_mut().find(|k| k.id == id) else {
+        let error = super::types::AdminErrorResponse::not_found(format!("API Key 不存在: {}", id));
+        return (axum::http::StatusCode::NOT_FOUND, Json(error)).into_response();
+    };
+
+    if let Some(name) = payload.name {
+        entry.name = name;
+    }
+    if let Some(enabled) = payload.enabled {
+        entry.enabled = enabled;
+    }
+    if let Some(allowed_models) = payload.allowed_models {
+        entry.allowed_models = allowed_models;
+    }
+    if let Some(rpm) = payload.rate_limit_rpm {
+        entry.rate_limit_rpm = (rpm > 0).then_some(rpm);
+    }
+    if let Some(quota) = payload.monthly_token_quota {
+        entry.monthly_token_quota = (quota > 0).then_some(quota);
+    }
+    if let Some(transform) = payload.request_transform {
+        entry.request_transform = (!transform.is_empty()).then_some(transform);
+    }
+
+    if let Err(e) = config.save(state.config_path.as_path()) {
+        let error = super::types::AdminErrorResponse::internal_error(format!("保存设置失败: {}", e));
+        return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+    }
+    API_KEY_REGISTRY.set_keys(config.api_keys.clone());
+
+    Json(SuccessResponse::new(format!("API Key {} 已更新", id))).into_response()
+}
+
+/// DELETE /api/admin/apikeys/:id
+/// 删除租户 API Key
+pub async fn delete_api_key(
+    State(state): State<AdminState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    use crate::api_keys::API_KEY_REGISTRY;
+
+    {
+        let mut config = state.config.lock();
+        let Some(pos) = config.api_keys.iter().position(|k| k.id == id) else {
+            let error = super::types::AdminErrorResponse::not_found(format!("API Key 不存在: {}", id));
+            return (axum::http::StatusCode::NOT_FOUND, Json(error)).into_response();
+        };
+        config.api_keys.remove(pos);
+
+        if let Err(e) = config.save(state.config_path.as_path()) {
+            let error = super::types::AdminErrorResponse::internal_error(format!("保存设置失败: {}", e));
+            return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+        API_KEY_REGISTRY.set_keys(config.api_keys.clone());
+    }
+    API_KEY_REGISTRY.remove_usage(&id);
+
+    Json(SuccessResponse::new(format!("API Key {} 已删除", id))).into_response()
+}
+
+// ============ 模型映射 ============
+
+/// GET /api/admin/group-rules
+/// 获取导入自动分组规则
+pub async fn get_group_rules(State(state): State<AdminState>) -> impl IntoResponse {
+    let rules = state.config.lock().group_rules.clone();
+    Json(super::types::GroupRulesBody { rules })
+}
+
+/// PUT /api/admin/group-rules
+/// 替换导入自动分组规则（对之后的导入生效）
+pub async fn set_group_rules(
+    State(state): State<AdminState>,
+    Json(payload): Json<super::types::GroupRulesBody>,
+) -> impl IntoResponse {
+    let mut config = state.config.lock();
+    if let Err(msg) = crate::group_rules::validate(&payload.rules, &config.groups) {
+        let error = super::types::AdminErrorResponse::invalid_request(msg);
+        return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+
+    config.group_rules = payload.rules;
+    if let Err(e) = config.save(state.config_path.as_path()) {
+        let error = super::types::AdminErrorResponse::internal_error(format!("保存设置失败: {}", e));
+        return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+    }
+
+    Json(SuccessResponse::new(format!(
+        "自动分组规则已更新（{} 条）",
+        config.group_rules.len()
+    )))
+    .into_response()
+}
+
+/// GET /api/admin/rotation-schedule
+/// 获取凭证轮换计划及当前命中的时段
+pub async fn get_rotation_schedule(State(state): State<AdminState>) -> impl IntoResponse {
+    let rules = state.config.lock().rotation_schedule.clone();
+    let active_index = crate::rotation::matching_rule(&rules, chrono::Local::now().time());
+    Json(super::types::RotationScheduleResponse { rules, active_index })
+}
+
+/// PUT /api/admin/rotation-schedule
+/// 替换凭证轮换计划（一分钟内按新计划切换）
+pub async fn set_rotation_schedule(
+    State(state): State<AdminState>,
+    Json(payload): Json<super::types::RotationScheduleBody>,
+) -> impl IntoResponse {
+    use crate::rotation::{ROTATION, validate};
+
+    if let Err(msg) = validate(&payload.rules) {
+        let error = super::types::AdminErrorResponse::invalid_request(msg);
+        return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+    for rule in &payload.rules {
+        if let Some(resp) = ensure_group_exists(&state, rule.group_id.as_deref()) {
+            return resp;
+        }
+        if let Some(id) = rule.credential_id {
+            if !state.token_manager.snapshot().entries.iter().any(|e| e.id == id) {
+                let error = super::types::AdminErrorResponse::not_found(format!("凭证不存在: {}", id));
+                return (axum::http::StatusCode::NOT_FOUND, Json(error)).into_response();
+            }
+        }
+    }
+
+    let mut config = state.config.lock();
+    config.rotation_schedule = payload.rules;
+    if let Err(e) = config.save(state.config_path.as_path()) {
+        let error = super::types::AdminErrorResponse::internal_error(format!("保存设置失败: {}", e));
+        return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+    }
+    ROTATION.set_rules(config.rotation_schedule.clone());
+
+    Json(SuccessResponse::new(format!(
+        "轮换计划已更新（{} 条）",
+        config.rotation_schedule.len()
+    )))
+    .into_response()
+}
+
+/// GET /api/admin/request-transform
+/// 获取全局请求改写配置
+pub async fn get_request_transform(State(state): State<AdminState>) -> impl IntoResponse {
+    Json(state.config.lock().request_transform.clone())
+}
+
+/// PUT /api/admin/request-transform
+/// 替换全局请求改写配置（立即生效）
+pub async fn set_request_transform(
+    State(state): State<AdminState>,
+    Json(payload): Json<crate::model::config::RequestTransform>,
+) -> impl IntoResponse {
+    use crate::request_transform::{REQUEST_TRANSFORMER, validate};
+
+    if let Err(msg) = validate(&payload) {
+        let error = super::types::AdminErrorResponse::invalid_request(msg);
+        return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+
+    let mut config = state.config.lock();
+    config.request_transform = payload;
+    if let Err(e) = config.save(state.config_path.as_path()) {
+        let error = super::types::AdminErrorResponse::internal_error(format!("保存设置失败: {}", e));
+        return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+    }
+    REQUEST_TRANSFORMER.set_global(config.request_transform.clone());
+
+    Json(SuccessResponse::new("请求改写配置已更新")).into_response()
+}
+
+/// GET /api/admin/redaction
+/// 获取出站内容脱敏配置
+pub async fn get_redaction(State(state): State<AdminState>) -> impl IntoResponse {
+    Json(state.config.lock().redaction.clone())
+}
+
+/// PUT /api/admin/redaction
+/// 替换出站内容脱敏配置（立即生效）
+pub async fn set_redaction(
+    State(state): State<AdminState>,
+    Json(payload): Json<crate::model::config::RedactionConfig>,
+) -> impl IntoResponse {
+    use crate::redaction::{REDACTOR, validate};
+
+    if let Err(msg) = validate(&payload) {
+        let error = super::types::AdminErrorResponse::invalid_request(msg);
+        return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+
+    let mut config = state.config.lock();
+    config.redaction = payload;
+    if let Err(e) = config.save(state.config_path.as_path()) {
+        let error = super::types::AdminErrorResponse::internal_error(format!("保存设置失败: {}", e));
+        return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+    }
+    REDACTOR.set_config(&config.redaction);
+
+    Json(SuccessResponse::new("内容脱敏配置已更新")).into_response()
+}
+
+/// GET /api/admin/post-process
+/// 获取响应后处理配置
+pub async fn get_post_process(State(state): State<AdminState>) -> impl IntoResponse {
+    Json(state.config.lock().post_process.clone())
+}
+
+/// PUT /api/admin/post-process
+/// 替换响应后处理配置（对之后开始的响应生效）
+pub async fn set_post_process(
+    State(state): State<AdminState>,
+    Json(payload): Json<crate::model::config::PostProcessConfig>,
+) -> impl IntoResponse {
+    use crate::post_process::{POST_PROCESSOR, validate};
+
+    if let Err(msg) = validate(&payload) {
+        let error = super::types::AdminErrorResponse::invalid_request(msg);
+        return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+
+    let mut config = state.config.lock();
+    config.post_process = payload;
+    if let Err(e) = config.save(state.config_path.as_path()) {
+        let error = super::types::AdminErrorResponse::internal_error(format!("保存设置失败: {}", e));
+        return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+    }
+    POST_PROCESSOR.set_config(&config.post_process);
+
+    Json(SuccessResponse::new("响应后处理配置已更新")).into_response()
+}
+
+/// GET /api/admin/alerts
+/// 获取额度告警配置
+pub async fn get_alerts(State(state): State<AdminState>) -> impl IntoResponse {
+    Json(state.config.lock().alerts.clone())
+}
+
+/// PUT /api/admin/alerts
+/// 替换额度告警配置（立即生效）
+pub async fn set_alerts(
+    State(state): State<AdminState>,
+    Json(payload): Json<crate::model::config::AlertConfig>,
+) -> impl IntoResponse {
+    use crate::alerts::{ALERTS, validate};
+
+    if let Err(msg) = validate(&payload) {
+        let error = super::types::AdminErrorResponse::invalid_request(msg);
+        return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+
+    let mut config = state.config.lock();
+    config.alerts = payload;
+    if let Err(e) = config.save(state.config_path.as_path()) {
+        let error = super::types::AdminErrorResponse::internal_error(format!("保存设置失败: {}", e));
+        return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+    }
+    ALERTS.set_config(config.alerts.clone());
+
+    Json(SuccessResponse::new("告警配置已更新")).into_response()
+}
+
+/// GET /api/admin/webhooks
+/// 获取凭证生命周期 Webhook 列表
+pub async fn get_webhooks(State(state): State<AdminState>) -> impl IntoResponse {
+    let webhooks = state.config.lock().webhooks.clone();
+    Json(super::types::WebhooksBody { webhooks })
+}
+
+/// PUT /api/admin/webhooks
+/// 替换凭证生命周期 Webhook 列表（立即生效）
+pub async fn set_webhooks(
+    State(state): State<AdminState>,
+    Json(payload): Json<super::types::WebhooksBody>,
+) -> impl IntoResponse {
+    use crate::webhooks::{WEBHOOKS, validate};
+
+    if let Err(msg) = validate(&payload.webhooks) {
+        let error = super::types::AdminErrorResponse::invalid_request(msg);
+        return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+
+    let mut config = state.config.lock();
+    config.webhooks = payload.webhooks;
+    if let Err(e) = config.save(state.config_path.as_path()) {
+        let error = super::types::AdminErrorResponse::internal_error(format!("保存设置失败: {}", e));
+        return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+    }
+    WEBHOOKS.set_webhooks(config.webhooks.clone());
+
+    Json(SuccessResponse::new(format!(
+        "Webhook 已更新（{} 个）",
+        config.webhooks.len()
+    )))
+    .into_response()
+}
+
+/// POST /api/admin/webhooks/test
+/// 向指定地址（或全部已配置的 Webhook）发送测试消息
+pub async fn test_webhooks(
+    State(state): State<AdminState>,
+    payload: Option<Json<super::types::TestWebhookRequest>>,
+) -> impl IntoResponse {
+    use crate::model::config::WebhookConfig;
+    use super::types::{TestWebhookResponse, WebhookTestResult};
+
+    let request = payload.map(|Json(p)| p).unwrap_or_default();
+    let targets = match request.url {
+        Some(url) => vec![WebhookConfig {
+            url,
+            format: request.format,
+            events: Vec::new(),
+        }],
+        None => state.config.lock().webhooks.clone(),
+    };
+
+    if targets.is_empty() {
+        let error = super::types::AdminErrorResponse::invalid_request("未配置任何 Webhook");
+        return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+    if let Err(msg) = crate::webhooks::validate(&targets) {
+        let error = super::types::AdminErrorResponse::invalid_request(msg);
+        return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+
+    let mut results = Vec::with_capacity(targets.len());
+    for webhook in &targets {
+        let result = crate::webhooks::send_test(webhook).await;
+        results.push(WebhookTestResult {
+            url: webhook.url.clone(),
+            success: result.is_ok(),
+            error: result.err().map(|e| e.to_string()),
+        });
+    }
+    Json(TestWebhookResponse { results }).into_response()
+}
+
+/// GET /api/admin/access
+/// 获取局域网访问控制配置与被拒绝连接的审计日志
+pub async fn get_access_control(State(state): State<AdminState>) -> impl IntoResponse {
+    use crate::access_control::ACCESS_CONTROL;
+
+    let config = state.config.lock().lan_access.clone();
+    Json(super::types::AccessControlResponse {
+        config,
+        rejections: ACCESS_CONTROL.rejections(),
+    })
+}
+
+/// PUT /api/admin/access
+/// 替换局域网访问控制配置（白名单与设备立即生效，启用/停用需重启反代服务）
+///
+/// 未填写令牌的设备由服务端生成令牌
+pub async fn set_access_control(
+    State(state): State<AdminState>,
+    Json(mut payload): Json<crate::model::config::LanAccessConfig>,
+) -> impl IntoResponse {
+    use crate::access_control::{ACCESS_CONTROL, prepare};
+
+    if let Err(msg) = prepare(&mut payload) {
+        let error = super::types::AdminErrorResponse::invalid_request(msg);
+        return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+
+    let mut config = state.config.lock();
+    let rebind = config.lan_access.enabled != payload.enabled;
+    config.lan_access = payload;
+    if let Err(e) = config.save(state.config_path.as_path()) {
+        let error = super::types::AdminErrorResponse::internal_error(format!("保存设置失败: {}", e));
+        return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+    }
+    ACCESS_CONTROL.set_config(config.lan_access.clone());
+
+    if rebind {
+        let action = if config.lan_access.enabled { "启用" } else { "停用" };
+        tracing::info!("局域网共享已{}，重启反代服务后切换监听地址", action);
+    }
+
+    // 返回保存后的配置，包含新生成的设备令牌
+    Json(super::types::AccessControlResponse {
+        config: config.lan_access.clone(),
+        rejections: ACCESS_CONTROL.rejections(),
+    })
+    .into_response()
+}
+
+/// POST /api/admin/access/rejections/clear
+/// 清空被拒绝连接的审计日志
+pub async fn clear_access_rejections() -> impl IntoResponse {
+    crate::access_control::ACCESS_CONTROL.clear_rejections();
+    Json(SuccessResponse::new("审计日志已清空"))
+}
+
+/// GET /api/admin/group-proxies
+/// 获取分组反代实例配置与运行状态
+pub async fn get_group_proxies(State(state): State<AdminState>) -> impl IntoResponse {
+    use crate::proxy_lifecycle::GROUP_PROXIES;
+
+    Json(super::types::GroupProxiesResponse {
+        listeners: state.config.lock().group_listeners.clone(),
+        instances: GROUP_PROXIES.snapshots(),
+        errors: Vec::new(),
+    })
+}
+
+/// PUT /api/admin/group-proxies
+/// 替换分组 → 端口映射并立即同步实例（新增的启动，删除或修改的停止）
+pub async fn set_group_proxies(
+    State(state): State<AdminState>,
+    Json(payload): Json<super::types::SetGroupProxiesRequest>,
+) -> impl IntoResponse {
+    use crate::proxy_lifecycle::{GROUP_PROXIES, validate_group_listeners};
+    use super::types::{GroupProxiesResponse, GroupProxyError};
+
+    {
+        let mut config = state.config.lock();
+        if let Err(msg) = validate_group_listeners(&payload.listeners, &config) {
+            let error = AdminErrorResponse::invalid_request(msg);
+            return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+        }
+        config.group_listeners = payload.listeners.clone();
+        if let Err(e) = config.save(state.config_path.as_path()) {
+            let error = AdminErrorResponse::internal_error(format!("保存设置失败: {}", e));
+            return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    }
+
+    let errors = GROUP_PROXIES
+        .sync(&payload.listeners)
+        .await
+        .into_iter()
+        .map(|(group_id, error)| GroupProxyError { group_id, error })
+        .collect();
+
+    Json(GroupProxiesResponse {
+        listeners: payload.listeners,
+        instances: GROUP_PROXIES.snapshots(),
+        errors,
+    })
+    .into_response()
+}
+
+/// POST /api/admin/admin-key/rotate
+/// 生成新的 Admin API Key 并保存，旧密钥立即失效
+pub async fn rotate_admin_api_key(State(state): State<AdminState>) -> impl IntoResponse {
+    use super::middleware::generate_admin_api_key;
+
+    let key = generate_admin_api_key();
+    let mut config = state.config.lock();
+    config.admin_api_key = Some(key.clone());
+    if let Err(e) = config.save(state.config_path.as_path()) {
+        let error = super::types::AdminErrorResponse::internal_error(format!("保存设置失败: {}", e));
+        return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+    }
+    *state.admin_api_key.write() = key.clone();
+    tracing::info!("Admin API Key 已轮换");
+
+    Json(super::types::RotateAdminKeyResponse {
+        success: true,
+        message: "Admin API Key 已轮换".to_string(),
+        admin_api_key: key,
+    })
+    .into_response()
+}
+
+/// GET /api/admin/models
+/// 获取模型目录（含已停用的模型）
+pub async fn get_models(State(state): State<AdminState>) -> impl IntoResponse {
+    let models = state.config.lock().models.clone();
+    Json(super::types::ModelsBody { models })
+}
+
+/// POST /api/admin/models
+/// 添加模型目录条目（立即生效）
+pub async fn add_model(
+    State(state): State<AdminState>,
+    Json(mut payload): Json<crate::model::config::ModelEntry>,
+) -> impl IntoResponse {
+    use crate::model_catalog::{MODEL_CATALOG, validate};
+
+    payload.id = payload.id.trim().to_string();
+    let id = payload.id.clone();
+
+    let mut config = state.config.lock();
+    let mut models = config.models.clone();
+    models.push(payload);
+    if let Err(msg) = validate(&models) {
+        let error = super::types::AdminErrorResponse::invalid_request(msg);
+        return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+    config.models = models;
+    if let Err(e) = config.save(state.config_path.as_path()) {
+        let error = super::types::AdminErrorResponse::internal_error(format!("保存设置失败: {}", e));
+        return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+    }
+    MODEL_CATALOG.set_models(config.models.clone());
+
+    Json(SuccessResponse::new(format!("模型 {} 已添加", id))).into_response()
+}
+
+/// PUT /api/admin/models/:id
+/// 更新模型目录条目（立即生效）
+pub async fn update_model(
+    State(state): State<AdminState>,
+    Path(id): Path<String>,
+    Json(payload): Json<super::types::UpdateModelRequest>,
+) -> impl IntoResponse {
+    use crate::model_catalog::MODEL_CATALOG;
+
+    if payload.max_tokens.is_some_and(|v| v < 0) {
+        let error = super::types::AdminErrorResponse::invalid_request("maxTokens 不能为负数");
+        return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+
+    let mut config = state.config.lock();
+    let Some(entry) = config.models.iter_mut().find(|m| m.id == id) else {
+        let error = super::types::AdminErrorResponse::not_found(format!("模型不存在: {}", id));
+        return (axum::http::StatusCode::NOT_FOUND, Json(error)).into_response();
+    };
+
+    if let Some(display_name) = payload.display_name {
+        entry.display_name = display_name;
+    }
+    if let Some(max_tokens) = payload.max_tokens {
+        entry.max_tokens = (max_tokens > 0).then_some(max_tokens);
+    }
+    if let Some(created) = payload.created {
+        entry.created = created;
+    }
+    if let Some(enabled) = payload.enabled {
+        entry.enabled = enabled;
+    }
+
+    if let Err(e) = config.save(state.config_path.as_path()) {
+        let error = super::types::AdminErrorResponse::internal_error(format!("保存设置失败: {}", e));
+        return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+    }
+    MODEL_CATALOG.set_models(config.models.clone());
+
+    Json(SuccessResponse::new(format!("模型 {} 已更新", id))).into_response()
+}
+
+/// DELETE /api/admin/models/:id
+/// 删除模型目录条目（立即生效）
+pub async fn delete_model(
+    State(state): State<AdminState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    use crate::model_catalog::MODEL_CATALOG;
+
+    let mut config = state.config.lock();
+    let Some(pos) = config.models.iter().position(|m| m.id == id) else {
+        let error = super::types::AdminErrorResponse::not_found(format!("模型不存在: {}", id));
+        return (axum::http::StatusCode::NOT_FOUND, Json(error)).into_response();
+    };
+    config.models.remove(pos);
+
+    if let Err(e) = config.save(state.config_path.as_path()) {
+        let error = super::types::AdminErrorResponse::internal_error(format!("保存设置失败: {}", e));
+        return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+    }
+    MODEL_CATALOG.set_models(config.models.clone());
+
+    Json(SuccessResponse::new(format!("模型 {} 已删除", id))).into_response()
+}
+
+/// GET /api/admin/audit
+/// 查询 Admin 修改操作的审计日志（按时间倒序）
+pub async fn get_audit_log(Query(query): Query<super::types::AuditQuery>) -> impl IntoResponse {
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    Json(super::types::AuditResponse {
+        entries: crate::audit::AUDIT_LOG.query(limit, query.since),
+    })
+}
+
+/// GET /api/admin/transcripts
+/// 检索对话记录（按时间倒序）
+pub async fn get_transcripts(Query(query): Query<super::types::TranscriptQuery>) -> impl IntoResponse {
+    use crate::transcripts::{TRANSCRIPTS, TranscriptFilter};
+
+    let filter = TranscriptFilter {
+        api_key_id: query.api_key_id.filter(|id| !id.is_empty()),
+        model: query.model.filter(|m| !m.is_empty()),
+        query: query.q,
+        since: query.since,
+        until: query.until,
+        limit: query.limit.unwrap_or(50).clamp(1, 500),
+    };
+    let entries = tokio::task::spawn_blocking(move || TRANSCRIPTS.search(&filter))
+        .await
+        .unwrap_or_default();
+    Json(super::types::TranscriptsResponse { entries })
+}
+
+/// GET /api/admin/transcripts/:id
+/// 获取完整的对话记录
+pub async fn get_transcript(Path(id): Path<String>) -> impl IntoResponse {
+    let lookup = id.clone();
+    let found = tokio::task::spawn_blocking(move || crate::transcripts::TRANSCRIPTS.get(&lookup))
+        .await
+        .ok()
+        .flatten();
+    match found {
+        Some(transcript) => Json(transcript).into_response(),
+        None => {
+            let error = AdminErrorResponse::not_found(format!("对话记录不存在: {}", id));
+            (axum::http::StatusCode::NOT_FOUND, Json(error)).into_response()
+        }
+    }
+}
+
+/// DELETE /api/admin/transcripts
+/// 删除所有对话记录
+pub async fn purge_transcripts() -> impl IntoResponse {
+    let purged = crate::transcripts::TRANSCRIPTS.purge();
+    Json(SuccessResponse::new(format!("已删除 {} 个对话记录文件", purged)))
+}
+
+/// GET /api/admin/transcripts/config
+/// 获取对话记录配置
+pub async fn get_transcript_config(State(state): State<AdminState>) -> impl IntoResponse {
+    Json(state.config.lock().transcripts.clone())
+}
+
+/// PUT /api/admin/transcripts/config
+/// 替换对话记录配置（立即生效）
+pub async fn set_transcript_config(
+    State(state): State<AdminState>,
+    Json(payload): Json<crate::model::config::TranscriptConfig>,
+) -> impl IntoResponse {
+    let mut config = state.config.lock();
+    config.transcripts = payload;
+    if let Err(e) = config.save(state.config_path.as_path()) {
+        let error = AdminErrorResponse::internal_error(format!("保存设置失败: {}", e));
+        return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+    }
+    crate::transcripts::TRANSCRIPTS.set_config(config.transcripts.clone());
+
+    Json(config.transcripts.clone()).into_response()
+}
+
+/// GET /api/admin/model-mappings
+/// 获取模型映射表
+pub async fn get_model_mappings(State(state): State<AdminState>) -> impl IntoResponse {
+    let mappings = state.config.lock().model_mappings.clone();
+    Json(super::types::ModelMappingsBody { mappings })
+}
+
+/// PUT /api/admin/model-mappings
+/// 替换模型映射表（立即生效）
+pub async fn set_model_mappings(
+    State(state): State<AdminState>,
+    Json(payload): Json<super::types::ModelMappingsBody>,
+) -> impl IntoResponse {
+    use crate::model_mapping::{MODEL_MAPPER, validate};
+
+    if let Err(msg) = validate(&payload.mappings) {
+        let error = super::types::AdminErrorResponse::invalid_request(msg);
+        return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+
+    let mut config = state.config.lock();
+    config.model_mappings = payload.mappings;
+    if let Err(e) = config.save(state.config_path.as_path()) {
+        let error = super::types::AdminErrorResponse::internal_error(format!("保存设置失败: {}", e));
+        return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+    }
+    MODEL_MAPPER.set_rules(config.model_mappings.clone());
+
+    Json(SuccessResponse::new(format!(
+        "模型映射已更新（{} 条）",
+        config.model_mappings.len()
+    )))
+    .into_response()
+}
+
+/// GET /api/admin/response-cache
+/// 获取响应缓存配置与命中统计
+pub async fn get_response_cache(State(state): State<AdminState>) -> impl IntoResponse {
+    use crate::cache::response::RESPONSE_CACHE;
+
+    let config = state.config.lock().response_cache.clone();
+    Json(super::types::ResponseCacheResponse {
+        config,
+        stats: RESPONSE_CACHE.stats(),
+    })
+}
+
+/// PUT /api/admin/response-cache
+/// 替换响应缓存配置（立即生效，禁用时清空缓存）
+pub async fn set_response_cache(
+    State(state): State<AdminState>,
+    Json(payload): Json<crate::model::config::ResponseCacheConfig>,
+) -> impl IntoResponse {
+    use crate::cache::response::RESPONSE_CACHE;
+
+    if payload.enabled && (payload.ttl_secs == 0 || payload.max_entries == 0) {
+        let error = AdminErrorResponse::invalid_request("缓存时间和容量必须大于 0");
+        return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+
+    let mut config = state.config.lock();
+    config.response_cache = payload;
+    if let Err(e) = config.save(state.config_path.as_path()) {
+        let error = AdminErrorResponse::internal_error(format!("保存设置失败: {}", e));
+        return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+    }
+    RESPONSE_CACHE.set_config(config.response_cache.clone());
+
+    Json(super::types::ResponseCacheResponse {
+        config: config.response_cache.clone(),
+        stats: RESPONSE_CACHE.stats(),
+    })
+    .into_response()
+}
+
+/// DELETE /api/admin/response-cache
+/// 清空响应缓存与命中统计
+pub async fn purge_response_cache() -> impl IntoResponse {
+    let purged = crate::cache::response::RESPONSE_CACHE.purge();
+    Json(SuccessResponse::new(format!("已清空 {} 条缓存", purged)))
+}
+
+/// POST /api/admin/backup/export
+/// 导出加密的网关状态备份（配置、分组、凭证、额度快照、API Key 用量）
+pub async fn export_backup(
+    State(state): State<AdminState>,
+    Json(payload): Json<super::types::ExportBackupRequest>,
+) -> impl IntoResponse {
+    use crate::backup::{self, BackupBundle, MIN_PASSWORD_LEN};
+
+    if payload.password.chars().count() < MIN_PASSWORD_LEN {
+        let error = super::types::AdminErrorResponse::invalid_request(format!(
+            "备份口令至少 {} 个字符",
+            MIN_PASSWORD_LEN
+        ));
+        return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+
+    // 先落盘尚未保存的统计数据
+    crate::usage_history::USAGE_HISTORY.flush();
+    crate::credential_stats::CREDENTIAL_STATS.flush();
+    crate::api_keys::API_KEY_REGISTRY.flush();
+    crate::group_budgets::GROUP_BUDGETS.flush();
+
+    let config_path = state.config_path.as_path();
+    let data_dir = config_path.parent().unwrap_or(std::path::Path::new("."));
+    let config = state.config.lock().clone();
+    let credentials = state.token_manager.get_credentials_for_export(&[]);
+    let encrypted = BackupBundle::collect(&config, &credentials, data_dir)
+        .and_then(|bundle| backup::encrypt(&bundle, &payload.password));
+
+    match encrypted {
+        Ok(data) => {
+            tracing::info!("已导出网关备份（{} 个凭证）", credentials.len());
+            (
+                [
+                    (axum::http::header::CONTENT_TYPE, "application/octet-stream".to_string()),
+                    (
+                        axum::http::header::CONTENT_DISPOSITION,
+                        format!(
+                            "attachment; filename=\"kiro-gateway-{}.kgbackup\"",
+                            chrono::Local::now().format("%Y%m%d-%H%M%S")
+                        ),
+                    ),
+                ],
+                data,
+            )
+                .into_response()
+        }
+        Err(e) => {
+            let error = super::types::AdminErrorResponse::internal_error(format!("导出备份失败: {}", e));
+            (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// POST /api/admin/backup/import
+/// 导入备份：覆盖配置、凭证和统计数据
+///
+/// 凭证、分组和可热更新的设置立即生效；监听地址、端口、TLS 等需重启。
+/// 保留本机当前的 Admin API Key，避免导入后 Admin UI 立即失去访问权限
+pub async fn import_backup(
+    State(state): State<AdminState>,
+    Json(payload): Json<super::types::ImportBackupRequest>,
+) -> impl IntoResponse {
+    use base64::Engine;
+
+    let bundle = base64::engine::general_purpose::STANDARD
+        .decode(payload.data.trim())
+        .map_err(|e| anyhow::anyhow!("备份文件不是有效的 Base64: {}", e))
+        .and_then(|data| crate::backup::decrypt(&data, &payload.password));
+    let bundle = match bundle {
+        Ok(bundle) => bundle,
+        Err(e) => {
+            let error = super::types::AdminErrorResponse::invalid_request(e.to_string());
+            return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+        }
+    };
+    let (mut config, credentials) = match bundle.config().and_then(|c| Ok((c, bundle.credentials()?))) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            let error = super::types::AdminErrorResponse::invalid_request(e.to_string());
+            return (axum::http::StatusCode::BAD_REQUEST, Json(error)).into_response();
+        }
+    };
+
+    let config_path = state.config_path.as_path();
+    let data_dir = config_path.parent().unwrap_or(std::path::Path::new(".")).to_path_buf();
+    let mut files = match bundle.restore_data_files(&data_dir) {
+        Ok(files) => files,
+        Err(e) => {
+            let error = super::types::AdminErrorResponse::internal_error(format!("恢复数据文件失败: {}", e));
+            return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    };
+
+    // 保存配置（保留当前 Admin API Key）
+    {
+        let mut current = state.config.lock();
+        config.admin_api_key = current.admin_api_key.clone();
+        if let Err(e) = config.save(config_path) {
+            let error = super::types::AdminErrorResponse::internal_error(format!("保存设置失败: {}", e));
+            return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+        *current = config.clone();
+    }
+    files.push(crate::backup::CONFIG_FILE.to_string());
+
+    // 凭证文件按数组格式写入，再替换内存中的凭证
+    if let Some(path) = state.token_manager.credentials_path() {
+        let written = serde_json::to_string_pretty(&credentials)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| Ok(std::fs::write(path, json)?));
+        if let Err(e) = written {
+            let error = super::types::AdminErrorResponse::internal_error(format!("写入凭证文件失败: {}", e));
+            return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+        files.push(crate::backup::CREDENTIALS_FILE.to_string());
+    }
+    let credential_count = match state.token_manager.replace_credentials(credentials) {
+        Ok(count) => count,
+        Err(e) => {
+            let error = super::types::AdminErrorResponse::internal_error(format!("恢复凭证失败: {}", e));
+            return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    };
+
+    // 重新加载可热更新的设置和统计数据
+    crate::api_keys::init(config.api_keys.clone(), config_path);
+    crate::group_budgets::init(&config.groups, config_path);
+    crate::usage_history::init(config_path);
+    crate::credential_stats::init(config_path);
+    crate::model_catalog::MODEL_CATALOG.set_models(config.models.clone());
+    crate::model_mapping::MODEL_MAPPER.set_rules(config.model_mappings.clone());
+    crate::request_transform::REQUEST_TRANSFORMER.set_global(config.request_transform.clone());
+    crate::redaction::REDACTOR.set_config(&config.redaction);
+    crate::post_process::POST_PROCESSOR.set_config(&config.post_process);
+    crate::transcripts::TRANSCRIPTS.set_config(config.transcripts.clone());
+    crate::alerts::ALERTS.set_config(config.alerts.clone());
+    crate::webhooks::WEBHOOKS.set_webhooks(config.webhooks.clone());
+    crate::rotation::ROTATION.set_rules(config.rotation_schedule.clone());
+    state.token_manager.set_fallback_group(config.fallback_group_id.clone());
+    state.token_manager.set_active_group(config.active_group_id.clone());
+
+    tracing::info!(
+        "已导入网关备份（创建于 {}，{} 个凭证）",
+        bundle.created_at,
+        credential_count
+    );
+    Json(super::types::ImportBackupResponse {
+        success: true,
+        message: format!(
+            "已恢复 {} 个凭证；监听地址、端口、TLS 等设置重启后生效",
+            credential_count
+        ),
+        credentials: credential_count,
+        files,
+        created_at: bundle.created_at,
+    })
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kiro::model::credentials::KiroCredentials;
+    use crate::kiro::token_manager::MultiTokenManager;
+    use crate::model::config::Config;
+    use crate::proxy_lifecycle::ProxyLifecycle;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_config_writes_go_to_instance_config_path() {
+        let dir = std::env::temp_dir().join(format!("kiro-profile-{}", uuid::Uuid::new_v4().simple()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config_path = dir.join("config.json");
+        Config::default().save(&config_path).unwrap();
+
+        let token_manager = Arc::new(
+            MultiTokenManager::new(Config::default(), vec![KiroCredentials::default()], None, None, false).unwrap(),
+        );
+        let state = AdminState::new(
+            "sk-admin-test",
+            super::super::service::AdminService::new(token_manager.clone()),
+            Arc::new(parking_lot::Mutex::new(Config::default())),
+            &config_path,
+            token_manager,
+            ProxyLifecycle::new(),
+        );
+
+        let payload = serde_json::from_value(serde_json::json!({ "region": "eu-west-1" })).unwrap();
+        let response = update_config(State(state.clone()), Json(payload)).await.into_response();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        assert_eq!(Config::load(&config_path).unwrap().region, "eu-west-1");
+
+        // Profile 选择同样写入本实例的配置文件
+        let mut stale = Config::load(&config_path).unwrap();
+        stale.kiro_profiles = vec!["-stale".to_string()];
+        stale.save(&config_path).unwrap();
+        let payload = serde_json::from_value(serde_json::json!({ "profiles": [] })).unwrap();
+        let response = set_kiro_profiles(State(state), Json(payload)).await.into_response();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let saved = Config::load(&config_path).unwrap();
+        assert!(saved.kiro_profiles.is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,410 @@
+//! 本地账号读取模块
+//! 
+//! 从 Kiro 客户端本地凭证文件读取 Token，并扫描 SSO 缓存目录发现可导入的凭证；
+//! 添加 / 导入凭证时从 SSO 缓存补全 IdC 凭证缺少的认证方式和客户端注册
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// 本地 Kiro 凭证结构
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalKiroCredential {
+    /// Access Token
+    pub access_token: Option<String>,
+    /// Refresh Token
+    pub refresh_token: Option<String>,
+    /// Profile ARN
+    pub profile_arn: Option<String>,
+    /// 过期时间
+    pub expires_at: Option<String>,
+    /// 认证方式
+    pub auth_method: Option<String>,
+    /// 提供者 (Google 等)
+    pub provider: Option<String>,
+}
+
+/// 获取本地 Kiro 凭证文件路径
+/// Windows: %USERPROFILE%\.aws\sso\cache\kiro-auth-token.json
+/// macOS/Linux: ~/.aws/sso/cache/kiro-auth-token.json
+pub fn get_local_credential_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| {
+        home.join(".aws")
+            .join("sso")
+            .join("cache")
+            .join("kiro-auth-token.json")
+    })
+}
+
+/// 读取本地 Kiro 凭证
+pub fn read_local_credential() -> anyhow::Result<LocalKiroCredential> {
+    let path = get_local_credential_path()
+        .ok_or_else(|| anyhow::anyhow!("无法获取用户目录"))?;
+    
+    if !path.exists() {
+        return Err(anyhow::anyhow!("本地凭证文件不存在: {:?}", path));
+    }
+    
+    let content = std::fs::read_to_string(&path)?;
+    let credential: LocalKiroCredential = serde_json::from_str(&content)?;
+    
+    if credential.refresh_token.is_none() {
+        return Err(anyhow::anyhow!("本地凭证文件中没有 refreshToken"));
+    }
+    
+    Ok(credential)
+}
+
+/// 写入本地 Kiro 凭证（用于切换账号）
+pub fn write_local_credential(credential: &LocalKiroCredential) -> anyhow::Result<()> {
+    let path = get_local_credential_path()
+        .ok_or_else(|| anyhow::anyhow!("无法获取用户目录"))?;
+    
+    // 确保目录存在
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    
+    let content = serde_json::to_string_pretty(credential)?;
+    std::fs::write(&path, content)?;
+    
+    Ok(())
+}
+
+/// SSO 缓存中发现的凭证
+#[derive(Debug, Clone)]
+pub struct DiscoveredCredential {
+    /// 缓存文件名（导入时用于指定凭证）
+    pub file: String,
+    pub refresh_token: String,
+    /// 认证方式（social / idc）
+    pub auth_method: String,
+    /// 提供者（Google、Github、BuilderId 等）
+    pub provider: Option<String>,
+    /// IdC 起始地址
+    pub start_url: Option<String>,
+    pub region: Option<String>,
+    pub expires_at: Option<String>,
+    /// OIDC 客户端注册（IdC 刷新需要）
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
+
+/// 获取 SSO 缓存目录（Kiro 和 AWS CLI 共用）
+pub fn get_sso_cache_dir() -> Option<PathBuf> {
+    get_local_credential_path().and_then(|p| p.parent().map(Path::to_path_buf))
+}
+
+/// 读取 JSON 字段中的字符串
+fn str_field(value: &serde_json::Value, key: &str) -> Option<String> {
+    value
+        .get(key)
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+/// 解析单个缓存文件
+///
+/// 只有包含 refreshToken 的文件才是可导入的 Token 缓存；客户端注册文件（只有 clientId/clientSecret）
+/// 通过 Token 中的 `clientIdHash` 关联，由 `registration` 查找
+fn parse_cache_file(
+    file: &str,
+    value: &serde_json::Value,
+    registration: impl Fn(&str) -> Option<serde_json::Value>,
+) -> Option<DiscoveredCredential> {
+    let refresh_token = str_field(value, "refreshToken")?;
+
+    let mut client_id = str_field(value, "clientId");
+    let mut client_secret = str_field(value, "clientSecret");
+    if client_id.is_none() {
+        if let Some(reg) = str_field(value, "clientIdHash").and_then(|hash| registration(&hash)) {
+            client_id = str_field(&reg, "clientId");
+            client_secret = str_field(&reg, "clientSecret");
+        }
+    }
+
+    let start_url = str_field(value, "startUrl");
+    let auth_method = match str_field(value, "authMethod") {
+        Some(method) => method.to_lowercase(),
+        None if client_id.is_some() || start_url.is_some() => "idc".to_string(),
+        None => "social".to_string(),
+    };
+
+    Some(DiscoveredCredential {
+        file: file.to_string(),
+        refresh_token,
+        auth_method,
+        provider: str_field(value, "provider"),
+        start_url,
+        region: str_field(value, "region"),
+        expires_at: str_field(value, "expiresAt"),
+        client_id,
+        client_secret,
+    })
+}
+
+/// 扫描目录中的所有凭证缓存文件（按文件名排序，无法解析的文件跳过）
+pub fn discover_in(dir: &Path) -> Vec<DiscoveredCredential> {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let read_json = |path: &Path| -> Option<serde_json::Value> {
+        let content = std::fs::read_to_string(path).ok()?;
+        serde_json::from_str(&content).ok()
+    };
+
+    let mut files: Vec<PathBuf> = read_dir
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+
+    files
+        .iter()
+        .filter_map(|path| {
+            let file = path.file_name()?.to_str()?;
+            let value = read_json(path)?;
+            parse_cache_file(file, &value, |hash| read_json(&dir.join(format!("{}.json", hash))))
+        })
+        .collect()
+}
+
+/// 扫描 SSO 缓存目录中的所有凭证（多个 Profile / Builder ID Token）
+pub fn discover_credentials() -> anyhow::Result<Vec<DiscoveredCredential>> {
+    let dir = get_sso_cache_dir().ok_or_else(|| anyhow::anyhow!("无法获取用户目录"))?;
+    Ok(discover_in(&dir))
+}
+
+/// SSO 缓存中的 OIDC 客户端注册（只有 clientId/clientSecret，没有 refreshToken 的文件）
+#[derive(Debug, Clone)]
+pub struct ClientRegistration {
+    pub file: String,
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+/// 扫描目录中未过期的客户端注册
+pub fn client_registrations_in(dir: &Path) -> Vec<ClientRegistration> {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut files: Vec<PathBuf> = read_dir
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+
+    let now = chrono::Utc::now();
+    files
+        .iter()
+        .filter_map(|path| {
+            let value: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()?;
+            if str_field(&value, "refreshToken").is_some() {
+                return None;
+            }
+            let expired = str_field(&value, "expiresAt")
+                .and_then(|at| chrono::DateTime::parse_from_rfc3339(&at).ok())
+                .is_some_and(|at| at < now);
+            if expired {
+                return None;
+            }
+            Some(ClientRegistration {
+                file: path.file_name()?.to_str()?.to_string(),
+                client_id: str_field(&value, "clientId")?,
+                client_secret: str_field(&value, "clientSecret")?,
+            })
+        })
+        .collect()
+}
+
+/// 本地 SSO 缓存快照（批量导入时只扫描一次）
+#[derive(Debug, Default)]
+pub struct SsoCache {
+    credentials: Vec<DiscoveredCredential>,
+    registrations: Vec<ClientRegistration>,
+}
+
+impl SsoCache {
+    /// 读取本机 SSO 缓存目录（目录不存在时为空）
+    pub fn load() -> Self {
+        get_sso_cache_dir()
+            .map(|dir| Self::load_from(&dir))
+            .unwrap_or_default()
+    }
+
+    pub fn load_from(dir: &Path) -> Self {
+        Self {
+            credentials: discover_in(dir),
+            registrations: client_registrations_in(dir),
+        }
+    }
+
+    /// 补全凭证的认证方式和 IdC 客户端注册
+    ///
+    /// - refreshToken 与缓存中的 Token 相同时，采用缓存中的认证方式和客户端注册；
+    /// - 提供了 clientId / clientSecret 却标记为 social 的凭证视为 IdC（只有 IdC 需要客户端注册）；
+    /// - IdC 凭证仍缺少客户端注册时，缓存中恰好只有一个未过期的注册则使用它。
+    ///
+    /// 补全后仍缺少字段时返回错误，列出缺少的字段。
+    pub fn complete_auth(
+        &self,
+        refresh_token: &str,
+        auth_method: &mut String,
+        client_id: &mut Option<String>,
+        client_secret: &mut Option<String>,
+    ) -> Result<(), String> {
+        let normalize = |v: &mut Option<String>| {
+            *v = v.take().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        };
+        normalize(client_id);
+        normalize(client_secret);
+        *auth_method = auth_method.trim().to_lowercase();
+        if auth_method.is_empty() {
+            *auth_method = "social".to_string();
+        }
+
+        let refresh_token = refresh_token.trim();
+        if let Some(cached) = self.credentials.iter().find(|c| c.refresh_token == refresh_token) {
+            if auth_method == "social" && cached.auth_method != "social" {
+                *auth_method = cached.auth_method.clone();
+            }
+            if client_id.is_none() && client_secret.is_none() {
+                client_id.clone_from(&cached.client_id);
+                client_secret.clone_from(&cached.client_secret);
+            }
+        }
+
+        if auth_method == "social" && (client_id.is_some() || client_secret.is_some()) {
+            *auth_method = "idc".to_string();
+        }
+        if auth_method == "social" {
+            return Ok(());
+        }
+
+        if client_id.is_none() && client_secret.is_none() {
+            if let [registration] = self.registrations.as_slice() {
+                tracing::info!("使用 SSO 缓存中的客户端注册 {} 补全 IdC 凭证", registration.file);
+                *client_id = Some(registration.client_id.clone());
+                *client_secret = Some(registration.client_secret.clone());
+            }
+        }
+
+        let missing: Vec<&str> = [("clientId", client_id.is_none()), ("clientSecret", client_secret.is_none())]
+            .into_iter()
+            .filter_map(|(name, missing)| missing.then_some(name))
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        let hint = if self.registrations.len() > 1 {
+            "本地 SSO 缓存中有多个客户端注册，无法确定对应哪一个"
+        } else {
+            "本地 SSO 缓存中未找到对应的客户端注册"
+        };
+        Err(format!(
+            "IdC 凭证缺少 {}（{}），请从 ~/.aws/sso/cache 中的客户端注册文件复制后重试",
+            missing.join("、"),
+            hint
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover_resolves_client_registration() {
+        let dir = std::env::temp_dir().join(format!("kiro-discover-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let write = |name: &str, value: serde_json::Value| {
+            std::fs::write(dir.join(name), value.to_string()).unwrap();
+        };
+
+        write(
+            "kiro-auth-token.json",
+            serde_json::json!({
+                "refreshToken": "social-token",
+                "authMethod": "social",
+                "provider": "Google"
+            }),
+        );
+        write(
+            "a1b2.json",
+            serde_json::json!({
+                "refreshToken": "idc-token",
+                "clientIdHash": "c3d4",
+                "region": "us-east-1"
+            }),
+        );
+        // 客户端注册文件：没有 refreshToken，不会作为凭证出现
+        write(
+            "c3d4.json",
+            serde_json::json!({ "clientId": "cid", "clientSecret": "secret" }),
+        );
+        std::fs::write(dir.join("broken.json"), "{").unwrap();
+
+        let found = discover_in(&dir);
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(found.len(), 2);
+        let idc = &found[0];
+        assert_eq!(idc.file, "a1b2.json");
+        assert_eq!(idc.auth_method, "idc");
+        assert_eq!(idc.client_id.as_deref(), Some("cid"));
+        assert_eq!(idc.client_secret.as_deref(), Some("secret"));
+
+        let social = &found[1];
+        assert_eq!(social.refresh_token, "social-token");
+        assert_eq!(social.auth_method, "social");
+        assert!(social.client_id.is_none());
+    }
+
+    #[test]
+    fn test_complete_auth_from_sso_cache() {
+        let dir = std::env::temp_dir().join(format!("kiro-complete-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("a1b2.json"),
+            serde_json::json!({ "refreshToken": "cached-idc", "clientIdHash": "c3d4" }).to_string(),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("c3d4.json"),
+            serde_json::json!({ "clientId": "cid", "clientSecret": "secret", "expiresAt": "2999-01-01T00:00:00Z" })
+                .to_string(),
+        )
+        .unwrap();
+        let cache = SsoCache::load_from(&dir);
+        std::fs::remove_dir_all(&dir).ok();
+
+        // 缓存中的 Token：自动识别为 IdC 并补全客户端注册
+        let (mut method, mut id, mut secret) = ("social".to_string(), None, None);
+        cache.complete_auth("cached-idc", &mut method, &mut id, &mut secret).unwrap();
+        assert_eq!(method, "idc");
+        assert_eq!(id.as_deref(), Some("cid"));
+
+        // 未知的 IdC Token：使用唯一的客户端注册
+        let (mut method, mut id, mut secret) = ("IdC".to_string(), None, None);
+        cache.complete_auth("other", &mut method, &mut id, &mut secret).unwrap();
+        assert_eq!(secret.as_deref(), Some("secret"));
+
+        // 只提供了 clientId：识别为 IdC，并报告缺少 clientSecret
+        let (mut method, mut id, mut secret) = ("social".to_string(), Some("cid".to_string()), None);
+        let err = SsoCache::default()
+            .complete_auth("other", &mut method, &mut id, &mut secret)
+            .unwrap_err();
+        assert_eq!(method, "idc");
+        assert!(err.contains("clientSecret") && !err.contains("clientId、"));
+
+        // Social Token 保持不变
+        let (mut method, mut id, mut secret) = ("social".to_string(), None, None);
+        cache.complete_auth("social-token", &mut method, &mut id, &mut secret).unwrap();
+        assert_eq!(method, "social");
+        assert!(id.is_none());
+    }
+}
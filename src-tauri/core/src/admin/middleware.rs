@@ -0,0 +1,152 @@
+//! Admin API 中间件
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use parking_lot::{Mutex, RwLock};
+
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, State},
+    http::{Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+
+use super::service::AdminService;
+use super::types::AdminErrorResponse;
+use crate::admin_ui;
+use crate::audit::{AUDIT_LOG, AuditEntry, describe};
+use crate::common::auth;
+use crate::common::redact::mask_secret;
+use crate::model::config::Config;
+use crate::kiro::token_manager::MultiTokenManager;
+use crate::proxy_lifecycle::ProxyLifecycle;
+
+/// Admin API 共享状态
+#[derive(Clone)]
+pub struct AdminState {
+    /// Admin API 密钥（可在运行时轮换）
+    pub admin_api_key: Arc<RwLock<String>>,
+    /// Admin 服务
+    pub service: Arc<AdminService>,
+    /// 配置（用于分组管理）
+    pub config: Arc<Mutex<Config>>,
+    /// 本实例的配置文件路径（`--profile` / `-c` 解析后的结果），所有配置写入都落到这里
+    pub config_path: Arc<PathBuf>,
+    /// Token 管理器
+    pub token_manager: Arc<MultiTokenManager>,
+    /// 反代服务生命周期
+    pub proxy: ProxyLifecycle,
+}
+
+impl AdminState {
+    pub fn new(
+        admin_api_key: impl Into<String>, 
+        service: AdminService,
+        config: Arc<Mutex<Config>>,
+        config_path: impl Into<PathBuf>,
+        token_manager: Arc<MultiTokenManager>,
+        proxy: ProxyLifecycle,
+    ) -> Self {
+        Self {
+            admin_api_key: Arc::new(RwLock::new(admin_api_key.into())),
+            service: Arc::new(service),
+            config,
+            config_path: Arc::new(config_path.into()),
+            token_manager,
+            proxy,
+        }
+    }
+}
+
+/// 生成新的 Admin API 密钥
+pub fn generate_admin_api_key() -> String {
+    format!("sk-admin-{}", uuid::Uuid::new_v4().simple())
+}
+
+/// 确保配置中有 Admin API 密钥，没有时生成并写回配置文件
+pub fn ensure_admin_api_key(config: &mut Config, config_path: &str) -> String {
+    if let Some(key) = config.admin_api_key.as_ref().filter(|k| !k.trim().is_empty()) {
+        return key.clone();
+    }
+
+    let key = generate_admin_api_key();
+    config.admin_api_key = Some(key.clone());
+    match config.save(config_path) {
+        Ok(()) => tracing::info!(
+            "已生成 Admin API Key {}，完整密钥见配置文件: {}",
+            mask_secret(&key),
+            config_path
+        ),
+        Err(e) => tracing::warn!(
+            "已生成 Admin API Key {}（保存到 {} 失败，重启后会重新生成）: {}",
+            mask_secret(&key),
+            config_path,
+            e
+        ),
+    }
+    key
+}
+
+/// 发起 Admin 请求的操作者（认证中间件写入请求扩展，供审计日志使用）
+#[derive(Debug, Clone)]
+pub struct AdminActor(pub String);
+
+/// Admin API 认证中间件
+///
+/// 需要 `x-api-key` / `Authorization: Bearer` / `?key=` 携带 Admin API 密钥或 Admin UI 的会话令牌
+/// （桌面内嵌窗口通过 Tauri IPC 获取会话令牌，不依赖客户端可伪造的请求头）
+pub async fn admin_auth_middleware(
+    State(state): State<AdminState>,
+    mut request: Request<Body>,
+    next: Next,
+) -> Response {
+    let actor = auth::extract_api_key(&request).and_then(|key| {
+        if auth::constant_time_eq(&key, &state.admin_api_key.read()) {
+            Some(format!("api-key {}", mask_secret(&key)))
+        } else if admin_ui::is_valid_session(&key) {
+            Some(format!("admin-ui session {}", mask_secret(&key)))
+        } else {
+            None
+        }
+    });
+
+    if let Some(actor) = actor {
+        request.extensions_mut().insert(AdminActor(actor));
+        next.run(request).await
+    } else {
+        let error = AdminErrorResponse::authentication_error();
+        (StatusCode::UNAUTHORIZED, Json(error)).into_response()
+    }
+}
+
+/// Admin API 审计中间件：记录所有修改操作（GET/HEAD/OPTIONS 以外的请求）
+pub async fn audit_middleware(request: Request<Body>, next: Next) -> Response {
+    let method = request.method().clone();
+    if matches!(method, Method::GET | Method::HEAD | Method::OPTIONS) {
+        return next.run(request).await;
+    }
+
+    let path = request.uri().path().to_string();
+    let actor = request
+        .extensions()
+        .get::<AdminActor>()
+        .map_or_else(|| "unknown".to_string(), |actor| actor.0.clone());
+    let client_ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_string());
+
+    let response = next.run(request).await;
+    AUDIT_LOG.record(AuditEntry {
+        timestamp: chrono::Utc::now(),
+        actor,
+        client_ip,
+        method: method.to_string(),
+        action: describe(method.as_str(), &path),
+        path,
+        status: response.status().as_u16(),
+    });
+    response
+}
@@ -12,18 +12,20 @@
 //! # 使用
 //! ```ignore
 //! let admin_service = AdminService::new(token_manager.clone());
-//! let admin_state = AdminState::new(admin_api_key, admin_service);
+//! let admin_state = AdminState::new(admin_api_key, admin_service, config, config_path, token_manager, proxy);
 //! let admin_router = create_admin_router(admin_state);
 //! ```
 
+mod confirmation;
 mod error;
 mod handlers;
 pub mod local_account;
 mod middleware;
 mod router;
 mod service;
+mod text_import;
 pub mod types;
 
-pub use middleware::AdminState;
+pub use middleware::{AdminState, ensure_admin_api_key};
 pub use router::create_admin_router;
 pub use service::AdminService;
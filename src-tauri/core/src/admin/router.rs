@@ -0,0 +1,264 @@
+//! Admin API 路由配置
+
+use axum::{
+    Router,
+    routing::{delete, get, post, put},
+};
+
+use super::{
+    handlers::{
+        add_credential, update_credential, delete_credential, get_all_credentials, get_credential_balance,
+        get_credential_usage_history,
+        reset_failure_count, regenerate_credential_machine_id, set_credential_disabled, import_credentials, import_credentials_text, validate_credentials,
+        get_logs, clear_logs, admin_events, get_log_level, set_log_level, get_config, update_config,
+        get_effective_config,
+        // 日志文件
+        get_log_files, download_log_archive,
+        // 新增 handlers
+        get_machine_id, backup_machine_id, restore_machine_id, reset_machine_id,
+        batch_delete_credentials, dedupe_credentials, export_credentials, import_encrypted_credentials,
+        get_locked_model, get_model_lock_status, set_locked_model, get_kiro_profiles, set_kiro_profiles,
+        // 本地账号
+        get_local_credential, import_local_credential, discover_credentials, import_discovered_credentials,
+        switch_to_credential, switch_to_next_credential,
+        // 刷新凭证
+        refresh_credential, refresh_all_credentials,
+        // 分组管理
+        get_groups, add_group, delete_group, rename_group, set_group_budget, set_active_group, set_fallback_group, set_credential_group,
+        // 代理服务控制
+        get_proxy_status, proxy_action, get_group_proxies, set_group_proxies,
+        // 版本信息与运行指标
+        get_version, get_metrics, get_realtime_stats,
+        // 租户 API Key
+        get_api_keys, add_api_key, update_api_key, delete_api_key,
+        // 模型目录
+        get_models, add_model, update_model, delete_model,
+        // 模型映射
+        get_model_mappings, set_model_mappings,
+        // 自动分组规则
+        get_group_rules, set_group_rules,
+        // 凭证轮换计划
+        get_rotation_schedule, set_rotation_schedule,
+        // 请求改写
+        get_request_transform, set_request_transform, get_redaction, set_redaction,
+        // 响应后处理
+        get_post_process, set_post_process,
+        // 额度告警
+        get_alerts, set_alerts,
+        // 生命周期 Webhook
+        get_webhooks, set_webhooks, test_webhooks,
+        // 审计日志
+        get_audit_log,
+        // 对话记录
+        get_transcripts, get_transcript, purge_transcripts, get_transcript_config, set_transcript_config,
+        // 局域网访问控制
+        get_access_control, set_access_control, clear_access_rejections,
+        // 响应缓存
+        get_response_cache, set_response_cache, purge_response_cache,
+        // Admin API Key
+        rotate_admin_api_key,
+        // 备份
+        export_backup, import_backup,
+    },
+    middleware::{AdminState, admin_auth_middleware, audit_middleware},
+};
+
+/// 创建 Admin API 路由
+///
+/// # 端点
+/// - `GET /credentials` - 获取所有凭证状态（Token 默认脱敏，`?reveal=true` + `x-reveal-key` 返回明文）
+/// - `POST /credentials` - 添加新凭证
+/// - `POST /credentials/import` - 批量导入凭证
+/// - `POST /credentials/import-text` - 从纯文本 / CSV 批量导入凭证
+/// - `POST /credentials/validate` - 批量验证凭证（不添加）
+/// - `GET /credentials/local` - 获取本地凭证信息
+/// - `POST /credentials/import-local` - 导入本地凭证
+/// - `GET /credentials/discover` - 扫描 SSO 缓存目录中的凭证
+/// - `POST /credentials/discover/import` - 批量导入发现的凭证
+/// - `PUT /credentials/:id` - 编辑凭证（认证方式、OIDC Client、代理、分组、替换 refreshToken）
+/// - `DELETE /credentials/:id` - 删除凭证
+/// - `DELETE /credentials/batch` - 批量删除凭证
+/// - `POST /credentials/dedupe` - 合并重复凭证
+/// - `POST /credentials/export` - 导出凭证（可选口令加密）
+/// - `POST /credentials/import-encrypted` - 导入加密凭证包
+/// - `POST /credentials/:id/disabled` - 设置凭证禁用状态
+/// - `POST /credentials/:id/reset` - 重置失败计数
+/// - `POST /credentials/:id/switch` - 切换到该账号
+/// - `GET /credentials/:id/balance` - 获取凭证余额
+/// - `GET /credentials/:id/usage-history` - 获取凭证额度快照时间线
+/// - `GET /logs` - 获取运行日志
+/// - `POST /logs/clear` - 清空日志
+/// - `GET /logs/files` - 列出有日志文件的日期
+/// - `GET /logs/archive/{date}` - 下载某一天的日志归档（gzip）
+/// - `GET /events` - 实时事件流（SSE：日志、凭证状态、反代启停）
+/// - `GET /log-level` - 获取当前日志过滤器
+/// - `POST /log-level` - 运行时调整日志级别（全局及按模块）
+/// - `GET /config` - 获取配置
+/// - `POST /config` - 更新配置
+/// - `GET /config/effective` - 获取实际生效的配置（含运行时状态，密钥脱敏）
+/// - `GET /config/model` - 获取锁定模型
+/// - `POST /config/model` - 设置锁定模型
+/// - `GET /config/model/status` - 获取模型锁定监控状态
+/// - `GET /kiro/profiles` - 列出 Kiro Profile
+/// - `POST /kiro/profiles` - 选择模型锁定作用的 Kiro Profile
+/// - `GET /machine-id` - 获取机器码
+/// - `POST /machine-id/backup` - 备份机器码
+/// - `POST /machine-id/restore` - 恢复机器码（`?dryRun=true` 预览，`?confirmToken=` 确认）
+/// - `POST /machine-id/reset` - 重置机器码（`?dryRun=true` 预览，`?confirmToken=` 确认）
+/// - `GET /apikeys` - 获取租户 API Key 列表及用量
+/// - `POST /apikeys` - 添加租户 API Key
+/// - `PUT /apikeys/:id` - 更新租户 API Key
+/// - `DELETE /apikeys/:id` - 删除租户 API Key
+/// - `GET /model-mappings` - 获取模型映射表
+/// - `PUT /model-mappings` - 替换模型映射表（立即生效）
+/// - `PUT /groups/:id/budget` - 设置分组月度预算
+/// - `GET /group-rules` - 获取导入自动分组规则
+/// - `PUT /group-rules` - 替换导入自动分组规则
+/// - `GET /rotation-schedule` - 获取凭证轮换计划
+/// - `PUT /rotation-schedule` - 替换凭证轮换计划（一分钟内生效）
+/// - `GET /request-transform` - 获取全局请求改写配置
+/// - `PUT /request-transform` - 替换全局请求改写配置（立即生效）
+/// - `GET /redaction` - 获取出站内容脱敏配置
+/// - `PUT /redaction` - 替换出站内容脱敏配置（立即生效）
+/// - `GET /post-process` - 获取响应后处理配置
+/// - `PUT /post-process` - 替换响应后处理配置（对之后开始的响应生效）
+/// - `GET /alerts` - 获取额度告警配置
+/// - `PUT /alerts` - 替换额度告警配置（立即生效）
+/// - `GET /webhooks` - 获取凭证生命周期 Webhook
+/// - `PUT /webhooks` - 替换凭证生命周期 Webhook（立即生效）
+/// - `POST /webhooks/test` - 发送测试消息
+/// - `GET /access` - 获取局域网访问控制配置与拒绝记录
+/// - `PUT /access` - 替换局域网访问控制配置
+/// - `POST /access/rejections/clear` - 清空拒绝记录
+/// - `POST /proxy` - 启动/停止/重启反代服务（可同时切换分组）
+/// - `GET /proxy/status` - 获取反代服务状态
+/// - `GET /group-proxies` - 获取分组反代实例配置与状态
+/// - `PUT /group-proxies` - 替换分组 → 端口映射并同步实例
+/// - `GET /metrics` - 获取运行指标（流式响应结束原因计数）
+/// - `GET /stats/realtime` - 获取最近 1/5/15 分钟的吞吐、延迟和错误率
+/// - `GET /response-cache` - 获取响应缓存配置与命中统计
+/// - `PUT /response-cache` - 替换响应缓存配置（立即生效）
+/// - `DELETE /response-cache` - 清空响应缓存
+/// - `POST /admin-key/rotate` - 轮换 Admin API Key
+/// - `POST /backup/export` - 导出加密的网关状态备份
+/// - `POST /backup/import` - 导入备份（凭证与分组立即生效）
+/// - `GET /audit` - 查询修改操作的审计日志（`?limit=N&since=RFC3339`）
+/// - `GET /transcripts` - 检索对话记录（`?apiKeyId=&model=&q=&since=&until=&limit=`）
+/// - `DELETE /transcripts` - 删除所有对话记录
+/// - `GET /transcripts/config` - 获取对话记录配置
+/// - `PUT /transcripts/config` - 替换对话记录配置（立即生效）
+/// - `GET /transcripts/{id}` - 获取完整的对话记录
+///
+/// # 认证
+/// 需要 Admin API Key 认证，支持：
+/// - `x-api-key` header
+/// - `Authorization: Bearer <token>` header
+/// - `?key=` 查询参数（用于 EventSource 等无法设置请求头的场景）
+///
+/// Admin UI 使用会话令牌认证（桌面内嵌窗口通过 Tauri IPC 获取）
+pub fn create_admin_router(state: AdminState) -> Router {
+    Router::new()
+        .route(
+            "/credentials",
+            get(get_all_credentials).post(add_credential),
+        )
+        .route("/credentials/import", post(import_credentials))
+        .route("/credentials/import-text", post(import_credentials_text))
+        .route("/credentials/validate", post(validate_credentials))
+        .route("/credentials/refresh-all", post(refresh_all_credentials))
+        .route("/credentials/switch-next", post(switch_to_next_credential))
+        .route("/credentials/local", get(get_local_credential))
+        .route("/credentials/import-local", post(import_local_credential))
+        .route("/credentials/discover", get(discover_credentials))
+        .route("/credentials/discover/import", post(import_discovered_credentials))
+        .route("/credentials/batch", delete(batch_delete_credentials))
+        .route("/credentials/dedupe", post(dedupe_credentials))
+        .route("/credentials/export", post(export_credentials))
+        .route("/credentials/import-encrypted", post(import_encrypted_credentials))
+        .route("/credentials/{id}", delete(delete_credential).put(update_credential))
+        .route("/credentials/{id}/disabled", post(set_credential_disabled))
+        .route("/credentials/{id}/reset", post(reset_failure_count))
+        .route("/credentials/{id}/machine-id", post(regenerate_credential_machine_id))
+        .route("/credentials/{id}/switch", post(switch_to_credential))
+        .route("/credentials/{id}/balance", get(get_credential_balance))
+        .route("/credentials/{id}/usage-history", get(get_credential_usage_history))
+        .route("/credentials/{id}/refresh", post(refresh_credential))
+        .route("/logs", get(get_logs))
+        .route("/logs/clear", post(clear_logs))
+        .route("/logs/files", get(get_log_files))
+        .route("/logs/archive/{date}", get(download_log_archive))
+        .route("/events", get(admin_events))
+        .route("/log-level", get(get_log_level).post(set_log_level))
+        .route("/config", get(get_config).post(update_config))
+        .route("/config/effective", get(get_effective_config))
+        .route("/config/model", get(get_locked_model).post(set_locked_model))
+        .route("/config/model/status", get(get_model_lock_status))
+        .route("/kiro/profiles", get(get_kiro_profiles).post(set_kiro_profiles))
+        .route("/machine-id", get(get_machine_id))
+        .route("/machine-id/backup", post(backup_machine_id))
+        .route("/machine-id/restore", post(restore_machine_id))
+        .route("/machine-id/reset", post(reset_machine_id))
+        // 分组管理
+        .route("/groups", get(get_groups).post(add_group))
+        .route("/groups/{id}", delete(delete_group).put(rename_group))
+        .route("/groups/{id}/budget", put(set_group_budget))
+        .route("/groups/active", post(set_active_group))
+        .route("/groups/fallback", post(set_fallback_group))
+        .route("/credentials/{id}/group", post(set_credential_group))
+        // 代理服务控制
+        .route("/proxy", post(proxy_action))
+        .route("/proxy/status", get(get_proxy_status))
+        .route("/group-proxies", get(get_group_proxies).put(set_group_proxies))
+        // 版本信息与运行指标
+        .route("/version", get(get_version))
+        .route("/metrics", get(get_metrics))
+        .route("/stats/realtime", get(get_realtime_stats))
+        // 租户 API Key
+        .route("/apikeys", get(get_api_keys).post(add_api_key))
+        .route("/apikeys/{id}", delete(delete_api_key).put(update_api_key))
+        // 模型目录
+        .route("/models", get(get_models).post(add_model))
+        .route("/models/{id}", delete(delete_model).put(update_model))
+        // 模型映射
+        .route("/model-mappings", get(get_model_mappings).put(set_model_mappings))
+        // 自动分组规则
+        .route("/group-rules", get(get_group_rules).put(set_group_rules))
+        // 凭证轮换计划
+        .route("/rotation-schedule", get(get_rotation_schedule).put(set_rotation_schedule))
+        // 请求改写
+        .route("/request-transform", get(get_request_transform).put(set_request_transform))
+        .route("/redaction", get(get_redaction).put(set_redaction))
+        // 响应后处理
+        .route("/post-process", get(get_post_process).put(set_post_process))
+        // 额度告警
+        .route("/alerts", get(get_alerts).put(set_alerts))
+        // 生命周期 Webhook
+        .route("/webhooks", get(get_webhooks).put(set_webhooks))
+        .route("/webhooks/test", post(test_webhooks))
+        // 局域网访问控制
+        .route("/access", get(get_access_control).put(set_access_control))
+        .route("/access/rejections/clear", post(clear_access_rejections))
+        // 响应缓存
+        .route(
+            "/response-cache",
+            get(get_response_cache).put(set_response_cache).delete(purge_response_cache),
+        )
+        // Admin API Key
+        .route("/admin-key/rotate", post(rotate_admin_api_key))
+        // 备份
+        .route("/backup/export", post(export_backup))
+        .route("/backup/import", post(import_backup))
+        // 审计日志
+        .route("/audit", get(get_audit_log))
+        // 对话记录
+        .route("/transcripts", get(get_transcripts).delete(purge_transcripts))
+        .route("/transcripts/config", get(get_transcript_config).put(set_transcript_config))
+        .route("/transcripts/{id}", get(get_transcript))
+        // 审计在认证之后执行，只记录已通过认证的操作
+        .layer(axum::middleware::from_fn(audit_middleware))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            admin_auth_middleware,
+        ))
+        .with_state(state)
+}
@@ -3,14 +3,41 @@
 use std::sync::Arc;
 
 use crate::kiro::model::credentials::KiroCredentials;
+use crate::kiro::model::upstream_error::UpstreamError;
+use crate::group_rules::{self, CredentialTraits};
 use crate::kiro::token_manager::MultiTokenManager;
+use crate::model::config::GroupRule;
+use crate::usage_history::USAGE_HISTORY;
+use crate::common::redact::mask_optional;
+use chrono::Utc;
 
 use super::error::AdminServiceError;
 use super::types::{
     AddCredentialRequest, AddCredentialResponse, BalanceResponse, CredentialStatusItem,
-    CredentialsStatusResponse, RefreshCredentialResponse, RefreshAllResponse, RefreshResultItem,
+    CredentialsStatusResponse, DedupeResponse, RefreshCredentialResponse, RefreshAllResponse, RefreshResultItem,
+    ImportItemReport, UpdateCredentialRequest, UsageHistoryResponse, ValidateCredentialsResponse,
+    ValidateResultItem,
 };
 
+/// 凭证快照的脱敏级别
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Redaction {
+    /// 遮蔽 Refresh Token / Access Token（默认）
+    Redacted,
+    /// 返回明文
+    Revealed,
+}
+
+impl Redaction {
+    /// 按级别处理单个敏感字段
+    pub fn apply(self, value: Option<String>) -> Option<String> {
+        match self {
+            Redaction::Redacted => mask_optional(value),
+            Redaction::Revealed => value,
+        }
+    }
+}
+
 /// Admin 服务
 ///
 /// 封装所有 Admin API 的业务逻辑
@@ -24,7 +51,7 @@ impl AdminService {
     }
 
     /// 获取所有凭证状态
-    pub fn get_all_credentials(&self) -> CredentialsStatusResponse {
+    pub fn get_all_credentials(&self, redaction: Redaction) -> CredentialsStatusResponse {
         let snapshot = self.token_manager.snapshot();
 
         let mut credentials: Vec<CredentialStatusItem> = snapshot
@@ -34,6 +61,7 @@ impl AdminService {
                 id: entry.id,
                 disabled: entry.disabled,
                 failure_count: entry.failure_count,
+                cooldown_secs: entry.cooldown_secs,
                 is_current: entry.id == snapshot.current_id,
                 expires_at: entry.expires_at,
                 auth_method: entry.auth_method,
@@ -44,11 +72,19 @@ impl AdminService {
                 usage_limit: entry.usage_limit,
                 remaining: entry.remaining,
                 next_reset_at: entry.next_reset_at,
-                refresh_token: entry.refresh_token,
-                access_token: entry.access_token,
+                refresh_token: redaction.apply(entry.refresh_token),
+                access_token: redaction.apply(entry.access_token),
                 profile_arn: entry.profile_arn,
                 status: entry.status,
                 group_id: entry.group_id,
+                proxy_url: entry.proxy_url,
+                kiro_version: entry.kiro_version,
+                system_version: entry.system_version,
+                node_version: entry.node_version,
+                machine_id_salt: entry.machine_id_salt,
+                last_health_check: entry.last_health_check,
+                quota_reset_at: entry.quota_reset_at,
+                request_stats: entry.request_stats,
             })
             .collect();
 
@@ -64,7 +100,7 @@ impl AdminService {
             total: snapshot.total,
             available: snapshot.available,
             current_id: snapshot.current_id,
-            local_refresh_token,
+            local_refresh_token: redaction.apply(local_refresh_token),
             credentials,
         }
     }
@@ -101,6 +137,13 @@ impl AdminService {
             .map_err(|e| self.classify_error(e, id))
     }
 
+    /// 为凭证重新生成随机 machineId
+    pub fn regenerate_machine_id(&self, id: u64) -> Result<String, AdminServiceError> {
+        self.token_manager
+            .regenerate_machine_id(id)
+            .map_err(|e| self.classify_error(e, id))
+    }
+
     /// 刷新单个凭证（刷新 Token + 更新余额 + 重置失败计数）
     pub async fn refresh_credential(&self, id: u64) -> Result<RefreshCredentialResponse, AdminServiceError> {
         // 首先重置失败计数并启用凭证
@@ -209,6 +252,88 @@ impl AdminService {
         })
     }
 
+    /// 批量验证凭证（并发执行，不添加），结果与请求顺序一致
+    pub async fn validate_credentials(&self, items: Vec<AddCredentialRequest>) -> ValidateCredentialsResponse {
+        use futures::stream::{self, StreamExt};
+
+        let sso_cache = super::local_account::SsoCache::load();
+        let sso_cache = &sso_cache;
+        let results: Vec<ValidateResultItem> = stream::iter(items.into_iter().enumerate())
+            .map(|(index, item)| async move { self.validate_one(index, item, sso_cache).await })
+            .buffered(10) // 最多 10 个并发
+            .collect()
+            .await;
+
+        let valid_count = results.iter().filter(|r| r.valid).count();
+        ValidateCredentialsResponse {
+            valid_count,
+            invalid_count: results.len() - valid_count,
+            total: results.len(),
+            results,
+        }
+    }
+
+    async fn validate_one(
+        &self,
+        index: usize,
+        mut item: AddCredentialRequest,
+        sso_cache: &super::local_account::SsoCache,
+    ) -> ValidateResultItem {
+        let completed = sso_cache.complete_auth(
+            &item.refresh_token,
+            &mut item.auth_method,
+            &mut item.client_id,
+            &mut item.client_secret,
+        );
+        let mut result = ValidateResultItem {
+            index,
+            valid: false,
+            auth_method: item.auth_method.clone(),
+            expires_at: None,
+            email: None,
+            subscription_title: None,
+            usage_limit: None,
+            remaining: None,
+            existing_id: self.token_manager.find_duplicate(&item.refresh_token),
+            rotated_refresh_token: None,
+            error: None,
+        };
+        if let Err(reason) = completed {
+            result.error = Some(reason);
+            return result;
+        }
+
+        let cred = KiroCredentials {
+            refresh_token: Some(item.refresh_token.clone()),
+            auth_method: Some(item.auth_method),
+            client_id: item.client_id,
+            client_secret: item.client_secret,
+            ..KiroCredentials::default()
+        };
+
+        match self.token_manager.validate_credential(&cred).await {
+            Ok((refreshed, usage)) => {
+                result.valid = true;
+                result.expires_at = refreshed.expires_at;
+                result.rotated_refresh_token = refreshed
+                    .refresh_token
+                    .filter(|t| *t != item.refresh_token);
+                match usage {
+                    Ok(usage) => {
+                        let usage_limit = usage.usage_limit();
+                        result.email = usage.email().map(|s| s.to_string());
+                        result.subscription_title = usage.subscription_title().map(|s| s.to_string());
+                        result.usage_limit = Some(usage_limit);
+                        result.remaining = Some((usage_limit - usage.current_usage()).max(0.0));
+                    }
+                    Err(e) => result.error = Some(format!("额度查询失败: {}", e)),
+                }
+            }
+            Err(e) => result.error = Some(e.to_string()),
+        }
+        result
+    }
+
     /// 批量刷新所有凭证（兼容旧 API）
     pub async fn refresh_all_credentials(&self) -> Result<RefreshAllResponse, AdminServiceError> {
         self.refresh_credentials(vec![]).await
@@ -253,11 +378,34 @@ impl AdminService {
         })
     }
 
+    /// 获取凭证的额度快照时间线
+    pub fn get_usage_history(
+        &self,
+        id: u64,
+        hours: Option<u32>,
+    ) -> Result<UsageHistoryResponse, AdminServiceError> {
+        let exists = self.token_manager.snapshot().entries.iter().any(|e| e.id == id);
+        if !exists {
+            return Err(AdminServiceError::NotFound { id });
+        }
+
+        let since = hours.map(|h| Utc::now() - chrono::Duration::hours(h as i64));
+        Ok(UsageHistoryResponse {
+            id,
+            points: USAGE_HISTORY.history(id, since),
+        })
+    }
+
     /// 添加新凭证
     pub async fn add_credential(
         &self,
-        req: AddCredentialRequest,
+        mut req: AddCredentialRequest,
     ) -> Result<AddCredentialResponse, AdminServiceError> {
+        // 补全认证方式和 IdC 客户端注册
+        super::local_account::SsoCache::load()
+            .complete_auth(&req.refresh_token, &mut req.auth_method, &mut req.client_id, &mut req.client_secret)
+            .map_err(AdminServiceError::InvalidCredential)?;
+
         // 构建凭证对象
         let new_cred = KiroCredentials {
             id: None,
@@ -268,6 +416,14 @@ impl AdminService {
             auth_method: Some(req.auth_method),
             client_id: req.client_id,
             client_secret: req.client_secret,
+            proxy_url: None,
+            proxy_username: None,
+            proxy_password: None,
+            kiro_version: None,
+            system_version: None,
+            node_version: None,
+            machine_id: None,
+            machine_id_salt: None,
             email: None,
             subscription_title: None,
             current_usage: None,
@@ -293,14 +449,39 @@ impl AdminService {
     }
 
     /// 批量导入凭证
+    ///
+    /// 未显式指定分组（即为 "default"）的凭证在添加后按 `group_rules` 自动分组
     pub async fn import_credentials(
         &self,
         items: Vec<super::types::ImportCredentialItem>,
+        group_rules: &[GroupRule],
     ) -> Result<super::types::ImportCredentialsResponse, AdminServiceError> {
         let mut imported_ids = Vec::new();
         let mut skipped_reasons: Vec<String> = Vec::new();
-
-        for item in items {
+        let mut reports = Vec::with_capacity(items.len());
+        let sso_cache = super::local_account::SsoCache::load();
+
+        for (index, mut item) in items.into_iter().enumerate() {
+            let explicit_group = item.group_id != "default";
+            // 补全认证方式和 IdC 客户端注册，缺少字段时跳过并说明缺少什么
+            if let Err(reason) = sso_cache.complete_auth(
+                &item.refresh_token,
+                &mut item.auth_method,
+                &mut item.client_id,
+                &mut item.client_secret,
+            ) {
+                tracing::warn!("导入凭证失败，已跳过: {}", reason);
+                reports.push(ImportItemReport {
+                    index,
+                    success: false,
+                    credential_id: None,
+                    group_id: None,
+                    matched_rule: None,
+                    error: Some(reason.clone()),
+                });
+                skipped_reasons.push(reason);
+                continue;
+            }
             // 构建凭证对象
             let new_cred = KiroCredentials {
                 id: None,
@@ -311,6 +492,14 @@ impl AdminService {
                 auth_method: Some(item.auth_method),
                 client_id: item.client_id,
                 client_secret: item.client_secret,
+                proxy_url: None,
+                proxy_username: None,
+                proxy_password: None,
+                kiro_version: None,
+                system_version: None,
+                node_version: None,
+                machine_id: None,
+                machine_id_salt: None,
                 email: None,
                 subscription_title: None,
                 current_usage: None,
@@ -325,10 +514,31 @@ impl AdminService {
             match self.token_manager.add_credential(new_cred).await {
                 Ok(id) => {
                     imported_ids.push(id);
+                    let (group_id, matched_rule) = if explicit_group {
+                        (item.group_id, None)
+                    } else {
+                        self.apply_group_rules(id, group_rules)
+                    };
+                    reports.push(ImportItemReport {
+                        index,
+                        success: true,
+                        credential_id: Some(id),
+                        group_id: Some(group_id),
+                        matched_rule,
+                        error: None,
+                    });
                 }
                 Err(e) => {
                     let reason = e.to_string();
                     tracing::warn!("导入凭证失败，已跳过: {}", reason);
+                    reports.push(ImportItemReport {
+                        index,
+                        success: false,
+                        credential_id: None,
+                        group_id: None,
+                        matched_rule: None,
+                        error: Some(reason.clone()),
+                    });
                     skipped_reasons.push(reason);
                 }
             }
@@ -349,9 +559,111 @@ impl AdminService {
             skipped_count,
             credential_ids: imported_ids,
             skipped_reasons,
+            items: reports,
         })
     }
 
+    /// 按自动分组规则移动新导入的凭证，返回 (最终分组, 命中规则描述)
+    ///
+    /// 邮箱和订阅类型在添加凭证时通过余额查询获得，查询失败时只能按认证方式匹配
+    fn apply_group_rules(&self, id: u64, rules: &[GroupRule]) -> (String, Option<String>) {
+        let snapshot = self.token_manager.snapshot();
+        let Some(entry) = snapshot.entries.iter().find(|e| e.id == id) else {
+            return ("default".to_string(), None);
+        };
+        let traits = CredentialTraits {
+            email: entry.email.as_deref(),
+            subscription_title: entry.subscription_title.as_deref(),
+            auth_method: entry.auth_method.as_deref(),
+        };
+        let Some(rule) = group_rules::assign(rules, &traits) else {
+            return (entry.group_id.clone(), None);
+        };
+
+        match self.token_manager.set_group(id, &rule.group_id) {
+            Ok(()) => {
+                tracing::info!("凭证 #{} 按规则自动分组: {}", id, group_rules::describe(rule));
+                (rule.group_id.clone(), Some(group_rules::describe(rule)))
+            }
+            Err(e) => {
+                tracing::warn!("凭证 #{} 自动分组失败: {}", id, e);
+                (entry.group_id.clone(), None)
+            }
+        }
+    }
+
+    /// 编辑凭证
+    ///
+    /// 修改认证信息或代理后会用新配置刷新 Token 重新验证，验证失败时凭证保持不变
+    pub async fn update_credential(
+        &self,
+        id: u64,
+        req: UpdateCredentialRequest,
+    ) -> Result<(), AdminServiceError> {
+        let mut cred = self
+            .token_manager
+            .get_credentials_for_export(&[id])
+            .into_iter()
+            .next()
+            .ok_or(AdminServiceError::NotFound { id })?;
+
+        // 空字符串表示清除
+        let clearable = |value: String| {
+            let value = value.trim().to_string();
+            if value.is_empty() { None } else { Some(value) }
+        };
+        if let Some(auth_method) = req.auth_method {
+            cred.auth_method = Some(auth_method);
+        }
+        if let Some(client_id) = req.client_id {
+            cred.client_id = clearable(client_id);
+        }
+        if let Some(client_secret) = req.client_secret {
+            cred.client_secret = clearable(client_secret);
+        }
+        if let Some(proxy_url) = req.proxy_url {
+            cred.proxy_url = clearable(proxy_url);
+        }
+        if let Some(proxy_username) = req.proxy_username {
+            cred.proxy_username = clearable(proxy_username);
+        }
+        if let Some(proxy_password) = req.proxy_password {
+            cred.proxy_password = clearable(proxy_password);
+        }
+        if let Some(kiro_version) = req.kiro_version {
+            cred.kiro_version = clearable(kiro_version);
+        }
+        if let Some(system_version) = req.system_version {
+            cred.system_version = clearable(system_version);
+        }
+        if let Some(node_version) = req.node_version {
+            cred.node_version = clearable(node_version);
+        }
+        if let Some(salt) = req.machine_id_salt {
+            cred.machine_id_salt = clearable(salt);
+        }
+        if let Some(group_id) = req.group_id {
+            cred.group_id = group_id;
+        }
+        if let Some(refresh_token) = req.refresh_token {
+            cred.refresh_token = Some(refresh_token.trim().to_string());
+        }
+
+        self.token_manager
+            .update_credential(id, cred)
+            .await
+            .map_err(|e| {
+                let msg = e.to_string();
+                if msg.contains("不存在") {
+                    AdminServiceError::NotFound { id }
+                } else if msg.contains("代理地址无效") || msg.contains("凭证已存在") {
+                    AdminServiceError::InvalidCredential(msg)
+                } else {
+                    self.classify_add_error(e)
+                }
+            })
+    }
+
     /// 删除凭证
     pub fn delete_credential(&self, id: u64) -> Result<(), AdminServiceError> {
         self.token_manager
@@ -359,6 +671,45 @@ impl AdminService {
             .map_err(|e| self.classify_delete_error(e, id))
     }
 
+    /// 合并重复凭证
+    ///
+    /// `refresh` 时先为尚未获取邮箱的凭证查询一次账号信息，使同一账号的不同 refreshToken 也能被识别
+    pub async fn dedupe_credentials(&self, dry_run: bool, refresh: bool) -> Result<DedupeResponse, AdminServiceError> {
+        if refresh {
+            let missing: Vec<u64> = self
+                .token_manager
+                .snapshot()
+                .entries
+                .iter()
+                .filter(|e| !e.disabled && e.email.is_none())
+                .map(|e| e.id)
+                .collect();
+            for id in missing {
+                if let Err(e) = self.token_manager.get_usage_limits_for(id).await {
+                    tracing::warn!("去重前查询凭证 #{} 账号信息失败: {}", id, e);
+                }
+            }
+        }
+
+        let groups = self
+            .token_manager
+            .dedupe_credentials(dry_run)
+            .map_err(|e| AdminServiceError::InternalError(e.to_string()))?;
+        let removed: usize = groups.iter().map(|g| g.removed_ids.len()).sum();
+        let message = match (dry_run, removed) {
+            (_, 0) => "没有发现重复凭证".to_string(),
+            (true, n) => format!("发现 {} 组重复凭证，将删除 {} 个", groups.len(), n),
+            (false, n) => format!("已合并 {} 组重复凭证，删除 {} 个", groups.len(), n),
+        };
+        Ok(DedupeResponse {
+            success: true,
+            message,
+            dry_run,
+            removed,
+            groups,
+        })
+    }
+
     /// 分类简单操作错误（set_disabled, reset_and_enable）
     fn classify_error(&self, e: anyhow::Error, id: u64) -> AdminServiceError {
         let msg = e.to_string();
@@ -379,9 +730,7 @@ impl AdminService {
         }
 
         // 2. 检测账户暂停/被封禁（返回友好消息，完整错误已记录到日志）
-        if msg.contains("TEMPORARILY_SUSPENDED") ||
-           msg.contains("temporarily is suspended") ||
-           msg.contains("temporarily suspended") {
+        if UpstreamError::find(&e).is_some_and(UpstreamError::is_suspended) {
             // 完整错误已通过 tracing::error! 记录到终端
             tracing::debug!("凭证 #{} 账户暂停原始错误: {}", id, msg);
             return AdminServiceError::UpstreamError(
@@ -433,9 +782,7 @@ impl AdminService {
         let msg = e.to_string();
 
         // 1. 检测账户暂停/被封禁
-        if msg.contains("TEMPORARILY_SUSPENDED") ||
-           msg.contains("temporarily is suspended") ||
-           msg.contains("temporarily suspended") {
+        if UpstreamError::find(&e).is_some_and(UpstreamError::is_suspended) {
             tracing::debug!("添加凭证失败，账户暂停原始错误: {}", msg);
             return AdminServiceError::InvalidCredential(
                 "账户已被暂停，无法添加此凭证，需要联系 AWS 支持解封".to_string()
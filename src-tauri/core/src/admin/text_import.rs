@@ -0,0 +1,182 @@
+//! 纯文本 / CSV 凭证导入解析
+//!
+//! 支持两种粘贴格式：
+//! - 纯文本：每行一个 refreshToken
+//! - CSV：列依次为 `token, authMethod, clientId, clientSecret, group`，后面的列可省略；
+//!   首行为表头时按列名匹配（不区分大小写），列顺序可以不同
+//!
+//! 空行和 `#` 开头的注释行会被忽略。
+
+use super::types::{ImportCredentialItem, ImportTextFormat, default_auth_method};
+
+/// 表头中表示 refreshToken 列的名称
+const TOKEN_COLUMNS: &[&str] = &["token", "refreshtoken", "refresh_token"];
+
+/// CSV 列
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Column {
+    Token,
+    AuthMethod,
+    ClientId,
+    ClientSecret,
+    Group,
+    Ignored,
+}
+
+impl Column {
+    /// 无表头时的列顺序
+    const POSITIONAL: [Column; 5] = [
+        Column::Token,
+        Column::AuthMethod,
+        Column::ClientId,
+        Column::ClientSecret,
+        Column::Group,
+    ];
+
+    fn from_header(name: &str) -> Self {
+        let name = name.trim().to_ascii_lowercase();
+        if TOKEN_COLUMNS.contains(&name.as_str()) {
+            return Column::Token;
+        }
+        match name.as_str() {
+            "authmethod" | "auth_method" => Column::AuthMethod,
+            "clientid" | "client_id" => Column::ClientId,
+            "clientsecret" | "client_secret" => Column::ClientSecret,
+            "group" | "groupid" | "group_id" => Column::Group,
+            _ => Column::Ignored,
+        }
+    }
+}
+
+/// 解析粘贴的文本为导入项
+///
+/// `default_group` 用于未指定分组的行；错误消息包含出错的行号。
+pub fn parse_import_text(
+    text: &str,
+    format: ImportTextFormat,
+    default_group: &str,
+) -> Result<Vec<ImportCredentialItem>, String> {
+    let lines: Vec<(usize, &str)> = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+
+    let is_csv = match format {
+        ImportTextFormat::Auto => lines.iter().any(|(_, line)| line.contains(',')),
+        ImportTextFormat::Lines => false,
+        ImportTextFormat::Csv => true,
+    };
+
+    if is_csv {
+        parse_csv(&lines, default_group)
+    } else {
+        parse_lines(&lines, default_group)
+    }
+}
+
+fn parse_lines(lines: &[(usize, &str)], default_group: &str) -> Result<Vec<ImportCredentialItem>, String> {
+    lines
+        .iter()
+        .map(|&(line_no, line)| {
+            if line.chars().any(char::is_whitespace) {
+                return Err(format!("第 {} 行包含空白字符，每行只能有一个 refreshToken", line_no));
+            }
+            Ok(item(line.to_string(), default_group))
+        })
+        .collect()
+}
+
+fn parse_csv(lines: &[(usize, &str)], default_group: &str) -> Result<Vec<ImportCredentialItem>, String> {
+    let mut rows = lines
+        .iter()
+        .map(|&(line_no, line)| split_csv_line(line).map(|cells| (line_no, cells)).map_err(|e| format!("第 {} 行 {}", line_no, e)));
+
+    let Some(first) = rows.next() else {
+        return Ok(Vec::new());
+    };
+    let (first_line_no, first_cells) = first?;
+
+    let is_header = first_cells
+        .iter()
+        .any(|cell| TOKEN_COLUMNS.contains(&cell.trim().to_ascii_lowercase().as_str()));
+    let (columns, pending) = if is_header {
+        let columns: Vec<Column> = first_cells.iter().map(|c| Column::from_header(c)).collect();
+        if !columns.contains(&Column::Token) {
+            return Err("CSV 表头缺少 token 列".to_string());
+        }
+        (columns, None)
+    } else {
+        (Column::POSITIONAL.to_vec(), Some((first_line_no, first_cells)))
+    };
+
+    let mut items = Vec::new();
+    for row in pending.into_iter().map(Ok).chain(rows) {
+        let (line_no, cells) = row?;
+        if cells.len() > columns.len() {
+            return Err(format!(
+                "第 {} 行有 {} 列，最多 {} 列",
+                line_no,
+                cells.len(),
+                columns.len()
+            ));
+        }
+
+        let mut parsed = item(String::new(), default_group);
+        for (column, cell) in columns.iter().zip(cells) {
+            let cell = cell.trim().to_string();
+            if cell.is_empty() {
+                continue;
+            }
+            match column {
+                Column::Token => parsed.refresh_token = cell,
+                Column::AuthMethod => parsed.auth_method = cell,
+                Column::ClientId => parsed.client_id = Some(cell),
+                Column::ClientSecret => parsed.client_secret = Some(cell),
+                Column::Group => parsed.group_id = cell,
+                Column::Ignored => {}
+            }
+        }
+        if parsed.refresh_token.is_empty() {
+            return Err(format!("第 {} 行缺少 refreshToken", line_no));
+        }
+        items.push(parsed);
+    }
+    Ok(items)
+}
+
+/// 按逗号拆分一行 CSV，支持双引号包裹的字段（`""` 表示引号本身）
+fn split_csv_line(line: &str) -> Result<Vec<String>, String> {
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => cells.push(std::mem::take(&mut cell)),
+            _ => cell.push(c),
+        }
+    }
+    if in_quotes {
+        return Err("引号未闭合".to_string());
+    }
+    cells.push(cell);
+    Ok(cells)
+}
+
+fn item(refresh_token: String, group_id: &str) -> ImportCredentialItem {
+    ImportCredentialItem {
+        refresh_token,
+        auth_method: default_auth_method(),
+        client_id: None,
+        client_secret: None,
+        group_id: group_id.to_string(),
+    }
+}
@@ -0,0 +1,1236 @@
+//! Admin API 类型定义
+
+use serde::{Deserialize, Deserializer, Serialize};
+use crate::credential_stats::CredentialRequestStats;
+use crate::error_code::ErrorCode;
+use crate::group_budgets::GroupBudgetStatus;
+use crate::kiro::token_manager::HealthCheckResult;
+use crate::model::config::{
+    CorsConfig, GroupBudget, GroupListener, GroupRule, LanAccessConfig, MachineIdBackup, MaintenanceWindow, ModelEntry, ModelMapping, RequestTransform, ResponseCacheConfig, RotationRule, RoutingStrategy,
+    TlsConfig, WebhookConfig,
+    WebhookFormat,
+};
+use crate::proxy_lifecycle::{ProxySnapshot, ProxyState};
+use crate::usage_history::UsageSnapshot;
+
+// ============ 凭证状态 ============
+
+/// 所有凭证状态响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialsStatusResponse {
+    /// 凭证总数
+    pub total: usize,
+    /// 可用凭证数量（未禁用）
+    pub available: usize,
+    /// 当前活跃凭证 ID（反代使用）
+    pub current_id: u64,
+    /// 本地 Kiro 客户端的 Refresh Token（用于匹配高亮，脱敏规则与凭证列表相同）
+    pub local_refresh_token: Option<String>,
+    /// 各凭证状态列表
+    pub credentials: Vec<CredentialStatusItem>,
+}
+
+/// 凭证列表查询参数
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct RevealQuery {
+    /// 是否返回明文 Token（需要额外认证）
+    pub reveal: bool,
+}
+
+/// 单个凭证的状态信息
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialStatusItem {
+    /// 凭证唯一 ID
+    pub id: u64,
+    /// 是否被禁用
+    pub disabled: bool,
+    /// 计数窗口内的 API 调用失败次数（各错误类型合计）
+    pub failure_count: u32,
+    /// 限流冷却剩余秒数（未冷却时为 null）
+    pub cooldown_secs: Option<u64>,
+    /// 是否为当前活跃凭证
+    pub is_current: bool,
+    /// Token 过期时间（RFC3339 格式）
+    pub expires_at: Option<String>,
+    /// 认证方式
+    pub auth_method: Option<String>,
+    /// 是否有 Profile ARN
+    pub has_profile_arn: bool,
+    /// 用户邮箱
+    pub email: Option<String>,
+    /// 订阅类型
+    pub subscription_title: Option<String>,
+    /// 当前使用量
+    pub current_usage: Option<f64>,
+    /// 使用限额
+    pub usage_limit: Option<f64>,
+    /// 剩余额度
+    pub remaining: Option<f64>,
+    /// 下次重置时间
+    pub next_reset_at: Option<f64>,
+    /// Refresh Token
+    pub refresh_token: Option<String>,
+    /// Access Token
+    pub access_token: Option<String>,
+    /// Profile ARN
+    pub profile_arn: Option<String>,
+    /// 凭证状态：normal(正常), invalid(无效/封禁), expired(过期)
+    pub status: String,
+    /// 分组 ID
+    pub group_id: String,
+    /// 凭证单独使用的代理地址（未设置时使用全局代理）
+    pub proxy_url: Option<String>,
+    /// 凭证单独使用的 Kiro 版本（未设置时使用全局配置）
+    pub kiro_version: Option<String>,
+    /// 凭证单独使用的系统版本（未设置时使用全局配置）
+    pub system_version: Option<String>,
+    /// 凭证单独使用的 Node.js 版本（未设置时使用全局配置）
+    pub node_version: Option<String>,
+    /// machineId 盐值
+    pub machine_id_salt: Option<String>,
+    /// 最近一次健康检查结果
+    pub last_health_check: Option<HealthCheckResult>,
+    /// 额度用尽被禁用时，预计自动启用的时间（RFC3339）
+    pub quota_reset_at: Option<String>,
+    /// 最近 1 小时 / 24 小时的请求成功率和平均延迟
+    pub request_stats: CredentialRequestStats,
+}
+
+/// 重新生成 machineId 响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegenerateMachineIdResponse {
+    pub success: bool,
+    pub message: String,
+    pub machine_id: String,
+}
+
+// ============ 刷新凭证响应 ============
+
+/// 刷新单个凭证响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshCredentialResponse {
+    pub id: u64,
+    pub success: bool,
+    pub email: Option<String>,
+    pub subscription_title: Option<String>,
+    pub remaining: f64,
+    pub message: String,
+}
+
+/// 批量刷新结果项
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshResultItem {
+    pub id: u64,
+    pub success: bool,
+    pub email: Option<String>,
+    pub remaining: Option<f64>,
+    pub error: Option<String>,
+}
+
+/// 批量刷新凭证响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshAllResponse {
+    pub success_count: u32,
+    pub fail_count: u32,
+    pub total: u32,
+    pub results: Vec<RefreshResultItem>,
+}
+
+/// 批量刷新凭证请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshBatchRequest {
+    /// 要刷新的凭证 ID 列表，为空则刷新所有活跃凭证
+    pub ids: Option<Vec<u64>>,
+}
+
+// ============ 操作请求 ============
+
+/// 启用/禁用凭证请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetDisabledRequest {
+    /// 是否禁用
+    pub disabled: bool,
+}
+
+/// 添加凭证请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddCredentialRequest {
+    /// 刷新令牌（必填）
+    pub refresh_token: String,
+
+    /// 认证方式（可选，默认 social）
+    #[serde(default = "default_auth_method")]
+    pub auth_method: String,
+
+    /// OIDC Client ID（IdC 认证需要）
+    pub client_id: Option<String>,
+
+    /// OIDC Client Secret（IdC 认证需要）
+    pub client_secret: Option<String>,
+}
+
+pub(super) fn default_auth_method() -> String {
+    "social".to_string()
+}
+
+/// 编辑凭证请求（未提供的字段保持不变）
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateCredentialRequest {
+    /// 认证方式（social / idc / builder-id）
+    pub auth_method: Option<String>,
+
+    /// OIDC Client ID（空字符串表示清除）
+    pub client_id: Option<String>,
+
+    /// OIDC Client Secret（空字符串表示清除）
+    pub client_secret: Option<String>,
+
+    /// 凭证单独使用的代理地址（空字符串表示清除，改用全局代理）
+    pub proxy_url: Option<String>,
+
+    /// 代理认证用户名（空字符串表示清除）
+    pub proxy_username: Option<String>,
+
+    /// 代理认证密码（空字符串表示清除）
+    pub proxy_password: Option<String>,
+
+    /// 凭证单独使用的 Kiro 版本（空字符串表示清除，改用全局配置）
+    pub kiro_version: Option<String>,
+
+    /// 凭证单独使用的系统版本，如 darwin#24.6.0（空字符串表示清除）
+    pub system_version: Option<String>,
+
+    /// 凭证单独使用的 Node.js 版本（空字符串表示清除）
+    pub node_version: Option<String>,
+
+    /// machineId 盐值（空字符串表示清除）
+    pub machine_id_salt: Option<String>,
+
+    /// 分组 ID
+    pub group_id: Option<String>,
+
+    /// 替换的刷新令牌（替换后重新验证）
+    pub refresh_token: Option<String>,
+}
+
+/// 添加凭证成功响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddCredentialResponse {
+    pub success: bool,
+    pub message: String,
+    /// 新添加的凭证 ID
+    pub credential_id: u64,
+}
+
+/// 批量导入凭证请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportCredentialsRequest {
+    /// 要导入的凭证列表
+    pub credentials: Vec<ImportCredentialItem>,
+}
+
+/// 单个导入凭证项
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportCredentialItem {
+    /// 刷新令牌（必填）
+    pub refresh_token: String,
+    /// 认证方式（可选，默认 social）
+    #[serde(default = "default_auth_method")]
+    pub auth_method: String,
+    /// OIDC Client ID（IdC 认证需要）
+    pub client_id: Option<String>,
+    /// OIDC Client Secret（IdC 认证需要）
+    pub client_secret: Option<String>,
+    /// 分组 ID（可选，默认 "default"）
+    #[serde(default = "default_group_id")]
+    pub group_id: String,
+}
+
+fn default_group_id() -> String {
+    "default".to_string()
+}
+
+/// 粘贴文本的格式
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportTextFormat {
+    /// 含逗号时按 CSV 解析，否则按每行一个 Token 解析
+    #[default]
+    Auto,
+    /// 每行一个 refreshToken
+    Lines,
+    /// CSV（token, authMethod, clientId, clientSecret, group）
+    Csv,
+}
+
+/// 从粘贴文本导入凭证请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportTextRequest {
+    /// 粘贴的文本
+    pub text: String,
+    /// 文本格式（默认自动识别）
+    #[serde(default)]
+    pub format: ImportTextFormat,
+    /// 未在 CSV 中指定分组的凭证所属分组（默认 "default"，即按分组规则自动分组）
+    #[serde(default = "default_group_id")]
+    pub group_id: String,
+}
+
+/// 批量导入凭证响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportCredentialsResponse {
+    pub success: bool,
+    pub message: String,
+    /// 成功导入的数量
+    pub imported_count: usize,
+    /// 跳过的数量（无效凭证）
+    pub skipped_count: usize,
+    /// 新添加的凭证 ID 列表
+    pub credential_ids: Vec<u64>,
+    /// 跳过的原因列表（每个失败的凭证对应一个原因）
+    pub skipped_reasons: Vec<String>,
+    /// 逐项导入结果（含自动分组情况），与请求中的凭证一一对应
+    pub items: Vec<ImportItemReport>,
+}
+
+/// 批量验证凭证请求（只验证，不添加）
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidateCredentialsRequest {
+    /// 要验证的凭证列表
+    pub credentials: Vec<AddCredentialRequest>,
+}
+
+/// 单个凭证的验证结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidateResultItem {
+    /// 在请求列表中的序号
+    pub index: usize,
+    /// Token 能否刷新
+    pub valid: bool,
+    pub auth_method: String,
+    /// 刷新后 Access Token 的过期时间（RFC3339 格式）
+    pub expires_at: Option<String>,
+    pub email: Option<String>,
+    pub subscription_title: Option<String>,
+    pub usage_limit: Option<f64>,
+    pub remaining: Option<f64>,
+    /// 已添加的重复凭证 ID
+    pub existing_id: Option<u64>,
+    /// 刷新时上游轮换了 Refresh Token，导入时应使用这个新值
+    pub rotated_refresh_token: Option<String>,
+    /// 验证失败或额度查询失败的原因
+    pub error: Option<String>,
+}
+
+/// 批量验证凭证响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidateCredentialsResponse {
+    pub valid_count: usize,
+    pub invalid_count: usize,
+    pub total: usize,
+    pub results: Vec<ValidateResultItem>,
+}
+
+/// SSO 缓存中发现的凭证（refreshToken 已脱敏）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoveredCredentialItem {
+    /// 缓存文件名，导入时用于指定凭证
+    pub file: String,
+    pub auth_method: String,
+    pub provider: Option<String>,
+    pub start_url: Option<String>,
+    pub region: Option<String>,
+    pub expires_at: Option<String>,
+    /// refreshToken 预览（脱敏）
+    pub refresh_token_preview: String,
+    /// 是否找到 OIDC 客户端注册（IdC 凭证刷新需要）
+    pub has_client_registration: bool,
+    /// 是否已导入（refreshToken 与现有凭证相同）
+    pub already_imported: bool,
+}
+
+/// 扫描 SSO 缓存目录响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoverCredentialsResponse {
+    /// 扫描的目录
+    pub directory: String,
+    pub credentials: Vec<DiscoveredCredentialItem>,
+}
+
+/// 导入发现的凭证请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportDiscoveredRequest {
+    /// 要导入的缓存文件名
+    pub files: Vec<String>,
+    /// 分组 ID（可选，默认 "default"）
+    #[serde(default = "default_group_id")]
+    pub group_id: String,
+}
+
+/// 单个凭证的导入结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportItemReport {
+    /// 在请求列表中的序号（从 0 开始）
+    pub index: usize,
+    pub success: bool,
+    pub credential_id: Option<u64>,
+    /// 最终所属分组
+    pub group_id: Option<String>,
+    /// 命中的自动分组规则（未命中或请求显式指定分组时为空）
+    pub matched_rule: Option<String>,
+    /// 失败原因
+    pub error: Option<String>,
+}
+
+// ============ 余额查询 ============
+
+/// 余额查询响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BalanceResponse {
+    /// 凭证 ID
+    pub id: u64,
+    /// 用户邮箱
+    pub email: Option<String>,
+    /// 订阅类型
+    pub subscription_title: Option<String>,
+    /// 当前使用量
+    pub current_usage: f64,
+    /// 使用限额
+    pub usage_limit: f64,
+    /// 剩余额度
+    pub remaining: f64,
+    /// 使用百分比
+    pub usage_percentage: f64,
+    /// 下次重置时间（Unix 时间戳）
+    pub next_reset_at: Option<f64>,
+    
+    // === 凭证详情 ===
+    /// 认证方式
+    pub auth_method: Option<String>,
+    /// Access Token
+    pub access_token: Option<String>,
+    /// Refresh Token
+    pub refresh_token: Option<String>,
+    /// Profile ARN
+    pub profile_arn: Option<String>,
+    /// Token 过期时间
+    pub expires_at: Option<String>,
+}
+
+// ============ 通用响应 ============
+
+/// 操作成功响应
+#[derive(Debug, Serialize)]
+pub struct SuccessResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+impl SuccessResponse {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            success: true,
+            message: message.into(),
+        }
+    }
+}
+
+/// 额度历史查询参数
+#[derive(Debug, Deserialize)]
+pub struct UsageHistoryQuery {
+    /// 只返回最近 N 小时的快照（缺省返回全部）
+    pub hours: Option<u32>,
+}
+
+/// 审计日志查询参数
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditQuery {
+    /// 最多返回的记录数（默认 100）
+    pub limit: Option<usize>,
+    /// 只返回该时间之后的记录
+    pub since: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// 对话记录检索参数
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptQuery {
+    /// 租户 API Key ID（`main` 表示主密钥）
+    pub api_key_id: Option<String>,
+    /// 模型名（包含匹配）
+    pub model: Option<String>,
+    /// 关键词（在请求和响应全文中查找）
+    pub q: Option<String>,
+    pub since: Option<chrono::DateTime<chrono::Utc>>,
+    pub until: Option<chrono::DateTime<chrono::Utc>>,
+    /// 最多返回的记录数（默认 50）
+    pub limit: Option<usize>,
+}
+
+/// 对话记录检索响应（按时间倒序）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptsResponse {
+    pub entries: Vec<crate::transcripts::TranscriptSummary>,
+}
+
+/// 审计日志响应（按时间倒序）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditResponse {
+    pub entries: Vec<crate::audit::AuditEntry>,
+}
+
+/// 额度历史响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageHistoryResponse {
+    /// 凭证 ID
+    pub id: u64,
+    /// 按时间升序的额度快照
+    pub points: Vec<UsageSnapshot>,
+}
+
+/// 错误响应
+#[derive(Debug, Serialize)]
+pub struct AdminErrorResponse {
+    pub error: AdminError,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AdminError {
+    #[serde(rename = "type")]
+    pub error_type: String,
+    pub message: String,
+    /// 稳定的机器可读错误码
+    pub code: ErrorCode,
+}
+
+impl AdminErrorResponse {
+    pub fn new(error_type: impl Into<String>, message: impl Into<String>) -> Self {
+        let error_type = error_type.into();
+        Self {
+            error: AdminError {
+                code: ErrorCode::from_error_type(&error_type),
+                error_type,
+                message: message.into(),
+            },
+        }
+    }
+
+    /// 指定错误码
+    pub fn with_code(mut self, code: ErrorCode) -> Self {
+        self.error.code = code;
+        self
+    }
+
+    pub fn invalid_request(message: impl Into<String>) -> Self {
+        Self::new("invalid_request", message)
+    }
+
+    pub fn authentication_error() -> Self {
+        Self::new("authentication_error", "Invalid or missing admin API key")
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new("not_found", message)
+    }
+
+    pub fn api_error(message: impl Into<String>) -> Self {
+        Self::new("api_error", message)
+    }
+
+    pub fn internal_error(message: impl Into<String>) -> Self {
+        Self::new("internal_error", message)
+    }
+}
+
+// ============ 日志 API ============
+
+/// 日志查询参数
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogsQuery {
+    /// 按凭证分组过滤
+    pub group_id: Option<String>,
+}
+
+/// 设置日志级别请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetLogLevelRequest {
+    /// 全局级别（trace/debug/info/warn/error/off），默认 info
+    #[serde(default = "default_log_level")]
+    pub level: String,
+    /// 按模块的级别，如 `{"kiro::provider": "debug"}`（路径相对本 crate）
+    #[serde(default)]
+    pub modules: std::collections::BTreeMap<String, String>,
+}
+
+fn default_log_level() -> String {
+    "info".to_string()
+}
+
+/// 日志级别响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogLevelResponse {
+    /// 当前生效的过滤器（EnvFilter 语法）
+    pub filter: Option<String>,
+}
+
+// ============ 配置 API ============
+
+/// 获取配置响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetConfigResponse {
+    /// 监听地址
+    pub host: String,
+    /// 监听地址列表（为空时只监听 host）
+    pub listen_hosts: Vec<String>,
+    /// Admin API 监听端口
+    pub port: u16,
+    /// 反代服务端口
+    pub proxy_port: u16,
+    /// API 密钥
+    pub api_key: Option<String>,
+    /// AWS 区域
+    pub region: String,
+    /// 是否启用自动刷新
+    pub auto_refresh_enabled: bool,
+    /// 自动刷新间隔（分钟）
+    pub auto_refresh_interval_minutes: u32,
+    /// 模型锁定
+    pub locked_model: Option<String>,
+    /// 机器码备份
+    pub machine_id_backup: Option<MachineIdBackup>,
+    /// 维护时间窗口
+    pub maintenance_windows: Vec<MaintenanceWindow>,
+    /// 凭证路由策略
+    pub routing_strategy: RoutingStrategy,
+    /// 停止反代时等待进行中流式响应结束的宽限期（秒）
+    pub proxy_drain_timeout_secs: u64,
+    /// 单个凭证的并发上限（0 表示不限制）
+    pub max_concurrent_per_credential: usize,
+    /// 全局排队请求数上限
+    pub request_queue_size: usize,
+    /// 排队最长等待时间（秒，0 表示不限制）
+    pub request_queue_timeout_secs: u64,
+    /// HTTPS 监听配置
+    pub tls: TlsConfig,
+    /// 跨域（CORS）配置
+    pub cors: CorsConfig,
+    /// 开机自启（仅桌面应用）
+    pub autostart: bool,
+}
+
+/// 生效配置响应（GET /config/effective）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EffectiveConfigResponse {
+    /// 服务实际使用的内存配置（包含运行时修改，密钥已脱敏）
+    pub config: serde_json::Value,
+    /// 与配置文件内容不一致的顶层字段（通常表示修改后尚未重启，或文件被外部编辑）
+    pub differs_from_file: Vec<String>,
+    /// 运行时状态
+    pub runtime: EffectiveRuntime,
+    /// 影响行为的环境变量
+    pub env: std::collections::BTreeMap<String, String>,
+}
+
+/// 运行时状态
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EffectiveRuntime {
+    /// Token 管理器实际使用的分组
+    pub active_group_id: Option<String>,
+    /// 是否为双端口模式（反代独立监听）
+    pub standalone_proxy: bool,
+    /// 反代服务状态
+    pub proxy_state: ProxyState,
+    /// 反代实际绑定的端口
+    pub proxy_bound_port: Option<u16>,
+    /// 当前日志过滤器
+    pub log_filter: Option<String>,
+    /// 当前是否处于维护时间窗口内
+    pub maintenance_allowed: bool,
+    /// 生效的模型锁定
+    pub locked_model: Option<String>,
+}
+
+/// 更新配置请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateConfigRequest {
+    /// 监听地址（可选）
+    pub host: Option<String>,
+    /// 监听地址列表（可选，空数组表示只监听 host）
+    pub listen_hosts: Option<Vec<String>>,
+    /// Admin API 端口（可选）
+    pub port: Option<u16>,
+    /// 反代服务端口（可选）
+    pub proxy_port: Option<u16>,
+    /// API 密钥（可选）
+    pub api_key: Option<String>,
+    /// AWS 区域（可选）
+    pub region: Option<String>,
+    /// 是否启用自动刷新（可选）
+    pub auto_refresh_enabled: Option<bool>,
+    /// 自动刷新间隔（可选）
+    pub auto_refresh_interval_minutes: Option<u32>,
+    /// 模型锁定（可选）
+    pub locked_model: Option<String>,
+    /// 维护时间窗口（可选，空数组表示不限制）
+    pub maintenance_windows: Option<Vec<MaintenanceWindow>>,
+    /// 凭证路由策略（可选）
+    pub routing_strategy: Option<RoutingStrategy>,
+    /// 停止反代时的排空宽限期（秒，可选）
+    pub proxy_drain_timeout_secs: Option<u64>,
+    /// 单个凭证的并发上限（可选，重启反代后生效）
+    pub max_concurrent_per_credential: Option<usize>,
+    /// 全局排队请求数上限（可选，重启反代后生效）
+    pub request_queue_size: Option<usize>,
+    /// 排队最长等待时间（秒，可选，重启反代后生效）
+    pub request_queue_timeout_secs: Option<u64>,
+    /// HTTPS 监听配置（可选，重启服务后生效）
+    pub tls: Option<TlsConfig>,
+    /// 跨域（CORS）配置（可选，重启服务后生效）
+    pub cors: Option<CorsConfig>,
+    /// 开机自启（可选，仅桌面应用，立即生效）
+    pub autostart: Option<bool>,
+    // machine_id_backup 应通过 backup API 设置
+}
+
+// ============ 批量操作 ============
+
+/// 批量删除请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchDeleteRequest {
+    /// 要删除的凭证 ID 列表
+    pub ids: Vec<u64>,
+}
+
+/// 导出凭证请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportCredentialsRequest {
+    /// 要导出的凭证 ID 列表（空则导出全部）
+    #[serde(default)]
+    pub ids: Vec<u64>,
+    /// 导出类型：full（完整数据）或 tokens_only（仅 token）
+    pub export_type: Option<String>,
+    /// 加密口令：设置后返回加密的凭证包（Base64），忽略 export_type
+    pub password: Option<String>,
+}
+
+/// 导入加密凭证包请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportEncryptedCredentialsRequest {
+    pub password: String,
+    /// 凭证包内容（Base64）
+    pub data: String,
+    /// 导入到的分组（默认 "default"，即按分组规则自动分组）
+    #[serde(default = "default_group_id")]
+    pub group_id: String,
+}
+
+/// 凭证去重查询参数
+#[derive(Debug, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DedupeQuery {
+    /// 仅返回合并计划，不修改凭证
+    pub dry_run: bool,
+    /// 先为缺少邮箱的凭证查询账号信息（默认 true）
+    pub refresh: bool,
+}
+
+impl Default for DedupeQuery {
+    fn default() -> Self {
+        Self {
+            dry_run: false,
+            refresh: true,
+        }
+    }
+}
+
+/// 凭证去重响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DedupeResponse {
+    pub success: bool,
+    pub message: String,
+    pub dry_run: bool,
+    /// 删除（或将删除）的凭证数
+    pub removed: usize,
+    pub groups: Vec<crate::kiro::dedupe::DedupeGroup>,
+}
+
+// ============ 模型锁定 ============
+
+/// 设置锁定模型请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetLockedModelRequest {
+    /// 要锁定的模型名称（null 或空表示取消锁定）
+    pub model: Option<String>,
+}
+
+/// Kiro Profile 列表响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KiroProfilesResponse {
+    pub profiles: Vec<crate::model_lock::KiroProfile>,
+    /// 选择锁定的 Profile（为空时自动选择）
+    pub selected: Vec<String>,
+    /// 实际锁定的 settings.json
+    pub settings_paths: Vec<String>,
+}
+
+/// 选择 Kiro Profile 请求
+#[derive(Debug, Deserialize)]
+pub struct SetKiroProfilesRequest {
+    /// Profile ID 列表（空列表表示自动选择）
+    pub profiles: Vec<String>,
+}
+
+// ============ 分组管理 ============
+
+/// 分组信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupInfo {
+    pub id: String,
+    pub name: String,
+    /// 该分组下的凭证数量
+    pub credential_count: u32,
+    /// 月度预算（null 表示不限制）
+    pub budget: Option<GroupBudget>,
+    /// 本月用量及是否超出预算
+    pub budget_status: GroupBudgetStatus,
+}
+
+/// 分组列表响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupsResponse {
+    pub groups: Vec<GroupInfo>,
+    /// 当前反代使用的分组 ID（null 表示使用所有分组）
+    pub active_group_id: Option<String>,
+    /// 备用分组 ID（活跃分组耗尽时自动转移）
+    pub fallback_group_id: Option<String>,
+    /// 已转移到的备用分组（活跃分组正常时为 null）
+    pub failover_group_id: Option<String>,
+}
+
+/// 添加分组请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddGroupRequest {
+    pub name: String,
+}
+
+/// 删除分组请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteGroupRequest {
+    pub id: String,
+}
+
+/// 设置活跃分组请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetActiveGroupRequest {
+    /// 要设置为活跃的分组 ID（null 表示使用所有分组）
+    pub group_id: Option<String>,
+}
+
+/// 设置备用分组请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetFallbackGroupRequest {
+    /// 备用分组 ID（null 表示不转移）
+    pub group_id: Option<String>,
+}
+
+/// 修改凭证分组请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetCredentialGroupRequest {
+    pub group_id: String,
+}
+
+/// 重命名分组请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenameGroupRequest {
+    pub name: String,
+}
+
+/// 设置分组预算请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetGroupBudgetRequest {
+    /// 月度预算（null 表示取消限制）
+    pub budget: Option<GroupBudget>,
+}
+
+/// 代理服务状态响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyStatusResponse {
+    /// 是否正在接收请求（等价于 state == running）
+    pub running: bool,
+    /// 生命周期状态
+    pub state: ProxyState,
+    /// 进入当前状态的时间（RFC 3339）
+    pub since: String,
+    /// 最近一次崩溃的错误信息
+    pub last_error: Option<String>,
+    /// 监听地址
+    pub host: String,
+    /// 配置的监听端口
+    pub port: u16,
+    /// 实际绑定的端口（未运行时为 null）
+    pub bound_port: Option<u16>,
+    /// 实际绑定的全部监听地址
+    pub bound_addresses: Vec<String>,
+    /// 使用的分组 ID（null 表示全部）
+    pub active_group_id: Option<String>,
+    /// 进行中的流式响应数量（Draining 时即排空进度）
+    pub active_streams: u64,
+    /// Draining 时强制中断剩余流的截止时间（RFC 3339）
+    pub drain_deadline: Option<String>,
+}
+
+/// 反代服务操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyAction {
+    Start,
+    Stop,
+    Restart,
+}
+
+/// 区分字段缺失（None）与显式 null（Some(None)）
+fn deserialize_present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// 反代服务操作请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyActionRequest {
+    pub action: ProxyAction,
+    /// 启动前切换的分组：缺省保持不变，null 表示全部
+    #[serde(default, deserialize_with = "deserialize_present")]
+    pub group_id: Option<Option<String>>,
+}
+
+/// 反代服务操作响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyActionResponse {
+    pub message: String,
+    /// 操作完成后的状态
+    #[serde(flatten)]
+    pub status: ProxyStatusResponse,
+}
+
+// ============ 租户 API Key ============
+
+/// 租户 API Key 信息
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyItem {
+    pub id: String,
+    pub name: String,
+    /// 脱敏后的 Key（仅显示首尾）
+    pub key_preview: String,
+    pub enabled: bool,
+    pub allowed_models: Vec<String>,
+    pub rate_limit_rpm: Option<u32>,
+    pub monthly_token_quota: Option<u64>,
+    pub request_transform: Option<RequestTransform>,
+    /// 本月用量
+    pub usage: Option<crate::api_keys::ApiKeyUsageSnapshot>,
+}
+
+/// 租户 API Key 列表响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeysResponse {
+    pub api_keys: Vec<ApiKeyItem>,
+}
+
+/// 添加租户 API Key 请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddApiKeyRequest {
+    pub name: String,
+    /// 自定义 Key（可选，为空时自动生成）
+    pub key: Option<String>,
+    #[serde(default)]
+    pub allowed_models: Vec<String>,
+    pub rate_limit_rpm: Option<u32>,
+    pub monthly_token_quota: Option<u64>,
+    /// 该 Key 的请求改写
+    pub request_transform: Option<RequestTransform>,
+}
+
+/// 添加租户 API Key 响应（仅此处返回完整 Key）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddApiKeyResponse {
+    pub success: bool,
+    pub message: String,
+    pub id: String,
+    pub key: String,
+}
+
+/// 更新租户 API Key 请求（字段均可选；限额传 0 表示不限制，请求改写传空对象表示清除）
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateApiKeyRequest {
+    pub name: Option<String>,
+    pub enabled: Option<bool>,
+    pub allowed_models: Option<Vec<String>>,
+    pub rate_limit_rpm: Option<u32>,
+    pub monthly_token_quota: Option<u64>,
+    pub request_transform: Option<RequestTransform>,
+}
+
+// ============ 模型映射 ============
+
+/// 自动分组规则（GET 响应 / PUT 请求）
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupRulesBody {
+    pub rules: Vec<GroupRule>,
+}
+
+/// 凭证轮换计划（PUT 请求）
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RotationScheduleBody {
+    pub rules: Vec<RotationRule>,
+}
+
+/// 凭证轮换计划响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RotationScheduleResponse {
+    pub rules: Vec<RotationRule>,
+    /// 当前时间命中的规则下标（不在任何时段内为 null）
+    pub active_index: Option<usize>,
+}
+
+/// 导出备份请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportBackupRequest {
+    /// 加密口令（导入时需要相同口令）
+    pub password: String,
+}
+
+/// 导入备份请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportBackupRequest {
+    pub password: String,
+    /// 备份文件内容（Base64）
+    pub data: String,
+}
+
+/// 导入备份响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportBackupResponse {
+    pub success: bool,
+    pub message: String,
+    /// 恢复的凭证数量
+    pub credentials: usize,
+    /// 恢复的文件
+    pub files: Vec<String>,
+    /// 备份创建时间
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// 生命周期 Webhook 列表（GET 响应 / PUT 请求）
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhooksBody {
+    pub webhooks: Vec<WebhookConfig>,
+}
+
+/// 测试 Webhook 请求（未指定 url 时测试全部已配置的 Webhook）
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestWebhookRequest {
+    pub url: Option<String>,
+    #[serde(default)]
+    pub format: WebhookFormat,
+}
+
+/// 单个 Webhook 的测试结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookTestResult {
+    pub url: String,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 测试 Webhook 响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestWebhookResponse {
+    pub results: Vec<WebhookTestResult>,
+}
+
+/// 轮换 Admin API Key 响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RotateAdminKeyResponse {
+    pub success: bool,
+    pub message: String,
+    /// 新的 Admin API Key（旧密钥立即失效）
+    pub admin_api_key: String,
+}
+
+/// 日志文件列表响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogFilesResponse {
+    /// 日志目录（未启用日志文件时为空）
+    pub directory: Option<String>,
+    /// 有日志的日期（最新的在前）
+    pub days: Vec<crate::logs::file_sink::LogDayInfo>,
+}
+
+/// 局域网访问控制（GET 响应）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessControlResponse {
+    pub config: LanAccessConfig,
+    /// 最近被拒绝的连接（最新的在前）
+    pub rejections: Vec<crate::access_control::RejectedConnection>,
+}
+
+/// 分组反代实例配置（PUT 请求）
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetGroupProxiesRequest {
+    pub listeners: Vec<GroupListener>,
+}
+
+/// 启动失败的分组反代实例
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupProxyError {
+    pub group_id: String,
+    pub error: String,
+}
+
+/// 分组反代实例配置与运行状态
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupProxiesResponse {
+    pub listeners: Vec<GroupListener>,
+    /// 各实例的运行状态
+    pub instances: Vec<ProxySnapshot>,
+    /// 本次同步中启动失败的实例
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<GroupProxyError>,
+}
+
+/// 模型目录（GET 响应）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelsBody {
+    pub models: Vec<ModelEntry>,
+}
+
+/// 更新模型目录条目请求（maxTokens 为 0 表示按模型能力表）
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateModelRequest {
+    pub display_name: Option<String>,
+    pub max_tokens: Option<i32>,
+    pub created: Option<i64>,
+    pub enabled: Option<bool>,
+}
+
+/// 模型映射表（GET 响应 / PUT 请求）
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelMappingsBody {
+    pub mappings: Vec<ModelMapping>,
+}
+
+/// 响应缓存配置与命中统计
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResponseCacheResponse {
+    pub config: ResponseCacheConfig,
+    pub stats: crate::cache::response::ResponseCacheStats,
+}
+
+// ============ 系统机器码 ============
+
+/// 重置/恢复系统机器码的查询参数
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct MachineIdWriteQuery {
+    /// 仅预览将要发生的变更并签发确认令牌，不写入
+    pub dry_run: bool,
+    /// 预览时签发的确认令牌（实际写入时必填）
+    pub confirm_token: Option<String>,
+    /// 恢复时使用的备份序号（0 为最近一次，默认 0）
+    pub index: usize,
+}
+
+/// 重置/恢复系统机器码的预览结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MachineIdPreviewResponse {
+    pub dry_run: bool,
+    /// `reset` 或 `restore`
+    pub operation: &'static str,
+    pub current_machine_id: Option<String>,
+    pub new_machine_id: String,
+    pub confirm_token: String,
+    pub expires_in_secs: u64,
+}
@@ -0,0 +1,263 @@
+//! 内嵌 Admin UI（浏览器访问）
+//!
+//! Admin 端口的 `/ui/` 下提供管理页面，无需桌面窗口即可在浏览器中管理。静态资源优先使用
+//! 桌面外壳注册的资源（打包进可执行文件的前端），否则从磁盘上的 `dist` 目录读取。
+//!
+//! 桌面端通过一次性启动令牌打开浏览器：访问 `/ui/?token=...` 时核销令牌、签发会话令牌并注入页面，
+//! 前端以会话令牌作为 `x-api-key` 调用 Admin API，用户无需手动输入 Admin API Key。
+//! 启动令牌只能使用一次，短时间内有效；会话令牌只保存在内存中，服务重启后失效。
+//! 桌面内嵌窗口通过 Tauri IPC 直接领取会话令牌（本机其他进程无法调用）。
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::{
+    Router,
+    extract::{Path as UrlPath, Query},
+    http::{StatusCode, header},
+    response::{IntoResponse, Redirect, Response},
+    routing::get,
+};
+use lazy_static::lazy_static;
+use parking_lot::{Mutex, RwLock};
+
+/// 启动令牌有效期
+const LAUNCH_TOKEN_TTL: Duration = Duration::from_secs(60);
+
+/// 会话令牌有效期
+const SESSION_TTL: Duration = Duration::from_secs(12 * 60 * 60);
+
+/// 前端保存会话令牌的 sessionStorage 键
+const SESSION_STORAGE_KEY: &str = "kiroAdminSession";
+
+/// 静态资源
+pub struct Asset {
+    pub bytes: Vec<u8>,
+    pub mime_type: String,
+}
+
+/// 静态资源来源（参数为去掉前导 `/` 的相对路径）
+pub type AssetSource = Arc<dyn Fn(&str) -> Option<Asset> + Send + Sync>;
+
+lazy_static! {
+    static ref ASSET_SOURCE: RwLock<Option<AssetSource>> = RwLock::new(None);
+    static ref BASE_URL: RwLock<Option<String>> = RwLock::new(None);
+    static ref LAUNCH_TOKENS: Mutex<HashMap<String, Instant>> = Mutex::new(HashMap::new());
+    static ref SESSIONS: Mutex<HashMap<String, Instant>> = Mutex::new(HashMap::new());
+}
+
+/// 注册静态资源来源（桌面外壳注册打包进可执行文件的前端资源）
+pub fn set_asset_source(source: AssetSource) {
+    *ASSET_SOURCE.write() = Some(source);
+}
+
+/// 记录 Admin API 实际监听的地址（未指定地址时使用回环地址）
+pub(crate) fn set_base_url(scheme: &str, addr: SocketAddr) {
+    let ip = match addr.ip() {
+        IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+        IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+        ip => ip,
+    };
+    *BASE_URL.write() = Some(format!("{}://{}", scheme, SocketAddr::new(ip, addr.port())));
+}
+
+/// 签发启动令牌并返回带令牌的 Admin UI 地址（Admin API 尚未启动时返回 None）
+pub fn launch_url() -> Option<String> {
+    let base = BASE_URL.read().clone()?;
+    Some(format!("{}/ui/?token={}", base, issue(&LAUNCH_TOKENS, LAUNCH_TOKEN_TTL)))
+}
+
+/// 为桌面内嵌窗口签发会话令牌
+pub fn issue_session() -> String {
+    issue(&SESSIONS, SESSION_TTL)
+}
+
+fn issue(tokens: &Mutex<HashMap<String, Instant>>, ttl: Duration) -> String {
+    let token = uuid::Uuid::new_v4().simple().to_string();
+    let mut tokens = tokens.lock();
+    tokens.retain(|_, issued_at| issued_at.elapsed() <= ttl);
+    tokens.insert(token.clone(), Instant::now());
+    token
+}
+
+/// 核销启动令牌
+fn take_launch_token(token: &str) -> bool {
+    LAUNCH_TOKENS
+        .lock()
+        .remove(token)
+        .is_some_and(|issued_at| issued_at.elapsed() <= LAUNCH_TOKEN_TTL)
+}
+
+/// 会话令牌是否有效（Admin API 认证中间件使用）
+pub(crate) fn is_valid_session(token: &str) -> bool {
+    SESSIONS
+        .lock()
+        .get(token)
+        .is_some_and(|issued_at| issued_at.elapsed() <= SESSION_TTL)
+}
+
+/// Admin UI 路由（挂载在 Admin 端口根路由下）
+pub fn router<S: Clone + Send + Sync + 'static>() -> Router<S> {
+    Router::new()
+        .route("/ui", get(|| async { Redirect::permanent("/ui/") }))
+        .route("/ui/", get(index))
+        .route("/ui/{*path}", get(asset))
+}
+
+async fn index(Query(query): Query<HashMap<String, String>>) -> Response {
+    let session = match query.get("token") {
+        Some(token) if take_launch_token(token) => {
+            tracing::info!("[Admin UI] 启动令牌已核销，签发会话令牌");
+            Some(issue(&SESSIONS, SESSION_TTL))
+        }
+        Some(_) => {
+            tracing::warn!("[Admin UI] 启动令牌无效或已过期");
+            None
+        }
+        None => None,
+    };
+    serve_index(session.as_deref())
+}
+
+async fn asset(UrlPath(path): UrlPath<String>) -> Response {
+    if path.is_empty() || path == "index.html" {
+        return serve_index(None);
+    }
+    if let Some(asset) = load(&path) {
+        return ([(header::CONTENT_TYPE, asset.mime_type)], asset.bytes).into_response();
+    }
+    // 前端路由：无扩展名的路径回退到 index.html
+    if Path::new(&path).extension().is_none() {
+        return serve_index(None);
+    }
+    StatusCode::NOT_FOUND.into_response()
+}
+
+fn serve_index(session: Option<&str>) -> Response {
+    let Some(asset) = load("index.html") else {
+        return (
+            StatusCode::NOT_FOUND,
+            "Admin UI 资源不存在，请先构建前端（npm run build）",
+        )
+            .into_response();
+    };
+    let html = String::from_utf8_lossy(&asset.bytes);
+    let html = match session {
+        Some(token) => inject_session(&html, token),
+        None => html.into_owned(),
+    };
+    (
+        [
+            (header::CONTENT_TYPE, "text/html; charset=utf-8"),
+            (header::CACHE_CONTROL, "no-store"),
+        ],
+        html,
+    )
+        .into_response()
+}
+
+/// 在页面中注入会话令牌，并从地址栏移除一次性启动令牌
+fn inject_session(html: &str, token: &str) -> String {
+    let script = format!(
+        "<script>sessionStorage.setItem(\"{}\",\"{}\");history.replaceState(null,\"\",location.pathname)</script>",
+        SESSION_STORAGE_KEY, token
+    );
+    match html.find("<head>") {
+        Some(pos) => {
+            let pos = pos + "<head>".len();
+            format!("{}{}{}", &html[..pos], script, &html[pos..])
+        }
+        None => format!("{}{}", script, html),
+    }
+}
+
+/// 读取静态资源（拒绝包含 `..` 等的路径）
+fn load(path: &str) -> Option<Asset> {
+    let relative = Path::new(path);
+    if !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+        return None;
+    }
+    if let Some(source) = ASSET_SOURCE.read().clone() {
+        return source(path);
+    }
+    dist_dirs().into_iter().find_map(|dir| {
+        let bytes = std::fs::read(dir.join(relative)).ok()?;
+        Some(Asset {
+            bytes,
+            mime_type: mime_type(path).to_string(),
+        })
+    })
+}
+
+/// 磁盘上的前端构建目录候选（可执行文件所在目录及工作目录）
+fn dist_dirs() -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+    if let Some(dir) = std::env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf)) {
+        roots.push(dir);
+    }
+    if let Ok(dir) = std::env::current_dir() {
+        roots.push(dir);
+    }
+    roots
+        .iter()
+        .flat_map(|root| [root.join("dist"), root.join("../dist")])
+        .filter(|dir| dir.join("index.html").is_file())
+        .collect()
+}
+
+fn mime_type(path: &str) -> &'static str {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("html") => "text/html; charset=utf-8",
+        Some("js" | "mjs") => "text/javascript; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("json") => "application/json",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("ico") => "image/x-icon",
+        Some("webp") => "image/webp",
+        Some("woff") => "font/woff",
+        Some("woff2") => "font/woff2",
+        Some("ttf") => "font/ttf",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_launch_token_creates_session() {
+        set_base_url("http", "0.0.0.0:8990".parse().unwrap());
+        let url = launch_url().unwrap();
+        let token = url.strip_prefix("http://127.0.0.1:8990/ui/?token=").unwrap();
+
+        assert!(!is_valid_session(token));
+        assert!(take_launch_token(token));
+        assert!(!take_launch_token(token), "启动令牌只能使用一次");
+
+        let session = issue_session();
+        assert!(is_valid_session(&session));
+        assert!(!is_valid_session("unknown"));
+    }
+
+    #[test]
+    fn test_inject_session_and_paths() {
+        let html = inject_session("<html><head><title>x</title></head></html>", "abc");
+        assert!(html.starts_with("<html><head><script>sessionStorage.setItem(\"kiroAdminSession\",\"abc\")"));
+        assert!(html.ends_with("</script><title>x</title></head></html>"));
+
+        assert!(load("../Cargo.toml").is_none());
+        assert!(load("/etc/passwd").is_none());
+        assert_eq!(mime_type("assets/index-abc.JS"), "text/javascript; charset=utf-8");
+    }
+}
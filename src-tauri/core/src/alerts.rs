@@ -0,0 +1,308 @@
+//! 额度告警与每日用量报告
+//!
+//! 凭证剩余额度低于阈值、或某个分组的凭证全部耗尽时产生告警；每日用量报告按配置的时间汇总
+//! 过去 24 小时各凭证的消耗。告警统一经过 [`AlertManager::emit`]：写入日志、广播到事件总线
+//! （Admin UI 实时事件；GUI 模式下 Tauri 层据此弹出桌面通知），并可选 POST 到 Webhook
+//! （经 [`crate::webhooks`] 投递，失败时重试）。
+//!
+//! 同一凭证/分组的告警在恢复（额度重置、充值）前只发送一次。
+
+use std::collections::HashSet;
+
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+use lazy_static::lazy_static;
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+
+use crate::events::{AdminEvent, EVENT_BUS};
+use crate::kiro::token_manager::CredentialEntrySnapshot;
+use crate::model::config::{AlertConfig, WebhookFormat};
+use crate::usage_history::USAGE_HISTORY;
+use crate::webhooks;
+
+/// 告警类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AlertKind {
+    /// 凭证剩余额度低于阈值
+    LowQuota,
+    /// 分组内凭证全部耗尽
+    GroupExhausted,
+    /// 每日用量报告
+    DailyReport,
+}
+
+/// 告警
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Alert {
+    pub kind: AlertKind,
+    pub title: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub credential_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_id: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl Alert {
+    fn new(kind: AlertKind, title: String, message: String) -> Self {
+        Self {
+            kind,
+            title,
+            message,
+            credential_id: None,
+            group_id: None,
+            timestamp: Utc::now(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct AlertState {
+    /// 已发送低额度告警的凭证
+    low_quota: HashSet<u64>,
+    /// 已发送耗尽告警的分组
+    exhausted_groups: HashSet<String>,
+    /// 最近一次发送每日报告的日期（本地时间）
+    last_report: Option<NaiveDate>,
+}
+
+/// 告警管理器
+pub struct AlertManager {
+    config: RwLock<AlertConfig>,
+    state: Mutex<AlertState>,
+}
+
+impl AlertManager {
+    pub fn new() -> Self {
+        Self {
+            config: RwLock::new(AlertConfig::default()),
+            state: Mutex::new(AlertState::default()),
+        }
+    }
+
+    /// 替换告警配置（立即生效）
+    pub fn set_config(&self, config: AlertConfig) {
+        *self.config.write() = config;
+    }
+
+    /// 检查凭证剩余额度，首次低于阈值时告警，恢复到阈值以上后重新布防
+    pub fn check_quota(&self, id: u64, email: Option<&str>, remaining: f64, usage_limit: f64) {
+        let threshold = self.config.read().low_quota_percent;
+        if usage_limit <= 0.0 {
+            return;
+        }
+        let percent = remaining / usage_limit * 100.0;
+
+        let mut state = self.state.lock();
+        if percent >= threshold {
+            state.low_quota.remove(&id);
+            return;
+        }
+        if !state.low_quota.insert(id) {
+            return;
+        }
+        drop(state);
+
+        let name = email.map(|e| format!("#{} ({})", id, e)).unwrap_or_else(|| format!("#{}", id));
+        let mut alert = Alert::new(
+            AlertKind::LowQuota,
+            "凭证额度不足".to_string(),
+            format!(
+                "凭证 {} 剩余额度 {:.1}/{:.1}（{:.1}%），低于告警阈值 {}%",
+                name, remaining, usage_limit, percent, threshold
+            ),
+        );
+        alert.credential_id = Some(id);
+        self.emit(alert);
+    }
+
+    /// 检查分组是否耗尽，首次耗尽时告警，分组内重新有可用额度后重新布防
+    pub fn check_group(&self, group_id: &str, exhausted: bool) {
+        let mut state = self.state.lock();
+        if !exhausted {
+            state.exhausted_groups.remove(group_id);
+            return;
+        }
+        if !state.exhausted_groups.insert(group_id.to_string()) {
+            return;
+        }
+        drop(state);
+
+        let mut alert = Alert::new(
+            AlertKind::GroupExhausted,
+            "分组额度耗尽".to_string(),
+            format!("分组 {} 内的凭证额度已全部耗尽或不可用", group_id),
+        );
+        alert.group_id = Some(group_id.to_string());
+        self.emit(alert);
+    }
+
+    /// 处于每日报告发送时段且今天尚未发送时返回 true（并记为已发送）
+    ///
+    /// 只在配置的那个小时内发送，服务在该时段之后启动不会补发
+    pub fn daily_report_due(&self, now: DateTime<Local>) -> bool {
+        let (enabled, hour) = {
+            let config = self.config.read();
+            (config.enabled && config.daily_report_enabled, config.daily_report_hour)
+        };
+        if !enabled || chrono::Timelike::hour(&now) != hour {
+            return false;
+        }
+        let today = now.date_naive();
+        let mut state = self.state.lock();
+        if state.last_report == Some(today) {
+            return false;
+        }
+        state.last_report = Some(today);
+        true
+    }
+
+    /// 发送告警：写日志、广播事件，配置了 Webhook 时异步推送
+    pub fn emit(&self, alert: Alert) {
+        let config = self.config.read().clone();
+        if !config.enabled {
+            return;
+        }
+
+        match alert.kind {
+            AlertKind::DailyReport => tracing::info!("[告警] {}: {}", alert.title, alert.message),
+            _ => tracing::warn!("[告警] {}: {}", alert.title, alert.message),
+        }
+
+        if let Some(url) = config.webhook_url.filter(|u| !u.trim().is_empty()) {
+            webhooks::spawn_delivery(url, webhook_body(&alert, config.webhook_format));
+        }
+
+        EVENT_BUS.publish(AdminEvent::Alert { alert });
+    }
+}
+
+impl Default for AlertManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+lazy_static! {
+    /// 全局告警管理器
+    pub static ref ALERTS: AlertManager = AlertManager::new();
+}
+
+/// 校验告警配置
+pub fn validate(config: &AlertConfig) -> Result<(), String> {
+    if !(0.0..=100.0).contains(&config.low_quota_percent) {
+        return Err("额度告警阈值必须在 0-100 之间".to_string());
+    }
+    if config.daily_report_hour > 23 {
+        return Err("每日报告时间必须在 0-23 之间".to_string());
+    }
+    if let Some(url) = config.webhook_url.as_deref().map(str::trim).filter(|u| !u.is_empty()) {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(format!("Webhook 地址必须以 http:// 或 https:// 开头: {}", url));
+        }
+    }
+    Ok(())
+}
+
+/// 按格式构建 Webhook 请求体
+fn webhook_body(alert: &Alert, format: WebhookFormat) -> serde_json::Value {
+    webhooks::render(format, &alert.title, &alert.message, serde_json::json!(alert))
+}
+
+/// 生成每日用量报告：各凭证过去 24 小时的消耗（来自额度快照）与当前余额
+pub fn daily_report(entries: &[CredentialEntrySnapshot]) -> Alert {
+    let since = Utc::now() - Duration::hours(24);
+    let mut total = 0.0;
+    let mut lines = Vec::new();
+
+    for entry in entries {
+        let history = USAGE_HISTORY.history(entry.id, Some(since));
+        let consumed = match (history.first(), history.last()) {
+            // 期间发生额度重置时，当前用量即为重置后的消耗
+            (Some(first), Some(last)) if last.current_usage >= first.current_usage => {
+                last.current_usage - first.current_usage
+            }
+            (Some(_), Some(last)) => last.current_usage,
+            _ => 0.0,
+        };
+        total += consumed;
+
+        let name = entry.email.as_deref().unwrap_or("-");
+        let balance = match (entry.remaining, entry.usage_limit) {
+            (Some(remaining), Some(limit)) => format!("{:.1}/{:.1}", remaining, limit),
+            _ => "未知".to_string(),
+        };
+        lines.push(format!("#{} {}: 消耗 {:.1}，剩余 {}", entry.id, name, consumed, balance));
+    }
+
+    let mut message = format!("过去 24 小时共消耗 {:.1}（{} 个凭证）", total, entries.len());
+    for line in lines {
+        message.push('\n');
+        message.push_str(&line);
+    }
+    Alert::new(AlertKind::DailyReport, "每日用量报告".to_string(), message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_low_quota_alerts_once_until_recovered() {
+        let alerts = AlertManager::new();
+        let mut rx = EVENT_BUS.subscribe();
+        let mut low_quota_alerts = || {
+            let mut count = 0;
+            while let Ok(event) = rx.try_recv() {
+                if let AdminEvent::Alert { alert } = event {
+                    if alert.kind == AlertKind::LowQuota && alert.credential_id == Some(42) {
+                        count += 1;
+                    }
+                }
+            }
+            count
+        };
+
+        alerts.check_quota(42, None, 50.0, 100.0);
+        assert_eq!(low_quota_alerts(), 0);
+
+        alerts.check_quota(42, Some("a@b.com"), 5.0, 100.0);
+        alerts.check_quota(42, Some("a@b.com"), 3.0, 100.0);
+        assert_eq!(low_quota_alerts(), 1);
+
+        // 额度重置后重新布防
+        alerts.check_quota(42, None, 100.0, 100.0);
+        alerts.check_quota(42, None, 1.0, 100.0);
+        assert_eq!(low_quota_alerts(), 1);
+    }
+
+    #[test]
+    fn test_daily_report_due_once_per_day() {
+        let alerts = AlertManager::new();
+        alerts.set_config(AlertConfig {
+            daily_report_enabled: true,
+            daily_report_hour: 9,
+            ..AlertConfig::default()
+        });
+
+        let morning = Local::now()
+            .with_time(chrono::NaiveTime::from_hms_opt(8, 0, 0).unwrap())
+            .unwrap();
+        assert!(!alerts.daily_report_due(morning));
+        let report_time = morning + Duration::minutes(75);
+        assert!(alerts.daily_report_due(report_time));
+        assert!(!alerts.daily_report_due(report_time + Duration::minutes(30)));
+        assert!(!alerts.daily_report_due(report_time + Duration::hours(2)));
+    }
+
+    #[test]
+    fn test_webhook_body_formats() {
+        let alert = Alert::new(AlertKind::GroupExhausted, "t".to_string(), "m".to_string());
+        assert_eq!(webhook_body(&alert, WebhookFormat::Slack)["text"], "*t*\nm");
+        assert_eq!(webhook_body(&alert, WebhookFormat::Discord)["content"], "**t**\nm");
+        assert_eq!(webhook_body(&alert, WebhookFormat::Generic)["kind"], "groupExhausted");
+    }
+}
@@ -0,0 +1,823 @@
+//! Messages Batches API 模拟（`/v1/messages/batches`）
+//!
+//! 批次提交后立即返回，后台以有限并发逐条调用 `/v1/messages` 的非流式处理逻辑（凭证故障转移由
+//! Provider 负责，上游限流或暂时不可用时整条重试）。批次状态持久化在配置目录的 `batches/` 下：
+//!
+//! - `<id>.json` 批次元数据
+//! - `<id>.requests.jsonl` 提交的请求
+//! - `<id>.results.jsonl` 已完成条目的结果（逐条追加）
+//!
+//! 服务重启后未完成的批次在反代路由创建时继续处理。反代服务停止期间暂停处理，
+//! 取消后尚未开始的条目记为 canceled，提交 24 小时后仍未处理的条目记为 expired。
+
+use std::collections::{HashMap, HashSet};
+use std::fs::OpenOptions;
+use std::future::Future;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use axum::{
+    Extension, Json as JsonExtractor,
+    body::Body,
+    extract::{Path as UrlPath, Query, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Json, Response},
+};
+use chrono::{DateTime, Utc};
+use futures::{StreamExt, stream};
+use lazy_static::lazy_static;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+
+use crate::api_keys::{API_KEY_REGISTRY, ApiKeyRegistry, Rejection, Tenant};
+use crate::error_code::ErrorCode;
+
+use super::middleware::AppState;
+use super::types::{ErrorResponse, MessagesRequest};
+
+/// 同一批次同时处理的条目数
+const BATCH_CONCURRENCY: usize = 4;
+
+/// 单条请求的最大尝试次数（仅限流 / 上游暂时不可用时重试）
+const MAX_ENTRY_ATTEMPTS: u32 = 3;
+
+/// 首次重试的退避时间，之后每次翻倍
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(2);
+
+/// 批次有效期（超过后未处理的条目记为 expired）
+const BATCH_TTL_HOURS: i64 = 24;
+
+/// 单个批次的最大条目数
+const MAX_BATCH_REQUESTS: usize = 10_000;
+
+/// 批次处理状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessingStatus {
+    InProgress,
+    Canceling,
+    Ended,
+}
+
+/// 各状态的条目数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RequestCounts {
+    pub processing: usize,
+    pub succeeded: usize,
+    pub errored: usize,
+    pub canceled: usize,
+    pub expired: usize,
+}
+
+/// 批次对象（与 Anthropic `message_batch` 结构一致）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageBatch {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub object_type: String,
+    pub processing_status: ProcessingStatus,
+    pub request_counts: RequestCounts,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub archived_at: Option<DateTime<Utc>>,
+    pub cancel_initiated_at: Option<DateTime<Utc>>,
+    pub results_url: Option<String>,
+}
+
+/// 持久化的批次（含提交者信息）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredBatch {
+    batch: MessageBatch,
+    /// 提交批次的租户 Key ID（只有同一租户可以查看）
+    api_key_id: Option<String>,
+    /// 提交时的 `anthropic-beta` 请求头
+    anthropic_beta: Option<String>,
+}
+
+/// 批次中的单条请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchRequest {
+    pub custom_id: String,
+    /// Messages 请求体（处理时再解析，`stream` 强制为 false）
+    pub params: serde_json::Value,
+}
+
+/// 单条结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BatchResultLine {
+    custom_id: String,
+    result: serde_json::Value,
+}
+
+/// 创建批次请求
+#[derive(Debug, Deserialize)]
+pub struct CreateBatchRequest {
+    pub requests: Vec<BatchRequest>,
+}
+
+/// 批次列表查询参数
+#[derive(Debug, Deserialize)]
+pub struct ListBatchesQuery {
+    pub limit: Option<usize>,
+    pub before_id: Option<String>,
+    pub after_id: Option<String>,
+}
+
+/// 批次列表响应
+#[derive(Debug, Serialize)]
+pub struct ListBatchesResponse {
+    pub data: Vec<MessageBatch>,
+    pub has_more: bool,
+    pub first_id: Option<String>,
+    pub last_id: Option<String>,
+}
+
+/// 批次存储
+pub struct BatchStore {
+    dir: RwLock<Option<PathBuf>>,
+    batches: Mutex<HashMap<String, StoredBatch>>,
+    /// 正在处理的批次（避免重复启动处理任务）
+    running: Mutex<HashSet<String>>,
+}
+
+impl BatchStore {
+    pub fn new() -> Self {
+        Self {
+            dir: RwLock::new(None),
+            batches: Mutex::new(HashMap::new()),
+            running: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// 从目录加载已有批次
+    pub fn load(&self, dir: PathBuf) {
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            tracing::warn!("创建批次目录失败 {}: {}", dir.display(), e);
+            return;
+        }
+        let mut batches = HashMap::new();
+        if let Ok(read_dir) = std::fs::read_dir(&dir) {
+            for entry in read_dir.filter_map(|e| e.ok()) {
+                let path = entry.path();
+                let is_meta = path
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.ends_with(".json"));
+                if !is_meta {
+                    continue;
+                }
+                match std::fs::read_to_string(&path)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|c| serde_json::from_str::<StoredBatch>(&c).map_err(anyhow::Error::from))
+                {
+                    Ok(stored) => {
+                        batches.insert(stored.batch.id.clone(), stored);
+                    }
+                    Err(e) => tracing::warn!("读取批次文件失败 {}: {}", path.display(), e),
+                }
+            }
+        }
+        if !batches.is_empty() {
+            tracing::info!("已加载 {} 个批次", batches.len());
+        }
+        *self.batches.lock() = batches;
+        *self.dir.write() = Some(dir);
+    }
+
+    fn dir(&self) -> anyhow::Result<PathBuf> {
+        self.dir
+            .read()
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("批次存储未初始化"))
+    }
+
+    fn file(&self, id: &str, suffix: &str) -> anyhow::Result<PathBuf> {
+        Ok(self.dir()?.join(format!("{}{}", id, suffix)))
+    }
+
+    fn save_meta(&self, stored: &StoredBatch) -> anyhow::Result<()> {
+        let path = self.file(&stored.batch.id, ".json")?;
+        std::fs::write(path, serde_json::to_vec_pretty(stored)?)?;
+        Ok(())
+    }
+
+    /// 创建批次并写入请求
+    fn create(
+        &self,
+        requests: &[BatchRequest],
+        api_key_id: Option<String>,
+        anthropic_beta: Option<String>,
+    ) -> anyhow::Result<MessageBatch> {
+        let now = Utc::now();
+        let id = format!("msgbatch_{}", uuid::Uuid::new_v4().simple());
+        let stored = StoredBatch {
+            batch: MessageBatch {
+                id: id.clone(),
+                object_type: "message_batch".to_string(),
+                processing_status: ProcessingStatus::InProgress,
+                request_counts: RequestCounts {
+                    processing: requests.len(),
+                    ..RequestCounts::default()
+                },
+                ended_at: None,
+                created_at: now,
+                expires_at: now + chrono::Duration::hours(BATCH_TTL_HOURS),
+                archived_at: None,
+                cancel_initiated_at: None,
+                results_url: None,
+            },
+            api_key_id,
+            anthropic_beta,
+        };
+
+        let mut lines = Vec::new();
+        for request in requests {
+            serde_json::to_writer(&mut lines, request)?;
+            lines.push(b'\n');
+        }
+        std::fs::write(self.file(&id, ".requests.jsonl")?, lines)?;
+        self.save_meta(&stored)?;
+
+        let batch = stored.batch.clone();
+        self.batches.lock().insert(id, stored);
+        Ok(batch)
+    }
+
+    /// 获取批次（只能查看自己提交的批次）
+    pub fn get(&self, id: &str, api_key_id: Option<&str>) -> Option<MessageBatch> {
+        self.batches
+            .lock()
+            .get(id)
+            .filter(|s| s.api_key_id.as_deref() == api_key_id)
+            .map(|s| s.batch.clone())
+    }
+
+    /// 批次列表（最新的在前）
+    pub fn list(&self, api_key_id: Option<&str>) -> Vec<MessageBatch> {
+        let mut batches: Vec<MessageBatch> = self
+            .batches
+            .lock()
+            .values()
+            .filter(|s| s.api_key_id.as_deref() == api_key_id)
+            .map(|s| s.batch.clone())
+            .collect();
+        batches.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| b.id.cmp(&a.id)));
+        batches
+    }
+
+    /// 请求取消（进行中的条目继续完成，尚未开始的记为 canceled）
+    pub fn cancel(&self, id: &str, api_key_id: Option<&str>) -> Option<MessageBatch> {
+        let mut batches = self.batches.lock();
+        let stored = batches
+            .get_mut(id)
+            .filter(|s| s.api_key_id.as_deref() == api_key_id)?;
+        if stored.batch.processing_status == ProcessingStatus::InProgress {
+            stored.batch.processing_status = ProcessingStatus::Canceling;
+            stored.batch.cancel_initiated_at = Some(Utc::now());
+            if let Err(e) = self.save_meta(stored) {
+                tracing::warn!("保存批次 {} 失败: {}", id, e);
+            }
+        }
+        Some(stored.batch.clone())
+    }
+
+    /// 删除已结束的批次及其文件
+    pub fn delete(&self, id: &str, api_key_id: Option<&str>) -> Result<(), BatchError> {
+        let mut batches = self.batches.lock();
+        let stored = batches
+            .get(id)
+            .filter(|s| s.api_key_id.as_deref() == api_key_id)
+            .ok_or(BatchError::NotFound)?;
+        if stored.batch.processing_status != ProcessingStatus::Ended {
+            return Err(BatchError::NotEnded);
+        }
+        batches.remove(id);
+        for suffix in [".json", ".requests.jsonl", ".results.jsonl"] {
+            if let Ok(path) = self.file(id, suffix) {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+        Ok(())
+    }
+
+    /// 已结束批次的结果（JSON Lines）
+    pub fn results(&self, id: &str, api_key_id: Option<&str>) -> Result<Vec<u8>, BatchError> {
+        let batch = self.get(id, api_key_id).ok_or(BatchError::NotFound)?;
+        if batch.processing_status != ProcessingStatus::Ended {
+            return Err(BatchError::NotEnded);
+        }
+        let path = self.file(id, ".results.jsonl").map_err(|_| BatchError::NotFound)?;
+        match std::fs::read(path) {
+            Ok(content) => Ok(content),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(BatchError::Io(e.to_string())),
+        }
+    }
+
+    /// 尚未产生结果的请求
+    fn pending_requests(&self, id: &str) -> anyhow::Result<Vec<BatchRequest>> {
+        let done: HashSet<String> = match std::fs::read_to_string(self.file(id, ".results.jsonl")?) {
+            Ok(content) => content
+                .lines()
+                .filter_map(|l| serde_json::from_str::<BatchResultLine>(l).ok())
+                .map(|r| r.custom_id)
+                .collect(),
+            Err(_) => HashSet::new(),
+        };
+        let content = std::fs::read_to_string(self.file(id, ".requests.jsonl")?)?;
+        Ok(content
+            .lines()
+            .filter_map(|l| serde_json::from_str::<BatchRequest>(l).ok())
+            .filter(|r| !done.contains(&r.custom_id))
+            .collect())
+    }
+
+    /// 记录一条结果，全部完成时批次结束
+    fn record_result(&self, id: &str, custom_id: &str, result: serde_json::Value) {
+        let mut batches = self.batches.lock();
+        let Some(stored) = batches.get_mut(id) else {
+            return;
+        };
+
+        let line = BatchResultLine {
+            custom_id: custom_id.to_string(),
+            result,
+        };
+        let appended = self.file(id, ".results.jsonl").and_then(|path| {
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            let mut bytes = serde_json::to_vec(&line)?;
+            bytes.push(b'\n');
+            file.write_all(&bytes)?;
+            Ok(())
+        });
+        if let Err(e) = appended {
+            tracing::warn!("写入批次 {} 结果失败: {}", id, e);
+            return;
+        }
+
+        let counts = &mut stored.batch.request_counts;
+        counts.processing = counts.processing.saturating_sub(1);
+        match line.result.get("type").and_then(|t| t.as_str()) {
+            Some("succeeded") => counts.succeeded += 1,
+            Some("canceled") => counts.canceled += 1,
+            Some("expired") => counts.expired += 1,
+            _ => counts.errored += 1,
+        }
+        if counts.processing == 0 {
+            stored.batch.processing_status = ProcessingStatus::Ended;
+            stored.batch.ended_at = Some(Utc::now());
+            stored.batch.results_url = Some(format!("/v1/messages/batches/{}/results", id));
+            tracing::info!("批次 {} 处理完成", id);
+        }
+        if let Err(e) = self.save_meta(stored) {
+            tracing::warn!("保存批次 {} 失败: {}", id, e);
+        }
+    }
+
+    fn stored(&self, id: &str) -> Option<StoredBatch> {
+        self.batches.lock().get(id).cloned()
+    }
+}
+
+impl Default for BatchStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+lazy_static! {
+    /// 全局批次存储
+    pub static ref BATCHES: BatchStore = BatchStore::new();
+}
+
+/// 初始化批次存储，批次目录与配置文件位于同一目录
+pub fn init(config_path: &Path) {
+    if let Some(dir) = config_path.parent() {
+        BATCHES.load(dir.join("batches"));
+    }
+}
+
+/// 批次操作错误
+#[derive(Debug)]
+pub enum BatchError {
+    NotFound,
+    NotEnded,
+    Io(String),
+}
+
+impl IntoResponse for BatchError {
+    fn into_response(self) -> Response {
+        let (status, error) = match self {
+            BatchError::NotFound => (
+                StatusCode::NOT_FOUND,
+                ErrorResponse::new("not_found_error", "Batch not found"),
+            ),
+            BatchError::NotEnded => (
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::new("invalid_request_error", "Batch has not ended yet")
+                    .with_code(ErrorCode::StateConflict),
+            ),
+            BatchError::Io(message) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ErrorResponse::new("internal_error", message),
+            ),
+        };
+        (status, Json(error)).into_response()
+    }
+}
+
+/// 条目结果：成功时为消息对象，失败时为错误响应体
+fn succeeded(message: serde_json::Value) -> serde_json::Value {
+    serde_json::json!({ "type": "succeeded", "message": message })
+}
+
+fn errored(error: serde_json::Value) -> serde_json::Value {
+    serde_json::json!({ "type": "errored", "error": { "type": "error", "error": error } })
+}
+
+/// 租户准入检查未通过时的条目结果（与 `/v1/messages` 认证中间件的错误一致）
+fn rejected(tenant: &Tenant, rejection: Rejection) -> serde_json::Value {
+    match rejection {
+        Rejection::RateLimited { .. } => errored(serde_json::json!({
+            "type": "rate_limit_error",
+            "message": format!("API key '{}' exceeded its request rate limit", tenant.name),
+        })),
+        Rejection::QuotaExceeded => errored(serde_json::json!({
+            "type": "rate_limit_error",
+            "message": format!("API key '{}' exceeded its monthly token quota", tenant.name),
+            "code": ErrorCode::QuotaExceeded,
+        })),
+    }
+}
+
+/// 每条请求单独做租户准入检查（每分钟请求数、月度配额），未通过时不发往上游
+async fn admit_entry<F>(
+    registry: &ApiKeyRegistry,
+    tenant: Option<&Tenant>,
+    process: F,
+) -> serde_json::Value
+where
+    F: Future<Output = serde_json::Value>,
+{
+    if let Some(tenant) = tenant {
+        if let Err(rejection) = registry.admit(&tenant.id) {
+            tracing::warn!("批次条目未通过 API Key '{}' 的准入检查: {:?}", tenant.name, rejection);
+            return rejected(tenant, rejection);
+        }
+    }
+    process.await
+}
+
+/// 限流、过载、上游暂时不可用时重试整条请求
+fn is_retryable(status: StatusCode) -> bool {
+    matches!(status.as_u16(), 429 | 502 | 503 | 529)
+}
+
+/// 处理一条请求：复用 `/v1/messages` 的非流式处理逻辑
+async fn process_entry(
+    state: &AppState,
+    tenant: Option<&Tenant>,
+    headers: &HeaderMap,
+    params: &serde_json::Value,
+) -> serde_json::Value {
+    let mut attempt = 1;
+    loop {
+        let mut request: MessagesRequest = match serde_json::from_value(params.clone()) {
+            Ok(request) => request,
+            Err(e) => {
+                return errored(serde_json::json!({
+                    "type": "invalid_request_error",
+                    "message": format!("Invalid params: {}", e),
+                    "code": ErrorCode::InvalidRequest,
+                }));
+            }
+        };
+        request.stream = false;
+
+        let response = super::handlers::create_message(
+            state.clone(),
+            tenant.cloned().map(Extension),
+            headers.clone(),
+            request,
+        )
+        .await;
+
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .ok()
+            .and_then(|b| serde_json::from_slice::<serde_json::Value>(&b).ok())
+            .unwrap_or_default();
+
+        if status.is_success() {
+            return succeeded(body);
+        }
+        if is_retryable(status) && attempt < MAX_ENTRY_ATTEMPTS {
+            let delay = RETRY_BASE_DELAY * 2u32.pow(attempt - 1);
+            tracing::debug!("批次条目返回 {}，{} 秒后重试", status, delay.as_secs());
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+            continue;
+        }
+        return errored(body.get("error").cloned().unwrap_or(body));
+    }
+}
+
+/// 在后台处理批次中尚未完成的条目（已在处理中时忽略）
+pub fn spawn_processing(state: AppState, id: String) {
+    if !BATCHES.running.lock().insert(id.clone()) {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let Some(stored) = BATCHES.stored(&id) else {
+            BATCHES.running.lock().remove(&id);
+            return;
+        };
+        let pending = match BATCHES.pending_requests(&id) {
+            Ok(pending) => pending,
+            Err(e) => {
+                tracing::error!("读取批次 {} 的请求失败: {}", id, e);
+                BATCHES.running.lock().remove(&id);
+                return;
+            }
+        };
+
+        let tenant = stored.api_key_id.as_deref().and_then(|k| API_KEY_REGISTRY.tenant(k));
+        let mut headers = HeaderMap::new();
+        if let Some(beta) = stored.anthropic_beta.as_deref().and_then(|b| HeaderValue::from_str(b).ok()) {
+            headers.insert("anthropic-beta", beta);
+        }
+        tracing::info!("批次 {} 开始处理 {} 条请求", id, pending.len());
+
+        stream::iter(pending)
+            .for_each_concurrent(BATCH_CONCURRENCY, |request| {
+                let (state, tenant, headers, id) = (&state, &tenant, &headers, &id);
+                async move {
+                    // 反代服务停止期间暂停处理
+                    let mut proxy_rx = state.proxy.subscribe();
+                    let _ = proxy_rx.wait_for(|s| s.state.accepts_requests()).await;
+
+                    let result = match BATCHES.stored(id).map(|s| s.batch) {
+                        Some(batch) if batch.processing_status == ProcessingStatus::Canceling => {
+                            serde_json::json!({ "type": "canceled" })
+                        }
+                        Some(batch) if batch.expires_at <= Utc::now() => {
+                            serde_json::json!({ "type": "expired" })
+                        }
+                        Some(_) => {
+                            admit_entry(
+                                &API_KEY_REGISTRY,
+                                tenant.as_ref(),
+                                process_entry(state, tenant.as_ref(), headers, &request.params),
+                            )
+                            .await
+                        }
+                        None => return,
+                    };
+                    BATCHES.record_result(id, &request.custom_id, result);
+                }
+            })
+            .await;
+
+        BATCHES.running.lock().remove(&id);
+    });
+}
+
+/// 继续处理未完成的批次（反代路由创建时调用，服务重启后恢复）
+pub fn resume_pending(state: &AppState) {
+    let pending: Vec<String> = BATCHES
+        .batches
+        .lock()
+        .values()
+        .filter(|s| s.batch.processing_status != ProcessingStatus::Ended)
+        .map(|s| s.batch.id.clone())
+        .collect();
+    for id in pending {
+        spawn_processing(state.clone(), id);
+    }
+}
+
+fn tenant_id(tenant: &Option<Extension<Tenant>>) -> Option<&str> {
+    tenant.as_ref().map(|Extension(t)| t.id.as_str())
+}
+
+/// POST /v1/messages/batches
+///
+/// 创建批次（立即返回，后台处理）
+pub async fn create_batch(
+    State(state): State<AppState>,
+    tenant: Option<Extension<Tenant>>,
+    headers: HeaderMap,
+    JsonExtractor(payload): JsonExtractor<CreateBatchRequest>,
+) -> Response {
+    let invalid = |message: String| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new("invalid_request_error", message)),
+        )
+            .into_response()
+    };
+
+    if payload.requests.is_empty() {
+        return invalid("requests must not be empty".to_string());
+    }
+    if payload.requests.len() > MAX_BATCH_REQUESTS {
+        return invalid(format!("A batch may contain at most {} requests", MAX_BATCH_REQUESTS));
+    }
+    let mut custom_ids = HashSet::new();
+    for request in &payload.requests {
+        if request.custom_id.is_empty() || !custom_ids.insert(request.custom_id.as_str()) {
+            return invalid(format!("custom_id must be non-empty and unique: '{}'", request.custom_id));
+        }
+        if let Err(e) = serde_json::from_value::<MessagesRequest>(request.params.clone()) {
+            return invalid(format!("Invalid params for '{}': {}", request.custom_id, e));
+        }
+    }
+
+    let anthropic_beta = headers
+        .get("anthropic-beta")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let batch = match BATCHES.create(&payload.requests, tenant_id(&tenant).map(str::to_string), anthropic_beta) {
+        Ok(batch) => batch,
+        Err(e) => {
+            tracing::error!("创建批次失败: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("internal_error", format!("Failed to create batch: {}", e))),
+            )
+                .into_response();
+        }
+    };
+
+    tracing::info!("📦 创建批次 {}（{} 条请求）", batch.id, payload.requests.len());
+    spawn_processing(state, batch.id.clone());
+    Json(batch).into_response()
+}
+
+/// GET /v1/messages/batches
+///
+/// 批次列表（最新的在前，支持 `limit` / `before_id` / `after_id` 分页）
+pub async fn list_batches(
+    tenant: Option<Extension<Tenant>>,
+    Query(query): Query<ListBatchesQuery>,
+) -> Response {
+    let all = BATCHES.list(tenant_id(&tenant));
+    let limit = query.limit.unwrap_or(20).clamp(1, 1000);
+
+    // after_id：从该批次之后（更早）开始；before_id：取该批次之前（更新）的一页
+    let (start, end) = if let Some(after) = &query.after_id {
+        let start = all.iter().position(|b| &b.id == after).map_or(all.len(), |i| i + 1);
+        (start, (start + limit).min(all.len()))
+    } else if let Some(before) = &query.before_id {
+        let end = all.iter().position(|b| &b.id == before).unwrap_or(0);
+        (end.saturating_sub(limit), end)
+    } else {
+        (0, limit.min(all.len()))
+    };
+
+    let has_more = if query.before_id.is_some() { start > 0 } else { end < all.len() };
+    let data = all[start..end].to_vec();
+    Json(ListBatchesResponse {
+        first_id: data.first().map(|b| b.id.clone()),
+        last_id: data.last().map(|b| b.id.clone()),
+        has_more,
+        data,
+    })
+    .into_response()
+}
+
+/// GET /v1/messages/batches/{id}
+pub async fn get_batch(tenant: Option<Extension<Tenant>>, UrlPath(id): UrlPath<String>) -> Response {
+    match BATCHES.get(&id, tenant_id(&tenant)) {
+        Some(batch) => Json(batch).into_response(),
+        None => BatchError::NotFound.into_response(),
+    }
+}
+
+/// GET /v1/messages/batches/{id}/results
+///
+/// 批次结果（JSON Lines，批次结束后可用）
+pub async fn get_batch_results(
+    tenant: Option<Extension<Tenant>>,
+    UrlPath(id): UrlPath<String>,
+) -> Response {
+    match BATCHES.results(&id, tenant_id(&tenant)) {
+        Ok(content) => (
+            [(header::CONTENT_TYPE, "application/x-jsonl")],
+            Body::from(content),
+        )
+            .into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// POST /v1/messages/batches/{id}/cancel
+pub async fn cancel_batch(tenant: Option<Extension<Tenant>>, UrlPath(id): UrlPath<String>) -> Response {
+    match BATCHES.cancel(&id, tenant_id(&tenant)) {
+        Some(batch) => {
+            tracing::info!("批次 {} 已请求取消", id);
+            Json(batch).into_response()
+        }
+        None => BatchError::NotFound.into_response(),
+    }
+}
+
+/// DELETE /v1/messages/batches/{id}
+pub async fn delete_batch(tenant: Option<Extension<Tenant>>, UrlPath(id): UrlPath<String>) -> Response {
+    match BATCHES.delete(&id, tenant_id(&tenant)) {
+        Ok(()) => Json(serde_json::json!({ "id": id, "type": "message_batch_deleted" })).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(custom_id: &str) -> BatchRequest {
+        BatchRequest {
+            custom_id: custom_id.to_string(),
+            params: serde_json::json!({
+                "model": "claude-sonnet-4-5",
+                "max_tokens": 16,
+                "messages": [{ "role": "user", "content": "hi" }]
+            }),
+        }
+    }
+
+    #[test]
+    fn test_batch_lifecycle_persists_and_scopes_to_tenant() {
+        let dir = std::env::temp_dir().join(format!("kiro-batches-{}", uuid::Uuid::new_v4()));
+        let store = BatchStore::new();
+        store.load(dir.clone());
+
+        let batch = store
+            .create(&[request("a"), request("b")], Some("key-1".to_string()), None)
+            .unwrap();
+        assert_eq!(batch.request_counts.processing, 2);
+        assert!(store.get(&batch.id, None).is_none());
+        assert!(matches!(store.results(&batch.id, Some("key-1")), Err(BatchError::NotEnded)));
+
+        store.record_result(&batch.id, "a", succeeded(serde_json::json!({ "id": "msg_1" })));
+        let pending = store.pending_requests(&batch.id).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].custom_id, "b");
+
+        store.cancel(&batch.id, Some("key-1")).unwrap();
+        store.record_result(&batch.id, "b", serde_json::json!({ "type": "canceled" }));
+
+        // 重新加载后状态保持
+        let reloaded = BatchStore::new();
+        reloaded.load(dir.clone());
+        let ended = reloaded.get(&batch.id, Some("key-1")).unwrap();
+        assert_eq!(ended.processing_status, ProcessingStatus::Ended);
+        assert_eq!(ended.request_counts.succeeded, 1);
+        assert_eq!(ended.request_counts.canceled, 1);
+        assert!(ended.results_url.is_some());
+
+        let results = reloaded.results(&batch.id, Some("key-1")).unwrap();
+        assert_eq!(String::from_utf8(results).unwrap().lines().count(), 2);
+
+        reloaded.delete(&batch.id, Some("key-1")).unwrap();
+        assert!(reloaded.list(Some("key-1")).is_empty());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_entries_rejected_after_quota_exhausted() {
+        let registry = ApiKeyRegistry::new();
+        registry.set_keys(vec![crate::model::config::ApiKeyConfig {
+            id: "key-1".to_string(),
+            name: "team".to_string(),
+            key: "sk-team".to_string(),
+            enabled: true,
+            allowed_models: Vec::new(),
+            rate_limit_rpm: None,
+            monthly_token_quota: Some(100),
+            request_transform: None,
+        }]);
+        let tenant = registry.tenant("key-1").unwrap();
+        let dispatched = std::sync::atomic::AtomicUsize::new(0);
+        let dispatch = || async {
+            dispatched.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            succeeded(serde_json::json!({ "id": "msg_1" }))
+        };
+
+        let result = admit_entry(&registry, Some(&tenant), dispatch()).await;
+        assert_eq!(result["type"], "succeeded");
+
+        // 配额用完后剩余条目直接记为错误，不再发往上游
+        registry.record_usage("key-1", 80, 20);
+        for _ in 0..3 {
+            let result = admit_entry(&registry, Some(&tenant), dispatch()).await;
+            assert_eq!(result["type"], "errored");
+            assert_eq!(result["error"]["error"]["type"], "rate_limit_error");
+        }
+        assert_eq!(dispatched.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,217 @@
+//! 超长对话历史压缩
+//!
+//! 估算输入超出模型上下文窗口时，默认直接返回 `prompt is too long`。开启 `historyCompaction` 后，
+//! 在转换为 Kiro 请求之前从最早的对话开始按轮次移除，直到输入加上输出空间能放进上下文窗口，
+//! 并在响应头中带上 `x-kiro-gateway-truncated: true` 告知客户端历史已被截断。
+//!
+//! 一轮对话从一条不含 `tool_result` 的用户消息开始，整轮移除可保证 `tool_use` 与 `tool_result`
+//! 始终成对出现；最后一轮始终保留。`summarize` 策略会把被移除消息的摘录放在保留的第一条消息前面，
+//! 让模型知道之前发生过什么。各消息的大小按本地估算。
+
+use serde_json::{Value, json};
+
+use super::limits::model_capability;
+use super::types::{Message, MessagesRequest};
+use crate::model::config::HistoryCompaction;
+use crate::token::{count_message_tokens, count_tokens};
+
+/// 历史被截断时添加的响应头
+pub const TRUNCATED_HEADER: &str = "x-kiro-gateway-truncated";
+
+/// 摘录中每条消息保留的字符数
+const EXCERPT_CHARS: usize = 200;
+
+/// 摘录最多列出的消息数（更早的只计数）
+const MAX_EXCERPTS: usize = 20;
+
+/// 压缩结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compaction {
+    /// 移除的消息数
+    pub removed_messages: usize,
+    /// 移除的估算 tokens（已扣除摘录本身）
+    pub removed_tokens: i32,
+}
+
+/// 输入超出上下文窗口时按策略压缩对话历史（未压缩时返回 None）
+///
+/// 压缩目标为上下文窗口减去输出空间（`max_tokens`，不超过模型最大输出和窗口的一半）。
+/// 无法压缩到目标以内时仍移除到只剩最后一轮，由后续的上下文校验决定是否拒绝。
+pub fn compact(
+    request: &mut MessagesRequest,
+    input_tokens: i32,
+    strategy: HistoryCompaction,
+) -> Option<Compaction> {
+    if strategy == HistoryCompaction::Off {
+        return None;
+    }
+    let capability = model_capability(&request.model)?;
+    if input_tokens < capability.context_window {
+        return None;
+    }
+    let reserve = request
+        .max_tokens
+        .clamp(0, capability.max_output_tokens)
+        .min(capability.context_window / 2);
+    let excess = i64::from(input_tokens) - i64::from(capability.context_window - reserve);
+
+    let boundaries = turn_boundaries(&request.messages);
+    let mut removed: i64 = 0;
+    let mut removed_until = 0;
+    let mut summary = None;
+    for &boundary in &boundaries {
+        removed += request.messages[removed_until..boundary]
+            .iter()
+            .map(|msg| count_message_tokens(msg) as i64)
+            .sum::<i64>();
+        removed_until = boundary;
+
+        let candidate = (strategy == HistoryCompaction::Summarize)
+            .then(|| summarize(&request.messages[..boundary]));
+        let added = candidate.as_deref().map_or(0, |text| count_tokens(text) as i64);
+        summary = candidate;
+        if removed - added >= excess {
+            break;
+        }
+    }
+    if removed_until == 0 {
+        return None;
+    }
+
+    let added = summary.as_deref().map_or(0, |text| count_tokens(text) as i64);
+    request.messages.drain(..removed_until);
+    if let Some(text) = summary {
+        prepend_text(&mut request.messages[0], text);
+    }
+
+    let compaction = Compaction {
+        removed_messages: removed_until,
+        removed_tokens: (removed - added).clamp(0, i64::from(i32::MAX)) as i32,
+    };
+    tracing::warn!(
+        "对话历史超出上下文窗口（约 {} tokens > {}），已移除最早的 {} 条消息（约 {} tokens）",
+        input_tokens,
+        capability.context_window,
+        compaction.removed_messages,
+        compaction.removed_tokens
+    );
+    Some(compaction)
+}
+
+/// 可作为保留起点的轮次起始位置（不含第一条消息）
+fn turn_boundaries(messages: &[Message]) -> Vec<usize> {
+    messages
+        .iter()
+        .enumerate()
+        .skip(1)
+        .filter(|(_, msg)| msg.role == "user" && !has_tool_result(msg))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+fn has_tool_result(msg: &Message) -> bool {
+    msg.content
+        .as_array()
+        .is_some_and(|blocks| blocks.iter().any(|b| b.get("type").and_then(Value::as_str) == Some("tool_result")))
+}
+
+/// 为被移除的消息生成摘录
+fn summarize(removed: &[Message]) -> String {
+    let mut text = format!(
+        "[Earlier conversation truncated by the gateway: {} messages were omitted to fit the context window.",
+        removed.len()
+    );
+    let skipped = removed.len().saturating_sub(MAX_EXCERPTS);
+    if skipped > 0 {
+        text.push_str(&format!(" The first {} are not shown.", skipped));
+    }
+    text.push_str(" Excerpts of the omitted messages:]");
+    for msg in &removed[skipped..] {
+        let preview = msg.content_preview(EXCERPT_CHARS).replace('\n', " ");
+        text.push_str(&format!("\n- {}: {}", msg.role, preview));
+    }
+    text
+}
+
+/// 在消息内容前插入一个文本块
+fn prepend_text(msg: &mut Message, text: String) {
+    let block = json!({ "type": "text", "text": text });
+    match &mut msg.content {
+        Value::Array(blocks) => blocks.insert(0, block),
+        content => {
+            let original = std::mem::take(content);
+            let original = match original {
+                Value::String(s) => json!({ "type": "text", "text": s }),
+                other => other,
+            };
+            *content = Value::Array(vec![block, original]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 约 30k tokens 的文本
+    fn big_text(tag: &str) -> String {
+        format!("{} {}", tag, "lorem ipsum dolor sit amet ".repeat(5_000))
+    }
+
+    /// 6 轮对话，每轮：用户提问、助手调用工具、工具结果、助手回答
+    fn request() -> MessagesRequest {
+        let mut messages = Vec::new();
+        for i in 0..6 {
+            messages.push(json!({ "role": "user", "content": big_text(&format!("question {}", i)) }));
+            messages.push(json!({ "role": "assistant", "content": [
+                { "type": "tool_use", "id": format!("t{}", i), "name": "Read", "input": {} }
+            ]}));
+            messages.push(json!({ "role": "user", "content": [
+                { "type": "tool_result", "tool_use_id": format!("t{}", i), "content": "ok" },
+                { "type": "text", "text": big_text("result") }
+            ]}));
+            messages.push(json!({ "role": "assistant", "content": [{ "type": "text", "text": "done" }] }));
+        }
+        serde_json::from_value(json!({
+            "model": "claude-sonnet-4-5-20250929",
+            "max_tokens": 8_192,
+            "messages": messages
+        }))
+        .unwrap()
+    }
+
+    fn total_tokens(request: &MessagesRequest) -> i32 {
+        request.messages.iter().map(|m| count_message_tokens(m) as i32).sum()
+    }
+
+    #[test]
+    fn test_drop_oldest_whole_turns() {
+        let mut req = request();
+        let input = total_tokens(&req);
+        assert!(input >= 200_000);
+        assert!(compact(&mut req, input, HistoryCompaction::Off).is_none());
+        assert!(compact(&mut req, 1_000, HistoryCompaction::DropOldest).is_none());
+
+        let result = compact(&mut req, input, HistoryCompaction::DropOldest).unwrap();
+        assert_eq!(result.removed_messages % 4, 0);
+        assert_eq!(input - total_tokens(&req), result.removed_tokens);
+        assert!(total_tokens(&req) <= 200_000 - 8_192);
+        // 保留部分从一轮的开头开始
+        assert_eq!(req.messages[0].role, "user");
+        assert!(req.messages[0].content.is_string());
+    }
+
+    #[test]
+    fn test_summarize_prepends_excerpts() {
+        let mut req = request();
+        let input = total_tokens(&req);
+        let result = compact(&mut req, input, HistoryCompaction::Summarize).unwrap();
+
+        let first = req.messages[0].content.as_array().unwrap();
+        let summary = first[0]["text"].as_str().unwrap();
+        assert!(summary.contains(&format!("{} messages were omitted", result.removed_messages)));
+        assert!(summary.contains("- user: question 0"));
+        assert!(first[1]["text"].as_str().unwrap().starts_with("question"));
+        assert!(total_tokens(&req) <= 200_000 - 8_192);
+    }
+}
@@ -12,7 +12,11 @@ use crate::kiro::model::requests::tool::{
     InputSchema, Tool, ToolResult, ToolSpecification, ToolUseEntry,
 };
 
-use super::types::{ContentBlock, MessagesRequest, Thinking};
+use super::images;
+use super::tool_choice::ToolChoice;
+use super::tool_result;
+use super::types::{ContentBlock, MessagesRequest};
+use super::version::{BETA_INTERLEAVED_THINKING, has_beta};
 
 /// 模型映射：将 Anthropic 模型名映射到 Kiro 模型 ID
 ///
@@ -39,6 +43,10 @@ pub fn map_model(model: &str) -> Option<String> {
 pub struct ConversionResult {
     /// 转换后的 Kiro 请求
     pub conversation_state: ConversationState,
+    /// 从 metadata.user_id 提取的会话 ID（用于会话粘性路由）
+    pub session_id: Option<String>,
+    /// 请求的工具选择策略（输出时据此过滤内容）
+    pub tool_choice: Option<ToolChoice>,
 }
 
 /// 转换错误
@@ -46,6 +54,10 @@ pub struct ConversionResult {
 pub enum ConversionError {
     UnsupportedModel(String),
     EmptyMessages,
+    /// 图片内容块无效（类型不支持、数据损坏或过大）
+    InvalidImage(String),
+    /// tool_choice 无效（类型未知或指定的工具不存在）
+    InvalidToolChoice(String),
 }
 
 impl std::fmt::Display for ConversionError {
@@ -53,6 +65,8 @@ impl std::fmt::Display for ConversionError {
         match self {
             ConversionError::UnsupportedModel(model) => write!(f, "模型不支持: {}", model),
             ConversionError::EmptyMessages => write!(f, "消息列表为空"),
+            ConversionError::InvalidImage(message) => write!(f, "图片无效: {}", message),
+            ConversionError::InvalidToolChoice(message) => write!(f, "{}", message),
         }
     }
 }
@@ -117,6 +131,18 @@ fn create_placeholder_tool(name: &str) -> Tool {
     }
 }
 
+/// 请求中设置了但 Kiro 不支持的采样参数
+pub(crate) fn ignored_sampling_params(req: &MessagesRequest) -> Vec<&'static str> {
+    [
+        ("temperature", req.temperature.is_some()),
+        ("top_p", req.top_p.is_some()),
+        ("top_k", req.top_k.is_some()),
+    ]
+    .into_iter()
+    .filter_map(|(name, set)| set.then_some(name))
+    .collect()
+}
+
 /// 将 Anthropic 请求转换为 Kiro 请求
 pub fn convert_request(req: &MessagesRequest) -> Result<ConversionResult, ConversionError> {
     // 1. 映射模型
@@ -128,13 +154,21 @@ pub fn convert_request(req: &MessagesRequest) -> Result<ConversionResult, Conver
         return Err(ConversionError::EmptyMessages);
     }
 
+    // Kiro 没有采样参数字段，使用服务端默认采样
+    let ignored = ignored_sampling_params(req);
+    if !ignored.is_empty() {
+        tracing::warn!("Kiro 不支持采样参数，已忽略: {}", ignored.join(", "));
+    }
+
     // 3. 生成会话 ID 和代理 ID
     // 优先从 metadata.user_id 中提取 session UUID 作为 conversationId
-    let conversation_id = req
+    let session_id = req
         .metadata
         .as_ref()
         .and_then(|m| m.user_id.as_ref())
-        .and_then(|user_id| extract_session_id(user_id))
+        .and_then(|user_id| extract_session_id(user_id));
+    let conversation_id = session_id
+        .clone()
         .unwrap_or_else(|| Uuid::new_v4().to_string());
     let agent_continuation_id = Uuid::new_v4().to_string();
 
@@ -145,8 +179,12 @@ pub fn convert_request(req: &MessagesRequest) -> Result<ConversionResult, Conver
     let last_message = req.messages.last().unwrap();
     let (text_content, images, tool_results) = process_message_content(&last_message.content)?;
 
-    // 6. 转换工具定义
+    // 6. 转换工具定义（按 tool_choice 过滤：none 不发送，tool 只发送指定工具）
+    let tool_choice = ToolChoice::from_request(req).map_err(ConversionError::InvalidToolChoice)?;
     let mut tools = convert_tools(&req.tools);
+    if let Some(choice) = &tool_choice {
+        tools.retain(|t| choice.sends_tool(&t.tool_specification.name));
+    }
 
     // 7. 构建历史消息（需要先构建，以便收集历史中使用的工具）
     let history = build_history(req, &model_id)?;
@@ -176,8 +214,12 @@ pub fn convert_request(req: &MessagesRequest) -> Result<ConversionResult, Conver
     }
 
     // 10. 构建当前消息
-    // 保留文本内容，即使有工具结果也不丢弃用户文本
-    let content = text_content;
+    // 保留文本内容，即使有工具结果也不丢弃用户文本；Kiro 不支持 tool_choice，以指令形式追加
+    let content = match tool_choice.as_ref().and_then(ToolChoice::instruction) {
+        Some(instruction) if text_content.is_empty() => instruction,
+        Some(instruction) => format!("{}\n\n{}", text_content, instruction),
+        None => text_content,
+    };
 
     let mut user_input = UserInputMessage::new(content, &model_id)
         .with_context(context)
@@ -197,7 +239,11 @@ pub fn convert_request(req: &MessagesRequest) -> Result<ConversionResult, Conver
         .with_current_message(current_message)
         .with_history(history);
 
-    Ok(ConversionResult { conversation_state })
+    Ok(ConversionResult {
+        conversation_state,
+        session_id,
+        tool_choice,
+    })
 }
 
 /// 确定聊天触发类型
@@ -229,14 +275,17 @@ fn process_message_content(
                         }
                         "image" => {
                             if let Some(source) = block.source {
-                                if let Some(format) = get_image_format(&source.media_type) {
-                                    images.push(KiroImage::from_base64(format, source.data));
-                                }
+                                let image = images::to_kiro_image(&source)
+                                    .map_err(ConversionError::InvalidImage)?;
+                                images.push(image);
                             }
                         }
                         "tool_result" => {
                             if let Some(tool_use_id) = block.tool_use_id {
-                                let result_content = extract_tool_result_content(&block.content);
+                                let normalized = tool_result::normalize(block.content.as_ref())
+                                    .map_err(ConversionError::InvalidImage)?;
+                                images.extend(normalized.images);
+                                let result_content = normalized.text;
                                 let is_error = block.is_error.unwrap_or(false);
 
                                 let mut result = if is_error {
@@ -264,35 +313,6 @@ fn process_message_content(
     Ok((text_parts.join("\n"), images, tool_results))
 }
 
-/// 从 media_type 获取图片格式
-fn get_image_format(media_type: &str) -> Option<String> {
-    match media_type {
-        "image/jpeg" => Some("jpeg".to_string()),
-        "image/png" => Some("png".to_string()),
-        "image/gif" => Some("gif".to_string()),
-        "image/webp" => Some("webp".to_string()),
-        _ => None,
-    }
-}
-
-/// 提取工具结果内容
-fn extract_tool_result_content(content: &Option<serde_json::Value>) -> String {
-    match content {
-        Some(serde_json::Value::String(s)) => s.clone(),
-        Some(serde_json::Value::Array(arr)) => {
-            let mut parts = Vec::new();
-            for item in arr {
-                if let Some(text) = item.get("text").and_then(|v| v.as_str()) {
-                    parts.push(text.to_string());
-                }
-            }
-            parts.join("\n")
-        }
-        Some(v) => v.to_string(),
-        None => String::new(),
-    }
-}
-
 /// 转换工具定义
 fn convert_tools(tools: &Option<Vec<super::types::Tool>>) -> Vec<Tool> {
     let Some(tools) = tools else {
@@ -326,17 +346,26 @@ fn is_unsupported_tool(name: &str) -> bool {
     matches!(name.to_lowercase().as_str(), "web_search" | "websearch")
 }
 
-/// 生成thinking标签前缀
-fn generate_thinking_prefix(thinking: &Option<Thinking>) -> Option<String> {
-    if let Some(t) = thinking {
-        if t.thinking_type == "enabled" {
-            return Some(format!(
-                "<thinking_mode>enabled</thinking_mode><max_thinking_length>{}</max_thinking_length>",
-                t.budget_tokens
-            ));
-        }
+/// 请求的思考预算（tokens），未启用 thinking 时返回 None
+///
+/// 与 Anthropic 语义一致：未声明 interleaved-thinking beta 时思考预算必须小于 max_tokens，
+/// 超出部分截断；声明后预算是多轮工具调用间的总预算，允许超过 max_tokens
+pub(crate) fn thinking_budget(req: &MessagesRequest) -> Option<i32> {
+    let t = req.thinking.as_ref().filter(|t| t.thinking_type == "enabled")?;
+    if has_beta(&req.betas, BETA_INTERLEAVED_THINKING) || req.max_tokens <= 1 {
+        Some(t.budget_tokens)
+    } else {
+        Some(t.budget_tokens.min(req.max_tokens - 1))
     }
-    None
+}
+
+/// 生成thinking标签前缀
+fn generate_thinking_prefix(req: &MessagesRequest) -> Option<String> {
+    let budget = thinking_budget(req)?;
+    Some(format!(
+        "<thinking_mode>enabled</thinking_mode><max_thinking_length>{}</max_thinking_length>",
+        budget
+    ))
 }
 
 /// 检查内容是否已包含thinking标签
@@ -349,7 +378,7 @@ fn build_history(req: &MessagesRequest, model_id: &str) -> Result<Vec<Message>,
     let mut history = Vec::new();
 
     // 生成thinking前缀（如果需要）
-    let thinking_prefix = generate_thinking_prefix(&req.thinking);
+    let thinking_prefix = generate_thinking_prefix(req);
 
     // 1. 处理系统消息
     if let Some(ref system) = req.system {
@@ -601,7 +630,12 @@ mod tests {
             tools: None,
             tool_choice: None,
             thinking: None,
+            stop_sequences: None,
+            temperature: None,
+            top_p: None,
+            top_k: None,
             metadata: None,
+            betas: Vec::new(),
         };
         assert_eq!(determine_chat_trigger_type(&req), "MANUAL");
     }
@@ -687,7 +721,12 @@ mod tests {
             tools: None, // 没有提供工具定义
             tool_choice: None,
             thinking: None,
+            stop_sequences: None,
+            temperature: None,
+            top_p: None,
+            top_k: None,
             metadata: None,
+            betas: Vec::new(),
         };
 
         let result = convert_request(&req).unwrap();
@@ -751,11 +790,16 @@ mod tests {
             tools: None,
             tool_choice: None,
             thinking: None,
+            stop_sequences: None,
+            temperature: None,
+            top_p: None,
+            top_k: None,
             metadata: Some(Metadata {
                 user_id: Some(
                     "user_0dede55c6dcc4a11a30bbb5e7f22e6fdf86cdeba3820019cc27612af4e1243cd_account__session_a0662283-7fd3-4399-a7eb-52b9a717ae88".to_string(),
                 ),
             }),
+            betas: Vec::new(),
         };
 
         let result = convert_request(&req).unwrap();
@@ -782,7 +826,12 @@ mod tests {
             tools: None,
             tool_choice: None,
             thinking: None,
+            stop_sequences: None,
+            temperature: None,
+            top_p: None,
+            top_k: None,
             metadata: None,
+            betas: Vec::new(),
         };
 
         let result = convert_request(&req).unwrap();
@@ -798,4 +847,93 @@ mod tests {
             4
         );
     }
+
+    #[test]
+    fn test_sampling_params_are_accepted_and_ignored() {
+        let req: MessagesRequest = serde_json::from_value(serde_json::json!({
+            "model": "claude-sonnet-4",
+            "max_tokens": 1024,
+            "temperature": 0.2,
+            "top_k": 40,
+            "messages": [{ "role": "user", "content": "Hello" }]
+        }))
+        .unwrap();
+
+        assert_eq!(ignored_sampling_params(&req), vec!["temperature", "top_k"]);
+        assert!(convert_request(&req).is_ok());
+    }
+
+    #[test]
+    fn test_tool_choice_filters_tools_and_appends_instruction() {
+        let request = |tool_choice: serde_json::Value| -> MessagesRequest {
+            serde_json::from_value(serde_json::json!({
+                "model": "claude-sonnet-4",
+                "max_tokens": 1024,
+                "messages": [{ "role": "user", "content": "List files" }],
+                "tools": [
+                    { "name": "Bash", "description": "run", "input_schema": { "type": "object" } },
+                    { "name": "Read", "description": "read", "input_schema": { "type": "object" } }
+                ],
+                "tool_choice": tool_choice
+            }))
+            .unwrap()
+        };
+        let tool_names = |result: &ConversionResult| -> Vec<String> {
+            result
+                .conversation_state
+                .current_message
+                .user_input_message
+                .user_input_message_context
+                .tools
+                .iter()
+                .map(|t| t.tool_specification.name.clone())
+                .collect()
+        };
+
+        let forced = convert_request(&request(serde_json::json!({ "type": "tool", "name": "Read" }))).unwrap();
+        assert_eq!(tool_names(&forced), vec!["Read"]);
+        let content = &forced.conversation_state.current_message.user_input_message.content;
+        assert!(content.starts_with("List files\n\n") && content.contains("`Read`"));
+
+        let none = convert_request(&request(serde_json::json!({ "type": "none" }))).unwrap();
+        assert!(tool_names(&none).is_empty());
+
+        let auto = convert_request(&request(serde_json::json!({ "type": "auto" }))).unwrap();
+        assert_eq!(tool_names(&auto), vec!["Bash", "Read"]);
+        assert_eq!(auto.conversation_state.current_message.user_input_message.content, "List files");
+
+        assert!(matches!(
+            convert_request(&request(serde_json::json!({ "type": "tool", "name": "Write" }))),
+            Err(ConversionError::InvalidToolChoice(_))
+        ));
+    }
+
+    #[test]
+    fn test_thinking_budget_clamped_unless_interleaved_beta() {
+        let mut req = MessagesRequest {
+            model: "claude-sonnet-4".to_string(),
+            max_tokens: 4096,
+            messages: vec![],
+            stream: false,
+            system: None,
+            tools: None,
+            tool_choice: None,
+            thinking: Some(super::super::types::Thinking {
+                thinking_type: "enabled".to_string(),
+                budget_tokens: 20000,
+            }),
+            stop_sequences: None,
+            temperature: None,
+            top_p: None,
+            top_k: None,
+            metadata: None,
+            betas: Vec::new(),
+        };
+        let prefix = generate_thinking_prefix(&req).unwrap();
+        assert!(prefix.contains("<max_thinking_length>4095</max_thinking_length>"));
+
+        req.betas = vec!["interleaved-thinking-2025-05-14".to_string()];
+        let prefix = generate_thinking_prefix(&req).unwrap();
+        assert!(prefix.contains("<max_thinking_length>20000</max_thinking_length>"));
+    }
 }
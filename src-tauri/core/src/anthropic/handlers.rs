@@ -0,0 +1,1100 @@
+//! Anthropic API Handler 函数
+
+use std::convert::Infallible;
+
+use crate::api_keys::{API_KEY_REGISTRY, Tenant};
+use crate::cache::idempotency::{self, IDEMPOTENCY_CACHE, Lookup};
+use crate::cache::response::{self as response_cache, RESPONSE_CACHE};
+use crate::error_code::ErrorCode;
+use crate::group_budgets::{GROUP_BUDGETS, GroupBudgetExceeded};
+use crate::kiro::failover_trace::{self, FailoverTrace};
+use crate::kiro::idle_stream::{self, StreamReadError};
+use crate::kiro::provider::UpstreamThrottled;
+use crate::kiro::request_queue::QueueRejected;
+use crate::kiro::token_manager::CredentialUnavailable;
+use crate::metrics::{REALTIME_STATS, StreamTermination, TerminationRecorder};
+use crate::model_catalog::MODEL_CATALOG;
+use crate::model_mapping::MODEL_MAPPER;
+use crate::post_process::POST_PROCESSOR;
+use crate::redaction::REDACTOR;
+use crate::request_transform::REQUEST_TRANSFORMER;
+use crate::kiro::model::events::Event;
+use crate::kiro::model::requests::kiro::KiroRequest;
+use crate::kiro::parser::decoder::EventStreamDecoder;
+use crate::proxy_lifecycle::ProxyLifecycle;
+use crate::token;
+use crate::transcripts::{PendingTranscript, TRANSCRIPTS};
+use crate::watermark::ResponseGroup;
+use axum::{
+    Extension, Json as JsonExtractor,
+    body::Body,
+    extract::{Path as UrlPath, Query, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Json, Response},
+};
+use bytes::Bytes;
+use futures::{Stream, StreamExt, stream};
+use serde_json::json;
+use std::time::Duration;
+use tokio::time::interval;
+use uuid::Uuid;
+
+use super::converter::{ConversionError, convert_request, thinking_budget};
+use super::images;
+use super::middleware::AppState;
+use super::openai_stream;
+use super::stream::{
+    SseEvent, StopSequenceMatcher, StreamContext, context_usage_input_tokens, find_stop_sequence, split_thinking, thinking_signature,
+    truncate_to_tokens,
+};
+use super::json_repair;
+use super::compaction;
+use super::limits;
+use super::resume::{self, STREAM_REPLAY};
+use super::tool_choice::ToolChoice;
+use super::types::{
+    CountTokensRequest, CountTokensResponse, ErrorResponse, ListModelsQuery, MessagesQuery, MessagesRequest,
+    ModelsResponse,
+};
+use super::version::{ApiHeaders, HeaderRejection};
+use super::websearch;
+
+/// GET /v1/models
+///
+/// 返回模型目录中启用的模型（支持 `limit` / `after_id` / `before_id` 分页）
+pub async fn get_models(Query(query): Query<ListModelsQuery>) -> impl IntoResponse {
+    tracing::info!("Received GET /v1/models request");
+
+    let limit = query.limit.unwrap_or(20).clamp(1, 1000);
+    let (models, has_more) = MODEL_CATALOG.page(limit, query.after_id.as_deref(), query.before_id.as_deref());
+
+    Json(ModelsResponse {
+        object: "list".to_string(),
+        first_id: models.first().map(|m| m.id.clone()),
+        last_id: models.last().map(|m| m.id.clone()),
+        has_more,
+        data: models,
+    })
+}
+
+/// GET /v1/models/{id}
+///
+/// 返回单个启用的模型（`client.models.retrieve()`）
+pub async fn get_model(UrlPath(id): UrlPath<String>) -> Response {
+    match MODEL_CATALOG.get(&id) {
+        Some(model) => Json(model).into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(
+                ErrorResponse::new("not_found_error", format!("model: {}", id))
+                    .with_code(ErrorCode::NotFound),
+            ),
+        )
+            .into_response(),
+    }
+}
+
+/// POST /v1/messages
+///
+/// 创建消息（对话），`?format=openai` 时流式响应转换为 OpenAI chunk 格式
+pub async fn post_messages(
+    State(state): State<AppState>,
+    tenant: Option<Extension<Tenant>>,
+    Query(query): Query<MessagesQuery>,
+    headers: HeaderMap,
+    JsonExtractor(payload): JsonExtractor<MessagesRequest>,
+) -> Response {
+    let openai = match query.format.as_deref() {
+        None | Some("anthropic") => false,
+        Some(openai_stream::FORMAT_OPENAI) if payload.stream => true,
+        Some(format) => {
+            let message = if format == openai_stream::FORMAT_OPENAI {
+                "format=openai is only supported for streaming requests".to_string()
+            } else {
+                format!("Unsupported format: {} (expected 'anthropic' or 'openai')", format)
+            };
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::new("invalid_request_error", message).with_code(ErrorCode::InvalidRequest)),
+            )
+                .into_response();
+        }
+    };
+
+    let response = create_message(state, tenant, headers, payload).await;
+    if openai {
+        openai_stream::adapt(response)
+    } else {
+        response
+    }
+}
+
+/// 创建消息（批处理逐条执行时直接调用）
+pub(super) async fn create_message(
+    state: AppState,
+    tenant: Option<Extension<Tenant>>,
+    headers: HeaderMap,
+    mut payload: MessagesRequest,
+) -> Response {
+    let tenant = tenant.map(|Extension(t)| t);
+
+    // anthropic-version / anthropic-beta：拒绝不兼容的版本和依赖服务端能力的 beta
+    let api_headers = match ApiHeaders::from_headers(&headers) {
+        Ok(api_headers) => api_headers,
+        Err(rejection) => {
+            tracing::warn!("请求头不受支持: {}", rejection);
+            let code = match rejection {
+                HeaderRejection::IncompatibleVersion(_) => ErrorCode::UnsupportedApiVersion,
+                HeaderRejection::UnsupportedBeta(_) => ErrorCode::UnsupportedBeta,
+            };
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::new("invalid_request_error", rejection.to_string()).with_code(code)),
+            )
+                .into_response();
+        }
+    };
+    payload.betas = api_headers.betas.clone();
+
+    // 断线续传：携带 Last-Event-ID 且对应的流仍在缓冲中时，从断点继续发送
+    if payload.stream {
+        if let Some(last_event_id) = headers.get("last-event-id").and_then(|v| v.to_str().ok()) {
+            let api_key_id = tenant.as_ref().map(|t| t.id.as_str());
+            match STREAM_REPLAY.find(last_event_id, api_key_id) {
+                Some((replay, after)) => {
+                    tracing::info!("🔄 断线续传: 从事件 {} 之后继续", last_event_id);
+                    return sse_response(Body::from_stream(resume::follow(
+                        replay,
+                        after,
+                        create_ping_sse(),
+                        Duration::from_secs(PING_INTERVAL_SECS),
+                    )));
+                }
+                None => tracing::info!("续传的流不存在或已过期，按新请求处理: {}", last_event_id),
+            }
+        }
+    }
+
+    // 模型名映射（在白名单检查之前，按实际使用的模型校验）
+    if let Some(mapped) = MODEL_MAPPER.resolve(&payload.model) {
+        tracing::debug!("模型映射: {} -> {}", payload.model, mapped);
+        payload.model = mapped;
+    }
+
+    // 租户模型白名单检查
+    if let Some(tenant) = &tenant {
+        if !tenant.allows_model(&payload.model) {
+            return (
+                StatusCode::FORBIDDEN,
+                Json(ErrorResponse::new(
+                    "permission_error",
+                    format!("API key '{}' is not allowed to use model {}", tenant.name, payload.model),
+                )
+                .with_code(ErrorCode::ModelNotAllowed)),
+            )
+                .into_response();
+        }
+    }
+
+    // 请求改写（全局 + API Key）
+    REQUEST_TRANSFORMER.apply(
+        &mut payload,
+        tenant.as_ref().and_then(|t| t.request_transform.as_ref()),
+    );
+    // 出站内容脱敏（在日志记录和缓存键计算之前）
+    let redactions = REDACTOR.apply(&mut payload);
+    let api_key_id = tenant.map(|t| t.id);
+
+    // 幂等键：非流式请求命中缓存时直接返回原响应，不再消耗额度
+    let mut idempotency = None;
+    if !payload.stream {
+        let key = match idempotency::key_from_headers(&headers) {
+            Ok(key) => key,
+            Err(message) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(ErrorResponse::new("invalid_request_error", message).with_code(ErrorCode::InvalidRequest)),
+                )
+                    .into_response();
+            }
+        };
+        if let Some(key) = key {
+            // 请求内容哈希：同一幂等键用于不同请求时拒绝
+            let fingerprint = response_cache::request_digest(None, &payload).unwrap_or_default();
+            match IDEMPOTENCY_CACHE.acquire(api_key_id.as_deref(), &key, &fingerprint).await {
+                Some(Lookup::Replay(cached)) => {
+                    tracing::info!("🔁 幂等键 {} 命中缓存，返回原响应", key);
+                    return cached.replay(idempotency::REPLAYED_HEADER);
+                }
+                Some(Lookup::Reserved(reservation)) => idempotency = Some(reservation),
+                Some(Lookup::Mismatch) => {
+                    tracing::warn!("幂等键 {} 已用于内容不同的请求，已拒绝", key);
+                    return (
+                        StatusCode::UNPROCESSABLE_ENTITY,
+                        Json(
+                            ErrorResponse::new(
+                                "invalid_request_error",
+                                "Idempotency-Key reused with a different request",
+                            )
+                            .with_code(ErrorCode::InvalidRequest),
+                        ),
+                    )
+                        .into_response();
+                }
+                None => {}
+            }
+        }
+    }
+
+    // 响应缓存（启用时）：相同的非流式请求直接返回缓存的响应
+    let cache_key = if payload.stream {
+        None
+    } else {
+        RESPONSE_CACHE.key(api_key_id.as_deref(), &payload)
+    };
+    if let Some(cached) = cache_key.as_deref().and_then(|key| RESPONSE_CACHE.get(key)) {
+        tracing::info!("💾 响应缓存命中，跳过上游调用");
+        let response = redactions.mark(cached.replay(response_cache::CACHE_HIT_HEADER));
+        return match idempotency {
+            Some(reservation) => reservation.complete(response).await,
+            None => response,
+        };
+    }
+
+    // 记录请求摘要
+    let last_user_msg = payload.messages.iter().rev()
+        .find(|m| m.role == "user")
+        .map(|m| {
+            let content_preview = m.content_preview(100);
+            content_preview
+        })
+        .unwrap_or_default();
+    
+    let system_preview = payload.system.as_ref()
+        .map(|messages| {
+            let combined: String = messages.iter().map(|m| m.text.as_str()).collect::<Vec<_>>().join(" ");
+            let char_count = combined.chars().count();
+            if char_count > 50 { 
+                let truncated: String = combined.chars().take(50).collect();
+                format!("{}...", truncated) 
+            } else { 
+                combined 
+            }
+        })
+        .unwrap_or_else(|| "(无)".to_string());
+
+    tracing::info!(
+        model = %payload.model,
+        max_tokens = %payload.max_tokens,
+        stream = %payload.stream,
+        message_count = %payload.messages.len(),
+        anthropic_version = %api_headers.version.as_deref().unwrap_or("-"),
+        anthropic_beta = %api_headers.betas.join(","),
+        system = %system_preview,
+        last_user_message = %last_user_msg,
+        "📨 收到 POST /v1/messages 请求"
+    );
+
+    // 记录到 Admin UI 日志
+    {
+        use crate::logs::{LOG_COLLECTOR, RequestInfo};
+        let active_group = state
+            .kiro_provider
+            .as_ref()
+            .and_then(|p| p.token_manager().get_active_group());
+        LOG_COLLECTOR.add_request_log(RequestInfo {
+            model: payload.model.clone(),
+            max_tokens: payload.max_tokens,
+            stream: payload.stream,
+            message_count: payload.messages.len(),
+            system_preview: system_preview.clone(),
+            user_message_preview: last_user_msg.clone(),
+        }, active_group);
+    }
+    // 检查 KiroProvider 是否可用
+    let provider = match &state.kiro_provider {
+        Some(p) => p.clone(),
+        None => {
+            tracing::error!("KiroProvider 未配置");
+            return (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(ErrorResponse::new(
+                    "service_unavailable",
+                    "Kiro API provider not configured",
+                )),
+            )
+                .into_response();
+        }
+    };
+
+    // 消息数、工具数超出上限时直接拒绝（在估算 tokens 和下载图片之前）
+    if let Err(e) = limits::check_counts(&payload, &provider.token_manager().config().request_limits) {
+        tracing::warn!("请求超出规模限制: {}", e);
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new("invalid_request_error", e.to_string()).with_code(e.code())),
+        )
+            .into_response();
+    }
+
+    // 检查是否为 WebSearch 请求
+    if websearch::has_web_search_tool(&payload) {
+        tracing::info!("检测到 WebSearch 工具，路由到 WebSearch 处理");
+        
+        // 估算输入 tokens
+        let input_tokens = token::count_all_tokens(
+            payload.model.clone(),
+            payload.system.clone(),
+            payload.messages.clone(),
+            payload.tools.clone(),
+        ) as i32;
+
+        if let Some(id) = &api_key_id {
+            API_KEY_REGISTRY.record_usage(id, input_tokens, 0);
+        }
+        
+        return websearch::handle_websearch_request(provider, &payload, input_tokens).await;
+    }
+
+    // 下载 URL 图片并改写为 base64
+    if let Err(message) = images::resolve_remote_images(&mut payload.messages).await {
+        tracing::warn!("图片处理失败: {}", message);
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new("invalid_request_error", message).with_code(ErrorCode::InvalidImage)),
+        )
+            .into_response();
+    }
+
+    // 估算输入 tokens，按配置压缩超长历史，再按模型能力校验 max_tokens 与上下文窗口
+    let mut input_tokens = token::count_all_tokens(
+        payload.model.clone(),
+        payload.system.clone(),
+        payload.messages.clone(),
+        payload.tools.clone(),
+    ) as i32;
+    let config = provider.token_manager().config();
+    let truncated = compaction::compact(&mut payload, input_tokens, config.history_compaction).is_some();
+    if truncated {
+        input_tokens = token::count_all_tokens(
+            payload.model.clone(),
+            payload.system.clone(),
+            payload.messages.clone(),
+            payload.tools.clone(),
+        ) as i32;
+    }
+    match limits::enforce(&mut payload, input_tokens, config.auto_clamp_max_tokens) {
+        Ok(Some(original)) => {
+            tracing::info!("max_tokens 已自动压低: {} -> {}", original, payload.max_tokens);
+        }
+        Ok(None) => {}
+        Err(e) => {
+            tracing::warn!("请求超出模型限制: {}", e);
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::new("invalid_request_error", e.to_string()).with_code(e.code())),
+            )
+                .into_response();
+        }
+    }
+
+    // 转换请求
+    let conversion_result = match convert_request(&payload) {
+        Ok(result) => result,
+        Err(e) => {
+            let (code, message) = match &e {
+                ConversionError::UnsupportedModel(model) => {
+                    (ErrorCode::UnsupportedModel, format!("模型不支持: {}", model))
+                }
+                ConversionError::EmptyMessages => {
+                    (ErrorCode::InvalidRequest, "消息列表为空".to_string())
+                }
+                ConversionError::InvalidImage(message) => {
+                    (ErrorCode::InvalidImage, message.clone())
+                }
+                ConversionError::InvalidToolChoice(message) => {
+                    (ErrorCode::InvalidRequest, message.clone())
+                }
+            };
+            tracing::warn!("请求转换失败: {}", e);
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::new("invalid_request_error", message).with_code(code)),
+            )
+                .into_response();
+        }
+    };
+
+    let session_id = conversion_result.session_id;
+    let tool_choice = conversion_result.tool_choice;
+
+    // 构建 Kiro 请求
+    let kiro_request = KiroRequest {
+        conversation_state: conversion_result.conversation_state,
+        profile_arn: state.profile_arn.clone(),
+    };
+
+    let request_body = match serde_json::to_string(&kiro_request) {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::error!("序列化请求失败: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new(
+                    "internal_error",
+                    format!("序列化请求失败: {}", e),
+                )),
+            )
+                .into_response();
+        }
+    };
+
+    tracing::debug!("Kiro request body: {}", request_body);
+
+    // thinking 预算（未启用 thinking 时为 None）
+    let thinking_budget = thinking_budget(&payload);
+
+    let stop_sequences = payload.stop_sequences.take().unwrap_or_default();
+    let trace_requested = failover_trace::requested(&headers);
+    let transcript = TRANSCRIPTS.begin(api_key_id.as_deref(), &payload);
+
+    if payload.stream {
+        // 流式响应
+        let response = handle_stream_request(
+            provider,
+            &request_body,
+            &payload.model,
+            input_tokens,
+            thinking_budget,
+            stop_sequences,
+            tool_choice,
+            state.proxy.clone(),
+            api_key_id,
+            session_id.as_deref(),
+            trace_requested,
+        )
+        .await;
+        let response = record_transcript(transcript, response).await;
+        redactions.mark(mark_truncated(response, truncated))
+    } else {
+        // 非流式响应
+        let response = handle_non_stream_request(
+            provider,
+            &request_body,
+            &payload.model,
+            input_tokens,
+            thinking_budget,
+            &stop_sequences,
+            tool_choice.as_ref(),
+            api_key_id,
+            session_id.as_deref(),
+            trace_requested,
+        )
+        .await;
+        let response = record_transcript(transcript, response).await;
+        let response = redactions.mark(mark_truncated(response, truncated));
+        let response = match cache_key {
+            Some(key) => RESPONSE_CACHE.store(key, response).await,
+            None => response,
+        };
+        match idempotency {
+            Some(reservation) => reservation.complete(response).await,
+            None => response,
+        }
+    }
+}
+
+/// 启用对话记录时保存请求和最终响应
+async fn record_transcript(transcript: Option<PendingTranscript>, response: Response) -> Response {
+    match transcript {
+        Some(transcript) => transcript.record(response).await,
+        None => response,
+    }
+}
+
+/// 对话历史被压缩时添加截断响应头
+fn mark_truncated(mut response: Response, truncated: bool) -> Response {
+    if truncated {
+        response
+            .headers_mut()
+            .insert(compaction::TRUNCATED_HEADER, HeaderValue::from_static("true"));
+    }
+    response
+}
+
+/// 请求开启故障转移追踪时添加追踪响应头
+fn with_trace(mut response: Response, trace: Option<&FailoverTrace>) -> Response {
+    if let Some(trace) = trace {
+        trace.apply(response.headers_mut());
+    }
+    response
+}
+
+/// 将 Kiro API 调用失败转换为错误响应
+///
+/// 凭证获取超时返回 503 `credential_unavailable`，排队已满或超时返回 503 `overloaded_error`，
+/// 上游限流返回 429，其余视为上游错误返回 502
+fn upstream_error_response(e: anyhow::Error) -> Response {
+    tracing::error!("Kiro API 调用失败: {}", e);
+    REALTIME_STATS.record_error();
+    if e.is::<QueueRejected>() {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(
+                ErrorResponse::new("overloaded_error", e.to_string())
+                    .with_code(ErrorCode::Overloaded),
+            ),
+        )
+            .into_response();
+    }
+    if e.is::<CredentialUnavailable>() {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse::new("credential_unavailable", e.to_string())),
+        )
+            .into_response();
+    }
+    if e.is::<GroupBudgetExceeded>() {
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(
+                ErrorResponse::new("rate_limit_error", e.to_string())
+                    .with_code(ErrorCode::GroupBudgetExceeded),
+            ),
+        )
+            .into_response();
+    }
+    if e.is::<UpstreamThrottled>() {
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(
+                ErrorResponse::new("rate_limit_error", e.to_string())
+                    .with_code(ErrorCode::UpstreamThrottled),
+            ),
+        )
+            .into_response();
+    }
+    (
+        StatusCode::BAD_GATEWAY,
+        Json(ErrorResponse::new(
+            "api_error",
+            format!("上游 API 调用失败: {}", e),
+        )),
+    )
+        .into_response()
+}
+
+/// 处理流式请求
+async fn handle_stream_request(
+    provider: std::sync::Arc<crate::kiro::provider::KiroProvider>,
+    request_body: &str,
+    model: &str,
+    input_tokens: i32,
+    thinking_budget: Option<i32>,
+    stop_sequences: Vec<String>,
+    tool_choice: Option<ToolChoice>,
+    proxy: ProxyLifecycle,
+    api_key_id: Option<String>,
+    session_id: Option<&str>,
+    trace_requested: bool,
+) -> Response {
+    let started_at = std::time::Instant::now();
+
+    // 调用 Kiro API（支持多凭证故障转移）
+    let mut trace = FailoverTrace::new();
+    let upstream = match provider.call_api_traced(request_body, true, session_id, &mut trace).await {
+        Ok(resp) => resp,
+        Err(e) => return with_trace(upstream_error_response(e), trace_requested.then_some(&trace)),
+    };
+    let trace = trace_requested.then_some(trace);
+
+    // 创建流处理上下文
+    let mut ctx = StreamContext::new_with_thinking(model, input_tokens, thinking_budget.is_some());
+    ctx.thinking_budget = thinking_budget;
+    ctx.stop_sequences = StopSequenceMatcher::new(stop_sequences);
+    ctx.tool_choice = tool_choice;
+    ctx.api_key_id = api_key_id;
+    ctx.started_at = started_at;
+    let group = ResponseGroup(upstream.group_id.clone());
+    ctx.group_id = Some(upstream.group_id);
+    ctx.credential_slot = upstream.slot;
+    ctx.replay = STREAM_REPLAY.start(&ctx.message_id, ctx.api_key_id.clone());
+    let response = upstream.response;
+
+    // 生成初始事件
+    let initial_events = ctx.generate_initial_events();
+
+    // 创建 SSE 流
+    let replay = ctx.replay.clone();
+    let idle = provider.token_manager().config().upstream_timeouts.idle();
+    let stream = create_sse_stream(response, ctx, initial_events, proxy, idle);
+
+    // 开启追踪时在事件流开头附带一条 SSE 注释（读不到响应头的客户端也能看到）
+    let trace_comment = stream::iter(trace.as_ref().map(|t| Ok(t.sse_comment())));
+    let body = match replay {
+        // 启用续传：后台驱动上游流到结束，客户端（含重连）从重放缓冲读取
+        Some(replay) => {
+            let driver = replay.clone();
+            tokio::spawn(async move {
+                // ping 由跟随流自行发送，这里只需驱动到结束
+                stream.for_each(|_| async {}).await;
+                driver.finish();
+            });
+            Body::from_stream(trace_comment.chain(resume::follow(
+                replay,
+                0,
+                create_ping_sse(),
+                Duration::from_secs(PING_INTERVAL_SECS),
+            )))
+        }
+        None => Body::from_stream(trace_comment.chain(stream)),
+    };
+
+    let mut response = sse_response(body);
+    response.extensions_mut().insert(group);
+    with_trace(response, trace.as_ref())
+}
+
+/// 构造 SSE 响应
+fn sse_response(body: Body) -> Response {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "text/event-stream")
+        .header(header::CACHE_CONTROL, "no-cache")
+        .header(header::CONNECTION, "keep-alive")
+        .body(body)
+        .unwrap()
+}
+
+/// 将事件编码为 SSE 字节（启用续传时分配事件 ID 并写入重放缓冲）
+fn encode_events(ctx: &StreamContext, events: Vec<SseEvent>) -> Vec<Result<Bytes, Infallible>> {
+    events
+        .into_iter()
+        .map(|e| {
+            Ok(match &ctx.replay {
+                Some(replay) => replay.push(e),
+                None => Bytes::from(e.to_sse_string()),
+            })
+        })
+        .collect()
+}
+
+/// Ping 事件间隔（25秒）
+const PING_INTERVAL_SECS: u64 = 25;
+
+/// 创建 ping 事件的 SSE 字符串
+fn create_ping_sse() -> Bytes {
+    Bytes::from("event: ping\ndata: {\"type\": \"ping\"}\n\n")
+}
+
+/// 创建 SSE 事件流
+fn create_sse_stream(
+    response: reqwest::Response,
+    ctx: StreamContext,
+    initial_events: Vec<SseEvent>,
+    proxy: ProxyLifecycle,
+    idle: Option<Duration>,
+) -> impl Stream<Item = Result<Bytes, Infallible>> {
+    // 先发送初始事件
+    let initial_stream = stream::iter(encode_events(&ctx, initial_events));
+
+    // 然后处理 Kiro 响应流，同时每25秒发送 ping 保活；上游超过 idle 未返回数据时结束流
+    let body_stream = idle_stream::body_stream(response, idle);
+
+    let processing_stream = stream::unfold(
+        (
+            body_stream,
+            ctx,
+            EventStreamDecoder::new(),
+            false,
+            interval(Duration::from_secs(PING_INTERVAL_SECS)),
+            proxy,
+            TerminationRecorder::new(),
+        ),
+        |(mut body_stream, mut ctx, mut decoder, finished, mut ping_interval, proxy, mut recorder)| async move {
+            if finished {
+                return None;
+            }
+
+            // 检查代理是否被禁用，如果禁用则中断流
+            if proxy.should_abort_streams() {
+                tracing::info!("代理服务已禁用，中断正在进行的流式响应");
+                recorder.finish(StreamTermination::ProxyDisabled);
+                // 发送错误事件并结束
+                let error_event = SseEvent::new(
+                    "error",
+                    json!({
+                        "type": "error",
+                        "error": {
+                            "type": "service_unavailable",
+                            "message": "Proxy service has been disabled",
+                            "code": ErrorCode::ProxyDisabled
+                        }
+                    }),
+                );
+                let bytes = encode_events(&ctx, vec![error_event]);
+                return Some((stream::iter(bytes), (body_stream, ctx, decoder, true, ping_interval, proxy, recorder)));
+            }
+
+            // 使用 select! 同时等待数据、ping 定时器和代理状态检查
+            tokio::select! {
+                // 处理数据流
+                chunk_result = body_stream.next() => {
+                    match chunk_result {
+                        Some(Ok(chunk)) => {
+                            // 解码事件
+                            if let Err(e) = decoder.feed(&chunk) {
+                                tracing::warn!("缓冲区溢出: {}", e);
+                            }
+
+                            let mut events = Vec::new();
+                            for result in decoder.decode_iter() {
+                                match result {
+                                    Ok(frame) => {
+                                        if let Ok(event) = Event::from_frame(frame) {
+                                            let sse_events = ctx.process_kiro_event(&event);
+                                            events.extend(sse_events);
+                                        }
+                                    }
+                                    Err(e) => {
+                                        tracing::warn!("解码事件失败: {}", e);
+                                    }
+                                }
+                            }
+
+                            // 命中停止序列：不再读取上游，直接结束
+                            let stopped = ctx.is_stopped();
+                            if stopped {
+                                recorder.finish(StreamTermination::Completed);
+                                events.extend(ctx.generate_final_events());
+                            }
+
+                            // 转换为 SSE 字节流
+                            let bytes = encode_events(&ctx, events);
+
+                            Some((stream::iter(bytes), (body_stream, ctx, decoder, stopped, ping_interval, proxy, recorder)))
+                        }
+                        Some(Err(StreamReadError::Idle(limit))) => {
+                            tracing::error!("上游流式响应 {} 秒未返回数据，中断响应", limit.as_secs());
+                            recorder.finish(StreamTermination::IdleTimeout);
+                            let error_event = SseEvent::new(
+                                "error",
+                                json!({
+                                    "type": "error",
+                                    "error": {
+                                        "type": "timeout_error",
+                                        "message": format!("Upstream stream stalled: no data received for {} seconds", limit.as_secs()),
+                                        "code": ErrorCode::UpstreamTimeout
+                                    }
+                                }),
+                            );
+                            let bytes = encode_events(&ctx, vec![error_event]);
+                            Some((stream::iter(bytes), (body_stream, ctx, decoder, true, ping_interval, proxy, recorder)))
+                        }
+                        Some(Err(e)) => {
+                            tracing::error!("读取响应流失败: {}", e);
+                            recorder.finish(if e.is_timeout() {
+                                StreamTermination::IdleTimeout
+                            } else {
+                                StreamTermination::UpstreamError
+                            });
+                            // 发送最终事件并结束
+                            let final_events = ctx.generate_final_events();
+                            let bytes = encode_events(&ctx, final_events);
+                            Some((stream::iter(bytes), (body_stream, ctx, decoder, true, ping_interval, proxy, recorder)))
+                        }
+                        None => {
+                            // 流结束，发送最终事件
+                            recorder.finish(StreamTermination::Completed);
+                            let final_events = ctx.generate_final_events();
+                            let bytes = encode_events(&ctx, final_events);
+                            Some((stream::iter(bytes), (body_stream, ctx, decoder, true, ping_interval, proxy, recorder)))
+                        }
+                    }
+                }
+                // 发送 ping 保活
+                _ = ping_interval.tick() => {
+                    tracing::trace!("发送 ping 保活事件");
+                    let bytes: Vec<Result<Bytes, Infallible>> = vec![Ok(create_ping_sse())];
+                    Some((stream::iter(bytes), (body_stream, ctx, decoder, false, ping_interval, proxy, recorder)))
+                }
+                // 快速检查代理状态（500ms 间隔）
+                _ = tokio::time::sleep(Duration::from_millis(500)) => {
+                    // 检查代理是否被禁用
+                    if proxy.should_abort_streams() {
+                        tracing::info!("代理服务已禁用，中断正在进行的流式响应");
+                        recorder.finish(StreamTermination::ProxyDisabled);
+                        let error_event = SseEvent::new(
+                            "error",
+                            json!({
+                                "type": "error",
+                                "error": {
+                                    "type": "service_unavailable",
+                                    "message": "Proxy service has been disabled",
+                                    "code": ErrorCode::ProxyDisabled
+                                }
+                            }),
+                        );
+                        let bytes = encode_events(&ctx, vec![error_event]);
+                        return Some((stream::iter(bytes), (body_stream, ctx, decoder, true, ping_interval, proxy, recorder)));
+                    }
+                    // 代理仍启用，返回空事件继续循环
+                    let bytes: Vec<Result<Bytes, Infallible>> = vec![];
+                    Some((stream::iter(bytes), (body_stream, ctx, decoder, false, ping_interval, proxy, recorder)))
+                }
+            }
+        },
+    )
+    .flatten();
+
+    initial_stream.chain(processing_stream)
+}
+
+/// 处理非流式请求
+async fn handle_non_stream_request(
+    provider: std::sync::Arc<crate::kiro::provider::KiroProvider>,
+    request_body: &str,
+    model: &str,
+    input_tokens: i32,
+    thinking_budget: Option<i32>,
+    stop_sequences: &[String],
+    tool_choice: Option<&ToolChoice>,
+    api_key_id: Option<String>,
+    session_id: Option<&str>,
+    trace_requested: bool,
+) -> Response {
+    let started_at = std::time::Instant::now();
+
+    // 调用 Kiro API（支持多凭证故障转移）
+    let mut trace = FailoverTrace::new();
+    let upstream = match provider.call_api_traced(request_body, false, session_id, &mut trace).await {
+        Ok(resp) => resp,
+        Err(e) => return with_trace(upstream_error_response(e), trace_requested.then_some(&trace)),
+    };
+    let trace = trace_requested.then_some(trace);
+
+    // 读取响应体
+    let group_id = upstream.group_id;
+    let body_bytes = match upstream.response.bytes().await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::error!("读取响应体失败: {}", e);
+            REALTIME_STATS.record_error();
+            let response = (
+                StatusCode::BAD_GATEWAY,
+                Json(
+                    ErrorResponse::new("api_error", format!("读取响应失败: {}", e))
+                        .with_code(ErrorCode::UpstreamBadResponse),
+                ),
+            )
+                .into_response();
+            return with_trace(response, trace.as_ref());
+        }
+    };
+
+    // 解析事件流
+    let mut decoder = EventStreamDecoder::new();
+    if let Err(e) = decoder.feed(&body_bytes) {
+        tracing::warn!("缓冲区溢出: {}", e);
+    }
+
+    let mut text_content = String::new();
+    let mut tool_uses: Vec<serde_json::Value> = Vec::new();
+    let mut has_tool_use = false;
+    let mut stop_reason = "end_turn".to_string();
+    // 从 contextUsageEvent 计算的实际输入 tokens
+    let mut context_input_tokens: Option<i32> = None;
+
+    // 收集工具调用的增量 JSON
+    let mut tool_json_buffers: std::collections::HashMap<String, String> =
+        std::collections::HashMap::new();
+
+    for result in decoder.decode_iter() {
+        match result {
+            Ok(frame) => {
+                if let Ok(event) = Event::from_frame(frame) {
+                    match event {
+                        Event::AssistantResponse(resp) => {
+                            text_content.push_str(&resp.content);
+                        }
+                        Event::ToolUse(tool_use) => {
+                            has_tool_use = true;
+
+                            // 累积工具的 JSON 输入
+                            let buffer = tool_json_buffers
+                                .entry(tool_use.tool_use_id.clone())
+                                .or_insert_with(String::new);
+                            buffer.push_str(&tool_use.input);
+
+                            // 如果是完整的工具调用，添加到列表
+                            if tool_use.stop {
+                                let input =
+                                    json_repair::parse_tool_input(buffer, &tool_use.tool_use_id);
+
+                                tool_uses.push(json!({
+                                    "type": "tool_use",
+                                    "id": tool_use.tool_use_id,
+                                    "name": tool_use.name,
+                                    "input": input
+                                }));
+                            }
+                        }
+                        Event::ContextUsage(context_usage) => {
+                            context_input_tokens = Some(context_usage_input_tokens(&context_usage));
+                        }
+                        Event::Exception { exception_type, .. } => {
+                            if exception_type == "ContentLengthExceededException" {
+                                stop_reason = "max_tokens".to_string();
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Err(e) => {
+                tracing::warn!("解码事件失败: {}", e);
+            }
+        }
+    }
+
+    // tool_choice 不允许的工具调用直接丢弃
+    if let Some(choice) = tool_choice {
+        tool_uses.retain(|t| t["name"].as_str().is_some_and(|name| choice.allows_tool(name)));
+        has_tool_use = !tool_uses.is_empty();
+    }
+
+    // 确定 stop_reason
+    if has_tool_use && stop_reason == "end_turn" {
+        stop_reason = "tool_use".to_string();
+    }
+
+    // 构建响应内容：启用 thinking 时拆分出 thinking 块（按预算截断，附带签名）
+    let mut content: Vec<serde_json::Value> = Vec::new();
+
+    if let Some(budget) = thinking_budget {
+        let (thinking, text) = split_thinking(&text_content);
+        if let Some(thinking) = thinking {
+            let thinking = truncate_to_tokens(&thinking, budget);
+            content.push(json!({
+                "type": "thinking",
+                "thinking": thinking,
+                "signature": thinking_signature(thinking)
+            }));
+        }
+        text_content = text;
+    }
+
+    // 响应后处理（与流式响应一致，在停止序列匹配之前执行）
+    text_content = POST_PROCESSOR.apply(&text_content);
+
+    // 命中停止序列：截断文本，之后的输出（含工具调用）丢弃
+    let mut stop_sequence: Option<String> = None;
+    if let Some((pos, sequence)) = find_stop_sequence(&text_content, stop_sequences) {
+        text_content.truncate(pos);
+        stop_sequence = Some(sequence.to_string());
+        stop_reason = "stop_sequence".to_string();
+        tool_uses.clear();
+        has_tool_use = false;
+    }
+
+    // tool_choice 要求调用工具且模型已调用时丢弃文本（未调用时照常返回文本）
+    if has_tool_use && tool_choice.is_some_and(ToolChoice::suppresses_text) {
+        text_content.clear();
+    }
+
+    if !text_content.is_empty() {
+        content.push(json!({
+            "type": "text",
+            "text": text_content
+        }));
+    }
+
+    content.extend(tool_uses);
+
+    // 估算输出 tokens
+    let output_tokens = token::estimate_output_tokens(&content);
+
+    // 使用从 contextUsageEvent 计算的 input_tokens，如果没有则使用估算值
+    let final_input_tokens = context_input_tokens.unwrap_or(input_tokens);
+
+    // 构建 Anthropic 响应
+    let response_body = json!({
+        "id": format!("msg_{}", Uuid::new_v4().to_string().replace('-', "")),
+        "type": "message",
+        "role": "assistant",
+        "content": content,
+        "model": model,
+        "stop_reason": stop_reason,
+        "stop_sequence": stop_sequence,
+        "usage": {
+            "input_tokens": final_input_tokens,
+            "output_tokens": output_tokens
+        }
+    });
+
+    // 记录响应摘要
+    let response_preview = {
+        let char_count = text_content.chars().count();
+        if char_count > 100 {
+            let truncated: String = text_content.chars().take(100).collect();
+            format!("{}...", truncated)
+        } else {
+            text_content.clone()
+        }
+    };
+    tracing::info!(
+        model = %model,
+        input_tokens = %final_input_tokens,
+        output_tokens = %output_tokens,
+        stop_reason = %stop_reason,
+        tool_calls = %has_tool_use,
+        response_preview = %response_preview,
+        "📤 非流式响应完成"
+    );
+
+    // 记录到 Admin UI 日志
+    {
+        use crate::logs::{LOG_COLLECTOR, ResponseInfo};
+        LOG_COLLECTOR.add_response_log(ResponseInfo {
+            model: model.to_string(),
+            input_tokens: final_input_tokens,
+            output_tokens,
+            stop_reason: stop_reason.clone(),
+            has_tool_use,
+            response_preview: response_preview.clone(),
+        }, false, Some(group_id.clone()));
+    }
+
+    if let Some(id) = &api_key_id {
+        API_KEY_REGISTRY.record_usage(id, final_input_tokens, output_tokens);
+    }
+    GROUP_BUDGETS.record_usage(&group_id, final_input_tokens, output_tokens);
+    REALTIME_STATS.record_success(started_at.elapsed(), final_input_tokens, output_tokens);
+
+    let response = (StatusCode::OK, Extension(ResponseGroup(group_id)), Json(response_body)).into_response();
+    with_trace(response, trace.as_ref())
+}
+
+/// POST /v1/messages/count_tokens
+///
+/// 计算消息的 token 数量
+pub async fn count_tokens(
+    JsonExtractor(payload): JsonExtractor<CountTokensRequest>,
+) -> impl IntoResponse {
+    tracing::info!(
+        model = %payload.model,
+        message_count = %payload.messages.len(),
+        "Received POST /v1/messages/count_tokens request"
+    );
+
+    let total_tokens = token::count_all_tokens(
+        payload.model,
+        payload.system,
+        payload.messages,
+        payload.tools,
+    ) as i32;
+
+    Json(CountTokensResponse {
+        input_tokens: total_tokens.max(1) as i32,
+    })
+}
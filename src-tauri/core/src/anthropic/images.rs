@@ -0,0 +1,292 @@
+//! 图片内容块处理
+//!
+//! 校验 base64 图片（格式、大小）并转换为 Kiro 图片；`source.type = "url"` 的图片在转换前
+//! 由 [`resolve_remote_images`] 下载并改写为 base64 数据源。
+
+use std::time::Duration;
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+
+use crate::kiro::model::requests::conversation::KiroImage;
+
+use super::types::{ImageSource, Message};
+
+/// 单张图片解码后的最大字节数
+pub(crate) const MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+
+/// 下载远程图片的超时
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 支持的媒体类型及对应的 Kiro 图片格式
+const SUPPORTED_FORMATS: [(&str, &str); 4] = [
+    ("image/jpeg", "jpeg"),
+    ("image/png", "png"),
+    ("image/gif", "gif"),
+    ("image/webp", "webp"),
+];
+
+/// 从媒体类型获取 Kiro 图片格式
+fn format_for_media_type(media_type: &str) -> Option<&'static str> {
+    let media_type = media_type.split(';').next().unwrap_or("").trim();
+    let media_type = if media_type.eq_ignore_ascii_case("image/jpg") {
+        "image/jpeg"
+    } else {
+        media_type
+    };
+    SUPPORTED_FORMATS
+        .iter()
+        .find(|(mt, _)| mt.eq_ignore_ascii_case(media_type))
+        .map(|(_, format)| *format)
+}
+
+/// 根据文件头识别图片格式
+fn sniff_format(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]) {
+        Some("png")
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("jpeg")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("gif")
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some("webp")
+    } else {
+        None
+    }
+}
+
+fn unsupported_media_type(media_type: &str) -> String {
+    let supported: Vec<&str> = SUPPORTED_FORMATS.iter().map(|(mt, _)| *mt).collect();
+    format!(
+        "不支持的图片类型: {}（支持 {}）",
+        media_type,
+        supported.join(", ")
+    )
+}
+
+/// 校验图片数据并返回格式（以文件头识别结果为准，声明的类型不一致时自动纠正）
+fn validate_bytes(declared: &str, bytes: &[u8]) -> Result<&'static str, String> {
+    if bytes.len() > MAX_IMAGE_BYTES {
+        return Err(format!(
+            "图片过大: {} 字节（上限 {} 字节）",
+            bytes.len(),
+            MAX_IMAGE_BYTES
+        ));
+    }
+    let Some(format) = sniff_format(bytes) else {
+        return Err(format!("图片数据无法识别（声明类型 {}）", declared));
+    };
+    if format_for_media_type(declared) != Some(format) {
+        tracing::debug!("图片声明类型 {} 与实际格式 {} 不一致，按实际格式发送", declared, format);
+    }
+    Ok(format)
+}
+
+/// 将 Anthropic 图片数据源转换为 Kiro 图片
+pub(crate) fn to_kiro_image(source: &ImageSource) -> Result<KiroImage, String> {
+    match source.source_type.as_str() {
+        "base64" => {}
+        "url" => return Err("图片 URL 未能下载，无法转发".to_string()),
+        other => return Err(format!("不支持的图片来源类型: {}", other)),
+    }
+
+    let media_type = source.media_type.as_deref().unwrap_or_default();
+    if format_for_media_type(media_type).is_none() {
+        return Err(unsupported_media_type(media_type));
+    }
+    let data = source.data.as_deref().unwrap_or_default();
+    // 部分客户端会在 base64 中插入换行
+    let data: String = data.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+    let bytes = STANDARD
+        .decode(&data)
+        .map_err(|e| format!("图片 base64 数据无效: {}", e))?;
+    let format = validate_bytes(media_type, &bytes)?;
+    Ok(KiroImage::from_base64(format, data))
+}
+
+/// 下载远程图片
+async fn fetch_image(client: &reqwest::Client, url: &str) -> Result<(String, Vec<u8>), String> {
+    let mut response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| format!("下载图片失败 ({}): {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("下载图片失败 ({}): HTTP {}", url, response.status()));
+    }
+    if response.content_length().is_some_and(|len| len as usize > MAX_IMAGE_BYTES) {
+        return Err(format!("图片过大 ({}): 上限 {} 字节", url, MAX_IMAGE_BYTES));
+    }
+    let media_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+
+    // 分块读取，超出上限立即中止
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("下载图片失败 ({}): {}", url, e))?
+    {
+        if bytes.len() + chunk.len() > MAX_IMAGE_BYTES {
+            return Err(format!("图片过大 ({}): 上限 {} 字节", url, MAX_IMAGE_BYTES));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok((media_type, bytes))
+}
+
+/// 将 URL 图片转换为 base64 数据源（`data:` URL 直接解析，不发起请求）
+async fn resolve_url(
+    client: &reqwest::Client,
+    url: &str,
+) -> Result<(&'static str, String), String> {
+    if let Some(rest) = url.strip_prefix("data:") {
+        let Some((meta, data)) = rest.split_once(',') else {
+            return Err("data URL 格式无效".to_string());
+        };
+        let Some(media_type) = meta.strip_suffix(";base64") else {
+            return Err("data URL 必须为 base64 编码".to_string());
+        };
+        let format = format_for_media_type(media_type)
+            .ok_or_else(|| unsupported_media_type(media_type))?;
+        let media_type = SUPPORTED_FORMATS.iter().find(|(_, f)| *f == format).unwrap().0;
+        return Ok((media_type, data.to_string()));
+    }
+
+    let scheme = url.split_once("://").map(|(s, _)| s.to_ascii_lowercase());
+    if !matches!(scheme.as_deref(), Some("http" | "https")) {
+        return Err(format!("图片 URL 仅支持 http/https: {}", url));
+    }
+
+    let (declared, bytes) = fetch_image(client, url).await?;
+    // 服务器返回的类型不可靠（常见 application/octet-stream），以文件头为准
+    if !declared.is_empty()
+        && !declared.starts_with("image/")
+        && !declared.starts_with("application/octet-stream")
+    {
+        return Err(unsupported_media_type(&declared));
+    }
+    let format = validate_bytes(&declared, &bytes)?;
+    let media_type = SUPPORTED_FORMATS.iter().find(|(_, f)| *f == format).unwrap().0;
+    Ok((media_type, STANDARD.encode(&bytes)))
+}
+
+/// 收集内容块中所有 URL 图片的数据源（包括 `tool_result` 等嵌套块中的图片）
+fn collect_url_sources<'a>(blocks: &'a mut [serde_json::Value], sources: &mut Vec<&'a mut serde_json::Value>) {
+    for block in blocks {
+        let Some(block) = block.as_object_mut() else {
+            continue;
+        };
+        if block.get("type").and_then(|t| t.as_str()) == Some("image") {
+            if let Some(source) = block
+                .get_mut("source")
+                .filter(|s| s.get("type").and_then(|t| t.as_str()) == Some("url"))
+            {
+                sources.push(source);
+            }
+        } else if let Some(inner) = block.get_mut("content").and_then(|c| c.as_array_mut()) {
+            collect_url_sources(inner, sources);
+        }
+    }
+}
+
+/// 下载消息中所有 URL 图片并改写为 base64 数据源，返回处理的图片数
+pub async fn resolve_remote_images(messages: &mut [Message]) -> Result<usize, String> {
+    let mut sources = Vec::new();
+    for message in messages.iter_mut() {
+        if let Some(blocks) = message.content.as_array_mut() {
+            collect_url_sources(blocks, &mut sources);
+        }
+    }
+
+    let mut client = None;
+    let mut resolved = 0;
+    for source in sources {
+        let Some(url) = source.get("url").and_then(|u| u.as_str()).map(str::to_string) else {
+            return Err("图片 URL 数据源缺少 url 字段".to_string());
+        };
+
+        if client.is_none() {
+            client = Some(
+                crate::http_client::build_client(None, FETCH_TIMEOUT.as_secs())
+                    .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?,
+            );
+        }
+        let (media_type, data) = resolve_url(client.as_ref().unwrap(), &url).await?;
+        *source = serde_json::json!({
+            "type": "base64",
+            "media_type": media_type,
+            "data": data,
+        });
+        resolved += 1;
+    }
+
+    if resolved > 0 {
+        tracing::debug!("已下载 {} 张 URL 图片", resolved);
+    }
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG_1X1: &str = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA60e6kgAAAABJRU5ErkJggg==";
+
+    fn base64_source(media_type: &str, data: &str) -> ImageSource {
+        ImageSource {
+            source_type: "base64".to_string(),
+            media_type: Some(media_type.to_string()),
+            data: Some(data.to_string()),
+            url: None,
+        }
+    }
+
+    #[test]
+    fn test_to_kiro_image_sniffs_format() {
+        // 声明为 jpeg 的 PNG 数据按实际格式发送
+        let image = to_kiro_image(&base64_source("image/jpeg", PNG_1X1)).unwrap();
+        assert_eq!(image.format, "png");
+        assert_eq!(image.source.bytes, PNG_1X1);
+    }
+
+    #[test]
+    fn test_to_kiro_image_rejects_invalid_input() {
+        let err = to_kiro_image(&base64_source("image/bmp", PNG_1X1)).unwrap_err();
+        assert!(err.contains("image/bmp"));
+
+        let err = to_kiro_image(&base64_source("image/png", "bm90IGFuIGltYWdl")).unwrap_err();
+        assert!(err.contains("无法识别"));
+
+        let err = to_kiro_image(&base64_source("image/png", "%%%")).unwrap_err();
+        assert!(err.contains("base64"));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_data_url() {
+        let mut messages = vec![Message {
+            role: "user".to_string(),
+            content: serde_json::json!([{
+                "type": "image",
+                "source": { "type": "url", "url": format!("data:image/png;base64,{}", PNG_1X1) }
+            }, {
+                "type": "tool_result",
+                "tool_use_id": "t1",
+                "content": [{
+                    "type": "image",
+                    "source": { "type": "url", "url": format!("data:image/png;base64,{}", PNG_1X1) }
+                }]
+            }]),
+        }];
+        assert_eq!(resolve_remote_images(&mut messages).await.unwrap(), 2);
+        let source = &messages[0].content[0]["source"];
+        assert_eq!(source["type"], "base64");
+        assert_eq!(source["media_type"], "image/png");
+        assert_eq!(source["data"], PNG_1X1);
+        // tool_result 中嵌套的图片同样被下载
+        assert_eq!(messages[0].content[1]["content"][0]["source"]["type"], "base64");
+    }
+}
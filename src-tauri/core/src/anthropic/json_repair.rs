@@ -0,0 +1,199 @@
+//! 工具输入 JSON 修复
+//!
+//! Kiro 在输出被截断（如达到输出上限或连接中断）时，工具调用的 JSON 输入可能不完整。
+//! 直接回退为 `{}` 会让 Agent 循环拿到空参数而失败，这里按括号配对补全被截断的 JSON：
+//! 闭合未结束的字符串、补全半截的字面量和数字、为悬空的键补 `null`，再依次闭合对象和数组。
+//!
+//! 修复结果尽量只在原文末尾追加内容，流式响应中可以把追加部分作为最后一个
+//! `input_json_delta` 发给客户端。
+
+use serde_json::Value;
+
+/// 尝试修复被截断的 JSON 对象，返回完整的 JSON 文本
+///
+/// 仅处理以 `{` 开头的输入；无法修复（如括号不匹配、中间内容本身非法）时返回 None。
+pub fn repair(input: &str) -> Option<String> {
+    let trimmed = input.trim_end();
+    if !trimmed.trim_start().starts_with('{') {
+        return None;
+    }
+
+    let mut stack: Vec<u8> = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut unicode_left = 0;
+    let mut string_is_key = false;
+    // 字符串外最后一个有效字符（字符串结束时记为 `"`）
+    let mut last = b' ';
+    let mut last_string_was_key = false;
+
+    for &b in trimmed.as_bytes() {
+        if in_string {
+            if unicode_left > 0 {
+                unicode_left -= 1;
+            } else if escaped {
+                escaped = false;
+                if b == b'u' {
+                    unicode_left = 4;
+                }
+            } else if b == b'\\' {
+                escaped = true;
+            } else if b == b'"' {
+                in_string = false;
+                last = b'"';
+                last_string_was_key = string_is_key;
+            }
+            continue;
+        }
+        match b {
+            b'"' => {
+                in_string = true;
+                string_is_key = stack.last() == Some(&b'{') && matches!(last, b'{' | b',');
+                continue;
+            }
+            b'{' | b'[' => stack.push(b),
+            b'}' | b']' => {
+                let open = if b == b'}' { b'{' } else { b'[' };
+                if stack.pop() != Some(open) {
+                    return None;
+                }
+            }
+            b' ' | b'\t' | b'\r' | b'\n' => continue,
+            _ => {}
+        }
+        last = b;
+    }
+
+    if stack.is_empty() && !in_string {
+        // 结构已完整，问题不在截断
+        return None;
+    }
+
+    let mut out = trimmed.to_string();
+    if in_string {
+        if escaped {
+            out.push('\\');
+        }
+        out.extend(std::iter::repeat_n('0', unicode_left));
+        out.push('"');
+        if string_is_key {
+            out.push_str(":null");
+        }
+    } else {
+        // 末尾半截的字面量或数字
+        let token_start = out
+            .rfind(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '-')))
+            .map_or(0, |i| i + 1);
+        let token = &out[token_start..];
+        if !token.is_empty() {
+            if let Some(rest) = ["true", "false", "null"]
+                .iter()
+                .find_map(|lit| lit.strip_prefix(token))
+            {
+                out.push_str(rest);
+            } else if token.ends_with(['-', '+', '.', 'e', 'E']) {
+                out.push('0');
+            }
+        } else {
+            match last {
+                b'"' if last_string_was_key => out.push_str(":null"),
+                b':' => out.push_str("null"),
+                // 悬空的逗号无法通过追加修复，只能删除
+                b',' => {
+                    out.pop();
+                    out.truncate(out.trim_end().len());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    for open in stack.iter().rev() {
+        out.push(if *open == b'{' { '}' } else { ']' });
+    }
+
+    serde_json::from_str::<Value>(&out).is_ok().then_some(out)
+}
+
+/// 解析工具输入：空输入视为 `{}`，解析失败时尝试修复，仍失败则回退为 `{}`
+pub fn parse_tool_input(raw: &str, tool_use_id: &str) -> Value {
+    if raw.trim().is_empty() {
+        return Value::Object(Default::default());
+    }
+    match serde_json::from_str(raw) {
+        Ok(value) => value,
+        Err(e) => match repair(raw).and_then(|fixed| serde_json::from_str(&fixed).ok()) {
+            Some(value) => {
+                tracing::warn!(
+                    "工具输入 JSON 不完整，已自动修复: {}, tool_use_id: {}, 原始长度: {}",
+                    e,
+                    tool_use_id,
+                    raw.len()
+                );
+                value
+            }
+            None => {
+                tracing::warn!(
+                    "工具输入 JSON 解析失败: {}, tool_use_id: {}, 原始内容: {}",
+                    e,
+                    tool_use_id,
+                    raw
+                );
+                Value::Object(Default::default())
+            }
+        },
+    }
+}
+
+/// 计算流式修复需要追加的内容（原文合法或无法仅靠追加修复时返回 None）
+pub fn repair_suffix(input: &str) -> Option<String> {
+    if input.trim().is_empty() || serde_json::from_str::<Value>(input).is_ok() {
+        return None;
+    }
+    let fixed = repair(input)?;
+    // 修复结果基于去掉尾部空白的原文
+    fixed
+        .strip_prefix(input.trim_end())
+        .filter(|suffix| !suffix.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fixed(input: &str) -> Value {
+        serde_json::from_str(&repair(input).expect("should repair")).unwrap()
+    }
+
+    #[test]
+    fn test_repair_truncated_inputs() {
+        assert_eq!(fixed(r#"{"path": "/tmp/a.txt", "content": "hel"#), json!({"path": "/tmp/a.txt", "content": "hel"}));
+        assert_eq!(fixed(r#"{"a": [1, 2, {"b": tr"#), json!({"a": [1, 2, {"b": true}]}));
+        assert_eq!(fixed(r#"{"a": 1, "b""#), json!({"a": 1, "b": null}));
+        assert_eq!(fixed(r#"{"a": 1, "b":"#), json!({"a": 1, "b": null}));
+        assert_eq!(fixed(r#"{"a": 1,"#), json!({"a": 1}));
+        assert_eq!(fixed(r#"{"a": -1."#), json!({"a": -1.0}));
+        assert_eq!(fixed(r#"{"a": "x\"#), json!({"a": "x\\"}));
+        assert_eq!(fixed(r#"{"a": "\u00"#), json!({"a": "\u{0}"}));
+
+        // 完整或结构错误的输入不处理
+        assert!(repair(r#"{"a": 1}"#).is_none());
+        assert!(repair(r#"{"a": 1]"#).is_none());
+        assert!(repair("not json").is_none());
+    }
+
+    #[test]
+    fn test_parse_tool_input_and_suffix() {
+        assert_eq!(parse_tool_input("", "t"), json!({}));
+        assert_eq!(parse_tool_input(r#"{"cmd": "ls"}"#, "t"), json!({"cmd": "ls"}));
+        assert_eq!(parse_tool_input(r#"{"cmd": "ls -"#, "t"), json!({"cmd": "ls -"}));
+        assert_eq!(parse_tool_input("garbage", "t"), json!({}));
+
+        assert_eq!(repair_suffix(r#"{"cmd": ["ls""#).as_deref(), Some("]}"));
+        assert!(repair_suffix(r#"{"cmd": "ls"}"#).is_none());
+        // 悬空逗号需要删除，无法仅靠追加修复
+        assert!(repair_suffix(r#"{"a": 1,"#).is_none());
+    }
+}
@@ -0,0 +1,209 @@
+//! 模型能力与 max_tokens / 上下文窗口校验
+//!
+//! 在调用 Kiro 之前按模型能力表校验 `max_tokens` 和估算的输入大小，超限时返回明确的
+//! `invalid_request_error`，而不是把请求发给上游再得到含糊的 400。
+//! 开启 `autoClampMaxTokens` 时，`max_tokens` 超出模型输出上限或剩余上下文时自动压低。
+//! 另外按 `requestLimits` 校验消息数和工具数，避免异常请求在估算 tokens 和转换时耗尽资源。
+
+use super::converter::map_model;
+use super::types::MessagesRequest;
+use crate::error_code::ErrorCode;
+use crate::model::config::RequestLimitsConfig;
+
+/// 模型能力
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModelCapability {
+    /// 上下文窗口（输入 + 输出 tokens）
+    pub context_window: i32,
+    /// 单次最大输出 tokens
+    pub max_output_tokens: i32,
+}
+
+/// 按 Kiro 模型查找能力（不支持的模型返回 None，由转换阶段报错）
+pub fn model_capability(model: &str) -> Option<ModelCapability> {
+    let capability = match map_model(model)?.as_str() {
+        "claude-sonnet-4.5" | "claude-opus-4.5" | "claude-haiku-4.5" => ModelCapability {
+            context_window: 200_000,
+            max_output_tokens: 64_000,
+        },
+        _ => return None,
+    };
+    Some(capability)
+}
+
+/// 超出模型限制
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LimitError {
+    /// max_tokens 超出模型最大输出
+    MaxTokensTooLarge { max_tokens: i32, limit: i32, model: String },
+    /// 输入本身已超出上下文窗口
+    PromptTooLong { input_tokens: i32, context_window: i32 },
+    /// 消息数超出网关上限
+    TooManyMessages { count: usize, limit: usize },
+    /// 工具数超出网关上限
+    TooManyTools { count: usize, limit: usize },
+}
+
+impl LimitError {
+    /// 对应的错误码
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            LimitError::MaxTokensTooLarge { .. } => ErrorCode::MaxTokensExceeded,
+            LimitError::PromptTooLong { .. } => ErrorCode::PromptTooLong,
+            LimitError::TooManyMessages { .. } | LimitError::TooManyTools { .. } => {
+                ErrorCode::RequestLimitExceeded
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for LimitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LimitError::MaxTokensTooLarge { max_tokens, limit, model } => write!(
+                f,
+                "max_tokens: {} > {}, which is the maximum allowed number of output tokens for {}. \
+                 Lower max_tokens or enable autoClampMaxTokens on the gateway.",
+                max_tokens, limit, model
+            ),
+            LimitError::PromptTooLong { input_tokens, context_window } => write!(
+                f,
+                "prompt is too long: about {} tokens > {} maximum. \
+                 Remove earlier messages or large attachments and retry.",
+                input_tokens, context_window
+            ),
+            LimitError::TooManyMessages { count, limit } => write!(
+                f,
+                "messages: {} messages exceeds the maximum of {} allowed by the gateway.",
+                count, limit
+            ),
+            LimitError::TooManyTools { count, limit } => write!(
+                f,
+                "tools: {} tools exceeds the maximum of {} allowed by the gateway.",
+                count, limit
+            ),
+        }
+    }
+}
+
+/// 校验请求，必要时压低 max_tokens（返回压低前的值）
+///
+/// - `max_tokens` 超出模型最大输出：开启自动压低时压到上限，否则报错
+/// - 估算输入超出上下文窗口：始终报错
+/// - 输入 + `max_tokens` 超出上下文窗口：开启自动压低时压到剩余空间，否则放行
+///   （输入为估算值，且 Kiro 不按 max_tokens 限制输出，避免误拒）
+pub fn enforce(
+    request: &mut MessagesRequest,
+    input_tokens: i32,
+    auto_clamp: bool,
+) -> Result<Option<i32>, LimitError> {
+    let Some(capability) = model_capability(&request.model) else {
+        return Ok(None);
+    };
+
+    if input_tokens >= capability.context_window {
+        return Err(LimitError::PromptTooLong {
+            input_tokens,
+            context_window: capability.context_window,
+        });
+    }
+
+    let original = request.max_tokens;
+    if request.max_tokens > capability.max_output_tokens {
+        if !auto_clamp {
+            return Err(LimitError::MaxTokensTooLarge {
+                max_tokens: request.max_tokens,
+                limit: capability.max_output_tokens,
+                model: request.model.clone(),
+            });
+        }
+        request.max_tokens = capability.max_output_tokens;
+    }
+    if auto_clamp {
+        let remaining = capability.context_window - input_tokens;
+        request.max_tokens = request.max_tokens.min(remaining);
+    }
+
+    Ok((request.max_tokens != original).then_some(original))
+}
+
+/// 校验消息数和工具数（上限为 0 表示不限制）
+pub fn check_counts(request: &MessagesRequest, limits: &RequestLimitsConfig) -> Result<(), LimitError> {
+    let messages = request.messages.len();
+    if limits.max_messages > 0 && messages > limits.max_messages {
+        return Err(LimitError::TooManyMessages {
+            count: messages,
+            limit: limits.max_messages,
+        });
+    }
+    let tools = request.tools.as_ref().map_or(0, Vec::len);
+    if limits.max_tools > 0 && tools > limits.max_tools {
+        return Err(LimitError::TooManyTools {
+            count: tools,
+            limit: limits.max_tools,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(max_tokens: i32) -> MessagesRequest {
+        serde_json::from_value(serde_json::json!({
+            "model": "claude-sonnet-4-5-20250929",
+            "max_tokens": max_tokens,
+            "messages": [{ "role": "user", "content": "Hello" }]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_max_tokens_rejected_or_clamped() {
+        let mut req = request(100_000);
+        assert!(matches!(
+            enforce(&mut req, 1_000, false),
+            Err(LimitError::MaxTokensTooLarge { limit: 64_000, .. })
+        ));
+        assert_eq!(req.max_tokens, 100_000);
+
+        assert_eq!(enforce(&mut req, 1_000, true), Ok(Some(100_000)));
+        assert_eq!(req.max_tokens, 64_000);
+
+        // 剩余上下文不足时压到剩余空间，未开启时放行
+        let mut req = request(32_000);
+        assert_eq!(enforce(&mut req, 180_000, false), Ok(None));
+        assert_eq!(enforce(&mut req, 180_000, true), Ok(Some(32_000)));
+        assert_eq!(req.max_tokens, 20_000);
+    }
+
+    #[test]
+    fn test_prompt_too_long_always_rejected() {
+        let mut req = request(1_024);
+        assert!(matches!(
+            enforce(&mut req, 250_000, true),
+            Err(LimitError::PromptTooLong { context_window: 200_000, .. })
+        ));
+
+        let mut unknown = request(1_000_000);
+        unknown.model = "gpt-4o".to_string();
+        assert_eq!(enforce(&mut unknown, 1_000_000, false), Ok(None));
+    }
+
+    #[test]
+    fn test_message_and_tool_counts() {
+        let limits = RequestLimitsConfig {
+            max_messages: 1,
+            max_tools: 0,
+            ..Default::default()
+        };
+        let mut req = request(1_024);
+        assert_eq!(check_counts(&req, &limits), Ok(()));
+
+        req.messages.push(req.messages[0].clone());
+        let err = check_counts(&req, &limits).unwrap_err();
+        assert_eq!(err, LimitError::TooManyMessages { count: 2, limit: 1 });
+        assert_eq!(err.code(), ErrorCode::RequestLimitExceeded);
+    }
+}
@@ -0,0 +1,123 @@
+//! Anthropic API 中间件
+
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{Request, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+
+use crate::api_keys::{API_KEY_REGISTRY, Rejection};
+use crate::common::auth;
+use crate::error_code::ErrorCode;
+use crate::kiro::provider::KiroProvider;
+use crate::proxy_lifecycle::ProxyLifecycle;
+
+use super::types::ErrorResponse;
+
+/// 应用共享状态
+#[derive(Clone)]
+pub struct AppState {
+    /// API 密钥
+    pub api_key: String,
+    /// Kiro Provider（可选，用于实际 API 调用）
+    /// 内部使用 MultiTokenManager，已支持线程安全的多凭证管理
+    pub kiro_provider: Option<Arc<KiroProvider>>,
+    /// Profile ARN（可选，用于请求）
+    pub profile_arn: Option<String>,
+    /// 反代服务生命周期
+    pub proxy: ProxyLifecycle,
+}
+
+impl AppState {
+    /// 创建新的应用状态
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            kiro_provider: None,
+            profile_arn: None,
+            proxy: ProxyLifecycle::shared_listener(),
+        }
+    }
+
+    /// 设置 KiroProvider
+    pub fn with_kiro_provider(mut self, provider: KiroProvider) -> Self {
+        self.kiro_provider = Some(Arc::new(provider));
+        self
+    }
+
+    /// 设置 Profile ARN
+    pub fn with_profile_arn(mut self, arn: impl Into<String>) -> Self {
+        self.profile_arn = Some(arn.into());
+        self
+    }
+    
+    /// 设置反代服务生命周期
+    pub fn with_proxy_lifecycle(mut self, proxy: ProxyLifecycle) -> Self {
+        self.proxy = proxy;
+        self
+    }
+}
+
+/// API Key 认证中间件
+pub async fn auth_middleware(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    // 首先检查反代服务是否接收新请求（Draining 期间拒绝）
+    if !state.proxy.accepts_requests() {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse::new(
+                "service_unavailable".to_string(),
+                "Proxy service is currently disabled".to_string(),
+            ).with_code(ErrorCode::ProxyDisabled))
+        ).into_response();
+    }
+    
+    let Some(key) = auth::extract_api_key(&request) else {
+        let error = ErrorResponse::authentication_error();
+        return (StatusCode::UNAUTHORIZED, Json(error)).into_response();
+    };
+
+    // 主密钥不受租户限制
+    if auth::constant_time_eq(&key, &state.api_key) {
+        return next.run(request).await;
+    }
+
+    // 租户 Key：准入检查后放入 extensions
+    let Some(tenant) = API_KEY_REGISTRY.resolve(&key) else {
+        let error = ErrorResponse::authentication_error();
+        return (StatusCode::UNAUTHORIZED, Json(error)).into_response();
+    };
+
+    match API_KEY_REGISTRY.admit(&tenant.id) {
+        Ok(()) => {
+            let mut request = request;
+            request.extensions_mut().insert(tenant);
+            next.run(request).await
+        }
+        Err(Rejection::RateLimited { retry_after_secs }) => (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, retry_after_secs.to_string())],
+            Json(ErrorResponse::new(
+                "rate_limit_error",
+                format!("API key '{}' exceeded its request rate limit", tenant.name),
+            )),
+        )
+            .into_response(),
+        Err(Rejection::QuotaExceeded) => (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(ErrorResponse::new(
+                "rate_limit_error",
+                format!("API key '{}' exceeded its monthly token quota", tenant.name),
+            )
+            .with_code(ErrorCode::QuotaExceeded)),
+        )
+            .into_response(),
+    }
+}
@@ -6,22 +6,33 @@
 //! - `GET /v1/models` - 获取可用模型列表
 //! - `POST /v1/messages` - 创建消息（对话）
 //! - `POST /v1/messages/count_tokens` - 计算 token 数量
+//! - `/v1/messages/batches` - Messages Batches API（见 `batches` 模块）
 //!
 //! # 使用示例
 //! ```rust,ignore
-//! use kiro_rs::anthropic;
+//! use kiro_gateway_core::anthropic;
 //!
 //! let app = anthropic::create_router("your-api-key");
 //! let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await?;
 //! axum::serve(listener, app).await?;
 //! ```
 
-mod converter;
+pub(crate) mod batches;
+pub(crate) mod compaction;
+pub(crate) mod converter;
 mod handlers;
-mod middleware;
+pub(crate) mod images;
+pub(crate) mod json_repair;
+pub(crate) mod limits;
+pub(crate) mod middleware;
+mod openai_stream;
+pub(crate) mod resume;
 mod router;
-mod stream;
+pub(crate) mod stream;
+pub(crate) mod tool_choice;
+pub(crate) mod tool_result;
 pub mod types;
+pub(crate) mod version;
 mod websearch;
 
 pub use router::create_router_with_provider;
@@ -0,0 +1,300 @@
+//! OpenAI 流式格式适配
+//!
+//! `POST /v1/messages?format=openai` 的流式响应由 Anthropic SSE 事件转换为 OpenAI
+//! `chat.completion.chunk`，供 OpenAI SDK 客户端直接消费：文本为 `delta.content`，
+//! thinking 为 `delta.reasoning_content`，工具调用为 `delta.tool_calls`（首个片段带 id 和函数名，
+//! 之后只追加 `arguments`），结束时发送 `finish_reason`、用量片段和 `data: [DONE]`。
+//! 转换在响应体上进行，对话记录、断线续传缓冲等仍按 Anthropic 格式处理。
+
+use std::collections::HashMap;
+
+use axum::body::Body;
+use axum::response::Response;
+use bytes::Bytes;
+use futures::{StreamExt, stream};
+use serde_json::{Value, json};
+
+/// `format` 查询参数取值
+pub const FORMAT_OPENAI: &str = "openai";
+
+/// Anthropic stop_reason -> OpenAI finish_reason
+fn finish_reason(stop_reason: &str) -> &'static str {
+    match stop_reason {
+        "max_tokens" => "length",
+        "tool_use" => "tool_calls",
+        _ => "stop",
+    }
+}
+
+/// Anthropic SSE 事件到 OpenAI chunk 的转换状态
+pub struct ChunkMapper {
+    id: String,
+    model: String,
+    created: i64,
+    /// 内容块索引 -> tool_calls 索引
+    tool_calls: HashMap<u64, usize>,
+    /// 未结束的 SSE 行（跨网络分片）
+    pending: String,
+    /// 当前事件的 `event:` / `id:` 行
+    event: Option<String>,
+    event_id: Option<String>,
+    done: bool,
+}
+
+impl ChunkMapper {
+    pub fn new() -> Self {
+        Self {
+            id: String::new(),
+            model: String::new(),
+            created: chrono::Utc::now().timestamp(),
+            tool_calls: HashMap::new(),
+            pending: String::new(),
+            event: None,
+            event_id: None,
+            done: false,
+        }
+    }
+
+    /// 输入一段 SSE 字节，输出转换后的 SSE 字节（不完整的行留待下一段）
+    pub fn push(&mut self, bytes: &[u8]) -> String {
+        self.pending.push_str(&String::from_utf8_lossy(bytes));
+        let mut out = String::new();
+        while let Some(pos) = self.pending.find('\n') {
+            let line: String = self.pending.drain(..=pos).collect();
+            self.line(line.trim_end_matches(['\r', '\n']), &mut out);
+        }
+        out
+    }
+
+    /// 流结束：上游未发送 message_stop 时补发 `[DONE]`
+    pub fn finish(&mut self) -> String {
+        let mut out = self.push(b"\n");
+        if !self.done {
+            self.done = true;
+            out.push_str("data: [DONE]\n\n");
+        }
+        out
+    }
+
+    fn line(&mut self, line: &str, out: &mut String) {
+        if let Some(event) = line.strip_prefix("event:") {
+            self.event = Some(event.trim().to_string());
+        } else if let Some(id) = line.strip_prefix("id:") {
+            self.event_id = Some(id.trim().to_string());
+        } else if let Some(data) = line.strip_prefix("data:") {
+            let event = self.event.take();
+            let event_id = self.event_id.take();
+            let Ok(data) = serde_json::from_str::<Value>(data.trim()) else {
+                return;
+            };
+            let kind = event.unwrap_or_else(|| data["type"].as_str().unwrap_or_default().to_string());
+            for payload in self.map_event(&kind, &data) {
+                if let Some(id) = &event_id {
+                    out.push_str(&format!("id: {}\n", id));
+                }
+                out.push_str(&format!("data: {}\n\n", payload));
+            }
+        } else if line.starts_with(':') {
+            // SSE 注释（故障转移追踪等）原样保留
+            out.push_str(line);
+            out.push_str("\n\n");
+        }
+    }
+
+    /// 转换单个事件（返回每个 `data:` 行的内容）
+    fn map_event(&mut self, kind: &str, data: &Value) -> Vec<String> {
+        let index = data["index"].as_u64().unwrap_or_default();
+        match kind {
+            "message_start" => {
+                let message = &data["message"];
+                self.id = message["id"].as_str().unwrap_or_default().to_string();
+                self.model = message["model"].as_str().unwrap_or_default().to_string();
+                vec![self.chunk(json!({ "role": "assistant", "content": "" }), None)]
+            }
+            "content_block_start" => {
+                let block = &data["content_block"];
+                if block["type"] != "tool_use" {
+                    return Vec::new();
+                }
+                let tool_index = self.tool_calls.len();
+                self.tool_calls.insert(index, tool_index);
+                vec![self.chunk(
+                    json!({ "tool_calls": [{
+                        "index": tool_index,
+                        "id": block["id"],
+                        "type": "function",
+                        "function": { "name": block["name"], "arguments": "" },
+                    }]}),
+                    None,
+                )]
+            }
+            "content_block_delta" => {
+                let delta = &data["delta"];
+                let delta = match delta["type"].as_str() {
+                    Some("text_delta") => json!({ "content": delta["text"] }),
+                    Some("thinking_delta") => json!({ "reasoning_content": delta["thinking"] }),
+                    Some("input_json_delta") => {
+                        let Some(tool_index) = self.tool_calls.get(&index) else {
+                            return Vec::new();
+                        };
+                        json!({ "tool_calls": [{
+                            "index": tool_index,
+                            "function": { "arguments": delta["partial_json"] },
+                        }]})
+                    }
+                    _ => return Vec::new(),
+                };
+                vec![self.chunk(delta, None)]
+            }
+            "message_delta" => {
+                let reason = finish_reason(data["delta"]["stop_reason"].as_str().unwrap_or_default());
+                let mut chunks = vec![self.chunk(json!({}), Some(reason))];
+                let usage = &data["usage"];
+                if usage.is_object() {
+                    let prompt = usage["input_tokens"].as_i64().unwrap_or_default();
+                    let completion = usage["output_tokens"].as_i64().unwrap_or_default();
+                    let mut chunk = self.base();
+                    chunk["choices"] = json!([]);
+                    chunk["usage"] = json!({
+                        "prompt_tokens": prompt,
+                        "completion_tokens": completion,
+                        "total_tokens": prompt + completion,
+                    });
+                    chunks.push(chunk.to_string());
+                }
+                chunks
+            }
+            "message_stop" => {
+                self.done = true;
+                vec!["[DONE]".to_string()]
+            }
+            "error" => vec![json!({ "error": {
+                "message": data["error"]["message"],
+                "type": data["error"]["type"],
+                "code": data["error"]["code"],
+            }})
+            .to_string()],
+            _ => Vec::new(),
+        }
+    }
+
+    fn base(&self) -> Value {
+        json!({
+            "id": self.id,
+            "object": "chat.completion.chunk",
+            "created": self.created,
+            "model": self.model,
+        })
+    }
+
+    fn chunk(&self, delta: Value, finish_reason: Option<&str>) -> String {
+        let mut chunk = self.base();
+        chunk["choices"] = json!([{ "index": 0, "delta": delta, "finish_reason": finish_reason }]);
+        chunk.to_string()
+    }
+}
+
+impl Default for ChunkMapper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 将 Anthropic SSE 响应转换为 OpenAI chunk 流（非 SSE 或失败的响应原样返回）
+pub fn adapt(response: Response) -> Response {
+    let is_sse = response
+        .headers()
+        .get(axum::http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream"));
+    if !response.status().is_success() || !is_sse {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let mapper = std::sync::Arc::new(parking_lot::Mutex::new(ChunkMapper::new()));
+    let tail = mapper.clone();
+    let data = body.into_data_stream().filter_map(move |chunk| {
+        let out = match chunk {
+            Ok(bytes) => Some(Ok(Bytes::from(mapper.lock().push(&bytes)))),
+            Err(e) => Some(Err(e)),
+        };
+        std::future::ready(out.filter(|r| r.as_ref().map_or(true, |b| !b.is_empty())))
+    });
+    let finish = stream::once(async move {
+        let out = tail.lock().finish();
+        (!out.is_empty()).then(|| Ok::<_, axum::Error>(Bytes::from(out)))
+    })
+    .filter_map(std::future::ready);
+    Response::from_parts(parts, Body::from_stream(data.chain(finish)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EVENTS: &str = concat!(
+        "event: message_start\ndata: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_1\",\"model\":\"claude-sonnet-4\"}}\n\n",
+        "event: content_block_start\ndata: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n",
+        "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hi\"}}\n\n",
+        "event: ping\ndata: {\"type\": \"ping\"}\n\n",
+        "event: content_block_start\ndata: {\"type\":\"content_block_start\",\"index\":1,\"content_block\":{\"type\":\"tool_use\",\"id\":\"toolu_1\",\"name\":\"Read\",\"input\":{}}}\n\n",
+        "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"{\\\"path\\\":\"}}\n\n",
+        "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"\\\"a\\\"}\"}}\n\n",
+        "event: message_delta\ndata: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"tool_use\"},\"usage\":{\"input_tokens\":10,\"output_tokens\":5}}\n\n",
+        "event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n",
+    );
+
+    fn chunks(sse: &str) -> Vec<Value> {
+        sse.lines()
+            .filter_map(|line| line.strip_prefix("data: "))
+            .filter(|data| *data != "[DONE]")
+            .map(|data| serde_json::from_str(data).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_maps_text_and_tool_calls() {
+        let mut mapper = ChunkMapper::new();
+        // 按任意位置切分，验证跨分片的行能正确拼接
+        let (a, b) = EVENTS.split_at(150);
+        let out = mapper.push(a.as_bytes()) + &mapper.push(b.as_bytes()) + &mapper.finish();
+        assert!(out.ends_with("data: [DONE]\n\n"));
+        assert_eq!(out.matches("[DONE]").count(), 1);
+
+        let chunks = chunks(&out);
+        assert_eq!(chunks.len(), 7);
+        assert!(chunks.iter().all(|c| c["id"] == "msg_1" && c["model"] == "claude-sonnet-4"));
+        assert_eq!(chunks[0]["object"], "chat.completion.chunk");
+        assert_eq!(chunks[0]["choices"][0]["delta"]["role"], "assistant");
+        assert_eq!(chunks[1]["choices"][0]["delta"]["content"], "Hi");
+
+        let call = &chunks[2]["choices"][0]["delta"]["tool_calls"][0];
+        assert_eq!(call["index"], 0);
+        assert_eq!(call["id"], "toolu_1");
+        assert_eq!(call["function"]["name"], "Read");
+        let arguments: String = chunks[3..5]
+            .iter()
+            .map(|c| c["choices"][0]["delta"]["tool_calls"][0]["function"]["arguments"].as_str().unwrap())
+            .collect();
+        assert_eq!(arguments, "{\"path\":\"a\"}");
+
+        assert_eq!(chunks[5]["choices"][0]["finish_reason"], "tool_calls");
+        assert_eq!(chunks[6]["choices"], json!([]));
+        assert_eq!(chunks[6]["usage"]["total_tokens"], 15);
+    }
+
+    #[test]
+    fn test_errors_ids_and_missing_stop() {
+        let mut mapper = ChunkMapper::new();
+        let out = mapper.push(b": trace\n\nid: s1-0\nevent: error\ndata: {\"type\":\"error\",\"error\":{\"type\":\"overloaded_error\",\"message\":\"busy\"}}\n\n");
+        assert!(out.starts_with(": trace\n\nid: s1-0\ndata: {\"error\""));
+        assert_eq!(chunks(&out)[0]["error"]["type"], "overloaded_error");
+
+        // 上游未发送 message_stop 时在结束时补发 [DONE]
+        assert_eq!(mapper.finish(), "data: [DONE]\n\n");
+        assert_eq!(mapper.finish(), "");
+        assert_eq!(finish_reason("max_tokens"), "length");
+        assert_eq!(finish_reason("end_turn"), "stop");
+    }
+}
@@ -0,0 +1,213 @@
+//! 流式响应断线续传
+//!
+//! 启用后（`streamResumeSecs > 0`），每个流式事件带有 `{message_id}:{序号}` 形式的递增 ID，
+//! 并写入该流的重放缓冲。上游流由后台任务独立驱动到结束，客户端连接断开不会中断生成；
+//! 客户端在流结束后的窗口期内携带 `Last-Event-ID` 重新请求 `/v1/messages`，
+//! 即可从断点之后继续接收事件。等待新事件期间照常发送 ping 保活。
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use futures::{Stream, stream};
+use lazy_static::lazy_static;
+use parking_lot::{Mutex, RwLock};
+use tokio::sync::Notify;
+
+use super::stream::SseEvent;
+
+/// 单个流的重放缓冲
+pub struct ReplayStream {
+    id: String,
+    api_key_id: Option<String>,
+    /// 已编码的事件（第 n 个事件的 ID 序号为 n + 1）
+    events: Mutex<Vec<Bytes>>,
+    finished: AtomicBool,
+    finished_at: Mutex<Option<Instant>>,
+    notify: Notify,
+}
+
+impl ReplayStream {
+    /// 为事件分配下一个 ID，写入缓冲并返回编码后的字节
+    pub fn push(&self, event: SseEvent) -> Bytes {
+        let mut events = self.events.lock();
+        let event = event.with_id(format!("{}:{}", self.id, events.len() + 1));
+        let bytes = Bytes::from(event.to_sse_string());
+        events.push(bytes.clone());
+        drop(events);
+        self.notify.notify_waiters();
+        bytes
+    }
+
+    /// 标记流已结束（之后不会再有新事件）
+    pub fn finish(&self) {
+        self.finished.store(true, Ordering::SeqCst);
+        *self.finished_at.lock() = Some(Instant::now());
+        self.notify.notify_waiters();
+    }
+
+    /// 返回序号 `after` 之后的事件，以及流是否已结束
+    fn events_after(&self, after: usize) -> (Vec<Bytes>, bool) {
+        // 先读结束标志，保证返回结束时不会漏掉最后写入的事件
+        let finished = self.finished.load(Ordering::SeqCst);
+        let events = self.events.lock();
+        (events.get(after..).map(<[Bytes]>::to_vec).unwrap_or_default(), finished)
+    }
+
+    fn len(&self) -> usize {
+        self.events.lock().len()
+    }
+
+    fn expired(&self, window: Duration) -> bool {
+        self.finished_at.lock().is_some_and(|at| at.elapsed() > window)
+    }
+}
+
+/// 解析事件 ID，返回 (流 ID, 序号)
+pub fn parse_event_id(id: &str) -> Option<(&str, usize)> {
+    let (stream_id, seq) = id.trim().rsplit_once(':')?;
+    Some((stream_id, seq.parse().ok()?))
+}
+
+/// 续传缓冲注册表
+pub struct ReplayRegistry {
+    streams: RwLock<HashMap<String, Arc<ReplayStream>>>,
+    window_secs: AtomicU64,
+}
+
+impl ReplayRegistry {
+    pub fn new() -> Self {
+        Self {
+            streams: RwLock::new(HashMap::new()),
+            window_secs: AtomicU64::new(0),
+        }
+    }
+
+    /// 设置续传窗口（秒，0 表示关闭）
+    pub fn set_window(&self, secs: u64) {
+        self.window_secs.store(secs, Ordering::Relaxed);
+        if secs == 0 {
+            self.streams.write().retain(|_, stream| !stream.finished.load(Ordering::SeqCst));
+        }
+    }
+
+    fn window(&self) -> Option<Duration> {
+        match self.window_secs.load(Ordering::Relaxed) {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+
+    /// 为新的流式响应创建缓冲（未启用时返回 None），顺带清理过期的缓冲
+    pub fn start(&self, message_id: &str, api_key_id: Option<String>) -> Option<Arc<ReplayStream>> {
+        let window = self.window()?;
+        let stream = Arc::new(ReplayStream {
+            id: message_id.to_string(),
+            api_key_id,
+            events: Mutex::new(Vec::new()),
+            finished: AtomicBool::new(false),
+            finished_at: Mutex::new(None),
+            notify: Notify::new(),
+        });
+        let mut streams = self.streams.write();
+        streams.retain(|_, s| !s.expired(window));
+        streams.insert(message_id.to_string(), stream.clone());
+        Some(stream)
+    }
+
+    /// 按 `Last-Event-ID` 查找可续传的流（须为同一 API Key 发起，且未过期）
+    pub fn find(&self, last_event_id: &str, api_key_id: Option<&str>) -> Option<(Arc<ReplayStream>, usize)> {
+        let window = self.window()?;
+        let (stream_id, seq) = parse_event_id(last_event_id)?;
+        let stream = self.streams.read().get(stream_id)?.clone();
+        if stream.api_key_id.as_deref() != api_key_id || stream.expired(window) || seq > stream.len() {
+            return None;
+        }
+        Some((stream, seq))
+    }
+}
+
+/// 从序号 `after` 之后重放事件，并持续跟随直到流结束
+pub fn follow(
+    replay: Arc<ReplayStream>,
+    after: usize,
+    ping: Bytes,
+    ping_interval: Duration,
+) -> impl Stream<Item = Result<Bytes, Infallible>> {
+    use futures::StreamExt;
+
+    stream::unfold(
+        (replay, after, tokio::time::interval(ping_interval)),
+        move |(replay, mut after, mut ticker)| {
+            let ping = ping.clone();
+            async move {
+                let items = loop {
+                    // 先登记等待，再读取缓冲，避免错过两者之间写入的事件
+                    let notified = replay.notify.notified();
+                    let (events, finished) = replay.events_after(after);
+                    if !events.is_empty() {
+                        after += events.len();
+                        break events.into_iter().map(Ok).collect::<Vec<Result<Bytes, Infallible>>>();
+                    }
+                    if finished {
+                        return None;
+                    }
+                    tokio::select! {
+                        _ = notified => continue,
+                        _ = ticker.tick() => break vec![Ok(ping)],
+                    }
+                };
+                Some((stream::iter(items), (replay, after, ticker)))
+            }
+        },
+    )
+    .flatten()
+}
+
+// 全局续传缓冲
+lazy_static! {
+    pub static ref STREAM_REPLAY: ReplayRegistry = ReplayRegistry::new();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_resume_after_last_event_id() {
+        let registry = ReplayRegistry::new();
+        assert!(registry.start("msg_off", None).is_none());
+
+        registry.set_window(60);
+        let replay = registry.start("msg_1", Some("key-a".to_string())).unwrap();
+        for i in 0..3 {
+            let bytes = replay.push(SseEvent::new("ping", json!({ "n": i })));
+            assert!(String::from_utf8_lossy(&bytes).starts_with(&format!("id: msg_1:{}\n", i + 1)));
+        }
+
+        // 其他 API Key 或未知 ID 不能续传
+        assert!(registry.find("msg_1:1", Some("key-b")).is_none());
+        assert!(registry.find("msg_2:1", Some("key-a")).is_none());
+        assert!(registry.find("msg_1:9", Some("key-a")).is_none());
+
+        let (found, seq) = registry.find("msg_1:1", Some("key-a")).unwrap();
+        assert_eq!(seq, 1);
+        let follower = tokio::spawn(
+            follow(found, seq, Bytes::from_static(b""), Duration::from_secs(60))
+                .map(|chunk| String::from_utf8_lossy(&chunk.unwrap()).to_string())
+                .collect::<Vec<_>>(),
+        );
+
+        replay.push(SseEvent::new("message_stop", json!({})));
+        replay.finish();
+
+        let chunks: Vec<String> = follower.await.unwrap().into_iter().filter(|c| !c.is_empty()).collect();
+        let ids: Vec<&str> = chunks.iter().map(|c| c.lines().next().unwrap()).collect();
+        assert_eq!(ids, vec!["id: msg_1:2", "id: msg_1:3", "id: msg_1:4"]);
+    }
+}
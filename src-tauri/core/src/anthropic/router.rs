@@ -0,0 +1,142 @@
+//! Anthropic API 路由配置
+
+use axum::{
+    Router, middleware,
+    routing::{get, post},
+};
+
+use super::batches;
+
+use crate::kiro::provider::KiroProvider;
+use crate::proxy_lifecycle::ProxyLifecycle;
+
+use super::{
+    handlers::{count_tokens, get_model, get_models, post_messages},
+    middleware::{AppState, auth_middleware},
+};
+
+/// 创建 Anthropic API 路由
+///
+/// # 端点
+/// - `GET /v1/models` - 获取可用模型列表（`limit` / `after_id` / `before_id` 分页）
+/// - `GET /v1/models/{id}` - 获取单个模型
+/// - `POST /v1/messages` - 创建消息（对话）
+/// - `POST /v1/messages/count_tokens` - 计算 token 数量
+/// - `POST /v1/messages/batches` - 创建批次（`GET` 列表）
+/// - `GET /v1/messages/batches/{id}` - 批次状态（`DELETE` 删除已结束的批次）
+/// - `GET /v1/messages/batches/{id}/results` - 批次结果（JSON Lines）
+/// - `POST /v1/messages/batches/{id}/cancel` - 取消批次
+/// - `POST /v1beta/models/{model}:generateContent` - Gemini 兼容（含 `:streamGenerateContent`）
+///
+/// # 认证
+/// 所有 `/v1`、`/v1beta` 路径需要 API Key 认证，支持：
+/// - `x-api-key` header
+/// - `Authorization: Bearer <token>` header
+/// - `x-goog-api-key` header 或 `?key=` 查询参数（Gemini 客户端）
+///
+/// # CORS
+/// 路由本身不带 CORS 层，由服务端按配置的 `cors` 统一添加（见 `kiro_server`）
+///
+/// # 参数
+/// - `api_key`: API 密钥，用于验证客户端请求
+/// - `kiro_provider`: 可选的 KiroProvider，用于调用上游 API
+
+/// 创建带有 KiroProvider 的 Anthropic API 路由
+pub fn create_router_with_provider(
+    api_key: impl Into<String>,
+    kiro_provider: Option<KiroProvider>,
+    profile_arn: Option<String>,
+) -> Router {
+    let mut state = AppState::new(api_key);
+    if let Some(provider) = kiro_provider {
+        state = state.with_kiro_provider(provider);
+    }
+    if let Some(arn) = profile_arn {
+        state = state.with_profile_arn(arn);
+    }
+
+    // 需要认证的 /v1 路由
+    let v1_routes = Router::new()
+        .route("/models", get(get_models))
+        .route("/models/{id}", get(get_model))
+        .route("/messages", post(post_messages))
+        .route("/messages/count_tokens", post(count_tokens))
+        .route(
+            "/messages/batches",
+            post(batches::create_batch).get(batches::list_batches),
+        )
+        .route(
+            "/messages/batches/{id}",
+            get(batches::get_batch).delete(batches::delete_batch),
+        )
+        .route("/messages/batches/{id}/results", get(batches::get_batch_results))
+        .route("/messages/batches/{id}/cancel", post(batches::cancel_batch))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth_middleware,
+        ));
+
+    // Gemini 兼容路由，与 /v1 共用认证
+    let v1beta_routes = crate::gemini::create_router().layer(middleware::from_fn_with_state(
+        state.clone(),
+        auth_middleware,
+    ));
+
+    batches::resume_pending(&state);
+
+    Router::new()
+        .nest("/v1", v1_routes)
+        .nest("/v1beta", v1beta_routes)
+        .with_state(state)
+}
+
+/// 创建带有 KiroProvider 和代理控制的 Anthropic API 路由
+pub fn create_router_with_provider_and_control(
+    api_key: impl Into<String>,
+    kiro_provider: Option<KiroProvider>,
+    profile_arn: Option<String>,
+    proxy: ProxyLifecycle,
+) -> Router {
+    let mut state = AppState::new(api_key);
+    if let Some(provider) = kiro_provider {
+        state = state.with_kiro_provider(provider);
+    }
+    if let Some(arn) = profile_arn {
+        state = state.with_profile_arn(arn);
+    }
+    state = state.with_proxy_lifecycle(proxy);
+
+    // 需要认证的 /v1 路由
+    let v1_routes = Router::new()
+        .route("/models", get(get_models))
+        .route("/models/{id}", get(get_model))
+        .route("/messages", post(post_messages))
+        .route("/messages/count_tokens", post(count_tokens))
+        .route(
+            "/messages/batches",
+            post(batches::create_batch).get(batches::list_batches),
+        )
+        .route(
+            "/messages/batches/{id}",
+            get(batches::get_batch).delete(batches::delete_batch),
+        )
+        .route("/messages/batches/{id}/results", get(batches::get_batch_results))
+        .route("/messages/batches/{id}/cancel", post(batches::cancel_batch))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth_middleware,
+        ));
+
+    // Gemini 兼容路由，与 /v1 共用认证
+    let v1beta_routes = crate::gemini::create_router().layer(middleware::from_fn_with_state(
+        state.clone(),
+        auth_middleware,
+    ));
+
+    batches::resume_pending(&state);
+
+    Router::new()
+        .nest("/v1", v1_routes)
+        .nest("/v1beta", v1beta_routes)
+        .with_state(state)
+}
@@ -2,12 +2,18 @@
 //!
 //! 实现 Kiro → Anthropic 流式响应转换和 SSE 状态管理
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 
+use base64::Engine;
 use serde_json::json;
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
-use crate::kiro::model::events::Event;
+use crate::kiro::model::events::{ContextUsageEvent, Event};
+use crate::kiro::request_queue::CredentialSlot;
+use crate::post_process::{POST_PROCESSOR, PostProcessSession};
+
+use super::tool_choice::ToolChoice;
 
 /// 找到小于等于目标位置的最近有效UTF-8字符边界
 ///
@@ -176,6 +182,8 @@ fn find_real_thinking_start_tag(buffer: &str) -> Option<usize> {
 pub struct SseEvent {
     pub event: String,
     pub data: serde_json::Value,
+    /// 事件 ID（启用断线续传时设置，客户端重连时通过 `Last-Event-ID` 回传）
+    pub id: Option<String>,
 }
 
 impl SseEvent {
@@ -183,13 +191,22 @@ impl SseEvent {
         Self {
             event: event.into(),
             data,
+            id: None,
         }
     }
 
+    /// 设置事件 ID
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
     /// 格式化为 SSE 字符串
     pub fn to_sse_string(&self) -> String {
+        let id = self.id.as_deref().map(|id| format!("id: {}\n", id)).unwrap_or_default();
         format!(
-            "event: {}\ndata: {}\n\n",
+            "{}event: {}\ndata: {}\n\n",
+            id,
             self.event,
             serde_json::to_string(&self.data).unwrap_or_default()
         )
@@ -235,6 +252,8 @@ pub struct SseStateManager {
     next_block_index: i32,
     /// 当前 stop_reason
     stop_reason: Option<String>,
+    /// 命中的停止序列
+    stop_sequence: Option<String>,
     /// 是否有工具调用
     has_tool_use: bool,
 }
@@ -254,6 +273,7 @@ impl SseStateManager {
             message_ended: false,
             next_block_index: 0,
             stop_reason: None,
+            stop_sequence: None,
             has_tool_use: false,
         }
     }
@@ -282,6 +302,12 @@ impl SseStateManager {
         self.stop_reason = Some(reason.into());
     }
 
+    /// 记录命中的停止序列（stop_reason 为 stop_sequence）
+    pub fn set_stop_sequence(&mut self, sequence: impl Into<String>) {
+        self.stop_reason = Some("stop_sequence".to_string());
+        self.stop_sequence = Some(sequence.into());
+    }
+
     /// 获取最终的 stop_reason
     pub fn get_stop_reason(&self) -> String {
         if let Some(ref reason) = self.stop_reason {
@@ -433,7 +459,7 @@ impl SseStateManager {
                     "type": "message_delta",
                     "delta": {
                         "stop_reason": self.get_stop_reason(),
-                        "stop_sequence": null
+                        "stop_sequence": self.stop_sequence
                     },
                     "usage": {
                         "input_tokens": input_tokens,
@@ -459,6 +485,27 @@ impl SseStateManager {
 /// 上下文窗口大小（200k tokens）
 const CONTEXT_WINDOW_SIZE: i32 = 200_000;
 
+/// 由 contextUsageEvent 的上下文使用百分比计算实际的 input_tokens
+///
+/// 公式: percentage * 200000 / 100 = percentage * 2000
+pub(crate) fn context_usage_input_tokens(event: &ContextUsageEvent) -> i32 {
+    let tokens = (event.context_usage_percentage * (CONTEXT_WINDOW_SIZE as f64) / 100.0) as i32;
+    tracing::debug!(
+        "收到 contextUsageEvent: {}%, 计算 input_tokens: {}",
+        event.context_usage_percentage,
+        tokens
+    );
+    tokens
+}
+
+/// 等待输出的工具调用（到达时另一个工具块尚未结束）
+struct PendingToolUse {
+    tool_use_id: String,
+    name: String,
+    input: String,
+    stop: bool,
+}
+
 /// 流处理上下文
 pub struct StreamContext {
     /// SSE 状态管理器
@@ -475,6 +522,12 @@ pub struct StreamContext {
     pub output_tokens: i32,
     /// 工具块索引映射 (tool_id -> block_index)
     pub tool_block_indices: HashMap<String, i32>,
+    /// 尚未结束的工具输入 (tool_id -> 已发送的 JSON)，用于结束时修复截断的输入
+    tool_input_buffers: HashMap<String, String>,
+    /// 正在输出的工具块（tool_id），同一时间只有一个工具块处于打开状态
+    active_tool: Option<String>,
+    /// 排队等待输出的工具调用（按首次到达顺序）
+    pending_tool_uses: VecDeque<PendingToolUse>,
     /// thinking 是否启用
     pub thinking_enabled: bool,
     /// thinking 内容缓冲区
@@ -485,8 +538,32 @@ pub struct StreamContext {
     pub thinking_extracted: bool,
     /// thinking 块索引
     pub thinking_block_index: Option<i32>,
+    /// thinking 预算（tokens，None 表示不限制），超出部分不再输出
+    pub thinking_budget: Option<i32>,
+    /// 已输出的 thinking tokens
+    pub thinking_tokens: i32,
+    /// 已输出 thinking 内容的摘要（用于生成 signature）
+    thinking_signer: Sha256,
     /// 文本块索引（thinking 启用时动态分配）
     pub text_block_index: Option<i32>,
+    /// 响应后处理（改写、去重），在停止序列匹配之前执行
+    post_process: PostProcessSession,
+    /// 客户端指定的停止序列（命中后截断文本并结束流）
+    pub stop_sequences: StopSequenceMatcher,
+    /// 工具选择策略（过滤不允许的工具调用，强制调用工具时暂扣文本）
+    pub tool_choice: Option<ToolChoice>,
+    /// 强制调用工具时暂扣的文本，流结束时仍未调用工具则照常输出
+    withheld_text: String,
+    /// 租户 API Key ID（用于用量统计）
+    pub api_key_id: Option<String>,
+    /// 处理请求的凭证分组（用于日志按分组过滤）
+    pub group_id: Option<String>,
+    /// 凭证并发槽位，随流结束释放
+    pub credential_slot: Option<CredentialSlot>,
+    /// 请求开始时间（用于实时延迟统计）
+    pub started_at: std::time::Instant,
+    /// 断线续传缓冲（启用时事件经此分配 ID 并缓存）
+    pub replay: Option<std::sync::Arc<super::resume::ReplayStream>>,
 }
 
 impl StreamContext {
@@ -504,12 +581,27 @@ impl StreamContext {
             context_input_tokens: None,
             output_tokens: 0,
             tool_block_indices: HashMap::new(),
+            tool_input_buffers: HashMap::new(),
+            active_tool: None,
+            pending_tool_uses: VecDeque::new(),
             thinking_enabled,
             thinking_buffer: String::new(),
             in_thinking_block: false,
             thinking_extracted: false,
             thinking_block_index: None,
+            thinking_budget: None,
+            thinking_tokens: 0,
+            thinking_signer: Sha256::new(),
             text_block_index: None,
+            post_process: POST_PROCESSOR.session(),
+            stop_sequences: StopSequenceMatcher::default(),
+            tool_choice: None,
+            withheld_text: String::new(),
+            api_key_id: None,
+            group_id: None,
+            credential_slot: None,
+            started_at: std::time::Instant::now(),
+            replay: None,
         }
     }
 
@@ -547,8 +639,9 @@ impl StreamContext {
         }
 
         // 如果启用了 thinking，不在这里创建文本块
-        // thinking 块和文本块会在 process_content_with_thinking 中按正确顺序创建
-        if self.thinking_enabled {
+        // thinking 块和文本块会在 process_content_with_thinking 中按正确顺序创建；
+        // 强制调用工具时文本被暂扣，同样不预先创建
+        if self.thinking_enabled || self.withholds_text() {
             return events;
         }
 
@@ -574,21 +667,23 @@ impl StreamContext {
 
     /// 处理 Kiro 事件并转换为 Anthropic SSE 事件
     pub fn process_kiro_event(&mut self, event: &Event) -> Vec<SseEvent> {
+        // 命中停止序列后丢弃后续输出
+        if self.is_stopped() && matches!(event, Event::AssistantResponse(_) | Event::ToolUse(_)) {
+            return Vec::new();
+        }
+
         match event {
             Event::AssistantResponse(resp) => self.process_assistant_response(&resp.content),
-            Event::ToolUse(tool_use) => self.process_tool_use(tool_use),
+            Event::ToolUse(tool_use) => {
+                if self.tool_choice.as_ref().is_some_and(|c| !c.allows_tool(&tool_use.name)) {
+                    tracing::debug!("tool_choice 不允许调用工具 {}，已丢弃", tool_use.name);
+                    return Vec::new();
+                }
+                self.process_tool_use(tool_use)
+            }
             Event::ContextUsage(context_usage) => {
-                // 从上下文使用百分比计算实际的 input_tokens
-                // 公式: percentage * 200000 / 100 = percentage * 2000
-                let actual_input_tokens = (context_usage.context_usage_percentage
-                    * (CONTEXT_WINDOW_SIZE as f64)
-                    / 100.0) as i32;
-                self.context_input_tokens = Some(actual_input_tokens);
-                tracing::debug!(
-                    "收到 contextUsageEvent: {}%, 计算 input_tokens: {}",
-                    context_usage.context_usage_percentage,
-                    actual_input_tokens
-                );
+                // 最终 message_delta.usage 使用实际值代替估算值
+                self.context_input_tokens = Some(context_usage_input_tokens(context_usage));
                 Vec::new()
             }
             Event::Error {
@@ -692,29 +787,14 @@ impl StreamContext {
                 if let Some(end_pos) = find_real_thinking_end_tag(&self.thinking_buffer) {
                     // 提取 thinking 内容
                     let thinking_content = self.thinking_buffer[..end_pos].to_string();
-                    if !thinking_content.is_empty() {
-                        if let Some(thinking_index) = self.thinking_block_index {
-                            events.push(
-                                self.create_thinking_delta_event(thinking_index, &thinking_content),
-                            );
-                        }
-                    }
+                    events.extend(self.thinking_delta_event(&thinking_content));
 
                     // 结束 thinking 块
                     self.in_thinking_block = false;
                     self.thinking_extracted = true;
 
-                    // 发送空的 thinking_delta 事件，然后发送 content_block_stop 事件
-                    if let Some(thinking_index) = self.thinking_block_index {
-                        // 先发送空的 thinking_delta
-                        events.push(self.create_thinking_delta_event(thinking_index, ""));
-                        // 再发送 content_block_stop
-                        if let Some(stop_event) =
-                            self.state_manager.handle_content_block_stop(thinking_index)
-                        {
-                            events.push(stop_event);
-                        }
-                    }
+                    // 发送 signature_delta 事件，然后发送 content_block_stop 事件
+                    events.extend(self.close_thinking_block());
 
                     self.thinking_buffer =
                         self.thinking_buffer[end_pos + "</thinking>".len()..].to_string();
@@ -728,13 +808,7 @@ impl StreamContext {
                     let safe_len = find_char_boundary(&self.thinking_buffer, target_len);
                     if safe_len > 0 {
                         let safe_content = self.thinking_buffer[..safe_len].to_string();
-                        if !safe_content.is_empty() {
-                            if let Some(thinking_index) = self.thinking_block_index {
-                                events.push(
-                                    self.create_thinking_delta_event(thinking_index, &safe_content),
-                                );
-                            }
-                        }
+                        events.extend(self.thinking_delta_event(&safe_content));
                         self.thinking_buffer = self.thinking_buffer[safe_len..].to_string();
                     }
                     break;
@@ -753,13 +827,74 @@ impl StreamContext {
         events
     }
 
-    /// 创建 text_delta 事件
+    /// 是否已命中停止序列（调用方应停止读取上游并发送最终事件）
+    pub fn is_stopped(&self) -> bool {
+        self.stop_sequences.matched().is_some()
+    }
+
+    /// 创建 text_delta 事件（经过响应后处理和停止序列过滤）
+    fn create_text_delta_events(&mut self, text: &str) -> Vec<SseEvent> {
+        if !self.post_process.is_active() {
+            return self.stop_sequence_text_events(text);
+        }
+        let text = self.post_process.push(text);
+        if text.is_empty() {
+            return Vec::new();
+        }
+        self.stop_sequence_text_events(&text)
+    }
+
+    /// 输出后处理缓冲中尚未换行的文本（工具调用开始前或流结束时）
+    fn flush_post_process(&mut self) -> Vec<SseEvent> {
+        let pending = self.post_process.flush();
+        if pending.is_empty() || self.is_stopped() {
+            Vec::new()
+        } else {
+            self.stop_sequence_text_events(&pending)
+        }
+    }
+
+    /// 经过停止序列过滤后发送 text_delta
+    ///
+    /// 可能是停止序列前缀的尾部文本暂不发送；命中停止序列时只发送其之前的文本
+    fn stop_sequence_text_events(&mut self, text: &str) -> Vec<SseEvent> {
+        if !self.stop_sequences.is_active() {
+            return self.emit_text_delta_events(text);
+        }
+
+        let text = self.stop_sequences.push(text);
+        let events = if text.is_empty() {
+            Vec::new()
+        } else {
+            self.emit_text_delta_events(&text)
+        };
+        if let Some(sequence) = self.stop_sequences.matched() {
+            self.state_manager.set_stop_sequence(sequence);
+        }
+        events
+    }
+
+    /// 发送暂存的文本（可能是停止序列前缀，但后续输出已不会再补全它）
+    fn flush_stop_sequence_buffer(&mut self) -> Vec<SseEvent> {
+        let pending = self.stop_sequences.flush();
+        if pending.is_empty() {
+            Vec::new()
+        } else {
+            self.emit_text_delta_events(&pending)
+        }
+    }
+
+    /// 发送 text_delta 事件
     ///
     /// 如果文本块尚未创建，会先创建文本块。
     /// 当发生 tool_use 时，状态机会自动关闭当前文本块；后续文本会自动创建新的文本块继续输出。
     ///
     /// 返回值包含可能的 content_block_start 事件和 content_block_delta 事件。
-    fn create_text_delta_events(&mut self, text: &str) -> Vec<SseEvent> {
+    fn emit_text_delta_events(&mut self, text: &str) -> Vec<SseEvent> {
+        if self.withholds_text() {
+            self.withheld_text.push_str(text);
+            return Vec::new();
+        }
         let mut events = Vec::new();
 
         // 如果当前 text_block_index 指向的块已经被关闭（例如 tool_use 开始时自动 stop），
@@ -813,9 +948,52 @@ impl StreamContext {
         events
     }
 
+    /// 是否暂扣文本（tool_choice 要求调用工具）
+    fn withholds_text(&self) -> bool {
+        self.tool_choice.as_ref().is_some_and(ToolChoice::suppresses_text)
+    }
+
+    /// 流结束时输出暂扣的文本：模型调用了工具则丢弃，否则照常输出，避免返回空响应
+    fn release_withheld_text(&mut self) -> Vec<SseEvent> {
+        let text = std::mem::take(&mut self.withheld_text);
+        if text.is_empty() || self.state_manager.has_tool_use() {
+            return Vec::new();
+        }
+        tracing::warn!("tool_choice 要求调用工具，但模型未调用任何工具，照常返回文本");
+        self.tool_choice = None;
+        self.emit_text_delta_events(&text)
+    }
+
     /// 创建 thinking_delta 事件
-    fn create_thinking_delta_event(&self, index: i32, thinking: &str) -> SseEvent {
-        SseEvent::new(
+    ///
+    /// 超出 thinking 预算的部分被截断丢弃；没有可发送的内容时返回 None
+    fn thinking_delta_event(&mut self, thinking: &str) -> Option<SseEvent> {
+        let index = self.thinking_block_index?;
+        if thinking.is_empty() {
+            return None;
+        }
+
+        let thinking = match self.thinking_budget {
+            Some(budget) => {
+                let remaining = budget - self.thinking_tokens;
+                if remaining <= 0 {
+                    return None;
+                }
+                let allowed = truncate_to_tokens(thinking, remaining);
+                if allowed.len() < thinking.len() {
+                    tracing::debug!("thinking 内容超出预算 {} tokens，已截断", budget);
+                }
+                allowed
+            }
+            None => thinking,
+        };
+        if thinking.is_empty() {
+            return None;
+        }
+
+        self.thinking_tokens += estimate_tokens(thinking);
+        self.thinking_signer.update(thinking.as_bytes());
+        Some(SseEvent::new(
             "content_block_delta",
             json!({
                 "type": "content_block_delta",
@@ -825,7 +1003,32 @@ impl StreamContext {
                     "thinking": thinking
                 }
             }),
-        )
+        ))
+    }
+
+    /// 关闭 thinking 块：发送 signature_delta 和 content_block_stop
+    ///
+    /// Kiro 不返回签名，签名由已发送的 thinking 内容计算，
+    /// 客户端在后续轮次回传 thinking 块时需要携带该字段
+    fn close_thinking_block(&mut self) -> Vec<SseEvent> {
+        let Some(index) = self.thinking_block_index else {
+            return Vec::new();
+        };
+
+        let digest = std::mem::take(&mut self.thinking_signer).finalize();
+        let mut events = vec![SseEvent::new(
+            "content_block_delta",
+            json!({
+                "type": "content_block_delta",
+                "index": index,
+                "delta": {
+                    "type": "signature_delta",
+                    "signature": encode_signature(&digest)
+                }
+            }),
+        )];
+        events.extend(self.state_manager.handle_content_block_stop(index));
+        events
     }
 
     /// 处理工具使用事件
@@ -844,28 +1047,13 @@ impl StreamContext {
         if self.thinking_enabled && self.in_thinking_block {
             if let Some(end_pos) = find_real_thinking_end_tag_at_buffer_end(&self.thinking_buffer) {
                 let thinking_content = self.thinking_buffer[..end_pos].to_string();
-                if !thinking_content.is_empty() {
-                    if let Some(thinking_index) = self.thinking_block_index {
-                        events.push(
-                            self.create_thinking_delta_event(thinking_index, &thinking_content),
-                        );
-                    }
-                }
+                events.extend(self.thinking_delta_event(&thinking_content));
 
                 // 结束 thinking 块
                 self.in_thinking_block = false;
                 self.thinking_extracted = true;
 
-                if let Some(thinking_index) = self.thinking_block_index {
-                    // 先发送空的 thinking_delta
-                    events.push(self.create_thinking_delta_event(thinking_index, ""));
-                    // 再发送 content_block_stop
-                    if let Some(stop_event) =
-                        self.state_manager.handle_content_block_stop(thinking_index)
-                    {
-                        events.push(stop_event);
-                    }
-                }
+                events.extend(self.close_thinking_block());
 
                 // 把结束标签后的内容当作普通文本（通常为空或空白）
                 let after_pos = end_pos + "</thinking>".len();
@@ -888,14 +1076,79 @@ impl StreamContext {
             let buffered = std::mem::take(&mut self.thinking_buffer);
             events.extend(self.create_text_delta_events(&buffered));
         }
+        events.extend(self.flush_post_process());
+        // 缓冲区中的文本可能恰好命中停止序列，此时不再开始工具调用
+        if self.is_stopped() {
+            return events;
+        }
+        events.extend(self.flush_stop_sequence_buffer());
+
+        // 已结束的工具块不再接收事件（重复的 stop 或迟到的增量）
+        if let Some(&idx) = self.tool_block_indices.get(&tool_use.tool_use_id) {
+            if !self.state_manager.is_block_open_of_type(idx, "tool_use") {
+                tracing::debug!("工具块 {} 已结束，忽略后续事件: {}", idx, tool_use.tool_use_id);
+                return events;
+            }
+        }
+
+        // 并行工具调用的事件可能交错到达。同一时间只输出一个工具块，其他工具的事件先排队，
+        // 当前工具结束后再按首次到达顺序输出，保证块索引递增、input_json_delta 不会写入其他块
+        if self
+            .active_tool
+            .as_ref()
+            .is_some_and(|active| *active != tool_use.tool_use_id)
+        {
+            self.queue_tool_use(tool_use);
+            return events;
+        }
+
+        events.extend(self.emit_tool_use(&tool_use.tool_use_id, &tool_use.name, &tool_use.input, tool_use.stop));
+        events.extend(self.drain_pending_tool_uses());
+        events
+    }
 
-        // 获取或分配块索引
-        let block_index = if let Some(&idx) = self.tool_block_indices.get(&tool_use.tool_use_id) {
+    /// 暂存交错到达的工具调用事件
+    fn queue_tool_use(&mut self, tool_use: &crate::kiro::model::events::ToolUseEvent) {
+        match self
+            .pending_tool_uses
+            .iter_mut()
+            .find(|p| p.tool_use_id == tool_use.tool_use_id)
+        {
+            Some(pending) => {
+                pending.input.push_str(&tool_use.input);
+                pending.stop |= tool_use.stop;
+            }
+            None => self.pending_tool_uses.push_back(PendingToolUse {
+                tool_use_id: tool_use.tool_use_id.clone(),
+                name: tool_use.name.clone(),
+                input: tool_use.input.clone(),
+                stop: tool_use.stop,
+            }),
+        }
+    }
+
+    /// 当前没有打开的工具块时，依次输出排队的工具调用（遇到尚未结束的工具时停下）
+    fn drain_pending_tool_uses(&mut self) -> Vec<SseEvent> {
+        let mut events = Vec::new();
+        while self.active_tool.is_none() {
+            let Some(pending) = self.pending_tool_uses.pop_front() else {
+                break;
+            };
+            events.extend(self.emit_tool_use(&pending.tool_use_id, &pending.name, &pending.input, pending.stop));
+        }
+        events
+    }
+
+    /// 输出工具块的 start / input_json_delta，`stop` 时结束该块
+    fn emit_tool_use(&mut self, tool_use_id: &str, name: &str, input: &str, stop: bool) -> Vec<SseEvent> {
+        let mut events = Vec::new();
+
+        // 获取或分配块索引（在块真正开始输出时分配，保证索引按输出顺序递增）
+        let block_index = if let Some(&idx) = self.tool_block_indices.get(tool_use_id) {
             idx
         } else {
             let idx = self.state_manager.next_block_index();
-            self.tool_block_indices
-                .insert(tool_use.tool_use_id.clone(), idx);
+            self.tool_block_indices.insert(tool_use_id.to_string(), idx);
             idx
         };
 
@@ -908,17 +1161,25 @@ impl StreamContext {
                 "index": block_index,
                 "content_block": {
                     "type": "tool_use",
-                    "id": tool_use.tool_use_id,
-                    "name": tool_use.name,
+                    "id": tool_use_id,
+                    "name": name,
                     "input": {}
                 }
             }),
         );
         events.extend(start_events);
+        self.active_tool = Some(tool_use_id.to_string());
 
         // 发送参数增量 (ToolUseEvent.input 是 String 类型)
-        if !tool_use.input.is_empty() {
-            self.output_tokens += (tool_use.input.len() as i32 + 3) / 4; // 估算 token
+        if !input.is_empty() {
+            self.tool_input_buffers
+                .entry(tool_use_id.to_string())
+                .or_default()
+                .push_str(input);
+
+            self.output_tokens += crate::token::bpe_count(input)
+                .map(|n| n as i32)
+                .unwrap_or((input.len() as i32 + 3) / 4); // 估算 token
 
             if let Some(delta_event) = self.state_manager.handle_content_block_delta(
                 block_index,
@@ -927,7 +1188,7 @@ impl StreamContext {
                     "index": block_index,
                     "delta": {
                         "type": "input_json_delta",
-                        "partial_json": tool_use.input
+                        "partial_json": input
                     }
                 }),
             ) {
@@ -936,19 +1197,72 @@ impl StreamContext {
         }
 
         // 如果是完整的工具调用（stop=true），发送 content_block_stop
-        if tool_use.stop {
-            if let Some(stop_event) = self.state_manager.handle_content_block_stop(block_index) {
-                events.push(stop_event);
-            }
+        if stop {
+            events.extend(self.finish_tool_block(tool_use_id));
         }
 
         events
     }
 
+    /// 结束工具块：补全截断的输入后发送 content_block_stop
+    fn finish_tool_block(&mut self, tool_use_id: &str) -> Vec<SseEvent> {
+        if self.active_tool.as_deref() == Some(tool_use_id) {
+            self.active_tool = None;
+        }
+        let mut events: Vec<SseEvent> = self.repair_tool_input(tool_use_id).into_iter().collect();
+        if let Some(&block_index) = self.tool_block_indices.get(tool_use_id) {
+            events.extend(self.state_manager.handle_content_block_stop(block_index));
+        }
+        events
+    }
+
+    /// 工具输入被截断时，补发修复所需的 input_json_delta
+    fn repair_tool_input(&mut self, tool_use_id: &str) -> Option<SseEvent> {
+        let buffer = self.tool_input_buffers.remove(tool_use_id)?;
+        let block_index = *self.tool_block_indices.get(tool_use_id)?;
+        if buffer.trim().is_empty() || serde_json::from_str::<serde_json::Value>(&buffer).is_ok() {
+            return None;
+        }
+        let Some(suffix) = super::json_repair::repair_suffix(&buffer) else {
+            tracing::warn!(
+                "工具输入 JSON 不完整且无法修复, tool_use_id: {}, 原始内容: {}",
+                tool_use_id,
+                buffer
+            );
+            return None;
+        };
+        tracing::warn!(
+            "工具输入 JSON 不完整，已自动补全: tool_use_id: {}, 补全内容: {}",
+            tool_use_id,
+            suffix
+        );
+        self.state_manager.handle_content_block_delta(
+            block_index,
+            json!({
+                "type": "content_block_delta",
+                "index": block_index,
+                "delta": {
+                    "type": "input_json_delta",
+                    "partial_json": suffix
+                }
+            }),
+        )
+    }
+
     /// 生成最终事件序列
     pub fn generate_final_events(&mut self) -> Vec<SseEvent> {
         let mut events = Vec::new();
 
+        // 流结束时仍未收到 stop 的工具调用：补全输入并结束当前工具块，再依次输出排队的工具调用
+        // （命中停止序列时排队的工具调用丢弃）
+        if self.is_stopped() {
+            self.pending_tool_uses.clear();
+        }
+        while let Some(tool_use_id) = self.active_tool.clone() {
+            events.extend(self.finish_tool_block(&tool_use_id));
+            events.extend(self.drain_pending_tool_uses());
+        }
+
         // Flush thinking_buffer 中的剩余内容
         if self.thinking_enabled && !self.thinking_buffer.is_empty() {
             if self.in_thinking_block {
@@ -957,23 +1271,10 @@ impl StreamContext {
                     find_real_thinking_end_tag_at_buffer_end(&self.thinking_buffer)
                 {
                     let thinking_content = self.thinking_buffer[..end_pos].to_string();
-                    if !thinking_content.is_empty() {
-                        if let Some(thinking_index) = self.thinking_block_index {
-                            events.push(
-                                self.create_thinking_delta_event(thinking_index, &thinking_content),
-                            );
-                        }
-                    }
+                    events.extend(self.thinking_delta_event(&thinking_content));
 
-                    // 关闭 thinking 块：先发送空的 thinking_delta，再发送 content_block_stop
-                    if let Some(thinking_index) = self.thinking_block_index {
-                        events.push(self.create_thinking_delta_event(thinking_index, ""));
-                        if let Some(stop_event) =
-                            self.state_manager.handle_content_block_stop(thinking_index)
-                        {
-                            events.push(stop_event);
-                        }
-                    }
+                    // 关闭 thinking 块：发送 signature_delta，再发送 content_block_stop
+                    events.extend(self.close_thinking_block());
 
                     // 把结束标签后的内容当作普通文本（通常为空或空白）
                     let after_pos = end_pos + "</thinking>".len();
@@ -986,22 +1287,10 @@ impl StreamContext {
                     }
                 } else {
                     // 如果还在 thinking 块内，发送剩余内容作为 thinking_delta
-                    if let Some(thinking_index) = self.thinking_block_index {
-                        events.push(
-                            self.create_thinking_delta_event(thinking_index, &self.thinking_buffer),
-                        );
-                    }
-                    // 关闭 thinking 块：先发送空的 thinking_delta，再发送 content_block_stop
-                    if let Some(thinking_index) = self.thinking_block_index {
-                        // 先发送空的 thinking_delta
-                        events.push(self.create_thinking_delta_event(thinking_index, ""));
-                        // 再发送 content_block_stop
-                        if let Some(stop_event) =
-                            self.state_manager.handle_content_block_stop(thinking_index)
-                        {
-                            events.push(stop_event);
-                        }
-                    }
+                    let remaining = std::mem::take(&mut self.thinking_buffer);
+                    events.extend(self.thinking_delta_event(&remaining));
+                    // 关闭 thinking 块：发送 signature_delta，再发送 content_block_stop
+                    events.extend(self.close_thinking_block());
                 }
             } else {
                 // 否则发送剩余内容作为 text_delta
@@ -1010,6 +1299,9 @@ impl StreamContext {
             }
             self.thinking_buffer.clear();
         }
+        events.extend(self.flush_post_process());
+        events.extend(self.flush_stop_sequence_buffer());
+        events.extend(self.release_withheld_text());
 
         // 使用从 contextUsageEvent 计算的 input_tokens，如果没有则使用估算值
         let final_input_tokens = self.context_input_tokens.unwrap_or(self.input_tokens);
@@ -1034,8 +1326,20 @@ impl StreamContext {
                 stop_reason: self.state_manager.stop_reason(),
                 has_tool_use: self.state_manager.has_tool_use(),
                 response_preview: String::new(), // 流式响应不保存预览
-            }, true);
+            }, true, self.group_id.clone());
+        }
+
+        if let Some(id) = &self.api_key_id {
+            crate::api_keys::API_KEY_REGISTRY.record_usage(id, final_input_tokens, self.output_tokens);
+        }
+        if let Some(group_id) = &self.group_id {
+            crate::group_budgets::GROUP_BUDGETS.record_usage(group_id, final_input_tokens, self.output_tokens);
         }
+        crate::metrics::REALTIME_STATS.record_success(
+            self.started_at.elapsed(),
+            final_input_tokens,
+            self.output_tokens,
+        );
 
         // 生成最终事件
         events.extend(
@@ -1046,8 +1350,135 @@ impl StreamContext {
     }
 }
 
-/// 简单的 token 估算
+/// 查找最早出现的停止序列，返回 (位置, 停止序列)
+pub(crate) fn find_stop_sequence<'a>(text: &str, sequences: &'a [String]) -> Option<(usize, &'a str)> {
+    sequences
+        .iter()
+        .filter(|seq| !seq.is_empty())
+        .filter_map(|seq| text.find(seq.as_str()).map(|pos| (pos, seq.as_str())))
+        .min_by_key(|(pos, _)| *pos)
+}
+
+/// 流式输出的停止序列匹配
+///
+/// Kiro 不支持停止序列，由网关在输出端截断。文本可能在停止序列中间被分片，
+/// 因此末尾可能是停止序列前缀的部分先暂存，等后续文本到达后再判断
+#[derive(Debug, Default)]
+pub struct StopSequenceMatcher {
+    sequences: Vec<String>,
+    /// 暂存的尾部文本（可能是停止序列的前缀）
+    pending: String,
+    /// 命中的停止序列
+    matched: Option<String>,
+}
+
+impl StopSequenceMatcher {
+    pub fn new(sequences: Vec<String>) -> Self {
+        Self {
+            sequences: sequences.into_iter().filter(|s| !s.is_empty()).collect(),
+            pending: String::new(),
+            matched: None,
+        }
+    }
+
+    /// 是否配置了停止序列
+    pub fn is_active(&self) -> bool {
+        !self.sequences.is_empty()
+    }
+
+    /// 命中的停止序列
+    pub fn matched(&self) -> Option<&str> {
+        self.matched.as_deref()
+    }
+
+    /// 输入一段文本，返回可以立即发送的部分（命中后不再返回任何文本）
+    pub fn push(&mut self, text: &str) -> String {
+        if self.matched.is_some() {
+            return String::new();
+        }
+        self.pending.push_str(text);
+
+        if let Some((pos, sequence)) = find_stop_sequence(&self.pending, &self.sequences) {
+            self.matched = Some(sequence.to_string());
+            let emitted = self.pending[..pos].to_string();
+            self.pending.clear();
+            return emitted;
+        }
+
+        let hold = self.partial_suffix_len();
+        let emitted: String = self.pending.drain(..self.pending.len() - hold).collect();
+        emitted
+    }
+
+    /// 取出暂存的文本（流结束或开始工具调用时）
+    pub fn flush(&mut self) -> String {
+        std::mem::take(&mut self.pending)
+    }
+
+    /// 暂存文本末尾与任一停止序列前缀重合的最大长度
+    fn partial_suffix_len(&self) -> usize {
+        self.sequences
+            .iter()
+            .filter_map(|seq| {
+                (1..seq.len())
+                    .rev()
+                    .filter(|&n| n <= self.pending.len() && seq.is_char_boundary(n))
+                    .find(|&n| self.pending.ends_with(&seq[..n]))
+            })
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// 截取不超过指定 token 数的前缀（按估算比例截断到字符边界）
+pub(crate) fn truncate_to_tokens(text: &str, max_tokens: i32) -> &str {
+    let tokens = estimate_tokens(text);
+    if tokens <= max_tokens {
+        return text;
+    }
+    let chars = text.chars().count() as i64 * max_tokens as i64 / tokens as i64;
+    match text.char_indices().nth(chars as usize) {
+        Some((pos, _)) => &text[..pos],
+        None => text,
+    }
+}
+
+/// thinking 块签名（base64 编码的内容摘要）
+fn encode_signature(digest: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(digest)
+}
+
+/// 计算 thinking 内容的签名（非流式响应使用，与流式输出的 signature_delta 一致）
+pub(crate) fn thinking_signature(thinking: &str) -> String {
+    encode_signature(&Sha256::digest(thinking.as_bytes()))
+}
+
+/// 从完整的响应文本中拆分 thinking 内容（非流式响应使用）
+///
+/// 与流式处理相同，只识别未被引用的 `<thinking>` 标签；返回 (thinking, 剩余文本)
+pub(crate) fn split_thinking(content: &str) -> (Option<String>, String) {
+    let Some(start) = find_real_thinking_start_tag(content) else {
+        return (None, content.to_string());
+    };
+    let before = &content[..start];
+    let rest = &content[start + "<thinking>".len()..];
+
+    let (thinking, after) = match find_real_thinking_end_tag(rest)
+        .or_else(|| find_real_thinking_end_tag_at_buffer_end(rest))
+    {
+        Some(end) => (&rest[..end], rest[end + "</thinking>".len()..].trim_start()),
+        // 没有结束标签（输出被截断）：剩余内容全部视为 thinking
+        None => (rest, ""),
+    };
+    (Some(thinking.to_string()), format!("{}{}", before, after))
+}
+
+/// 简单的 token 估算（启用 tokenizer 特性时使用 BPE 精确计数）
 fn estimate_tokens(text: &str) -> i32 {
+    if let Some(tokens) = crate::token::bpe_count(text) {
+        return (tokens as i32).max(1);
+    }
+
     let chars: Vec<char> = text.chars().collect();
     let mut chinese_count = 0;
     let mut other_count = 0;
@@ -1115,6 +1546,103 @@ mod tests {
         assert!(event.is_none());
     }
 
+    #[test]
+    fn test_stop_sequence_matcher_holds_partial_prefix() {
+        let mut matcher = StopSequenceMatcher::new(vec!["END".to_string(), String::new()]);
+        assert_eq!(matcher.push("hello E"), "hello ");
+        assert_eq!(matcher.push("ND"), "");
+        assert_eq!(matcher.matched(), Some("END"));
+        assert_eq!(matcher.push("more"), "");
+
+        // 暂存的前缀最终没有补全时原样发送
+        let mut matcher = StopSequenceMatcher::new(vec!["世界!".to_string()]);
+        assert_eq!(matcher.push("你好世"), "你好");
+        assert_eq!(matcher.push("纪"), "世纪");
+        assert_eq!(matcher.push("世界"), "");
+        assert_eq!(matcher.flush(), "世界");
+        assert!(matcher.matched().is_none());
+    }
+
+    #[test]
+    fn test_stream_cuts_at_stop_sequence() {
+        let mut ctx = StreamContext::new_with_thinking("test-model", 1, false);
+        ctx.stop_sequences = StopSequenceMatcher::new(vec!["\n\nHuman:".to_string()]);
+        ctx.generate_initial_events();
+
+        let mut events = ctx.process_assistant_response("Answer.\n\nHu");
+        events.extend(ctx.process_assistant_response("man: next"));
+        assert!(ctx.is_stopped());
+        // 命中后的工具调用被丢弃
+        events.extend(ctx.process_kiro_event(&Event::ToolUse(crate::kiro::model::events::ToolUseEvent {
+            name: "test_tool".to_string(),
+            tool_use_id: "tool_1".to_string(),
+            input: "{}".to_string(),
+            stop: true,
+        })));
+        events.extend(ctx.generate_final_events());
+
+        let text: String = events
+            .iter()
+            .filter_map(|e| e.data["delta"]["text"].as_str())
+            .collect();
+        assert_eq!(text, "Answer.");
+        assert!(!events.iter().any(|e| e.data["content_block"]["type"] == "tool_use"));
+
+        let delta = events.iter().find(|e| e.event == "message_delta").unwrap();
+        assert_eq!(delta.data["delta"]["stop_reason"], "stop_sequence");
+        assert_eq!(delta.data["delta"]["stop_sequence"], "\n\nHuman:");
+    }
+
+    #[test]
+    fn test_tool_choice_filters_text_and_tools() {
+        let tool_use = |name: &str, id: &str| {
+            Event::ToolUse(crate::kiro::model::events::ToolUseEvent {
+                name: name.to_string(),
+                tool_use_id: id.to_string(),
+                input: "{}".to_string(),
+                stop: true,
+            })
+        };
+
+        // 强制调用 Bash：文本被丢弃，其他工具调用被过滤
+        let mut ctx = StreamContext::new_with_thinking("test-model", 1, false);
+        ctx.tool_choice = Some(ToolChoice::Tool("Bash".to_string()));
+        let mut events = ctx.generate_initial_events();
+        events.extend(ctx.process_assistant_response("Let me check."));
+        events.extend(ctx.process_kiro_event(&tool_use("Grep", "tool_1")));
+        events.extend(ctx.process_kiro_event(&tool_use("Bash", "tool_2")));
+        events.extend(ctx.generate_final_events());
+
+        let blocks: Vec<_> = events
+            .iter()
+            .filter(|e| e.event == "content_block_start")
+            .map(|e| (e.data["index"].as_i64().unwrap(), e.data["content_block"]["name"].clone()))
+            .collect();
+        assert_eq!(blocks, vec![(0, json!("Bash"))]);
+        let delta = events.iter().find(|e| e.event == "message_delta").unwrap();
+        assert_eq!(delta.data["delta"]["stop_reason"], "tool_use");
+
+        // 模型未调用工具时照常输出暂扣的文本
+        let mut ctx = StreamContext::new_with_thinking("test-model", 1, false);
+        ctx.tool_choice = Some(ToolChoice::Any);
+        let mut events = ctx.generate_initial_events();
+        events.extend(ctx.process_assistant_response("No tool needed."));
+        assert!(events.iter().all(|e| e.event == "message_start"));
+        events.extend(ctx.generate_final_events());
+        let text: String = events
+            .iter()
+            .filter_map(|e| e.data["delta"]["text"].as_str())
+            .collect();
+        assert_eq!(text, "No tool needed.");
+
+        // none：工具调用全部丢弃
+        let mut ctx = StreamContext::new_with_thinking("test-model", 1, false);
+        ctx.tool_choice = Some(ToolChoice::None);
+        ctx.generate_initial_events();
+        assert!(ctx.process_kiro_event(&tool_use("Bash", "tool_1")).is_empty());
+        assert!(!ctx.state_manager.has_tool_use());
+    }
+
     #[test]
     fn test_text_delta_after_tool_use_restarts_text_block() {
         let mut ctx = StreamContext::new_with_thinking("test-model", 1, false);
@@ -1174,6 +1702,148 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_final_usage_uses_context_usage_event() {
+        let mut ctx = StreamContext::new_with_thinking("test-model", 10, false);
+        let _ = ctx.generate_initial_events();
+        let _ = ctx.process_assistant_response("hi");
+        let _ = ctx.process_kiro_event(&Event::ContextUsage(ContextUsageEvent {
+            context_usage_percentage: 1.5,
+        }));
+
+        let events = ctx.generate_final_events();
+        let delta = events.iter().find(|e| e.event == "message_delta").unwrap();
+        assert_eq!(delta.data["usage"]["input_tokens"], 3000);
+    }
+
+    #[test]
+    fn test_truncated_tool_input_is_completed_before_block_stop() {
+        let mut ctx = StreamContext::new_with_thinking("test-model", 1, false);
+        let _ = ctx.generate_initial_events();
+
+        let tool = |input: &str, stop: bool| crate::kiro::model::events::ToolUseEvent {
+            name: "Write".to_string(),
+            tool_use_id: "tool_1".to_string(),
+            input: input.to_string(),
+            stop,
+        };
+        let _ = ctx.process_tool_use(&tool(r#"{"path": "a.txt", "#, false));
+        let events = ctx.process_tool_use(&tool(r#""content": "hel"#, true));
+
+        let partial: String = events
+            .iter()
+            .filter(|e| e.event == "content_block_delta")
+            .filter_map(|e| e.data["delta"]["partial_json"].as_str())
+            .collect();
+        assert_eq!(partial, r#""content": "hel"}"#);
+        assert_eq!(events.last().unwrap().event, "content_block_stop");
+    }
+
+    /// 回放录制的 Kiro 事件流（每行 `事件类型 JSON 负载`），返回全部 SSE 事件
+    fn replay(recording: &str) -> Vec<SseEvent> {
+        use crate::kiro::model::events::{AssistantResponseEvent, ToolUseEvent};
+
+        let mut ctx = StreamContext::new_with_thinking("test-model", 1, false);
+        let mut events = ctx.generate_initial_events();
+        for line in recording.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let (event_type, payload) = line.split_once(' ').unwrap();
+            let event = match event_type {
+                "assistantResponseEvent" => {
+                    Event::AssistantResponse(serde_json::from_str::<AssistantResponseEvent>(payload).unwrap())
+                }
+                "toolUseEvent" => Event::ToolUse(serde_json::from_str::<ToolUseEvent>(payload).unwrap()),
+                other => panic!("未知事件类型: {}", other),
+            };
+            events.extend(ctx.process_kiro_event(&event));
+        }
+        events.extend(ctx.generate_final_events());
+        events
+    }
+
+    /// 校验内容块按顺序输出（同一时间只有一个块打开，增量只写入打开的块），返回各工具的 (块索引, 输入)
+    fn tool_inputs(events: &[SseEvent]) -> Vec<(i64, String, serde_json::Value)> {
+        let mut open: Option<i64> = None;
+        let mut last_index = -1;
+        let mut tools: Vec<(i64, String, String)> = Vec::new();
+        for event in events {
+            let index = event.data["index"].as_i64();
+            match event.event.as_str() {
+                "content_block_start" => {
+                    assert_eq!(open, None, "块 {:?} 开始时仍有块未结束", index);
+                    let index = index.unwrap();
+                    assert!(index > last_index, "块索引必须递增");
+                    last_index = index;
+                    open = Some(index);
+                    if let Some(id) = event.data["content_block"]["id"].as_str() {
+                        tools.push((index, id.to_string(), String::new()));
+                    }
+                }
+                "content_block_delta" => {
+                    assert_eq!(index, open, "增量写入了未打开的块");
+                    if let Some(partial) = event.data["delta"]["partial_json"].as_str() {
+                        let tool = tools.iter_mut().find(|t| Some(t.0) == index).unwrap();
+                        tool.2.push_str(partial);
+                    }
+                }
+                "content_block_stop" => {
+                    assert_eq!(index, open);
+                    open = None;
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(open, None);
+        tools
+            .into_iter()
+            .map(|(index, id, input)| (index, id, serde_json::from_str(&input).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_interleaved_parallel_tool_uses() {
+        let events = replay(
+            r#"
+            assistantResponseEvent {"content":"Reading both files."}
+            toolUseEvent {"name":"Read","toolUseId":"tooluse_a","input":""}
+            toolUseEvent {"name":"Read","toolUseId":"tooluse_b","input":""}
+            toolUseEvent {"name":"Read","toolUseId":"tooluse_a","input":"{\"file_path\": "}
+            toolUseEvent {"name":"Read","toolUseId":"tooluse_b","input":"{\"file_path\": \"/src/b.rs\"}"}
+            toolUseEvent {"name":"Read","toolUseId":"tooluse_a","input":"\"/src/a.rs\"}"}
+            toolUseEvent {"name":"Read","toolUseId":"tooluse_b","stop":true}
+            toolUseEvent {"name":"Read","toolUseId":"tooluse_a","stop":true}
+            toolUseEvent {"name":"Read","toolUseId":"tooluse_a","stop":true}
+            "#,
+        );
+
+        let tools = tool_inputs(&events);
+        assert_eq!(tools.len(), 2);
+        assert_eq!(tools[0], (1, "tooluse_a".to_string(), json!({ "file_path": "/src/a.rs" })));
+        assert_eq!(tools[1], (2, "tooluse_b".to_string(), json!({ "file_path": "/src/b.rs" })));
+        let delta = events.iter().find(|e| e.event == "message_delta").unwrap();
+        assert_eq!(delta.data["delta"]["stop_reason"], "tool_use");
+    }
+
+    #[test]
+    fn test_interleaved_tool_uses_flushed_at_stream_end() {
+        let events = replay(
+            r#"
+            toolUseEvent {"name":"Bash","toolUseId":"tooluse_a","input":"{\"command\": \"ls"}
+            toolUseEvent {"name":"Glob","toolUseId":"tooluse_b","input":"{\"pattern\": \"*.rs\"}","stop":true}
+            toolUseEvent {"name":"Grep","toolUseId":"tooluse_c","input":"{\"pattern\": "}
+            toolUseEvent {"name":"Bash","toolUseId":"tooluse_a","input":" -la\"}","stop":true}
+            toolUseEvent {"name":"Grep","toolUseId":"tooluse_c","input":"\"fn main"}
+            "#,
+        );
+
+        let tools = tool_inputs(&events);
+        let ids: Vec<_> = tools.iter().map(|t| (t.0, t.1.as_str())).collect();
+        assert_eq!(ids, vec![(1, "tooluse_a"), (2, "tooluse_b"), (3, "tooluse_c")]);
+        assert_eq!(tools[0].2, json!({ "command": "ls -la" }));
+        assert_eq!(tools[1].2, json!({ "pattern": "*.rs" }));
+        // 流结束时未收到 stop 的工具输入被补全
+        assert_eq!(tools[2].2, json!({ "pattern": "fn main" }));
+    }
+
     #[test]
     fn test_tool_use_flushes_pending_thinking_buffer_text_before_tool_block() {
         // thinking 模式下，短文本可能被暂存在 thinking_buffer 以等待 `<thinking>` 的跨 chunk 匹配。
@@ -1453,4 +2123,66 @@ mod tests {
             "`</thinking>` should be filtered during final flush"
         );
     }
+
+    #[test]
+    fn test_thinking_block_closes_with_signature_delta() {
+        let mut ctx = StreamContext::new_with_thinking("test-model", 1, true);
+        let _initial_events = ctx.generate_initial_events();
+
+        let mut all_events = Vec::new();
+        all_events.extend(ctx.process_assistant_response("<thinking>abc</thinking>\n\nhello"));
+        all_events.extend(ctx.generate_final_events());
+
+        let signature_pos = all_events
+            .iter()
+            .position(|e| e.data["delta"]["type"] == "signature_delta")
+            .expect("thinking block should carry a signature_delta");
+        assert_eq!(all_events[signature_pos].data["index"], 0);
+        assert_eq!(all_events[signature_pos].data["delta"]["signature"], thinking_signature("abc"));
+
+        let stop_pos = all_events
+            .iter()
+            .position(|e| e.event == "content_block_stop" && e.data["index"] == 0)
+            .unwrap();
+        assert!(signature_pos < stop_pos);
+    }
+
+    #[test]
+    fn test_thinking_budget_truncates_deltas() {
+        let mut ctx = StreamContext::new_with_thinking("test-model", 1, true);
+        ctx.thinking_budget = Some(2);
+        let _initial_events = ctx.generate_initial_events();
+
+        let mut all_events = Vec::new();
+        all_events.extend(ctx.process_assistant_response("<thinking>"));
+        all_events.extend(ctx.process_assistant_response(&"word ".repeat(50)));
+        all_events.extend(ctx.process_assistant_response("</thinking>\n\ndone"));
+        all_events.extend(ctx.generate_final_events());
+
+        let thinking: String = all_events
+            .iter()
+            .filter(|e| e.data["delta"]["type"] == "thinking_delta")
+            .filter_map(|e| e.data["delta"]["thinking"].as_str())
+            .collect();
+        assert!(!thinking.is_empty());
+        assert!(estimate_tokens(&thinking) <= 2);
+        assert!(ctx.thinking_tokens <= 2);
+    }
+
+    #[test]
+    fn test_split_thinking_for_non_stream_response() {
+        let (thinking, text) = split_thinking("<thinking>plan</thinking>\n\nanswer");
+        assert_eq!(thinking.as_deref(), Some("plan"));
+        assert_eq!(text, "answer");
+
+        // 被引用的标签不是 thinking 块
+        let (thinking, text) = split_thinking("use `<thinking>` tags");
+        assert!(thinking.is_none());
+        assert_eq!(text, "use `<thinking>` tags");
+
+        // 输出被截断、没有结束标签
+        let (thinking, text) = split_thinking("<thinking>partial");
+        assert_eq!(thinking.as_deref(), Some("partial"));
+        assert!(text.is_empty());
+    }
 }
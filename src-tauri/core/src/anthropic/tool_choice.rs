@@ -0,0 +1,159 @@
+//! `tool_choice` 语义
+//!
+//! Kiro 的 conversationState 没有 tool_choice 字段，网关分两层实现 Anthropic 的语义：
+//! - 转换时：`none` 不发送请求中的工具定义，`tool` 只发送指定的工具，并在当前消息末尾追加指令；
+//! - 输出时：`any` / `tool` 在模型调用了工具时丢弃文本内容，`none` 与 `tool` 丢弃不允许的工具调用。
+//!
+//! 模型在 `any` / `tool` 下仍未调用任何工具时，照常返回被暂扣的文本，而不是返回空响应。
+
+use serde_json::Value;
+
+use super::types::MessagesRequest;
+
+/// 工具选择策略
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToolChoice {
+    /// 由模型决定（默认）
+    Auto,
+    /// 必须调用至少一个工具
+    Any,
+    /// 必须调用指定的工具
+    Tool(String),
+    /// 不调用工具
+    None,
+}
+
+impl ToolChoice {
+    /// 解析并校验请求中的 `tool_choice`（未设置时返回 None）
+    pub fn from_request(req: &MessagesRequest) -> Result<Option<Self>, String> {
+        let Some(value) = req.tool_choice.as_ref().filter(|v| !v.is_null()) else {
+            return Ok(None);
+        };
+        let choice = Self::parse(value)?;
+
+        let tools = req.tools.as_deref().unwrap_or_default();
+        match &choice {
+            ToolChoice::Any if tools.is_empty() => {
+                return Err("tool_choice 为 any 时必须提供 tools".to_string());
+            }
+            ToolChoice::Tool(name) if !tools.iter().any(|t| &t.name == name) => {
+                return Err(format!("tool_choice 指定的工具不存在: {}", name));
+            }
+            _ => {}
+        }
+        Ok(Some(choice))
+    }
+
+    fn parse(value: &Value) -> Result<Self, String> {
+        let choice_type = value
+            .get("type")
+            .and_then(Value::as_str)
+            .ok_or_else(|| "tool_choice 缺少 type 字段".to_string())?;
+        match choice_type {
+            "auto" => Ok(ToolChoice::Auto),
+            "any" => Ok(ToolChoice::Any),
+            "none" => Ok(ToolChoice::None),
+            "tool" => value
+                .get("name")
+                .and_then(Value::as_str)
+                .filter(|name| !name.is_empty())
+                .map(|name| ToolChoice::Tool(name.to_string()))
+                .ok_or_else(|| "tool_choice 为 tool 时必须指定 name".to_string()),
+            other => Err(format!("不支持的 tool_choice 类型: {}", other)),
+        }
+    }
+
+    /// 追加到当前消息末尾的指令（`auto` 不需要）
+    pub fn instruction(&self) -> Option<String> {
+        match self {
+            ToolChoice::Auto => None,
+            ToolChoice::Any => Some(
+                "You must respond by calling at least one of the available tools. Do not reply with plain text."
+                    .to_string(),
+            ),
+            ToolChoice::Tool(name) => Some(format!(
+                "You must respond by calling the `{}` tool. Do not reply with plain text and do not call any other tool.",
+                name
+            )),
+            ToolChoice::None => {
+                Some("Do not call any tools in this response. Reply with text only.".to_string())
+            }
+        }
+    }
+
+    /// 是否需要发送请求中的工具定义
+    pub fn sends_tool(&self, name: &str) -> bool {
+        match self {
+            ToolChoice::Auto | ToolChoice::Any => true,
+            ToolChoice::Tool(forced) => forced == name,
+            ToolChoice::None => false,
+        }
+    }
+
+    /// 是否允许向客户端输出该工具调用
+    pub fn allows_tool(&self, name: &str) -> bool {
+        match self {
+            ToolChoice::Auto | ToolChoice::Any => true,
+            // Kiro 匹配工具名称时忽略大小写
+            ToolChoice::Tool(forced) => forced.eq_ignore_ascii_case(name),
+            ToolChoice::None => false,
+        }
+    }
+
+    /// 调用了工具时是否丢弃文本内容
+    pub fn suppresses_text(&self) -> bool {
+        matches!(self, ToolChoice::Any | ToolChoice::Tool(_))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request(tool_choice: Value) -> MessagesRequest {
+        serde_json::from_value(json!({
+            "model": "claude-sonnet-4-5-20250929",
+            "max_tokens": 1024,
+            "messages": [{ "role": "user", "content": "hi" }],
+            "tools": [{ "name": "Bash", "description": "run", "input_schema": { "type": "object" } }],
+            "tool_choice": tool_choice
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_validate() {
+        assert_eq!(ToolChoice::from_request(&request(Value::Null)).unwrap(), None);
+        assert_eq!(
+            ToolChoice::from_request(&request(json!({ "type": "auto" }))).unwrap(),
+            Some(ToolChoice::Auto)
+        );
+        assert_eq!(
+            ToolChoice::from_request(&request(json!({ "type": "tool", "name": "Bash" }))).unwrap(),
+            Some(ToolChoice::Tool("Bash".to_string()))
+        );
+        assert!(ToolChoice::from_request(&request(json!({ "type": "tool", "name": "Grep" }))).is_err());
+        assert!(ToolChoice::from_request(&request(json!({ "type": "required" }))).is_err());
+
+        let mut no_tools = request(json!({ "type": "any" }));
+        no_tools.tools = None;
+        assert!(ToolChoice::from_request(&no_tools).is_err());
+    }
+
+    #[test]
+    fn test_enforcement_rules() {
+        let forced = ToolChoice::Tool("Bash".to_string());
+        assert!(forced.sends_tool("Bash") && !forced.sends_tool("Grep"));
+        assert!(forced.allows_tool("bash") && !forced.allows_tool("Grep"));
+        assert!(forced.suppresses_text());
+
+        assert!(!ToolChoice::None.sends_tool("Bash"));
+        assert!(!ToolChoice::None.allows_tool("Bash"));
+        assert!(!ToolChoice::None.suppresses_text());
+
+        assert!(ToolChoice::Any.suppresses_text());
+        assert!(ToolChoice::Auto.instruction().is_none());
+        assert!(ToolChoice::Any.instruction().is_some());
+    }
+}
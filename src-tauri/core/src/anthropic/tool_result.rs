@@ -0,0 +1,149 @@
+//! 工具结果内容规范化
+//!
+//! 客户端发送的 `tool_result.content` 形态各异：纯字符串（Aider、Cline 的大部分工具）、
+//! 文本块数组（Claude Code）、夹带截图的图片块（Cline 浏览器工具、Claude Code 读取图片），
+//! 以及单个块对象或嵌套的 `tool_result`。Kiro 的 toolResults 只接受文本，这里按顺序拼接所有文本，
+//! 图片提取出来随所在消息的 images 一起发送，并在文本中原位留下占位符。
+
+use serde_json::Value;
+
+use super::images;
+use super::types::ImageSource;
+use crate::kiro::model::requests::conversation::KiroImage;
+
+/// 图片在工具结果文本中的占位符
+pub const IMAGE_PLACEHOLDER: &str = "[image]";
+
+/// 嵌套块的最大展开深度
+const MAX_DEPTH: usize = 8;
+
+/// 规范化后的工具结果
+#[derive(Debug, Default)]
+pub struct NormalizedToolResult {
+    pub text: String,
+    pub images: Vec<KiroImage>,
+}
+
+/// 规范化 `tool_result.content`（图片无效时返回错误信息）
+pub fn normalize(content: Option<&Value>) -> Result<NormalizedToolResult, String> {
+    let mut parts = Vec::new();
+    let mut images = Vec::new();
+    if let Some(content) = content {
+        collect(content, 0, &mut parts, &mut images)?;
+    }
+    Ok(NormalizedToolResult {
+        text: parts.join("\n"),
+        images,
+    })
+}
+
+fn collect(value: &Value, depth: usize, parts: &mut Vec<String>, images: &mut Vec<KiroImage>) -> Result<(), String> {
+    match value {
+        Value::Null => {}
+        Value::String(s) => parts.push(s.clone()),
+        Value::Bool(_) | Value::Number(_) => parts.push(value.to_string()),
+        Value::Array(items) if depth < MAX_DEPTH => {
+            for item in items {
+                collect(item, depth + 1, parts, images)?;
+            }
+        }
+        Value::Object(block) if depth < MAX_DEPTH => {
+            let block_type = block.get("type").and_then(Value::as_str).unwrap_or_default();
+            if block_type == "image" {
+                let source: ImageSource = block
+                    .get("source")
+                    .cloned()
+                    .and_then(|s| serde_json::from_value(s).ok())
+                    .ok_or_else(|| "工具结果中的图片缺少有效的 source".to_string())?;
+                images.push(images::to_kiro_image(&source)?);
+                parts.push(IMAGE_PLACEHOLDER.to_string());
+            } else if let Some(text) = block.get("text").and_then(Value::as_str) {
+                parts.push(text.to_string());
+            } else if let Some(inner) = block.get("content") {
+                // 嵌套的 tool_result 或其他容器块
+                collect(inner, depth + 1, parts, images)?;
+            } else {
+                // 未知块原样保留为 JSON，避免丢失信息
+                parts.push(value.to_string());
+            }
+        }
+        _ => parts.push(value.to_string()),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const PNG_1X1: &str = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA60e6kgAAAABJRU5ErkJggg==";
+
+    fn image_block() -> Value {
+        json!({ "type": "image", "source": { "type": "base64", "media_type": "image/png", "data": PNG_1X1 } })
+    }
+
+    #[test]
+    fn test_claude_code_text_blocks_with_image() {
+        let content = json!([
+            { "type": "text", "text": "Read image file" },
+            image_block()
+        ]);
+        let result = normalize(Some(&content)).unwrap();
+        assert_eq!(result.text, "Read image file\n[image]");
+        assert_eq!(result.images.len(), 1);
+    }
+
+    #[test]
+    fn test_cline_string_and_browser_screenshot() {
+        let result = normalize(Some(&json!("The file was saved."))).unwrap();
+        assert_eq!(result.text, "The file was saved.");
+        assert!(result.images.is_empty());
+
+        let content = json!([
+            { "type": "text", "text": "The browser action has been executed." },
+            image_block(),
+            { "type": "text", "text": "Console logs:\n(No new logs)" }
+        ]);
+        let result = normalize(Some(&content)).unwrap();
+        assert_eq!(
+            result.text,
+            "The browser action has been executed.\n[image]\nConsole logs:\n(No new logs)"
+        );
+        assert_eq!(result.images.len(), 1);
+    }
+
+    #[test]
+    fn test_aider_single_block_and_missing_content() {
+        let result = normalize(Some(&json!({ "type": "text", "text": "ok" }))).unwrap();
+        assert_eq!(result.text, "ok");
+        assert_eq!(normalize(None).unwrap().text, "");
+        assert_eq!(normalize(Some(&Value::Null)).unwrap().text, "");
+        assert_eq!(normalize(Some(&json!(42))).unwrap().text, "42");
+    }
+
+    #[test]
+    fn test_nested_and_unknown_blocks() {
+        let content = json!([
+            { "type": "tool_result", "tool_use_id": "t1", "content": [
+                { "type": "text", "text": "inner" },
+                image_block()
+            ]},
+            ["plain", { "type": "text", "text": "deep" }],
+            { "type": "search_result", "url": "https://example.com" }
+        ]);
+        let result = normalize(Some(&content)).unwrap();
+        assert_eq!(
+            result.text,
+            "inner\n[image]\nplain\ndeep\n{\"type\":\"search_result\",\"url\":\"https://example.com\"}"
+        );
+        assert_eq!(result.images.len(), 1);
+    }
+
+    #[test]
+    fn test_invalid_image_is_rejected() {
+        let content = json!([{ "type": "image", "source": { "type": "base64", "media_type": "image/png", "data": "bm90IGFuIGltYWdl" } }]);
+        assert!(normalize(Some(&content)).is_err());
+        assert!(normalize(Some(&json!([{ "type": "image" }]))).is_err());
+    }
+}
@@ -3,6 +3,8 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::error_code::ErrorCode;
+
 // === 错误响应 ===
 
 /// API 错误响应
@@ -17,19 +19,29 @@ pub struct ErrorDetail {
     #[serde(rename = "type")]
     pub error_type: String,
     pub message: String,
+    /// 稳定的机器可读错误码
+    pub code: ErrorCode,
 }
 
 impl ErrorResponse {
-    /// 创建新的错误响应
+    /// 创建新的错误响应（错误码按类型推断）
     pub fn new(error_type: impl Into<String>, message: impl Into<String>) -> Self {
+        let error_type = error_type.into();
         Self {
             error: ErrorDetail {
-                error_type: error_type.into(),
+                code: ErrorCode::from_error_type(&error_type),
+                error_type,
                 message: message.into(),
             },
         }
     }
 
+    /// 指定错误码
+    pub fn with_code(mut self, code: ErrorCode) -> Self {
+        self.error.code = code;
+        self
+    }
+
     /// 创建认证错误响应
     pub fn authentication_error() -> Self {
         Self::new("authentication_error", "Invalid API key")
@@ -39,29 +51,49 @@ impl ErrorResponse {
 // === Models 端点类型 ===
 
 /// 模型信息
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Model {
     pub id: String,
     pub object: String,
     pub created: i64,
     pub owned_by: String,
+    /// 发布时间（RFC 3339，Anthropic 格式）
+    pub created_at: String,
     pub display_name: String,
     #[serde(rename = "type")]
     pub model_type: String,
     pub max_tokens: i32,
 }
 
+/// 模型列表查询参数（Anthropic 分页）
+#[derive(Debug, Default, Deserialize)]
+pub struct ListModelsQuery {
+    pub limit: Option<usize>,
+    pub after_id: Option<String>,
+    pub before_id: Option<String>,
+}
+
+/// 创建消息的查询参数
+#[derive(Debug, Default, Deserialize)]
+pub struct MessagesQuery {
+    /// 流式响应格式：默认 Anthropic SSE，`openai` 时转换为 `chat.completion.chunk`
+    pub format: Option<String>,
+}
+
 /// 模型列表响应
 #[derive(Debug, Serialize)]
 pub struct ModelsResponse {
     pub object: String,
     pub data: Vec<Model>,
+    pub has_more: bool,
+    pub first_id: Option<String>,
+    pub last_id: Option<String>,
 }
 
 // === Messages 端点类型 ===
 
 /// 最大思考预算 tokens
-const MAX_BUDGET_TOKENS: i32 = 24576;
+pub(crate) const MAX_BUDGET_TOKENS: i32 = 24576;
 
 /// Thinking 配置
 #[derive(Debug, Deserialize, Clone)]
@@ -105,8 +137,17 @@ pub struct MessagesRequest {
     pub tools: Option<Vec<Tool>>,
     pub tool_choice: Option<serde_json::Value>,
     pub thinking: Option<Thinking>,
+    /// 停止序列（Kiro 不支持，由网关在输出中截断）
+    pub stop_sequences: Option<Vec<String>>,
+    /// 采样参数（Kiro 的 conversationState 不支持，转换时忽略并记录警告）
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub top_k: Option<u32>,
     /// Claude Code 请求中的 metadata，包含 session 信息
     pub metadata: Option<Metadata>,
+    /// `anthropic-beta` 请求头声明的特性（由 handler 填充，供转换层使用）
+    #[serde(skip)]
+    pub betas: Vec<String>,
 }
 
 /// 消息
@@ -219,13 +260,17 @@ pub struct ContentBlock {
     pub source: Option<ImageSource>,
 }
 
-/// 图片数据源
+/// 图片数据源（`base64` 或 `url`）
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ImageSource {
     #[serde(rename = "type")]
     pub source_type: String,
-    pub media_type: String,
-    pub data: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
 }
 
 // === Count Tokens 端点类型 ===
@@ -0,0 +1,139 @@
+//! `anthropic-version` / `anthropic-beta` 请求头处理
+//!
+//! 解析客户端声明的 API 版本与 beta 特性：已知与本网关不兼容的版本、
+//! 依赖 Anthropic 服务端能力（Kiro 无法模拟）的 beta 直接拒绝，
+//! 其余 beta 随请求传给转换层，由转换层按需调整行为。
+
+use axum::http::HeaderMap;
+
+/// 本网关实现的 API 版本
+pub const SUPPORTED_VERSION: &str = "2023-06-01";
+
+/// 已知不兼容的 API 版本（旧版流式事件格式与本网关输出不同）
+const INCOMPATIBLE_VERSIONS: &[&str] = &["2023-01-01"];
+
+/// 依赖 Anthropic 服务端能力的 beta 前缀（请求中会出现 Kiro 无法处理的字段或内容块）
+const UNSUPPORTED_BETA_PREFIXES: &[&str] = &[
+    "mcp-client-",
+    "files-api-",
+    "code-execution-",
+    "computer-use-",
+];
+
+/// 交错思考：thinking 预算是多轮工具调用间的总预算，可以超过 max_tokens
+pub const BETA_INTERLEAVED_THINKING: &str = "interleaved-thinking-";
+
+/// 请求头解析失败原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HeaderRejection {
+    /// API 版本不兼容
+    IncompatibleVersion(String),
+    /// beta 特性不受支持
+    UnsupportedBeta(String),
+}
+
+impl std::fmt::Display for HeaderRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HeaderRejection::IncompatibleVersion(version) => write!(
+                f,
+                "anthropic-version {} is not supported by this gateway, use {}",
+                version, SUPPORTED_VERSION
+            ),
+            HeaderRejection::UnsupportedBeta(beta) => {
+                write!(f, "anthropic-beta {} is not supported by this gateway", beta)
+            }
+        }
+    }
+}
+
+/// 客户端声明的 API 版本与 beta 特性
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApiHeaders {
+    pub version: Option<String>,
+    pub betas: Vec<String>,
+}
+
+impl ApiHeaders {
+    /// 从请求头解析并校验
+    ///
+    /// `anthropic-beta` 可以重复出现，也可以用逗号分隔多个值
+    pub fn from_headers(headers: &HeaderMap) -> Result<Self, HeaderRejection> {
+        let version = headers
+            .get("anthropic-version")
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+
+        if let Some(version) = &version {
+            if INCOMPATIBLE_VERSIONS.contains(&version.as_str()) {
+                return Err(HeaderRejection::IncompatibleVersion(version.clone()));
+            }
+        }
+
+        let mut betas: Vec<String> = Vec::new();
+        for value in headers.get_all("anthropic-beta") {
+            let Ok(value) = value.to_str() else { continue };
+            for beta in value.split(',').map(str::trim).filter(|b| !b.is_empty()) {
+                let beta = beta.to_lowercase();
+                if UNSUPPORTED_BETA_PREFIXES.iter().any(|p| beta.starts_with(p)) {
+                    return Err(HeaderRejection::UnsupportedBeta(beta));
+                }
+                if !betas.contains(&beta) {
+                    betas.push(beta);
+                }
+            }
+        }
+
+        Ok(Self { version, betas })
+    }
+}
+
+/// 检查 beta 列表中是否包含指定特性（按前缀匹配，忽略日期后缀）
+pub fn has_beta(betas: &[String], prefix: &str) -> bool {
+    betas.iter().any(|b| b.starts_with(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_parses_betas_from_repeated_and_comma_separated_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("anthropic-version", HeaderValue::from_static("2023-06-01"));
+        headers.append(
+            "anthropic-beta",
+            HeaderValue::from_static("claude-code-20250219, interleaved-thinking-2025-05-14"),
+        );
+        headers.append("anthropic-beta", HeaderValue::from_static("Claude-Code-20250219"));
+
+        let parsed = ApiHeaders::from_headers(&headers).unwrap();
+        assert_eq!(parsed.version.as_deref(), Some("2023-06-01"));
+        assert_eq!(
+            parsed.betas,
+            vec!["claude-code-20250219", "interleaved-thinking-2025-05-14"]
+        );
+        assert!(has_beta(&parsed.betas, BETA_INTERLEAVED_THINKING));
+    }
+
+    #[test]
+    fn test_rejects_incompatible_version_and_server_side_betas() {
+        let mut headers = HeaderMap::new();
+        headers.insert("anthropic-version", HeaderValue::from_static("2023-01-01"));
+        assert!(matches!(
+            ApiHeaders::from_headers(&headers),
+            Err(HeaderRejection::IncompatibleVersion(_))
+        ));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("anthropic-beta", HeaderValue::from_static("mcp-client-2025-04-04"));
+        assert_eq!(
+            ApiHeaders::from_headers(&headers),
+            Err(HeaderRejection::UnsupportedBeta("mcp-client-2025-04-04".to_string()))
+        );
+
+        assert_eq!(ApiHeaders::from_headers(&HeaderMap::new()), Ok(ApiHeaders::default()));
+    }
+}
@@ -0,0 +1,421 @@
+//! 多租户 API Key 管理
+//!
+//! 将客户端 API Key 解析为租户，执行模型白名单、每分钟请求数和月度 token 配额检查，
+//! 并按 Key 统计用量。配置中的 `apiKey` 仍作为不受限的主密钥。
+
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use chrono::Local;
+use lazy_static::lazy_static;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+
+use crate::common::auth;
+use crate::model::config::{ApiKeyConfig, RequestTransform};
+
+/// 用量文件写盘的最小间隔
+const USAGE_SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 已解析的租户（放入请求 extensions 供 handler 使用）
+#[derive(Debug, Clone)]
+pub struct Tenant {
+    pub id: String,
+    pub name: String,
+    pub allowed_models: Vec<String>,
+    /// 该 Key 的请求改写
+    pub request_transform: Option<RequestTransform>,
+}
+
+impl Tenant {
+    fn from_config(k: &ApiKeyConfig) -> Self {
+        Self {
+            id: k.id.clone(),
+            name: k.name.clone(),
+            allowed_models: k.allowed_models.clone(),
+            request_transform: k.request_transform.clone(),
+        }
+    }
+
+    /// 检查模型是否在白名单内（白名单为空表示不限制）
+    pub fn allows_model(&self, model: &str) -> bool {
+        if self.allowed_models.is_empty() {
+            return true;
+        }
+        let model = model.to_lowercase();
+        self.allowed_models
+            .iter()
+            .any(|pattern| matches_model_pattern(&pattern.to_lowercase(), &model))
+    }
+}
+
+/// 白名单条目匹配：不含 `*` 时要求完全相同，`*` 匹配任意字符（如 `claude-sonnet-4-5*`）
+fn matches_model_pattern(pattern: &str, model: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = model.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        // 没有通配符
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// 准入检查失败原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rejection {
+    /// 超过每分钟请求数
+    RateLimited { retry_after_secs: u64 },
+    /// 月度配额已用完
+    QuotaExceeded,
+}
+
+/// 单个 Key 的月度用量（持久化）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonthlyUsage {
+    /// 统计月份（YYYY-MM）
+    pub month: String,
+    pub request_count: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+impl MonthlyUsage {
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens
+    }
+}
+
+/// 用量快照（Admin API）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyUsageSnapshot {
+    #[serde(flatten)]
+    pub usage: MonthlyUsage,
+    /// 最近一分钟请求数
+    pub requests_last_minute: usize,
+}
+
+#[derive(Default)]
+struct UsageState {
+    monthly: HashMap<String, MonthlyUsage>,
+    minute_windows: HashMap<String, VecDeque<Instant>>,
+    dirty: bool,
+    last_saved: Option<Instant>,
+}
+
+/// API Key 注册表
+pub struct ApiKeyRegistry {
+    keys: RwLock<Vec<ApiKeyConfig>>,
+    usage: Mutex<UsageState>,
+    usage_path: RwLock<Option<PathBuf>>,
+}
+
+pub(crate) fn current_month() -> String {
+    Local::now().format("%Y-%m").to_string()
+}
+
+impl ApiKeyRegistry {
+    pub fn new() -> Self {
+        Self {
+            keys: RwLock::new(Vec::new()),
+            usage: Mutex::new(UsageState::default()),
+            usage_path: RwLock::new(None),
+        }
+    }
+
+    /// 加载 Key 配置，并从用量文件恢复本月用量
+    pub fn load(&self, keys: Vec<ApiKeyConfig>, usage_path: Option<PathBuf>) {
+        if let Some(path) = &usage_path {
+            if let Ok(content) = std::fs::read_to_string(path) {
+                match serde_json::from_str::<HashMap<String, MonthlyUsage>>(&content) {
+                    Ok(monthly) => self.usage.lock().monthly = monthly,
+                    Err(e) => tracing::warn!("读取 API Key 用量文件失败: {}", e),
+                }
+            }
+        }
+        *self.usage_path.write() = usage_path;
+        self.set_keys(keys);
+    }
+
+    /// 替换 Key 配置（Admin API 修改后调用）
+    pub fn set_keys(&self, keys: Vec<ApiKeyConfig>) {
+        *self.keys.write() = keys;
+    }
+
+    /// 将客户端提供的 Key 解析为租户（已禁用的 Key 视为无效）
+    pub fn resolve(&self, key: &str) -> Option<Tenant> {
+        let keys = self.keys.read();
+        // 遍历全部 Key，避免提前返回泄露匹配位置
+        let mut found = None;
+        for k in keys.iter() {
+            if auth::constant_time_eq(key, &k.key) && k.enabled && found.is_none() {
+                found = Some(Tenant::from_config(k));
+            }
+        }
+        found
+    }
+
+    /// 按 ID 查找租户（已禁用的 Key 返回 None）
+    pub fn tenant(&self, id: &str) -> Option<Tenant> {
+        self.keys.read().iter().find(|k| k.id == id && k.enabled).map(Tenant::from_config)
+    }
+
+    /// 准入检查：每分钟请求数和月度配额，通过时计入一次请求
+    pub fn admit(&self, tenant_id: &str) -> Result<(), Rejection> {
+        let (rpm, quota) = {
+            let keys = self.keys.read();
+            match keys.iter().find(|k| k.id == tenant_id) {
+                Some(k) => (k.rate_limit_rpm, k.monthly_token_quota),
+                None => (None, None),
+            }
+        };
+
+        let month = current_month();
+        let now = Instant::now();
+        let mut usage = self.usage.lock();
+
+        if let Some(quota) = quota {
+            let used = usage
+                .monthly
+                .get(tenant_id)
+                .filter(|u| u.month == month)
+                .map(|u| u.total_tokens())
+                .unwrap_or(0);
+            if used >= quota {
+                return Err(Rejection::QuotaExceeded);
+            }
+        }
+
+        let window = usage
+            .minute_windows
+            .entry(tenant_id.to_string())
+            .or_default();
+        while window
+            .front()
+            .is_some_and(|t| now.duration_since(*t) >= Duration::from_secs(60))
+        {
+            window.pop_front();
+        }
+        if let Some(rpm) = rpm {
+            if window.len() >= rpm as usize {
+                let retry_after_secs = window
+                    .front()
+                    .map(|t| 60u64.saturating_sub(now.duration_since(*t).as_secs()))
+                    .unwrap_or(60)
+                    .max(1);
+                return Err(Rejection::RateLimited { retry_after_secs });
+            }
+        }
+        window.push_back(now);
+
+        let entry = Self::monthly_entry(&mut usage.monthly, tenant_id, &month);
+        entry.request_count += 1;
+        usage.dirty = true;
+        Ok(())
+    }
+
+    /// 记录 token 用量
+    pub fn record_usage(&self, tenant_id: &str, input_tokens: i32, output_tokens: i32) {
+        let month = current_month();
+        let mut usage = self.usage.lock();
+        let entry = Self::monthly_entry(&mut usage.monthly, tenant_id, &month);
+        entry.input_tokens += input_tokens.max(0) as u64;
+        entry.output_tokens += output_tokens.max(0) as u64;
+        usage.dirty = true;
+
+        let due = usage
+            .last_saved
+            .is_none_or(|t| t.elapsed() >= USAGE_SAVE_INTERVAL);
+        if due {
+            self.save_locked(&mut usage);
+        }
+    }
+
+    /// 获取各 Key 本月用量
+    pub fn usage_snapshot(&self) -> HashMap<String, ApiKeyUsageSnapshot> {
+        let month = current_month();
+        let now = Instant::now();
+        let usage = self.usage.lock();
+        self.keys
+            .read()
+            .iter()
+            .map(|k| {
+                let monthly = usage
+                    .monthly
+                    .get(&k.id)
+                    .filter(|u| u.month == month)
+                    .cloned()
+                    .unwrap_or_else(|| MonthlyUsage {
+                        month: month.clone(),
+                        ..Default::default()
+                    });
+                let requests_last_minute = usage
+                    .minute_windows
+                    .get(&k.id)
+                    .map(|w| {
+                        w.iter()
+                            .filter(|t| now.duration_since(**t) < Duration::from_secs(60))
+                            .count()
+                    })
+                    .unwrap_or(0);
+                (
+                    k.id.clone(),
+                    ApiKeyUsageSnapshot {
+                        usage: monthly,
+                        requests_last_minute,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// 立即保存未落盘的用量
+    pub fn flush(&self) {
+        let mut usage = self.usage.lock();
+        self.save_locked(&mut usage);
+    }
+
+    /// 删除 Key 的用量记录
+    pub fn remove_usage(&self, tenant_id: &str) {
+        let mut usage = self.usage.lock();
+        usage.monthly.remove(tenant_id);
+        usage.minute_windows.remove(tenant_id);
+        usage.dirty = true;
+        self.save_locked(&mut usage);
+    }
+
+    /// 取得（必要时新建或跨月重置）月度用量条目
+    fn monthly_entry<'a>(
+        monthly: &'a mut HashMap<String, MonthlyUsage>,
+        tenant_id: &str,
+        month: &str,
+    ) -> &'a mut MonthlyUsage {
+        let entry = monthly.entry(tenant_id.to_string()).or_default();
+        if entry.month != month {
+            *entry = MonthlyUsage {
+                month: month.to_string(),
+                ..Default::default()
+            };
+        }
+        entry
+    }
+
+    fn save_locked(&self, usage: &mut UsageState) {
+        if !usage.dirty {
+            return;
+        }
+        let Some(path) = self.usage_path.read().clone() else {
+            return;
+        };
+        match serde_json::to_string_pretty(&usage.monthly) {
+            Ok(json) => {
+                if let Err(e) = std::fs::write(&path, json) {
+                    tracing::warn!("保存 API Key 用量失败: {}", e);
+                    return;
+                }
+                usage.dirty = false;
+                usage.last_saved = Some(Instant::now());
+            }
+            Err(e) => tracing::warn!("序列化 API Key 用量失败: {}", e),
+        }
+    }
+}
+
+impl Default for ApiKeyRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+lazy_static! {
+    /// 全局 API Key 注册表
+    pub static ref API_KEY_REGISTRY: ApiKeyRegistry = ApiKeyRegistry::new();
+}
+
+/// 初始化全局注册表，用量文件与配置文件位于同一目录
+pub fn init(keys: Vec<ApiKeyConfig>, config_path: &std::path::Path) {
+    let usage_path = config_path
+        .parent()
+        .map(|dir| dir.join("api_key_usage.json"));
+    API_KEY_REGISTRY.load(keys, usage_path);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(id: &str, rpm: Option<u32>, quota: Option<u64>) -> ApiKeyConfig {
+        ApiKeyConfig {
+            id: id.to_string(),
+            name: id.to_string(),
+            key: format!("sk-{}", id),
+            enabled: true,
+            allowed_models: vec!["claude-sonnet-4-5*".to_string(), "claude-haiku-4-5".to_string()],
+            rate_limit_rpm: rpm,
+            monthly_token_quota: quota,
+            request_transform: None,
+        }
+    }
+
+    #[test]
+    fn test_resolve_and_model_whitelist() {
+        let registry = ApiKeyRegistry::new();
+        let mut disabled = key("b", None, None);
+        disabled.enabled = false;
+        registry.set_keys(vec![key("a", None, None), disabled]);
+
+        let tenant = registry.resolve("sk-a").unwrap();
+        assert_eq!(tenant.id, "a");
+        assert!(tenant.allows_model("claude-sonnet-4-5-20250929"));
+        assert!(tenant.allows_model("Claude-Haiku-4-5"));
+        assert!(!tenant.allows_model("claude-opus-4-5-20251101"));
+        // 不含通配符的条目不按包含匹配
+        assert!(!tenant.allows_model("claude-haiku-4-5-20251001"));
+        assert!(!tenant.allows_model("x-claude-sonnet-4-5"));
+
+        assert!(matches_model_pattern("claude-*-4-5", "claude-opus-4-5"));
+        assert!(!matches_model_pattern("claude-*-4-5", "claude-opus-4-5-thinking"));
+        assert!(!matches_model_pattern("ab*ba", "aba"));
+        assert!(registry.resolve("sk-b").is_none());
+        assert!(registry.resolve("sk-x").is_none());
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let registry = ApiKeyRegistry::new();
+        registry.set_keys(vec![key("a", Some(2), None)]);
+
+        assert!(registry.admit("a").is_ok());
+        assert!(registry.admit("a").is_ok());
+        assert!(matches!(
+            registry.admit("a"),
+            Err(Rejection::RateLimited { .. })
+        ));
+    }
+
+    #[test]
+    fn test_monthly_quota() {
+        let registry = ApiKeyRegistry::new();
+        registry.set_keys(vec![key("a", None, Some(100))]);
+
+        assert!(registry.admit("a").is_ok());
+        registry.record_usage("a", 60, 40);
+        assert_eq!(registry.admit("a"), Err(Rejection::QuotaExceeded));
+
+        let snapshot = registry.usage_snapshot();
+        assert_eq!(snapshot["a"].usage.total_tokens(), 100);
+        assert_eq!(snapshot["a"].usage.request_count, 1);
+    }
+}
@@ -0,0 +1,217 @@
+//! Admin 操作审计日志
+//!
+//! 记录所有通过 Admin API 发起的修改操作（非 GET 请求）：操作者、时间、来源 IP、操作与结果，
+//! 逐条追加写入配置目录下的 `audit.jsonl`（只追加，不改写），最近的记录同时保留在内存中
+//! 供 `GET /api/admin/audit` 查询。请求体不写入审计日志，避免泄露凭证等敏感信息。
+
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+
+/// 内存中保留的最近记录数（文件中的记录不受影响）
+const MAX_MEMORY_ENTRIES: usize = 2000;
+
+/// 单条审计记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Utc>,
+    /// 操作者（遮蔽后的 Admin UI 会话令牌或 Admin API Key）
+    pub actor: String,
+    /// 来源 IP
+    pub client_ip: Option<String>,
+    pub method: String,
+    /// 请求路径（相对 `/api/admin`）
+    pub path: String,
+    /// 操作描述（如"删除凭证"）
+    pub action: String,
+    /// 响应状态码
+    pub status: u16,
+}
+
+/// 审计日志
+pub struct AuditLog {
+    path: RwLock<Option<PathBuf>>,
+    entries: Mutex<VecDeque<AuditEntry>>,
+}
+
+impl AuditLog {
+    pub fn new() -> Self {
+        Self {
+            path: RwLock::new(None),
+            entries: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// 从文件加载最近的记录（跳过无法解析的行）
+    pub fn load(&self, path: Option<PathBuf>) {
+        let mut entries: VecDeque<AuditEntry> = VecDeque::new();
+        if let Some(content) = path.as_ref().and_then(|p| std::fs::read_to_string(p).ok()) {
+            for line in content.lines().filter(|l| !l.trim().is_empty()) {
+                match serde_json::from_str(line) {
+                    Ok(entry) => {
+                        entries.push_back(entry);
+                        if entries.len() > MAX_MEMORY_ENTRIES {
+                            entries.pop_front();
+                        }
+                    }
+                    Err(e) => tracing::warn!("跳过无法解析的审计记录: {}", e),
+                }
+            }
+        }
+        *self.path.write() = path;
+        *self.entries.lock() = entries;
+    }
+
+    /// 记录一条操作并追加写入文件
+    pub fn record(&self, entry: AuditEntry) {
+        if let Some(path) = self.path.read().as_ref() {
+            let written = serde_json::to_string(&entry).map_err(std::io::Error::from).and_then(|line| {
+                let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+                writeln!(file, "{}", line)
+            });
+            if let Err(e) = written {
+                tracing::warn!("写入审计日志失败: {}", e);
+            }
+        }
+
+        let mut entries = self.entries.lock();
+        entries.push_back(entry);
+        while entries.len() > MAX_MEMORY_ENTRIES {
+            entries.pop_front();
+        }
+    }
+
+    /// 查询最近的记录（按时间倒序），可选只返回指定时间之后的部分
+    pub fn query(&self, limit: usize, since: Option<DateTime<Utc>>) -> Vec<AuditEntry> {
+        self.entries
+            .lock()
+            .iter()
+            .rev()
+            .filter(|e| since.is_none_or(|since| e.timestamp >= since))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+}
+
+impl Default for AuditLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+lazy_static! {
+    /// 全局审计日志
+    pub static ref AUDIT_LOG: AuditLog = AuditLog::new();
+}
+
+/// 初始化全局审计日志，日志文件与配置文件位于同一目录
+pub fn init(config_path: &std::path::Path) {
+    let path = config_path.parent().map(|dir| dir.join("audit.jsonl"));
+    AUDIT_LOG.load(path);
+}
+
+/// 已知操作的描述（路径相对 `/api/admin`，`*` 匹配一段路径，按顺序首条命中生效）
+const ACTIONS: &[(&str, &str, &str)] = &[
+    ("POST", "/credentials", "添加凭证"),
+    ("POST", "/credentials/import", "批量导入凭证"),
+    ("POST", "/credentials/import-text", "从文本导入凭证"),
+    ("POST", "/credentials/import-local", "导入本机凭证"),
+    ("POST", "/credentials/discover/import", "导入发现的凭证"),
+    ("POST", "/credentials/export", "导出凭证"),
+    ("POST", "/credentials/import-encrypted", "导入加密凭证包"),
+    ("DELETE", "/credentials/batch", "批量删除凭证"),
+    ("POST", "/credentials/dedupe", "合并重复凭证"),
+    ("DELETE", "/credentials/*", "删除凭证"),
+    ("PUT", "/credentials/*", "编辑凭证"),
+    ("POST", "/credentials/*/disabled", "启用/禁用凭证"),
+    ("POST", "/credentials/*/reset", "重置凭证失败计数"),
+    ("POST", "/credentials/*/switch", "切换当前凭证"),
+    ("POST", "/credentials/*/machine-id", "重新生成凭证 machineId"),
+    ("POST", "/credentials/*/group", "修改凭证分组"),
+    ("PUT", "/groups/*/budget", "设置分组预算"),
+    ("POST", "/config", "修改配置"),
+    ("POST", "/config/model", "锁定模型"),
+    ("POST", "/kiro/profiles", "选择 Kiro Profile"),
+    ("PUT", "/redaction", "修改内容脱敏规则"),
+    ("PUT", "/post-process", "修改响应后处理规则"),
+    ("DELETE", "/transcripts", "删除对话记录"),
+    ("PUT", "/transcripts/config", "修改对话记录配置"),
+    ("POST", "/machine-id/backup", "备份系统机器码"),
+    ("POST", "/machine-id/restore", "恢复系统机器码"),
+    ("POST", "/machine-id/reset", "重置系统机器码"),
+    ("POST", "/proxy", "控制反代服务"),
+    ("POST", "/logs/clear", "清空日志"),
+    ("POST", "/admin-key/rotate", "轮换 Admin API Key"),
+    ("POST", "/backup/export", "导出备份"),
+    ("POST", "/backup/import", "导入备份"),
+];
+
+/// 按方法和路径描述操作，未知操作返回 `方法 路径`
+pub fn describe(method: &str, path: &str) -> String {
+    let segments: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+    ACTIONS
+        .iter()
+        .find(|(m, pattern, _)| {
+            let pattern: Vec<&str> = pattern.split('/').collect();
+            *m == method
+                && pattern.len() == segments.len()
+                && pattern.iter().zip(&segments).all(|(p, s)| *p == "*" || p == s)
+        })
+        .map(|(_, _, action)| action.to_string())
+        .unwrap_or_else(|| format!("{} {}", method, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, minutes_ago: i64) -> AuditEntry {
+        AuditEntry {
+            timestamp: Utc::now() - chrono::Duration::minutes(minutes_ago),
+            actor: "admin-ui".to_string(),
+            client_ip: Some("127.0.0.1".to_string()),
+            method: "DELETE".to_string(),
+            path: path.to_string(),
+            action: describe("DELETE", path),
+            status: 200,
+        }
+    }
+
+    #[test]
+    fn test_describe_actions() {
+        assert_eq!(describe("DELETE", "/credentials/3"), "删除凭证");
+        assert_eq!(describe("DELETE", "/credentials/batch"), "批量删除凭证");
+        assert_eq!(describe("POST", "/credentials/3/disabled"), "启用/禁用凭证");
+        assert_eq!(describe("POST", "/machine-id/reset"), "重置系统机器码");
+        assert_eq!(describe("PUT", "/alerts"), "PUT /alerts");
+    }
+
+    #[test]
+    fn test_record_appends_and_reloads() {
+        let dir = std::env::temp_dir().join(format!("kiro-audit-{}", uuid::Uuid::new_v4().simple()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("audit.jsonl");
+
+        let log = AuditLog::new();
+        log.load(Some(path.clone()));
+        log.record(entry("/credentials/1", 30));
+        log.record(entry("/credentials/2", 5));
+
+        let reloaded = AuditLog::new();
+        reloaded.load(Some(path.clone()));
+        let recent = reloaded.query(10, None);
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].path, "/credentials/2");
+        assert_eq!(reloaded.query(10, Some(Utc::now() - chrono::Duration::minutes(10))).len(), 1);
+        assert_eq!(reloaded.query(1, None).len(), 1);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,316 @@
+//! 网关状态备份包
+//!
+//! 将配置（含分组、API Key 等）、凭证、额度快照和 API Key 用量打包为单个加密文件，
+//! 迁移到新机器时导出一次、导入一次即可，无需手动复制配置目录下的多个文件。
+//!
+//! 格式：`KGBACKUP1` 魔数 + 16 字节盐 + 12 字节 nonce + AES-256-GCM 密文。
+//! 明文为 gzip 压缩的 JSON，密钥由口令经 PBKDF2-HMAC-SHA256 派生。
+//!
+//! 选中凭证的加密导出（[`CredentialExport`]）使用相同格式，魔数为 `KGCREDS1`，
+//! 用于在网关实例之间分享凭证，传输和保存时都不暴露明文 Token。
+
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::num::NonZeroU32;
+use std::path::Path;
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::kiro::model::credentials::KiroCredentials;
+use crate::model::config::Config;
+
+/// 文件头魔数
+const MAGIC: &[u8] = b"KGBACKUP1";
+/// 凭证导出包魔数
+const CREDENTIALS_MAGIC: &[u8] = b"KGCREDS1";
+/// 盐长度
+const SALT_LEN: usize = 16;
+/// PBKDF2 迭代次数
+const PBKDF2_ITERATIONS: u32 = 200_000;
+/// 备份包格式版本
+const BUNDLE_VERSION: u32 = 1;
+/// 凭证导出包格式版本
+const CREDENTIAL_EXPORT_VERSION: u32 = 1;
+
+/// 配置文件
+pub const CONFIG_FILE: &str = "config.json";
+/// 凭证文件
+pub const CREDENTIALS_FILE: &str = "credentials.json";
+/// 与配置文件同目录的统计数据文件
+const DATA_FILES: &[&str] = &[
+    "usage_history.json",
+    "credential_stats.json",
+    "api_key_usage.json",
+    "group_usage.json",
+];
+
+/// 口令最短长度
+pub const MIN_PASSWORD_LEN: usize = 8;
+
+/// 备份包内容（文件名 → JSON 内容）
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupBundle {
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    pub gateway_version: String,
+    pub files: BTreeMap<String, serde_json::Value>,
+}
+
+impl BackupBundle {
+    /// 收集当前状态：配置和凭证取内存中的最新值，统计数据读取配置目录下的文件
+    pub fn collect(config: &Config, credentials: &[KiroCredentials], data_dir: &Path) -> anyhow::Result<Self> {
+        let mut files = BTreeMap::new();
+        files.insert(CONFIG_FILE.to_string(), serde_json::to_value(config)?);
+        files.insert(CREDENTIALS_FILE.to_string(), serde_json::to_value(credentials)?);
+        for name in DATA_FILES {
+            let path = data_dir.join(name);
+            let Ok(content) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            match serde_json::from_str(&content) {
+                Ok(value) => {
+                    files.insert(name.to_string(), value);
+                }
+                Err(e) => tracing::warn!("备份时跳过无法解析的文件 {:?}: {}", path, e),
+            }
+        }
+
+        Ok(Self {
+            version: BUNDLE_VERSION,
+            created_at: Utc::now(),
+            gateway_version: env!("CARGO_PKG_VERSION").to_string(),
+            files,
+        })
+    }
+
+    /// 解析包内的配置
+    pub fn config(&self) -> anyhow::Result<Config> {
+        let value = self.files.get(CONFIG_FILE).context("备份包缺少 config.json")?;
+        serde_json::from_value(value.clone()).context("备份包中的配置格式错误")
+    }
+
+    /// 解析包内的凭证
+    pub fn credentials(&self) -> anyhow::Result<Vec<KiroCredentials>> {
+        let value = self.files.get(CREDENTIALS_FILE).context("备份包缺少 credentials.json")?;
+        serde_json::from_value(value.clone()).context("备份包中的凭证格式错误")
+    }
+
+    /// 将统计数据文件写入配置目录（配置和凭证由调用方写入），返回写入的文件名
+    pub fn restore_data_files(&self, data_dir: &Path) -> anyhow::Result<Vec<String>> {
+        let mut restored = Vec::new();
+        for name in DATA_FILES {
+            let Some(value) = self.files.get(*name) else {
+                continue;
+            };
+            let path = data_dir.join(name);
+            let json = serde_json::to_string_pretty(value)?;
+            std::fs::write(&path, json).with_context(|| format!("写入 {:?} 失败", path))?;
+            restored.push(name.to_string());
+        }
+        Ok(restored)
+    }
+}
+
+/// 加密导出的凭证包
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialExport {
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    pub gateway_version: String,
+    pub credentials: Vec<ExportedCredential>,
+}
+
+/// 导出包中的单个凭证（只包含在另一个网关导入所需的字段）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedCredential {
+    pub refresh_token: String,
+    pub auth_method: String,
+    #[serde(default)]
+    pub client_id: Option<String>,
+    #[serde(default)]
+    pub client_secret: Option<String>,
+}
+
+impl CredentialExport {
+    /// 由凭证生成导出包（跳过没有 refreshToken 的凭证）
+    pub fn new(credentials: &[KiroCredentials]) -> Self {
+        Self {
+            version: CREDENTIAL_EXPORT_VERSION,
+            created_at: Utc::now(),
+            gateway_version: env!("CARGO_PKG_VERSION").to_string(),
+            credentials: credentials
+                .iter()
+                .filter_map(|c| {
+                    Some(ExportedCredential {
+                        refresh_token: c.refresh_token.clone()?,
+                        auth_method: c.auth_method.clone().unwrap_or_else(|| "social".to_string()),
+                        client_id: c.client_id.clone(),
+                        client_secret: c.client_secret.clone(),
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+/// 由口令派生 AES-256 密钥
+fn derive_key(password: &str, salt: &[u8]) -> anyhow::Result<LessSafeKey> {
+    let mut key = [0u8; 32];
+    ring::pbkdf2::derive(
+        ring::pbkdf2::PBKDF2_HMAC_SHA256,
+        NonZeroU32::new(PBKDF2_ITERATIONS).expect("迭代次数非零"),
+        salt,
+        password.as_bytes(),
+        &mut key,
+    );
+    let key = UnboundKey::new(&AES_256_GCM, &key).map_err(|_| anyhow::anyhow!("密钥初始化失败"))?;
+    Ok(LessSafeKey::new(key))
+}
+
+/// 压缩并加密备份包
+pub fn encrypt(bundle: &BackupBundle, password: &str) -> anyhow::Result<Vec<u8>> {
+    seal(MAGIC, bundle, password)
+}
+
+/// 解密并解压备份包（口令错误或文件损坏时返回错误）
+pub fn decrypt(data: &[u8], password: &str) -> anyhow::Result<BackupBundle> {
+    let bundle: BackupBundle = open(MAGIC, data, password, "备份文件")?;
+    if bundle.version > BUNDLE_VERSION {
+        anyhow::bail!("备份包版本 {} 高于当前支持的版本 {}，请先升级网关", bundle.version, BUNDLE_VERSION);
+    }
+    Ok(bundle)
+}
+
+/// 压缩并加密凭证导出包
+pub fn encrypt_credentials(export: &CredentialExport, password: &str) -> anyhow::Result<Vec<u8>> {
+    seal(CREDENTIALS_MAGIC, export, password)
+}
+
+/// 解密并解压凭证导出包
+pub fn decrypt_credentials(data: &[u8], password: &str) -> anyhow::Result<CredentialExport> {
+    let export: CredentialExport = open(CREDENTIALS_MAGIC, data, password, "凭证导出文件")?;
+    if export.version > CREDENTIAL_EXPORT_VERSION {
+        anyhow::bail!(
+            "凭证导出包版本 {} 高于当前支持的版本 {}，请先升级网关",
+            export.version,
+            CREDENTIAL_EXPORT_VERSION
+        );
+    }
+    Ok(export)
+}
+
+/// 序列化、压缩并加密，魔数同时作为附加认证数据
+fn seal<T: Serialize>(magic: &[u8], value: &T, password: &str) -> anyhow::Result<Vec<u8>> {
+    let json = serde_json::to_vec(value)?;
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(&json)?;
+    let mut data = encoder.finish()?;
+
+    let rng = SystemRandom::new();
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill(&mut salt).map_err(|_| anyhow::anyhow!("生成随机数失败"))?;
+    rng.fill(&mut nonce).map_err(|_| anyhow::anyhow!("生成随机数失败"))?;
+
+    derive_key(password, &salt)?
+        .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(magic), &mut data)
+        .map_err(|_| anyhow::anyhow!("加密失败"))?;
+
+    let mut out = Vec::with_capacity(magic.len() + SALT_LEN + NONCE_LEN + data.len());
+    out.extend_from_slice(magic);
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&data);
+    Ok(out)
+}
+
+/// [`seal`] 的逆操作，`kind` 用于错误消息
+fn open<T: DeserializeOwned>(magic: &[u8], data: &[u8], password: &str, kind: &str) -> anyhow::Result<T> {
+    let header_len = magic.len() + SALT_LEN + NONCE_LEN;
+    if data.len() < header_len || !data.starts_with(magic) {
+        anyhow::bail!("不是有效的网关{}", kind);
+    }
+    let salt = &data[magic.len()..magic.len() + SALT_LEN];
+    let nonce: [u8; NONCE_LEN] = data[magic.len() + SALT_LEN..header_len].try_into()?;
+
+    let mut buf = data[header_len..].to_vec();
+    let plain = derive_key(password, salt)?
+        .open_in_place(Nonce::assume_unique_for_key(nonce), Aad::from(magic), &mut buf)
+        .map_err(|_| anyhow::anyhow!("解密失败：口令错误或{}已损坏", kind))?;
+
+    let mut json = Vec::new();
+    flate2::read::GzDecoder::new(&plain[..])
+        .read_to_end(&mut json)
+        .with_context(|| format!("{}解压失败", kind))?;
+    serde_json::from_slice(&json).with_context(|| format!("{}内容格式错误", kind))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip_and_wrong_password() {
+        let dir = std::env::temp_dir().join(format!("kiro-backup-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("usage_history.json"), r#"{"1":[]}"#).unwrap();
+
+        let config = Config {
+            proxy_port: 9999,
+            ..Default::default()
+        };
+        let credentials = vec![KiroCredentials {
+            id: Some(3),
+            refresh_token: Some("rt".to_string()),
+            ..Default::default()
+        }];
+        let bundle = BackupBundle::collect(&config, &credentials, &dir).unwrap();
+        let data = encrypt(&bundle, "correct horse").unwrap();
+
+        assert!(decrypt(&data, "wrong password").is_err());
+        assert!(decrypt(b"not a backup", "correct horse").is_err());
+
+        let restored = decrypt(&data, "correct horse").unwrap();
+        assert_eq!(restored.config().unwrap().proxy_port, 9999);
+        assert_eq!(restored.credentials().unwrap()[0].id, Some(3));
+
+        let target = dir.join("restore");
+        std::fs::create_dir_all(&target).unwrap();
+        assert_eq!(restored.restore_data_files(&target).unwrap(), vec!["usage_history.json"]);
+        assert!(target.join("usage_history.json").exists());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_credential_export_roundtrip() {
+        let credentials = vec![
+            KiroCredentials {
+                refresh_token: Some("rt".to_string()),
+                auth_method: Some("idc".to_string()),
+                client_id: Some("cid".to_string()),
+                client_secret: Some("secret".to_string()),
+                ..Default::default()
+            },
+            KiroCredentials::default(),
+        ];
+        let data = encrypt_credentials(&CredentialExport::new(&credentials), "correct horse").unwrap();
+
+        // 凭证包与备份包互不兼容
+        assert!(decrypt(&data, "correct horse").is_err());
+        assert!(decrypt_credentials(&data, "wrong password").is_err());
+
+        let export = decrypt_credentials(&data, "correct horse").unwrap();
+        assert_eq!(export.credentials.len(), 1);
+        assert_eq!(export.credentials[0].refresh_token, "rt");
+        assert_eq!(export.credentials[0].client_secret.as_deref(), Some("secret"));
+    }
+}
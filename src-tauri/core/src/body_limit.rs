@@ -0,0 +1,75 @@
+//! 反代请求体大小限制
+//!
+//! 配合 axum 的 `DefaultBodyLimit` 使用：Content-Length 已超限的请求在读取请求体之前直接拒绝；
+//! 分块传输的请求体在读取时超限，axum 返回纯文本 413，这里统一改写为带错误码的 JSON 错误。
+
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{Request, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+
+use crate::anthropic::types::ErrorResponse;
+use crate::error_code::ErrorCode;
+
+/// 请求体大小上限
+#[derive(Debug, Clone, Copy)]
+pub struct BodyLimit {
+    pub max_bytes: usize,
+}
+
+/// 请求体大小限制中间件
+pub async fn body_limit_middleware(
+    State(limit): State<Arc<BodyLimit>>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let content_length = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    if content_length.is_some_and(|len| len > limit.max_bytes as u64) {
+        tracing::warn!(
+            "请求体过大: {} 字节 > {} 字节 ({})",
+            content_length.unwrap_or_default(),
+            limit.max_bytes,
+            request.uri().path()
+        );
+        return too_large(limit.max_bytes);
+    }
+
+    let response = next.run(request).await;
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    if response.status() == StatusCode::PAYLOAD_TOO_LARGE && !is_json {
+        tracing::warn!("请求体过大: 读取时超出 {} 字节", limit.max_bytes);
+        return too_large(limit.max_bytes);
+    }
+    response
+}
+
+fn too_large(max_bytes: usize) -> Response {
+    (
+        StatusCode::PAYLOAD_TOO_LARGE,
+        Json(
+            ErrorResponse::new(
+                "request_too_large",
+                format!(
+                    "Request exceeds the maximum allowed size of {} MB. \
+                     Reduce image attachments or send fewer messages.",
+                    max_bytes / (1024 * 1024)
+                ),
+            )
+            .with_code(ErrorCode::RequestTooLarge),
+        ),
+    )
+        .into_response()
+}
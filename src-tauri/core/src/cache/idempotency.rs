@@ -0,0 +1,323 @@
+//! 幂等键缓存
+//!
+//! 客户端在非流式请求上携带 `Idempotency-Key`（或 `X-Idempotency-Key`）时，成功的响应按
+//! 「租户 + 幂等键」缓存一段时间，网络抖动后的重试直接返回原响应（带 `idempotent-replayed: true`），
+//! 不会再次调用上游。原请求仍在处理时，重试会等待其完成；原请求失败时不缓存，重试会正常执行。
+//! 每个幂等键同时记录请求内容的哈希，同一幂等键用于不同的请求时拒绝，而不是返回其他请求的响应。
+//! 默认关闭，需在配置中启用。
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::{http::HeaderMap, response::Response};
+use lazy_static::lazy_static;
+use parking_lot::{Mutex, RwLock};
+use tokio::sync::watch;
+
+use super::CachedResponse;
+use crate::model::config::IdempotencyConfig;
+
+/// 幂等键请求头（按顺序取第一个）
+pub const IDEMPOTENCY_KEY_HEADERS: [&str; 2] = ["idempotency-key", "x-idempotency-key"];
+
+/// 重放的响应带有该响应头
+pub const REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// 幂等键最大长度
+const MAX_KEY_LEN: usize = 255;
+
+enum Slot {
+    /// 原请求处理中，完成后通过 channel 发布响应
+    Pending {
+        fingerprint: String,
+        rx: watch::Receiver<Option<CachedResponse>>,
+    },
+    /// 已缓存的响应
+    Ready {
+        fingerprint: String,
+        response: CachedResponse,
+        expires_at: Instant,
+    },
+}
+
+impl Slot {
+    fn fingerprint(&self) -> &str {
+        match self {
+            Slot::Pending { fingerprint, .. } | Slot::Ready { fingerprint, .. } => fingerprint,
+        }
+    }
+}
+
+type Slots = Arc<Mutex<HashMap<String, Slot>>>;
+
+/// 查找结果
+pub enum Lookup {
+    /// 命中缓存，直接返回
+    Replay(CachedResponse),
+    /// 未命中，由调用方处理请求并通过 [`Reservation::complete`] 写入缓存
+    Reserved(Reservation),
+    /// 幂等键已用于内容不同的请求
+    Mismatch,
+}
+
+/// 幂等键占位：处理完成前同一键的重试会等待；未调用 `complete`（或响应失败）时释放占位
+pub struct Reservation {
+    slots: Slots,
+    key: String,
+    fingerprint: String,
+    tx: watch::Sender<Option<CachedResponse>>,
+    config: IdempotencyConfig,
+    done: bool,
+}
+
+impl Reservation {
+    /// 成功的响应写入缓存后原样返回，失败的响应不缓存
+    pub async fn complete(mut self, response: Response) -> Response {
+        let (response, cached) = CachedResponse::capture(response).await;
+        if let Some(cached) = cached {
+            self.store(cached);
+        }
+        response
+    }
+
+    fn store(&mut self, response: CachedResponse) {
+        let now = Instant::now();
+        let mut slots = self.slots.lock();
+        slots.retain(|_, slot| !matches!(slot, Slot::Ready { expires_at, .. } if *expires_at <= now));
+
+        // 超过上限时淘汰最早过期的响应
+        let ready = slots.values().filter(|s| matches!(s, Slot::Ready { .. })).count();
+        if ready >= self.config.max_entries.max(1) {
+            let oldest = slots
+                .iter()
+                .filter_map(|(key, slot)| match slot {
+                    Slot::Ready { expires_at, .. } => Some((key.clone(), *expires_at)),
+                    Slot::Pending { .. } => None,
+                })
+                .min_by_key(|(_, expires_at)| *expires_at)
+                .map(|(key, _)| key);
+            if let Some(key) = oldest {
+                slots.remove(&key);
+            }
+        }
+
+        slots.insert(
+            self.key.clone(),
+            Slot::Ready {
+                fingerprint: self.fingerprint.clone(),
+                response: response.clone(),
+                expires_at: now + Duration::from_secs(self.config.ttl_secs),
+            },
+        );
+        self.done = true;
+        let _ = self.tx.send(Some(response));
+    }
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        if !self.done {
+            // 等待中的重试收到 channel 关闭后重新占位
+            self.slots.lock().remove(&self.key);
+        }
+    }
+}
+
+/// 幂等键缓存
+pub struct IdempotencyCache {
+    config: RwLock<IdempotencyConfig>,
+    slots: Slots,
+}
+
+impl IdempotencyCache {
+    pub fn new() -> Self {
+        Self {
+            config: RwLock::new(IdempotencyConfig::default()),
+            slots: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// 替换配置（禁用时清空缓存）
+    pub fn set_config(&self, config: IdempotencyConfig) {
+        if !config.enabled {
+            self.slots.lock().clear();
+        }
+        *self.config.write() = config;
+    }
+
+    /// 查找幂等键（`scope` 为租户 Key ID，不同租户的相同键互不影响），未启用时返回 None
+    ///
+    /// `fingerprint` 为请求内容的哈希，与原请求不同时返回 [`Lookup::Mismatch`]；
+    /// 同一键的原请求仍在处理时等待其完成
+    pub async fn acquire(&self, scope: Option<&str>, key: &str, fingerprint: &str) -> Option<Lookup> {
+        let config = self.config.read().clone();
+        if !config.enabled {
+            return None;
+        }
+        let key = format!("{}\n{}", scope.unwrap_or("-"), key);
+
+        loop {
+            let mut rx = {
+                let mut slots = self.slots.lock();
+                let live = slots.get(&key).filter(|slot| match slot {
+                    Slot::Ready { expires_at, .. } => *expires_at > Instant::now(),
+                    Slot::Pending { .. } => true,
+                });
+                if live.is_some_and(|slot| slot.fingerprint() != fingerprint) {
+                    return Some(Lookup::Mismatch);
+                }
+                match live {
+                    Some(Slot::Ready { response, .. }) => {
+                        return Some(Lookup::Replay(response.clone()));
+                    }
+                    Some(Slot::Pending { rx, .. }) => rx.clone(),
+                    None => {
+                        let (tx, rx) = watch::channel(None);
+                        slots.insert(
+                            key.clone(),
+                            Slot::Pending {
+                                fingerprint: fingerprint.to_string(),
+                                rx,
+                            },
+                        );
+                        return Some(Lookup::Reserved(Reservation {
+                            slots: self.slots.clone(),
+                            key,
+                            fingerprint: fingerprint.to_string(),
+                            tx,
+                            config,
+                            done: false,
+                        }));
+                    }
+                }
+            };
+
+            let replayed = rx.wait_for(Option::is_some).await.ok().and_then(|r| r.clone());
+            if let Some(response) = replayed {
+                return Some(Lookup::Replay(response));
+            }
+            // 原请求失败（未缓存），重新查找并占位
+        }
+    }
+}
+
+impl Default for IdempotencyCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+lazy_static! {
+    /// 全局幂等键缓存
+    pub static ref IDEMPOTENCY_CACHE: IdempotencyCache = IdempotencyCache::new();
+}
+
+/// 从请求头读取幂等键，格式无效时返回错误信息
+pub fn key_from_headers(headers: &HeaderMap) -> Result<Option<String>, String> {
+    let Some(value) = IDEMPOTENCY_KEY_HEADERS.iter().find_map(|name| headers.get(*name)) else {
+        return Ok(None);
+    };
+    let key = value
+        .to_str()
+        .map_err(|_| "Idempotency-Key must be visible ASCII".to_string())?
+        .trim();
+    if key.is_empty() || key.len() > MAX_KEY_LEN {
+        return Err(format!("Idempotency-Key must be 1-{} characters", MAX_KEY_LEN));
+    }
+    Ok(Some(key.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{HeaderValue, StatusCode};
+    use axum::response::IntoResponse;
+    use bytes::Bytes;
+
+    fn reserve(lookup: Option<Lookup>) -> Reservation {
+        match lookup {
+            Some(Lookup::Reserved(reservation)) => reservation,
+            _ => panic!("应当占位"),
+        }
+    }
+
+    async fn body_of(response: Response) -> Bytes {
+        axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap()
+    }
+
+    fn enabled_cache() -> IdempotencyCache {
+        let cache = IdempotencyCache::new();
+        cache.set_config(IdempotencyConfig {
+            enabled: true,
+            ..IdempotencyConfig::default()
+        });
+        cache
+    }
+
+    #[tokio::test]
+    async fn test_replays_successful_response_per_tenant() {
+        let cache = enabled_cache();
+
+        let reservation = reserve(cache.acquire(Some("tenant-a"), "k1", "req-1").await);
+        let response = reservation.complete((StatusCode::OK, "{\"id\":\"msg_1\"}").into_response()).await;
+        assert_eq!(body_of(response).await, "{\"id\":\"msg_1\"}");
+
+        let Some(Lookup::Replay(cached)) = cache.acquire(Some("tenant-a"), "k1", "req-1").await else {
+            panic!("应当命中缓存");
+        };
+        let replayed = cached.replay(REPLAYED_HEADER);
+        assert_eq!(replayed.headers()[REPLAYED_HEADER], "true");
+        assert_eq!(body_of(replayed).await, "{\"id\":\"msg_1\"}");
+
+        // 其他租户的相同键不命中
+        assert!(matches!(cache.acquire(Some("tenant-b"), "k1", "req-1").await, Some(Lookup::Reserved(_))));
+    }
+
+    #[tokio::test]
+    async fn test_key_reused_with_different_request_is_rejected() {
+        let cache = enabled_cache();
+
+        let reservation = reserve(cache.acquire(None, "k3", "req-1").await);
+        // 原请求处理中和完成后，内容不同的请求都不会等待或拿到原响应
+        assert!(matches!(cache.acquire(None, "k3", "req-2").await, Some(Lookup::Mismatch)));
+        reservation.complete((StatusCode::OK, "{}").into_response()).await;
+        assert!(matches!(cache.acquire(None, "k3", "req-2").await, Some(Lookup::Mismatch)));
+        assert!(matches!(cache.acquire(None, "k3", "req-1").await, Some(Lookup::Replay(_))));
+
+        // 默认关闭
+        assert!(IdempotencyCache::new().acquire(None, "k3", "req-1").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_failures_are_not_cached_and_waiters_retry() {
+        let cache = Arc::new(enabled_cache());
+
+        let reservation = reserve(cache.acquire(None, "k2", "req-2").await);
+        let waiter = {
+            let cache = cache.clone();
+            tokio::spawn(async move { matches!(cache.acquire(None, "k2", "req-2").await, Some(Lookup::Reserved(_))) })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let failed = reservation
+            .complete((StatusCode::TOO_MANY_REQUESTS, "{}").into_response())
+            .await;
+        assert_eq!(failed.status(), StatusCode::TOO_MANY_REQUESTS);
+        // 原请求失败后，等待中的重试重新占位执行
+        assert!(waiter.await.unwrap());
+
+        cache.set_config(IdempotencyConfig {
+            enabled: false,
+            ..IdempotencyConfig::default()
+        });
+        assert!(cache.acquire(None, "k2", "req-2").await.is_none());
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(key_from_headers(&headers), Ok(None));
+        headers.insert("x-idempotency-key", HeaderValue::from_static(" abc "));
+        assert_eq!(key_from_headers(&headers), Ok(Some("abc".to_string())));
+        headers.insert("idempotency-key", HeaderValue::from_static(""));
+        assert!(key_from_headers(&headers).is_err());
+    }
+}
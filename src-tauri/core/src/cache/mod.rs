@@ -0,0 +1,67 @@
+//! 响应缓存
+//!
+//! - [`idempotency`]：按 `Idempotency-Key` 缓存已完成的非流式响应，客户端重试时直接返回
+//!   原响应，避免重复消耗额度
+//! - [`response`]：可选的相同请求响应缓存（按模型、系统提示、消息、工具的哈希），
+//!   用于反复发送相同提示的测试场景
+
+pub mod idempotency;
+pub mod response;
+
+use axum::{
+    body::Body,
+    http::{HeaderValue, StatusCode, header},
+    response::{IntoResponse, Json, Response},
+};
+use bytes::Bytes;
+
+use crate::anthropic::types::ErrorResponse;
+
+/// 缓存的响应（JSON）
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    status: StatusCode,
+    body: Bytes,
+}
+
+impl CachedResponse {
+    /// 读取响应体：返回重建后的响应，成功的响应同时返回可缓存的副本
+    pub async fn capture(response: Response) -> (Response, Option<CachedResponse>) {
+        if !response.status().is_success() {
+            return (response, None);
+        }
+
+        let (parts, body) = response.into_parts();
+        let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                tracing::warn!("读取待缓存的响应失败: {}", e);
+                let response = (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse::new("internal_error", format!("读取响应失败: {}", e))),
+                )
+                    .into_response();
+                return (response, None);
+            }
+        };
+
+        let cached = CachedResponse {
+            status: parts.status,
+            body: bytes.clone(),
+        };
+        (Response::from_parts(parts, Body::from(bytes)), Some(cached))
+    }
+
+    /// 重放缓存的响应，`marker` 响应头标记来源
+    pub fn replay(self, marker: &'static str) -> Response {
+        (
+            self.status,
+            [
+                (header::CONTENT_TYPE, HeaderValue::from_static("application/json")),
+                (header::HeaderName::from_static(marker), HeaderValue::from_static("true")),
+            ],
+            Body::from(self.body),
+        )
+            .into_response()
+    }
+}
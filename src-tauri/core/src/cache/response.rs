@@ -0,0 +1,283 @@
+//! 相同请求的响应缓存（可选）
+//!
+//! 启用后，非流式请求按「租户 + 模型 + 系统提示 + 消息 + 工具」（含 `max_tokens`、`tool_choice`、
+//! thinking 配置和停止序列）的 SHA-256 哈希缓存成功的响应。命中时直接返回（带 `response-cache-hit: true`），
+//! 不调用上游。缓存按 TTL 过期，超过容量时淘汰最久未使用的条目；命中率可在 Admin API 查看。
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use axum::response::Response;
+use lazy_static::lazy_static;
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use super::CachedResponse;
+use crate::anthropic::types::MessagesRequest;
+use crate::model::config::ResponseCacheConfig;
+
+/// 命中缓存的响应带有该响应头
+pub const CACHE_HIT_HEADER: &str = "response-cache-hit";
+
+struct Entry {
+    response: CachedResponse,
+    expires_at: Instant,
+    /// 最近一次使用的序号（用于 LRU 淘汰）
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<String, Entry>,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl CacheState {
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    /// 删除过期条目，并淘汰最久未使用的条目直到不超过 `capacity`
+    fn evict(&mut self, capacity: usize) {
+        let now = Instant::now();
+        self.entries.retain(|_, e| e.expires_at > now);
+        while self.entries.len() > capacity {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, e)| e.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+    }
+}
+
+/// 缓存命中统计
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResponseCacheStats {
+    /// 当前缓存的响应数
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    /// 命中率（0-1，没有请求时为 0）
+    pub hit_rate: f64,
+}
+
+/// 响应缓存
+pub struct ResponseCache {
+    config: RwLock<ResponseCacheConfig>,
+    state: Mutex<CacheState>,
+}
+
+impl ResponseCache {
+    pub fn new() -> Self {
+        Self {
+            config: RwLock::new(ResponseCacheConfig::default()),
+            state: Mutex::new(CacheState::default()),
+        }
+    }
+
+    /// 替换配置（立即生效，禁用时清空缓存）
+    pub fn set_config(&self, config: ResponseCacheConfig) {
+        let mut state = self.state.lock();
+        if config.enabled {
+            state.evict(config.max_entries);
+        } else {
+            state.entries.clear();
+        }
+        *self.config.write() = config;
+    }
+
+    /// 计算请求的缓存键（未启用时返回 None）
+    pub fn key(&self, scope: Option<&str>, request: &MessagesRequest) -> Option<String> {
+        if !self.config.read().enabled {
+            return None;
+        }
+        request_digest(scope, request)
+    }
+
+    /// 查找缓存的响应（计入命中统计）
+    pub fn get(&self, key: &str) -> Option<CachedResponse> {
+        let mut state = self.state.lock();
+        let tick = state.next_tick();
+        let now = Instant::now();
+
+        let found = match state.entries.get_mut(key) {
+            Some(entry) if entry.expires_at > now => {
+                entry.last_used = tick;
+                Some(entry.response.clone())
+            }
+            Some(_) => {
+                state.entries.remove(key);
+                None
+            }
+            None => None,
+        };
+        match found {
+            Some(_) => state.hits += 1,
+            None => state.misses += 1,
+        }
+        found
+    }
+
+    /// 缓存成功的响应后原样返回
+    pub async fn store(&self, key: String, response: Response) -> Response {
+        let (response, cached) = CachedResponse::capture(response).await;
+        let Some(cached) = cached else {
+            return response;
+        };
+
+        let config = self.config.read().clone();
+        if !config.enabled {
+            return response;
+        }
+        let mut state = self.state.lock();
+        let last_used = state.next_tick();
+        state.entries.insert(
+            key,
+            Entry {
+                response: cached,
+                expires_at: Instant::now() + Duration::from_secs(config.ttl_secs),
+                last_used,
+            },
+        );
+        state.evict(config.max_entries);
+        response
+    }
+
+    /// 命中统计
+    pub fn stats(&self) -> ResponseCacheStats {
+        let mut state = self.state.lock();
+        let now = Instant::now();
+        state.entries.retain(|_, e| e.expires_at > now);
+        let total = state.hits + state.misses;
+        ResponseCacheStats {
+            entries: state.entries.len(),
+            hits: state.hits,
+            misses: state.misses,
+            hit_rate: if total == 0 { 0.0 } else { state.hits as f64 / total as f64 },
+        }
+    }
+
+    /// 清空缓存和命中统计，返回清除的条目数
+    pub fn purge(&self) -> usize {
+        let mut state = self.state.lock();
+        let purged = state.entries.len();
+        state.entries.clear();
+        state.hits = 0;
+        state.misses = 0;
+        purged
+    }
+}
+
+impl Default for ResponseCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 请求内容的 SHA-256 哈希（模型、系统提示、消息、工具等影响回复的字段，采样参数不参与）
+pub(crate) fn request_digest(scope: Option<&str>, request: &MessagesRequest) -> Option<String> {
+    // 先转为 Value，对象键按字母顺序序列化，工具 schema 的 HashMap 顺序不影响哈希
+    let canonical = serde_json::to_value(serde_json::json!({
+        "scope": scope,
+        "model": request.model,
+        "max_tokens": request.max_tokens,
+        "system": request.system,
+        "messages": request.messages,
+        "tools": request.tools,
+        "tool_choice": request.tool_choice,
+        "thinking": request.thinking.as_ref().map(|t| (&t.thinking_type, t.budget_tokens)),
+        "stop_sequences": request.stop_sequences,
+    }))
+    .ok()?;
+    let bytes = serde_json::to_vec(&canonical).ok()?;
+    Some(hex::encode(Sha256::digest(&bytes)))
+}
+
+lazy_static! {
+    /// 全局响应缓存
+    pub static ref RESPONSE_CACHE: ResponseCache = ResponseCache::new();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use axum::response::IntoResponse;
+
+    fn request(text: &str) -> MessagesRequest {
+        serde_json::from_value(serde_json::json!({
+            "model": "claude-sonnet-4-5",
+            "max_tokens": 32,
+            "messages": [{ "role": "user", "content": text }],
+            "tools": [{
+                "name": "lookup",
+                "description": "d",
+                "input_schema": { "type": "object", "properties": {}, "required": [] }
+            }]
+        }))
+        .unwrap()
+    }
+
+    fn enabled(max_entries: usize) -> ResponseCacheConfig {
+        ResponseCacheConfig {
+            enabled: true,
+            ttl_secs: 60,
+            max_entries,
+        }
+    }
+
+    #[test]
+    fn test_key_is_stable_and_scoped() {
+        let cache = ResponseCache::new();
+        assert!(cache.key(None, &request("hi")).is_none());
+
+        cache.set_config(enabled(10));
+        let key = cache.key(None, &request("hi")).unwrap();
+        assert_eq!(cache.key(None, &request("hi")), Some(key.clone()));
+        assert_ne!(cache.key(None, &request("hello")), Some(key.clone()));
+        assert_ne!(cache.key(Some("tenant"), &request("hi")), Some(key));
+    }
+
+    #[tokio::test]
+    async fn test_hits_lru_eviction_and_purge() {
+        let cache = ResponseCache::new();
+        cache.set_config(enabled(2));
+
+        assert!(cache.get("a").is_none());
+        for key in ["a", "b"] {
+            cache
+                .store(key.to_string(), (StatusCode::OK, "{}").into_response())
+                .await;
+        }
+        // 失败的响应不缓存
+        cache
+            .store("c".to_string(), StatusCode::BAD_GATEWAY.into_response())
+            .await;
+        assert!(cache.get("c").is_none());
+
+        // 访问 a 后 b 成为最久未使用的条目
+        assert!(cache.get("a").is_some());
+        cache
+            .store("d".to_string(), (StatusCode::OK, "{}").into_response())
+            .await;
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("a").is_some());
+
+        let stats = cache.stats();
+        assert_eq!(stats.entries, 2);
+        assert_eq!(stats.hits, 2);
+        assert_eq!(stats.misses, 3);
+        assert_eq!(cache.purge(), 2);
+        assert_eq!(cache.stats().hits, 0);
+    }
+}
@@ -8,9 +8,11 @@ use subtle::ConstantTimeEq;
 
 /// 从请求中提取 API Key
 ///
-/// 支持两种认证方式：
+/// 支持以下认证方式（按优先级）：
 /// - `x-api-key` header
 /// - `Authorization: Bearer <token>` header
+/// - `x-goog-api-key` header（Gemini 客户端）
+/// - `?key=` 查询参数（Gemini 客户端）
 pub fn extract_api_key(request: &Request<Body>) -> Option<String> {
     // 优先检查 x-api-key
     if let Some(key) = request
@@ -22,12 +24,31 @@ pub fn extract_api_key(request: &Request<Body>) -> Option<String> {
     }
 
     // 其次检查 Authorization: Bearer
-    request
+    if let Some(key) = request
         .headers()
         .get(header::AUTHORIZATION)
         .and_then(|v| v.to_str().ok())
         .and_then(|v| v.strip_prefix("Bearer "))
-        .map(|s| s.to_string())
+    {
+        return Some(key.to_string());
+    }
+
+    // Gemini 客户端使用 x-goog-api-key 或 key 查询参数
+    if let Some(key) = request
+        .headers()
+        .get("x-goog-api-key")
+        .and_then(|v| v.to_str().ok())
+    {
+        return Some(key.to_string());
+    }
+
+    request.uri().query().and_then(|query| {
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(name, _)| *name == "key")
+            .map(|(_, value)| value.to_string())
+    })
 }
 
 /// 常量时间字符串比较，防止时序攻击
@@ -1,3 +1,4 @@
 //! 公共工具模块
 
 pub mod auth;
+pub mod redact;
@@ -0,0 +1,32 @@
+//! 敏感字段脱敏
+
+/// 遮蔽密钥类字段：保留前 6 位和后 4 位，过短时全部遮蔽
+///
+/// 同一个值的遮蔽结果固定，前端可以用遮蔽后的值做相等比较
+pub fn mask_secret(value: &str) -> String {
+    let chars: Vec<char> = value.chars().collect();
+    if chars.len() <= 12 {
+        return "*".repeat(chars.len());
+    }
+    let head: String = chars[..6].iter().collect();
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}...{}", head, tail)
+}
+
+/// 遮蔽可选字段（None 保持不变）
+pub fn mask_optional(value: Option<String>) -> Option<String> {
+    value.map(|v| mask_secret(&v))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_secret() {
+        assert_eq!(mask_secret("aorAAAAAGabcdefghijk1234"), "aorAAA...1234");
+        assert_eq!(mask_secret("short"), "*****");
+        assert_eq!(mask_optional(None), None);
+        assert_eq!(mask_optional(Some("x".repeat(20))), Some("xxxxxx...xxxx".to_string()));
+    }
+}
@@ -0,0 +1,108 @@
+//! 跨域（CORS）策略
+//!
+//! 按配置的 `cors` 构建 Admin 与反代服务共用的 CORS 层。默认允许任意来源；配置来源列表后只有列出的
+//! 来源能跨域访问。允许携带凭据时浏览器不接受通配响应，方法和请求头改为回显预检请求中的值。
+
+use std::time::Duration;
+
+use axum::http::HeaderValue;
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, Any, CorsLayer};
+
+use crate::model::config::CorsConfig;
+
+/// 规范化来源：`*` 原样保留，其余须为 `scheme://host[:port]`（去掉末尾的 `/`，转为小写）
+pub fn normalize_origin(origin: &str) -> Result<String, String> {
+    let origin = origin.trim();
+    if origin == "*" {
+        return Ok(origin.to_string());
+    }
+    let trimmed = origin.trim_end_matches('/');
+    let valid = trimmed
+        .split_once("://")
+        .is_some_and(|(scheme, host)| {
+            !scheme.is_empty()
+                && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+                && !host.is_empty()
+                && !host.contains(['/', '?', '#', ' '])
+        });
+    if !valid || HeaderValue::from_str(trimmed).is_err() {
+        return Err(format!("无效的 CORS 来源: {}（格式如 http://192.168.1.20:3000）", origin));
+    }
+    Ok(trimmed.to_ascii_lowercase())
+}
+
+/// 校验并规范化 CORS 配置（来源去重，保持顺序）
+pub fn normalize(config: &CorsConfig) -> Result<CorsConfig, String> {
+    let mut origins: Vec<String> = Vec::new();
+    for origin in &config.allowed_origins {
+        let origin = normalize_origin(origin)?;
+        if !origins.contains(&origin) {
+            origins.push(origin);
+        }
+    }
+    if config.allow_credentials && origins.iter().any(|o| o == "*") {
+        return Err("允许携带凭据时不能使用 * 作为来源，请列出具体来源".to_string());
+    }
+    Ok(CorsConfig {
+        allowed_origins: origins,
+        ..config.clone()
+    })
+}
+
+/// 按配置构建 CORS 层（配置无效时记录警告并拒绝所有跨域请求）
+pub fn layer(config: &CorsConfig) -> CorsLayer {
+    let config = normalize(config).unwrap_or_else(|e| {
+        tracing::warn!("CORS 配置无效，已拒绝所有跨域请求: {}", e);
+        CorsConfig {
+            allowed_origins: Vec::new(),
+            allow_credentials: false,
+            max_age_secs: 0,
+        }
+    });
+
+    let layer = if config.allowed_origins.iter().any(|o| o == "*") {
+        CorsLayer::new().allow_origin(Any)
+    } else {
+        let origins = config
+            .allowed_origins
+            .iter()
+            .filter_map(|o| HeaderValue::from_str(o).ok())
+            .collect::<Vec<_>>();
+        CorsLayer::new().allow_origin(AllowOrigin::list(origins))
+    };
+    let layer = if config.allow_credentials {
+        layer
+            .allow_methods(AllowMethods::mirror_request())
+            .allow_headers(AllowHeaders::mirror_request())
+            .allow_credentials(true)
+    } else {
+        layer.allow_methods(Any).allow_headers(Any)
+    };
+    if config.max_age_secs > 0 {
+        layer.max_age(Duration::from_secs(config.max_age_secs))
+    } else {
+        layer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(origins: &[&str], allow_credentials: bool) -> CorsConfig {
+        CorsConfig {
+            allowed_origins: origins.iter().map(|o| o.to_string()).collect(),
+            allow_credentials,
+            max_age_secs: 600,
+        }
+    }
+
+    #[test]
+    fn test_normalize() {
+        let normalized = normalize(&config(&["HTTP://192.168.1.20:3000/", "http://192.168.1.20:3000"], false)).unwrap();
+        assert_eq!(normalized.allowed_origins, vec!["http://192.168.1.20:3000"]);
+        assert!(normalize(&config(&["*"], true)).is_err());
+        assert!(normalize(&config(&["192.168.1.20"], false)).is_err());
+        assert!(normalize(&config(&["http://host/path"], false)).is_err());
+    }
+}
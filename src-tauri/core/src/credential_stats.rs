@@ -0,0 +1,265 @@
+//! 凭证请求统计
+//!
+//! 按凭证记录对话 API 每次尝试的结果（成功 / 失败）和成功请求的延迟（发出请求到收到响应头），
+//! 以 5 分钟为一桶保留最近 24 小时，汇总为最近 1 小时 / 24 小时的成功率和平均延迟，
+//! 供 Admin UI 在凭证达到失败阈值被禁用之前发现表现变差的账户。
+//! 请求参数错误（400 等非凭证原因的 4xx）不计入。统计持久化到配置目录下的 `credential_stats.json`。
+
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use lazy_static::lazy_static;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+
+/// 每个桶覆盖的秒数
+const BUCKET_SECS: i64 = 5 * 60;
+
+/// 保留的时长（秒）
+const RETENTION_SECS: i64 = 24 * 60 * 60;
+
+/// 统计文件写盘的最小间隔
+const STATS_SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 一个时间桶内的统计
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StatsBucket {
+    /// 桶起始时间（Unix 秒，按 BUCKET_SECS 对齐）
+    start: i64,
+    successes: u64,
+    failures: u64,
+    /// 成功请求的延迟总和（毫秒）
+    latency_ms_total: u64,
+}
+
+/// 单个时间窗口的汇总
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestWindowStats {
+    pub requests: u64,
+    pub failures: u64,
+    /// 成功率（0-1），无请求时为 null
+    pub success_rate: Option<f64>,
+    /// 成功请求的平均延迟（毫秒），无成功请求时为 null
+    pub avg_latency_ms: Option<u64>,
+}
+
+/// 凭证最近 1 小时 / 24 小时的请求统计
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialRequestStats {
+    pub last_hour: RequestWindowStats,
+    pub last_day: RequestWindowStats,
+}
+
+#[derive(Default)]
+struct StatsState {
+    series: HashMap<u64, VecDeque<StatsBucket>>,
+    dirty: bool,
+    last_saved: Option<Instant>,
+}
+
+/// 凭证请求统计存储
+pub struct CredentialStats {
+    path: RwLock<Option<PathBuf>>,
+    state: Mutex<StatsState>,
+}
+
+impl CredentialStats {
+    pub fn new() -> Self {
+        Self {
+            path: RwLock::new(None),
+            state: Mutex::new(StatsState::default()),
+        }
+    }
+
+    /// 从文件加载统计（文件不存在或损坏时从空开始）
+    pub fn load(&self, path: Option<PathBuf>) {
+        let series = path
+            .as_ref()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|content| {
+                serde_json::from_str::<HashMap<u64, VecDeque<StatsBucket>>>(&content)
+                    .map_err(|e| tracing::warn!("解析凭证请求统计文件失败，将重新记录: {}", e))
+                    .ok()
+            })
+            .unwrap_or_default();
+
+        *self.path.write() = path;
+        let mut state = self.state.lock();
+        state.series = series;
+        state.dirty = false;
+    }
+
+    /// 记录一次成功的请求
+    pub fn record_success(&self, id: u64, latency: Duration) {
+        self.record_at(id, Utc::now().timestamp(), Some(latency));
+        self.save_if_due();
+    }
+
+    /// 记录一次失败的请求
+    pub fn record_failure(&self, id: u64) {
+        self.record_at(id, Utc::now().timestamp(), None);
+        self.save_if_due();
+    }
+
+    fn record_at(&self, id: u64, now: i64, latency: Option<Duration>) {
+        let start = now - now.rem_euclid(BUCKET_SECS);
+
+        let mut state = self.state.lock();
+        let series = state.series.entry(id).or_default();
+        if series.back().is_none_or(|last| last.start != start) {
+            series.push_back(StatsBucket {
+                start,
+                ..Default::default()
+            });
+        }
+        while series.front().is_some_and(|b| b.start + RETENTION_SECS <= now) {
+            series.pop_front();
+        }
+
+        let bucket = series.back_mut().expect("刚插入的桶");
+        match latency {
+            Some(latency) => {
+                bucket.successes += 1;
+                bucket.latency_ms_total += latency.as_millis().min(u64::MAX as u128) as u64;
+            }
+            None => bucket.failures += 1,
+        }
+        state.dirty = true;
+    }
+
+    /// 凭证最近 1 小时 / 24 小时的统计
+    pub fn stats(&self, id: u64) -> CredentialRequestStats {
+        self.stats_at(id, Utc::now().timestamp())
+    }
+
+    fn stats_at(&self, id: u64, now: i64) -> CredentialRequestStats {
+        let state = self.state.lock();
+        let Some(series) = state.series.get(&id) else {
+            return CredentialRequestStats::default();
+        };
+        CredentialRequestStats {
+            last_hour: summarize(series, now, 60 * 60),
+            last_day: summarize(series, now, RETENTION_SECS),
+        }
+    }
+
+    /// 删除凭证的统计
+    pub fn remove(&self, id: u64) {
+        let mut state = self.state.lock();
+        if state.series.remove(&id).is_some() {
+            state.dirty = true;
+            self.save_locked(&mut state);
+        }
+    }
+
+    /// 立即写盘
+    pub fn flush(&self) {
+        let mut state = self.state.lock();
+        self.save_locked(&mut state);
+    }
+
+    fn save_if_due(&self) {
+        let mut state = self.state.lock();
+        let due = state
+            .last_saved
+            .is_none_or(|t| t.elapsed() >= STATS_SAVE_INTERVAL);
+        if due {
+            self.save_locked(&mut state);
+        }
+    }
+
+    fn save_locked(&self, state: &mut StatsState) {
+        if !state.dirty {
+            return;
+        }
+        let Some(path) = self.path.read().clone() else {
+            return;
+        };
+        match serde_json::to_string(&state.series) {
+            Ok(json) => {
+                if let Err(e) = std::fs::write(&path, json) {
+                    tracing::warn!("保存凭证请求统计失败: {}", e);
+                    return;
+                }
+                state.dirty = false;
+                state.last_saved = Some(Instant::now());
+            }
+            Err(e) => tracing::warn!("序列化凭证请求统计失败: {}", e),
+        }
+    }
+}
+
+impl Default for CredentialStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 汇总与窗口有重叠的桶（精度为一个桶）
+fn summarize(series: &VecDeque<StatsBucket>, now: i64, window_secs: i64) -> RequestWindowStats {
+    let (successes, failures, latency_ms_total) = series
+        .iter()
+        .filter(|b| b.start + BUCKET_SECS > now - window_secs && b.start <= now)
+        .fold((0, 0, 0), |(s, f, l), b| (s + b.successes, f + b.failures, l + b.latency_ms_total));
+    let requests = successes + failures;
+    RequestWindowStats {
+        requests,
+        failures,
+        success_rate: (requests > 0).then(|| successes as f64 / requests as f64),
+        avg_latency_ms: (successes > 0).then(|| latency_ms_total / successes),
+    }
+}
+
+lazy_static! {
+    /// 全局凭证请求统计
+    pub static ref CREDENTIAL_STATS: CredentialStats = CredentialStats::new();
+}
+
+/// 初始化全局统计存储，统计文件与配置文件位于同一目录
+pub fn init(config_path: &std::path::Path) {
+    let path = config_path.parent().map(|dir| dir.join("credential_stats.json"));
+    CREDENTIAL_STATS.load(path);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_windows_and_retention() {
+        let stats = CredentialStats::new();
+        let now = 1_700_000_000 - 1_700_000_000 % BUCKET_SECS + 10;
+
+        // 3 小时前：1 成功 1 失败，只计入 24 小时窗口
+        stats.record_at(1, now - 3 * 3600, Some(Duration::from_millis(1000)));
+        stats.record_at(1, now - 3 * 3600, None);
+        // 最近：2 成功 1 失败
+        stats.record_at(1, now - 600, Some(Duration::from_millis(200)));
+        stats.record_at(1, now, Some(Duration::from_millis(400)));
+        stats.record_at(1, now, None);
+
+        let result = stats.stats_at(1, now);
+        assert_eq!(result.last_hour.requests, 3);
+        assert_eq!(result.last_hour.failures, 1);
+        assert_eq!(result.last_hour.avg_latency_ms, Some(300));
+        assert!((result.last_hour.success_rate.unwrap() - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(result.last_day.requests, 5);
+        assert_eq!(result.last_day.avg_latency_ms, Some(533));
+
+        // 24 小时后旧桶被清理
+        let later = now + RETENTION_SECS;
+        stats.record_at(1, later, None);
+        let result = stats.stats_at(1, later);
+        assert_eq!(result.last_day.requests, 1);
+        assert_eq!(result.last_day.success_rate, Some(0.0));
+        assert_eq!(result.last_day.avg_latency_ms, None);
+
+        stats.remove(1);
+        assert_eq!(stats.stats_at(1, later), CredentialRequestStats::default());
+    }
+}
@@ -0,0 +1,181 @@
+//! 稳定的机器可读错误码
+//!
+//! 所有 Admin / Anthropic / Gemini 错误响应在人类可读的 message 之外都附带一个错误码，
+//! 脚本和前端可以据此分支处理，而不必解析中文错误文本。
+//!
+//! 编码规则 `KG<分类><序号>_<名称>`：
+//! - 1xxx 凭证
+//! - 2xxx 上游 Kiro API
+//! - 3xxx 请求参数
+//! - 4xxx 访问控制（认证、权限、限流）
+//! - 5xxx 服务自身
+//!
+//! 错误码一经发布不再修改或复用，新增错误只能追加新的编号。
+
+use serde::{Serialize, Serializer};
+
+/// 错误码
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    /// 凭证不存在
+    CredentialNotFound,
+    /// 凭证无效（验证失败）
+    CredentialInvalid,
+    /// 暂无可用凭证（全部禁用、冷却或获取超时）
+    CredentialUnavailable,
+    /// 上游调用失败
+    UpstreamError,
+    /// 上游响应无法读取或内容为空
+    UpstreamBadResponse,
+    /// 上游限流（429）
+    UpstreamThrottled,
+    /// 上游响应超时（未收到响应或流式响应长时间无数据）
+    UpstreamTimeout,
+    /// 请求参数无效
+    InvalidRequest,
+    /// 模型不支持
+    UnsupportedModel,
+    /// 图片无效（格式、大小或下载失败）
+    InvalidImage,
+    /// 资源不存在
+    NotFound,
+    /// 与当前状态冲突
+    StateConflict,
+    /// anthropic-version 不兼容
+    UnsupportedApiVersion,
+    /// anthropic-beta 特性不受支持
+    UnsupportedBeta,
+    /// max_tokens 超出模型最大输出
+    MaxTokensExceeded,
+    /// 输入超出模型上下文窗口
+    PromptTooLong,
+    /// 请求体超出大小上限
+    RequestTooLarge,
+    /// 消息数或工具数超出上限
+    RequestLimitExceeded,
+    /// 认证失败
+    AuthenticationFailed,
+    /// 无权访问
+    PermissionDenied,
+    /// API Key 不允许使用该模型
+    ModelNotAllowed,
+    /// 请求频率超限
+    RateLimited,
+    /// Token 配额用尽
+    QuotaExceeded,
+    /// 分组月度预算用尽
+    GroupBudgetExceeded,
+    /// 内部错误
+    InternalError,
+    /// 服务不可用
+    ServiceUnavailable,
+    /// 反代服务已禁用
+    ProxyDisabled,
+    /// 请求排队已满或排队超时
+    Overloaded,
+}
+
+impl ErrorCode {
+    /// 错误码字符串
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::CredentialNotFound => "KG1001_CREDENTIAL_NOT_FOUND",
+            ErrorCode::CredentialInvalid => "KG1002_CREDENTIAL_INVALID",
+            ErrorCode::CredentialUnavailable => "KG1003_CREDENTIAL_UNAVAILABLE",
+            ErrorCode::UpstreamError => "KG2001_UPSTREAM_ERROR",
+            ErrorCode::UpstreamBadResponse => "KG2002_UPSTREAM_BAD_RESPONSE",
+            ErrorCode::UpstreamThrottled => "KG2003_UPSTREAM_THROTTLED",
+            ErrorCode::UpstreamTimeout => "KG2004_UPSTREAM_TIMEOUT",
+            ErrorCode::InvalidRequest => "KG3001_INVALID_REQUEST",
+            ErrorCode::UnsupportedModel => "KG3002_UNSUPPORTED_MODEL",
+            ErrorCode::InvalidImage => "KG3003_INVALID_IMAGE",
+            ErrorCode::NotFound => "KG3004_NOT_FOUND",
+            ErrorCode::StateConflict => "KG3005_STATE_CONFLICT",
+            ErrorCode::UnsupportedApiVersion => "KG3006_UNSUPPORTED_API_VERSION",
+            ErrorCode::UnsupportedBeta => "KG3007_UNSUPPORTED_BETA",
+            ErrorCode::MaxTokensExceeded => "KG3008_MAX_TOKENS_EXCEEDED",
+            ErrorCode::PromptTooLong => "KG3009_PROMPT_TOO_LONG",
+            ErrorCode::RequestTooLarge => "KG3010_REQUEST_TOO_LARGE",
+            ErrorCode::RequestLimitExceeded => "KG3011_REQUEST_LIMIT_EXCEEDED",
+            ErrorCode::AuthenticationFailed => "KG4001_AUTHENTICATION_FAILED",
+            ErrorCode::PermissionDenied => "KG4002_PERMISSION_DENIED",
+            ErrorCode::ModelNotAllowed => "KG4003_MODEL_NOT_ALLOWED",
+            ErrorCode::RateLimited => "KG4004_RATE_LIMITED",
+            ErrorCode::QuotaExceeded => "KG4005_QUOTA_EXCEEDED",
+            ErrorCode::GroupBudgetExceeded => "KG4006_GROUP_BUDGET_EXCEEDED",
+            ErrorCode::InternalError => "KG5001_INTERNAL_ERROR",
+            ErrorCode::ServiceUnavailable => "KG5002_SERVICE_UNAVAILABLE",
+            ErrorCode::ProxyDisabled => "KG5003_PROXY_DISABLED",
+            ErrorCode::Overloaded => "KG5004_OVERLOADED",
+        }
+    }
+
+    /// 按错误类型（Anthropic / Admin 的 `type` 字段）推断默认错误码
+    ///
+    /// 未识别的类型归为内部错误；需要更精确的错误码时由调用方显式指定
+    pub fn from_error_type(error_type: &str) -> Self {
+        match error_type {
+            "invalid_request" | "invalid_request_error" => ErrorCode::InvalidRequest,
+            "authentication_error" => ErrorCode::AuthenticationFailed,
+            "permission_error" => ErrorCode::PermissionDenied,
+            "not_found" | "not_found_error" => ErrorCode::NotFound,
+            "conflict" => ErrorCode::StateConflict,
+            "rate_limit_error" => ErrorCode::RateLimited,
+            "request_too_large" => ErrorCode::RequestTooLarge,
+            "api_error" => ErrorCode::UpstreamError,
+            "timeout_error" => ErrorCode::UpstreamTimeout,
+            "credential_unavailable" => ErrorCode::CredentialUnavailable,
+            "service_unavailable" | "overloaded_error" => ErrorCode::ServiceUnavailable,
+            _ => ErrorCode::InternalError,
+        }
+    }
+
+    /// 按 HTTP 状态码推断默认错误码（Gemini 错误响应使用）
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            400 | 413 | 422 => ErrorCode::InvalidRequest,
+            401 => ErrorCode::AuthenticationFailed,
+            403 => ErrorCode::PermissionDenied,
+            404 => ErrorCode::NotFound,
+            409 => ErrorCode::StateConflict,
+            429 => ErrorCode::RateLimited,
+            502 => ErrorCode::UpstreamError,
+            503 => ErrorCode::ServiceUnavailable,
+            504 => ErrorCode::UpstreamTimeout,
+            _ => ErrorCode::InternalError,
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for ErrorCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serializes_as_stable_string() {
+        let json = serde_json::to_string(&ErrorCode::UpstreamThrottled).unwrap();
+        assert_eq!(json, "\"KG2003_UPSTREAM_THROTTLED\"");
+        assert_eq!(ErrorCode::CredentialNotFound.to_string(), "KG1001_CREDENTIAL_NOT_FOUND");
+    }
+
+    #[test]
+    fn test_default_mapping() {
+        assert_eq!(ErrorCode::from_error_type("invalid_request_error"), ErrorCode::InvalidRequest);
+        assert_eq!(ErrorCode::from_error_type("credential_unavailable"), ErrorCode::CredentialUnavailable);
+        assert_eq!(ErrorCode::from_error_type("something_else"), ErrorCode::InternalError);
+        assert_eq!(ErrorCode::from_status(429), ErrorCode::RateLimited);
+        assert_eq!(ErrorCode::from_status(502), ErrorCode::UpstreamError);
+    }
+}
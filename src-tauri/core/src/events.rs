@@ -0,0 +1,150 @@
+//! Admin 实时事件总线
+//!
+//! 日志、凭证状态变化、反代生命周期、服务线程状态和告警统一广播到 `EVENT_BUS`，
+//! 由 `GET /api/admin/events`（SSE）推送给 Admin UI，避免轮询 `/logs`；
+//! GUI 模式下 Tauri 层也订阅该总线以同步窗口状态。
+
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::alerts::Alert;
+use crate::logs::{LOG_COLLECTOR, LogEntry};
+use crate::proxy_lifecycle::ProxySnapshot;
+
+/// 广播缓冲区大小（订阅者落后超过该数量时会丢弃最旧的事件）
+const EVENT_BUS_CAPACITY: usize = 256;
+
+/// 凭证状态变化类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CredentialChange {
+    Added,
+    Deleted,
+    /// 凭证信息被编辑（认证方式、代理、分组或 refreshToken）
+    Updated,
+    Enabled,
+    Disabled,
+    /// 账户暂停/凭证无效被自动禁用
+    Suspended,
+    /// 被上游限流进入冷却
+    CoolingDown,
+}
+
+/// 服务核心事件（运行 Admin API 的服务线程）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ServerEvent {
+    /// 监听器已绑定
+    Started { host: String, port: u16 },
+    /// 收到停止信号后正常退出
+    Stopped,
+    /// 服务线程异常退出（返回错误或 panic）
+    Crashed { error: String },
+}
+
+/// Admin 事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum AdminEvent {
+    /// 新日志
+    Log { entry: LogEntry },
+    /// 凭证状态变化（客户端按需重新拉取凭证列表）
+    Credential { id: u64, change: CredentialChange },
+    /// 反代生命周期变化
+    Proxy { status: ProxySnapshot },
+    /// 服务核心状态变化
+    Server { event: ServerEvent },
+    /// 额度告警或每日用量报告
+    Alert { alert: Alert },
+    /// 开机自启设置变化（桌面应用据此注册或移除自启项）
+    Autostart { enabled: bool },
+    /// 订阅者处理过慢丢失了事件，客户端应重新拉取全量状态
+    Lagged { skipped: u64 },
+}
+
+/// 事件总线
+pub struct EventBus {
+    tx: broadcast::Sender<AdminEvent>,
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(capacity);
+        Self { tx }
+    }
+
+    /// 发布事件（无订阅者时直接丢弃）
+    pub fn publish(&self, event: AdminEvent) {
+        let _ = self.tx.send(event);
+    }
+
+    /// 发布凭证状态变化
+    pub fn credential_changed(&self, id: u64, change: CredentialChange) {
+        self.publish(AdminEvent::Credential { id, change });
+    }
+
+    /// 发布服务核心事件（同时写入 Admin 日志）
+    pub fn server_event(&self, event: ServerEvent) {
+        if let ServerEvent::Crashed { error } = &event {
+            tracing::error!("服务异常退出: {}", error);
+        }
+        LOG_COLLECTOR.record_server_event(&event);
+        self.publish(AdminEvent::Server { event });
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<AdminEvent> {
+        self.tx.subscribe()
+    }
+}
+
+// 全局事件总线
+lazy_static::lazy_static! {
+    pub static ref EVENT_BUS: EventBus = EventBus::new(EVENT_BUS_CAPACITY);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_serialization() {
+        let event = AdminEvent::Credential {
+            id: 3,
+            change: CredentialChange::CoolingDown,
+        };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "credential");
+        assert_eq!(json["id"], 3);
+        assert_eq!(json["change"], "coolingDown");
+    }
+
+    #[tokio::test]
+    async fn test_publish_reaches_subscribers() {
+        let bus = EventBus::new(4);
+        // 无订阅者时发布不报错
+        bus.credential_changed(1, CredentialChange::Added);
+
+        let mut rx = bus.subscribe();
+        bus.credential_changed(2, CredentialChange::Deleted);
+        match rx.recv().await.unwrap() {
+            AdminEvent::Credential { id, change } => {
+                assert_eq!(id, 2);
+                assert_eq!(change, CredentialChange::Deleted);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_server_event_serialization() {
+        let event = AdminEvent::Server {
+            event: ServerEvent::Crashed {
+                error: "bind failed".to_string(),
+            },
+        };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "server");
+        assert_eq!(json["event"]["kind"], "crashed");
+        assert_eq!(json["event"]["error"], "bind failed");
+    }
+}
@@ -0,0 +1,452 @@
+//! Gemini 请求/响应转换
+//!
+//! 请求侧把 Gemini generateContent 请求转换为 Anthropic MessagesRequest，复用现有的
+//! Anthropic -> Kiro 转换；响应侧把 `StreamContext` 产出的 Anthropic SSE 事件映射为
+//! Gemini 响应片段，流式与非流式共用同一套映射
+
+use std::collections::{HashMap, VecDeque};
+
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::anthropic::converter::map_model;
+use crate::anthropic::json_repair;
+use crate::anthropic::stream::SseEvent;
+use crate::anthropic::types::{
+    MAX_BUDGET_TOKENS, Message, MessagesRequest, SystemMessage, Thinking, Tool,
+};
+
+use super::types::{
+    Candidate, Content, FunctionCall, FunctionDeclaration, GenerateContentRequest,
+    GenerateContentResponse, Part, UsageMetadata,
+};
+
+/// 未指定 maxOutputTokens 时的默认值
+const DEFAULT_MAX_OUTPUT_TOKENS: i32 = 8192;
+
+/// 将 Gemini 模型名映射为 Anthropic 模型名
+///
+/// 已经是 Claude 模型名的直接透传；flash / lite 系列映射到 Haiku，其余映射到 Sonnet
+pub fn resolve_model(model: &str) -> String {
+    if map_model(model).is_some() {
+        return model.to_string();
+    }
+    let model_lower = model.to_lowercase();
+    if model_lower.contains("flash") || model_lower.contains("lite") {
+        "claude-haiku-4-5".to_string()
+    } else {
+        "claude-sonnet-4-5".to_string()
+    }
+}
+
+/// 将 Gemini 请求转换为 Anthropic MessagesRequest
+pub fn convert_request(
+    model: &str,
+    req: GenerateContentRequest,
+    stream: bool,
+) -> Result<MessagesRequest, String> {
+    if req.contents.is_empty() {
+        return Err("contents is empty".to_string());
+    }
+
+    let system = req
+        .system_instruction
+        .map(|content| join_text(&content.parts))
+        .filter(|text| !text.is_empty())
+        .map(|text| vec![SystemMessage { text }]);
+
+    // Gemini 的 functionCall/functionResponse 通常不带 id，按函数名顺序配对生成 tool_use_id
+    let mut pending_calls: HashMap<String, VecDeque<String>> = HashMap::new();
+    let messages = req
+        .contents
+        .into_iter()
+        .map(|content| convert_content(content, &mut pending_calls))
+        .filter(|message| message.content.as_array().is_some_and(|blocks| !blocks.is_empty()))
+        .collect::<Vec<_>>();
+    if messages.is_empty() {
+        return Err("contents has no supported parts".to_string());
+    }
+
+    let tools: Vec<Tool> = req
+        .tools
+        .into_iter()
+        .flat_map(|t| t.function_declarations)
+        .map(convert_function_declaration)
+        .collect();
+
+    let generation_config = req.generation_config.unwrap_or_default();
+    let thinking = generation_config
+        .thinking_config
+        .and_then(|c| c.thinking_budget)
+        .filter(|budget| *budget > 0)
+        .map(|budget| Thinking {
+            thinking_type: "enabled".to_string(),
+            budget_tokens: budget.min(MAX_BUDGET_TOKENS),
+        });
+
+    Ok(MessagesRequest {
+        model: resolve_model(model),
+        max_tokens: generation_config
+            .max_output_tokens
+            .unwrap_or(DEFAULT_MAX_OUTPUT_TOKENS),
+        messages,
+        stream,
+        system,
+        tools: (!tools.is_empty()).then_some(tools),
+        tool_choice: None,
+        thinking,
+        stop_sequences: None,
+        temperature: None,
+        top_p: None,
+        top_k: None,
+        metadata: None,
+        betas: Vec::new(),
+    })
+}
+
+/// 拼接所有文本片段
+fn join_text(parts: &[Part]) -> String {
+    parts
+        .iter()
+        .filter_map(|p| p.text.as_deref())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 转换单条对话内容为 Anthropic 消息
+fn convert_content(
+    content: Content,
+    pending_calls: &mut HashMap<String, VecDeque<String>>,
+) -> Message {
+    let role = match content.role.as_deref() {
+        Some("model") => "assistant",
+        _ => "user",
+    };
+
+    let mut blocks = Vec::new();
+    for part in content.parts {
+        // 历史中的思考内容不回传给上游
+        if part.thought == Some(true) {
+            continue;
+        }
+        if let Some(text) = part.text {
+            blocks.push(json!({ "type": "text", "text": text }));
+        }
+        if let Some(blob) = part.inline_data {
+            blocks.push(json!({
+                "type": "image",
+                "source": {
+                    "type": "base64",
+                    "media_type": blob.mime_type,
+                    "data": blob.data
+                }
+            }));
+        }
+        if let Some(file) = part.file_data {
+            // 交由 images::resolve_remote_images 下载
+            blocks.push(json!({
+                "type": "image",
+                "source": { "type": "url", "url": file.file_uri }
+            }));
+        }
+        if let Some(call) = part.function_call {
+            let id = call.id.unwrap_or_else(new_tool_use_id);
+            pending_calls
+                .entry(call.name.clone())
+                .or_default()
+                .push_back(id.clone());
+            blocks.push(json!({
+                "type": "tool_use",
+                "id": id,
+                "name": call.name,
+                "input": if call.args.is_null() { json!({}) } else { call.args }
+            }));
+        }
+        if let Some(response) = part.function_response {
+            let paired = pending_calls
+                .get_mut(&response.name)
+                .and_then(|ids| ids.pop_front());
+            let id = response.id.or(paired).unwrap_or_else(new_tool_use_id);
+            blocks.push(json!({
+                "type": "tool_result",
+                "tool_use_id": id,
+                "content": response.response.to_string()
+            }));
+        }
+    }
+
+    Message {
+        role: role.to_string(),
+        content: serde_json::Value::Array(blocks),
+    }
+}
+
+fn new_tool_use_id() -> String {
+    format!("toolu_{}", Uuid::new_v4().simple())
+}
+
+/// 转换函数声明为 Anthropic 工具定义
+fn convert_function_declaration(decl: FunctionDeclaration) -> Tool {
+    let mut schema = decl
+        .parameters_json_schema
+        .or(decl.parameters)
+        .unwrap_or_else(|| json!({ "type": "object", "properties": {} }));
+    normalize_schema_types(&mut schema);
+
+    Tool {
+        tool_type: None,
+        name: decl.name,
+        description: decl.description,
+        input_schema: serde_json::from_value(schema).unwrap_or_default(),
+        max_uses: None,
+    }
+}
+
+/// Gemini 的 OpenAPI schema 使用大写类型名（OBJECT / STRING），转换为 JSON Schema 小写形式
+fn normalize_schema_types(schema: &mut serde_json::Value) {
+    match schema {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                match (key.as_str(), value) {
+                    ("type", serde_json::Value::String(t)) => *t = t.to_lowercase(),
+                    (_, value) => normalize_schema_types(value),
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(normalize_schema_types),
+        _ => {}
+    }
+}
+
+/// 正在接收参数的函数调用
+struct PendingCall {
+    id: String,
+    name: String,
+    args_json: String,
+}
+
+/// Anthropic SSE 事件 -> Gemini 响应映射器
+pub struct ResponseMapper {
+    model: String,
+    response_id: String,
+    include_thoughts: bool,
+    /// 内容块索引 -> 正在接收参数的函数调用
+    calls: HashMap<i64, PendingCall>,
+}
+
+impl ResponseMapper {
+    pub fn new(model: impl Into<String>, include_thoughts: bool) -> Self {
+        Self {
+            model: model.into(),
+            response_id: Uuid::new_v4().simple().to_string(),
+            include_thoughts,
+            calls: HashMap::new(),
+        }
+    }
+
+    /// 映射一批事件，产出一个响应片段（没有新内容且未结束时返回 None）
+    pub fn map_events(&mut self, events: &[SseEvent]) -> Option<GenerateContentResponse> {
+        let mut parts: Vec<Part> = Vec::new();
+        let mut finish_reason = None;
+        let mut usage_metadata = None;
+
+        for event in events {
+            let data = &event.data;
+            let index = data["index"].as_i64().unwrap_or_default();
+            match event.event.as_str() {
+                "content_block_start" => {
+                    let block = &data["content_block"];
+                    if block["type"] == "tool_use" {
+                        self.calls.insert(
+                            index,
+                            PendingCall {
+                                id: block["id"].as_str().unwrap_or_default().to_string(),
+                                name: block["name"].as_str().unwrap_or_default().to_string(),
+                                args_json: String::new(),
+                            },
+                        );
+                    }
+                }
+                "content_block_delta" => {
+                    let delta = &data["delta"];
+                    match delta["type"].as_str() {
+                        Some("text_delta") => {
+                            push_text(&mut parts, delta["text"].as_str().unwrap_or_default(), false)
+                        }
+                        Some("thinking_delta") if self.include_thoughts => push_text(
+                            &mut parts,
+                            delta["thinking"].as_str().unwrap_or_default(),
+                            true,
+                        ),
+                        Some("input_json_delta") => {
+                            if let Some(call) = self.calls.get_mut(&index) {
+                                call.args_json
+                                    .push_str(delta["partial_json"].as_str().unwrap_or_default());
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                "content_block_stop" => {
+                    if let Some(call) = self.calls.remove(&index) {
+                        let args = json_repair::parse_tool_input(&call.args_json, &call.id);
+                        parts.push(Part {
+                            function_call: Some(FunctionCall {
+                                id: Some(call.id),
+                                name: call.name,
+                                args,
+                            }),
+                            ..Default::default()
+                        });
+                    }
+                }
+                "message_delta" => {
+                    finish_reason = Some(
+                        match data["delta"]["stop_reason"].as_str() {
+                            Some("max_tokens") => "MAX_TOKENS",
+                            _ => "STOP",
+                        }
+                        .to_string(),
+                    );
+                    let prompt = data["usage"]["input_tokens"].as_i64().unwrap_or_default() as i32;
+                    let candidates =
+                        data["usage"]["output_tokens"].as_i64().unwrap_or_default() as i32;
+                    usage_metadata = Some(UsageMetadata {
+                        prompt_token_count: prompt,
+                        candidates_token_count: candidates,
+                        total_token_count: prompt + candidates,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        if parts.is_empty() && finish_reason.is_none() {
+            return None;
+        }
+
+        Some(GenerateContentResponse {
+            candidates: vec![Candidate {
+                content: Content {
+                    role: Some("model".to_string()),
+                    parts,
+                },
+                finish_reason,
+                index: 0,
+            }],
+            usage_metadata,
+            model_version: self.model.clone(),
+            response_id: self.response_id.clone(),
+        })
+    }
+}
+
+/// 追加文本片段，与前一个同类文本片段合并
+fn push_text(parts: &mut Vec<Part>, text: &str, thought: bool) {
+    if text.is_empty() {
+        return;
+    }
+    let thought = thought.then_some(true);
+    if let Some(last) = parts.last_mut() {
+        if last.thought == thought {
+            if let Some(existing) = last.text.as_mut() {
+                existing.push_str(text);
+                return;
+            }
+        }
+    }
+    parts.push(Part {
+        text: Some(text.to_string()),
+        thought,
+        ..Default::default()
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_model() {
+        assert_eq!(resolve_model("gemini-2.5-pro"), "claude-sonnet-4-5");
+        assert_eq!(resolve_model("gemini-2.5-flash-lite"), "claude-haiku-4-5");
+        assert_eq!(resolve_model("claude-opus-4-5"), "claude-opus-4-5");
+    }
+
+    #[test]
+    fn test_convert_request_pairs_function_calls() {
+        let req: GenerateContentRequest = serde_json::from_value(json!({
+            "systemInstruction": { "parts": [{ "text": "be brief" }] },
+            "contents": [
+                { "role": "user", "parts": [{ "text": "weather?" }] },
+                { "role": "model", "parts": [{ "functionCall": { "name": "get_weather", "args": { "city": "Paris" } } }] },
+                { "role": "user", "parts": [{ "functionResponse": { "name": "get_weather", "response": { "temp": 20 } } }] }
+            ],
+            "tools": [{ "functionDeclarations": [{
+                "name": "get_weather",
+                "parameters": { "type": "OBJECT", "properties": { "city": { "type": "STRING" } } }
+            }] }],
+            "generationConfig": { "maxOutputTokens": 256, "thinkingConfig": { "thinkingBudget": 1024 } }
+        }))
+        .unwrap();
+
+        let converted = convert_request("gemini-2.5-pro", req, false).unwrap();
+        assert_eq!(converted.max_tokens, 256);
+        assert_eq!(converted.system.unwrap()[0].text, "be brief");
+        assert_eq!(converted.thinking.unwrap().budget_tokens, 1024);
+        assert_eq!(converted.messages[1].role, "assistant");
+
+        let tool_use_id = converted.messages[1].content[0]["id"].clone();
+        assert_eq!(converted.messages[2].content[0]["type"], "tool_result");
+        assert_eq!(converted.messages[2].content[0]["tool_use_id"], tool_use_id);
+
+        let tool = &converted.tools.unwrap()[0];
+        assert_eq!(tool.input_schema["type"], "object");
+        assert_eq!(tool.input_schema["properties"]["city"]["type"], "string");
+    }
+
+    #[test]
+    fn test_convert_request_rejects_empty_contents() {
+        let req: GenerateContentRequest = serde_json::from_value(json!({ "contents": [] })).unwrap();
+        assert!(convert_request("gemini-2.5-pro", req, false).is_err());
+
+        // 仅含未知片段的内容也视为空
+        let req = GenerateContentRequest {
+            contents: vec![Content {
+                role: Some("user".to_string()),
+                parts: vec![Part::default()],
+            }],
+            system_instruction: None,
+            tools: Vec::new(),
+            generation_config: None,
+        };
+        assert!(convert_request("gemini-2.5-pro", req, false).is_err());
+    }
+
+    #[test]
+    fn test_response_mapper() {
+        let mut mapper = ResponseMapper::new("claude-sonnet-4-5", false);
+        let events = vec![
+            SseEvent::new("content_block_start", json!({ "index": 0, "content_block": { "type": "text", "text": "" } })),
+            SseEvent::new("content_block_delta", json!({ "index": 0, "delta": { "type": "text_delta", "text": "Hel" } })),
+            SseEvent::new("content_block_delta", json!({ "index": 0, "delta": { "type": "text_delta", "text": "lo" } })),
+            SseEvent::new("content_block_start", json!({ "index": 1, "content_block": { "type": "tool_use", "id": "t1", "name": "f" } })),
+            SseEvent::new("content_block_delta", json!({ "index": 1, "delta": { "type": "input_json_delta", "partial_json": "{\"a\":" } })),
+            SseEvent::new("content_block_delta", json!({ "index": 1, "delta": { "type": "input_json_delta", "partial_json": "1}" } })),
+            SseEvent::new("content_block_stop", json!({ "index": 1 })),
+            SseEvent::new("message_delta", json!({ "delta": { "stop_reason": "tool_use" }, "usage": { "input_tokens": 10, "output_tokens": 5 } })),
+        ];
+
+        let response = mapper.map_events(&events).unwrap();
+        let candidate = &response.candidates[0];
+        assert_eq!(candidate.content.parts.len(), 2);
+        assert_eq!(candidate.content.parts[0].text.as_deref(), Some("Hello"));
+        let call = candidate.content.parts[1].function_call.as_ref().unwrap();
+        assert_eq!(call.name, "f");
+        assert_eq!(call.args["a"], 1);
+        assert_eq!(candidate.finish_reason.as_deref(), Some("STOP"));
+        assert_eq!(response.usage_metadata.unwrap().total_token_count, 15);
+
+        assert!(mapper.map_events(&[]).is_none());
+    }
+}
@@ -0,0 +1,496 @@
+//! Gemini API Handler 函数
+
+use std::convert::Infallible;
+use std::time::Duration;
+
+use axum::{
+    Extension, Json as JsonExtractor,
+    body::Body,
+    extract::{Path, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Json, Response},
+};
+use bytes::Bytes;
+use futures::{Stream, StreamExt, stream};
+
+use crate::anthropic::converter::{ConversionError, convert_request as convert_to_kiro, thinking_budget};
+use crate::anthropic::limits;
+use crate::anthropic::images::resolve_remote_images;
+use crate::anthropic::middleware::AppState;
+use crate::anthropic::stream::{SseEvent, StreamContext};
+use crate::api_keys::Tenant;
+use crate::error_code::ErrorCode;
+use crate::group_budgets::GroupBudgetExceeded;
+use crate::kiro::idle_stream::{self, StreamReadError};
+use crate::kiro::model::events::Event;
+use crate::kiro::model::requests::kiro::KiroRequest;
+use crate::kiro::parser::decoder::EventStreamDecoder;
+use crate::kiro::provider::UpstreamThrottled;
+use crate::kiro::request_queue::QueueRejected;
+use crate::kiro::token_manager::CredentialUnavailable;
+use crate::metrics::{StreamTermination, TerminationRecorder};
+use crate::model_mapping::MODEL_MAPPER;
+use crate::redaction::REDACTOR;
+use crate::request_transform::REQUEST_TRANSFORMER;
+use crate::proxy_lifecycle::ProxyLifecycle;
+use crate::token;
+use crate::watermark::ResponseGroup;
+
+use super::converter::{ResponseMapper, convert_request};
+use super::types::{ErrorResponse, GenerateContentRequest, GenerateContentResponse};
+
+/// 构建 Gemini 风格错误响应（错误码按 HTTP 状态码推断）
+fn error_response(status: StatusCode, gemini_status: &str, message: impl Into<String>) -> Response {
+    coded_error_response(status, gemini_status, ErrorCode::from_status(status.as_u16()), message)
+}
+
+/// 构建带指定错误码的 Gemini 风格错误响应
+fn coded_error_response(
+    status: StatusCode,
+    gemini_status: &str,
+    code: ErrorCode,
+    message: impl Into<String>,
+) -> Response {
+    (
+        status,
+        Json(ErrorResponse::new(status.as_u16(), gemini_status, message).with_code(code)),
+    )
+        .into_response()
+}
+
+/// 将 Kiro API 调用失败转换为错误响应
+fn upstream_error_response(e: anyhow::Error) -> Response {
+    tracing::error!("Kiro API 调用失败: {}", e);
+    crate::metrics::REALTIME_STATS.record_error();
+    if e.is::<CredentialUnavailable>() {
+        return coded_error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "UNAVAILABLE",
+            ErrorCode::CredentialUnavailable,
+            e.to_string(),
+        );
+    }
+    if e.is::<QueueRejected>() {
+        return coded_error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "UNAVAILABLE",
+            ErrorCode::Overloaded,
+            e.to_string(),
+        );
+    }
+    if e.is::<GroupBudgetExceeded>() {
+        return coded_error_response(
+            StatusCode::TOO_MANY_REQUESTS,
+            "RESOURCE_EXHAUSTED",
+            ErrorCode::GroupBudgetExceeded,
+            e.to_string(),
+        );
+    }
+    if e.is::<UpstreamThrottled>() {
+        return coded_error_response(
+            StatusCode::TOO_MANY_REQUESTS,
+            "RESOURCE_EXHAUSTED",
+            ErrorCode::UpstreamThrottled,
+            e.to_string(),
+        );
+    }
+    error_response(
+        StatusCode::BAD_GATEWAY,
+        "INTERNAL",
+        format!("上游 API 调用失败: {}", e),
+    )
+}
+
+/// POST /v1beta/models/{model}:generateContent
+/// POST /v1beta/models/{model}:streamGenerateContent
+///
+/// 流式响应总是以 SSE（等价于 `alt=sse`）返回
+pub async fn post_model_action(
+    State(state): State<AppState>,
+    tenant: Option<Extension<Tenant>>,
+    Path(model_action): Path<String>,
+    JsonExtractor(payload): JsonExtractor<GenerateContentRequest>,
+) -> Response {
+    let Some((model, action)) = model_action.split_once(':') else {
+        return error_response(StatusCode::NOT_FOUND, "NOT_FOUND", "缺少方法名，例如 :generateContent");
+    };
+    let stream = match action {
+        "generateContent" => false,
+        "streamGenerateContent" => true,
+        _ => {
+            return error_response(
+                StatusCode::NOT_FOUND,
+                "NOT_FOUND",
+                format!("不支持的方法: {}", action),
+            );
+        }
+    };
+
+    // 模型名映射（在白名单检查之前）
+    let mapped_model = MODEL_MAPPER.resolve(model);
+    let model = mapped_model.as_deref().unwrap_or(model);
+
+    let tenant = tenant.map(|Extension(t)| t);
+    if let Some(tenant) = &tenant {
+        if !tenant.allows_model(model) {
+            return coded_error_response(
+                StatusCode::FORBIDDEN,
+                "PERMISSION_DENIED",
+                ErrorCode::ModelNotAllowed,
+                format!("API key '{}' is not allowed to use model {}", tenant.name, model),
+            );
+        }
+    }
+    let tenant_transform = tenant.as_ref().and_then(|t| t.request_transform.clone());
+    let api_key_id = tenant.map(|t| t.id);
+
+    let include_thoughts = payload
+        .generation_config
+        .as_ref()
+        .and_then(|c| c.thinking_config.as_ref())
+        .is_some_and(|c| c.include_thoughts);
+
+    let mut request = match convert_request(model, payload, stream) {
+        Ok(request) => request,
+        Err(message) => return error_response(StatusCode::BAD_REQUEST, "INVALID_ARGUMENT", message),
+    };
+    REQUEST_TRANSFORMER.apply(&mut request, tenant_transform.as_ref());
+    // 出站内容脱敏（在日志记录之前）
+    let redactions = REDACTOR.apply(&mut request);
+
+    // 下载 fileData 引用的图片并改写为 base64
+    if let Err(message) = resolve_remote_images(&mut request.messages).await {
+        tracing::warn!("图片处理失败: {}", message);
+        return coded_error_response(
+            StatusCode::BAD_REQUEST,
+            "INVALID_ARGUMENT",
+            ErrorCode::InvalidImage,
+            message,
+        );
+    }
+
+    tracing::info!(
+        model = %model,
+        mapped_model = %request.model,
+        stream = %stream,
+        message_count = %request.messages.len(),
+        "📨 收到 Gemini {} 请求",
+        action
+    );
+
+    // 记录到 Admin UI 日志
+    {
+        use crate::logs::{LOG_COLLECTOR, RequestInfo};
+        let active_group = state
+            .kiro_provider
+            .as_ref()
+            .and_then(|p| p.token_manager().get_active_group());
+        LOG_COLLECTOR.add_request_log(
+            RequestInfo {
+                model: request.model.clone(),
+                max_tokens: request.max_tokens,
+                stream,
+                message_count: request.messages.len(),
+                system_preview: request
+                    .system
+                    .as_ref()
+                    .map(|s| {
+                        let combined = s.iter().map(|m| m.text.as_str()).collect::<Vec<_>>().join(" ");
+                        combined.chars().take(50).collect()
+                    })
+                    .unwrap_or_else(|| "(无)".to_string()),
+                user_message_preview: request
+                    .messages
+                    .iter()
+                    .rev()
+                    .find(|m| m.role == "user")
+                    .map(|m| m.content_preview(100))
+                    .unwrap_or_default(),
+            },
+            active_group,
+        );
+    }
+
+    let Some(provider) = state.kiro_provider.clone() else {
+        tracing::error!("KiroProvider 未配置");
+        return error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "UNAVAILABLE",
+            "Kiro API provider not configured",
+        );
+    };
+
+    // 消息数、工具数超出上限时直接拒绝
+    if let Err(e) = limits::check_counts(&request, &provider.token_manager().config().request_limits) {
+        tracing::warn!("请求超出规模限制: {}", e);
+        return coded_error_response(StatusCode::BAD_REQUEST, "INVALID_ARGUMENT", e.code(), e.to_string());
+    }
+
+    // 估算输入 tokens，并按模型能力校验 maxOutputTokens 与上下文窗口
+    let input_tokens = token::count_all_tokens(
+        request.model.clone(),
+        request.system.clone(),
+        request.messages.clone(),
+        request.tools.clone(),
+    ) as i32;
+    let auto_clamp = provider.token_manager().config().auto_clamp_max_tokens;
+    match limits::enforce(&mut request, input_tokens, auto_clamp) {
+        Ok(Some(original)) => {
+            tracing::info!("max_tokens 已自动压低: {} -> {}", original, request.max_tokens);
+        }
+        Ok(None) => {}
+        Err(e) => {
+            tracing::warn!("请求超出模型限制: {}", e);
+            return coded_error_response(StatusCode::BAD_REQUEST, "INVALID_ARGUMENT", e.code(), e.to_string());
+        }
+    }
+
+    let conversion_result = match convert_to_kiro(&request) {
+        Ok(result) => result,
+        Err(e) => {
+            let (code, message) = match &e {
+                ConversionError::UnsupportedModel(model) => {
+                    (ErrorCode::UnsupportedModel, format!("模型不支持: {}", model))
+                }
+                ConversionError::EmptyMessages => (ErrorCode::InvalidRequest, "消息列表为空".to_string()),
+                ConversionError::InvalidImage(message) => (ErrorCode::InvalidImage, message.clone()),
+                ConversionError::InvalidToolChoice(message) => (ErrorCode::InvalidRequest, message.clone()),
+            };
+            tracing::warn!("请求转换失败: {}", e);
+            return coded_error_response(StatusCode::BAD_REQUEST, "INVALID_ARGUMENT", code, message);
+        }
+    };
+
+    let kiro_request = KiroRequest {
+        conversation_state: conversion_result.conversation_state,
+        profile_arn: state.profile_arn.clone(),
+    };
+    let request_body = match serde_json::to_string(&kiro_request) {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::error!("序列化请求失败: {}", e);
+            return error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL",
+                format!("序列化请求失败: {}", e),
+            );
+        }
+    };
+
+    let thinking_budget = thinking_budget(&request);
+
+    let mut ctx = StreamContext::new_with_thinking(&request.model, input_tokens, thinking_budget.is_some());
+    ctx.thinking_budget = thinking_budget;
+    ctx.tool_choice = conversion_result.tool_choice;
+    ctx.api_key_id = api_key_id;
+    let mapper = ResponseMapper::new(&request.model, include_thoughts);
+
+    if stream {
+        let upstream = match provider.call_api_stream(&request_body, None).await {
+            Ok(resp) => resp,
+            Err(e) => return upstream_error_response(e),
+        };
+        let group = ResponseGroup(upstream.group_id.clone());
+        ctx.group_id = Some(upstream.group_id);
+        ctx.credential_slot = upstream.slot;
+
+        let response = Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "text/event-stream")
+            .header(header::CACHE_CONTROL, "no-cache")
+            .header(header::CONNECTION, "keep-alive")
+            .extension(group)
+            .body(Body::from_stream(create_sse_stream(
+                upstream.response,
+                ctx,
+                mapper,
+                state.proxy.clone(),
+                provider.token_manager().config().upstream_timeouts.idle(),
+            )))
+            .unwrap();
+        redactions.mark(response)
+    } else {
+        let upstream = match provider.call_api(&request_body, None).await {
+            Ok(resp) => resp,
+            Err(e) => return upstream_error_response(e),
+        };
+        let group = ResponseGroup(upstream.group_id.clone());
+        ctx.group_id = Some(upstream.group_id);
+
+        let body_bytes = match upstream.response.bytes().await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                tracing::error!("读取响应体失败: {}", e);
+                crate::metrics::REALTIME_STATS.record_error();
+                return coded_error_response(
+                    StatusCode::BAD_GATEWAY,
+                    "INTERNAL",
+                    ErrorCode::UpstreamBadResponse,
+                    format!("读取响应失败: {}", e),
+                );
+            }
+        };
+
+        let mut decoder = EventStreamDecoder::new();
+        if let Err(e) = decoder.feed(&body_bytes) {
+            tracing::warn!("缓冲区溢出: {}", e);
+        }
+        let mut events = ctx.generate_initial_events();
+        events.extend(decode_events(&mut decoder, &mut ctx));
+        events.extend(ctx.generate_final_events());
+
+        let mut mapper = mapper;
+        match mapper.map_events(&events) {
+            Some(response) => redactions.mark((StatusCode::OK, Extension(group), Json(response)).into_response()),
+            None => coded_error_response(
+                StatusCode::BAD_GATEWAY,
+                "INTERNAL",
+                ErrorCode::UpstreamBadResponse,
+                "上游未返回任何内容",
+            ),
+        }
+    }
+}
+
+/// 解码缓冲区中的 Kiro 事件并转换为 Anthropic SSE 事件
+fn decode_events(decoder: &mut EventStreamDecoder, ctx: &mut StreamContext) -> Vec<SseEvent> {
+    let mut events = Vec::new();
+    for result in decoder.decode_iter() {
+        match result {
+            Ok(frame) => {
+                if let Ok(event) = Event::from_frame(frame) {
+                    events.extend(ctx.process_kiro_event(&event));
+                }
+            }
+            Err(e) => {
+                tracing::warn!("解码事件失败: {}", e);
+            }
+        }
+    }
+    events
+}
+
+/// 格式化为 Gemini SSE 数据行
+fn to_sse_bytes(response: &GenerateContentResponse) -> Bytes {
+    Bytes::from(format!(
+        "data: {}\n\n",
+        serde_json::to_string(response).unwrap_or_default()
+    ))
+}
+
+/// 创建 Gemini SSE 事件流
+fn create_sse_stream(
+    response: reqwest::Response,
+    mut ctx: StreamContext,
+    mut mapper: ResponseMapper,
+    proxy: ProxyLifecycle,
+    idle: Option<Duration>,
+) -> impl Stream<Item = Result<Bytes, Infallible>> {
+    // 初始事件只建立块状态，不产生 Gemini 输出
+    let initial_events = ctx.generate_initial_events();
+    mapper.map_events(&initial_events);
+
+    stream::unfold(
+        (
+            idle_stream::body_stream(response, idle),
+            ctx,
+            mapper,
+            EventStreamDecoder::new(),
+            false,
+            proxy,
+            TerminationRecorder::new(),
+        ),
+        |(mut body_stream, mut ctx, mut mapper, mut decoder, finished, proxy, mut recorder)| async move {
+            if finished {
+                return None;
+            }
+
+            let proxy_disabled_chunk = || {
+                let error = ErrorResponse::new(503, "UNAVAILABLE", "Proxy service has been disabled")
+                    .with_code(ErrorCode::ProxyDisabled);
+                Bytes::from(format!(
+                    "data: {}\n\n",
+                    serde_json::to_string(&error).unwrap_or_default()
+                ))
+            };
+
+            if proxy.should_abort_streams() {
+                tracing::info!("代理服务已禁用，中断正在进行的流式响应");
+                recorder.finish(StreamTermination::ProxyDisabled);
+                let bytes = vec![Ok(proxy_disabled_chunk())];
+                return Some((
+                    stream::iter(bytes),
+                    (body_stream, ctx, mapper, decoder, true, proxy, recorder),
+                ));
+            }
+
+            tokio::select! {
+                chunk_result = body_stream.next() => {
+                    let (events, finished) = match chunk_result {
+                        Some(Ok(chunk)) => {
+                            if let Err(e) = decoder.feed(&chunk) {
+                                tracing::warn!("缓冲区溢出: {}", e);
+                            }
+                            (decode_events(&mut decoder, &mut ctx), false)
+                        }
+                        Some(Err(StreamReadError::Idle(limit))) => {
+                            tracing::error!("上游流式响应 {} 秒未返回数据，中断响应", limit.as_secs());
+                            recorder.finish(StreamTermination::IdleTimeout);
+                            let error = ErrorResponse::new(
+                                504,
+                                "DEADLINE_EXCEEDED",
+                                format!("Upstream stream stalled: no data received for {} seconds", limit.as_secs()),
+                            )
+                            .with_code(ErrorCode::UpstreamTimeout);
+                            let bytes = vec![Ok(Bytes::from(format!(
+                                "data: {}\n\n",
+                                serde_json::to_string(&error).unwrap_or_default()
+                            )))];
+                            return Some((
+                                stream::iter(bytes),
+                                (body_stream, ctx, mapper, decoder, true, proxy, recorder),
+                            ));
+                        }
+                        Some(Err(e)) => {
+                            tracing::error!("读取响应流失败: {}", e);
+                            recorder.finish(if e.is_timeout() {
+                                StreamTermination::IdleTimeout
+                            } else {
+                                StreamTermination::UpstreamError
+                            });
+                            (ctx.generate_final_events(), true)
+                        }
+                        None => {
+                            recorder.finish(StreamTermination::Completed);
+                            (ctx.generate_final_events(), true)
+                        }
+                    };
+                    let bytes: Vec<Result<Bytes, Infallible>> = mapper
+                        .map_events(&events)
+                        .map(|response| Ok(to_sse_bytes(&response)))
+                        .into_iter()
+                        .collect();
+                    Some((
+                        stream::iter(bytes),
+                        (body_stream, ctx, mapper, decoder, finished, proxy, recorder),
+                    ))
+                }
+                // 定期检查代理状态
+                _ = tokio::time::sleep(Duration::from_millis(500)) => {
+                    let (bytes, finished) = if proxy.should_abort_streams() {
+                        tracing::info!("代理服务已禁用，中断正在进行的流式响应");
+                        recorder.finish(StreamTermination::ProxyDisabled);
+                        (vec![Ok(proxy_disabled_chunk())], true)
+                    } else {
+                        (Vec::new(), false)
+                    };
+                    Some((
+                        stream::iter(bytes),
+                        (body_stream, ctx, mapper, decoder, finished, proxy, recorder),
+                    ))
+                }
+            }
+        },
+    )
+    .flatten()
+}
@@ -0,0 +1,15 @@
+//! Gemini API 兼容服务模块
+//!
+//! 将 Google Gemini generateContent 请求转换为 Kiro 调用，供写死 Gemini 协议的工具使用。
+//! 与 Anthropic 端点共用认证、限流和反代生命周期。
+//!
+//! # 支持的端点
+//! - `POST /v1beta/models/{model}:generateContent` - 生成内容
+//! - `POST /v1beta/models/{model}:streamGenerateContent` - 流式生成内容（SSE）
+
+mod converter;
+mod handlers;
+mod router;
+pub mod types;
+
+pub use router::create_router;
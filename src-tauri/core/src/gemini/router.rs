@@ -0,0 +1,16 @@
+//! Gemini API 路由配置
+
+use axum::{Router, routing::post};
+
+use crate::anthropic::middleware::AppState;
+
+use super::handlers::post_model_action;
+
+/// 创建 Gemini API 路由（由调用方挂载到 `/v1beta` 并加上认证中间件）
+///
+/// # 端点
+/// - `POST /models/{model}:generateContent` - 生成内容
+/// - `POST /models/{model}:streamGenerateContent` - 流式生成内容
+pub fn create_router() -> Router<AppState> {
+    Router::new().route("/models/{model_action}", post(post_model_action))
+}
@@ -0,0 +1,194 @@
+//! Gemini API 类型定义
+
+use serde::{Deserialize, Serialize};
+
+use crate::error_code::ErrorCode;
+
+// === 错误响应 ===
+
+/// Gemini 风格错误响应
+#[derive(Debug, Serialize)]
+pub struct ErrorResponse {
+    pub error: ErrorDetail,
+}
+
+/// 错误详情
+#[derive(Debug, Serialize)]
+pub struct ErrorDetail {
+    pub code: u16,
+    pub message: String,
+    pub status: String,
+    /// 稳定的机器可读错误码（`code` 字段在 Gemini 协议中为 HTTP 状态码）
+    #[serde(rename = "errorCode")]
+    pub error_code: ErrorCode,
+}
+
+impl ErrorResponse {
+    /// 创建新的错误响应（错误码按 HTTP 状态码推断）
+    pub fn new(code: u16, status: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            error: ErrorDetail {
+                code,
+                message: message.into(),
+                status: status.into(),
+                error_code: ErrorCode::from_status(code),
+            },
+        }
+    }
+
+    /// 指定错误码
+    pub fn with_code(mut self, code: ErrorCode) -> Self {
+        self.error.error_code = code;
+        self
+    }
+}
+
+// === generateContent 请求 ===
+
+/// generateContent / streamGenerateContent 请求体
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerateContentRequest {
+    #[serde(default)]
+    pub contents: Vec<Content>,
+    pub system_instruction: Option<Content>,
+    #[serde(default)]
+    pub tools: Vec<ToolDeclaration>,
+    pub generation_config: Option<GenerationConfig>,
+}
+
+/// 对话内容（role 为 user 或 model）
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Content {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    #[serde(default)]
+    pub parts: Vec<Part>,
+}
+
+/// 内容片段（text / inlineData / fileData / functionCall / functionResponse 之一）
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Part {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// 是否为思考内容
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thought: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inline_data: Option<Blob>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_data: Option<FileData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function_call: Option<FunctionCall>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function_response: Option<FunctionResponse>,
+}
+
+/// 内联二进制数据（base64）
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Blob {
+    pub mime_type: String,
+    pub data: String,
+}
+
+/// 按 URI 引用的文件（仅支持 http/https 图片）
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileData {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    pub file_uri: String,
+}
+
+/// 模型发起的函数调用
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FunctionCall {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub args: serde_json::Value,
+}
+
+/// 客户端返回的函数执行结果
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FunctionResponse {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub response: serde_json::Value,
+}
+
+/// 工具声明（仅支持 functionDeclarations，googleSearch 等内置工具被忽略）
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolDeclaration {
+    #[serde(default)]
+    pub function_declarations: Vec<FunctionDeclaration>,
+}
+
+/// 函数声明
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionDeclaration {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// OpenAPI 子集 schema（类型名可能为大写，如 "OBJECT"）
+    pub parameters: Option<serde_json::Value>,
+    /// 标准 JSON Schema（与 parameters 二选一）
+    pub parameters_json_schema: Option<serde_json::Value>,
+}
+
+/// 生成配置（仅使用与 Kiro 相关的字段）
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationConfig {
+    pub max_output_tokens: Option<i32>,
+    pub thinking_config: Option<ThinkingConfig>,
+}
+
+/// 思考配置
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThinkingConfig {
+    pub thinking_budget: Option<i32>,
+    #[serde(default)]
+    pub include_thoughts: bool,
+}
+
+// === generateContent 响应 ===
+
+/// generateContent 响应（流式响应每个 chunk 也是该结构）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerateContentResponse {
+    pub candidates: Vec<Candidate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage_metadata: Option<UsageMetadata>,
+    pub model_version: String,
+    pub response_id: String,
+}
+
+/// 候选结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Candidate {
+    pub content: Content,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finish_reason: Option<String>,
+    pub index: i32,
+}
+
+/// Token 用量
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageMetadata {
+    pub prompt_token_count: i32,
+    pub candidates_token_count: i32,
+    pub total_token_count: i32,
+}
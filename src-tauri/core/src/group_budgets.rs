@@ -0,0 +1,265 @@
+//! 分组月度预算
+//!
+//! 按凭证所属分组统计本月请求数和 token 用量，与分组配置中的预算比较。
+//! 超出预算的分组按策略拒绝请求或转移到备用分组（见 `MultiTokenManager::effective_group`）。
+//! 用量持久化到配置目录下的 `group_usage.json`，跨月自动清零。
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+
+use crate::api_keys::{MonthlyUsage, current_month};
+use crate::model::config::{BudgetPolicy, GroupBudget, GroupConfig};
+
+/// 用量文件写盘的最小间隔
+const USAGE_SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 分组预算已用尽
+///
+/// 调用方可通过 `downcast_ref` 识别，直接向客户端返回 429 而不再重试
+#[derive(Debug)]
+pub struct GroupBudgetExceeded {
+    pub group_id: String,
+}
+
+impl std::fmt::Display for GroupBudgetExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "分组 '{}' 本月预算已用尽", self.group_id)
+    }
+}
+
+impl std::error::Error for GroupBudgetExceeded {}
+
+/// 分组预算状态（Admin API）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupBudgetStatus {
+    #[serde(flatten)]
+    pub usage: MonthlyUsage,
+    /// 是否已超出预算（未配置预算时恒为 false）
+    pub exceeded: bool,
+}
+
+#[derive(Default)]
+struct UsageState {
+    monthly: HashMap<String, MonthlyUsage>,
+    dirty: bool,
+    last_saved: Option<Instant>,
+}
+
+/// 分组预算注册表
+pub struct GroupBudgets {
+    budgets: RwLock<HashMap<String, GroupBudget>>,
+    usage: Mutex<UsageState>,
+    usage_path: RwLock<Option<PathBuf>>,
+}
+
+impl GroupBudgets {
+    pub fn new() -> Self {
+        Self {
+            budgets: RwLock::new(HashMap::new()),
+            usage: Mutex::new(UsageState::default()),
+            usage_path: RwLock::new(None),
+        }
+    }
+
+    /// 加载分组预算，并从用量文件恢复本月用量
+    pub fn load(&self, groups: &[GroupConfig], usage_path: Option<PathBuf>) {
+        let mut monthly = HashMap::new();
+        if let Some(path) = &usage_path {
+            if let Ok(content) = std::fs::read_to_string(path) {
+                match serde_json::from_str::<HashMap<String, MonthlyUsage>>(&content) {
+                    Ok(loaded) => monthly = loaded,
+                    Err(e) => tracing::warn!("读取分组用量文件失败: {}", e),
+                }
+            }
+        }
+        self.usage.lock().monthly = monthly;
+        *self.usage_path.write() = usage_path;
+        self.set_groups(groups);
+    }
+
+    /// 替换分组预算（Admin API 修改分组后调用）
+    pub fn set_groups(&self, groups: &[GroupConfig]) {
+        *self.budgets.write() = groups
+            .iter()
+            .filter_map(|g| g.budget.clone().map(|b| (g.id.clone(), b)))
+            .collect();
+    }
+
+    /// 记录一次已完成请求的用量
+    pub fn record_usage(&self, group_id: &str, input_tokens: i32, output_tokens: i32) {
+        let month = current_month();
+        let mut usage = self.usage.lock();
+        let entry = usage.monthly.entry(group_id.to_string()).or_default();
+        if entry.month != month {
+            *entry = MonthlyUsage {
+                month,
+                ..Default::default()
+            };
+        }
+        entry.request_count += 1;
+        entry.input_tokens += input_tokens.max(0) as u64;
+        entry.output_tokens += output_tokens.max(0) as u64;
+        usage.dirty = true;
+
+        let due = usage
+            .last_saved
+            .is_none_or(|t| t.elapsed() >= USAGE_SAVE_INTERVAL);
+        if due {
+            self.save_locked(&mut usage);
+        }
+    }
+
+    /// 分组本月用量（跨月后为零）
+    pub fn usage(&self, group_id: &str) -> MonthlyUsage {
+        let month = current_month();
+        self.usage
+            .lock()
+            .monthly
+            .get(group_id)
+            .filter(|u| u.month == month)
+            .cloned()
+            .unwrap_or(MonthlyUsage {
+                month,
+                ..Default::default()
+            })
+    }
+
+    /// 分组已超出预算时返回其处理策略
+    pub fn exceeded(&self, group_id: &str) -> Option<BudgetPolicy> {
+        let budget = self.budgets.read().get(group_id).cloned()?;
+        let usage = self.usage(group_id);
+        let over = budget.monthly_tokens.is_some_and(|max| usage.total_tokens() >= max)
+            || budget.monthly_requests.is_some_and(|max| usage.request_count >= max);
+        over.then_some(budget.policy)
+    }
+
+    /// 分组超出预算且策略为转移
+    pub fn should_fall_back(&self, group_id: &str) -> bool {
+        self.exceeded(group_id) == Some(BudgetPolicy::Fallback)
+    }
+
+    /// 准入检查：分组超出预算（不论策略）时返回 [`GroupBudgetExceeded`]
+    pub fn check(&self, group_id: &str) -> Result<(), GroupBudgetExceeded> {
+        match self.exceeded(group_id) {
+            Some(_) => Err(GroupBudgetExceeded {
+                group_id: group_id.to_string(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// 分组预算状态
+    pub fn status(&self, group_id: &str) -> GroupBudgetStatus {
+        GroupBudgetStatus {
+            usage: self.usage(group_id),
+            exceeded: self.exceeded(group_id).is_some(),
+        }
+    }
+
+    /// 立即保存未落盘的用量
+    pub fn flush(&self) {
+        let mut usage = self.usage.lock();
+        self.save_locked(&mut usage);
+    }
+
+    /// 删除分组的用量记录
+    pub fn remove_usage(&self, group_id: &str) {
+        let mut usage = self.usage.lock();
+        usage.monthly.remove(group_id);
+        usage.dirty = true;
+        self.save_locked(&mut usage);
+    }
+
+    fn save_locked(&self, usage: &mut UsageState) {
+        if !usage.dirty {
+            return;
+        }
+        let Some(path) = self.usage_path.read().clone() else {
+            return;
+        };
+        match serde_json::to_string_pretty(&usage.monthly) {
+            Ok(json) => {
+                if let Err(e) = std::fs::write(&path, json) {
+                    tracing::warn!("保存分组用量失败: {}", e);
+                    return;
+                }
+                usage.dirty = false;
+                usage.last_saved = Some(Instant::now());
+            }
+            Err(e) => tracing::warn!("序列化分组用量失败: {}", e),
+        }
+    }
+}
+
+impl Default for GroupBudgets {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+lazy_static! {
+    /// 全局分组预算
+    pub static ref GROUP_BUDGETS: GroupBudgets = GroupBudgets::new();
+}
+
+/// 初始化全局分组预算，用量文件与配置文件位于同一目录
+pub fn init(groups: &[GroupConfig], config_path: &std::path::Path) {
+    let usage_path = config_path
+        .parent()
+        .map(|dir| dir.join("group_usage.json"));
+    GROUP_BUDGETS.load(groups, usage_path);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(id: &str, budget: Option<GroupBudget>) -> GroupConfig {
+        GroupConfig {
+            id: id.to_string(),
+            name: id.to_string(),
+            budget,
+        }
+    }
+
+    #[test]
+    fn test_budget_limits_and_policy() {
+        let budgets = GroupBudgets::new();
+        budgets.set_groups(&[
+            group("tokens", Some(GroupBudget {
+                monthly_tokens: Some(100),
+                ..Default::default()
+            })),
+            group("requests", Some(GroupBudget {
+                monthly_requests: Some(2),
+                policy: BudgetPolicy::Fallback,
+                ..Default::default()
+            })),
+            group("free", None),
+        ]);
+
+        budgets.record_usage("tokens", 60, 30);
+        assert!(budgets.check("tokens").is_ok());
+        budgets.record_usage("tokens", 5, 5);
+        assert_eq!(budgets.exceeded("tokens"), Some(BudgetPolicy::Reject));
+        assert!(!budgets.should_fall_back("tokens"));
+        assert_eq!(budgets.check("tokens").unwrap_err().group_id, "tokens");
+
+        budgets.record_usage("requests", 1, 1);
+        assert!(budgets.exceeded("requests").is_none());
+        budgets.record_usage("requests", 1, 1);
+        assert!(budgets.should_fall_back("requests"));
+
+        budgets.record_usage("free", 1_000_000, 0);
+        assert!(budgets.check("free").is_ok());
+        let status = budgets.status("free");
+        assert!(!status.exceeded);
+        assert_eq!(status.usage.request_count, 1);
+    }
+}
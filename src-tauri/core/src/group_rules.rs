@@ -0,0 +1,119 @@
+//! 导入凭证自动分组规则
+//!
+//! 导入时按配置顺序把凭证属性（邮箱域名、订阅类型、认证方式）与规则匹配，
+//! 第一条命中的规则决定凭证所属分组，例如 `@corp.com` → "work"、FREE → "free"。
+
+use crate::model::config::{GroupConfig, GroupRule, GroupRuleField};
+
+/// 用于匹配规则的凭证属性
+#[derive(Debug, Default)]
+pub struct CredentialTraits<'a> {
+    pub email: Option<&'a str>,
+    pub subscription_title: Option<&'a str>,
+    pub auth_method: Option<&'a str>,
+}
+
+/// 规则的可读描述（写入导入报告）
+pub fn describe(rule: &GroupRule) -> String {
+    let field = match rule.field {
+        GroupRuleField::EmailDomain => "emailDomain",
+        GroupRuleField::Subscription => "subscription",
+        GroupRuleField::AuthMethod => "authMethod",
+    };
+    format!("{}={} → {}", field, rule.pattern, rule.group_id)
+}
+
+/// 检查单条规则是否命中（均忽略大小写）
+fn matches(rule: &GroupRule, traits: &CredentialTraits<'_>) -> bool {
+    let pattern = rule.pattern.trim().to_lowercase();
+    match rule.field {
+        GroupRuleField::EmailDomain => {
+            let domain = pattern.trim_start_matches('@');
+            traits
+                .email
+                .and_then(|email| email.rsplit_once('@'))
+                .is_some_and(|(_, d)| d.eq_ignore_ascii_case(domain))
+        }
+        GroupRuleField::Subscription => traits
+            .subscription_title
+            .is_some_and(|title| title.to_lowercase().contains(&pattern)),
+        GroupRuleField::AuthMethod => traits
+            .auth_method
+            .is_some_and(|method| method.eq_ignore_ascii_case(&pattern)),
+    }
+}
+
+/// 查找第一条命中的规则
+pub fn assign<'a>(rules: &'a [GroupRule], traits: &CredentialTraits<'_>) -> Option<&'a GroupRule> {
+    rules.iter().find(|rule| matches(rule, traits))
+}
+
+/// 校验规则：pattern 不能为空，目标分组必须存在
+pub fn validate(rules: &[GroupRule], groups: &[GroupConfig]) -> Result<(), String> {
+    for rule in rules {
+        if rule.pattern.trim().trim_start_matches('@').is_empty() {
+            return Err("分组规则的匹配内容不能为空".to_string());
+        }
+        if !groups.iter().any(|g| g.id == rule.group_id) {
+            return Err(format!("分组规则的目标分组不存在: {}", rule.group_id));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(field: GroupRuleField, pattern: &str, group_id: &str) -> GroupRule {
+        GroupRule {
+            field,
+            pattern: pattern.to_string(),
+            group_id: group_id.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let rules = vec![
+            rule(GroupRuleField::EmailDomain, "@Corp.com", "work"),
+            rule(GroupRuleField::Subscription, "free", "free"),
+            rule(GroupRuleField::AuthMethod, "idc", "enterprise"),
+        ];
+
+        let work = CredentialTraits {
+            email: Some("alice@corp.com"),
+            subscription_title: Some("KIRO FREE"),
+            auth_method: Some("social"),
+        };
+        assert_eq!(assign(&rules, &work).unwrap().group_id, "work");
+
+        let free = CredentialTraits {
+            email: Some("bob@gmail.com"),
+            subscription_title: Some("KIRO FREE"),
+            auth_method: Some("social"),
+        };
+        assert_eq!(assign(&rules, &free).unwrap().group_id, "free");
+
+        let idc = CredentialTraits {
+            email: None,
+            subscription_title: Some("KIRO PRO"),
+            auth_method: Some("IdC"),
+        };
+        assert_eq!(assign(&rules, &idc).unwrap().group_id, "enterprise");
+
+        assert!(assign(&rules, &CredentialTraits::default()).is_none());
+    }
+
+    #[test]
+    fn test_validate_rejects_unknown_group() {
+        let groups = vec![GroupConfig {
+            id: "default".to_string(),
+            name: "默认分组".to_string(),
+            budget: None,
+        }];
+        assert!(validate(&[rule(GroupRuleField::AuthMethod, "idc", "default")], &groups).is_ok());
+        assert!(validate(&[rule(GroupRuleField::AuthMethod, "idc", "missing")], &groups).is_err());
+        assert!(validate(&[rule(GroupRuleField::EmailDomain, "@", "default")], &groups).is_err());
+    }
+}
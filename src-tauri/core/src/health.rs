@@ -0,0 +1,260 @@
+//! 存活与就绪探针
+//!
+//! `/healthz`（存活）只要进程能响应就返回 200；`/readyz`（就绪）检查依赖项：至少一个可用凭证、
+//! Token 刷新端点可达、反代监听器已绑定并接收请求，全部通过返回 200，否则返回 503。
+//! 响应为结构化 JSON，可直接用于 Docker `HEALTHCHECK` 和 Kubernetes 探针。
+//! 刷新端点的探测结果缓存一段时间，避免探针频繁请求上游。
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::{
+    Json, Router,
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use crate::kiro::token_manager::{MultiTokenManager, refresh_endpoint};
+use crate::proxy_lifecycle::{ProxyLifecycle, ProxySnapshot};
+
+/// 刷新端点探测结果的缓存时间
+const PROBE_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// 刷新端点探测超时
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+lazy_static! {
+    /// 刷新端点 -> (探测时间, 结果)
+    static ref PROBE_CACHE: Mutex<HashMap<String, (Instant, Check)>> = Mutex::new(HashMap::new());
+}
+
+/// 单项检查结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Check {
+    pub ok: bool,
+    pub detail: String,
+    /// 探测耗时（毫秒，仅网络探测）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+}
+
+impl Check {
+    fn new(ok: bool, detail: impl Into<String>) -> Self {
+        Self {
+            ok,
+            detail: detail.into(),
+            latency_ms: None,
+        }
+    }
+}
+
+/// 就绪检查的各项结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Checks {
+    pub credentials: Check,
+    pub token_refresh: Check,
+    pub proxy_listener: Check,
+}
+
+/// `/readyz` 响应
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Readiness {
+    /// `ready` 或 `not_ready`
+    pub status: &'static str,
+    pub service: &'static str,
+    pub checks: Checks,
+}
+
+impl Readiness {
+    fn new(service: &'static str, checks: Checks) -> Self {
+        let ready = checks.credentials.ok && checks.token_refresh.ok && checks.proxy_listener.ok;
+        Self {
+            status: if ready { "ready" } else { "not_ready" },
+            service,
+            checks,
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.status == "ready"
+    }
+}
+
+/// 探针所需的状态
+struct HealthState {
+    service: &'static str,
+    token_manager: Arc<MultiTokenManager>,
+    proxy: ProxyLifecycle,
+}
+
+/// 探针路由：`/healthz`（存活）和 `/readyz`（就绪）
+///
+/// `proxy` 为本探针负责的反代服务（单端口模式下即共用的监听器）
+pub fn router<S: Clone + Send + Sync + 'static>(
+    service: &'static str,
+    token_manager: Arc<MultiTokenManager>,
+    proxy: ProxyLifecycle,
+) -> Router<S> {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .with_state(Arc::new(HealthState {
+            service,
+            token_manager,
+            proxy,
+        }))
+}
+
+async fn healthz(State(state): State<Arc<HealthState>>) -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "status": "ok",
+        "service": state.service,
+    }))
+}
+
+async fn readyz(State(state): State<Arc<HealthState>>) -> Response {
+    let snapshot = state.token_manager.snapshot();
+    // 探测当前凭证（或第一个可用凭证）实际使用的刷新端点
+    let auth_method = snapshot
+        .entries
+        .iter()
+        .find(|e| e.id == snapshot.current_id && !e.disabled)
+        .or_else(|| snapshot.entries.iter().find(|e| !e.disabled))
+        .and_then(|e| e.auth_method.clone());
+    let endpoint = refresh_endpoint(auth_method.as_deref(), &state.token_manager.config().region);
+
+    let readiness = Readiness::new(
+        state.service,
+        Checks {
+            credentials: credentials_check(snapshot.available, snapshot.total),
+            token_refresh: probe_refresh(&state.token_manager, &endpoint).await,
+            proxy_listener: listener_check(&state.proxy.snapshot()),
+        },
+    );
+    if !readiness.is_ready() {
+        tracing::debug!("[就绪检查] 未就绪: {:?}", readiness.checks);
+    }
+    let status = if readiness.is_ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(readiness)).into_response()
+}
+
+fn credentials_check(available: usize, total: usize) -> Check {
+    let detail = format!("{}/{} credentials available", available, total);
+    Check::new(available > 0, detail)
+}
+
+fn listener_check(snapshot: &ProxySnapshot) -> Check {
+    match snapshot.port {
+        Some(port) if snapshot.state.accepts_requests() => Check::new(true, format!("listening on port {}", port)),
+        _ => {
+            let state = serde_json::to_value(snapshot.state)
+                .ok()
+                .and_then(|v| v.as_str().map(str::to_string))
+                .unwrap_or_default();
+            let detail = match &snapshot.last_error {
+                Some(error) => format!("proxy is {}: {}", state, error),
+                None => format!("proxy is {}", state),
+            };
+            Check::new(false, detail)
+        }
+    }
+}
+
+/// 探测刷新端点是否可达（收到任意 HTTP 响应即视为可达）
+async fn probe_refresh(token_manager: &MultiTokenManager, endpoint: &str) -> Check {
+    if let Some((checked_at, check)) = PROBE_CACHE.lock().get(endpoint) {
+        if checked_at.elapsed() < PROBE_CACHE_TTL {
+            return check.clone();
+        }
+    }
+
+    let started = Instant::now();
+    let result = token_manager
+        .http_clients()
+        .refresh
+        .head(endpoint)
+        .timeout(PROBE_TIMEOUT)
+        .send()
+        .await;
+    let mut check = match result {
+        Ok(response) => Check::new(true, format!("{} reachable (HTTP {})", endpoint, response.status().as_u16())),
+        Err(e) => {
+            tracing::warn!("[就绪检查] Token 刷新端点不可达: {} ({})", endpoint, e);
+            Check::new(false, format!("{} unreachable: {}", endpoint, e))
+        }
+    };
+    check.latency_ms = Some(started.elapsed().as_millis() as u64);
+
+    PROBE_CACHE
+        .lock()
+        .insert(endpoint.to_string(), (Instant::now(), check.clone()));
+    check
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy_lifecycle::ProxyState;
+    use chrono::Utc;
+
+    fn snapshot(state: ProxyState, port: Option<u16>) -> ProxySnapshot {
+        ProxySnapshot {
+            state,
+            since: Utc::now(),
+            last_error: None,
+            port,
+            addresses: Vec::new(),
+            drain_deadline: None,
+            group_id: None,
+        }
+    }
+
+    #[test]
+    fn test_checks() {
+        assert!(credentials_check(1, 3).ok);
+        assert_eq!(credentials_check(0, 2).detail, "0/2 credentials available");
+        assert!(!credentials_check(0, 0).ok);
+
+        assert!(listener_check(&snapshot(ProxyState::Running, Some(8990))).ok);
+        assert!(!listener_check(&snapshot(ProxyState::Running, None)).ok);
+        let mut crashed = snapshot(ProxyState::Crashed, None);
+        crashed.last_error = Some("address in use".to_string());
+        assert_eq!(listener_check(&crashed).detail, "proxy is crashed: address in use");
+        assert_eq!(listener_check(&snapshot(ProxyState::Draining, Some(8990))).detail, "proxy is draining");
+    }
+
+    #[test]
+    fn test_readiness_status() {
+        let ok = || Check::new(true, "ok");
+        let ready = Readiness::new("kiro-gateway", Checks {
+            credentials: ok(),
+            token_refresh: ok(),
+            proxy_listener: ok(),
+        });
+        assert!(ready.is_ready());
+
+        let not_ready = Readiness::new("kiro-gateway", Checks {
+            credentials: ok(),
+            token_refresh: Check::new(false, "unreachable"),
+            proxy_listener: ok(),
+        });
+        assert!(!not_ready.is_ready());
+        let json = serde_json::to_value(&not_ready).unwrap();
+        assert_eq!(json["status"], "not_ready");
+        assert_eq!(json["checks"]["tokenRefresh"]["ok"], false);
+        assert!(json["checks"]["proxyListener"].get("latencyMs").is_none());
+    }
+}
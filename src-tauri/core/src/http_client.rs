@@ -0,0 +1,196 @@
+//! HTTP Client 构建模块
+//!
+//! 提供统一的 HTTP Client 构建功能，支持代理配置
+
+use reqwest::{Client, Proxy};
+use std::time::Duration;
+
+use crate::model::config::UpstreamTimeoutConfig;
+
+/// 代理配置
+#[derive(Debug, Clone, Default)]
+pub struct ProxyConfig {
+    /// 代理地址，支持 http/https/socks5
+    pub url: String,
+    /// 代理认证用户名
+    pub username: Option<String>,
+    /// 代理认证密码
+    pub password: Option<String>,
+}
+
+impl ProxyConfig {
+    /// 从 url 创建代理配置
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            username: None,
+            password: None,
+        }
+    }
+
+    /// 设置认证信息
+    pub fn with_auth(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.username = Some(username.into());
+        self.password = Some(password.into());
+        self
+    }
+}
+
+/// 构建 HTTP Client
+///
+/// # Arguments
+/// * `proxy` - 可选的代理配置
+/// * `timeout_secs` - 超时时间（秒）
+///
+/// # Returns
+/// 配置好的 reqwest::Client
+pub fn build_client(proxy: Option<&ProxyConfig>, timeout_secs: u64) -> anyhow::Result<Client> {
+    build_client_with_options(proxy, &ClientOptions::with_timeout(timeout_secs))
+}
+
+/// 单个用途 Client 的连接参数
+#[derive(Debug, Clone)]
+pub struct ClientOptions {
+    /// 请求总超时（秒）
+    pub timeout_secs: u64,
+    /// 建立连接超时（秒）
+    pub connect_timeout_secs: u64,
+    /// 空闲连接保留时间（秒）
+    pub pool_idle_timeout_secs: u64,
+    /// 每个 Host 最多保留的空闲连接数
+    pub pool_max_idle_per_host: usize,
+}
+
+impl ClientOptions {
+    /// 仅指定超时，其余使用默认值
+    pub fn with_timeout(timeout_secs: u64) -> Self {
+        Self {
+            timeout_secs,
+            connect_timeout_secs: 30,
+            pool_idle_timeout_secs: 90,
+            pool_max_idle_per_host: usize::MAX,
+        }
+    }
+}
+
+/// 按参数构建 HTTP Client
+pub fn build_client_with_options(
+    proxy: Option<&ProxyConfig>,
+    options: &ClientOptions,
+) -> anyhow::Result<Client> {
+    let mut builder = Client::builder()
+        .timeout(Duration::from_secs(options.timeout_secs))
+        .connect_timeout(Duration::from_secs(options.connect_timeout_secs))
+        .pool_idle_timeout(Duration::from_secs(options.pool_idle_timeout_secs))
+        .pool_max_idle_per_host(options.pool_max_idle_per_host);
+
+    if let Some(proxy_config) = proxy {
+        let mut proxy = Proxy::all(&proxy_config.url)?;
+
+        // 设置代理认证
+        if let (Some(username), Some(password)) = (&proxy_config.username, &proxy_config.password) {
+            proxy = proxy.basic_auth(username, password);
+        }
+
+        builder = builder.proxy(proxy);
+        tracing::debug!("HTTP Client 使用代理: {}", proxy_config.url);
+    }
+
+    Ok(builder.build()?)
+}
+
+/// 按用途划分的 HTTP Client 集合
+///
+/// 各用途使用独立连接池，避免长时间的流式补全占满连接影响 Token 刷新等短请求。
+/// reqwest::Client 内部为 Arc，克隆后共享同一连接池。
+#[derive(Debug, Clone)]
+pub struct HttpClients {
+    /// Token 刷新（Social / IdC）
+    pub refresh: Client,
+    /// 额度查询（getUsageLimits）
+    pub usage: Client,
+    /// MCP 调用（WebSearch 等）
+    pub mcp: Client,
+    /// 对话补全（generateAssistantResponse）
+    pub completion: Client,
+}
+
+impl HttpClients {
+    /// 使用各用途的默认参数构建
+    pub fn new(proxy: Option<&ProxyConfig>) -> anyhow::Result<Self> {
+        Self::with_timeouts(proxy, &UpstreamTimeoutConfig::default())
+    }
+
+    /// 构建，对话补全 Client 使用配置的连接超时和总超时
+    pub fn with_timeouts(proxy: Option<&ProxyConfig>, timeouts: &UpstreamTimeoutConfig) -> anyhow::Result<Self> {
+        Ok(Self {
+            refresh: build_client_with_options(
+                proxy,
+                &ClientOptions {
+                    timeout_secs: 60,
+                    connect_timeout_secs: 15,
+                    pool_idle_timeout_secs: 30,
+                    pool_max_idle_per_host: 4,
+                },
+            )?,
+            usage: build_client_with_options(
+                proxy,
+                &ClientOptions {
+                    timeout_secs: 60,
+                    connect_timeout_secs: 15,
+                    pool_idle_timeout_secs: 30,
+                    pool_max_idle_per_host: 4,
+                },
+            )?,
+            mcp: build_client_with_options(proxy, &ClientOptions::with_timeout(120))?,
+            // 默认 12 分钟总超时，覆盖长时间的流式输出
+            completion: build_client_with_options(
+                proxy,
+                &ClientOptions {
+                    connect_timeout_secs: timeouts.connect_secs.max(1),
+                    ..ClientOptions::with_timeout(timeouts.total_secs.max(1))
+                },
+            )?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proxy_config_new() {
+        let config = ProxyConfig::new("http://127.0.0.1:7890");
+        assert_eq!(config.url, "http://127.0.0.1:7890");
+        assert!(config.username.is_none());
+        assert!(config.password.is_none());
+    }
+
+    #[test]
+    fn test_proxy_config_with_auth() {
+        let config = ProxyConfig::new("socks5://127.0.0.1:1080").with_auth("user", "pass");
+        assert_eq!(config.url, "socks5://127.0.0.1:1080");
+        assert_eq!(config.username, Some("user".to_string()));
+        assert_eq!(config.password, Some("pass".to_string()));
+    }
+
+    #[test]
+    fn test_build_client_without_proxy() {
+        let client = build_client(None, 30);
+        assert!(client.is_ok());
+    }
+
+    #[test]
+    fn test_http_clients_new() {
+        let clients = HttpClients::new(None);
+        assert!(clients.is_ok());
+    }
+
+    #[test]
+    fn test_build_client_with_proxy() {
+        let config = ProxyConfig::new("http://127.0.0.1:7890");
+        let client = build_client(Some(&config), 30);
+        assert!(client.is_ok());
+    }
+}
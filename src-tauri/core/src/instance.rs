@@ -0,0 +1,221 @@
+//! 单实例运行
+//!
+//! 同一配置目录只允许运行一个服务实例：启动时对配置目录下的 `instance.lock` 加排他文件锁，
+//! 加锁失败说明已有实例在运行。已运行的实例在 `127.0.0.1` 的随机端口上监听唤醒请求，
+//! 并把进程 ID、唤醒端口和实际绑定的服务端口写入 `instance.json`；后启动的实例据此通知
+//! 已运行的实例显示主窗口，然后直接退出，不再顺延端口启动第二套服务。
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::events::{AdminEvent, EVENT_BUS, ServerEvent};
+
+/// 唤醒请求内容
+const ACTIVATE_MESSAGE: &str = "activate";
+
+/// 连接已运行实例的超时
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 运行中实例的信息（`instance.json`）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceInfo {
+    pub pid: u32,
+    /// 唤醒请求监听端口
+    pub activation_port: Option<u16>,
+    /// 实际绑定的 Admin API 端口
+    pub admin_port: Option<u16>,
+    /// 实际绑定的反代端口
+    pub proxy_port: Option<u16>,
+}
+
+/// 已在运行的实例
+#[derive(Debug, Clone)]
+pub struct ExistingInstance {
+    pub info: Option<InstanceInfo>,
+}
+
+impl ExistingInstance {
+    /// 请求已运行的实例显示主窗口
+    pub fn activate(&self) -> std::io::Result<()> {
+        let port = self
+            .info
+            .as_ref()
+            .and_then(|info| info.activation_port)
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "运行中的实例未开放唤醒端口"))?;
+        let addr = (Ipv4Addr::LOCALHOST, port).into();
+        let mut stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?;
+        stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+        writeln!(stream, "{}", ACTIVATE_MESSAGE)?;
+        // 等待对方确认，保证唤醒请求已被处理
+        let mut reply = String::new();
+        BufReader::new(stream).read_line(&mut reply)?;
+        Ok(())
+    }
+}
+
+/// 单实例锁（持有期间其他实例无法获取，进程退出时自动释放）
+pub struct InstanceLock {
+    _file: File,
+    info_path: PathBuf,
+    info: Arc<Mutex<InstanceInfo>>,
+}
+
+impl InstanceLock {
+    /// 获取配置目录的单实例锁，已有实例运行时返回该实例的信息
+    pub fn acquire(dir: &Path) -> anyhow::Result<Result<Self, ExistingInstance>> {
+        std::fs::create_dir_all(dir)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(dir.join("instance.lock"))?;
+        let info_path = dir.join("instance.json");
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(std::fs::TryLockError::WouldBlock) => {
+                let info = std::fs::read_to_string(&info_path)
+                    .ok()
+                    .and_then(|content| serde_json::from_str(&content).ok());
+                return Ok(Err(ExistingInstance { info }));
+            }
+            Err(std::fs::TryLockError::Error(e)) => return Err(e.into()),
+        }
+
+        let lock = Self {
+            _file: file,
+            info_path,
+            info: Arc::new(Mutex::new(InstanceInfo {
+                pid: std::process::id(),
+                ..Default::default()
+            })),
+        };
+        lock.save();
+        Ok(Ok(lock))
+    }
+
+    /// 在后台线程监听唤醒请求，收到时调用 `on_activate`
+    pub fn listen_for_activation(&self, on_activate: impl Fn() + Send + 'static) -> std::io::Result<()> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        let port = listener.local_addr()?.port();
+        self.info.lock().activation_port = Some(port);
+        self.save();
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let _ = stream.set_read_timeout(Some(CONNECT_TIMEOUT));
+                let mut line = String::new();
+                let mut reader = BufReader::new(&stream);
+                if reader.read_line(&mut line).is_ok() && line.trim() == ACTIVATE_MESSAGE {
+                    tracing::info!("收到其他实例的唤醒请求");
+                    on_activate();
+                    let _ = writeln!(&stream, "ok");
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// 共享的实例信息写入器（服务端口变化时更新 `instance.json`）
+    pub fn reporter(&self) -> InstanceReporter {
+        InstanceReporter {
+            info_path: self.info_path.clone(),
+            info: self.info.clone(),
+        }
+    }
+
+    fn save(&self) {
+        self.reporter().save();
+    }
+}
+
+/// 记录实际绑定的服务端口
+#[derive(Clone)]
+pub struct InstanceReporter {
+    info_path: PathBuf,
+    info: Arc<Mutex<InstanceInfo>>,
+}
+
+impl InstanceReporter {
+    pub fn set_admin_port(&self, port: Option<u16>) {
+        self.info.lock().admin_port = port;
+        self.save();
+    }
+
+    pub fn set_proxy_port(&self, port: Option<u16>) {
+        self.info.lock().proxy_port = port;
+        self.save();
+    }
+
+    /// 订阅事件总线，随服务启停更新实际绑定的端口（调用时即订阅，不会漏掉之后的启动事件）
+    pub fn track_ports(self) -> impl std::future::Future<Output = ()> + Send + 'static {
+        use tokio::sync::broadcast::error::RecvError;
+
+        let mut rx = EVENT_BUS.subscribe();
+        async move {
+            loop {
+                match rx.recv().await {
+                    Ok(AdminEvent::Server { event: ServerEvent::Started { port, .. } }) => self.set_admin_port(Some(port)),
+                    Ok(AdminEvent::Server { .. }) => {
+                        self.set_admin_port(None);
+                        self.set_proxy_port(None);
+                    }
+                    Ok(AdminEvent::Proxy { status }) if status.group_id.is_none() => {
+                        self.set_proxy_port(status.port.filter(|_| status.state.accepts_requests()));
+                    }
+                    Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        }
+    }
+
+    fn save(&self) {
+        let content = serde_json::to_string_pretty(&*self.info.lock()).unwrap_or_default();
+        if let Err(e) = std::fs::write(&self.info_path, content) {
+            tracing::warn!("写入实例信息失败: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_second_instance_activates_first() {
+        let dir = std::env::temp_dir().join(format!("kiro-instance-{}", uuid::Uuid::new_v4().simple()));
+        let first = InstanceLock::acquire(&dir).unwrap().ok().unwrap();
+        let activations = Arc::new(AtomicUsize::new(0));
+        let counter = activations.clone();
+        first
+            .listen_for_activation(move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+            })
+            .unwrap();
+        first.reporter().set_admin_port(Some(8990));
+
+        let Err(existing) = InstanceLock::acquire(&dir).unwrap() else {
+            panic!("第二个实例不应获取到锁");
+        };
+        let info = existing.info.clone().unwrap();
+        assert_eq!(info.pid, std::process::id());
+        assert_eq!(info.admin_port, Some(8990));
+        existing.activate().unwrap();
+        assert_eq!(activations.load(Ordering::SeqCst), 1);
+
+        // 第一个实例退出后可以重新获取
+        drop(first);
+        assert!(InstanceLock::acquire(&dir).unwrap().is_ok());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,183 @@
+//! 凭证去重
+//!
+//! 多次批量导入后，同一账号可能以多条凭证存在。按账号（邮箱 + Profile ARN，邮箱未知时按
+//! refreshToken 前 50 字符）分组，每组保留 ID 最小的条目，换上组内最新的 Token
+//! （`expiresAt` 最晚，相同时取后导入的），其余条目缺失的元数据（代理、版本、邮箱等）补到保留条目上。
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use super::model::credentials::KiroCredentials;
+
+/// 一组重复凭证的合并结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DedupeGroup {
+    /// 账号邮箱（未知时为 None）
+    pub email: Option<String>,
+    /// 保留的凭证 ID
+    pub kept_id: u64,
+    /// 被合并删除的凭证 ID
+    pub removed_ids: Vec<u64>,
+    /// 保留的 Token 来自哪条凭证
+    pub token_from_id: u64,
+}
+
+/// 合并计划：分组结果和保留条目合并后的凭证
+#[derive(Debug, Clone)]
+pub struct DedupePlan {
+    pub group: DedupeGroup,
+    pub merged: KiroCredentials,
+}
+
+/// 账号标识（无法识别时返回 None，不参与去重）
+fn account_key(cred: &KiroCredentials) -> Option<String> {
+    if let Some(email) = cred.email.as_deref().map(str::trim).filter(|e| !e.is_empty()) {
+        return Some(format!(
+            "{}|{}",
+            email.to_lowercase(),
+            cred.profile_arn.as_deref().unwrap_or_default()
+        ));
+    }
+    cred.refresh_token
+        .as_deref()
+        .filter(|t| !t.is_empty())
+        .map(|t| format!("rt:{}", t.chars().take(50).collect::<String>()))
+}
+
+fn expires_at(cred: &KiroCredentials) -> Option<DateTime<Utc>> {
+    cred.expires_at
+        .as_deref()
+        .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// 计算合并计划（凭证须带 ID，只返回有重复的分组）
+pub fn plan(credentials: &[KiroCredentials]) -> Vec<DedupePlan> {
+    let mut groups: Vec<(String, Vec<&KiroCredentials>)> = Vec::new();
+    for cred in credentials.iter().filter(|c| c.id.is_some()) {
+        let Some(key) = account_key(cred) else { continue };
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, members)) => members.push(cred),
+            None => groups.push((key, vec![cred])),
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(_, mut members)| {
+            members.sort_by_key(|c| c.id);
+            let freshest = members
+                .iter()
+                .max_by_key(|c| (expires_at(c), c.id))
+                .copied()
+                .unwrap_or(members[0]);
+
+            let mut merged = members[0].clone();
+            take_token(&mut merged, freshest);
+            for other in &members[1..] {
+                fill_missing(&mut merged, other);
+            }
+
+            DedupePlan {
+                group: DedupeGroup {
+                    email: merged.email.clone(),
+                    kept_id: members[0].id.unwrap_or_default(),
+                    removed_ids: members[1..].iter().filter_map(|c| c.id).collect(),
+                    token_from_id: freshest.id.unwrap_or_default(),
+                },
+                merged,
+            }
+        })
+        .collect()
+}
+
+/// 换上来源凭证的 Token 及与之配套的认证信息
+fn take_token(target: &mut KiroCredentials, source: &KiroCredentials) {
+    if target.id == source.id {
+        return;
+    }
+    target.access_token = source.access_token.clone();
+    target.refresh_token = source.refresh_token.clone();
+    target.expires_at = source.expires_at.clone();
+    target.auth_method = source.auth_method.clone();
+    target.client_id = source.client_id.clone();
+    target.client_secret = source.client_secret.clone();
+    target.status = source.status.clone();
+}
+
+/// 用其他条目补全保留条目缺失的字段
+fn fill_missing(target: &mut KiroCredentials, other: &KiroCredentials) {
+    fn fill<T: Clone>(field: &mut Option<T>, value: &Option<T>) {
+        if field.is_none() {
+            field.clone_from(value);
+        }
+    }
+    fill(&mut target.profile_arn, &other.profile_arn);
+    fill(&mut target.proxy_url, &other.proxy_url);
+    fill(&mut target.proxy_username, &other.proxy_username);
+    fill(&mut target.proxy_password, &other.proxy_password);
+    fill(&mut target.kiro_version, &other.kiro_version);
+    fill(&mut target.system_version, &other.system_version);
+    fill(&mut target.node_version, &other.node_version);
+    fill(&mut target.machine_id, &other.machine_id);
+    fill(&mut target.machine_id_salt, &other.machine_id_salt);
+    fill(&mut target.email, &other.email);
+    fill(&mut target.subscription_title, &other.subscription_title);
+    fill(&mut target.current_usage, &other.current_usage);
+    fill(&mut target.usage_limit, &other.usage_limit);
+    fill(&mut target.remaining, &other.remaining);
+    fill(&mut target.next_reset_at, &other.next_reset_at);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cred(id: u64, email: Option<&str>, refresh_token: &str, expires_at: &str) -> KiroCredentials {
+        KiroCredentials {
+            id: Some(id),
+            email: email.map(str::to_string),
+            refresh_token: Some(refresh_token.to_string()),
+            expires_at: Some(expires_at.to_string()),
+            status: "normal".to_string(),
+            group_id: "default".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_merges_same_account_and_keeps_freshest_token() {
+        let mut first = cred(1, Some("a@example.com"), "rt-old", "2025-01-01T00:00:00Z");
+        first.status = "invalid".to_string();
+        let mut second = cred(4, Some("A@Example.com "), "rt-new", "2025-06-01T00:00:00Z");
+        second.proxy_url = Some("http://127.0.0.1:7890".to_string());
+        let other = cred(2, Some("b@example.com"), "rt-b", "2025-06-01T00:00:00Z");
+
+        let plans = plan(&[first, other, second]);
+        assert_eq!(plans.len(), 1);
+        let p = &plans[0];
+        assert_eq!(p.group.kept_id, 1);
+        assert_eq!(p.group.removed_ids, vec![4]);
+        assert_eq!(p.group.token_from_id, 4);
+        assert_eq!(p.merged.id, Some(1));
+        assert_eq!(p.merged.refresh_token.as_deref(), Some("rt-new"));
+        assert_eq!(p.merged.status, "normal");
+        assert_eq!(p.merged.proxy_url.as_deref(), Some("http://127.0.0.1:7890"));
+    }
+
+    #[test]
+    fn test_falls_back_to_refresh_token_prefix() {
+        let token = "x".repeat(60);
+        let a = cred(1, None, &token, "2025-01-01T00:00:00Z");
+        let b = cred(2, None, &format!("{}-suffix", token), "2025-01-01T00:00:00Z");
+        let c = cred(3, None, "different", "2025-01-01T00:00:00Z");
+
+        let plans = plan(&[a, b, c]);
+        assert_eq!(plans.len(), 1);
+        // expiresAt 相同时取后导入的 Token
+        assert_eq!(plans[0].group.token_from_id, 2);
+        assert_eq!(plans[0].group.removed_ids, vec![2]);
+    }
+}
@@ -0,0 +1,152 @@
+//! 故障转移追踪
+//!
+//! 一次请求可能在多个凭证之间重试（限流、额度用尽、上游 5xx 等），客户端只能看到最终耗时。
+//! 请求带上 `x-kiro-gateway-failover-trace: true` 时，响应头（流式响应另在开头加一条 SSE 注释）
+//! 中返回每次尝试的凭证、失败原因和上游耗时，方便客户端和排查人员定位慢请求，无需翻查服务端日志。
+
+use std::time::{Duration, Instant};
+
+use axum::http::{HeaderMap, HeaderValue};
+use bytes::Bytes;
+use serde::Serialize;
+
+/// 开启追踪的请求头，同名响应头返回追踪结果
+pub const TRACE_HEADER: &str = "x-kiro-gateway-failover-trace";
+
+/// 请求是否开启了故障转移追踪
+pub fn requested(headers: &HeaderMap) -> bool {
+    headers
+        .get(TRACE_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+}
+
+/// 单次上游尝试
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceAttempt {
+    /// 使用的凭证 ID（获取凭证失败时为 None）
+    pub credential_id: Option<u64>,
+    /// 结果：`ok`、`rate_limited`、`quota_exhausted`、`auth_error`、`server_error`、`network_error` 等
+    pub reason: &'static str,
+    /// 上游 HTTP 状态码
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// 本次上游调用耗时（毫秒）
+    pub latency_ms: u64,
+}
+
+/// 一次请求的故障转移追踪
+#[derive(Debug, Clone)]
+pub struct FailoverTrace {
+    started_at: Instant,
+    attempts: Vec<TraceAttempt>,
+}
+
+impl Default for FailoverTrace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TraceSummary<'a> {
+    attempts: &'a [TraceAttempt],
+    retry_count: usize,
+    upstream_ms: u64,
+    elapsed_ms: u64,
+}
+
+impl FailoverTrace {
+    pub fn new() -> Self {
+        Self {
+            started_at: Instant::now(),
+            attempts: Vec::new(),
+        }
+    }
+
+    /// 记录一次尝试
+    pub fn record(&mut self, credential_id: Option<u64>, reason: &'static str, status: Option<u16>, latency: Duration) {
+        self.attempts.push(TraceAttempt {
+            credential_id,
+            reason,
+            status,
+            latency_ms: latency.as_millis() as u64,
+        });
+    }
+
+    pub fn attempts(&self) -> &[TraceAttempt] {
+        &self.attempts
+    }
+
+    /// 重试次数（首次尝试不计）
+    pub fn retry_count(&self) -> usize {
+        self.attempts.len().saturating_sub(1)
+    }
+
+    /// 上游调用累计耗时（不含退避等待和排队）
+    pub fn upstream_ms(&self) -> u64 {
+        self.attempts.iter().map(|a| a.latency_ms).sum()
+    }
+
+    /// 序列化为紧凑 JSON（只含 ASCII，可直接作为响应头）
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&TraceSummary {
+            attempts: &self.attempts,
+            retry_count: self.retry_count(),
+            upstream_ms: self.upstream_ms(),
+            elapsed_ms: self.started_at.elapsed().as_millis() as u64,
+        })
+        .unwrap_or_default()
+    }
+
+    /// 写入响应头
+    pub fn apply(&self, headers: &mut HeaderMap) {
+        if let Ok(value) = HeaderValue::from_str(&self.to_json()) {
+            headers.insert(TRACE_HEADER, value);
+        }
+    }
+
+    /// SSE 注释行（客户端解析器会忽略，不影响事件流）
+    pub fn sse_comment(&self) -> Bytes {
+        Bytes::from(format!(": failover-trace {}\n\n", self.to_json()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requested() {
+        let mut headers = HeaderMap::new();
+        assert!(!requested(&headers));
+        headers.insert(TRACE_HEADER, HeaderValue::from_static(" True "));
+        assert!(requested(&headers));
+        headers.insert(TRACE_HEADER, HeaderValue::from_static("0"));
+        assert!(!requested(&headers));
+    }
+
+    #[test]
+    fn test_summary() {
+        let mut trace = FailoverTrace::new();
+        trace.record(Some(1), "rate_limited", Some(429), Duration::from_millis(120));
+        trace.record(None, "credential_unavailable", None, Duration::ZERO);
+        trace.record(Some(4), "ok", Some(200), Duration::from_millis(800));
+        assert_eq!(trace.retry_count(), 2);
+        assert_eq!(trace.upstream_ms(), 920);
+
+        let json: serde_json::Value = serde_json::from_str(&trace.to_json()).unwrap();
+        assert_eq!(json["retryCount"], 2);
+        assert_eq!(json["upstreamMs"], 920);
+        assert_eq!(json["attempts"][0]["credentialId"], 1);
+        assert_eq!(json["attempts"][0]["status"], 429);
+        assert!(json["attempts"][1].get("status").is_none());
+
+        let mut headers = HeaderMap::new();
+        trace.apply(&mut headers);
+        assert!(headers.contains_key(TRACE_HEADER));
+        assert!(trace.sse_comment().starts_with(b": failover-trace {"));
+    }
+}
@@ -0,0 +1,156 @@
+//! 凭证失败计数
+//!
+//! 按错误类型分别记录 API 调用失败的时间，配合 [`FailurePolicyConfig`] 决定何时自动禁用凭证：
+//! 网络抖动和 403 的含义不同，不应共用同一个计数；超过计数窗口的失败不再计入。
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::Instant;
+
+use reqwest::StatusCode;
+
+use crate::model::config::{FailurePolicyConfig, FailureRule};
+
+/// 失败的错误类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureClass {
+    /// 401/403 凭证或权限错误
+    Auth,
+    /// 408/5xx 上游瞬态错误
+    Server,
+    /// 网络错误、首字节超时
+    Network,
+    /// 其他错误
+    Other,
+}
+
+impl FailureClass {
+    /// 按上游响应状态码分类
+    pub fn from_status(status: StatusCode) -> Self {
+        match status.as_u16() {
+            401 | 403 => Self::Auth,
+            408 => Self::Server,
+            _ if status.is_server_error() => Self::Server,
+            _ => Self::Other,
+        }
+    }
+
+    /// 对应的禁用阈值
+    pub fn rule(self, policy: &FailurePolicyConfig) -> &FailureRule {
+        match self {
+            Self::Auth => &policy.auth,
+            Self::Server => &policy.server,
+            Self::Network => &policy.network,
+            Self::Other => &policy.other,
+        }
+    }
+}
+
+impl fmt::Display for FailureClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Auth => "凭证/权限错误",
+            Self::Server => "上游错误",
+            Self::Network => "网络错误",
+            Self::Other => "其他错误",
+        })
+    }
+}
+
+/// 单个凭证的失败记录
+#[derive(Debug, Default)]
+pub struct FailureTracker {
+    failures: VecDeque<(FailureClass, Instant)>,
+}
+
+impl FailureTracker {
+    /// 记录一次失败，返回计数窗口内该类失败的次数（该类不计数时返回 None）
+    pub fn record(&mut self, class: FailureClass, policy: &FailurePolicyConfig, now: Instant) -> Option<u32> {
+        if class.rule(policy).threshold == 0 {
+            return None;
+        }
+        self.prune(policy, now);
+        self.failures.push_back((class, now));
+        Some(self.count(class))
+    }
+
+    /// 调用成功或重新启用时清零
+    pub fn clear(&mut self) {
+        self.failures.clear();
+    }
+
+    /// 计数窗口内各类失败的总次数
+    pub fn total(&self, policy: &FailurePolicyConfig, now: Instant) -> u32 {
+        self.failures
+            .iter()
+            .filter(|&&(class, at)| !is_expired(class.rule(policy), at, now))
+            .count() as u32
+    }
+
+    /// 是否有某类失败再发生一次就会达到阈值
+    pub fn near_threshold(&self, policy: &FailurePolicyConfig, now: Instant) -> bool {
+        [FailureClass::Auth, FailureClass::Server, FailureClass::Network, FailureClass::Other]
+            .into_iter()
+            .any(|class| {
+                let count = self
+                    .failures
+                    .iter()
+                    .filter(|&&(c, at)| c == class && !is_expired(class.rule(policy), at, now))
+                    .count() as u32;
+                count > 0 && count + 1 >= class.rule(policy).threshold
+            })
+    }
+
+    fn count(&self, class: FailureClass) -> u32 {
+        self.failures.iter().filter(|(c, _)| *c == class).count() as u32
+    }
+
+    fn prune(&mut self, policy: &FailurePolicyConfig, now: Instant) {
+        self.failures
+            .retain(|&(class, at)| !is_expired(class.rule(policy), at, now));
+    }
+}
+
+fn is_expired(rule: &FailureRule, at: Instant, now: Instant) -> bool {
+    rule.decay().is_some_and(|decay| now.saturating_duration_since(at) >= decay)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_classes_count_separately_and_decay() {
+        let policy = FailurePolicyConfig {
+            network: FailureRule {
+                threshold: 5,
+                decay_mins: 10,
+            },
+            ..Default::default()
+        };
+        let mut tracker = FailureTracker::default();
+        let t0 = Instant::now();
+
+        // 默认不计数的类型
+        assert_eq!(tracker.record(FailureClass::Server, &policy, t0), None);
+
+        assert_eq!(tracker.record(FailureClass::Network, &policy, t0), Some(1));
+        assert_eq!(tracker.record(FailureClass::Auth, &policy, t0), Some(1));
+        assert_eq!(tracker.record(FailureClass::Network, &policy, t0), Some(2));
+        assert!(!tracker.near_threshold(&policy, t0));
+        assert_eq!(tracker.record(FailureClass::Auth, &policy, t0), Some(2));
+        assert!(tracker.near_threshold(&policy, t0));
+
+        // 10 分钟后网络错误过期，凭证错误（不过期）仍然计入
+        let later = t0 + Duration::from_secs(10 * 60);
+        assert_eq!(tracker.total(&policy, later), 2);
+        assert_eq!(tracker.record(FailureClass::Network, &policy, later), Some(1));
+        assert_eq!(tracker.record(FailureClass::Auth, &policy, later), Some(3));
+
+        tracker.clear();
+        assert_eq!(tracker.total(&policy, later), 0);
+        assert_eq!(FailureClass::from_status(StatusCode::BAD_GATEWAY), FailureClass::Server);
+        assert_eq!(FailureClass::from_status(StatusCode::FORBIDDEN), FailureClass::Auth);
+    }
+}
@@ -0,0 +1,100 @@
+//! 上游响应流的空闲超时
+//!
+//! reqwest 的总超时覆盖整个响应，长时间停滞的流要等到总超时（默认 12 分钟）才会结束。
+//! 这里给响应体流加上两次数据之间的空闲超时，超时后产生 [`StreamReadError::Idle`] 并结束流，
+//! 由流式处理循环向客户端发送错误事件。
+//!
+//! 计时器保存在流内部，处理循环中 `select!` 的其他分支（ping、代理状态检查）触发时不会重置。
+
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::stream::{self, BoxStream, Stream, StreamExt};
+
+/// 读取上游响应流失败
+#[derive(Debug)]
+pub enum StreamReadError<E = reqwest::Error> {
+    /// 上游连接错误（含 reqwest 总超时）
+    Upstream(E),
+    /// 超过空闲超时未收到数据
+    Idle(Duration),
+}
+
+impl StreamReadError<reqwest::Error> {
+    /// 是否为超时（空闲超时或 reqwest 总超时）
+    pub fn is_timeout(&self) -> bool {
+        match self {
+            StreamReadError::Upstream(e) => e.is_timeout(),
+            StreamReadError::Idle(_) => true,
+        }
+    }
+}
+
+impl<E: std::fmt::Display> std::fmt::Display for StreamReadError<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamReadError::Upstream(e) => write!(f, "{}", e),
+            StreamReadError::Idle(limit) => write!(f, "上游 {} 秒未返回数据", limit.as_secs()),
+        }
+    }
+}
+
+/// 上游响应体流（`idle` 为 None 时不限制空闲时间）
+pub fn body_stream(
+    response: reqwest::Response,
+    idle: Option<Duration>,
+) -> BoxStream<'static, Result<Bytes, StreamReadError>> {
+    with_idle_timeout(response.bytes_stream(), idle)
+}
+
+/// 给字节流加上空闲超时
+pub fn with_idle_timeout<S, E>(
+    inner: S,
+    idle: Option<Duration>,
+) -> BoxStream<'static, Result<Bytes, StreamReadError<E>>>
+where
+    S: Stream<Item = Result<Bytes, E>> + Send + 'static,
+    E: Send + 'static,
+{
+    stream::unfold((inner.boxed(), false), move |(mut inner, timed_out)| async move {
+        if timed_out {
+            return None;
+        }
+        let next = match idle {
+            Some(limit) => tokio::time::timeout(limit, inner.next()).await.map_err(|_| limit),
+            None => Ok(inner.next().await),
+        };
+        match next {
+            Ok(Some(Ok(chunk))) => Some((Ok(chunk), (inner, false))),
+            Ok(Some(Err(e))) => Some((Err(StreamReadError::Upstream(e)), (inner, false))),
+            Ok(None) => None,
+            Err(limit) => Some((Err(StreamReadError::Idle(limit)), (inner, true))),
+        }
+    })
+    .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_idle_timeout_ends_stalled_stream() {
+        let chunks = stream::iter(vec![Ok::<_, std::io::Error>(Bytes::from_static(b"a"))]).chain(stream::pending());
+        let mut body = with_idle_timeout(chunks, Some(Duration::from_millis(50)));
+
+        assert_eq!(body.next().await.unwrap().unwrap(), Bytes::from_static(b"a"));
+        assert!(matches!(body.next().await, Some(Err(StreamReadError::Idle(_)))));
+        assert!(body.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_without_idle_timeout_passes_through() {
+        let chunks = stream::iter(vec![
+            Ok::<_, std::io::Error>(Bytes::from_static(b"a")),
+            Ok(Bytes::from_static(b"b")),
+        ]);
+        let body = with_idle_timeout(chunks, None);
+        assert_eq!(body.count().await, 2);
+    }
+}
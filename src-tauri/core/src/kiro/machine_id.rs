@@ -0,0 +1,121 @@
+//! 设备指纹生成器
+//!
+
+use sha2::{Digest, Sha256};
+
+use crate::kiro::model::credentials::KiroCredentials;
+
+/// 根据凭证信息生成唯一的 Machine ID
+///
+/// 凭证保存了有效的 machineId 时直接使用；否则使用 refreshToken 生成，
+/// 配置了 machineIdSalt 时一并参与哈希
+pub fn generate_from_credentials(credentials: &KiroCredentials) -> Option<String> {
+    if let Some(machine_id) = credentials.machine_id.as_deref() {
+        if is_valid(machine_id) {
+            return Some(machine_id.to_ascii_lowercase());
+        }
+        tracing::warn!("凭证 #{:?} 的 machineId 格式无效，改用 refreshToken 派生", credentials.id);
+    }
+
+    // 使用 refreshToken 生成
+    if let Some(ref refresh_token) = credentials.refresh_token {
+        if !refresh_token.is_empty() {
+            let input = match credentials.machine_id_salt.as_deref().filter(|s| !s.is_empty()) {
+                Some(salt) => format!("KotlinNativeAPI/{}/{}", refresh_token, salt),
+                None => format!("KotlinNativeAPI/{}", refresh_token),
+            };
+            return Some(sha256_hex(&input));
+        }
+    }
+
+    // 没有有效的凭证
+    None
+}
+
+/// 生成随机的 Machine ID（64 位十六进制）
+pub fn generate_random() -> String {
+    sha256_hex(&uuid::Uuid::new_v4().to_string())
+}
+
+/// 检查 Machine ID 格式（64 位十六进制）
+pub fn is_valid(machine_id: &str) -> bool {
+    machine_id.len() == 64 && machine_id.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// SHA256 哈希实现（返回十六进制字符串）
+fn sha256_hex(input: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(input.as_bytes());
+    let result = hasher.finalize();
+    hex::encode(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha256_hex() {
+        let result = sha256_hex("test");
+        assert_eq!(result.len(), 64);
+        assert_eq!(
+            result,
+            "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"
+        );
+    }
+
+    #[test]
+    fn test_generate_with_refresh_token() {
+        let mut credentials = KiroCredentials::default();
+        credentials.refresh_token = Some("test_refresh_token".to_string());
+
+        let result = generate_from_credentials(&credentials);
+        assert!(result.is_some());
+        assert_eq!(result.as_ref().unwrap().len(), 64);
+    }
+
+    #[test]
+    fn test_salt_changes_machine_id() {
+        let mut credentials = KiroCredentials {
+            refresh_token: Some("test_refresh_token".to_string()),
+            ..Default::default()
+        };
+        let unsalted = generate_from_credentials(&credentials).unwrap();
+
+        credentials.machine_id_salt = Some(String::new());
+        assert_eq!(generate_from_credentials(&credentials).unwrap(), unsalted);
+
+        credentials.machine_id_salt = Some("account-b".to_string());
+        let salted = generate_from_credentials(&credentials).unwrap();
+        assert_ne!(salted, unsalted);
+        assert_eq!(salted.len(), 64);
+    }
+
+    #[test]
+    fn test_persisted_machine_id_takes_precedence() {
+        let mut credentials = KiroCredentials {
+            refresh_token: Some("test_refresh_token".to_string()),
+            ..Default::default()
+        };
+        let derived = generate_from_credentials(&credentials).unwrap();
+
+        let random = generate_random();
+        assert!(is_valid(&random));
+        assert_ne!(random, generate_random());
+
+        credentials.machine_id = Some(random.to_uppercase());
+        assert_eq!(generate_from_credentials(&credentials).unwrap(), random);
+
+        // 格式无效时回退到 refreshToken 派生
+        credentials.machine_id = Some("not-a-machine-id".to_string());
+        assert_eq!(generate_from_credentials(&credentials).unwrap(), derived);
+    }
+
+    #[test]
+    fn test_generate_without_credentials() {
+        let credentials = KiroCredentials::default();
+
+        let result = generate_from_credentials(&credentials);
+        assert!(result.is_none());
+    }
+}
@@ -1,7 +1,12 @@
 //! Kiro API 客户端模块
 
+pub mod dedupe;
+pub mod failover_trace;
+pub mod failure_tracker;
+pub mod idle_stream;
 pub mod machine_id;
 pub mod model;
 pub mod parser;
 pub mod provider;
+pub mod request_queue;
 pub mod token_manager;
@@ -7,6 +7,17 @@ use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
 
+use crate::http_client::ProxyConfig;
+use crate::model::config::Config;
+
+/// 请求头中的客户端指纹
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fingerprint {
+    pub kiro_version: String,
+    pub system_version: String,
+    pub node_version: String,
+}
+
 /// Kiro OAuth 凭证
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
@@ -43,6 +54,38 @@ pub struct KiroCredentials {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub client_secret: Option<String>,
 
+    /// 该凭证单独使用的代理地址（未设置时使用全局代理）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy_url: Option<String>,
+
+    /// 代理认证用户名
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy_username: Option<String>,
+
+    /// 代理认证密码
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy_password: Option<String>,
+
+    /// 该凭证单独使用的 Kiro 版本（未设置时使用全局 kiroVersion）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kiro_version: Option<String>,
+
+    /// 该凭证单独使用的系统版本（未设置时使用全局 systemVersion）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_version: Option<String>,
+
+    /// 该凭证单独使用的 Node.js 版本（未设置时使用全局 nodeVersion）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_version: Option<String>,
+
+    /// 该凭证固定使用的 machineId（64 位十六进制，添加凭证时随机生成；未设置时由 refreshToken 派生）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub machine_id: Option<String>,
+
+    /// machineId 盐值（未保存 machineId 时参与派生，生成不同的 machineId，不影响 refreshToken）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub machine_id_salt: Option<String>,
+
     /// 用户邮箱（从 API 获取后缓存）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub email: Option<String>,
@@ -210,6 +253,32 @@ impl KiroCredentials {
     pub fn to_pretty_json(&self) -> Result<String, serde_json::Error> {
         serde_json::to_string_pretty(self)
     }
+
+    /// 请求头中的客户端指纹：凭证单独配置的版本优先，未配置时使用全局配置
+    pub fn fingerprint(&self, config: &Config) -> Fingerprint {
+        let pick = |value: &Option<String>, default: &str| {
+            value
+                .as_deref()
+                .filter(|v| !v.trim().is_empty())
+                .unwrap_or(default)
+                .to_string()
+        };
+        Fingerprint {
+            kiro_version: pick(&self.kiro_version, &config.kiro_version),
+            system_version: pick(&self.system_version, &config.system_version),
+            node_version: pick(&self.node_version, &config.node_version),
+        }
+    }
+
+    /// 凭证单独配置的代理（未配置时返回 None）
+    pub fn proxy(&self) -> Option<ProxyConfig> {
+        let url = self.proxy_url.as_deref().filter(|url| !url.is_empty())?;
+        let proxy = ProxyConfig::new(url);
+        Some(match (&self.proxy_username, &self.proxy_password) {
+            (Some(username), Some(password)) => proxy.with_auth(username, password),
+            _ => proxy,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -256,6 +325,14 @@ mod tests {
             auth_method: Some("social".to_string()),
             client_id: None,
             client_secret: None,
+            proxy_url: None,
+            proxy_username: None,
+            proxy_password: None,
+            kiro_version: None,
+            system_version: None,
+            node_version: None,
+            machine_id: None,
+            machine_id_salt: None,
             email: None,
             subscription_title: None,
             current_usage: None,
@@ -272,6 +349,25 @@ mod tests {
         assert!(!json.contains("refreshToken"));
     }
 
+    #[test]
+    fn test_fingerprint_overrides_global_config() {
+        let config = Config {
+            kiro_version: "0.8.0".to_string(),
+            system_version: "darwin#24.6.0".to_string(),
+            ..Default::default()
+        };
+
+        let creds = KiroCredentials {
+            kiro_version: Some("0.9.2".to_string()),
+            node_version: Some(" ".to_string()),
+            ..Default::default()
+        };
+        let fingerprint = creds.fingerprint(&config);
+        assert_eq!(fingerprint.kiro_version, "0.9.2");
+        assert_eq!(fingerprint.system_version, "darwin#24.6.0");
+        assert_eq!(fingerprint.node_version, config.node_version);
+    }
+
     #[test]
     fn test_default_credentials_path() {
         assert_eq!(
@@ -21,7 +21,7 @@ use super::base::EventPayload;
 /// # 示例
 ///
 /// ```rust
-/// use kiro_rs::kiro::model::events::AssistantResponseEvent;
+/// use kiro_gateway_core::kiro::model::events::AssistantResponseEvent;
 ///
 /// let json = r#"{"content":"Hello, world!"}"#;
 /// let event: AssistantResponseEvent = serde_json::from_str(json).unwrap();
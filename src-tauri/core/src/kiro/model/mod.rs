@@ -7,10 +7,12 @@
 //! - `credentials`: OAuth 凭证
 //! - `token_refresh`: Token 刷新
 //! - `usage_limits`: 使用额度查询
+//! - `upstream_error`: 上游错误响应
 
 pub mod common;
 pub mod credentials;
 pub mod events;
 pub mod requests;
 pub mod token_refresh;
+pub mod upstream_error;
 pub mod usage_limits;
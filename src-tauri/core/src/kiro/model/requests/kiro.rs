@@ -12,8 +12,8 @@ use super::conversation::ConversationState;
 ///
 /// # 示例
 ///
-/// ```rust
-/// use kiro_rs::kiro::model::requests::{
+/// ```rust,ignore
+/// use kiro_gateway_core::kiro::model::requests::{
 ///     KiroRequest, ConversationState, CurrentMessage, UserInputMessage, Tool
 /// };
 ///
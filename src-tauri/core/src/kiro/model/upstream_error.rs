@@ -0,0 +1,263 @@
+//! 上游错误响应数据模型
+//!
+//! Kiro / AWS 的错误响应体是 JSON，常见形式：
+//!
+//! ```json
+//! {"__type": "com.amazon.aws.codewhisperer#AccessDeniedException", "message": "...", "reason": "TEMPORARILY_SUSPENDED"}
+//! {"error": "invalid_grant", "error_description": "Invalid refresh token provided"}
+//! ```
+//!
+//! 解析为 [`UpstreamError`] 后按 `__type` / `reason` / `error` 判断错误性质，
+//! 而不是在拼接后的错误字符串里查找关键词。
+
+use std::fmt;
+
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+/// 产生错误的上游端点
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpstreamEndpoint {
+    /// Token 刷新（Social / IdC）
+    TokenRefresh,
+    /// 使用额度查询
+    UsageLimits,
+    /// 对话 / MCP API
+    Api,
+}
+
+/// 错误类型（`__type` 字段，去掉命名空间前缀）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpstreamErrorType {
+    AccessDenied,
+    Throttling,
+    Validation,
+    ServiceQuotaExceeded,
+    InternalServer,
+    ExpiredToken,
+    UnrecognizedClient,
+    Other(String),
+}
+
+impl UpstreamErrorType {
+    fn parse(raw: &str) -> Self {
+        // "com.amazon.aws.codewhisperer#AccessDeniedException:http://..." -> "AccessDeniedException"
+        let name = raw.rsplit('#').next().unwrap_or(raw);
+        let name = name.split(':').next().unwrap_or(name);
+        match name.trim_end_matches("Exception") {
+            "AccessDenied" => Self::AccessDenied,
+            "Throttling" => Self::Throttling,
+            "Validation" => Self::Validation,
+            "ServiceQuotaExceeded" => Self::ServiceQuotaExceeded,
+            "InternalServer" => Self::InternalServer,
+            "ExpiredToken" => Self::ExpiredToken,
+            "UnrecognizedClient" => Self::UnrecognizedClient,
+            _ => Self::Other(raw.to_string()),
+        }
+    }
+}
+
+/// 错误原因（`reason` 字段）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpstreamErrorReason {
+    /// 账户被暂停
+    TemporarilySuspended,
+    /// 月度请求额度用尽
+    MonthlyRequestCount,
+    Other(String),
+}
+
+impl UpstreamErrorReason {
+    fn parse(raw: &str) -> Self {
+        match raw {
+            "TEMPORARILY_SUSPENDED" => Self::TemporarilySuspended,
+            "MONTHLY_REQUEST_COUNT" => Self::MonthlyRequestCount,
+            _ => Self::Other(raw.to_string()),
+        }
+    }
+}
+
+/// OAuth / OIDC 错误码（`error` 字段）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OAuthErrorCode {
+    InvalidGrant,
+    InvalidClient,
+    UnauthorizedClient,
+    Other(String),
+}
+
+impl OAuthErrorCode {
+    fn parse(raw: &str) -> Self {
+        match raw {
+            "invalid_grant" | "InvalidGrantException" => Self::InvalidGrant,
+            "invalid_client" | "InvalidClientException" => Self::InvalidClient,
+            "unauthorized_client" | "UnauthorizedClientException" => Self::UnauthorizedClient,
+            _ => Self::Other(raw.to_string()),
+        }
+    }
+}
+
+/// 错误响应体原始字段
+#[derive(Debug, Default, Deserialize)]
+struct RawErrorBody {
+    #[serde(rename = "__type", default)]
+    error_type: Option<String>,
+    #[serde(alias = "Message", default)]
+    message: Option<String>,
+    #[serde(default)]
+    reason: Option<String>,
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    error_description: Option<String>,
+}
+
+/// 解析后的上游错误
+///
+/// 实现 `std::error::Error`，可直接放入 `anyhow::Error`，
+/// 调用方通过 [`UpstreamError::find`] 从错误链中取回。
+/// `Display` 保持 `{提示}: {状态码} {响应体}` 格式，与原有错误消息一致。
+#[derive(Debug, Clone)]
+pub struct UpstreamError {
+    pub endpoint: UpstreamEndpoint,
+    pub status: StatusCode,
+    pub error_type: Option<UpstreamErrorType>,
+    pub reason: Option<UpstreamErrorReason>,
+    pub oauth_error: Option<OAuthErrorCode>,
+    pub message: Option<String>,
+    /// 中文提示（用于日志和 Admin 错误消息）
+    pub hint: String,
+    /// 原始响应体
+    pub body: String,
+}
+
+impl UpstreamError {
+    /// 解析错误响应体（非 JSON 响应体只保留状态码和原文）
+    pub fn parse(
+        endpoint: UpstreamEndpoint,
+        status: StatusCode,
+        body: &str,
+        hint: impl Into<String>,
+    ) -> Self {
+        let raw: RawErrorBody = serde_json::from_str(body).unwrap_or_default();
+        Self {
+            endpoint,
+            status,
+            error_type: raw.error_type.as_deref().map(UpstreamErrorType::parse),
+            reason: raw.reason.as_deref().map(UpstreamErrorReason::parse),
+            oauth_error: raw.error.as_deref().map(OAuthErrorCode::parse),
+            message: raw.message.or(raw.error_description),
+            hint: hint.into(),
+            body: body.to_string(),
+        }
+    }
+
+    /// 从 anyhow 错误链中查找上游错误
+    pub fn find(error: &anyhow::Error) -> Option<&UpstreamError> {
+        error.chain().find_map(|e| e.downcast_ref::<UpstreamError>())
+    }
+
+    /// 账户被暂停
+    pub fn is_suspended(&self) -> bool {
+        self.reason == Some(UpstreamErrorReason::TemporarilySuspended)
+    }
+
+    /// 月度额度用尽
+    pub fn is_quota_exhausted(&self) -> bool {
+        self.status == StatusCode::PAYMENT_REQUIRED
+            || self.reason == Some(UpstreamErrorReason::MonthlyRequestCount)
+    }
+
+    /// 凭证本身无效（需要禁用凭证）
+    ///
+    /// 限流、服务器错误等临时性错误不算；对话 API 的 401 通常只是 access token 过期，刷新即可，也不算。
+    pub fn is_credential_invalid(&self) -> bool {
+        if self.is_suspended() {
+            return true;
+        }
+        // refresh token 被吊销或客户端注册失效
+        if matches!(
+            self.oauth_error,
+            Some(OAuthErrorCode::InvalidGrant
+                | OAuthErrorCode::InvalidClient
+                | OAuthErrorCode::UnauthorizedClient)
+        ) {
+            return true;
+        }
+        match self.endpoint {
+            UpstreamEndpoint::TokenRefresh | UpstreamEndpoint::UsageLimits => {
+                self.status == StatusCode::UNAUTHORIZED
+                    || matches!(self.error_type, Some(UpstreamErrorType::UnrecognizedClient))
+            }
+            UpstreamEndpoint::Api => false,
+        }
+    }
+}
+
+impl fmt::Display for UpstreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} {}", self.hint, self.status, self.body)
+    }
+}
+
+impl std::error::Error for UpstreamError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_aws_error_body() {
+        let err = UpstreamError::parse(
+            UpstreamEndpoint::Api,
+            StatusCode::FORBIDDEN,
+            r#"{"__type":"com.amazon.aws.codewhisperer#AccessDeniedException","message":"Your account is temporarily suspended","reason":"TEMPORARILY_SUSPENDED"}"#,
+            "API 请求失败",
+        );
+        assert_eq!(err.error_type, Some(UpstreamErrorType::AccessDenied));
+        assert!(err.is_suspended());
+        assert!(err.is_credential_invalid());
+        assert!(err.to_string().starts_with("API 请求失败: 403 Forbidden {"));
+    }
+
+    #[test]
+    fn test_credential_invalid_depends_on_endpoint() {
+        let api = UpstreamError::parse(UpstreamEndpoint::Api, StatusCode::UNAUTHORIZED, "", "");
+        assert!(!api.is_credential_invalid());
+
+        let refresh =
+            UpstreamError::parse(UpstreamEndpoint::TokenRefresh, StatusCode::UNAUTHORIZED, "Bad credentials", "");
+        assert!(refresh.error_type.is_none());
+        assert!(refresh.is_credential_invalid());
+
+        let throttled = UpstreamError::parse(
+            UpstreamEndpoint::TokenRefresh,
+            StatusCode::TOO_MANY_REQUESTS,
+            r#"{"__type":"ThrottlingException","message":"Rate exceeded"}"#,
+            "",
+        );
+        assert_eq!(throttled.error_type, Some(UpstreamErrorType::Throttling));
+        assert!(!throttled.is_credential_invalid());
+
+        let revoked = UpstreamError::parse(
+            UpstreamEndpoint::TokenRefresh,
+            StatusCode::BAD_REQUEST,
+            r#"{"error":"invalid_grant","error_description":"Invalid refresh token provided"}"#,
+            "",
+        );
+        assert_eq!(revoked.message.as_deref(), Some("Invalid refresh token provided"));
+        assert!(revoked.is_credential_invalid());
+    }
+
+    #[test]
+    fn test_find_in_anyhow_chain() {
+        let err = UpstreamError::parse(
+            UpstreamEndpoint::Api,
+            StatusCode::PAYMENT_REQUIRED,
+            r#"{"reason":"MONTHLY_REQUEST_COUNT"}"#,
+            "",
+        );
+        let wrapped = anyhow::Error::new(err).context("刷新失败");
+        assert!(UpstreamError::find(&wrapped).unwrap().is_quota_exhausted());
+    }
+}
@@ -29,9 +29,18 @@
 //!                  │   Stopped  │ (终止态)
 //!                  └────────────┘
 //! ```
+//!
+//! ## 内存管理
+//!
+//! 输入数据只在 `feed()` 时拷贝一次到内部缓冲区；完整帧通过 `split_to` 从缓冲区前端切出并冻结，
+//! payload 是该帧内存的切片，不再逐帧拷贝。已切出的帧释放后，缓冲区在扩容时复用原有内存，
+//! 长时间的流因此在一块有界的内存里循环使用。
+//!
+//! 缓冲区中待解析的数据不超过 `max_buffer_size`，超出时 `feed()` 返回 `BufferOverflow`；
+//! 缓冲区排空后容量超过初始容量的 [`SHRINK_FACTOR`] 倍时会被收缩，避免一次大帧之后长期占用内存。
 
 use super::error::{ParseError, ParseResult};
-use super::frame::{Frame, PRELUDE_SIZE, parse_frame};
+use super::frame::{Frame, PRELUDE_SIZE, parse_frame_parts};
 use bytes::{Buf, BytesMut};
 
 /// 默认最大缓冲区大小 (16 MB)
@@ -43,6 +52,9 @@ pub const DEFAULT_MAX_ERRORS: usize = 5;
 /// 默认初始缓冲区容量
 pub const DEFAULT_BUFFER_CAPACITY: usize = 8192;
 
+/// 缓冲区排空后，容量超过初始容量的该倍数时收缩回初始容量
+pub const SHRINK_FACTOR: usize = 64;
+
 /// 解码器状态
 ///
 /// 采用四态模型，参考 kiro-kt 的设计：
@@ -69,7 +81,7 @@ pub enum DecoderState {
 /// # Example
 ///
 /// ```rust,ignore
-/// use kiro_rs::kiro::parser::EventStreamDecoder;
+/// use kiro_gateway_core::kiro::parser::EventStreamDecoder;
 ///
 /// let mut decoder = EventStreamDecoder::new();
 ///
@@ -87,6 +99,8 @@ pub enum DecoderState {
 pub struct EventStreamDecoder {
     /// 内部缓冲区
     buffer: BytesMut,
+    /// 初始缓冲区容量（收缩时恢复到该容量）
+    initial_capacity: usize,
     /// 当前状态
     state: DecoderState,
     /// 已处理的帧数量
@@ -117,6 +131,7 @@ impl EventStreamDecoder {
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
             buffer: BytesMut::with_capacity(capacity),
+            initial_capacity: capacity,
             state: DecoderState::Ready,
             frames_decoded: 0,
             error_count: 0,
@@ -130,6 +145,7 @@ impl EventStreamDecoder {
     pub fn with_config(capacity: usize, max_errors: usize, max_buffer_size: usize) -> Self {
         Self {
             buffer: BytesMut::with_capacity(capacity),
+            initial_capacity: capacity,
             state: DecoderState::Ready,
             frames_decoded: 0,
             error_count: 0,
@@ -188,10 +204,15 @@ impl EventStreamDecoder {
         // 转移到 Parsing 状态
         self.state = DecoderState::Parsing;
 
-        match parse_frame(&self.buffer) {
-            Ok(Some((frame, consumed))) => {
-                // 成功解析
-                self.buffer.advance(consumed);
+        match parse_frame_parts(&self.buffer) {
+            Ok(Some((headers, payload, consumed))) => {
+                // 成功解析：切出整帧并冻结，payload 共享帧内存
+                let frame_bytes = self.buffer.split_to(consumed).freeze();
+                let frame = Frame {
+                    headers,
+                    payload: frame_bytes.slice(payload),
+                };
+                self.shrink_if_drained();
                 self.state = DecoderState::Ready;
                 self.frames_decoded += 1;
                 self.error_count = 0; // 重置连续错误计数
@@ -228,6 +249,17 @@ impl EventStreamDecoder {
         }
     }
 
+    /// 缓冲区排空且容量过大时收缩
+    ///
+    /// 排空后的 `BytesMut` 仍可能持有扩容后的大块内存（或与尚未释放的帧共享内存），
+    /// 换成新的初始容量缓冲区后，旧内存随最后一个帧释放。
+    fn shrink_if_drained(&mut self) {
+        let threshold = self.initial_capacity.max(1).saturating_mul(SHRINK_FACTOR);
+        if self.buffer.is_empty() && self.buffer.capacity() > threshold {
+            self.buffer = BytesMut::with_capacity(self.initial_capacity);
+        }
+    }
+
     /// 创建解码迭代器
     pub fn decode_iter(&mut self) -> DecodeIter<'_> {
         DecodeIter { decoder: self }
@@ -356,6 +388,11 @@ impl EventStreamDecoder {
         self.buffer.len()
     }
 
+    /// 获取缓冲区当前容量
+    pub fn buffer_capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
     /// 尝试从 Stopped 状态恢复
     ///
     /// 重置错误计数并转移到 Ready 状态
@@ -450,6 +487,56 @@ mod tests {
         assert!(!decoder.is_recovering());
     }
 
+    /// 构造一个不带头部的完整帧
+    fn encode_frame(payload: &[u8]) -> Vec<u8> {
+        use super::super::crc::crc32;
+
+        let total_length = (PRELUDE_SIZE + payload.len() + 4) as u32;
+        let mut frame = Vec::with_capacity(total_length as usize);
+        frame.extend_from_slice(&total_length.to_be_bytes());
+        frame.extend_from_slice(&0u32.to_be_bytes());
+        let prelude_crc = crc32(&frame);
+        frame.extend_from_slice(&prelude_crc.to_be_bytes());
+        frame.extend_from_slice(payload);
+        let message_crc = crc32(&frame);
+        frame.extend_from_slice(&message_crc.to_be_bytes());
+        frame
+    }
+
+    #[test]
+    fn test_decoder_split_chunks_zero_copy() {
+        let mut data = encode_frame(b"{\"a\":1}");
+        data.extend(encode_frame(b"{\"b\":2}"));
+
+        let mut decoder = EventStreamDecoder::new();
+        let mut frames = Vec::new();
+        // 逐 3 字节喂入，帧跨越多个分块
+        for chunk in data.chunks(3) {
+            decoder.feed(chunk).unwrap();
+            frames.extend(decoder.decode_iter().map(Result::unwrap));
+        }
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(&frames[0].payload[..], b"{\"a\":1}");
+        assert_eq!(&frames[1].payload[..], b"{\"b\":2}");
+        assert_eq!(decoder.buffer_len(), 0);
+    }
+
+    #[test]
+    fn test_decoder_shrinks_after_large_frame() {
+        let mut decoder = EventStreamDecoder::with_config(64, 5, 1024 * 1024);
+        let large = encode_frame(&vec![b'x'; 64 * 1024]);
+        decoder.feed(&large).unwrap();
+
+        let frame = decoder.decode().unwrap().unwrap();
+        assert_eq!(frame.payload.len(), 64 * 1024);
+        assert!(decoder.buffer_capacity() <= 64 * SHRINK_FACTOR);
+
+        // 收缩后的缓冲区仍可继续解码
+        decoder.feed(&encode_frame(b"ok")).unwrap();
+        assert_eq!(&decoder.decode().unwrap().unwrap().payload[..], b"ok");
+    }
+
     #[test]
     fn test_decoder_try_resume() {
         let mut decoder = EventStreamDecoder::new();
@@ -16,6 +16,10 @@
 //! - Payload: 载荷数据（通常是 JSON）
 //! - Message CRC: 整个消息（不含 Message CRC 自身）的 CRC32 校验
 
+use std::ops::Range;
+
+use bytes::Bytes;
+
 use super::crc::crc32;
 use super::error::{ParseError, ParseResult};
 use super::header::{Headers, parse_headers};
@@ -35,7 +39,9 @@ pub struct Frame {
     /// 消息头部
     pub headers: Headers,
     /// 消息负载
-    pub payload: Vec<u8>,
+    ///
+    /// 由 `EventStreamDecoder` 解码时与解码缓冲区共享内存，不发生拷贝
+    pub payload: Bytes,
 }
 
 impl Frame {
@@ -62,8 +68,8 @@ impl Frame {
 
 /// 尝试从缓冲区解析一个完整的帧
 ///
-/// 这是一个无状态的纯函数，每次调用独立解析。
-/// 缓冲区管理由上层 `EventStreamDecoder` 负责。
+/// 这是一个无状态的纯函数，每次调用独立解析，payload 会拷贝一份。
+/// 缓冲区管理由上层 `EventStreamDecoder` 负责（解码器使用 [`parse_frame_parts`] 零拷贝切分）。
 ///
 /// # Arguments
 /// * `buffer` - 输入缓冲区
@@ -73,6 +79,17 @@ impl Frame {
 /// - `Ok(None)` - 数据不足，需要更多数据
 /// - `Err(e)` - 解析错误
 pub fn parse_frame(buffer: &[u8]) -> ParseResult<Option<(Frame, usize)>> {
+    Ok(parse_frame_parts(buffer)?.map(|(headers, payload, total_length)| {
+        let payload = Bytes::copy_from_slice(&buffer[payload]);
+        (Frame { headers, payload }, total_length)
+    }))
+}
+
+/// 校验并解析一个完整帧的头部，返回头部、payload 在缓冲区中的范围和帧总长度
+///
+/// 不拷贝 payload，调用方可据此从共享缓冲区中切出 payload。
+/// 返回值语义与 [`parse_frame`] 相同。
+pub fn parse_frame_parts(buffer: &[u8]) -> ParseResult<Option<(Headers, Range<usize>, usize)>> {
     // 检查是否有足够的数据读取 prelude
     if buffer.len() < PRELUDE_SIZE {
         return Ok(None);
@@ -145,12 +162,11 @@ pub fn parse_frame(buffer: &[u8]) -> ParseResult<Option<(Frame, usize)>> {
 
     let headers = parse_headers(&buffer[headers_start..headers_end], header_length)?;
 
-    // 提取 payload (去除最后4字节的 message_crc)
+    // payload 范围 (去除最后4字节的 message_crc)
     let payload_start = headers_end;
     let payload_end = total_length - 4;
-    let payload = buffer[payload_start..payload_end].to_vec();
 
-    Ok(Some((Frame { headers, payload }, total_length)))
+    Ok(Some((headers, payload_start..payload_end, total_length)))
 }
 
 #[cfg(test)]
@@ -4,16 +4,21 @@
 //! 支持流式和非流式请求
 //! 支持多凭证故障转移和重试
 
-use reqwest::Client;
 use reqwest::header::{AUTHORIZATION, CONNECTION, CONTENT_TYPE, HOST, HeaderMap, HeaderValue};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::sleep;
 use uuid::Uuid;
 
-use crate::http_client::{ProxyConfig, build_client};
+use crate::credential_stats::CREDENTIAL_STATS;
+use crate::group_budgets::GroupBudgetExceeded;
+use crate::http_client::{HttpClients, ProxyConfig};
+use crate::kiro::failover_trace::FailoverTrace;
+use crate::kiro::failure_tracker::FailureClass;
 use crate::kiro::machine_id;
-use crate::kiro::token_manager::{CallContext, MultiTokenManager};
+use crate::kiro::request_queue::{CredentialSlot, RequestQueue};
+use crate::kiro::model::upstream_error::{UpstreamEndpoint, UpstreamError};
+use crate::kiro::token_manager::{CallContext, CredentialUnavailable, MultiTokenManager};
 
 /// 每个凭证的最大重试次数
 const MAX_RETRIES_PER_CREDENTIAL: usize = 3;
@@ -21,29 +26,104 @@ const MAX_RETRIES_PER_CREDENTIAL: usize = 3;
 /// 总重试次数硬上限（避免无限重试）
 const MAX_TOTAL_RETRIES: usize = 9;
 
+/// 上游限流错误
+///
+/// 重试耗尽且最后一次失败为 429 时返回，调用方可通过 `downcast_ref` 识别并向客户端返回限流错误
+#[derive(Debug)]
+pub struct UpstreamThrottled {
+    pub message: String,
+}
+
+impl std::fmt::Display for UpstreamThrottled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "上游限流: {}", self.message)
+    }
+}
+
+impl std::error::Error for UpstreamThrottled {}
+
+/// 上游响应及实际使用的凭证
+pub struct ApiResponse {
+    pub response: reqwest::Response,
+    /// 处理请求的凭证 ID
+    pub credential_id: u64,
+    /// 处理请求的凭证所属分组
+    pub group_id: String,
+    /// 凭证并发槽位（未限制并发时为 None），需持有到响应读取完毕
+    pub slot: Option<CredentialSlot>,
+}
+
 /// Kiro API Provider
 ///
 /// 核心组件，负责与 Kiro API 通信
 /// 支持多凭证故障转移和重试机制
 pub struct KiroProvider {
     token_manager: Arc<MultiTokenManager>,
-    client: Client,
+    clients: HttpClients,
+    queue: RequestQueue,
+    /// 固定使用的凭证分组（分组反代实例），None 表示跟随活跃分组
+    group: Option<String>,
 }
 
 impl KiroProvider {
     /// 创建新的 KiroProvider 实例
+    ///
+    /// 复用 token_manager 持有的 HTTP Client（共享连接池）
     pub fn new(token_manager: Arc<MultiTokenManager>) -> Self {
-        Self::with_proxy(token_manager, None)
+        let clients = token_manager.http_clients().clone();
+        let queue = RequestQueue::from_config(token_manager.config());
+        Self {
+            token_manager,
+            clients,
+            queue,
+            group: None,
+        }
     }
 
     /// 创建带代理配置的 KiroProvider 实例
     pub fn with_proxy(token_manager: Arc<MultiTokenManager>, proxy: Option<ProxyConfig>) -> Self {
-        let client = build_client(proxy.as_ref(), 720) // 12 分钟超时
+        if proxy.is_none() {
+            return Self::new(token_manager);
+        }
+
+        let clients = HttpClients::with_timeouts(proxy.as_ref(), &token_manager.config().upstream_timeouts)
             .expect("创建 HTTP 客户端失败");
+        let queue = RequestQueue::from_config(token_manager.config());
 
         Self {
             token_manager,
-            client,
+            clients,
+            queue,
+            group: None,
+        }
+    }
+
+    /// 使用指定的请求队列（反代重启时按最新配置重建并发限制）
+    pub fn with_request_queue(mut self, queue: RequestQueue) -> Self {
+        self.queue = queue;
+        self
+    }
+
+    /// 只使用指定分组的凭证（不受活跃分组切换影响）
+    pub fn with_group(mut self, group: Option<String>) -> Self {
+        self.group = group;
+        self
+    }
+
+    /// 获取调用上下文对应的 machine_id（优先使用凭证条目中的缓存）
+    fn machine_id_for(ctx: &CallContext) -> anyhow::Result<String> {
+        ctx.machine_id
+            .clone()
+            .or_else(|| machine_id::generate_from_credentials(&ctx.credentials))
+            .ok_or_else(|| anyhow::anyhow!("无法生成 machine_id，请检查凭证配置"))
+    }
+
+    /// 调用使用的 HTTP Client（凭证单独配置了代理时优先使用凭证代理）
+    fn clients_for(&self, ctx: &CallContext) -> HttpClients {
+        if ctx.credentials.proxy().is_some() {
+            self.token_manager.clients_for(&ctx.credentials)
+        } else {
+            self.clients.clone()
         }
     }
 
@@ -80,12 +160,12 @@ impl KiroProvider {
     fn build_headers(&self, ctx: &CallContext) -> anyhow::Result<HeaderMap> {
         let config = self.token_manager.config();
 
-        let machine_id = machine_id::generate_from_credentials(&ctx.credentials)
-            .ok_or_else(|| anyhow::anyhow!("无法生成 machine_id，请检查凭证配置"))?;
+        let machine_id = Self::machine_id_for(ctx)?;
 
-        let kiro_version = &config.kiro_version;
-        let os_name = &config.system_version;
-        let node_version = &config.node_version;
+        let fingerprint = ctx.credentials.fingerprint(config);
+        let kiro_version = &fingerprint.kiro_version;
+        let os_name = &fingerprint.system_version;
+        let node_version = &fingerprint.node_version;
 
         let x_amz_user_agent = format!("aws-sdk-js/1.0.27 KiroIDE-{}-{}", kiro_version, machine_id);
 
@@ -136,11 +216,17 @@ impl KiroProvider {
     ///
     /// # Arguments
     /// * `request_body` - JSON 格式的请求体字符串
+    /// * `session_id` - 会话 ID（可选，启用会话粘性时用于固定凭证）
     ///
     /// # Returns
-    /// 返回原始的 HTTP Response，不做解析
-    pub async fn call_api(&self, request_body: &str) -> anyhow::Result<reqwest::Response> {
-        self.call_api_with_retry(request_body, false).await
+    /// 返回原始的 HTTP Response（不做解析）及所用凭证
+    pub async fn call_api(
+        &self,
+        request_body: &str,
+        session_id: Option<&str>,
+    ) -> anyhow::Result<ApiResponse> {
+        self.call_api_with_retry(request_body, false, session_id, &mut FailoverTrace::new())
+            .await
     }
 
     /// 发送流式 API 请求
@@ -151,23 +237,43 @@ impl KiroProvider {
     ///
     /// # Arguments
     /// * `request_body` - JSON 格式的请求体字符串
+    /// * `session_id` - 会话 ID（可选，启用会话粘性时用于固定凭证）
     ///
     /// # Returns
-    /// 返回原始的 HTTP Response，调用方负责处理流式数据
-    pub async fn call_api_stream(&self, request_body: &str) -> anyhow::Result<reqwest::Response> {
-        self.call_api_with_retry(request_body, true).await
+    /// 返回原始的 HTTP Response 及所用凭证，调用方负责处理流式数据
+    pub async fn call_api_stream(
+        &self,
+        request_body: &str,
+        session_id: Option<&str>,
+    ) -> anyhow::Result<ApiResponse> {
+        self.call_api_with_retry(request_body, true, session_id, &mut FailoverTrace::new())
+            .await
+    }
+
+    /// 发送 API 请求并记录每次尝试（凭证、失败原因、上游耗时）
+    ///
+    /// 故障转移策略与 [`call_api`](Self::call_api) 相同，请求失败时 `trace` 中同样保留已发生的尝试
+    pub async fn call_api_traced(
+        &self,
+        request_body: &str,
+        is_stream: bool,
+        session_id: Option<&str>,
+        trace: &mut FailoverTrace,
+    ) -> anyhow::Result<ApiResponse> {
+        self.call_api_with_retry(request_body, is_stream, session_id, trace)
+            .await
     }
 
     /// 构建 MCP 请求头
     fn build_mcp_headers(&self, ctx: &CallContext) -> anyhow::Result<HeaderMap> {
         let config = self.token_manager.config();
 
-        let machine_id = machine_id::generate_from_credentials(&ctx.credentials)
-            .ok_or_else(|| anyhow::anyhow!("无法生成 machine_id，请检查凭证配置"))?;
+        let machine_id = Self::machine_id_for(ctx)?;
 
-        let kiro_version = &config.kiro_version;
-        let os_name = &config.system_version;
-        let node_version = &config.node_version;
+        let fingerprint = ctx.credentials.fingerprint(config);
+        let kiro_version = &fingerprint.kiro_version;
+        let os_name = &fingerprint.system_version;
+        let node_version = &fingerprint.node_version;
 
         let x_amz_user_agent = format!("aws-sdk-js/1.0.27 KiroIDE-{}-{}", kiro_version, machine_id);
 
@@ -232,8 +338,12 @@ impl KiroProvider {
 
         for _attempt in 0..max_retries {
             // 获取调用上下文
-            let ctx = match self.token_manager.acquire_context().await {
+            let ctx = match self.token_manager.acquire_context_in(self.group.as_deref()).await {
                 Ok(c) => c,
+                // 凭证获取已超时或分组预算用尽，继续重试只会拉长客户端等待
+                Err(e) if e.is::<CredentialUnavailable>() || e.is::<GroupBudgetExceeded>() => {
+                    return Err(e);
+                }
                 Err(e) => {
                     last_error = Some(e);
                     continue;
@@ -251,7 +361,8 @@ impl KiroProvider {
 
             // 发送请求
             let response = match self
-                .client
+                .clients_for(&ctx)
+                .mcp
                 .post(&url)
                 .headers(headers)
                 .body(request_body.to_string())
@@ -261,6 +372,7 @@ impl KiroProvider {
                 Ok(resp) => resp,
                 Err(e) => {
                     last_error = Some(e.into());
+                    self.token_manager.report_failure(ctx.id, FailureClass::Network);
                     continue;
                 }
             };
@@ -273,7 +385,8 @@ impl KiroProvider {
 
             // 非成功状态，记录错误
             last_error = Some(anyhow::anyhow!("MCP API 请求失败: {}", status));
-            self.token_manager.report_failure(ctx.id);
+            self.token_manager.report_failure(ctx.id, FailureClass::from_status(status));
+            self.token_manager.prewarm_standby(ctx.id);
         }
 
         Err(last_error.unwrap_or_else(|| anyhow::anyhow!("MCP API 调用失败")))
@@ -289,13 +402,17 @@ impl KiroProvider {
     /// 错误处理策略：
     /// - 400 Bad Request: 直接返回错误，不计入凭证失败
     /// - 401/403: 视为凭证/权限问题，计入失败并允许故障转移
-    /// - 429/408/5xx: 瞬态上游错误，重试但不禁用或切换凭证
+    /// - 429: 凭证进入冷却（Retry-After 或指数退避），退避后换用其他凭证重试
+    /// - 408/5xx: 瞬态上游错误，重试但不禁用或切换凭证
     /// - 网络错误: 重试但不禁用或切换凭证
+    /// - 凭证并发已满: 排队等待空闲槽位，队列满或等待超时返回 [`QueueRejected`](crate::kiro::request_queue::QueueRejected)
     async fn call_api_with_retry(
         &self,
         request_body: &str,
         is_stream: bool,
-    ) -> anyhow::Result<reqwest::Response> {
+        session_id: Option<&str>,
+        trace: &mut FailoverTrace,
+    ) -> anyhow::Result<ApiResponse> {
         let total_credentials = self.token_manager.total_count();
         let max_retries = (total_credentials * MAX_RETRIES_PER_CREDENTIAL).min(MAX_TOTAL_RETRIES);
         let mut last_error: Option<anyhow::Error> = None;
@@ -303,42 +420,92 @@ impl KiroProvider {
 
         for attempt in 0..max_retries {
             // 获取调用上下文（绑定 index、credentials、token）
-            let ctx = match self.token_manager.acquire_context().await {
+            let ctx = match self
+                .token_manager
+                .acquire_context_for_session(session_id, self.group.as_deref())
+                .await
+            {
                 Ok(c) => c,
+                // 凭证获取已超时，继续重试只会拉长客户端等待
+                Err(e) if e.is::<CredentialUnavailable>() => {
+                    trace.record(None, "credential_unavailable", None, Duration::ZERO);
+                    return Err(e);
+                }
+                // 分组预算用尽，本月内重试不会成功
+                Err(e) if e.is::<GroupBudgetExceeded>() => {
+                    trace.record(None, "group_budget_exceeded", None, Duration::ZERO);
+                    return Err(e);
+                }
                 Err(e) => {
+                    trace.record(None, "credential_error", None, Duration::ZERO);
                     last_error = Some(e);
                     continue;
                 }
             };
 
+            // 凭证并发已满时排队等待空闲槽位；队列满或超时直接返回，重试只会加剧拥塞
+            let slot = match self.queue.acquire(ctx.id).await {
+                Ok(slot) => slot,
+                Err(e) => {
+                    trace.record(Some(ctx.id), "queue_rejected", None, Duration::ZERO);
+                    return Err(e.into());
+                }
+            };
+
             let url = self.base_url();
             let headers = match self.build_headers(&ctx) {
                 Ok(h) => h,
                 Err(e) => {
+                    trace.record(Some(ctx.id), "credential_error", None, Duration::ZERO);
                     last_error = Some(e);
                     continue;
                 }
             };
 
-            // 发送请求
-            let response = match self
-                .client
+            // 发送请求（等待响应头受首字节超时限制）
+            let sent_at = std::time::Instant::now();
+            let send = self
+                .clients_for(&ctx)
+                .completion
                 .post(&url)
                 .headers(headers)
                 .body(request_body.to_string())
-                .send()
-                .await
-            {
-                Ok(resp) => resp,
-                Err(e) => {
+                .send();
+            let first_byte = self.token_manager.config().upstream_timeouts.first_byte();
+            let sent = match first_byte {
+                Some(limit) => tokio::time::timeout(limit, send).await.ok(),
+                None => Some(send.await),
+            };
+            let response = match sent {
+                Some(Ok(resp)) => resp,
+                None => {
+                    let secs = first_byte.unwrap_or_default().as_secs();
+                    trace.record(Some(ctx.id), "timeout", None, sent_at.elapsed());
+                    CREDENTIAL_STATS.record_failure(ctx.id);
+                    self.token_manager.report_failure(ctx.id, FailureClass::Network);
+                    self.token_manager.prewarm_standby(ctx.id);
+                    tracing::warn!(
+                        "API 请求超时（尝试 {}/{}）：{} 秒内未收到响应头",
+                        attempt + 1,
+                        max_retries,
+                        secs
+                    );
+                    last_error = Some(anyhow::anyhow!("{} API 请求超时：{} 秒内未收到响应", api_type, secs));
+                    continue;
+                }
+                Some(Err(e)) => {
+                    trace.record(Some(ctx.id), "network_error", None, sent_at.elapsed());
+                    CREDENTIAL_STATS.record_failure(ctx.id);
                     tracing::warn!(
                         "API 请求发送失败（尝试 {}/{})，网络错误: {}",
                         attempt + 1,
                         max_retries,
                         e
                     );
-                    // 网络错误通常是上游/链路瞬态问题，不应导致"禁用凭证"或"切换凭证"
-                    // （否则一段时间网络抖动会把所有凭证都误禁用，需要重启才能恢复）
+                    // 网络错误通常是上游/链路瞬态问题，默认不计入失败（failurePolicy.network），
+                    // 否则一段时间网络抖动会把所有凭证都误禁用
+                    self.token_manager.report_failure(ctx.id, FailureClass::Network);
+                    self.token_manager.prewarm_standby(ctx.id);
                     last_error = Some(e.into());
                     if attempt + 1 < max_retries {
                         sleep(Self::retry_delay(attempt)).await;
@@ -351,12 +518,67 @@ impl KiroProvider {
 
             // 成功响应
             if status.is_success() {
+                trace.record(Some(ctx.id), "ok", Some(status.as_u16()), sent_at.elapsed());
+                CREDENTIAL_STATS.record_success(ctx.id, sent_at.elapsed());
                 self.token_manager.report_success(ctx.id);
-                return Ok(response);
+                return Ok(ApiResponse {
+                    response,
+                    credential_id: ctx.id,
+                    group_id: ctx.credentials.group_id,
+                    slot,
+                });
             }
 
-            // 失败响应：读取 body 用于日志/错误信息
+            // 失败响应：读取 body 用于日志/错误信息（先取出 Retry-After，读取 body 会消耗响应）
+            let retry_after = Self::parse_retry_after(response.headers());
             let body = response.text().await.unwrap_or_default();
+            let upstream = UpstreamError::parse(
+                UpstreamEndpoint::Api,
+                status,
+                &body,
+                format!("{} API 请求失败", api_type),
+            );
+            let reason = match status.as_u16() {
+                _ if upstream.is_quota_exhausted() => "quota_exhausted",
+                400 => "bad_request",
+                401 | 403 => "auth_error",
+                429 => "rate_limited",
+                408 => "timeout",
+                _ if status.is_server_error() => "server_error",
+                _ if status.is_client_error() => "client_error",
+                _ => "unknown",
+            };
+            trace.record(Some(ctx.id), reason, Some(status.as_u16()), sent_at.elapsed());
+            // 请求本身的问题不计入凭证的失败统计
+            if !matches!(reason, "bad_request" | "client_error") {
+                CREDENTIAL_STATS.record_failure(ctx.id);
+            }
+
+            // 402 / MONTHLY_REQUEST_COUNT - 额度用尽：禁用到额度重置并切换凭证
+            if upstream.is_quota_exhausted() {
+                tracing::warn!(
+                    "凭证 #{} 额度已用尽（尝试 {}/{}): {} {}",
+                    ctx.id,
+                    attempt + 1,
+                    max_retries,
+                    status,
+                    body
+                );
+                let has_available = self.token_manager.report_quota_exhausted(ctx.id);
+                if let Some(session_id) = session_id {
+                    self.token_manager.unbind_session(session_id);
+                }
+                if !has_available {
+                    anyhow::bail!(
+                        "{} API 请求失败（所有凭证已用尽）: {} {}",
+                        api_type,
+                        status,
+                        body
+                    );
+                }
+                last_error = Some(anyhow::anyhow!("{} API 请求失败: {} {}", api_type, status, body));
+                continue;
+            }
 
             // 400 Bad Request - 请求问题，重试/切换凭证无意义
             if status.as_u16() == 400 {
@@ -375,7 +597,12 @@ impl KiroProvider {
 
                 // 使用 report_failure_with_error 检测账户暂停/凭证无效
                 // 如果检测到 SUSPENDED 等错误会立即禁用凭证
-                let has_available = self.token_manager.report_failure_with_error(ctx.id, &body);
+                let has_available = self.token_manager.report_failure_with_error(ctx.id, &upstream);
+                self.token_manager.prewarm_standby(ctx.id);
+                // 凭证出错时解除会话绑定，下次重试走正常故障转移
+                if let Some(session_id) = session_id {
+                    self.token_manager.unbind_session(session_id);
+                }
                 if !has_available {
                     anyhow::bail!(
                         "{} API 请求失败（所有凭证已用尽）: {} {}",
@@ -389,9 +616,34 @@ impl KiroProvider {
                 continue;
             }
 
-            // 429/408/5xx - 瞬态上游错误：重试但不禁用或切换凭证
-            // （避免 429 high traffic / 502 high load 等瞫态错误把所有凭证锁死）
-            if matches!(status.as_u16(), 408 | 429) || status.is_server_error() {
+            // 429 - 凭证被限流：不计入失败，让该凭证冷却，后续尝试会优先选择其他凭证
+            if status.as_u16() == 429 {
+                let cooldown = self.token_manager.report_rate_limited(ctx.id, retry_after);
+                tracing::warn!(
+                    "API 请求被限流（尝试 {}/{}），凭证 #{} 冷却 {} 秒: {}",
+                    attempt + 1,
+                    max_retries,
+                    ctx.id,
+                    cooldown.as_secs().max(1),
+                    body
+                );
+                if let Some(session_id) = session_id {
+                    self.token_manager.unbind_session(session_id);
+                }
+                last_error = Some(anyhow::Error::new(UpstreamThrottled {
+                    message: format!("{} API 请求失败: {} {}", api_type, status, body),
+                }));
+                if attempt + 1 < max_retries {
+                    sleep(Self::retry_delay(attempt)).await;
+                }
+                continue;
+            }
+
+            // 408/5xx - 瞬态上游错误：重试，默认不计入失败（failurePolicy.server）
+            // （避免 502 high load 等瞬态错误把所有凭证锁死）
+            if status.as_u16() == 408 || status.is_server_error() {
+                self.token_manager.report_failure(ctx.id, FailureClass::Server);
+                self.token_manager.prewarm_standby(ctx.id);
                 tracing::warn!(
                     "API 请求失败（上游瞬态错误，尝试 {}/{}): {} {}",
                     attempt + 1,
@@ -435,6 +687,18 @@ impl KiroProvider {
         }))
     }
 
+    /// 解析 Retry-After 响应头（仅支持秒数格式）
+    fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+        headers
+            .get(reqwest::header::RETRY_AFTER)?
+            .to_str()
+            .ok()?
+            .trim()
+            .parse::<u64>()
+            .ok()
+            .map(Duration::from_secs)
+    }
+
     /// 指数退避 + 抠动，避免上游抖动时放大故障
     fn retry_delay(attempt: usize) -> Duration {
         const BASE_MS: u64 = 200;
@@ -492,6 +756,7 @@ mod tests {
             id: 1,
             credentials,
             token: "test_token".to_string(),
+            machine_id: None,
         };
         let headers = provider.build_headers(&ctx).unwrap();
 
@@ -508,4 +773,23 @@ mod tests {
         );
         assert_eq!(headers.get(CONNECTION).unwrap(), "close");
     }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(KiroProvider::parse_retry_after(&headers), None);
+
+        headers.insert(reqwest::header::RETRY_AFTER, HeaderValue::from_static("12"));
+        assert_eq!(
+            KiroProvider::parse_retry_after(&headers),
+            Some(Duration::from_secs(12))
+        );
+
+        // HTTP-date 格式不支持，回退到指数退避
+        headers.insert(
+            reqwest::header::RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(KiroProvider::parse_retry_after(&headers), None);
+    }
 }
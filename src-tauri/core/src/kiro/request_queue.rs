@@ -0,0 +1,183 @@
+//! 凭证并发限制与请求排队
+//!
+//! Kiro 账号对并发的 agentic 请求会限流。每个凭证持有一个信号量，
+//! 同时进行的上游请求数超过 `max_concurrent_per_credential` 时，新请求在全局队列中等待空闲槽位，
+//! 而不是一拥而上触发 429 风暴、进而因失败计数被禁用。
+//! 队列长度和等待时间都有上限，超出时返回 [`QueueRejected`]。
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::model::config::Config;
+
+/// 请求排队失败
+///
+/// 调用方可通过 `downcast_ref` 识别，向客户端返回 overloaded 而不是上游错误
+#[derive(Debug)]
+pub enum QueueRejected {
+    /// 排队请求数已达上限
+    Full { capacity: usize },
+    /// 等待空闲槽位超时
+    Timeout { waited: Duration },
+}
+
+impl std::fmt::Display for QueueRejected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueueRejected::Full { capacity } => {
+                write!(f, "请求排队已满（{} 个请求正在等待凭证空闲）", capacity)
+            }
+            QueueRejected::Timeout { waited } => {
+                write!(f, "等待凭证空闲超时（{} 秒）", waited.as_secs())
+            }
+        }
+    }
+}
+
+impl std::error::Error for QueueRejected {}
+
+/// 凭证并发槽位，释放（drop）时归还
+///
+/// 流式请求需要持有到流结束，槽位才能真正限制同时进行的上游请求数
+#[derive(Debug)]
+pub struct CredentialSlot {
+    _permit: OwnedSemaphorePermit,
+}
+
+/// 排队计数守卫，离开队列（获得槽位、超时或取消）时自动减一
+struct Waiting<'a>(&'a AtomicUsize);
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// 按凭证限制并发的请求队列
+pub struct RequestQueue {
+    max_per_credential: usize,
+    capacity: usize,
+    timeout: Option<Duration>,
+    semaphores: Mutex<HashMap<u64, Arc<Semaphore>>>,
+    waiting: AtomicUsize,
+}
+
+impl RequestQueue {
+    pub fn new(max_per_credential: usize, capacity: usize, timeout: Option<Duration>) -> Self {
+        Self {
+            max_per_credential,
+            capacity,
+            timeout,
+            semaphores: Mutex::new(HashMap::new()),
+            waiting: AtomicUsize::new(0),
+        }
+    }
+
+    /// 按配置创建
+    pub fn from_config(config: &Config) -> Self {
+        let timeout = (config.request_queue_timeout_secs > 0)
+            .then(|| Duration::from_secs(config.request_queue_timeout_secs));
+        Self::new(
+            config.max_concurrent_per_credential,
+            config.request_queue_size,
+            timeout,
+        )
+    }
+
+    /// 当前排队等待的请求数
+    pub fn waiting(&self) -> usize {
+        self.waiting.load(Ordering::SeqCst)
+    }
+
+    /// 获取凭证的并发槽位
+    ///
+    /// 未限制并发时返回 None；凭证繁忙时排队等待，队列已满或等待超时返回 [`QueueRejected`]
+    pub async fn acquire(&self, credential_id: u64) -> Result<Option<CredentialSlot>, QueueRejected> {
+        if self.max_per_credential == 0 {
+            return Ok(None);
+        }
+
+        let semaphore = self
+            .semaphores
+            .lock()
+            .entry(credential_id)
+            .or_insert_with(|| Arc::new(Semaphore::new(self.max_per_credential)))
+            .clone();
+
+        if let Ok(permit) = semaphore.clone().try_acquire_owned() {
+            return Ok(Some(CredentialSlot { _permit: permit }));
+        }
+
+        let queued = self.waiting.fetch_add(1, Ordering::SeqCst);
+        let _waiting = Waiting(&self.waiting);
+        if queued >= self.capacity {
+            return Err(QueueRejected::Full {
+                capacity: self.capacity,
+            });
+        }
+
+        tracing::debug!("凭证 #{} 并发已满，排队等待（前方 {} 个请求）", credential_id, queued);
+        let permit = match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, semaphore.acquire_owned())
+                .await
+                .map_err(|_| QueueRejected::Timeout { waited: timeout })?,
+            None => semaphore.acquire_owned().await,
+        };
+
+        // 信号量不会被关闭
+        Ok(permit.ok().map(|permit| CredentialSlot { _permit: permit }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_limits_concurrency_per_credential() {
+        let queue = RequestQueue::new(1, 1, Some(Duration::from_millis(50)));
+
+        let slot = queue.acquire(1).await.unwrap();
+        assert!(slot.is_some());
+        // 其他凭证不受影响
+        assert!(queue.acquire(2).await.unwrap().is_some());
+
+        // 同一凭证繁忙：排队超时
+        assert!(matches!(queue.acquire(1).await, Err(QueueRejected::Timeout { .. })));
+        assert_eq!(queue.waiting(), 0);
+
+        // 槽位释放后可以再次获取
+        drop(slot);
+        assert!(queue.acquire(1).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_when_queue_full() {
+        let queue = Arc::new(RequestQueue::new(1, 1, None));
+        let slot = queue.acquire(1).await.unwrap();
+
+        let waiter = {
+            let queue = queue.clone();
+            tokio::spawn(async move { queue.acquire(1).await.map(|s| s.is_some()) })
+        };
+        while queue.waiting() == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        assert!(matches!(queue.acquire(1).await, Err(QueueRejected::Full { capacity: 1 })));
+
+        drop(slot);
+        assert!(waiter.await.unwrap().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_unlimited_when_disabled() {
+        let queue = RequestQueue::new(0, 0, None);
+        assert!(queue.acquire(1).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,3476 @@
+//! Token 管理模块
+//!
+//! 负责 Token 过期检测和刷新，支持 Social 和 IdC 认证方式
+//! 支持单凭证 (TokenManager) 和多凭证 (MultiTokenManager) 管理
+
+use anyhow::bail;
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::Mutex;
+use serde::Serialize;
+use tokio::sync::Mutex as TokioMutex;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::events::{CredentialChange, EVENT_BUS};
+use crate::group_budgets::GROUP_BUDGETS;
+use crate::http_client::{HttpClients, ProxyConfig};
+use crate::kiro::dedupe::{self, DedupeGroup};
+use crate::kiro::failure_tracker::{FailureClass, FailureTracker};
+use crate::kiro::machine_id;
+use crate::kiro::model::credentials::KiroCredentials;
+use crate::kiro::model::token_refresh::{
+    IdcRefreshRequest, IdcRefreshResponse, RefreshRequest, RefreshResponse,
+};
+use crate::kiro::model::upstream_error::{UpstreamEndpoint, UpstreamError};
+use crate::kiro::model::usage_limits::UsageLimitsResponse;
+use crate::model::config::{Config, RoutingStrategy};
+use crate::alerts::ALERTS;
+use crate::logs::LOG_COLLECTOR;
+use crate::credential_stats::{CREDENTIAL_STATS, CredentialRequestStats};
+use crate::usage_history::USAGE_HISTORY;
+use crate::webhooks::{WEBHOOKS, WebhookEvent};
+
+/// Token 管理器
+///
+/// 负责管理凭证和 Token 的自动刷新
+pub struct TokenManager {
+    config: Config,
+    credentials: KiroCredentials,
+    clients: HttpClients,
+}
+
+impl TokenManager {
+    /// 创建新的 TokenManager 实例
+    pub fn new(config: Config, credentials: KiroCredentials, proxy: Option<ProxyConfig>) -> Self {
+        let clients = HttpClients::new(proxy.as_ref()).expect("创建 HTTP 客户端失败");
+        Self {
+            config,
+            credentials,
+            clients,
+        }
+    }
+
+    /// 获取凭证的引用
+    pub fn credentials(&self) -> &KiroCredentials {
+        &self.credentials
+    }
+
+    /// 获取配置的引用
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// 确保获取有效的访问 Token
+    ///
+    /// 如果 Token 过期或即将过期，会自动刷新
+    pub async fn ensure_valid_token(&mut self) -> anyhow::Result<String> {
+        if is_token_expired(&self.credentials) || is_token_expiring_soon(&self.credentials) {
+            self.credentials =
+                refresh_token(&self.credentials, &self.config, &self.clients.refresh).await?;
+
+            // 刷新后再次检查 token 时间有效性
+            if is_token_expired(&self.credentials) {
+                anyhow::bail!("刷新后的 Token 仍然无效或已过期");
+            }
+        }
+
+        self.credentials
+            .access_token
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("没有可用的 accessToken"))
+    }
+
+    /// 获取使用额度信息
+    ///
+    /// 调用 getUsageLimits API 查询当前账户的使用额度
+    pub async fn get_usage_limits(&mut self) -> anyhow::Result<UsageLimitsResponse> {
+        let token = self.ensure_valid_token().await?;
+        get_usage_limits(&self.credentials, &self.config, &token, &self.clients.usage).await
+    }
+}
+
+/// 检查 Token 是否在指定时间内过期
+pub(crate) fn is_token_expiring_within(
+    credentials: &KiroCredentials,
+    minutes: i64,
+) -> Option<bool> {
+    credentials
+        .expires_at
+        .as_ref()
+        .and_then(|expires_at| DateTime::parse_from_rfc3339(expires_at).ok())
+        .map(|expires| expires <= Utc::now() + Duration::minutes(minutes))
+}
+
+/// 检查 Token 是否已过期（提前 5 分钟判断）
+pub(crate) fn is_token_expired(credentials: &KiroCredentials) -> bool {
+    is_token_expiring_within(credentials, 5).unwrap_or(true)
+}
+
+/// 检查 Token 是否即将过期（10分钟内）
+pub(crate) fn is_token_expiring_soon(credentials: &KiroCredentials) -> bool {
+    is_token_expiring_within(credentials, 10).unwrap_or(false)
+}
+
+/// 根据刚刷新得到的凭证计算单调时钟截止时间
+///
+/// expires_at 由刷新时的 `Utc::now() + expires_in` 得出，刷新后立即调用时二者之差即为 expires_in，
+/// 与本地时钟是否准确无关；之后的过期判断基于 Instant，不受系统时间调整影响
+fn monotonic_deadline(credentials: &KiroCredentials) -> Option<Instant> {
+    let expires_at = credentials.expires_at.as_ref()?;
+    let expires = DateTime::parse_from_rfc3339(expires_at).ok()?;
+    let remaining = (expires.with_timezone(&Utc) - Utc::now()).to_std().ok()?;
+    Some(Instant::now() + remaining)
+}
+
+/// 验证 refreshToken 的基本有效性
+pub(crate) fn validate_refresh_token(credentials: &KiroCredentials) -> anyhow::Result<()> {
+    let refresh_token = credentials
+        .refresh_token
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("缺少 refreshToken"))?;
+
+    if refresh_token.is_empty() {
+        bail!("refreshToken 为空");
+    }
+
+    if refresh_token.len() < 100 || refresh_token.ends_with("...") || refresh_token.contains("...")
+    {
+        bail!(
+            "refreshToken 已被截断（长度: {} 字符）。\n\
+             这通常是 Kiro IDE 为了防止凭证被第三方工具使用而故意截断的。",
+            refresh_token.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// Token 刷新端点（按认证方式区分 Social / IdC）
+pub(crate) fn refresh_endpoint(auth_method: Option<&str>, region: &str) -> String {
+    match auth_method.unwrap_or("social").to_lowercase().as_str() {
+        "idc" | "builder-id" => format!("https://oidc.{}.amazonaws.com/token", region),
+        _ => format!("https://prod.{}.auth.desktop.kiro.dev/refreshToken", region),
+    }
+}
+
+/// 刷新 Token
+pub(crate) async fn refresh_token(
+    credentials: &KiroCredentials,
+    config: &Config,
+    client: &reqwest::Client,
+) -> anyhow::Result<KiroCredentials> {
+    validate_refresh_token(credentials)?;
+
+    // 根据 auth_method 选择刷新方式
+    let auth_method = credentials.auth_method.as_deref().unwrap_or("social");
+
+    match auth_method.to_lowercase().as_str() {
+        "idc" | "builder-id" => refresh_idc_token(credentials, config, client).await,
+        _ => refresh_social_token(credentials, config, client).await,
+    }
+}
+
+/// 刷新 Social Token
+async fn refresh_social_token(
+    credentials: &KiroCredentials,
+    config: &Config,
+    client: &reqwest::Client,
+) -> anyhow::Result<KiroCredentials> {
+    tracing::info!("正在刷新 Social Token...");
+
+    let refresh_token = credentials.refresh_token.as_ref().unwrap();
+    let region = &config.region;
+
+    let refresh_url = refresh_endpoint(Some("social"), region);
+    let refresh_domain = format!("prod.{}.auth.desktop.kiro.dev", region);
+    let machine_id = machine_id::generate_from_credentials(credentials)
+        .ok_or_else(|| anyhow::anyhow!("无法生成 machineId"))?;
+    let kiro_version = &credentials.fingerprint(config).kiro_version;
+
+    let body = RefreshRequest {
+        refresh_token: refresh_token.to_string(),
+    };
+
+    let response = client
+        .post(&refresh_url)
+        .header("Accept", "application/json, text/plain, */*")
+        .header("Content-Type", "application/json")
+        .header(
+            "User-Agent",
+            format!("KiroIDE-{}-{}", kiro_version, machine_id),
+        )
+        .header("Accept-Encoding", "gzip, compress, deflate, br")
+        .header("host", &refresh_domain)
+        .header("Connection", "close")
+        .json(&body)
+        .send()
+        .await?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body_text = response.text().await.unwrap_or_default();
+        let error_msg = match status.as_u16() {
+            401 => "OAuth 凭证已过期或无效，需要重新认证",
+            403 => "权限不足，无法刷新 Token",
+            429 => "请求过于频繁，已被限流",
+            500..=599 => "服务器错误，AWS OAuth 服务暂时不可用",
+            _ => "Token 刷新失败",
+        };
+        return Err(UpstreamError::parse(UpstreamEndpoint::TokenRefresh, status, &body_text, error_msg).into());
+    }
+
+    let data: RefreshResponse = response.json().await?;
+
+    let mut new_credentials = credentials.clone();
+    new_credentials.access_token = Some(data.access_token);
+
+    if let Some(new_refresh_token) = data.refresh_token {
+        new_credentials.refresh_token = Some(new_refresh_token);
+    }
+
+    if let Some(profile_arn) = data.profile_arn {
+        new_credentials.profile_arn = Some(profile_arn);
+    }
+
+    if let Some(expires_in) = data.expires_in {
+        let expires_at = Utc::now() + Duration::seconds(expires_in);
+        new_credentials.expires_at = Some(expires_at.to_rfc3339());
+    }
+
+    Ok(new_credentials)
+}
+
+/// IdC Token 刷新所需的 x-amz-user-agent header
+const IDC_AMZ_USER_AGENT: &str = "aws-sdk-js/3.738.0 ua/2.1 os/other lang/js md/browser#unknown_unknown api/sso-oidc#3.738.0 m/E KiroIDE";
+
+/// 刷新 IdC Token (AWS SSO OIDC)
+async fn refresh_idc_token(
+    credentials: &KiroCredentials,
+    config: &Config,
+    client: &reqwest::Client,
+) -> anyhow::Result<KiroCredentials> {
+    tracing::info!("正在刷新 IdC Token...");
+
+    let refresh_token = credentials.refresh_token.as_ref().unwrap();
+    let client_id = credentials
+        .client_id
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("IdC 刷新需要 clientId"))?;
+    let client_secret = credentials
+        .client_secret
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("IdC 刷新需要 clientSecret"))?;
+
+    let region = &config.region;
+    let refresh_url = refresh_endpoint(Some("idc"), region);
+
+    let body = IdcRefreshRequest {
+        client_id: client_id.to_string(),
+        client_secret: client_secret.to_string(),
+        refresh_token: refresh_token.to_string(),
+        grant_type: "refresh_token".to_string(),
+    };
+
+    let response = client
+        .post(&refresh_url)
+        .header("Content-Type", "application/json")
+        .header("Host", format!("oidc.{}.amazonaws.com", region))
+        .header("Connection", "keep-alive")
+        .header("x-amz-user-agent", IDC_AMZ_USER_AGENT)
+        .header("Accept", "*/*")
+        .header("Accept-Language", "*")
+        .header("sec-fetch-mode", "cors")
+        .header("User-Agent", "node")
+        .header("Accept-Encoding", "br, gzip, deflate")
+        .json(&body)
+        .send()
+        .await?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body_text = response.text().await.unwrap_or_default();
+        let error_msg = match status.as_u16() {
+            401 => "IdC 凭证已过期或无效，需要重新认证",
+            403 => "权限不足，无法刷新 Token",
+            429 => "请求过于频繁，已被限流",
+            500..=599 => "服务器错误，AWS OIDC 服务暂时不可用",
+            _ => "IdC Token 刷新失败",
+        };
+        return Err(UpstreamError::parse(UpstreamEndpoint::TokenRefresh, status, &body_text, error_msg).into());
+    }
+
+    let data: IdcRefreshResponse = response.json().await?;
+
+    let mut new_credentials = credentials.clone();
+    new_credentials.access_token = Some(data.access_token);
+
+    if let Some(new_refresh_token) = data.refresh_token {
+        new_credentials.refresh_token = Some(new_refresh_token);
+    }
+
+    if let Some(expires_in) = data.expires_in {
+        let expires_at = Utc::now() + Duration::seconds(expires_in);
+        new_credentials.expires_at = Some(expires_at.to_rfc3339());
+    }
+
+    Ok(new_credentials)
+}
+
+/// getUsageLimits API 所需的 x-amz-user-agent header 前缀
+const USAGE_LIMITS_AMZ_USER_AGENT_PREFIX: &str = "aws-sdk-js/1.0.0";
+
+/// 获取使用额度信息
+pub(crate) async fn get_usage_limits(
+    credentials: &KiroCredentials,
+    config: &Config,
+    token: &str,
+    client: &reqwest::Client,
+) -> anyhow::Result<UsageLimitsResponse> {
+    tracing::debug!("正在获取使用额度信息...");
+
+    let region = &config.region;
+    let host = format!("q.{}.amazonaws.com", region);
+    let machine_id = machine_id::generate_from_credentials(credentials)
+        .ok_or_else(|| anyhow::anyhow!("无法生成 machineId"))?;
+    let kiro_version = &credentials.fingerprint(config).kiro_version;
+    // 该接口默认使用固定的系统和 Node.js 版本，凭证单独配置时以凭证为准
+    let override_or = |value: &Option<String>, default: &'static str| {
+        value.clone().filter(|v| !v.trim().is_empty()).unwrap_or_else(|| default.to_string())
+    };
+    let os_name = override_or(&credentials.system_version, "darwin#24.6.0");
+    let node_version = override_or(&credentials.node_version, "22.21.1");
+
+    // 构建 URL
+    let mut url = format!(
+        "https://{}/getUsageLimits?isEmailRequired=true&origin=AI_EDITOR&resourceType=AGENTIC_REQUEST",
+        host
+    );
+
+    // profileArn 是可选的
+    if let Some(profile_arn) = &credentials.profile_arn {
+        url.push_str(&format!("&profileArn={}", urlencoding::encode(profile_arn)));
+    }
+
+    // 构建 User-Agent headers
+    let user_agent = format!(
+        "aws-sdk-js/1.0.0 ua/2.1 os/{} lang/js md/nodejs#{} \
+         api/codewhispererruntime#1.0.0 m/N,E KiroIDE-{}-{}",
+        os_name, node_version, kiro_version, machine_id
+    );
+    let amz_user_agent = format!(
+        "{} KiroIDE-{}-{}",
+        USAGE_LIMITS_AMZ_USER_AGENT_PREFIX, kiro_version, machine_id
+    );
+
+
+    let response = client
+        .get(&url)
+        .header("x-amz-user-agent", &amz_user_agent)
+        .header("User-Agent", &user_agent)
+        .header("host", &host)
+        .header("amz-sdk-invocation-id", uuid::Uuid::new_v4().to_string())
+        .header("amz-sdk-request", "attempt=1; max=1")
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Connection", "close")
+        .send()
+        .await?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body_text = response.text().await.unwrap_or_default();
+        let error_msg = match status.as_u16() {
+            401 => "认证失败，Token 无效或已过期",
+            403 => "权限不足，无法获取使用额度",
+            429 => "请求过于频繁，已被限流",
+            500..=599 => "服务器错误，AWS 服务暂时不可用",
+            _ => "获取使用额度失败",
+        };
+        return Err(UpstreamError::parse(UpstreamEndpoint::UsageLimits, status, &body_text, error_msg).into());
+    }
+
+    let data: UsageLimitsResponse = response.json().await?;
+    Ok(data)
+}
+
+// ============================================================================
+// 多凭证 Token 管理器
+// ============================================================================
+
+/// 单个凭证条目的状态
+struct CredentialEntry {
+    /// 凭证唯一 ID
+    id: u64,
+    /// 凭证信息
+    credentials: KiroCredentials,
+    /// API 调用失败记录（按错误类型分别计数）
+    failures: FailureTracker,
+    /// 是否已禁用
+    disabled: bool,
+    /// 禁用原因（用于区分手动禁用 vs 自动禁用，便于自愈）
+    disabled_reason: Option<DisabledReason>,
+    /// 缓存的 machineId（refreshToken 变化时重新生成）
+    machine_id_cache: Option<CachedMachineId>,
+    /// 本次运行内刷新得到的 Token 截止时间（单调时钟），None 时回退到 expiresAt
+    token_deadline: Option<Instant>,
+    /// 上次 Token 刷新尝试时间（无论成败，用于限制刷新频率）
+    last_refresh_attempt: Option<Instant>,
+    /// Token 连续刷新失败次数（刷新成功后清零）
+    refresh_failures: u32,
+    /// 429 限流冷却截止时间，期间选择凭证时跳过
+    cooldown_until: Option<Instant>,
+    /// 连续被限流次数（决定下次冷却时长）
+    rate_limit_strikes: u32,
+    /// 最近一次健康检查结果
+    last_health_check: Option<HealthCheckResult>,
+    /// 额度用尽被禁用时，额度重置后自动启用的时间
+    quota_reset_at: Option<DateTime<Utc>>,
+}
+
+/// 缓存的 machineId 及其来源 refreshToken
+struct CachedMachineId {
+    refresh_token: String,
+    machine_id: String,
+}
+
+impl CredentialEntry {
+    /// 从文件加载的凭证创建条目
+    ///
+    /// 根据 status 字段初始化 disabled 状态，这样 invalid 状态的凭证在重启后仍然被禁用
+    fn loaded(id: u64, credentials: KiroCredentials) -> Self {
+        let (disabled, disabled_reason) = if credentials.status == "invalid" {
+            tracing::warn!("凭证 #{} 状态为 invalid，已自动禁用", id);
+            (true, Some(DisabledReason::Suspended))
+        } else {
+            (false, None)
+        };
+        Self {
+            id,
+            credentials,
+            failures: FailureTracker::default(),
+            disabled,
+            disabled_reason,
+            machine_id_cache: None,
+            token_deadline: None,
+            last_refresh_attempt: None,
+            refresh_failures: 0,
+            cooldown_until: None,
+            rate_limit_strikes: 0,
+            last_health_check: None,
+            quota_reset_at: None,
+        }
+    }
+
+    /// 检查 Token 是否在指定时间内过期
+    ///
+    /// 优先使用刷新时记录的单调时钟截止时间，进程重启后回退到持久化的 expiresAt
+    fn is_token_expiring_within(&self, minutes: i64) -> Option<bool> {
+        match self.token_deadline {
+            Some(deadline) => {
+                let margin = Duration::minutes(minutes).to_std().unwrap_or_default();
+                Some(deadline <= Instant::now() + margin)
+            }
+            None => is_token_expiring_within(&self.credentials, minutes),
+        }
+    }
+
+    /// 是否需要刷新 Token（已过期或 10 分钟内过期）
+    fn needs_refresh(&self) -> bool {
+        self.is_token_expiring_within(5).unwrap_or(true)
+            || self.is_token_expiring_within(10).unwrap_or(false)
+    }
+
+    /// 距离允许下一次刷新尝试还需等待的时间（None 表示可以立即刷新）
+    fn refresh_cooldown_remaining(&self) -> Option<std::time::Duration> {
+        let elapsed = self.last_refresh_attempt?.elapsed();
+        MIN_REFRESH_INTERVAL.checked_sub(elapsed).filter(|d| !d.is_zero())
+    }
+
+    /// 限流冷却剩余时间（None 表示未在冷却）
+    fn cooldown_remaining(&self) -> Option<std::time::Duration> {
+        self.cooldown_until?
+            .checked_duration_since(Instant::now())
+            .filter(|d| !d.is_zero())
+    }
+
+    /// 是否处于限流冷却中
+    fn is_cooling_down(&self) -> bool {
+        self.cooldown_remaining().is_some()
+    }
+
+    /// 写入刷新后的凭证并记录单调时钟截止时间
+    fn apply_refreshed(&mut self, credentials: KiroCredentials) {
+        self.token_deadline = monotonic_deadline(&credentials);
+        self.refresh_failures = 0;
+        self.credentials = credentials;
+    }
+
+    /// 获取 machineId，命中缓存时不再重复计算哈希
+    fn machine_id(&mut self) -> Option<String> {
+        let refresh_token = self.credentials.refresh_token.as_deref()?;
+        if let Some(cache) = &self.machine_id_cache {
+            if cache.refresh_token == refresh_token {
+                return Some(cache.machine_id.clone());
+            }
+        }
+
+        let machine_id = machine_id::generate_from_credentials(&self.credentials)?;
+        self.machine_id_cache = Some(CachedMachineId {
+            refresh_token: refresh_token.to_string(),
+            machine_id: machine_id.clone(),
+        });
+        Some(machine_id)
+    }
+
+    /// 检查凭证是否可用于反代
+    /// 
+    /// 同时检查以下条件：
+    /// - disabled 为 false
+    /// - status 不是 "invalid"
+    fn is_available(&self) -> bool {
+        !self.disabled && self.credentials.status != "invalid"
+    }
+}
+
+/// 禁用原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DisabledReason {
+    /// Admin API 手动禁用
+    Manual,
+    /// 连续失败达到阈值后自动禁用
+    TooManyFailures,
+    /// 账户被暂停（TEMPORARILY_SUSPENDED 或类似 403/401 错误）
+    Suspended,
+    /// 额度用尽，额度重置后自动启用
+    QuotaExhausted,
+}
+
+/// 额度重置时间：优先使用缓存的 nextResetAt，未知时按下个月 1 日（UTC）计算
+fn quota_reset_time(credentials: &KiroCredentials, now: DateTime<Utc>) -> DateTime<Utc> {
+    use chrono::{Datelike, TimeZone};
+
+    if let Some(at) = credentials
+        .next_reset_at
+        .and_then(|secs| DateTime::from_timestamp(secs as i64, 0))
+        .filter(|at| *at > now)
+    {
+        return at;
+    }
+    let (year, month) = if now.month() == 12 {
+        (now.year() + 1, 1)
+    } else {
+        (now.year(), now.month() + 1)
+    };
+    Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0)
+        .single()
+        .unwrap_or(now + Duration::days(30))
+}
+
+/// 检查错误是否表示凭证被暂停/无效（需要禁用凭证）
+///
+/// 只有在确定凭证本身无效时才返回 true，临时性错误（如限流、服务器错误）不会触发禁用。
+/// 判断依据是解析后的上游错误响应（见 [`UpstreamError::is_credential_invalid`]），
+/// 网络错误等没有上游响应的错误一律不禁用。
+fn is_credential_invalid_error(error: &anyhow::Error) -> bool {
+    UpstreamError::find(error).is_some_and(UpstreamError::is_credential_invalid)
+}
+
+// ============================================================================
+// Admin API 公开结构
+// ============================================================================
+
+/// 凭证条目快照（用于 Admin API 读取）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialEntrySnapshot {
+    /// 凭证唯一 ID
+    pub id: u64,
+    /// 是否被禁用
+    pub disabled: bool,
+    /// 计数窗口内的 API 调用失败次数（各错误类型合计）
+    pub failure_count: u32,
+    /// 限流冷却剩余秒数
+    pub cooldown_secs: Option<u64>,
+    /// 认证方式
+    pub auth_method: Option<String>,
+    /// 是否有 Profile ARN
+    pub has_profile_arn: bool,
+    /// Token 过期时间
+    pub expires_at: Option<String>,
+    /// 用户邮箱
+    pub email: Option<String>,
+    /// 订阅类型
+    pub subscription_title: Option<String>,
+    /// 当前使用量
+    pub current_usage: Option<f64>,
+    /// 使用限额
+    pub usage_limit: Option<f64>,
+    /// 剩余额度
+    pub remaining: Option<f64>,
+    /// 下次重置时间
+    pub next_reset_at: Option<f64>,
+    /// Refresh Token
+    pub refresh_token: Option<String>,
+    /// Access Token
+    pub access_token: Option<String>,
+    /// Profile ARN
+    pub profile_arn: Option<String>,
+    /// 凭证状态：normal(正常), invalid(无效/封禁), expired(过期)
+    pub status: String,
+    /// 分组 ID
+    pub group_id: String,
+    /// 凭证单独使用的代理地址
+    pub proxy_url: Option<String>,
+    /// 凭证单独使用的 Kiro 版本
+    pub kiro_version: Option<String>,
+    /// 凭证单独使用的系统版本
+    pub system_version: Option<String>,
+    /// 凭证单独使用的 Node.js 版本
+    pub node_version: Option<String>,
+    /// machineId 盐值
+    pub machine_id_salt: Option<String>,
+    /// 最近一次健康检查结果
+    pub last_health_check: Option<HealthCheckResult>,
+    /// 额度用尽被禁用时，预计自动启用的时间（RFC3339）
+    pub quota_reset_at: Option<String>,
+    /// 最近 1 小时 / 24 小时的请求成功率和平均延迟
+    pub request_stats: CredentialRequestStats,
+}
+
+/// 凭证健康检查结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthCheckResult {
+    /// 检查时间（RFC3339）
+    pub checked_at: String,
+    /// 探测耗时（毫秒）
+    pub latency_ms: u64,
+    /// 探测是否成功
+    pub healthy: bool,
+    /// 失败原因
+    pub error: Option<String>,
+}
+
+/// 凭证管理器状态快照
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManagerSnapshot {
+    /// 凭证条目列表
+    pub entries: Vec<CredentialEntrySnapshot>,
+    /// 当前活跃凭证 ID
+    pub current_id: u64,
+    /// 总凭证数量
+    pub total: usize,
+    /// 可用凭证数量
+    pub available: usize,
+}
+
+/// 多凭证 Token 管理器
+///
+/// 支持多个凭证的管理，实现固定优先级 + 故障转移策略
+/// 故障统计基于 API 调用结果，而非 Token 刷新结果
+pub struct MultiTokenManager {
+    config: Config,
+    /// 按用途划分的 HTTP Client（刷新 / 额度查询 / MCP / 补全）
+    clients: HttpClients,
+    /// 凭证单独配置代理时使用的 HTTP Client（按代理配置缓存）
+    proxy_clients: Mutex<HashMap<String, HttpClients>>,
+    /// 凭证条目列表
+    entries: Mutex<Vec<CredentialEntry>>,
+    /// 当前活动凭证 ID
+    current_id: Mutex<u64>,
+    /// 按凭证划分的 Token 刷新锁：同一凭证同一时间只有一个刷新操作，不同凭证可并发刷新
+    refresh_locks: Mutex<HashMap<u64, Arc<TokioMutex<()>>>>,
+    /// 凭证文件路径（用于回写）
+    credentials_path: Option<PathBuf>,
+    /// 是否为多凭证格式（数组格式才回写）
+    is_multiple_format: bool,
+    /// 活跃分组 ID（反代使用，None 表示使用所有分组）
+    active_group_id: Mutex<Option<String>>,
+    /// 备用分组 ID（活跃分组耗尽时使用）
+    fallback_group_id: Mutex<Option<String>>,
+    /// 正在转移到备用分组的活跃分组（用于只在状态切换时通知）
+    group_failover: Mutex<Option<String>>,
+    /// 会话粘性绑定（session_id -> 凭证 ID）
+    session_bindings: Mutex<HashMap<String, SessionBinding>>,
+}
+
+/// 会话绑定的凭证
+struct SessionBinding {
+    credential_id: u64,
+    last_used: Instant,
+}
+
+/// 会话绑定闲置超时（超过后视为新会话）
+const SESSION_BINDING_TTL: std::time::Duration = std::time::Duration::from_secs(30 * 60);
+
+/// Token 连续刷新失败达到该次数时推送 Webhook
+const REFRESH_FAILURE_WEBHOOK_THRESHOLD: u32 = 3;
+
+/// 同一凭证两次 Token 刷新尝试的最小间隔（失败时同样生效，防止刷新风暴）
+const MIN_REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// 429 限流冷却的基础时长（连续限流时按 2 的幂增长）
+const RATE_LIMIT_COOLDOWN_BASE: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// 429 限流冷却时长上限（同样约束上游 Retry-After）
+const RATE_LIMIT_COOLDOWN_MAX: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
+/// 通知凭证被自动禁用（事件总线 + Webhook）
+fn notify_auto_disabled(id: u64, change: CredentialChange, reason: &str) {
+    EVENT_BUS.credential_changed(id, change);
+    WEBHOOKS.notify(WebhookEvent::credential_disabled(id, reason));
+}
+
+/// 记录一次 Token 刷新失败，连续失败次数恰好达到阈值时推送 Webhook（每轮连续失败只推送一次）
+fn record_refresh_failure(entries: &Mutex<Vec<CredentialEntry>>, id: u64, error: &str) {
+    let failures = match entries.lock().iter_mut().find(|e| e.id == id) {
+        Some(entry) => {
+            entry.refresh_failures += 1;
+            entry.refresh_failures
+        }
+        None => return,
+    };
+    if failures == REFRESH_FAILURE_WEBHOOK_THRESHOLD {
+        WEBHOOKS.notify(WebhookEvent::refresh_failed(id, failures, error));
+    }
+}
+
+/// 计算第 `strikes` 次连续限流后的冷却时长（指数退避 + 最多 25% 抖动）
+fn rate_limit_cooldown(strikes: u32) -> std::time::Duration {
+    let exp = strikes.saturating_sub(1).min(16);
+    let backoff = RATE_LIMIT_COOLDOWN_BASE
+        .saturating_mul(1 << exp)
+        .min(RATE_LIMIT_COOLDOWN_MAX);
+    let jitter_max = (backoff.as_millis() as u64 / 4).max(1);
+    (backoff + std::time::Duration::from_millis(fastrand::u64(0..=jitter_max)))
+        .min(RATE_LIMIT_COOLDOWN_MAX)
+}
+
+/// 凭证获取超时错误
+///
+/// 在 `credential_acquire_timeout_secs` 内没有任何凭证能提供有效 Token（如凭证池大面积刷新失败）。
+/// 调用方可通过 `downcast_ref` 识别，直接向客户端返回 `credential_unavailable` 而不再重试
+#[derive(Debug)]
+pub struct CredentialUnavailable {
+    pub timeout: std::time::Duration,
+}
+
+impl std::fmt::Display for CredentialUnavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}s 内未能获取可用凭证", self.timeout.as_secs())
+    }
+}
+
+impl std::error::Error for CredentialUnavailable {}
+
+/// API 调用上下文
+///
+/// 绑定特定凭证的调用上下文，确保 token、credentials 和 id 的一致性
+/// 用于解决并发调用时 current_id 竞态问题
+#[derive(Clone)]
+pub struct CallContext {
+    /// 凭证 ID（用于 report_success/report_failure）
+    pub id: u64,
+    /// 凭证信息（用于构建请求头）
+    pub credentials: KiroCredentials,
+    /// 访问 Token
+    pub token: String,
+    /// 设备指纹（来自凭证条目缓存）
+    pub machine_id: Option<String>,
+}
+
+impl MultiTokenManager {
+    /// 创建多凭证 Token 管理器
+    ///
+    /// # Arguments
+    /// * `config` - 应用配置
+    /// * `credentials` - 凭证列表
+    /// * `proxy` - 可选的代理配置
+    /// * `credentials_path` - 凭证文件路径（用于回写）
+    /// * `is_multiple_format` - 是否为多凭证格式（数组格式才回写）
+    pub fn new(
+        config: Config,
+        credentials: Vec<KiroCredentials>,
+        proxy: Option<ProxyConfig>,
+        credentials_path: Option<PathBuf>,
+        is_multiple_format: bool,
+    ) -> anyhow::Result<Self> {
+        // 计算当前最大 ID，为没有 ID 的凭证分配新 ID
+        let max_existing_id = credentials.iter().filter_map(|c| c.id).max().unwrap_or(0);
+        let mut next_id = max_existing_id + 1;
+        let mut has_new_ids = false;
+
+        let entries: Vec<CredentialEntry> = credentials
+            .into_iter()
+            .map(|mut cred| {
+                let id = cred.id.unwrap_or_else(|| {
+                    let id = next_id;
+                    next_id += 1;
+                    cred.id = Some(id);
+                    has_new_ids = true;
+                    id
+                });
+                CredentialEntry::loaded(id, cred)
+            })
+            .collect();
+
+        // 检测重复 ID
+        let mut seen_ids = std::collections::HashSet::new();
+        let mut duplicate_ids = Vec::new();
+        for entry in &entries {
+            if !seen_ids.insert(entry.id) {
+                duplicate_ids.push(entry.id);
+            }
+        }
+        if !duplicate_ids.is_empty() {
+            anyhow::bail!("检测到重复的凭证 ID: {:?}", duplicate_ids);
+        }
+
+        // 选择初始凭证：ID 最小的可用凭证，无可用凭证时为 0
+        let initial_id = entries
+            .iter()
+            .filter(|e| e.is_available())
+            .min_by_key(|e| e.id)
+            .map(|e| e.id)
+            .unwrap_or(0);
+
+        let fallback_group_id = config.fallback_group_id.clone();
+        let clients = HttpClients::with_timeouts(proxy.as_ref(), &config.upstream_timeouts)?;
+        let manager = Self {
+            config,
+            clients,
+            proxy_clients: Mutex::new(HashMap::new()),
+            entries: Mutex::new(entries),
+            current_id: Mutex::new(initial_id),
+            refresh_locks: Mutex::new(HashMap::new()),
+            credentials_path,
+            is_multiple_format,
+            active_group_id: Mutex::new(None),
+            fallback_group_id: Mutex::new(fallback_group_id),
+            group_failover: Mutex::new(None),
+            session_bindings: Mutex::new(HashMap::new()),
+        };
+
+        // 如果有新分配的 ID，立即持久化到配置文件
+        if has_new_ids {
+            if let Err(e) = manager.persist_credentials() {
+                tracing::warn!("新分配 ID 后持久化失败: {}", e);
+            } else {
+                tracing::info!("已为凭证分配新 ID 并写回配置文件");
+            }
+        }
+
+        Ok(manager)
+    }
+
+    /// 凭证文件路径（未配置回写时为 None）
+    pub fn credentials_path(&self) -> Option<&std::path::Path> {
+        self.credentials_path.as_deref()
+    }
+
+    /// 获取配置的引用
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// 获取按用途划分的 HTTP Client
+    pub fn http_clients(&self) -> &HttpClients {
+        &self.clients
+    }
+
+    /// 构建（或复用）指定代理的 HTTP Client
+    fn proxy_http_clients(&self, proxy: &ProxyConfig) -> anyhow::Result<HttpClients> {
+        let key = format!(
+            "{}\n{}\n{}",
+            proxy.url,
+            proxy.username.as_deref().unwrap_or_default(),
+            proxy.password.as_deref().unwrap_or_default()
+        );
+        let mut cache = self.proxy_clients.lock();
+        if let Some(clients) = cache.get(&key) {
+            return Ok(clients.clone());
+        }
+        let clients = HttpClients::with_timeouts(Some(proxy), &self.config.upstream_timeouts)?;
+        cache.insert(key, clients.clone());
+        Ok(clients)
+    }
+
+    /// 获取凭证使用的 HTTP Client（凭证单独配置了代理时使用该代理，否则为全局 Client）
+    pub fn clients_for(&self, credentials: &KiroCredentials) -> HttpClients {
+        let Some(proxy) = credentials.proxy() else {
+            return self.clients.clone();
+        };
+        self.proxy_http_clients(&proxy).unwrap_or_else(|e| {
+            tracing::warn!("凭证代理 {} 无效，改用全局代理: {}", proxy.url, e);
+            self.clients.clone()
+        })
+    }
+
+    /// 登记一次 Token 刷新尝试
+    ///
+    /// 距上次尝试不足 MIN_REFRESH_INTERVAL 时返回错误，不发起刷新请求
+    fn begin_refresh_attempt(&self, id: u64) -> anyhow::Result<()> {
+        let mut entries = self.entries.lock();
+        let entry = entries
+            .iter_mut()
+            .find(|e| e.id == id)
+            .ok_or_else(|| anyhow::anyhow!("凭证 #{} 不存在", id))?;
+        if let Some(remaining) = entry.refresh_cooldown_remaining() {
+            bail!(
+                "凭证 #{} Token 刷新过于频繁，{} 秒后再试",
+                id,
+                remaining.as_secs().max(1)
+            );
+        }
+        entry.last_refresh_attempt = Some(Instant::now());
+        Ok(())
+    }
+
+    /// 检查指定凭证是否需要刷新 Token（凭证不存在时视为需要）
+    fn entry_needs_refresh(&self, id: u64) -> bool {
+        self.entries
+            .lock()
+            .iter()
+            .find(|e| e.id == id)
+            .map(|e| e.needs_refresh())
+            .unwrap_or(true)
+    }
+
+    /// 获取当前活动凭证的克隆
+    pub fn credentials(&self) -> KiroCredentials {
+        let entries = self.entries.lock();
+        let current_id = *self.current_id.lock();
+        entries
+            .iter()
+            .find(|e| e.id == current_id)
+            .map(|e| e.credentials.clone())
+            .unwrap_or_default()
+    }
+
+    /// 获取凭证总数
+    pub fn total_count(&self) -> usize {
+        self.entries.lock().len()
+    }
+
+    /// 获取可用凭证数量
+    pub fn available_count(&self) -> usize {
+        self.entries.lock().iter().filter(|e| e.is_available()).count()
+    }
+
+    /// 获取当前使用的凭证 ID
+    pub fn current_id(&self) -> u64 {
+        *self.current_id.lock()
+    }
+
+    /// 设置活跃分组（反代使用）
+    pub fn set_active_group(&self, group_id: Option<String>) {
+        {
+            let mut active = self.active_group_id.lock();
+            *active = group_id;
+        }
+        *self.group_failover.lock() = None;
+        // 切换分组后重新选择凭证（锁已释放）
+        self.select_smallest_id_in_group();
+    }
+
+    /// 获取当前活跃分组
+    pub fn get_active_group(&self) -> Option<String> {
+        self.active_group_id.lock().clone()
+    }
+
+    /// 设置备用分组（None 表示不转移）
+    pub fn set_fallback_group(&self, group_id: Option<String>) {
+        *self.fallback_group_id.lock() = group_id;
+        *self.group_failover.lock() = None;
+    }
+
+    /// 当前是否已转移到备用分组（返回备用分组 ID）
+    pub fn failover_group(&self) -> Option<String> {
+        self.group_failover
+            .lock()
+            .as_ref()
+            .and_then(|_| self.fallback_group_id.lock().clone())
+    }
+
+    /// 刷新凭证选择（重新选择当前分组内 ID 最小的凭证）
+    pub fn refresh_credential_selection(&self) {
+        self.select_smallest_id_in_group();
+    }
+
+    /// 切换当前凭证（优先级路由下后续请求沿用该凭证）
+    ///
+    /// 凭证必须存在、可用且位于活跃分组内
+    pub fn switch_to(&self, id: u64) -> anyhow::Result<()> {
+        let credentials = {
+            let entries = self.entries.lock();
+            let entry = entries
+                .iter()
+                .find(|e| e.id == id)
+                .ok_or_else(|| anyhow::anyhow!("凭证不存在: {}", id))?;
+            if !entry.is_available() {
+                anyhow::bail!("凭证 #{} 不可用", id);
+            }
+            entry.credentials.clone()
+        };
+        if !self.is_in_active_group(&credentials) {
+            anyhow::bail!("凭证 #{} 不在活跃分组内", id);
+        }
+
+        let mut current_id = self.current_id.lock();
+        if *current_id != id {
+            tracing::info!("切换当前凭证: #{} -> #{}", *current_id, id);
+            *current_id = id;
+        }
+        Ok(())
+    }
+
+    /// 检查凭证是否在活跃分组内
+    fn is_in_active_group(&self, credentials: &KiroCredentials) -> bool {
+        let active_group = self.active_group_id.lock();
+        match active_group.as_ref() {
+            None => true, // 无分组限制，所有凭证可用
+            Some(group_id) => &credentials.group_id == group_id,
+        }
+    }
+
+    /// 选择活跃分组内 ID 最小的凭证
+    fn select_smallest_id_in_group(&self) {
+        let entries = self.entries.lock();
+        let mut current_id = self.current_id.lock();
+        let active_group = self.active_group_id.lock();
+
+        // 选择活跃分组内 ID 最小的可用凭证
+        let best = entries
+            .iter()
+            .filter(|e| {
+                if !e.is_available() {
+                    return false;
+                }
+                match active_group.as_ref() {
+                    None => true,
+                    Some(group_id) => &e.credentials.group_id == group_id,
+                }
+            })
+            .min_by_key(|e| e.id);
+
+        match best {
+            Some(entry) => {
+                if entry.id != *current_id {
+                    tracing::info!(
+                        "分组切换后选择凭证: #{} -> #{}",
+                        *current_id,
+                        entry.id
+                    );
+                    *current_id = entry.id;
+                } else {
+                    tracing::info!("分组内当前凭证有效: #{}", entry.id);
+                }
+            }
+            None => {
+                // 分组内没有可用凭证，将 current_id 设为 0
+                tracing::warn!("活跃分组内没有可用凭证，current_id 设为 0");
+                *current_id = 0;
+            }
+        }
+    }
+
+    /// 获取用于导出的凭证数据
+    /// 
+    /// # Arguments
+    /// * `ids` - 要导出的凭证 ID 列表，为空则导出全部
+    ///
+    /// # Returns
+    /// 凭证列表（包含完整数据）
+    pub fn get_credentials_for_export(&self, ids: &[u64]) -> Vec<KiroCredentials> {
+        let entries = self.entries.lock();
+        let id_set: std::collections::HashSet<u64> = ids.iter().cloned().collect();
+        
+        entries
+            .iter()
+            .filter(|e| id_set.is_empty() || id_set.contains(&e.id))
+            .map(|e| e.credentials.clone())
+            .collect()
+    }
+
+    /// 获取 API 调用上下文
+    ///
+    /// 返回绑定了 id、credentials 和 token 的调用上下文
+    /// 确保整个 API 调用过程中使用一致的凭证信息
+    ///
+    /// 如果 Token 过期或即将过期，会自动刷新
+    /// Token 刷新失败时会尝试下一个可用凭证（不计入失败次数）
+    ///
+    /// 整个过程受 `credential_acquire_timeout_secs` 限制，超时返回 [`CredentialUnavailable`]
+    pub async fn acquire_context(&self) -> anyhow::Result<CallContext> {
+        self.acquire_context_in(None).await
+    }
+
+    /// 在指定分组内获取 API 调用上下文
+    ///
+    /// `group` 为 None 时使用活跃分组；分组反代实例传入固定分组，不影响活跃分组和当前凭证
+    pub async fn acquire_context_in(&self, group: Option<&str>) -> anyhow::Result<CallContext> {
+        self.with_acquire_deadline(self.select_context(group)).await
+    }
+
+    /// 实际生效的分组过滤：固定分组优先，否则使用活跃分组（None 表示全部）
+    ///
+    /// 活跃分组耗尽（或超出预算且策略为转移）且配置了可用的备用分组时使用备用分组；固定分组不转移
+    fn effective_group(&self, pinned: Option<&str>) -> Option<String> {
+        if let Some(group) = pinned {
+            return Some(group.to_string());
+        }
+        let active = self.active_group_id.lock().clone()?;
+        let Some(fallback) = self.fallback_group_id.lock().clone().filter(|f| *f != active) else {
+            return Some(active);
+        };
+
+        if self.group_usable(&active) && !GROUP_BUDGETS.should_fall_back(&active) {
+            self.note_group_failover(&active, None);
+            Some(active)
+        } else if self.group_usable(&fallback) && GROUP_BUDGETS.exceeded(&fallback).is_none() {
+            self.note_group_failover(&active, Some(&fallback));
+            Some(fallback)
+        } else {
+            // 备用分组同样不可用，仍在活跃分组内选择（返回原有的错误）
+            Some(active)
+        }
+    }
+
+    /// 分组内是否有可用凭证（未禁用且余额未用尽）
+    fn group_usable(&self, group_id: &str) -> bool {
+        self.entries.lock().iter().any(|e| {
+            e.credentials.group_id == group_id
+                && e.is_available()
+                && !e.credentials.remaining.is_some_and(|r| r <= 0.0)
+        })
+    }
+
+    /// 记录分组转移状态，进入或退出转移时写日志并推送 Webhook
+    fn note_group_failover(&self, active: &str, fallback: Option<&str>) {
+        let mut failover = self.group_failover.lock();
+        match fallback {
+            Some(fallback) if failover.as_deref() != Some(active) => {
+                *failover = Some(active.to_string());
+                let event = WebhookEvent::group_failover(active, fallback);
+                tracing::warn!("{}", event.message);
+                LOG_COLLECTOR.add_log("WARN", &format!("🔀 {}", event.message));
+                WEBHOOKS.notify(event);
+            }
+            None if failover.is_some() => {
+                let previous = failover.take().unwrap_or_default();
+                let event = WebhookEvent::group_recovered(&previous);
+                tracing::info!("{}", event.message);
+                LOG_COLLECTOR.add_log("INFO", &format!("✅ {}", event.message));
+                WEBHOOKS.notify(event);
+            }
+            _ => {}
+        }
+    }
+
+    /// 为凭证获取过程加上总超时，避免凭证池故障时串行刷新拖长客户端延迟
+    async fn with_acquire_deadline(
+        &self,
+        acquire: impl std::future::Future<Output = anyhow::Result<CallContext>>,
+    ) -> anyhow::Result<CallContext> {
+        let secs = self.config.credential_acquire_timeout_secs;
+        if secs == 0 {
+            return acquire.await;
+        }
+
+        let timeout = std::time::Duration::from_secs(secs);
+        match tokio::time::timeout(timeout, acquire).await {
+            Ok(result) => result,
+            Err(_) => {
+                tracing::warn!("{}s 内未能获取可用凭证，放弃本次请求", secs);
+                Err(CredentialUnavailable { timeout }.into())
+            }
+        }
+    }
+
+    /// 选择凭证并确保 Token 有效（无超时限制）
+    ///
+    /// `pinned` 为固定分组时只在分组内选择，且不修改全局的当前凭证
+    async fn select_context(&self, pinned: Option<&str>) -> anyhow::Result<CallContext> {
+        let total = self.total_count();
+        let active_group = self.effective_group(pinned);
+        if let Some(group) = &active_group {
+            GROUP_BUDGETS.check(group)?;
+        }
+        let mut tried: Vec<u64> = Vec::new();
+
+        loop {
+            if tried.len() >= total {
+                anyhow::bail!(
+                    "所有凭证均无法获取有效 Token（可用: {}/{}）",
+                    self.available_count(),
+                    total
+                );
+            }
+
+            let (id, credentials) = {
+                let mut entries = self.entries.lock();
+                let current_id = *self.current_id.lock();
+
+                // 分组过滤闭包（不限分组时跳过超出预算的分组）
+                let in_group = |cred: &KiroCredentials| -> bool {
+                    match active_group.as_ref() {
+                        None => GROUP_BUDGETS.exceeded(&cred.group_id).is_none(),
+                        Some(group_id) => &cred.group_id == group_id,
+                    }
+                };
+
+                // 优先级策略下沿用当前凭证（需要在分组内、可用且未在限流冷却中）；
+                // 额度策略每次都重新选择
+                let current = match self.config.routing_strategy {
+                    RoutingStrategy::Priority => entries.iter().find(|e| {
+                        e.id == current_id
+                            && e.is_available()
+                            && !e.is_cooling_down()
+                            && in_group(&e.credentials)
+                            && !tried.contains(&e.id)
+                    }),
+                    RoutingStrategy::LeastUsage => None,
+                };
+
+                if let Some(entry) = current {
+                    (entry.id, entry.credentials.clone())
+                } else {
+                    // 按路由策略选择分组内本轮未尝试过的可用凭证
+                    let mut best = self.pick_candidate(entries.iter().filter(|e| {
+                        e.is_available() && in_group(&e.credentials) && !tried.contains(&e.id)
+                    }));
+
+                    // 没有可用凭证：如果是"自动禁用导致全灭"，做一次类似重启的自愈
+                    if best.is_none()
+                        && entries.iter().any(|e| {
+                            e.disabled && e.disabled_reason == Some(DisabledReason::TooManyFailures)
+                        })
+                    {
+                        tracing::warn!(
+                            "所有凭证均已被自动禁用，执行自愈：重置失败计数并重新启用（等价于重启）"
+                        );
+                        for e in entries.iter_mut() {
+                            if e.disabled_reason == Some(DisabledReason::TooManyFailures) {
+                                e.disabled = false;
+                                e.disabled_reason = None;
+                                e.failures.clear();
+                                EVENT_BUS.credential_changed(e.id, CredentialChange::Enabled);
+                            }
+                        }
+                        best = self.pick_candidate(entries.iter().filter(|e| {
+                            e.is_available() && in_group(&e.credentials) && !tried.contains(&e.id)
+                        }));
+                    }
+
+                    if let Some(entry) = best {
+                        // 先提取数据
+                        let new_id = entry.id;
+                        let new_creds = entry.credentials.clone();
+                        drop(entries);
+                        // 更新 current_id（固定分组的实例不影响全局当前凭证）
+                        if pinned.is_none() {
+                            *self.current_id.lock() = new_id;
+                        }
+                        (new_id, new_creds)
+                    } else if !tried.is_empty() {
+                        let available = entries.iter().filter(|e| !e.disabled).count();
+                        anyhow::bail!(
+                            "所有凭证均无法获取有效 Token（可用: {}/{}）",
+                            available,
+                            total
+                        );
+                    } else {
+                        // 注意：必须在 bail! 之前计算 available_count，
+                        // 因为 available_count() 会尝试获取 entries 锁，
+                        // 而此时我们已经持有该锁，会导致死锁
+                        let available = entries.iter().filter(|e| !e.disabled).count();
+                        let group_info = match active_group.as_ref() {
+                            Some(g) => format!("分组 '{}' 内", g),
+                            None => "全部".to_string(),
+                        };
+                        anyhow::bail!("{}凭证均已禁用或无可用凭证（{}/{}）", group_info, available, total);
+                    }
+                }
+            };
+
+            // 尝试获取/刷新 Token
+            match self.try_ensure_token(id, &credentials).await {
+                Ok(ctx) => {
+                    return Ok(ctx);
+                }
+                Err(e) => {
+                    let error_msg = e.to_string();
+                    tracing::warn!("凭证 #{} Token 刷新失败，尝试下一个凭证: {}", id, error_msg);
+
+                    // 检测是否为凭证无效/被暂停的错误
+                    if is_credential_invalid_error(&e) {
+                        let mut entries = self.entries.lock();
+                        if let Some(entry) = entries.iter_mut().find(|e| e.id == id) {
+                            entry.disabled = true;
+                            entry.disabled_reason = Some(DisabledReason::Suspended);
+                            entry.credentials.status = "invalid".to_string();
+                            tracing::error!(
+                                "凭证 #{} 已被自动禁用（账户暂停/无效）: {}",
+                                id,
+                                error_msg
+                            );
+                            notify_auto_disabled(id, CredentialChange::Suspended, &error_msg);
+                        }
+                        drop(entries);
+                        // 持久化更改
+                        if let Err(persist_err) = self.persist_credentials() {
+                            tracing::warn!("凭证禁用后持久化失败: {}", persist_err);
+                        }
+                    }
+
+                    // Token 刷新失败，切换到下一个优先级的凭证（不计入失败次数）
+                    if pinned.is_none() {
+                        self.switch_to_next_by_id();
+                    }
+                    tried.push(id);
+                }
+            }
+        }
+    }
+
+    /// 按路由策略从候选凭证中选择一个（内部方法）
+    ///
+    /// 冷却中的凭证排在最后，全部冷却时选最先解除冷却的；其余按策略选择 ID 最小
+    /// 或剩余额度最多的凭证（额度未知的排在已知之后）
+    fn pick_candidate<'a>(
+        &self,
+        candidates: impl Iterator<Item = &'a CredentialEntry>,
+    ) -> Option<&'a CredentialEntry> {
+        match self.config.routing_strategy {
+            RoutingStrategy::Priority => candidates.min_by_key(|e| (e.cooldown_remaining(), e.id)),
+            RoutingStrategy::LeastUsage => candidates.min_by(|a, b| {
+                let remaining = |e: &CredentialEntry| e.credentials.remaining.unwrap_or(f64::NEG_INFINITY);
+                a.cooldown_remaining()
+                    .cmp(&b.cooldown_remaining())
+                    .then_with(|| remaining(b).total_cmp(&remaining(a)))
+                    .then_with(|| a.id.cmp(&b.id))
+            }),
+        }
+    }
+
+    /// 切换到下一个 ID 最小的可用凭证（内部方法，Token 刷新失败时调用）
+    fn switch_to_next_by_id(&self) {
+        let entries = self.entries.lock();
+        let mut current_id = self.current_id.lock();
+
+        // 选择 ID 最小的未禁用凭证（排除当前凭证）
+        if let Some(entry) = entries
+            .iter()
+            .filter(|e| !e.disabled && e.id != *current_id)
+            .min_by_key(|e| e.id)
+        {
+            let from = std::mem::replace(&mut *current_id, entry.id);
+            tracing::info!(
+                "已切换到凭证 #{}",
+                entry.id
+            );
+            WEBHOOKS.notify(WebhookEvent::failover(from, entry.id, "Token 刷新失败"));
+        }
+    }
+
+    /// 选择 ID 最小的未禁用凭证作为当前凭证（内部方法）
+    ///
+    /// 不排除当前凭证，纯粹按 ID 选择
+    fn select_smallest_id(&self) {
+        let entries = self.entries.lock();
+        let mut current_id = self.current_id.lock();
+
+        // 选择 ID 最小的未禁用凭证（不排除当前凭证）
+        if let Some(best) = entries
+            .iter()
+            .filter(|e| !e.disabled)
+            .min_by_key(|e| e.id)
+        {
+            if best.id != *current_id {
+                tracing::info!(
+                    "切换凭证: #{} -> #{}",
+                    *current_id,
+                    best.id
+                );
+                *current_id = best.id;
+            }
+        }
+    }
+
+    /// 按会话获取 API 调用上下文
+    ///
+    /// 启用会话粘性时，同一会话优先使用上次绑定的凭证（凭证不可用或获取 Token 失败时才回退到
+    /// 正常选择逻辑并重新绑定），以提高 Kiro 侧上下文缓存命中率
+    ///
+    /// `group` 含义同 [`Self::acquire_context_in`]
+    pub async fn acquire_context_for_session(
+        &self,
+        session_id: Option<&str>,
+        group: Option<&str>,
+    ) -> anyhow::Result<CallContext> {
+        let session_id = match session_id {
+            Some(s) if self.config.session_affinity_enabled => s,
+            _ => return self.acquire_context_in(group).await,
+        };
+
+        self.with_acquire_deadline(async {
+            if let Some((id, credentials)) = self.bound_credential(session_id, group) {
+                match self.try_ensure_token(id, &credentials).await {
+                    Ok(ctx) => {
+                        self.bind_session(session_id, ctx.id);
+                        return Ok(ctx);
+                    }
+                    Err(e) => {
+                        tracing::warn!("会话绑定的凭证 #{} 不可用，重新选择: {}", id, e);
+                        self.unbind_session(session_id);
+                    }
+                }
+            }
+
+            let ctx = self.select_context(group).await?;
+            self.bind_session(session_id, ctx.id);
+            Ok(ctx)
+        })
+        .await
+    }
+
+    /// 获取会话绑定的凭证（需在分组内、可用且未在冷却中，绑定过期视为无绑定）
+    fn bound_credential(&self, session_id: &str, group: Option<&str>) -> Option<(u64, KiroCredentials)> {
+        let credential_id = {
+            let bindings = self.session_bindings.lock();
+            let binding = bindings.get(session_id)?;
+            if binding.last_used.elapsed() >= SESSION_BINDING_TTL {
+                return None;
+            }
+            binding.credential_id
+        };
+
+        let active_group = self.effective_group(group);
+        let entries = self.entries.lock();
+        entries
+            .iter()
+            .find(|e| {
+                e.id == credential_id
+                    && e.is_available()
+                    && !e.is_cooling_down()
+                    && GROUP_BUDGETS.exceeded(&e.credentials.group_id).is_none()
+                    && active_group
+                        .as_ref()
+                        .is_none_or(|g| &e.credentials.group_id == g)
+            })
+            .map(|e| (e.id, e.credentials.clone()))
+    }
+
+    /// 绑定会话到凭证，并顺带清理过期绑定
+    fn bind_session(&self, session_id: &str, credential_id: u64) {
+        let mut bindings = self.session_bindings.lock();
+        bindings.retain(|_, b| b.last_used.elapsed() < SESSION_BINDING_TTL);
+        bindings.insert(
+            session_id.to_string(),
+            SessionBinding {
+                credential_id,
+                last_used: Instant::now(),
+            },
+        );
+    }
+
+    /// 解除会话绑定（凭证调用失败时由 Provider 调用）
+    pub fn unbind_session(&self, session_id: &str) {
+        self.session_bindings.lock().remove(session_id);
+    }
+
+    /// 获取指定凭证的刷新锁（不存在时创建）
+    fn refresh_lock(&self, id: u64) -> Arc<TokioMutex<()>> {
+        self.refresh_locks.lock().entry(id).or_default().clone()
+    }
+
+    /// 尝试使用指定凭证获取有效 Token
+    ///
+    /// 使用双重检查锁定模式，确保同一凭证同一时间只有一个刷新操作
+    ///
+    /// # Arguments
+    /// * `id` - 凭证 ID，用于更新正确的条目
+    /// * `credentials` - 凭证信息
+    async fn try_ensure_token(
+        &self,
+        id: u64,
+        credentials: &KiroCredentials,
+    ) -> anyhow::Result<CallContext> {
+        // 第一次检查（无锁）：快速判断是否需要刷新
+        let needs_refresh = self.entry_needs_refresh(id);
+
+        let creds = if needs_refresh {
+            // 获取该凭证的刷新锁，确保同一凭证同一时间只有一个刷新操作
+            let lock = self.refresh_lock(id);
+            let _guard = lock.lock().await;
+
+            // 第二次检查：获取锁后重新读取凭证，因为其他请求可能已经完成刷新
+            let current_creds = {
+                let entries = self.entries.lock();
+                entries
+                    .iter()
+                    .find(|e| e.id == id)
+                    .map(|e| e.credentials.clone())
+                    .ok_or_else(|| anyhow::anyhow!("凭证 #{} 不存在", id))?
+            };
+
+            if self.entry_needs_refresh(id) {
+                // 确实需要刷新
+                self.begin_refresh_attempt(id)?;
+                let new_creds =
+                    match refresh_token(&current_creds, &self.config, &self.clients_for(&current_creds).refresh).await {
+                        Ok(creds) => creds,
+                        Err(e) => {
+                            record_refresh_failure(&self.entries, id, &e.to_string());
+                            return Err(e);
+                        }
+                    };
+
+                if is_token_expired(&new_creds) {
+                    anyhow::bail!("刷新后的 Token 仍然无效或已过期");
+                }
+
+                // 更新凭证
+                {
+                    let mut entries = self.entries.lock();
+                    if let Some(entry) = entries.iter_mut().find(|e| e.id == id) {
+                        entry.apply_refreshed(new_creds.clone());
+                    }
+                }
+
+                // 回写凭证到文件（仅多凭证格式），失败只记录警告
+                if let Err(e) = self.persist_credentials() {
+                    tracing::warn!("Token 刷新后持久化失败（不影响本次请求）: {}", e);
+                }
+
+                new_creds
+            } else {
+                // 其他请求已经完成刷新，直接使用新凭证
+                tracing::debug!("Token 已被其他请求刷新，跳过刷新");
+                current_creds
+            }
+        } else {
+            credentials.clone()
+        };
+
+        let token = creds
+            .access_token
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("没有可用的 accessToken"))?;
+
+        let machine_id = self
+            .entries
+            .lock()
+            .iter_mut()
+            .find(|e| e.id == id)
+            .and_then(|e| e.machine_id());
+
+        Ok(CallContext {
+            id,
+            credentials: creds,
+            token,
+            machine_id,
+        })
+    }
+
+    /// 将凭证列表回写到源文件
+    ///
+    /// 仅在以下条件满足时回写：
+    /// - 源文件是多凭证格式（数组）
+    /// - credentials_path 已设置
+    ///
+    /// # Returns
+    /// - `Ok(true)` - 成功写入文件
+    /// - `Ok(false)` - 跳过写入（非多凭证格式或无路径配置）
+    /// - `Err(_)` - 写入失败
+    fn persist_credentials(&self) -> anyhow::Result<bool> {
+        use anyhow::Context;
+
+        // 仅多凭证格式才回写
+        if !self.is_multiple_format {
+            return Ok(false);
+        }
+
+        let path = match &self.credentials_path {
+            Some(p) => p,
+            None => return Ok(false),
+        };
+
+        // 收集所有凭证
+        let credentials: Vec<KiroCredentials> = {
+            let entries = self.entries.lock();
+            entries.iter().map(|e| e.credentials.clone()).collect()
+        };
+
+        // 序列化为 pretty JSON
+        let json = serde_json::to_string_pretty(&credentials).context("序列化凭证失败")?;
+
+        // 写入文件（在 Tokio runtime 内使用 block_in_place 避免阻塞 worker）
+        if tokio::runtime::Handle::try_current().is_ok() {
+            tokio::task::block_in_place(|| std::fs::write(path, &json))
+                .with_context(|| format!("回写凭证文件失败: {:?}", path))?;
+        } else {
+            std::fs::write(path, &json).with_context(|| format!("回写凭证文件失败: {:?}", path))?;
+        }
+
+        tracing::debug!("已回写凭证到文件: {:?}", path);
+        Ok(true)
+    }
+
+    /// 报告指定凭证 API 调用成功
+    ///
+    /// 重置该凭证的失败计数
+    ///
+    /// # Arguments
+    /// * `id` - 凭证 ID（来自 CallContext）
+    pub fn report_success(&self, id: u64) {
+        let mut entries = self.entries.lock();
+        if let Some(entry) = entries.iter_mut().find(|e| e.id == id) {
+            entry.failures.clear();
+            entry.rate_limit_strikes = 0;
+            entry.cooldown_until = None;
+            tracing::debug!("凭证 #{} API 调用成功", id);
+        }
+    }
+
+    /// 报告指定凭证被上游限流（429）
+    ///
+    /// 不计入失败次数，只让凭证进入冷却：优先使用上游 Retry-After，
+    /// 否则按连续限流次数指数退避。冷却期间选择凭证时会跳过该凭证
+    ///
+    /// # Returns
+    /// 本次冷却时长
+    pub fn report_rate_limited(
+        &self,
+        id: u64,
+        retry_after: Option<std::time::Duration>,
+    ) -> std::time::Duration {
+        let mut entries = self.entries.lock();
+        let Some(entry) = entries.iter_mut().find(|e| e.id == id) else {
+            return std::time::Duration::ZERO;
+        };
+
+        entry.rate_limit_strikes = entry.rate_limit_strikes.saturating_add(1);
+        let cooldown = match retry_after {
+            Some(d) => d.min(RATE_LIMIT_COOLDOWN_MAX),
+            None => rate_limit_cooldown(entry.rate_limit_strikes),
+        };
+        entry.cooldown_until = Some(Instant::now() + cooldown);
+
+        tracing::warn!(
+            "凭证 #{} 被上游限流（连续 {} 次），冷却 {} 秒",
+            id,
+            entry.rate_limit_strikes,
+            cooldown.as_secs().max(1)
+        );
+        EVENT_BUS.credential_changed(id, CredentialChange::CoolingDown);
+        cooldown
+    }
+
+    /// 设置凭证分组（Admin API）
+    pub fn set_group(&self, id: u64, group_id: &str) -> anyhow::Result<()> {
+        {
+            let mut entries = self.entries.lock();
+            let entry = entries
+                .iter_mut()
+                .find(|e| e.id == id)
+                .ok_or_else(|| anyhow::anyhow!("凭证不存在: {}", id))?;
+            entry.credentials.group_id = group_id.to_string();
+        }
+        // 持久化更改
+        self.persist_credentials()?;
+        Ok(())
+    }
+
+    /// 报告指定凭证 API 调用失败
+    ///
+    /// 按错误类型计数（`failurePolicy`），某一类在计数窗口内达到阈值时禁用凭证并切换到优先级最高的可用凭证；
+    /// 不计数的错误类型只检查可用性
+    /// 返回是否还有可用凭证可以重试
+    ///
+    /// # Arguments
+    /// * `id` - 凭证 ID（来自 CallContext）
+    /// * `class` - 错误类型
+    pub fn report_failure(&self, id: u64, class: FailureClass) -> bool {
+        let mut entries = self.entries.lock();
+        let mut current_id = self.current_id.lock();
+
+        let entry = match entries.iter_mut().find(|e| e.id == id) {
+            Some(e) => e,
+            None => return entries.iter().any(|e| !e.disabled),
+        };
+
+        let rule = class.rule(&self.config.failure_policy);
+        let Some(failure_count) = entry.failures.record(class, &self.config.failure_policy, Instant::now()) else {
+            return entries.iter().any(|e| e.is_available());
+        };
+
+        tracing::warn!(
+            "凭证 #{} API 调用失败 [{}]（{}/{}）",
+            id,
+            class,
+            failure_count,
+            rule.threshold
+        );
+
+        if failure_count >= rule.threshold {
+            entry.disabled = true;
+            entry.disabled_reason = Some(DisabledReason::TooManyFailures);
+            let reason = match rule.decay_mins {
+                0 => format!("API 调用连续失败 {} 次（{}）", failure_count, class),
+                mins => format!("API 调用 {} 分钟内失败 {} 次（{}）", mins, failure_count, class),
+            };
+            tracing::error!("凭证 #{} {}，已被禁用", id, reason);
+            notify_auto_disabled(id, CredentialChange::Disabled, &reason);
+
+            // 切换到 ID 最小的可用凭证
+            if let Some(next) = entries
+                .iter()
+                .filter(|e| e.is_available())
+                .min_by_key(|e| e.id)
+            {
+                *current_id = next.id;
+                tracing::info!(
+                    "已切换到凭证 #{}",
+                    next.id
+                );
+                WEBHOOKS.notify(WebhookEvent::failover(id, next.id, &reason));
+            } else {
+                tracing::error!("所有凭证均已禁用！");
+                return false;
+            }
+        }
+
+        // 检查是否还有可用凭证
+        entries.iter().any(|e| e.is_available())
+    }
+
+    /// 预热备用凭证
+    ///
+    /// 凭证连续失败即将达到禁用阈值时，在后台提前刷新下一个候选凭证的 Token，
+    /// 使随后的故障转移不必在请求路径上等待一次刷新往返
+    pub fn prewarm_standby(self: &Arc<Self>, failing_id: u64) {
+        let Some((id, credentials)) = self.standby_to_prewarm(failing_id) else {
+            return;
+        };
+
+        tracing::info!("凭证 #{} 即将达到失败阈值，后台预刷新备用凭证 #{}", failing_id, id);
+        let manager = Arc::clone(self);
+        tokio::spawn(async move {
+            if let Err(e) = manager.try_ensure_token(id, &credentials).await {
+                tracing::warn!("备用凭证 #{} 预刷新失败: {}", id, e);
+            }
+        });
+    }
+
+    /// 选择需要预热的备用凭证：失败凭证即将达到阈值时，返回活跃分组内下一个需要刷新的候选凭证
+    fn standby_to_prewarm(&self, failing_id: u64) -> Option<(u64, KiroCredentials)> {
+        let entries = self.entries.lock();
+        let now = Instant::now();
+        let approaching_threshold = entries.iter().any(|e| {
+            e.id == failing_id
+                && !e.disabled
+                && e.failures.near_threshold(&self.config.failure_policy, now)
+        });
+        if !approaching_threshold {
+            return None;
+        }
+
+        let active_group = self.active_group_id.lock();
+        self.pick_candidate(entries.iter().filter(|e| {
+            e.id != failing_id
+                && e.is_available()
+                && active_group
+                    .as_ref()
+                    .is_none_or(|g| &e.credentials.group_id == g)
+        }))
+        .filter(|e| e.needs_refresh() && e.refresh_cooldown_remaining().is_none())
+        .map(|e| (e.id, e.credentials.clone()))
+    }
+
+    /// 报告指定凭证 API 调用失败（带错误消息）
+    ///
+    /// 与 report_failure 类似，但会检测错误消息：
+    /// - 如果是账户暂停/凭证无效错误，立即禁用凭证
+    /// - 否则按状态码对应的错误类型计数
+    ///
+    /// # Arguments
+    /// * `id` - 凭证 ID
+    /// * `error` - 解析后的上游错误响应
+    ///
+    /// # Returns
+    /// 是否还有可用凭证
+    pub fn report_failure_with_error(&self, id: u64, error: &UpstreamError) -> bool {
+        // 检测是否为凭证无效/被暂停的错误
+        if error.is_credential_invalid() {
+            let mut entries = self.entries.lock();
+            let mut current_id = self.current_id.lock();
+            
+            if let Some(entry) = entries.iter_mut().find(|e| e.id == id) {
+                entry.disabled = true;
+                entry.disabled_reason = Some(DisabledReason::Suspended);
+                entry.credentials.status = "invalid".to_string();
+                tracing::error!(
+                    "凭证 #{} 已被自动禁用（账户暂停/无效）",
+                    id
+                );
+                notify_auto_disabled(id, CredentialChange::Suspended, &error.body);
+                
+                // 切换到 ID 最小的可用凭证
+                if let Some(next) = entries.iter().filter(|e| e.is_available()).min_by_key(|e| e.id) {
+                    *current_id = next.id;
+                    tracing::info!("已切换到凭证 #{}", next.id);
+                    WEBHOOKS.notify(WebhookEvent::failover(id, next.id, "账户暂停/无效"));
+                } else {
+                    tracing::error!("所有凭证均已禁用！");
+                }
+                
+                // 释放锁后持久化
+                drop(current_id);
+                drop(entries);
+                if let Err(e) = self.persist_credentials() {
+                    tracing::warn!("凭证禁用后持久化失败: {}", e);
+                }
+            }
+            
+            return self.entries.lock().iter().any(|e| e.is_available());
+        }
+        
+        // 普通失败处理
+        self.report_failure(id, FailureClass::from_status(error.status))
+    }
+
+    /// 报告指定凭证额度用尽
+    ///
+    /// 禁用凭证直到额度重置（由后台任务按 `quota_reset_at` 自动启用），并切换到其他可用凭证。
+    /// 返回是否还有可用凭证
+    pub fn report_quota_exhausted(&self, id: u64) -> bool {
+        let mut entries = self.entries.lock();
+        let mut current_id = self.current_id.lock();
+
+        if let Some(entry) = entries.iter_mut().find(|e| e.id == id) {
+            let reset_at = quota_reset_time(&entry.credentials, Utc::now());
+            entry.disabled = true;
+            entry.disabled_reason = Some(DisabledReason::QuotaExhausted);
+            entry.quota_reset_at = Some(reset_at);
+            tracing::warn!("凭证 #{} 额度已用尽，将于 {} 自动启用", id, reset_at.to_rfc3339());
+            let reason = format!("额度已用尽，将于 {} 自动启用", reset_at.to_rfc3339());
+            notify_auto_disabled(id, CredentialChange::Disabled, &reason);
+
+            if *current_id == id {
+                if let Some(next) = entries.iter().filter(|e| e.is_available()).min_by_key(|e| e.id) {
+                    *current_id = next.id;
+                    tracing::info!("已切换到凭证 #{}", next.id);
+                    WEBHOOKS.notify(WebhookEvent::failover(id, next.id, "额度已用尽"));
+                }
+            }
+        }
+
+        entries.iter().any(|e| e.is_available())
+    }
+
+    /// 重新启用额度已重置的凭证，返回启用的凭证 ID
+    pub fn reenable_quota_reset(&self, now: DateTime<Utc>) -> Vec<u64> {
+        let mut reenabled = Vec::new();
+        for entry in self.entries.lock().iter_mut() {
+            if entry.disabled_reason != Some(DisabledReason::QuotaExhausted)
+                || entry.quota_reset_at.is_some_and(|at| at > now)
+            {
+                continue;
+            }
+            entry.disabled = false;
+            entry.disabled_reason = None;
+            entry.quota_reset_at = None;
+            entry.failures.clear();
+            reenabled.push(entry.id);
+        }
+        for &id in &reenabled {
+            tracing::info!("凭证 #{} 额度已重置，重新启用", id);
+            EVENT_BUS.credential_changed(id, CredentialChange::Enabled);
+        }
+        reenabled
+    }
+
+    /// 切换到下一个可用凭证（按列表顺序轮询）
+    ///
+    /// 返回是否成功切换
+    pub fn switch_to_next(&self) -> bool {
+        let entries = self.entries.lock();
+        let mut current_id = self.current_id.lock();
+        let active_group = self.active_group_id.lock();
+
+        // 分组过滤闭包
+        let in_group = |cred: &KiroCredentials| -> bool {
+            match active_group.as_ref() {
+                None => true,
+                Some(group_id) => &cred.group_id == group_id,
+            }
+        };
+
+        // 收集分组内可用的凭证
+        let available: Vec<_> = entries
+            .iter()
+            .filter(|e| e.is_available() && in_group(&e.credentials))
+            .collect();
+        
+        if available.is_empty() {
+            tracing::warn!("没有可用的凭证");
+            return false;
+        }
+        
+        if available.len() == 1 {
+            // 只有一个凭证，无法切换
+            tracing::info!("只有一个可用凭证，无法切换");
+            return false;
+        }
+
+        // 找到当前凭证在可用列表中的位置，然后选择下一个
+        let current_available_pos = available.iter().position(|e| e.id == *current_id);
+        
+        let next = if let Some(pos) = current_available_pos {
+            // 循环到下一个
+            let next_pos = (pos + 1) % available.len();
+            available[next_pos]
+        } else {
+            // 当前凭证不在可用列表中，选择第一个
+            available[0]
+        };
+
+        *current_id = next.id;
+        tracing::info!(
+            "已切换到凭证 #{}（顺序轮询）",
+            next.id,
+        );
+        true
+    }
+
+    /// 获取使用额度信息
+    pub async fn get_usage_limits(&self) -> anyhow::Result<UsageLimitsResponse> {
+        let ctx = self.acquire_context().await?;
+        get_usage_limits(
+            &ctx.credentials,
+            &self.config,
+            &ctx.token,
+            &self.clients_for(&ctx.credentials).usage,
+        )
+        .await
+    }
+
+    /// 刷新所有凭证的 Token
+    /// 
+    /// 返回成功刷新的凭证数量（10 并发）
+    pub async fn refresh_all_credentials(&self) -> anyhow::Result<usize> {
+        use futures::stream::{self, StreamExt};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        
+        let credentials_to_refresh: Vec<(u64, KiroCredentials)> = {
+            let mut entries = self.entries.lock();
+            let now = Instant::now();
+            entries
+                .iter_mut()
+                .filter(|e| !e.disabled && e.refresh_cooldown_remaining().is_none())
+                .map(|e| {
+                    e.last_refresh_attempt = Some(now);
+                    (e.id, e.credentials.clone())
+                })
+                .collect()
+        };
+
+        if credentials_to_refresh.is_empty() {
+            return Ok(0);
+        }
+
+        let refreshed_count = Arc::new(AtomicUsize::new(0));
+        let config = self.config.clone();
+        let entries_ref = &self.entries;
+        
+        // 10 并发刷新
+        stream::iter(credentials_to_refresh)
+            .for_each_concurrent(10, |(id, credentials)| {
+                let config = config.clone();
+                let client = self.clients_for(&credentials).refresh;
+                let refreshed_count = refreshed_count.clone();
+                
+                async move {
+                    match refresh_token(&credentials, &config, &client).await {
+                        Ok(new_creds) => {
+                            let mut entries = entries_ref.lock();
+                            if let Some(entry) = entries.iter_mut().find(|e| e.id == id) {
+                                entry.apply_refreshed(new_creds);
+                                refreshed_count.fetch_add(1, Ordering::SeqCst);
+                                tracing::debug!("凭证 #{} Token 已刷新", id);
+                            }
+                        }
+                        Err(e) => {
+                            let error_msg = e.to_string();
+                            tracing::warn!("凭证 #{} Token 刷新失败: {}", id, error_msg);
+                            record_refresh_failure(entries_ref, id, &error_msg);
+                            
+                            // 检测是否为凭证无效/被暂停的错误
+                            if is_credential_invalid_error(&e) {
+                                let mut entries = entries_ref.lock();
+                                if let Some(entry) = entries.iter_mut().find(|e| e.id == id) {
+                                    entry.disabled = true;
+                                    entry.disabled_reason = Some(DisabledReason::Suspended);
+                                    entry.credentials.status = "invalid".to_string();
+                                    tracing::error!(
+                                        "凭证 #{} 已被自动禁用（账户暂停/无效）: {}",
+                                        id,
+                                        error_msg
+                                    );
+                                    notify_auto_disabled(id, CredentialChange::Suspended, &error_msg);
+                                }
+                            }
+                        }
+                    }
+                }
+            })
+            .await;
+
+        let count = refreshed_count.load(Ordering::SeqCst);
+        
+        // 持久化凭证（无论成功还是有凭证被禁用都需要持久化）
+        if let Err(e) = self.persist_credentials() {
+            tracing::warn!("刷新后持久化失败: {}", e);
+        }
+
+        Ok(count)
+    }
+
+    // ========================================================================
+    // Admin API 方法
+    // ========================================================================
+
+    /// 获取管理器状态快照（用于 Admin API）
+    pub fn snapshot(&self) -> ManagerSnapshot {
+        let entries = self.entries.lock();
+        let current_id = *self.current_id.lock();
+        let available = entries.iter().filter(|e| e.is_available()).count();
+        let now = Instant::now();
+
+        ManagerSnapshot {
+            entries: entries
+                .iter()
+                .map(|e| CredentialEntrySnapshot {
+                    id: e.id,
+                    disabled: e.disabled,
+                    failure_count: e.failures.total(&self.config.failure_policy, now),
+                    cooldown_secs: e.cooldown_remaining().map(|d| d.as_secs().max(1)),
+                    auth_method: e.credentials.auth_method.clone(),
+                    has_profile_arn: e.credentials.profile_arn.is_some(),
+                    expires_at: e.credentials.expires_at.clone(),
+                    email: e.credentials.email.clone(),
+                    subscription_title: e.credentials.subscription_title.clone(),
+                    current_usage: e.credentials.current_usage,
+                    usage_limit: e.credentials.usage_limit,
+                    remaining: e.credentials.remaining,
+                    next_reset_at: e.credentials.next_reset_at,
+                    refresh_token: e.credentials.refresh_token.clone(),
+                    access_token: e.credentials.access_token.clone(),
+                    profile_arn: e.credentials.profile_arn.clone(),
+                    status: e.credentials.status.clone(),
+                    group_id: e.credentials.group_id.clone(),
+                    proxy_url: e.credentials.proxy_url.clone(),
+                    kiro_version: e.credentials.kiro_version.clone(),
+                    system_version: e.credentials.system_version.clone(),
+                    node_version: e.credentials.node_version.clone(),
+                    machine_id_salt: e.credentials.machine_id_salt.clone(),
+                    last_health_check: e.last_health_check.clone(),
+                    quota_reset_at: e
+                        .quota_reset_at
+                        .filter(|_| e.disabled_reason == Some(DisabledReason::QuotaExhausted))
+                        .map(|at| at.to_rfc3339()),
+                    request_stats: CREDENTIAL_STATS.stats(e.id),
+                })
+                .collect(),
+            current_id,
+            total: entries.len(),
+            available,
+        }
+    }
+
+    /// 设置凭证禁用状态（Admin API）
+    pub fn set_disabled(&self, id: u64, disabled: bool) -> anyhow::Result<()> {
+        {
+            let mut entries = self.entries.lock();
+            let entry = entries
+                .iter_mut()
+                .find(|e| e.id == id)
+                .ok_or_else(|| anyhow::anyhow!("凭证不存在: {}", id))?;
+            entry.disabled = disabled;
+            if !disabled {
+                // 启用时重置失败计数
+                entry.failures.clear();
+                entry.disabled_reason = None;
+            } else {
+                entry.disabled_reason = Some(DisabledReason::Manual);
+            }
+        }
+        let change = if disabled {
+            CredentialChange::Disabled
+        } else {
+            CredentialChange::Enabled
+        };
+        EVENT_BUS.credential_changed(id, change);
+        // 持久化更改
+        self.persist_credentials()?;
+        Ok(())
+    }
+
+    /// 标记凭证为暂停/无效状态
+    /// 
+    /// 用于自动检测到凭证无效（如 TEMPORARILY_SUSPENDED）时禁用凭证
+    pub fn mark_as_suspended(&self, id: u64) -> anyhow::Result<()> {
+        {
+            let mut entries = self.entries.lock();
+            let entry = entries
+                .iter_mut()
+                .find(|e| e.id == id)
+                .ok_or_else(|| anyhow::anyhow!("凭证不存在: {}", id))?;
+            entry.disabled = true;
+            entry.disabled_reason = Some(DisabledReason::Suspended);
+            entry.credentials.status = "invalid".to_string();
+            tracing::error!("凭证 #{} 已被标记为暂停/无效", id);
+        }
+        notify_auto_disabled(id, CredentialChange::Suspended, "账户暂停/无效");
+        // 持久化更改
+        self.persist_credentials()?;
+        Ok(())
+    }
+
+    /// 重置凭证失败计数并重新启用（Admin API）
+    pub fn reset_and_enable(&self, id: u64) -> anyhow::Result<()> {
+        {
+            let mut entries = self.entries.lock();
+            let entry = entries
+                .iter_mut()
+                .find(|e| e.id == id)
+                .ok_or_else(|| anyhow::anyhow!("凭证不存在: {}", id))?;
+            entry.failures.clear();
+            entry.disabled = false;
+            entry.disabled_reason = None;
+            entry.rate_limit_strikes = 0;
+            entry.cooldown_until = None;
+            // 如果凭证状态是 invalid（被暂停导致），恢复为 normal
+            if entry.credentials.status == "invalid" {
+                entry.credentials.status = "normal".to_string();
+            }
+        }
+        EVENT_BUS.credential_changed(id, CredentialChange::Enabled);
+        // 持久化更改
+        self.persist_credentials()?;
+        Ok(())
+    }
+
+    /// 为凭证重新生成随机 machineId 并持久化（Admin API）
+    pub fn regenerate_machine_id(&self, id: u64) -> anyhow::Result<String> {
+        let machine_id = machine_id::generate_random();
+        {
+            let mut entries = self.entries.lock();
+            let entry = entries
+                .iter_mut()
+                .find(|e| e.id == id)
+                .ok_or_else(|| anyhow::anyhow!("凭证不存在: {}", id))?;
+            entry.credentials.machine_id = Some(machine_id.clone());
+            entry.machine_id_cache = None;
+        }
+        self.persist_credentials()?;
+        tracing::info!("凭证 #{} 已重新生成 machineId", id);
+        EVENT_BUS.credential_changed(id, CredentialChange::Updated);
+        Ok(machine_id)
+    }
+
+    /// 更新凭证状态（Admin API）
+    pub fn update_status(&self, id: u64, status: &str) -> anyhow::Result<()> {
+        {
+            let mut entries = self.entries.lock();
+            let entry = entries
+                .iter_mut()
+                .find(|e| e.id == id)
+                .ok_or_else(|| anyhow::anyhow!("凭证不存在: {}", id))?;
+            entry.credentials.status = status.to_string();
+        }
+        // 持久化更改
+        self.persist_credentials()?;
+        Ok(())
+    }
+
+    /// 刷新指定凭证的 Token（Admin API）
+    pub async fn refresh_token_for(&self, id: u64) -> anyhow::Result<()> {
+        let credentials = {
+            let entries = self.entries.lock();
+            entries
+                .iter()
+                .find(|e| e.id == id)
+                .map(|e| e.credentials.clone())
+                .ok_or_else(|| anyhow::anyhow!("凭证不存在: {}", id))?
+        };
+
+        // 手动刷新不受最小间隔限制，但仍记录尝试时间
+        if let Some(entry) = self.entries.lock().iter_mut().find(|e| e.id == id) {
+            entry.last_refresh_attempt = Some(Instant::now());
+        }
+
+        // 刷新 Token
+        let new_credentials = match refresh_token(&credentials, &self.config, &self.clients_for(&credentials).refresh).await {
+            Ok(creds) => creds,
+            Err(e) => {
+                record_refresh_failure(&self.entries, id, &e.to_string());
+                return Err(e);
+            }
+        };
+
+        // 更新凭证（刷新成功，状态设为 normal）
+        {
+            let mut entries = self.entries.lock();
+            if let Some(entry) = entries.iter_mut().find(|e| e.id == id) {
+                entry.token_deadline = monotonic_deadline(&new_credentials);
+                entry.refresh_failures = 0;
+                entry.credentials.access_token = new_credentials.access_token;
+                entry.credentials.expires_at = new_credentials.expires_at;
+                entry.credentials.profile_arn = new_credentials.profile_arn.or(entry.credentials.profile_arn.clone());
+                entry.credentials.status = "normal".to_string();
+            }
+        }
+
+        // 持久化更改
+        self.persist_credentials()?;
+        Ok(())
+    }
+
+    /// 刷新所有未禁用凭证的剩余额度（供 leastUsage 路由使用），返回成功数量
+    pub async fn refresh_all_usage(&self) -> usize {
+        use futures::stream::{self, StreamExt};
+
+        let ids: Vec<u64> = self
+            .entries
+            .lock()
+            .iter()
+            .filter(|e| !e.disabled)
+            .map(|e| e.id)
+            .collect();
+
+        // 5 并发查询
+        stream::iter(ids)
+            .map(|id| async move { (id, self.get_usage_limits_for(id).await) })
+            .buffer_unordered(5)
+            .filter_map(|(id, result)| async move {
+                match result {
+                    Ok(_) => Some(id),
+                    Err(e) => {
+                        tracing::warn!("凭证 #{} 额度刷新失败: {}", id, e);
+                        None
+                    }
+                }
+            })
+            .count()
+            .await
+    }
+
+    /// 需要后台预刷新的凭证：未禁用、Token 已进入 10 分钟过期窗口且不在刷新冷却中
+    fn expiring_tokens(&self) -> Vec<(u64, KiroCredentials)> {
+        self.entries
+            .lock()
+            .iter()
+            .filter(|e| {
+                !e.disabled
+                    && e.credentials.refresh_token.as_deref().is_some_and(|t| !t.is_empty())
+                    && e.needs_refresh()
+                    && e.refresh_cooldown_remaining().is_none()
+            })
+            .map(|e| (e.id, e.credentials.clone()))
+            .collect()
+    }
+
+    /// 后台预刷新即将过期的 Token，使请求路径上的 `acquire_context` 几乎不必同步刷新，返回成功数量
+    ///
+    /// 与请求路径共用按凭证划分的刷新锁，同一凭证不会重复刷新
+    pub async fn refresh_expiring_tokens(&self) -> usize {
+        use futures::stream::{self, StreamExt};
+
+        // 5 并发刷新
+        stream::iter(self.expiring_tokens())
+            .map(|(id, credentials)| async move { (id, self.try_ensure_token(id, &credentials).await) })
+            .buffer_unordered(5)
+            .filter_map(|(id, result)| async move {
+                match result {
+                    Ok(_) => Some(id),
+                    Err(e) => {
+                        tracing::warn!("凭证 #{} Token 预刷新失败: {}", id, e);
+                        None
+                    }
+                }
+            })
+            .count()
+            .await
+    }
+
+    /// 健康检查：对除手动禁用外的所有凭证调用 getUsageLimits 探测，返回 (健康数, 检查数)
+    ///
+    /// 探测失败的凭证标记为不健康（账户暂停等确定性错误会被自动禁用），
+    /// 自动禁用的凭证探测成功后重新启用
+    pub async fn run_health_checks(&self) -> (usize, usize) {
+        use futures::stream::{self, StreamExt};
+
+        let targets: Vec<(u64, bool)> = self
+            .entries
+            .lock()
+            .iter()
+            .filter(|e| e.disabled_reason != Some(DisabledReason::Manual))
+            .map(|e| (e.id, e.disabled))
+            .collect();
+        let checked = targets.len();
+
+        // 5 并发探测
+        let healthy = stream::iter(targets)
+            .map(|(id, was_disabled)| async move {
+                let started = Instant::now();
+                let result = self.get_usage_limits_for(id).await.map(|_| ()).map_err(|e| e.to_string());
+                self.record_health_check(id, was_disabled, started.elapsed(), result)
+            })
+            .buffer_unordered(5)
+            .filter(|healthy| futures::future::ready(*healthy))
+            .count()
+            .await;
+
+        (healthy, checked)
+    }
+
+    /// 记录健康检查结果，处理自动禁用/恢复，返回是否健康
+    fn record_health_check(
+        &self,
+        id: u64,
+        was_disabled: bool,
+        latency: std::time::Duration,
+        result: Result<(), String>,
+    ) -> bool {
+        let healthy = result.is_ok();
+        let mut change = None;
+        {
+            let mut entries = self.entries.lock();
+            let Some(entry) = entries.iter_mut().find(|e| e.id == id) else {
+                return false;
+            };
+            match &result {
+                Ok(()) if entry.disabled
+                    && !matches!(
+                        entry.disabled_reason,
+                        Some(DisabledReason::Manual | DisabledReason::QuotaExhausted)
+                    ) =>
+                {
+                    entry.disabled = false;
+                    entry.disabled_reason = None;
+                    entry.failures.clear();
+                    if entry.credentials.status == "invalid" {
+                        entry.credentials.status = "normal".to_string();
+                    }
+                    tracing::info!("[健康检查] 凭证 #{} 已恢复，重新启用", id);
+                    change = Some(CredentialChange::Enabled);
+                }
+                Err(e) => {
+                    tracing::warn!("[健康检查] 凭证 #{} 探测失败: {}", id, e);
+                    if !was_disabled && entry.disabled {
+                        change = Some(CredentialChange::Suspended);
+                    }
+                }
+                _ => {}
+            }
+            entry.last_health_check = Some(HealthCheckResult {
+                checked_at: Utc::now().to_rfc3339(),
+                latency_ms: latency.as_millis() as u64,
+                healthy,
+                error: result.err(),
+            });
+        }
+
+        if let Some(change) = change {
+            EVENT_BUS.credential_changed(id, change);
+            if change == CredentialChange::Enabled {
+                if let Err(e) = self.persist_credentials() {
+                    tracing::warn!("健康检查恢复凭证后持久化失败: {}", e);
+                }
+            }
+        }
+        healthy
+    }
+
+    /// 分组内的凭证是否全部耗尽（余额用尽或已禁用）
+    fn group_exhausted(&self, group_id: &str) -> bool {
+        let entries = self.entries.lock();
+        let mut members = entries
+            .iter()
+            .filter(|e| e.credentials.group_id == group_id)
+            .peekable();
+        members.peek().is_some()
+            && members.all(|e| e.disabled || e.credentials.remaining.is_some_and(|r| r <= 0.0))
+    }
+
+    /// 获取指定凭证的使用额度（Admin API）
+    pub async fn get_usage_limits_for(&self, id: u64) -> anyhow::Result<UsageLimitsResponse> {
+        let credentials = {
+            let entries = self.entries.lock();
+            entries
+                .iter()
+                .find(|e| e.id == id)
+                .map(|e| e.credentials.clone())
+                .ok_or_else(|| anyhow::anyhow!("凭证不存在: {}", id))?
+        };
+
+        // 检查是否需要刷新 token
+        let needs_refresh = self.entry_needs_refresh(id);
+
+        let token = if needs_refresh {
+            let lock = self.refresh_lock(id);
+            let _guard = lock.lock().await;
+            let current_creds = {
+                let entries = self.entries.lock();
+                entries
+                    .iter()
+                    .find(|e| e.id == id)
+                    .map(|e| e.credentials.clone())
+                    .ok_or_else(|| anyhow::anyhow!("凭证不存在: {}", id))?
+            };
+
+            if self.entry_needs_refresh(id) {
+                self.begin_refresh_attempt(id)?;
+                match refresh_token(&current_creds, &self.config, &self.clients_for(&current_creds).refresh).await {
+                    Ok(new_creds) => {
+                        {
+                            let mut entries = self.entries.lock();
+                            if let Some(entry) = entries.iter_mut().find(|e| e.id == id) {
+                                entry.apply_refreshed(new_creds.clone());
+                            }
+                        }
+                        // 持久化失败只记录警告，不影响本次请求
+                        if let Err(e) = self.persist_credentials() {
+                            tracing::warn!("Token 刷新后持久化失败（不影响本次请求）: {}", e);
+                        }
+                        new_creds
+                            .access_token
+                            .ok_or_else(|| anyhow::anyhow!("刷新后无 access_token"))?
+                    }
+                    Err(e) => {
+                        let error_msg = e.to_string();
+                        record_refresh_failure(&self.entries, id, &error_msg);
+                        // 检测是否为凭证无效/被暂停的错误
+                        if is_credential_invalid_error(&e) {
+                            let mut entries = self.entries.lock();
+                            if let Some(entry) = entries.iter_mut().find(|e| e.id == id) {
+                                entry.disabled = true;
+                                entry.disabled_reason = Some(DisabledReason::Suspended);
+                                entry.credentials.status = "invalid".to_string();
+                                tracing::error!(
+                                    "凭证 #{} 已被自动禁用（账户暂停/无效）: {}",
+                                    id,
+                                    error_msg
+                                );
+                                notify_auto_disabled(id, CredentialChange::Suspended, &error_msg);
+                            }
+                            drop(entries);
+                            let _ = self.persist_credentials();
+                        }
+                        return Err(e);
+                    }
+                }
+            } else {
+                current_creds
+                    .access_token
+                    .ok_or_else(|| anyhow::anyhow!("凭证无 access_token"))?
+            }
+        } else {
+            credentials
+                .access_token
+                .ok_or_else(|| anyhow::anyhow!("凭证无 access_token"))?
+        };
+
+        let credentials = {
+            let entries = self.entries.lock();
+            entries
+                .iter()
+                .find(|e| e.id == id)
+                .map(|e| e.credentials.clone())
+                .ok_or_else(|| anyhow::anyhow!("凭证不存在: {}", id))?
+        };
+
+        let usage = match get_usage_limits(&credentials, &self.config, &token, &self.clients_for(&credentials).usage).await {
+            Ok(u) => u,
+            Err(e) => {
+                let error_msg = e.to_string();
+                // 检测是否为凭证无效/被暂停的错误
+                if is_credential_invalid_error(&e) {
+                    let mut entries = self.entries.lock();
+                    if let Some(entry) = entries.iter_mut().find(|e| e.id == id) {
+                        entry.disabled = true;
+                        entry.disabled_reason = Some(DisabledReason::Suspended);
+                        entry.credentials.status = "invalid".to_string();
+                        tracing::error!(
+                            "凭证 #{} 已被自动禁用（账户暂停/无效）: {}",
+                            id,
+                            error_msg
+                        );
+                        notify_auto_disabled(id, CredentialChange::Suspended, &error_msg);
+                    }
+                    drop(entries);
+                    let _ = self.persist_credentials();
+                }
+                return Err(e);
+            }
+        };
+        
+        // 更新凭证的缓存信息（email、subscription、余额）
+        let email = usage.email().map(|s| s.to_string());
+        let subscription_title = usage.subscription_title().map(|s| s.to_string());
+        let current_usage = usage.current_usage();
+        let usage_limit_val = usage.usage_limit();
+        let remaining = (usage_limit_val - current_usage).max(0.0);
+        let next_reset_at = usage.next_date_reset;
+        
+        {
+            let mut entries = self.entries.lock();
+            if let Some(entry) = entries.iter_mut().find(|e| e.id == id) {
+                let mut changed = false;
+                if email.is_some() && entry.credentials.email != email {
+                    entry.credentials.email = email;
+                    changed = true;
+                }
+                if subscription_title.is_some() && entry.credentials.subscription_title != subscription_title {
+                    entry.credentials.subscription_title = subscription_title;
+                    changed = true;
+                }
+                // 更新余额信息
+                entry.credentials.current_usage = Some(current_usage);
+                entry.credentials.usage_limit = Some(usage_limit_val);
+                entry.credentials.remaining = Some(remaining);
+                entry.credentials.next_reset_at = next_reset_at;
+                changed = true;
+                
+                if changed {
+                    drop(entries);
+                    if let Err(e) = self.persist_credentials() {
+                        tracing::warn!("更新缓存信息后持久化失败: {}", e);
+                    }
+                }
+            }
+        }
+        USAGE_HISTORY.record(id, current_usage, usage_limit_val);
+        ALERTS.check_quota(id, usage.email(), remaining, usage_limit_val);
+        let group_id = &credentials.group_id;
+        ALERTS.check_group(group_id, self.group_exhausted(group_id));
+
+        Ok(usage)
+    }
+
+    /// 查找与 refresh_token 重复的已有凭证（基于前 50 字符）
+    pub fn find_duplicate(&self, refresh_token: &str) -> Option<u64> {
+        let prefix: String = refresh_token.chars().take(50).collect();
+        self.entries
+            .lock()
+            .iter()
+            .find(|entry| {
+                entry
+                    .credentials
+                    .refresh_token
+                    .as_ref()
+                    .is_some_and(|t| t.chars().take(50).collect::<String>() == prefix)
+            })
+            .map(|entry| entry.id)
+    }
+
+    /// 验证凭证但不添加（Admin API 批量筛选）
+    ///
+    /// 刷新 Token 后查询额度，不修改已有凭证、不持久化。返回刷新后的凭证和额度查询结果：
+    /// 刷新失败说明凭证无效，额度查询失败时凭证本身仍可能有效
+    pub async fn validate_credential(
+        &self,
+        cred: &KiroCredentials,
+    ) -> anyhow::Result<(KiroCredentials, anyhow::Result<UsageLimitsResponse>)> {
+        let clients = self.clients_for(cred);
+        let refreshed = refresh_token(cred, &self.config, &clients.refresh).await?;
+        let usage = match refreshed.access_token.as_deref() {
+            Some(token) => get_usage_limits(&refreshed, &self.config, token, &clients.usage).await,
+            None => Err(anyhow::anyhow!("刷新后无 access_token")),
+        };
+        Ok((refreshed, usage))
+    }
+
+    /// 添加新凭证（Admin API）
+    ///
+    /// # 流程
+    /// 1. 验证凭证基本字段（refresh_token 不为空）
+    /// 2. 检查重复（refresh_token 已存在则跳过）
+    /// 3. 尝试刷新 Token 验证凭证有效性
+    /// 4. 分配新 ID（当前最大 ID + 1）
+    /// 5. 添加到 entries 列表
+    /// 6. 持久化到配置文件
+    ///
+    /// # 返回
+    /// - `Ok(u64)` - 新凭证 ID
+    /// - `Err(_)` - 验证失败或添加失败
+    pub async fn add_credential(&self, mut new_cred: KiroCredentials) -> anyhow::Result<u64> {
+        // 1. 基本验证
+        validate_refresh_token(&new_cred)?;
+        // 新凭证固定使用随机生成的 machineId，与其他账号区分
+        if new_cred.machine_id.as_deref().is_none_or(|id| !machine_id::is_valid(id)) {
+            new_cred.machine_id = Some(machine_id::generate_random());
+        }
+
+        // 2. 检查重复（基于 refresh_token 前 50 字符）
+        if let Some(existing_id) = self.find_duplicate(new_cred.refresh_token.as_deref().unwrap()) {
+            anyhow::bail!("凭证已存在（与凭证 #{} 重复）", existing_id);
+        }
+
+        // 3. 尝试刷新 Token 验证凭证有效性
+        let mut validated_cred =
+            refresh_token(&new_cred, &self.config, &self.clients_for(&new_cred).refresh).await?;
+
+
+        // 4. 分配新 ID（找最小可用 ID，从 1 开始，复用已删除的 ID）
+        let new_id = {
+            let entries = self.entries.lock();
+            let used_ids: std::collections::HashSet<u64> = entries.iter().map(|e| e.id).collect();
+            // 从 1 开始找第一个未使用的 ID
+            let mut id = 1u64;
+            while used_ids.contains(&id) {
+                id += 1;
+            }
+            id
+        };
+
+        // 5. 设置 ID 并保留用户输入的元数据
+        validated_cred.id = Some(new_id);
+        validated_cred.auth_method = new_cred.auth_method;
+        validated_cred.client_id = new_cred.client_id;
+        validated_cred.client_secret = new_cred.client_secret;
+
+        {
+            let token_deadline = monotonic_deadline(&validated_cred);
+            let mut entries = self.entries.lock();
+            entries.push(CredentialEntry {
+                id: new_id,
+                credentials: validated_cred,
+                failures: FailureTracker::default(),
+                disabled: false,
+                disabled_reason: None,
+                machine_id_cache: None,
+                token_deadline,
+                last_refresh_attempt: Some(Instant::now()),
+                refresh_failures: 0,
+                cooldown_until: None,
+                rate_limit_strikes: 0,
+                last_health_check: None,
+            quota_reset_at: None,
+            });
+        }
+
+        // 6. 持久化
+        self.persist_credentials()?;
+
+        tracing::info!("成功添加凭证 #{}", new_id);
+        EVENT_BUS.credential_changed(new_id, CredentialChange::Added);
+
+        // 7. 获取余额信息（异步，不影响添加结果）
+        // 这会在后台更新 email、subscription、balance 等信息
+        if let Err(e) = self.get_usage_limits_for(new_id).await {
+            tracing::warn!("添加凭证 #{} 后获取余额失败: {}", new_id, e);
+        }
+
+        Ok(new_id)
+    }
+
+    /// 编辑凭证（Admin API）
+    ///
+    /// `updated` 为编辑后的完整凭证。refreshToken、认证方式、OIDC Client 或代理发生变化时，
+    /// 先用新配置刷新 Token 验证有效，验证失败则不做任何修改；验证通过后丢弃旧的 Token、
+    /// machineId 缓存和失败计数，被自动禁用的凭证重新启用（手动禁用的保持禁用）
+    pub async fn update_credential(&self, id: u64, mut updated: KiroCredentials) -> anyhow::Result<()> {
+        let current = self
+            .entries
+            .lock()
+            .iter()
+            .find(|e| e.id == id)
+            .map(|e| e.credentials.clone())
+            .ok_or_else(|| anyhow::anyhow!("凭证不存在: {}", id))?;
+
+        let token_changed = updated.refresh_token != current.refresh_token;
+        let auth_changed = token_changed
+            || updated.auth_method != current.auth_method
+            || updated.client_id != current.client_id
+            || updated.client_secret != current.client_secret;
+        let proxy_changed = updated.proxy_url != current.proxy_url
+            || updated.proxy_username != current.proxy_username
+            || updated.proxy_password != current.proxy_password;
+
+        if let Some(proxy) = updated.proxy() {
+            self.proxy_http_clients(&proxy)
+                .map_err(|e| anyhow::anyhow!("代理地址无效: {}", e))?;
+        }
+
+        if token_changed {
+            validate_refresh_token(&updated)?;
+            let duplicate = self
+                .entries
+                .lock()
+                .iter()
+                .filter(|e| e.id != id)
+                .find(|e| {
+                    let prefix = |t: &str| t.chars().take(50).collect::<String>();
+                    match (&e.credentials.refresh_token, &updated.refresh_token) {
+                        (Some(a), Some(b)) => prefix(a) == prefix(b),
+                        _ => false,
+                    }
+                })
+                .map(|e| e.id);
+            if let Some(existing_id) = duplicate {
+                bail!("凭证已存在（与凭证 #{} 重复）", existing_id);
+            }
+        }
+
+        let revalidate = auth_changed || proxy_changed;
+        if revalidate {
+            // 旧 Token 作废，用新配置重新获取
+            updated.access_token = None;
+            updated.expires_at = None;
+            let refreshed = refresh_token(&updated, &self.config, &self.clients_for(&updated).refresh).await?;
+            updated.access_token = refreshed.access_token;
+            updated.expires_at = refreshed.expires_at;
+            updated.refresh_token = refreshed.refresh_token;
+            updated.profile_arn = refreshed.profile_arn.or(updated.profile_arn);
+            updated.status = "normal".to_string();
+        }
+
+        {
+            let mut entries = self.entries.lock();
+            let entry = entries
+                .iter_mut()
+                .find(|e| e.id == id)
+                .ok_or_else(|| anyhow::anyhow!("凭证不存在: {}", id))?;
+            updated.id = Some(id);
+            if revalidate {
+                entry.token_deadline = monotonic_deadline(&updated);
+                entry.last_refresh_attempt = Some(Instant::now());
+                entry.refresh_failures = 0;
+                entry.failures.clear();
+                entry.cooldown_until = None;
+                entry.rate_limit_strikes = 0;
+                entry.machine_id_cache = None;
+                if matches!(
+                    entry.disabled_reason,
+                    Some(DisabledReason::TooManyFailures | DisabledReason::Suspended)
+                ) {
+                    entry.disabled = false;
+                    entry.disabled_reason = None;
+                }
+            }
+            if updated.machine_id != entry.credentials.machine_id
+                || updated.machine_id_salt != entry.credentials.machine_id_salt
+            {
+                entry.machine_id_cache = None;
+            }
+            entry.credentials = updated;
+        }
+
+        self.persist_credentials()?;
+        tracing::info!("已编辑凭证 #{}", id);
+        EVENT_BUS.credential_changed(id, CredentialChange::Updated);
+        Ok(())
+    }
+
+    /// 删除凭证（Admin API）
+    ///
+    /// # 行为
+    /// 1. 验证凭证存在
+    /// 2. 从 entries 移除
+    /// 3. 如果删除的是当前凭证，切换到优先级最高的可用凭证
+    /// 4. 如果删除后没有凭证，将 current_id 重置为 0
+    /// 5. 持久化到文件
+    ///
+    /// # 返回
+    /// - `Ok(())` - 删除成功
+    /// - `Err(_)` - 凭证不存在或持久化失败
+    pub fn delete_credential(&self, id: u64) -> anyhow::Result<()> {
+        let was_current = {
+            let mut entries = self.entries.lock();
+
+            // 查找凭证
+            let _entry = entries
+                .iter()
+                .find(|e| e.id == id)
+                .ok_or_else(|| anyhow::anyhow!("凭证不存在: {}", id))?;
+
+            // 记录是否是当前凭证
+            let current_id = *self.current_id.lock();
+            let was_current = current_id == id;
+
+            // 删除凭证
+            entries.retain(|e| e.id != id);
+            self.refresh_locks.lock().remove(&id);
+
+            was_current
+        };
+
+        // 如果删除的是当前凭证，切换到优先级最高的可用凭证
+        if was_current {
+            self.select_smallest_id();
+        }
+
+        // 如果删除后没有任何凭证，将 current_id 重置为 0（与初始化行为保持一致）
+        {
+            let entries = self.entries.lock();
+            if entries.is_empty() {
+                let mut current_id = self.current_id.lock();
+                *current_id = 0;
+                tracing::info!("所有凭证已删除，current_id 已重置为 0");
+            }
+        }
+
+        // 持久化更改
+        self.persist_credentials()?;
+        USAGE_HISTORY.remove(id);
+        CREDENTIAL_STATS.remove(id);
+
+        tracing::info!("已删除凭证 #{}", id);
+        EVENT_BUS.credential_changed(id, CredentialChange::Deleted);
+        Ok(())
+    }
+
+    /// 合并指向同一账号的重复凭证（Admin API）
+    ///
+    /// 每组保留 ID 最小的条目并换上组内最新的 Token，其余条目删除；`dry_run` 时只返回合并计划。
+    /// Token 来自其他条目时重置保留条目的运行状态（手动禁用保持不变）
+    pub fn dedupe_credentials(&self, dry_run: bool) -> anyhow::Result<Vec<DedupeGroup>> {
+        let plans = {
+            let entries = self.entries.lock();
+            let credentials: Vec<KiroCredentials> = entries
+                .iter()
+                .map(|e| {
+                    let mut cred = e.credentials.clone();
+                    cred.id = Some(e.id);
+                    cred
+                })
+                .collect();
+            dedupe::plan(&credentials)
+        };
+        if dry_run || plans.is_empty() {
+            return Ok(plans.into_iter().map(|p| p.group).collect());
+        }
+
+        let removed: Vec<u64> = plans.iter().flat_map(|p| p.group.removed_ids.iter().copied()).collect();
+        {
+            let mut entries = self.entries.lock();
+            for plan in &plans {
+                let Some(entry) = entries.iter_mut().find(|e| e.id == plan.group.kept_id) else {
+                    continue;
+                };
+                if plan.group.token_from_id == entry.id {
+                    entry.credentials = plan.merged.clone();
+                } else {
+                    let manual = entry.disabled_reason == Some(DisabledReason::Manual);
+                    *entry = CredentialEntry::loaded(entry.id, plan.merged.clone());
+                    if manual {
+                        entry.disabled = true;
+                        entry.disabled_reason = Some(DisabledReason::Manual);
+                    }
+                }
+            }
+            entries.retain(|e| !removed.contains(&e.id));
+        }
+        self.refresh_locks.lock().retain(|id, _| !removed.contains(id));
+        self.session_bindings
+            .lock()
+            .retain(|_, binding| !removed.contains(&binding.credential_id));
+        if removed.contains(&self.current_id()) {
+            self.select_smallest_id();
+        }
+        self.persist_credentials()?;
+
+        for plan in &plans {
+            EVENT_BUS.credential_changed(plan.group.kept_id, CredentialChange::Updated);
+        }
+        for &id in &removed {
+            USAGE_HISTORY.remove(id);
+            CREDENTIAL_STATS.remove(id);
+            EVENT_BUS.credential_changed(id, CredentialChange::Deleted);
+        }
+        tracing::info!("已合并 {} 组重复凭证，删除 {} 个", plans.len(), removed.len());
+        Ok(plans.into_iter().map(|p| p.group).collect())
+    }
+
+    /// 用新的凭证列表整体替换当前凭证（恢复备份）
+    ///
+    /// 没有 ID 的凭证分配新 ID；运行状态（失败计数、冷却、会话绑定等）全部重置，
+    /// 然后在活跃分组内重新选择 ID 最小的可用凭证并回写文件
+    pub fn replace_credentials(&self, credentials: Vec<KiroCredentials>) -> anyhow::Result<usize> {
+        let mut next_id = credentials.iter().filter_map(|c| c.id).max().unwrap_or(0) + 1;
+        let mut seen_ids = std::collections::HashSet::new();
+        let mut new_entries = Vec::with_capacity(credentials.len());
+        for mut cred in credentials {
+            let id = match cred.id {
+                Some(id) => id,
+                None => {
+                    let id = next_id;
+                    next_id += 1;
+                    cred.id = Some(id);
+                    id
+                }
+            };
+            if !seen_ids.insert(id) {
+                anyhow::bail!("检测到重复的凭证 ID: {}", id);
+            }
+            new_entries.push(CredentialEntry::loaded(id, cred));
+        }
+
+        let old_ids: Vec<u64> = {
+            let mut entries = self.entries.lock();
+            let old_ids = entries.iter().map(|e| e.id).collect();
+            *entries = new_entries;
+            old_ids
+        };
+        self.session_bindings.lock().clear();
+        *self.group_failover.lock() = None;
+        self.select_smallest_id_in_group();
+        self.persist_credentials()?;
+
+        for id in old_ids {
+            EVENT_BUS.credential_changed(id, CredentialChange::Deleted);
+        }
+        let count = self.total_count();
+        for id in seen_ids {
+            EVENT_BUS.credential_changed(id, CredentialChange::Added);
+        }
+        tracing::info!("已替换全部凭证（{} 个）", count);
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 只有 refreshToken 的测试凭证（使用前需要刷新）
+    fn credential(refresh_token: &str) -> KiroCredentials {
+        KiroCredentials {
+            refresh_token: Some(refresh_token.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// accessToken 一小时后过期的测试凭证（无需刷新即可使用）
+    fn fresh_credential(refresh_token: &str) -> KiroCredentials {
+        KiroCredentials {
+            access_token: Some(format!("access-{}", refresh_token)),
+            expires_at: Some((Utc::now() + Duration::hours(1)).to_rfc3339()),
+            ..credential(refresh_token)
+        }
+    }
+
+    #[test]
+    fn test_token_manager_new() {
+        let config = Config::default();
+        let credentials = KiroCredentials::default();
+        let tm = TokenManager::new(config, credentials, None);
+        assert!(tm.credentials().access_token.is_none());
+    }
+
+    #[test]
+    fn test_is_token_expired_with_expired_token() {
+        let mut credentials = KiroCredentials::default();
+        credentials.expires_at = Some("2020-01-01T00:00:00Z".to_string());
+        assert!(is_token_expired(&credentials));
+    }
+
+    #[test]
+    fn test_is_token_expired_with_valid_token() {
+        let mut credentials = KiroCredentials::default();
+        let future = Utc::now() + Duration::hours(1);
+        credentials.expires_at = Some(future.to_rfc3339());
+        assert!(!is_token_expired(&credentials));
+    }
+
+    #[test]
+    fn test_is_token_expired_within_5_minutes() {
+        let mut credentials = KiroCredentials::default();
+        let expires = Utc::now() + Duration::minutes(3);
+        credentials.expires_at = Some(expires.to_rfc3339());
+        assert!(is_token_expired(&credentials));
+    }
+
+    #[test]
+    fn test_is_token_expired_no_expires_at() {
+        let credentials = KiroCredentials::default();
+        assert!(is_token_expired(&credentials));
+    }
+
+    #[test]
+    fn test_is_token_expiring_soon_within_10_minutes() {
+        let mut credentials = KiroCredentials::default();
+        let expires = Utc::now() + Duration::minutes(8);
+        credentials.expires_at = Some(expires.to_rfc3339());
+        assert!(is_token_expiring_soon(&credentials));
+    }
+
+    #[test]
+    fn test_is_token_expiring_soon_beyond_10_minutes() {
+        let mut credentials = KiroCredentials::default();
+        let expires = Utc::now() + Duration::minutes(15);
+        credentials.expires_at = Some(expires.to_rfc3339());
+        assert!(!is_token_expiring_soon(&credentials));
+    }
+
+    #[test]
+    fn test_validate_refresh_token_missing() {
+        let credentials = KiroCredentials::default();
+        let result = validate_refresh_token(&credentials);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_validate_refresh_token_valid() {
+        let mut credentials = KiroCredentials::default();
+        credentials.refresh_token = Some("a".repeat(150));
+        let result = validate_refresh_token(&credentials);
+        assert!(result.is_ok());
+    }
+
+    // MultiTokenManager 测试
+
+    #[test]
+    fn test_multi_token_manager_new() {
+        let config = Config::default();
+        let cred1 = KiroCredentials::default();
+        let cred2 = KiroCredentials::default();
+
+        let manager =
+            MultiTokenManager::new(config, vec![cred1, cred2], None, None, false).unwrap();
+        assert_eq!(manager.total_count(), 2);
+        assert_eq!(manager.available_count(), 2);
+    }
+
+    #[test]
+    fn test_multi_token_manager_empty_credentials() {
+        let config = Config::default();
+        let result = MultiTokenManager::new(config, vec![], None, None, false);
+        // 支持 0 个凭证启动（可通过管理面板添加）
+        assert!(result.is_ok());
+        let manager = result.unwrap();
+        assert_eq!(manager.total_count(), 0);
+        assert_eq!(manager.available_count(), 0);
+    }
+
+    #[test]
+    fn test_multi_token_manager_duplicate_ids() {
+        let config = Config::default();
+        let mut cred1 = KiroCredentials::default();
+        cred1.id = Some(1);
+        let mut cred2 = KiroCredentials::default();
+        cred2.id = Some(1); // 重复 ID
+
+        let result = MultiTokenManager::new(config, vec![cred1, cred2], None, None, false);
+        assert!(result.is_err());
+        let err_msg = result.err().unwrap().to_string();
+        assert!(
+            err_msg.contains("重复的凭证 ID"),
+            "错误消息应包含 '重复的凭证 ID'，实际: {}",
+            err_msg
+        );
+    }
+
+    #[test]
+    fn test_multi_token_manager_report_failure() {
+        let config = Config::default();
+        let cred1 = KiroCredentials::default();
+        let cred2 = KiroCredentials::default();
+
+        let manager =
+            MultiTokenManager::new(config, vec![cred1, cred2], None, None, false).unwrap();
+
+        // 凭证会自动分配 ID（从 1 开始）
+        // 前两次失败不会禁用（使用 ID 1）
+        assert!(manager.report_failure(1, FailureClass::Auth));
+        assert!(manager.report_failure(1, FailureClass::Auth));
+        assert_eq!(manager.available_count(), 2);
+
+        // 第三次失败会禁用第一个凭证
+        assert!(manager.report_failure(1, FailureClass::Auth));
+        assert_eq!(manager.available_count(), 1);
+
+        // 继续失败第二个凭证（使用 ID 2）
+        assert!(manager.report_failure(2, FailureClass::Auth));
+        assert!(manager.report_failure(2, FailureClass::Auth));
+        assert!(!manager.report_failure(2, FailureClass::Auth)); // 所有凭证都禁用了
+        assert_eq!(manager.available_count(), 0);
+    }
+
+    #[test]
+    fn test_quota_exhausted_reenabled_after_reset() {
+        let config = Config::default();
+        let reset_at = Utc::now() + Duration::hours(2);
+        let cred1 = KiroCredentials {
+            next_reset_at: Some(reset_at.timestamp() as f64),
+            ..Default::default()
+        };
+        let cred2 = KiroCredentials::default();
+
+        let manager =
+            MultiTokenManager::new(config, vec![cred1, cred2], None, None, false).unwrap();
+        let quota_error = |status, body| {
+            UpstreamError::parse(UpstreamEndpoint::Api, status, body, "").is_quota_exhausted()
+        };
+        assert!(quota_error(reqwest::StatusCode::PAYMENT_REQUIRED, ""));
+        assert!(quota_error(reqwest::StatusCode::BAD_REQUEST, r#"{"reason":"MONTHLY_REQUEST_COUNT"}"#));
+
+        assert!(manager.report_quota_exhausted(1));
+        assert_eq!(manager.available_count(), 1);
+        let snapshot = manager.snapshot();
+        let entry = snapshot.entries.iter().find(|e| e.id == 1).unwrap();
+        assert_eq!(entry.quota_reset_at, Some(DateTime::from_timestamp(reset_at.timestamp(), 0).unwrap().to_rfc3339()));
+
+        // 重置时间之前不启用
+        assert!(manager.reenable_quota_reset(Utc::now()).is_empty());
+        assert_eq!(manager.reenable_quota_reset(reset_at + Duration::minutes(1)), vec![1]);
+        assert_eq!(manager.available_count(), 2);
+        assert!(manager.snapshot().entries.iter().all(|e| e.quota_reset_at.is_none()));
+    }
+
+    #[test]
+    fn test_quota_reset_time_falls_back_to_next_month() {
+        use chrono::TimeZone;
+
+        let now = Utc.with_ymd_and_hms(2025, 12, 15, 8, 0, 0).unwrap();
+        let expected = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(quota_reset_time(&KiroCredentials::default(), now), expected);
+
+        // 已过去的 nextResetAt 视为未知
+        let stale = KiroCredentials {
+            next_reset_at: Some((now - Duration::days(1)).timestamp() as f64),
+            ..Default::default()
+        };
+        assert_eq!(quota_reset_time(&stale, now), expected);
+    }
+
+    #[test]
+    fn test_multi_token_manager_report_success() {
+        let config = Config::default();
+        let cred = KiroCredentials::default();
+
+        let manager = MultiTokenManager::new(config, vec![cred], None, None, false).unwrap();
+
+        // 失败两次（使用 ID 1）
+        manager.report_failure(1, FailureClass::Auth);
+        manager.report_failure(1, FailureClass::Auth);
+
+        // 成功后重置计数（使用 ID 1）
+        manager.report_success(1);
+
+        // 再失败两次不会禁用
+        manager.report_failure(1, FailureClass::Auth);
+        manager.report_failure(1, FailureClass::Auth);
+        assert_eq!(manager.available_count(), 1);
+    }
+
+    #[test]
+    fn test_multi_token_manager_switch_to_next() {
+        let config = Config::default();
+        let mut cred1 = KiroCredentials::default();
+        cred1.refresh_token = Some("token1".to_string());
+        let mut cred2 = KiroCredentials::default();
+        cred2.refresh_token = Some("token2".to_string());
+
+        let manager =
+            MultiTokenManager::new(config, vec![cred1, cred2], None, None, false).unwrap();
+
+        // 初始是第一个凭证
+        assert_eq!(
+            manager.credentials().refresh_token,
+            Some("token1".to_string())
+        );
+
+        // 切换到下一个
+        assert!(manager.switch_to_next());
+        assert_eq!(
+            manager.credentials().refresh_token,
+            Some("token2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_credential_entry_machine_id_cache() {
+        let mut entry = CredentialEntry::loaded(1, credential("token1"));
+
+        let first = entry.machine_id().unwrap();
+        assert_eq!(entry.machine_id().unwrap(), first);
+
+        // refreshToken 变化后重新生成
+        entry.credentials.refresh_token = Some("token2".to_string());
+        let second = entry.machine_id().unwrap();
+        assert_ne!(first, second);
+        assert_eq!(
+            Some(second),
+            machine_id::generate_from_credentials(&entry.credentials)
+        );
+    }
+
+    #[test]
+    fn test_credential_entry_prefers_monotonic_deadline() {
+        // 本地时钟偏差导致 expiresAt 看起来已过期
+        let cred = KiroCredentials {
+            expires_at: Some((Utc::now() - Duration::hours(1)).to_rfc3339()),
+            ..Default::default()
+        };
+        let mut entry = CredentialEntry::loaded(1, cred);
+        assert!(entry.needs_refresh());
+
+        entry.token_deadline = Some(Instant::now() + std::time::Duration::from_secs(3600));
+        assert!(!entry.needs_refresh());
+
+        entry.token_deadline = Some(Instant::now() + std::time::Duration::from_secs(60));
+        assert!(entry.needs_refresh());
+    }
+
+    #[test]
+    fn test_monotonic_deadline_from_fresh_credentials() {
+        let mut cred = KiroCredentials {
+            expires_at: Some((Utc::now() + Duration::hours(1)).to_rfc3339()),
+            ..Default::default()
+        };
+        let deadline = monotonic_deadline(&cred).unwrap();
+        let remaining = deadline - Instant::now();
+        assert!(remaining.as_secs() > 3500 && remaining.as_secs() <= 3600);
+
+        cred.expires_at = Some((Utc::now() - Duration::hours(1)).to_rfc3339());
+        assert!(monotonic_deadline(&cred).is_none());
+    }
+
+    #[test]
+    fn test_refresh_attempt_min_interval() {
+        let manager =
+            MultiTokenManager::new(Config::default(), vec![credential("token1")], None, None, false).unwrap();
+
+        assert!(manager.begin_refresh_attempt(1).is_ok());
+        // 30 秒内再次尝试被拒绝（即使上次失败）
+        assert!(manager.begin_refresh_attempt(1).is_err());
+        assert!(manager.begin_refresh_attempt(99).is_err());
+    }
+
+    #[test]
+    fn test_session_binding_respects_availability() {
+        let config = Config {
+            session_affinity_enabled: true,
+            ..Default::default()
+        };
+        let creds = vec![credential("token1"), credential("token2")];
+        let manager = MultiTokenManager::new(config, creds, None, None, false).unwrap();
+
+        manager.bind_session("s1", 2);
+        assert_eq!(manager.bound_credential("s1", None).map(|(id, _)| id), Some(2));
+
+        // 绑定的凭证被禁用后不再命中
+        manager.set_disabled(2, true).unwrap();
+        assert!(manager.bound_credential("s1", None).is_none());
+
+        manager.unbind_session("s1");
+        assert!(manager.bound_credential("s1", None).is_none());
+    }
+
+    #[test]
+    fn test_rate_limit_cooldown_grows_and_caps() {
+        let first = rate_limit_cooldown(1);
+        assert!(first >= RATE_LIMIT_COOLDOWN_BASE);
+        assert!(first <= RATE_LIMIT_COOLDOWN_BASE * 5 / 4);
+        assert!(rate_limit_cooldown(3) >= RATE_LIMIT_COOLDOWN_BASE * 4);
+        assert_eq!(rate_limit_cooldown(100), RATE_LIMIT_COOLDOWN_MAX);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_credential_is_skipped() {
+        let creds = vec![fresh_credential("token1"), fresh_credential("token2")];
+        let manager = MultiTokenManager::new(Config::default(), creds, None, None, false).unwrap();
+
+        assert_eq!(manager.acquire_context().await.unwrap().id, 1);
+
+        let cooldown = manager.report_rate_limited(1, None);
+        assert!(cooldown >= RATE_LIMIT_COOLDOWN_BASE);
+        assert_eq!(manager.acquire_context().await.unwrap().id, 2);
+
+        // 全部冷却时选最先解除冷却的凭证
+        manager.report_rate_limited(2, Some(std::time::Duration::from_secs(600)));
+        assert_eq!(manager.acquire_context().await.unwrap().id, 1);
+
+        manager.report_success(1);
+        assert!(manager.snapshot().entries[0].cooldown_secs.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_active_group_falls_over_to_fallback_group() {
+        let in_group = |token: &str, group: &str, remaining: f64| KiroCredentials {
+            group_id: group.to_string(),
+            remaining: Some(remaining),
+            ..fresh_credential(token)
+        };
+        let creds = vec![in_group("token1", "primary", 0.0), in_group("token2", "backup", 50.0)];
+        let manager = MultiTokenManager::new(Config::default(), creds, None, None, false).unwrap();
+        manager.set_active_group(Some("primary".to_string()));
+
+        // 未配置备用分组时仍使用活跃分组
+        assert_eq!(manager.acquire_context().await.unwrap().id, 1);
+        assert!(manager.failover_group().is_none());
+
+        manager.set_fallback_group(Some("backup".to_string()));
+        assert_eq!(manager.acquire_context().await.unwrap().id, 2);
+        assert_eq!(manager.failover_group().as_deref(), Some("backup"));
+
+        // 固定分组的实例不转移
+        assert_eq!(manager.acquire_context_in(Some("primary")).await.unwrap().id, 1);
+    }
+
+    #[tokio::test]
+    async fn test_least_usage_routing_picks_highest_remaining() {
+        let config = Config {
+            routing_strategy: RoutingStrategy::LeastUsage,
+            ..Default::default()
+        };
+        let with_remaining = |token: &str, remaining: f64| KiroCredentials {
+            remaining: Some(remaining),
+            ..fresh_credential(token)
+        };
+        let creds = vec![
+            with_remaining("token1", 10.0),
+            with_remaining("token2", 80.0),
+            fresh_credential("token3"),
+        ];
+        let manager = MultiTokenManager::new(config, creds, None, None, false).unwrap();
+
+        assert_eq!(manager.acquire_context().await.unwrap().id, 2);
+
+        // 额度未知的凭证排在已知之后；冷却中的凭证排在最后
+        manager.report_rate_limited(2, None);
+        assert_eq!(manager.acquire_context().await.unwrap().id, 1);
+    }
+
+    #[test]
+    fn test_prewarm_standby_near_failure_threshold() {
+        // 备用凭证没有 accessToken，需要刷新
+        let creds = vec![fresh_credential("token1"), credential("token2")];
+        let manager = MultiTokenManager::new(Config::default(), creds, None, None, false).unwrap();
+        let standby = |m: &MultiTokenManager| m.standby_to_prewarm(1).map(|(id, _)| id);
+
+        // 远未到阈值时不预热
+        manager.report_failure(1, FailureClass::Auth);
+        assert_eq!(standby(&manager), None);
+
+        manager.report_failure(1, FailureClass::Auth);
+        assert_eq!(standby(&manager), Some(2));
+
+        // 备用凭证刚尝试过刷新时跳过
+        manager.begin_refresh_attempt(2).unwrap();
+        assert_eq!(standby(&manager), None);
+    }
+
+    #[test]
+    fn test_health_check_recovers_auto_disabled() {
+        let creds = vec![credential("token1"), credential("token2")];
+        let manager = MultiTokenManager::new(Config::default(), creds, None, None, false).unwrap();
+        for _ in 0..3 {
+            manager.report_failure(1, FailureClass::Auth);
+        }
+        manager.set_disabled(2, true).unwrap();
+        let latency = std::time::Duration::from_millis(120);
+
+        // 自动禁用的凭证探测成功后恢复
+        assert!(manager.record_health_check(1, true, latency, Ok(())));
+        // 手动禁用的凭证不会被健康检查启用
+        assert!(manager.record_health_check(2, true, latency, Ok(())));
+        let snapshot = manager.snapshot();
+        let entry1 = snapshot.entries.iter().find(|e| e.id == 1).unwrap();
+        assert!(!entry1.disabled);
+        assert_eq!(entry1.failure_count, 0);
+        let check = entry1.last_health_check.as_ref().unwrap();
+        assert!(check.healthy);
+        assert_eq!(check.latency_ms, 120);
+        assert!(snapshot.entries.iter().find(|e| e.id == 2).unwrap().disabled);
+
+        assert!(!manager.record_health_check(1, false, latency, Err("timeout".to_string())));
+        let snapshot = manager.snapshot();
+        let check = snapshot.entries[0].last_health_check.as_ref().unwrap();
+        assert!(!check.healthy);
+        assert_eq!(check.error.as_deref(), Some("timeout"));
+        assert!(!snapshot.entries[0].disabled);
+    }
+
+    #[tokio::test]
+    async fn test_acquire_context_times_out() {
+        let config = Config {
+            credential_acquire_timeout_secs: 1,
+            ..Default::default()
+        };
+        let manager = MultiTokenManager::new(config, vec![credential("token1")], None, None, false).unwrap();
+
+        // 模拟另一个请求正卡在刷新中：凭证需要刷新，但刷新锁一直被占用
+        let lock = manager.refresh_lock(1);
+        let _guard = lock.lock().await;
+        let err = match manager.acquire_context().await {
+            Ok(_) => panic!("expected acquisition to time out"),
+            Err(e) => e,
+        };
+        let unavailable = err.downcast_ref::<CredentialUnavailable>().unwrap();
+        assert_eq!(unavailable.timeout, std::time::Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_refresh_locks_are_per_credential() {
+        let creds = vec![credential("token1"), credential("token2")];
+        let manager = MultiTokenManager::new(Config::default(), creds, None, None, false).unwrap();
+
+        let lock = manager.refresh_lock(1);
+        let _guard = lock.lock().await;
+        // 同一凭证共用一把锁，其他凭证不受影响
+        assert!(manager.refresh_lock(1).try_lock().is_err());
+        assert!(manager.refresh_lock(2).try_lock().is_ok());
+
+        manager.delete_credential(2).unwrap();
+        assert!(!manager.refresh_locks.lock().contains_key(&2));
+    }
+
+    #[test]
+    fn test_expiring_tokens() {
+        let token = |id: u64, minutes: i64| KiroCredentials {
+            id: Some(id),
+            refresh_token: Some(format!("token{}", id)),
+            access_token: Some("access".to_string()),
+            expires_at: Some((Utc::now() + Duration::minutes(minutes)).to_rfc3339()),
+            ..Default::default()
+        };
+        let creds = vec![token(1, 60), token(2, 8), token(3, 2), token(4, 8)];
+        let manager = MultiTokenManager::new(Config::default(), creds, None, None, false).unwrap();
+        manager.set_disabled(4, true).unwrap();
+
+        let ids = |manager: &MultiTokenManager| manager.expiring_tokens().into_iter().map(|(id, _)| id).collect::<Vec<_>>();
+        assert_eq!(ids(&manager), vec![2, 3]);
+        // 刚尝试过刷新的凭证在冷却期内跳过
+        manager.begin_refresh_attempt(2).unwrap();
+        assert_eq!(ids(&manager), vec![3]);
+    }
+
+    #[tokio::test]
+    async fn test_update_credential_without_auth_change_keeps_token() {
+        let manager =
+            MultiTokenManager::new(Config::default(), vec![fresh_credential("token1")], None, None, false).unwrap();
+
+        let mut updated = manager.get_credentials_for_export(&[1]).remove(0);
+        updated.group_id = "team".to_string();
+        manager.update_credential(1, updated.clone()).await.unwrap();
+        let entry = &manager.snapshot().entries[0];
+        assert_eq!(entry.group_id, "team");
+        assert_eq!(entry.access_token.as_deref(), Some("access-token1"));
+
+        // 无效代理在重新验证前被拒绝，凭证保持不变
+        updated.proxy_url = Some("://invalid".to_string());
+        let err = manager.update_credential(1, updated.clone()).await.unwrap_err();
+        assert!(err.to_string().contains("代理地址无效"));
+        assert!(manager.snapshot().entries[0].proxy_url.is_none());
+
+        assert!(manager.update_credential(2, updated).await.is_err());
+    }
+}
@@ -0,0 +1,673 @@
+use std::sync::Arc;
+use crate::{
+    admin, anthropic, listen,
+    body_limit::{BodyLimit, body_limit_middleware},
+    kiro::{self, provider::KiroProvider, request_queue::RequestQueue, token_manager::MultiTokenManager},
+    model::config::{Config, RoutingStrategy},
+    token, tls,
+    access_control::access_control_middleware,
+    events::{EVENT_BUS, ServerEvent},
+    logs::LOG_COLLECTOR,
+    proxy_lifecycle::{GROUP_PROXIES, ProxyLifecycle},
+    rate_limit::{RateLimiter, rate_limit_middleware},
+    watermark::watermark_middleware,
+};
+use axum_server::tls_rustls::RustlsConfig;
+use kiro::model::credentials::CredentialsConfig;
+use tokio::sync::watch;
+
+/// leastUsage 路由下后台刷新凭证额度的间隔
+const USAGE_REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+
+/// 启动后台额度刷新任务（仅 leastUsage 路由策略需要），遵循维护时间窗口
+fn spawn_usage_refresh(token_manager: Arc<MultiTokenManager>, config: &Config) {
+    if config.routing_strategy != RoutingStrategy::LeastUsage {
+        return;
+    }
+
+    let maintenance_windows = config.maintenance_windows.clone();
+    tokio::spawn(async move {
+        tracing::info!(
+            "[额度刷新] 已启动（leastUsage 路由），间隔 {} 分钟",
+            USAGE_REFRESH_INTERVAL.as_secs() / 60
+        );
+        let mut ticker = tokio::time::interval(USAGE_REFRESH_INTERVAL);
+        loop {
+            ticker.tick().await;
+            if !crate::maintenance::is_maintenance_allowed(&maintenance_windows) {
+                tracing::debug!("[额度刷新] 不在维护时间窗口内，跳过本轮");
+                continue;
+            }
+            let refreshed = token_manager.refresh_all_usage().await;
+            crate::usage_history::USAGE_HISTORY.flush();
+            tracing::debug!("[额度刷新] 已更新 {} 个凭证的剩余额度", refreshed);
+        }
+    });
+}
+
+/// 启动每日用量报告任务（每分钟检查一次是否到达发送时间，配置变更即时生效）
+fn spawn_daily_report(token_manager: Arc<MultiTokenManager>) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(60));
+        loop {
+            ticker.tick().await;
+            if !crate::alerts::ALERTS.daily_report_due(chrono::Local::now()) {
+                continue;
+            }
+            let snapshot = token_manager.snapshot();
+            crate::alerts::ALERTS.emit(crate::alerts::daily_report(&snapshot.entries));
+        }
+    });
+}
+
+/// 启动额度重置恢复任务（每分钟检查一次，重新启用额度已重置的凭证）
+fn spawn_quota_reenable(token_manager: Arc<MultiTokenManager>) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(60));
+        loop {
+            ticker.tick().await;
+            for id in token_manager.reenable_quota_reset(chrono::Utc::now()) {
+                LOG_COLLECTOR.add_log("INFO", &format!("✅ 凭证 #{} 额度已重置，已自动重新启用", id));
+            }
+        }
+    });
+}
+
+/// 启动对话记录清理任务（每小时按保留天数和大小上限删除旧文件）
+fn spawn_transcript_retention() {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(60 * 60));
+        loop {
+            ticker.tick().await;
+            crate::transcripts::TRANSCRIPTS.enforce_retention(chrono::Utc::now().date_naive());
+        }
+    });
+}
+
+/// 启动 Token 预刷新任务（每分钟扫描一次，提前刷新进入 10 分钟过期窗口的 Token）
+fn spawn_token_prerefresh(token_manager: Arc<MultiTokenManager>) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(60));
+        loop {
+            ticker.tick().await;
+            let refreshed = token_manager.refresh_expiring_tokens().await;
+            if refreshed > 0 {
+                tracing::debug!("[Token 预刷新] 已刷新 {} 个即将过期的 Token", refreshed);
+            }
+        }
+    });
+}
+
+/// 启动凭证轮换计划任务（每分钟检查一次命中的时段，计划变更即时生效）
+fn spawn_rotation_schedule(token_manager: Arc<MultiTokenManager>) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(60));
+        loop {
+            ticker.tick().await;
+            let Some(rule) = crate::rotation::ROTATION.due(chrono::Local::now().time()) else {
+                continue;
+            };
+            if let Some(group_id) = rule.group_id.clone() {
+                token_manager.set_active_group(Some(group_id));
+            }
+            if let Some(id) = rule.credential_id {
+                if let Err(e) = token_manager.switch_to(id) {
+                    LOG_COLLECTOR.add_log("WARN", &format!("⏰ 轮换计划切换凭证失败: {}", e));
+                    continue;
+                }
+            }
+            let target = match (&rule.group_id, rule.credential_id) {
+                (Some(group), Some(id)) => format!("分组 '{}' / 凭证 #{}", group, id),
+                (Some(group), None) => format!("分组 '{}'", group),
+                (None, Some(id)) => format!("凭证 #{}", id),
+                (None, None) => continue,
+            };
+            LOG_COLLECTOR.add_log(
+                "INFO",
+                &format!("⏰ 轮换计划 {} - {}：已切换到{}", rule.start, rule.end, target),
+            );
+        }
+    });
+}
+
+/// 启动凭证健康检查任务（独立于自动刷新），遵循维护时间窗口
+fn spawn_health_check(token_manager: Arc<MultiTokenManager>, config: &Config) {
+    if !config.health_check.enabled {
+        return;
+    }
+
+    let interval_minutes = config.health_check.interval_minutes.max(1);
+    let maintenance_windows = config.maintenance_windows.clone();
+    tokio::spawn(async move {
+        let interval = std::time::Duration::from_secs(interval_minutes as u64 * 60);
+        tracing::info!("[健康检查] 已启动，间隔 {} 分钟", interval_minutes);
+        loop {
+            tokio::time::sleep(interval).await;
+            if !crate::maintenance::is_maintenance_allowed(&maintenance_windows) {
+                tracing::debug!("[健康检查] 不在维护时间窗口内，跳过本轮");
+                continue;
+            }
+            let (healthy, checked) = token_manager.run_health_checks().await;
+            if healthy < checked {
+                LOG_COLLECTOR.add_log(
+                    "WARN",
+                    &format!("🩺 健康检查完成：{}/{} 个凭证正常", healthy, checked),
+                );
+            } else {
+                tracing::debug!("[健康检查] {} 个凭证全部正常", checked);
+            }
+        }
+    });
+}
+
+/// 按配置启动分组反代实例（后台执行，不阻塞主服务启动）
+fn spawn_group_proxies(config: &Config) {
+    if !config.group_listeners.iter().any(|l| l.enabled) {
+        return;
+    }
+    let listeners = config.group_listeners.clone();
+    tokio::spawn(async move {
+        for (group_id, error) in GROUP_PROXIES.sync(&listeners).await {
+            LOG_COLLECTOR.add_log("ERROR", &format!("❌ 分组 {} 反代实例启动失败: {}", group_id, error));
+        }
+    });
+}
+
+/// 按配置初始化全局状态（租户 Key、统计、缓存、改写规则等），两种服务模式共用
+fn init_globals(config: &Config, config_path: &std::path::Path) {
+    crate::api_keys::init(config.api_keys.clone(), config_path);
+    crate::group_budgets::init(&config.groups, config_path);
+    anthropic::batches::init(config_path);
+    crate::model_catalog::MODEL_CATALOG.set_models(config.models.clone());
+    crate::model_mapping::MODEL_MAPPER.set_rules(config.model_mappings.clone());
+    crate::request_transform::REQUEST_TRANSFORMER.set_global(config.request_transform.clone());
+    crate::redaction::REDACTOR.set_config(&config.redaction);
+    crate::post_process::POST_PROCESSOR.set_config(&config.post_process);
+    anthropic::resume::STREAM_REPLAY.set_window(config.stream_resume_secs);
+    crate::usage_history::init(config_path);
+    crate::credential_stats::init(config_path);
+    crate::audit::init(config_path);
+    crate::transcripts::init(config_path, config.transcripts.clone());
+    crate::logs::init_file_sink(config_path, &config.log_file);
+    crate::cache::idempotency::IDEMPOTENCY_CACHE.set_config(config.idempotency.clone());
+    crate::cache::response::RESPONSE_CACHE.set_config(config.response_cache.clone());
+    crate::alerts::ALERTS.set_config(config.alerts.clone());
+    crate::rotation::ROTATION.set_rules(config.rotation_schedule.clone());
+    crate::webhooks::WEBHOOKS.set_webhooks(config.webhooks.clone());
+    crate::access_control::ACCESS_CONTROL.set_config(config.lan_access.clone());
+}
+
+/// 按配置加载 TLS（证书默认放在配置文件所在目录）
+fn load_tls(config: &Config, config_path: &str) -> anyhow::Result<Option<RustlsConfig>> {
+    let config_dir = std::path::Path::new(config_path)
+        .parent()
+        .unwrap_or_else(|| std::path::Path::new("."));
+    tls::load(&config.tls, &config.host, config_dir).map_err(|e| {
+        tracing::error!("加载 TLS 证书失败: {}", e);
+        anyhow::anyhow!("Load TLS Error: {}", e)
+    })
+}
+
+/// 共享的 Admin 上下文，用于反代服务控制
+#[derive(Clone)]
+pub struct AdminContext {
+    pub config: Arc<parking_lot::Mutex<Config>>,
+    pub token_manager: Arc<MultiTokenManager>,
+    pub api_key: String,
+    pub credentials_path: String,
+    /// 反代服务的 TLS 配置（启动时加载，None 表示 HTTP）
+    pub tls: Option<RustlsConfig>,
+}
+
+/// 独立的反代服务器（只包含 Anthropic API 端点）
+///
+/// `lifecycle` 为分组反代实例时固定使用该分组的凭证并监听其端口，不修改活跃分组
+pub(crate) async fn run_proxy_only_server(
+    config: Config,
+    token_manager: Arc<MultiTokenManager>,
+    api_key: String,
+    tls: Option<RustlsConfig>,
+    mut shutdown_rx: watch::Receiver<bool>,
+    lifecycle: ProxyLifecycle,
+) -> anyhow::Result<()> {
+    let group_listener = lifecycle.group_listener().cloned();
+    if group_listener.is_none() {
+        // 同步活跃分组到 token_manager
+        token_manager.set_active_group(config.active_group_id.clone());
+    }
+    
+    // 创建 KiroProvider（并发限制按本次启动时的配置）
+    let kiro_provider = KiroProvider::with_proxy(token_manager.clone(), None)
+        .with_request_queue(RequestQueue::from_config(&config))
+        .with_group(group_listener.as_ref().map(|l| l.group_id.clone()));
+    
+    // 构建 Anthropic API 路由
+    let first_credentials = token_manager.credentials();
+    let anthropic_app = anthropic::create_router_with_provider_and_control(
+        &api_key,
+        Some(kiro_provider),
+        first_credentials.profile_arn.clone(),
+        lifecycle.clone(),
+    )
+    // 限流在 API Key 认证之前执行
+    .layer(axum::middleware::from_fn_with_state(
+        Arc::new(RateLimiter::new(config.rate_limit.clone(), &api_key)),
+        rate_limit_middleware,
+    ))
+    .layer(axum::middleware::from_fn_with_state(
+        Arc::new(config.watermark.clone()),
+        watermark_middleware,
+    ))
+    // 请求体大小限制（超限返回 JSON 413）
+    .layer(axum::extract::DefaultBodyLimit::max(config.request_limits.max_body_bytes()))
+    .layer(axum::middleware::from_fn_with_state(
+        Arc::new(BodyLimit {
+            max_bytes: config.request_limits.max_body_bytes(),
+        }),
+        body_limit_middleware,
+    ));
+    
+    // 配置 CORS
+    let cors = crate::cors::layer(&config.cors);
+    
+    // 健康检查
+    async fn health_check() -> axum::Json<serde_json::Value> {
+        axum::Json(serde_json::json!({
+            "status": "ok",
+            "service": "kiro-gateway-proxy"
+        }))
+    }
+    
+    let app = axum::Router::new()
+        .route("/", axum::routing::get(health_check))
+        .route("/health", axum::routing::get(health_check))
+        .merge(crate::health::router("kiro-gateway-proxy", token_manager.clone(), lifecycle.clone()))
+        .merge(anthropic_app)
+        .layer(axum::middleware::from_fn(access_control_middleware))
+        .layer(cors);
+    
+    let hosts = listen::listen_hosts(&config)?;
+    // 分组实例的端口由用户指定，被占用时直接失败，避免顺延到其他实例的端口
+    let (listeners, actual_port) = match &group_listener {
+        Some(l) => listen::bind_all(&hosts, l.port, 1).await?,
+        None => listen::bind_all(&hosts, config.proxy_port, 10).await?,
+    };
+    let addresses = listen::bound_addresses(&listeners);
+    let group_info = match (&group_listener, &config.active_group_id) {
+        (Some(l), _) => format!("分组实例: {}", l.group_id),
+        (None, Some(gid)) => format!("分组: {}", gid),
+        (None, None) => "分组: 全部".to_string(),
+    };
+    let scheme = tls::scheme(&tls);
+    let urls = addresses.iter().map(|a| format!("{}://{}", scheme, a)).collect::<Vec<_>>().join(", ");
+    lifecycle.mark_running(actual_port, addresses);
+    tracing::info!("[反代服务] 启动监听: {} ({})", urls, group_info);
+    LOG_COLLECTOR.add_log("INFO", &format!("🚀 反代服务已启动: {} ({})", urls, group_info));
+    
+    tls::serve_all(listeners, app, tls, async move {
+        let _ = shutdown_rx.changed().await;
+        tracing::info!("[反代服务] 收到停止信号 ({})", group_info);
+        LOG_COLLECTOR.add_log("INFO", &format!("🛑 反代服务已停止 ({})", group_info));
+    })
+    .await?;
+    
+    Ok(())
+}
+
+/// 核心启动逻辑（单端口模式，用于 CLI）
+/// config_path: 配置文件路径
+/// credentials_path: 凭证文件路径
+/// shutdown_rx: 停机信号接收器
+pub async fn run_server(
+    config_path: String,
+    credentials_path: String,
+    mut shutdown_rx: watch::Receiver<bool>,
+) -> anyhow::Result<()> {
+    // 加载配置（如果不存在则创建默认配置）
+    let mut config = Config::load_or_create(&config_path).map_err(|e| {
+        tracing::error!("加载配置失败: {}", e);
+        anyhow::anyhow!("Load Config Error: {}", e)
+    })?;
+
+    init_globals(&config, std::path::Path::new(&config_path));
+    let admin_api_key = admin::ensure_admin_api_key(&mut config, &config_path);
+    let tls = load_tls(&config, &config_path)?;
+
+    // 加载凭证（如果不存在则创建空文件）
+    let credentials_config = CredentialsConfig::load_or_create(&credentials_path).map_err(|e| {
+        tracing::error!("加载凭证失败: {}", e);
+        anyhow::anyhow!("Load Credentials Error: {}", e)
+    })?;
+
+    // 判断是否为多凭证格式
+    let is_multiple_format = credentials_config.is_multiple();
+
+    // 转换为按优先级排序的凭证列表
+    let credentials_list = credentials_config.into_sorted_credentials();
+    tracing::info!("已加载 {} 个凭证配置", credentials_list.len());
+
+    // 获取 API Key
+    let api_key = config.api_key.clone().unwrap_or_else(|| {
+        tracing::error!("配置文件中未设置 apiKey");
+        std::process::exit(1);
+    });
+
+    // 创建 MultiTokenManager 和 KiroProvider
+    let token_manager = MultiTokenManager::new(
+        config.clone(),
+        credentials_list,
+        None,
+        Some(credentials_path.clone().into()),
+        is_multiple_format,
+    )?;
+    
+    let token_manager = Arc::new(token_manager);
+    let kiro_provider = KiroProvider::with_proxy(token_manager.clone(), None);
+    spawn_usage_refresh(token_manager.clone(), &config);
+    spawn_health_check(token_manager.clone(), &config);
+    spawn_daily_report(token_manager.clone());
+    spawn_rotation_schedule(token_manager.clone());
+    spawn_transcript_retention();
+    spawn_token_prerefresh(token_manager.clone());
+    spawn_quota_reenable(token_manager.clone());
+
+    // 初始化 count_tokens 配置（禁用外部 API）
+    token::init_config(token::CountTokensConfig {
+        api_url: None,
+        api_key: None,
+        auth_type: "x-api-key".to_string(),
+        proxy: None,
+    });
+
+    // 单端口模式：反代与 Admin API 共用监听器，仅软启停
+    let proxy = ProxyLifecycle::shared_listener();
+
+    // 构建 Anthropic API 路由 (使用第一个凭证的 profile_arn 占位，实际由 Provider 动态处理)
+    let first_credentials = token_manager.credentials();
+    
+    let anthropic_app = anthropic::create_router_with_provider_and_control(
+        &api_key,
+        Some(kiro_provider),
+        first_credentials.profile_arn.clone(),
+        proxy.clone(),
+    )
+    // 限流在 API Key 认证之前执行
+    .layer(axum::middleware::from_fn_with_state(
+        Arc::new(RateLimiter::new(config.rate_limit.clone(), &api_key)),
+        rate_limit_middleware,
+    ))
+    .layer(axum::middleware::from_fn_with_state(
+        Arc::new(config.watermark.clone()),
+        watermark_middleware,
+    ))
+    // 请求体大小限制（超限返回 JSON 413）
+    .layer(axum::extract::DefaultBodyLimit::max(config.request_limits.max_body_bytes()))
+    .layer(axum::middleware::from_fn_with_state(
+        Arc::new(BodyLimit {
+            max_bytes: config.request_limits.max_body_bytes(),
+        }),
+        body_limit_middleware,
+    ));
+
+    // Admin API 需要 Admin API Key 或 Admin UI 会话令牌
+    let admin_service = admin::AdminService::new(token_manager.clone());
+    let config_arc = Arc::new(parking_lot::Mutex::new(config.clone()));
+    // 分组反代实例使用独立端口，单端口模式下同样可用
+    GROUP_PROXIES.attach_context(Arc::new(AdminContext {
+        config: config_arc.clone(),
+        token_manager: token_manager.clone(),
+        api_key: api_key.clone(),
+        credentials_path,
+        tls: tls.clone(),
+    }));
+    spawn_group_proxies(&config);
+    let admin_state = admin::AdminState::new(
+        admin_api_key,
+        admin_service,
+        config_arc,
+        &config_path,
+        token_manager.clone(),
+        proxy.clone(),
+    );
+    
+    let admin_app = admin::create_admin_router(admin_state);
+
+    tracing::info!("Admin API 已启用");
+    
+    // 配置 CORS
+    let cors = crate::cors::layer(&config.cors);
+    
+    // 健康检查响应
+    async fn health_check() -> axum::Json<serde_json::Value> {
+        axum::Json(serde_json::json!({
+            "status": "ok",
+            "service": "kiro-gateway"
+        }))
+    }
+    
+    // 创建基础路由（健康检查和 Admin API）
+    let base_routes = axum::Router::new()
+        .route("/", axum::routing::get(health_check))
+        .route("/health", axum::routing::get(health_check))
+        .route("/ping", axum::routing::get(health_check))
+        .merge(crate::health::router("kiro-gateway", token_manager.clone(), proxy.clone()))
+        .nest("/api/admin", admin_app);
+    
+    // 合并所有路由（局域网模式下 Admin API 仍只允许本机访问）
+    let app = base_routes
+        .merge(anthropic_app)
+        .layer(axum::middleware::from_fn(access_control_middleware))
+        .layer(cors);
+
+    let hosts = listen::listen_hosts(&config)?;
+    let (listeners, actual_port) = listen::bind_all(&hosts, config.port, 10).await?;
+    let addresses = listen::bound_addresses(&listeners);
+    tracing::info!("启动监听: {}://{}", tls::scheme(&tls), addresses.join(", "));
+    proxy.set_bound_port(actual_port, addresses);
+    EVENT_BUS.server_event(ServerEvent::Started {
+        host: hosts.join(", "),
+        port: actual_port,
+    });
+    
+    // 收到停止信号后优雅关闭
+    tls::serve_all(listeners, app, tls, async move {
+        let _ = shutdown_rx.changed().await;
+        tracing::info!("收到停止信号，正在关闭服务...");
+    })
+    .await?;
+    EVENT_BUS.server_event(ServerEvent::Stopped);
+
+    Ok(())
+}
+
+/// 双端口模式：Admin API（端口 8990）+ 反代服务（端口 8991）
+/// 用于 GUI 模式下运行，支持反代服务独立启停；`proxy` 与调用方共享以便同步状态
+pub async fn run_dual_port_server(
+    config_path: String,
+    credentials_path: String,
+    proxy: ProxyLifecycle,
+) -> anyhow::Result<()> {
+    // 加载配置
+    let mut config = Config::load_or_create(&config_path).map_err(|e| {
+        tracing::error!("加载配置失败: {}", e);
+        anyhow::anyhow!("Load Config Error: {}", e)
+    })?;
+
+    init_globals(&config, std::path::Path::new(&config_path));
+    let admin_api_key = admin::ensure_admin_api_key(&mut config, &config_path);
+    let tls = load_tls(&config, &config_path)?;
+
+    // 加载凭证
+    let credentials_config = CredentialsConfig::load_or_create(&credentials_path).map_err(|e| {
+        tracing::error!("加载凭证失败: {}", e);
+        anyhow::anyhow!("Load Credentials Error: {}", e)
+    })?;
+
+    let is_multiple_format = credentials_config.is_multiple();
+    let credentials_list = credentials_config.into_sorted_credentials();
+    tracing::info!("已加载 {} 个凭证配置", credentials_list.len());
+
+    // 获取 API Key（反代需要）
+    let api_key = config.api_key.clone().unwrap_or_else(|| {
+        "sk-kiro-gateway-default".to_string()
+    });
+
+    // 创建 MultiTokenManager
+    let token_manager = MultiTokenManager::new(
+        config.clone(),
+        credentials_list,
+        None,
+        Some(credentials_path.clone().into()),
+        is_multiple_format,
+    )?;
+    
+    let token_manager = Arc::new(token_manager);
+    spawn_usage_refresh(token_manager.clone(), &config);
+    spawn_health_check(token_manager.clone(), &config);
+    spawn_daily_report(token_manager.clone());
+    spawn_rotation_schedule(token_manager.clone());
+    spawn_transcript_retention();
+    spawn_token_prerefresh(token_manager.clone());
+    spawn_quota_reenable(token_manager.clone());
+
+    // 初始化 count_tokens 配置（禁用外部 API）
+    token::init_config(token::CountTokensConfig {
+        api_url: None,
+        api_key: None,
+        auth_type: "x-api-key".to_string(),
+        proxy: None,
+    });
+
+    // 创建 Admin 上下文（用于反代服务控制）
+    let config_arc = Arc::new(parking_lot::Mutex::new(config.clone()));
+    let admin_ctx = AdminContext {
+        config: config_arc.clone(),
+        token_manager: token_manager.clone(),
+        api_key: api_key.clone(),
+        credentials_path,
+        tls: tls.clone(),
+    };
+    let admin_ctx = Arc::new(admin_ctx);
+    proxy.attach_context(admin_ctx.clone());
+    GROUP_PROXIES.attach_context(admin_ctx);
+    spawn_group_proxies(&config);
+
+    // 根据配置决定是否自动启动反代服务
+    if config.proxy_auto_start {
+        if let Err(e) = proxy.start().await {
+            tracing::error!("自动启动反代服务失败: {}", e);
+        }
+    }
+
+    // 启动模型锁定监控
+    crate::model_lock::set_profiles(config.kiro_profiles.clone());
+    if let Some(ref locked_model) = config.locked_model {
+        tracing::info!("从配置加载锁定模型: {}", locked_model);
+        crate::model_lock::set_locked_model(Some(locked_model.clone()));
+    }
+    crate::model_lock::start_model_lock_watcher();
+
+    // 创建 Admin 服务
+    let admin_service = admin::AdminService::new(token_manager.clone());
+    let admin_state = admin::AdminState::new(
+        admin_api_key,
+        admin_service,
+        config_arc,
+        &config_path,
+        token_manager.clone(),
+        proxy.clone(),
+    );
+    
+    let admin_app = admin::create_admin_router(admin_state);
+
+    tracing::info!("[Admin API] 已启用（双端口模式）");
+    
+    // 启动后台自动刷新任务
+    if config.auto_refresh_enabled {
+        let interval_minutes = config.auto_refresh_interval_minutes.max(5); // 至少 5 分钟
+        let token_manager_for_refresh = token_manager.clone();
+        let maintenance_windows = config.maintenance_windows.clone();
+        tokio::spawn(async move {
+            let interval = tokio::time::Duration::from_secs(interval_minutes as u64 * 60);
+            tracing::info!("[自动刷新] 已启动，间隔 {} 分钟", interval_minutes);
+            LOG_COLLECTOR.add_log("INFO", &format!("🔄 自动刷新已启动，间隔 {} 分钟", interval_minutes));
+            
+            loop {
+                tokio::time::sleep(interval).await;
+                if !crate::maintenance::is_maintenance_allowed(&maintenance_windows) {
+                    tracing::debug!("[自动刷新] 不在维护时间窗口内，跳过本轮");
+                    continue;
+                }
+                tracing::debug!("[自动刷新] 开始刷新所有凭证...");
+                
+                // 刷新所有凭证
+                let result = token_manager_for_refresh.refresh_all_credentials().await;
+                match result {
+                    Ok(refreshed) => {
+                        if refreshed > 0 {
+                            tracing::info!("[自动刷新] 成功刷新 {} 个凭证", refreshed);
+                            LOG_COLLECTOR.add_log("INFO", &format!("🔄 自动刷新完成：{} 个凭证已更新", refreshed));
+                        }
+                    }
+                    Err(e) => {
+                        tracing::warn!("[自动刷新] 刷新失败: {}", e);
+                    }
+                }
+            }
+        });
+    }
+    
+    // 配置 CORS
+    let cors = crate::cors::layer(&config.cors);
+    
+    // 健康检查
+    async fn health_check() -> axum::Json<serde_json::Value> {
+        axum::Json(serde_json::json!({
+            "status": "ok",
+            "service": "kiro-gateway-admin"
+        }))
+    }
+    
+    // Admin API 路由（不包含反代端点）
+    let app = axum::Router::new()
+        .route("/", axum::routing::get(health_check))
+        .route("/health", axum::routing::get(health_check))
+        .route("/ping", axum::routing::get(health_check))
+        .merge(crate::health::router("kiro-gateway-admin", token_manager.clone(), proxy))
+        .nest("/api/admin", admin_app)
+        .merge(crate::admin_ui::router())
+        .layer(cors);
+
+    // Admin API 不随局域网共享开放，只监听配置的地址
+    let hosts = listen::configured_hosts(&config)?;
+    let (listeners, actual_port) = listen::bind_all(&hosts, config.port, 10).await?;
+    tracing::info!(
+        "[Admin API] 启动监听: {}://{}",
+        tls::scheme(&tls),
+        listen::bound_addresses(&listeners).join(", ")
+    );
+    if let Some(addr) = listeners.first().and_then(|l| l.local_addr().ok()) {
+        crate::admin_ui::set_base_url(tls::scheme(&tls), addr);
+        tracing::info!("[Admin UI] 浏览器访问: {}://{}/ui/", tls::scheme(&tls), addr);
+    }
+    EVENT_BUS.server_event(ServerEvent::Started {
+        host: hosts.join(", "),
+        port: actual_port,
+    });
+    tracing::info!("[反代服务] 配置端口: {}", config.proxy_port);
+    
+    tls::serve_all(listeners, app, tls, std::future::pending()).await?;
+
+    Ok(())
+}
+
+/// 独立模式：Admin API + 可控的反代服务（单端口，旧版兼容）
+/// 用于 GUI 模式下运行
+pub async fn run_admin_server(
+    config_path: String,
+    credentials_path: String,
+    proxy: ProxyLifecycle,
+) -> anyhow::Result<()> {
+    // 调用双端口模式
+    run_dual_port_server(config_path, credentials_path, proxy).await
+}
@@ -0,0 +1,59 @@
+//! Kiro Gateway 核心库
+//!
+//! 包含反代服务（Anthropic / Gemini 兼容 API）、Admin API 和 Kiro 凭证管理，
+//! 不依赖桌面外壳，可独立测试或嵌入其他 Rust 程序。
+//!
+//! # 使用示例
+//! ```rust,ignore
+//! let (_tx, rx) = tokio::sync::watch::channel(false);
+//! kiro_gateway_core::log_level::init();
+//! kiro_gateway_core::kiro_server::run_server(config_path, credentials_path, rx).await?;
+//! ```
+
+mod access_control;
+pub mod admin;
+pub mod admin_ui;
+pub mod alerts;
+pub mod anthropic;
+mod api_keys;
+mod audit;
+mod backup;
+mod body_limit;
+mod cache;
+mod common;
+mod cors;
+mod credential_stats;
+pub mod error_code;
+pub mod events;
+pub mod gemini;
+mod group_budgets;
+mod group_rules;
+mod health;
+mod http_client;
+pub mod instance;
+mod listen;
+pub mod kiro;
+pub mod kiro_server;
+pub mod log_level;
+mod logs;
+mod maintenance;
+mod metrics;
+pub mod model;
+mod model_catalog;
+mod model_lock;
+mod model_mapping;
+mod post_process;
+pub mod proxy_lifecycle;
+mod rate_limit;
+mod redaction;
+mod request_transform;
+mod rotation;
+mod tls;
+pub mod token;
+mod transcripts;
+mod usage_history;
+mod watermark;
+mod webhooks;
+
+/// 产品名（用于版本信息和响应水印）
+pub const PRODUCT_NAME: &str = "kiro-gateway";
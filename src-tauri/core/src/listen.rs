@@ -0,0 +1,155 @@
+//! 监听地址
+//!
+//! 支持同时监听多个地址（如 `["127.0.0.1", "::1"]`），IPv6 字面量可带或不带方括号，
+//! 统一规范化后绑定。所有地址使用同一端口，端口被占用时整体顺延到下一个端口。
+
+use std::net::{IpAddr, SocketAddr};
+
+use tokio::net::TcpListener;
+
+use crate::model::config::Config;
+
+/// 规范化监听地址：IP 字面量转为标准形式（去掉 IPv6 方括号），主机名转为小写
+pub fn normalize_host(host: &str) -> Result<String, String> {
+    let host = host.trim();
+    let unbracketed = host
+        .strip_prefix('[')
+        .and_then(|h| h.strip_suffix(']'))
+        .unwrap_or(host);
+    if let Ok(ip) = unbracketed.parse::<IpAddr>() {
+        return Ok(ip.to_string());
+    }
+    let valid_hostname = !host.is_empty()
+        && host.len() <= 253
+        && host.split('.').all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+    if valid_hostname {
+        Ok(host.to_ascii_lowercase())
+    } else {
+        Err(format!("无效的监听地址: {}", host))
+    }
+}
+
+/// 校验并规范化监听地址列表（去重，保持顺序）
+pub fn normalize_hosts(hosts: &[String]) -> Result<Vec<String>, String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for host in hosts {
+        let host = normalize_host(host)?;
+        if !normalized.contains(&host) {
+            normalized.push(host);
+        }
+    }
+    Ok(normalized)
+}
+
+/// 拼接 `地址:端口`（IPv6 地址加方括号）
+pub fn join_host_port(host: &str, port: u16) -> String {
+    match host.parse::<IpAddr>() {
+        Ok(ip) => SocketAddr::new(ip, port).to_string(),
+        Err(_) => format!("{}:{}", host, port),
+    }
+}
+
+/// 按配置确定监听地址：局域网共享模式监听所有网卡，否则使用配置的地址
+pub fn listen_hosts(config: &Config) -> anyhow::Result<Vec<String>> {
+    if config.lan_access.enabled {
+        return Ok(vec!["0.0.0.0".to_string()]);
+    }
+    configured_hosts(config)
+}
+
+/// 配置的监听地址：`listenHosts`，为空时使用 `host`
+pub fn configured_hosts(config: &Config) -> anyhow::Result<Vec<String>> {
+    let hosts = if config.listen_hosts.is_empty() {
+        std::slice::from_ref(&config.host)
+    } else {
+        config.listen_hosts.as_slice()
+    };
+    normalize_hosts(hosts).map_err(|e| anyhow::anyhow!(e))
+}
+
+/// 在所有地址上绑定同一端口，任一地址绑定失败则尝试下一个端口
+pub async fn bind_all(hosts: &[String], port: u16, max_attempts: u16) -> anyhow::Result<(Vec<TcpListener>, u16)> {
+    let mut last_error = None;
+    for offset in 0..max_attempts {
+        let try_port = port + offset;
+        match bind_port(hosts, try_port).await {
+            Ok(listeners) => {
+                if offset > 0 {
+                    tracing::warn!("端口 {} 被占用，改用端口 {}", port, try_port);
+                }
+                // 端口为 0 时由系统分配，以实际端口为准
+                let bound = listeners
+                    .first()
+                    .and_then(|l| l.local_addr().ok())
+                    .map_or(try_port, |addr| addr.port());
+                return Ok((listeners, bound));
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+    match last_error {
+        Some(e) if max_attempts > 1 => Err(anyhow::anyhow!("无法绑定端口 {}-{}: {}", port, port + max_attempts - 1, e)),
+        Some(e) => Err(anyhow::anyhow!("无法绑定端口 {}: {}", port, e)),
+        None => Err(anyhow::anyhow!("无法绑定端口")),
+    }
+}
+
+async fn bind_port(hosts: &[String], port: u16) -> anyhow::Result<Vec<TcpListener>> {
+    let mut listeners = Vec::with_capacity(hosts.len());
+    for host in hosts {
+        let addr = join_host_port(host, port);
+        let listener = TcpListener::bind(&addr)
+            .await
+            .map_err(|e| anyhow::anyhow!("{}: {}", addr, e))?;
+        listeners.push(listener);
+    }
+    Ok(listeners)
+}
+
+/// 监听器实际绑定的地址
+pub fn bound_addresses(listeners: &[TcpListener]) -> Vec<String> {
+    listeners
+        .iter()
+        .filter_map(|l| l.local_addr().ok())
+        .map(|addr| addr.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_hosts() {
+        assert_eq!(normalize_host(" 127.0.0.1 ").unwrap(), "127.0.0.1");
+        assert_eq!(normalize_host("[::1]").unwrap(), "::1");
+        assert_eq!(normalize_host("0:0:0:0:0:0:0:1").unwrap(), "::1");
+        assert_eq!(normalize_host("LocalHost").unwrap(), "localhost");
+        assert!(normalize_host("").is_err());
+        assert!(normalize_host("::1]").is_err());
+        assert!(normalize_host("bad host").is_err());
+
+        let hosts = vec!["127.0.0.1".to_string(), "[::1]".to_string(), "::1".to_string()];
+        assert_eq!(normalize_hosts(&hosts).unwrap(), vec!["127.0.0.1", "::1"]);
+
+        assert_eq!(join_host_port("::1", 8990), "[::1]:8990");
+        assert_eq!(join_host_port("localhost", 8990), "localhost:8990");
+    }
+
+    #[tokio::test]
+    async fn test_bind_all_moves_to_next_port_when_occupied() {
+        let hosts = vec!["127.0.0.1".to_string()];
+        let (first, port) = bind_all(&hosts, 0, 1).await.unwrap();
+        let occupied = first[0].local_addr().unwrap().port();
+        assert_eq!(port, occupied);
+
+        let (listeners, port) = bind_all(&hosts, occupied, 5).await.unwrap();
+        assert!(port > occupied);
+        assert_eq!(bound_addresses(&listeners), vec![format!("127.0.0.1:{}", port)]);
+        assert!(bind_all(&hosts, occupied, 1).await.is_err());
+    }
+}
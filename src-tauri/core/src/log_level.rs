@@ -0,0 +1,94 @@
+//! 运行时日志级别调整
+//!
+//! 日志过滤器通过 `reload` 层安装，Admin API 可在不重启进程的情况下替换
+//! 全局级别和按模块的指令（如排障时临时打开 `kiro::provider=debug`）。
+
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::{
+    EnvFilter, Registry, layer::SubscriberExt, reload, util::SubscriberInitExt,
+};
+
+/// 本 crate 的日志 target 前缀（模块指令会自动补全）
+const CRATE_TARGET: &str = env!("CARGO_CRATE_NAME");
+
+static RELOAD_HANDLE: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// 初始化日志（默认 INFO，可被 RUST_LOG 中的模块指令细化）
+pub fn init() {
+    let filter = EnvFilter::from_default_env().add_directive(tracing::Level::INFO.into());
+    let (filter, handle) = reload::Layer::new(filter);
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer())
+        .init();
+    let _ = RELOAD_HANDLE.set(handle);
+}
+
+/// 当前生效的过滤器（EnvFilter 语法）
+pub fn current_filter() -> Option<String> {
+    RELOAD_HANDLE.get()?.with_current(|f| f.to_string()).ok()
+}
+
+/// 由全局级别和模块指令构建过滤器字符串
+///
+/// 模块路径按本 crate 内的路径书写（如 `kiro::provider`），自动补全 crate 前缀；
+/// 已带前缀的路径原样使用
+fn build_filter(level: &str, modules: &BTreeMap<String, String>) -> String {
+    let mut directives = vec![level.to_string()];
+    for (module, module_level) in modules {
+        let module = module.trim_start_matches("crate::");
+        let target = if module == CRATE_TARGET || module.starts_with(&format!("{}::", CRATE_TARGET)) {
+            module.to_string()
+        } else {
+            format!("{}::{}", CRATE_TARGET, module)
+        };
+        directives.push(format!("{}={}", target, module_level));
+    }
+    directives.join(",")
+}
+
+/// 替换日志过滤器，返回新的过滤器字符串
+pub fn set_filter(level: &str, modules: &BTreeMap<String, String>) -> Result<String, String> {
+    // 单独校验级别：EnvFilter 会把无法识别的单词当作 target 名而不报错
+    for value in std::iter::once(level).chain(modules.values().map(String::as_str)) {
+        value
+            .parse::<LevelFilter>()
+            .map_err(|_| format!("无效的日志级别: {}", value))?;
+    }
+    let handle = RELOAD_HANDLE
+        .get()
+        .ok_or_else(|| "日志系统未初始化".to_string())?;
+    let spec = build_filter(level, modules);
+    let filter = EnvFilter::try_new(&spec).map_err(|e| format!("无效的日志指令 '{}': {}", spec, e))?;
+    handle
+        .reload(filter)
+        .map_err(|e| format!("更新日志级别失败: {}", e))?;
+    tracing::info!("日志过滤器已更新: {}", spec);
+    Ok(spec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_filter_qualifies_modules() {
+        let mut modules = BTreeMap::new();
+        modules.insert("kiro::provider".to_string(), "debug".to_string());
+        modules.insert("kiro_gateway_core::logs".to_string(), "trace".to_string());
+        assert_eq!(
+            build_filter("warn", &modules),
+            "warn,kiro_gateway_core::kiro::provider=debug,kiro_gateway_core::logs=trace"
+        );
+        assert!(EnvFilter::try_new(build_filter("warn", &modules)).is_ok());
+    }
+
+    #[test]
+    fn test_set_filter_rejects_unknown_level() {
+        let err = set_filter("loud", &BTreeMap::new()).unwrap_err();
+        assert!(err.contains("loud"));
+    }
+}
@@ -0,0 +1,333 @@
+//! 日志文件输出
+//!
+//! 每条 [`LogEntry`] 以一行 JSON 追加到配置目录下的 `logs/` 中（含完整的请求/响应信息和日期）。
+//! 每天一个文件 `kiro-gateway-YYYY-MM-DD.jsonl`，超过大小上限时另起 `kiro-gateway-YYYY-MM-DD.N.jsonl`；
+//! 超过保留天数的文件在换日时清理。Admin API 可以按天下载 gzip 归档。
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::{Local, NaiveDate};
+use flate2::{Compression, write::GzEncoder};
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use super::LogEntry;
+use crate::model::config::LogFileConfig;
+
+/// 日志文件名前缀
+const FILE_PREFIX: &str = "kiro-gateway-";
+
+/// 日志文件扩展名
+const FILE_EXT: &str = ".jsonl";
+
+/// 写入文件的日志记录（`time` 为完整的本地时间）
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FileLogRecord<'a> {
+    time: String,
+    #[serde(flatten)]
+    entry: &'a LogEntry,
+}
+
+/// 某一天的日志文件汇总
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogDayInfo {
+    pub date: NaiveDate,
+    /// 文件数（含轮转出的分片）
+    pub files: usize,
+    /// 总大小（字节）
+    pub size: u64,
+}
+
+/// 当前写入的文件
+struct ActiveFile {
+    date: NaiveDate,
+    part: u32,
+    file: File,
+    size: u64,
+}
+
+struct SinkState {
+    dir: PathBuf,
+    config: LogFileConfig,
+    active: Option<ActiveFile>,
+}
+
+/// 日志文件输出
+pub struct LogFileSink {
+    state: Mutex<Option<SinkState>>,
+}
+
+/// 日志文件名（分片 0 不带序号）
+fn file_name(date: NaiveDate, part: u32) -> String {
+    if part == 0 {
+        format!("{}{}{}", FILE_PREFIX, date, FILE_EXT)
+    } else {
+        format!("{}{}.{}{}", FILE_PREFIX, date, part, FILE_EXT)
+    }
+}
+
+/// 从文件名解析日期和分片序号
+fn parse_file_name(name: &str) -> Option<(NaiveDate, u32)> {
+    let stem = name.strip_prefix(FILE_PREFIX)?.strip_suffix(FILE_EXT)?;
+    let (date, part) = match stem.split_once('.') {
+        Some((date, part)) => (date, part.parse().ok()?),
+        None => (stem, 0),
+    };
+    Some((date.parse().ok()?, part))
+}
+
+/// 目录中的所有日志文件（按日期、分片排序）
+fn list_files(dir: &Path) -> Vec<(NaiveDate, u32, PathBuf)> {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<_> = read_dir
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name();
+            let (date, part) = parse_file_name(name.to_str()?)?;
+            Some((date, part, e.path()))
+        })
+        .collect();
+    files.sort_by_key(|(date, part, _)| (*date, *part));
+    files
+}
+
+impl SinkState {
+    fn max_size(&self) -> u64 {
+        self.config.max_file_size_mb.max(1) * 1024 * 1024
+    }
+
+    /// 打开指定日期的分片；`part` 为 None 时续写当天最后一个分片
+    fn open(&mut self, date: NaiveDate, part: Option<u32>) -> std::io::Result<()> {
+        let part = part.unwrap_or_else(|| {
+            list_files(&self.dir)
+                .into_iter()
+                .filter(|(d, _, _)| *d == date)
+                .map(|(_, p, _)| p)
+                .max()
+                .unwrap_or(0)
+        });
+        let path = self.dir.join(file_name(date, part));
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        self.active = Some(ActiveFile { date, part, file, size });
+        Ok(())
+    }
+
+    /// 删除超过保留天数的日志文件
+    fn prune(&self, today: NaiveDate) {
+        if self.config.retention_days == 0 {
+            return;
+        }
+        let oldest = today - chrono::Duration::days(self.config.retention_days as i64 - 1);
+        for (date, _, path) in list_files(&self.dir) {
+            if date < oldest {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+
+    fn write_line(&mut self, line: &[u8], today: NaiveDate) -> std::io::Result<()> {
+        let max_size = self.max_size();
+        match &self.active {
+            Some(active) if active.date != today => {
+                self.open(today, Some(0))?;
+                self.prune(today);
+            }
+            Some(active) if active.size > 0 && active.size + line.len() as u64 > max_size => {
+                let next = active.part + 1;
+                self.open(today, Some(next))?;
+            }
+            Some(_) => {}
+            None => self.open(today, None)?,
+        }
+
+        let active = self.active.as_mut().expect("日志文件已打开");
+        active.file.write_all(line)?;
+        active.size += line.len() as u64;
+        Ok(())
+    }
+}
+
+impl LogFileSink {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(None),
+        }
+    }
+
+    /// 按配置启用文件输出（未启用时关闭已打开的文件）
+    pub fn configure(&self, dir: PathBuf, config: LogFileConfig) {
+        let mut state = self.state.lock();
+        if !config.enabled {
+            *state = None;
+            return;
+        }
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            tracing::warn!("创建日志目录失败 {}: {}", dir.display(), e);
+            *state = None;
+            return;
+        }
+
+        let sink = SinkState {
+            dir,
+            config,
+            active: None,
+        };
+        sink.prune(Local::now().date_naive());
+        *state = Some(sink);
+    }
+
+    /// 追加一条日志（未启用时忽略）
+    pub fn write(&self, entry: &LogEntry) {
+        let mut guard = self.state.lock();
+        let Some(state) = guard.as_mut() else {
+            return;
+        };
+
+        let now = Local::now();
+        let record = FileLogRecord {
+            time: now.to_rfc3339(),
+            entry,
+        };
+        let Ok(mut line) = serde_json::to_vec(&record) else {
+            return;
+        };
+        line.push(b'\n');
+
+        if let Err(e) = state.write_line(&line, now.date_naive()) {
+            // 不能写回 LOG_COLLECTOR，否则会递归
+            tracing::warn!("写入日志文件失败: {}", e);
+            state.active = None;
+        }
+    }
+
+    /// 日志目录（未启用时返回 None）
+    pub fn dir(&self) -> Option<PathBuf> {
+        self.state.lock().as_ref().map(|s| s.dir.clone())
+    }
+
+    /// 有日志文件的日期（最新的在前）
+    pub fn days(&self) -> Vec<LogDayInfo> {
+        let Some(dir) = self.dir() else {
+            return Vec::new();
+        };
+        let mut days: Vec<LogDayInfo> = Vec::new();
+        for (date, _, path) in list_files(&dir) {
+            let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            match days.last_mut() {
+                Some(day) if day.date == date => {
+                    day.files += 1;
+                    day.size += size;
+                }
+                _ => days.push(LogDayInfo { date, files: 1, size }),
+            }
+        }
+        days.reverse();
+        days
+    }
+
+    /// 打包某一天的日志（按分片顺序拼接后 gzip 压缩），当天没有日志时返回 None
+    pub fn archive(&self, date: NaiveDate) -> std::io::Result<Option<Vec<u8>>> {
+        let Some(dir) = self.dir() else {
+            return Ok(None);
+        };
+        let files: Vec<PathBuf> = list_files(&dir)
+            .into_iter()
+            .filter(|(d, _, _)| *d == date)
+            .map(|(_, _, path)| path)
+            .collect();
+        if files.is_empty() {
+            return Ok(None);
+        }
+
+        // 当天的文件可能仍在写入，先刷新
+        if let Some(active) = self.state.lock().as_mut().and_then(|s| s.active.as_mut()) {
+            let _ = active.file.flush();
+        }
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        for path in files {
+            let mut file = File::open(path)?;
+            std::io::copy(&mut file, &mut encoder)?;
+        }
+        encoder.finish().map(Some)
+    }
+}
+
+impl Default for LogFileSink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn entry(message: &str) -> LogEntry {
+        LogEntry {
+            timestamp: "00:00:00".to_string(),
+            level: "INFO".to_string(),
+            message: message.to_string(),
+            request: None,
+            response: None,
+            group_id: None,
+        }
+    }
+
+    #[test]
+    fn test_file_name_round_trip() {
+        let date = NaiveDate::from_ymd_opt(2026, 1, 2).unwrap();
+        assert_eq!(file_name(date, 0), "kiro-gateway-2026-01-02.jsonl");
+        assert_eq!(parse_file_name(&file_name(date, 3)), Some((date, 3)));
+        assert_eq!(parse_file_name("other.jsonl"), None);
+    }
+
+    #[test]
+    fn test_rotates_by_size_and_archives_day() {
+        let dir = std::env::temp_dir().join(format!("kiro-logs-{}", uuid::Uuid::new_v4()));
+        let sink = LogFileSink::new();
+        sink.configure(
+            dir.clone(),
+            LogFileConfig {
+                enabled: true,
+                max_file_size_mb: 1,
+                retention_days: 0,
+            },
+        );
+
+        let big = "x".repeat(600 * 1024);
+        sink.write(&entry(&big));
+        sink.write(&entry(&big));
+        sink.write(&entry("small"));
+
+        let today = Local::now().date_naive();
+        let days = sink.days();
+        assert_eq!(days.len(), 1);
+        assert_eq!(days[0].date, today);
+        assert_eq!(days[0].files, 2);
+
+        let archive = sink.archive(today).unwrap().unwrap();
+        let mut content = String::new();
+        flate2::read::GzDecoder::new(&archive[..])
+            .read_to_string(&mut content)
+            .unwrap();
+        let lines: Vec<serde_json::Value> = content
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[2]["message"], "small");
+        assert!(lines[2]["time"].as_str().unwrap().starts_with(&today.to_string()));
+
+        assert!(sink.archive(today - chrono::Duration::days(1)).unwrap().is_none());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
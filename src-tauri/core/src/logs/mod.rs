@@ -1,12 +1,18 @@
 //! 日志收集模块
 //! 
-//! 用于收集应用日志并通过 API 提供给 Admin UI
+//! 用于收集应用日志并通过 API 提供给 Admin UI，同时按配置写入日志文件（见 [`file_sink`]）
+
+pub mod file_sink;
 
 use std::sync::{Arc, RwLock};
 use std::collections::VecDeque;
 use chrono::Local;
 use serde::Serialize;
 
+use crate::events::{AdminEvent, EVENT_BUS, ServerEvent};
+use crate::model::config::LogFileConfig;
+use file_sink::LogFileSink;
+
 /// 单条日志记录
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -21,6 +27,8 @@ pub struct LogEntry {
     pub request: Option<RequestInfo>,
     /// 响应详情（可选）
     pub response: Option<ResponseInfo>,
+    /// 所属凭证分组（系统日志为 None）
+    pub group_id: Option<String>,
 }
 
 /// 请求信息
@@ -69,24 +77,30 @@ impl LogCollector {
             message: message.to_string(),
             request: None,
             response: None,
+            group_id: None,
         };
         self.push_entry(entry);
     }
 
     /// 添加请求日志
-    pub fn add_request_log(&self, request: RequestInfo) {
+    ///
+    /// 请求尚未分配凭证，`group_id` 为当前服务的活跃分组
+    pub fn add_request_log(&self, request: RequestInfo, group_id: Option<String>) {
         let entry = LogEntry {
             timestamp: Local::now().format("%H:%M:%S").to_string(),
             level: "INFO".to_string(),
             message: format!("📨 收到请求: {} ({}条消息)", request.model, request.message_count),
             request: Some(request),
             response: None,
+            group_id,
         };
         self.push_entry(entry);
     }
 
     /// 添加响应日志
-    pub fn add_response_log(&self, response: ResponseInfo, is_stream: bool) {
+    ///
+    /// `group_id` 为实际处理请求的凭证所属分组
+    pub fn add_response_log(&self, response: ResponseInfo, is_stream: bool, group_id: Option<String>) {
         let entry = LogEntry {
             timestamp: Local::now().format("%H:%M:%S").to_string(),
             level: "INFO".to_string(),
@@ -98,11 +112,27 @@ impl LogCollector {
             ),
             request: None,
             response: Some(response),
+            group_id,
         };
         self.push_entry(entry);
     }
 
+    /// 记录服务核心事件
+    pub fn record_server_event(&self, event: &ServerEvent) {
+        match event {
+            ServerEvent::Started { host, port } => {
+                self.add_log("INFO", &format!("🚀 服务已启动: {}:{}", host, port))
+            }
+            ServerEvent::Stopped => self.add_log("INFO", "🛑 服务已停止"),
+            ServerEvent::Crashed { error } => {
+                self.add_log("ERROR", &format!("💥 服务异常退出: {}", error))
+            }
+        }
+    }
+
     fn push_entry(&self, entry: LogEntry) {
+        LOG_FILE_SINK.write(&entry);
+        EVENT_BUS.publish(AdminEvent::Log { entry: entry.clone() });
         let mut logs = self.logs.write().unwrap();
         if logs.len() >= self.max_size {
             logs.pop_front();
@@ -115,6 +145,17 @@ impl LogCollector {
         self.logs.read().unwrap().iter().cloned().collect()
     }
 
+    /// 获取指定分组的日志（不含系统日志）
+    pub fn get_logs_for_group(&self, group_id: &str) -> Vec<LogEntry> {
+        self.logs
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|e| e.group_id.as_deref() == Some(group_id))
+            .cloned()
+            .collect()
+    }
+
     /// 获取指定索引之后的日志
     pub fn get_logs_since(&self, since_index: usize) -> Vec<LogEntry> {
         let logs = self.logs.read().unwrap();
@@ -138,6 +179,17 @@ impl LogCollector {
 // 全局日志收集器
 lazy_static::lazy_static! {
     pub static ref LOG_COLLECTOR: Arc<LogCollector> = Arc::new(LogCollector::new(500));
+    /// 全局日志文件输出
+    pub static ref LOG_FILE_SINK: LogFileSink = LogFileSink::new();
+}
+
+/// 启用日志文件输出（写入配置文件所在目录下的 logs/）
+pub fn init_file_sink(config_path: &std::path::Path, config: &LogFileConfig) {
+    let dir = config_path
+        .parent()
+        .unwrap_or_else(|| std::path::Path::new("."))
+        .join("logs");
+    LOG_FILE_SINK.configure(dir, config.clone());
 }
 
 /// 安全截取字符串
@@ -150,3 +202,33 @@ pub fn safe_truncate(s: &str, max_chars: usize) -> String {
         s.to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(model: &str) -> RequestInfo {
+        RequestInfo {
+            model: model.to_string(),
+            max_tokens: 1024,
+            stream: false,
+            message_count: 1,
+            system_preview: String::new(),
+            user_message_preview: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_get_logs_for_group() {
+        let collector = LogCollector::new(10);
+        collector.add_log("INFO", "system");
+        collector.add_request_log(request("a"), Some("team-a".to_string()));
+        collector.add_request_log(request("b"), Some("team-b".to_string()));
+        collector.add_request_log(request("all"), None);
+
+        let logs = collector.get_logs_for_group("team-a");
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].request.as_ref().unwrap().model, "a");
+        assert_eq!(collector.get_logs().len(), 4);
+    }
+}
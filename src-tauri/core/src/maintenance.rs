@@ -0,0 +1,100 @@
+//! 维护时间窗口
+//!
+//! 自动刷新、余额刷新、备份、凭证探测等后台任务只在配置的窗口内运行，
+//! 避免在工作时间产生额外的上游请求而触发限流。
+
+use chrono::{Local, NaiveTime};
+
+use crate::model::config::MaintenanceWindow;
+
+/// 解析 HH:MM 格式的时间
+pub(crate) fn parse_hhmm(s: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(s.trim(), "%H:%M").ok()
+}
+
+/// 判断给定时间是否落在某个窗口内
+///
+/// 窗口为左闭右开区间；start > end 表示跨午夜。格式错误的窗口会被忽略。
+pub fn is_within_windows(windows: &[MaintenanceWindow], now: NaiveTime) -> bool {
+    windows.iter().any(|w| window_contains(&w.start, &w.end, now))
+}
+
+/// 判断给定时间是否落在 [start, end) 内（start > end 表示跨午夜，格式错误返回 false）
+pub(crate) fn window_contains(start: &str, end: &str, now: NaiveTime) -> bool {
+    let (Some(start), Some(end)) = (parse_hhmm(start), parse_hhmm(end)) else {
+        return false;
+    };
+    if start <= end {
+        now >= start && now < end
+    } else {
+        now >= start || now < end
+    }
+}
+
+/// 当前是否允许执行后台维护任务
+///
+/// 未配置任何窗口时始终允许
+pub fn is_maintenance_allowed(windows: &[MaintenanceWindow]) -> bool {
+    windows.is_empty() || is_within_windows(windows, Local::now().time())
+}
+
+/// 校验窗口配置，返回第一个格式错误的描述
+pub fn validate_windows(windows: &[MaintenanceWindow]) -> Result<(), String> {
+    for w in windows {
+        if parse_hhmm(&w.start).is_none() || parse_hhmm(&w.end).is_none() {
+            return Err(format!("维护窗口格式错误: {} - {}（应为 HH:MM）", w.start, w.end));
+        }
+        if w.start.trim() == w.end.trim() {
+            return Err(format!("维护窗口起止时间不能相同: {}", w.start));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(start: &str, end: &str) -> MaintenanceWindow {
+        MaintenanceWindow {
+            start: start.to_string(),
+            end: end.to_string(),
+        }
+    }
+
+    fn t(s: &str) -> NaiveTime {
+        parse_hhmm(s).unwrap()
+    }
+
+    #[test]
+    fn test_same_day_window() {
+        let windows = vec![window("02:00", "05:00")];
+        assert!(is_within_windows(&windows, t("02:00")));
+        assert!(is_within_windows(&windows, t("04:59")));
+        assert!(!is_within_windows(&windows, t("05:00")));
+        assert!(!is_within_windows(&windows, t("12:00")));
+    }
+
+    #[test]
+    fn test_cross_midnight_window() {
+        let windows = vec![window("23:00", "06:00")];
+        assert!(is_within_windows(&windows, t("23:30")));
+        assert!(is_within_windows(&windows, t("00:10")));
+        assert!(!is_within_windows(&windows, t("06:00")));
+        assert!(!is_within_windows(&windows, t("15:00")));
+    }
+
+    #[test]
+    fn test_invalid_window_ignored() {
+        let windows = vec![window("25:00", "03:00")];
+        assert!(!is_within_windows(&windows, t("01:00")));
+        assert!(validate_windows(&windows).is_err());
+        assert!(validate_windows(&[window("12:00", "12:00")]).is_err());
+        assert!(validate_windows(&[window("01:00", "03:00")]).is_ok());
+    }
+
+    #[test]
+    fn test_empty_windows_always_allowed() {
+        assert!(is_maintenance_allowed(&[]));
+    }
+}
@@ -0,0 +1,335 @@
+//! 运行指标
+//!
+//! - SSE 流式响应的结束原因，用于排查"响应随机被截断"一类问题
+//! - 最近 1/5/15 分钟的实时吞吐与延迟（按秒分桶的环形缓冲区，供 Admin UI 仪表盘使用）
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// 流式响应结束原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamTermination {
+    /// 上游正常结束
+    Completed,
+    /// 读取上游响应流出错
+    UpstreamError,
+    /// 客户端提前断开（响应流在结束前被丢弃）
+    ClientDisconnect,
+    /// 反代服务被停止
+    ProxyDisabled,
+    /// 读取上游响应超时
+    IdleTimeout,
+}
+
+/// 各结束原因的累计次数
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamTerminationStats {
+    pub completed: u64,
+    pub upstream_error: u64,
+    pub client_disconnect: u64,
+    pub proxy_disabled: u64,
+    pub idle_timeout: u64,
+}
+
+/// 流式响应指标
+#[derive(Default)]
+pub struct StreamMetrics {
+    /// 进行中的流（反代停止排空时用于展示进度）
+    active: AtomicU64,
+    completed: AtomicU64,
+    upstream_error: AtomicU64,
+    client_disconnect: AtomicU64,
+    proxy_disabled: AtomicU64,
+    idle_timeout: AtomicU64,
+}
+
+impl StreamMetrics {
+    /// 记录一次流结束
+    pub fn record(&self, cause: StreamTermination) {
+        let counter = match cause {
+            StreamTermination::Completed => &self.completed,
+            StreamTermination::UpstreamError => &self.upstream_error,
+            StreamTermination::ClientDisconnect => &self.client_disconnect,
+            StreamTermination::ProxyDisabled => &self.proxy_disabled,
+            StreamTermination::IdleTimeout => &self.idle_timeout,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 进行中的流式响应数量
+    pub fn active_streams(&self) -> u64 {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    pub fn snapshot(&self) -> StreamTerminationStats {
+        StreamTerminationStats {
+            completed: self.completed.load(Ordering::Relaxed),
+            upstream_error: self.upstream_error.load(Ordering::Relaxed),
+            client_disconnect: self.client_disconnect.load(Ordering::Relaxed),
+            proxy_disabled: self.proxy_disabled.load(Ordering::Relaxed),
+            idle_timeout: self.idle_timeout.load(Ordering::Relaxed),
+        }
+    }
+}
+
+// 全局流式响应指标
+lazy_static::lazy_static! {
+    pub static ref STREAM_METRICS: StreamMetrics = StreamMetrics::default();
+}
+
+/// 流结束原因记录器
+///
+/// 随响应流一起存活：创建时计入进行中的流，Drop 时按结束原因计数；
+/// 流在标记结束原因之前被丢弃说明客户端已断开
+#[derive(Debug)]
+pub struct TerminationRecorder {
+    cause: Option<StreamTermination>,
+}
+
+impl TerminationRecorder {
+    pub fn new() -> Self {
+        STREAM_METRICS.active.fetch_add(1, Ordering::Relaxed);
+        Self { cause: None }
+    }
+
+    /// 标记结束原因（仅第一次生效）
+    pub fn finish(&mut self, cause: StreamTermination) {
+        self.cause.get_or_insert(cause);
+    }
+}
+
+impl Default for TerminationRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for TerminationRecorder {
+    fn drop(&mut self) {
+        let cause = self.cause.unwrap_or(StreamTermination::ClientDisconnect);
+        tracing::debug!("流式响应结束: {:?}", cause);
+        STREAM_METRICS.active.fetch_sub(1, Ordering::Relaxed);
+        STREAM_METRICS.record(cause);
+    }
+}
+
+/// 实时统计保留的时长（秒），决定最大统计窗口
+const REALTIME_HISTORY_SECS: u64 = 15 * 60;
+
+/// 实时统计的窗口（秒）
+const REALTIME_WINDOWS: [(&str, u64); 3] = [("1m", 60), ("5m", 5 * 60), ("15m", 15 * 60)];
+
+/// 每秒最多保留的延迟样本数（超出后不再采样，避免高并发时内存增长）
+const MAX_LATENCY_SAMPLES_PER_SECOND: usize = 1000;
+
+/// 一秒内的请求统计
+#[derive(Default)]
+struct SecondBucket {
+    /// 所属秒（自统计开始）
+    second: u64,
+    requests: u64,
+    errors: u64,
+    input_tokens: u64,
+    output_tokens: u64,
+    latencies_ms: Vec<u32>,
+}
+
+/// 单个窗口的聚合结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowStats {
+    pub window: &'static str,
+    pub window_secs: u64,
+    pub requests: u64,
+    pub errors: u64,
+    pub requests_per_sec: f64,
+    pub input_tokens_per_sec: f64,
+    pub output_tokens_per_sec: f64,
+    /// 成功请求的延迟中位数（毫秒），无样本时为 null
+    pub p50_latency_ms: Option<u32>,
+    pub p95_latency_ms: Option<u32>,
+    /// 错误率（0-1）
+    pub error_rate: f64,
+}
+
+/// 实时统计快照
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RealtimeSnapshot {
+    pub windows: Vec<WindowStats>,
+    /// 进行中的流式响应
+    pub active_streams: u64,
+}
+
+/// 实时吞吐与延迟统计
+///
+/// 请求完成时写入当前秒的桶，查询时汇总窗口内的桶；桶按秒数取模复用，过期的桶在写入时重置
+pub struct RealtimeStats {
+    origin: Instant,
+    buckets: Mutex<Vec<SecondBucket>>,
+}
+
+impl RealtimeStats {
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+            buckets: Mutex::new((0..REALTIME_HISTORY_SECS).map(|_| SecondBucket::default()).collect()),
+        }
+    }
+
+    fn now_second(&self) -> u64 {
+        self.origin.elapsed().as_secs()
+    }
+
+    /// 记录一次成功完成的请求（延迟从收到请求到响应结束）
+    pub fn record_success(&self, latency: Duration, input_tokens: i32, output_tokens: i32) {
+        self.record_at(self.now_second(), Some(latency), input_tokens, output_tokens);
+    }
+
+    /// 记录一次失败的请求（上游调用失败，不计入延迟）
+    pub fn record_error(&self) {
+        self.record_at(self.now_second(), None, 0, 0);
+    }
+
+    fn record_at(&self, second: u64, latency: Option<Duration>, input_tokens: i32, output_tokens: i32) {
+        let mut buckets = self.buckets.lock();
+        let bucket = &mut buckets[(second % REALTIME_HISTORY_SECS) as usize];
+        if bucket.second != second {
+            *bucket = SecondBucket {
+                second,
+                ..Default::default()
+            };
+        }
+        bucket.requests += 1;
+        match latency {
+            Some(latency) => {
+                bucket.input_tokens += input_tokens.max(0) as u64;
+                bucket.output_tokens += output_tokens.max(0) as u64;
+                if bucket.latencies_ms.len() < MAX_LATENCY_SAMPLES_PER_SECOND {
+                    bucket.latencies_ms.push(latency.as_millis().min(u32::MAX as u128) as u32);
+                }
+            }
+            None => bucket.errors += 1,
+        }
+    }
+
+    /// 汇总 1/5/15 分钟窗口
+    pub fn snapshot(&self) -> RealtimeSnapshot {
+        RealtimeSnapshot {
+            windows: self.windows_at(self.now_second()),
+            active_streams: STREAM_METRICS.active_streams(),
+        }
+    }
+
+    fn windows_at(&self, now: u64) -> Vec<WindowStats> {
+        let buckets = self.buckets.lock();
+        REALTIME_WINDOWS
+            .iter()
+            .map(|&(window, window_secs)| {
+                let mut requests = 0;
+                let mut errors = 0;
+                let mut input_tokens = 0;
+                let mut output_tokens = 0;
+                let mut latencies = Vec::new();
+                for bucket in buckets.iter() {
+                    if bucket.requests == 0 || bucket.second > now || now - bucket.second >= window_secs {
+                        continue;
+                    }
+                    requests += bucket.requests;
+                    errors += bucket.errors;
+                    input_tokens += bucket.input_tokens;
+                    output_tokens += bucket.output_tokens;
+                    latencies.extend_from_slice(&bucket.latencies_ms);
+                }
+                latencies.sort_unstable();
+
+                // 刚启动时按实际经过的时间计算速率
+                let elapsed = window_secs.min(now + 1) as f64;
+                WindowStats {
+                    window,
+                    window_secs,
+                    requests,
+                    errors,
+                    requests_per_sec: requests as f64 / elapsed,
+                    input_tokens_per_sec: input_tokens as f64 / elapsed,
+                    output_tokens_per_sec: output_tokens as f64 / elapsed,
+                    p50_latency_ms: percentile(&latencies, 50),
+                    p95_latency_ms: percentile(&latencies, 95),
+                    error_rate: if requests == 0 { 0.0 } else { errors as f64 / requests as f64 },
+                }
+            })
+            .collect()
+    }
+}
+
+impl Default for RealtimeStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 最近秩法百分位（输入需已排序）
+fn percentile(sorted: &[u32], p: usize) -> Option<u32> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (sorted.len() * p).div_ceil(100).max(1);
+    Some(sorted[rank - 1])
+}
+
+// 全局实时统计
+lazy_static::lazy_static! {
+    pub static ref REALTIME_STATS: RealtimeStats = RealtimeStats::new();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unfinished_recorder_counts_as_client_disconnect() {
+        let before = STREAM_METRICS.snapshot();
+
+        let mut recorder = TerminationRecorder::new();
+        recorder.finish(StreamTermination::IdleTimeout);
+        recorder.finish(StreamTermination::Completed);
+        drop(recorder);
+        drop(TerminationRecorder::new());
+
+        let after = STREAM_METRICS.snapshot();
+        assert_eq!(after.idle_timeout, before.idle_timeout + 1);
+        assert_eq!(after.client_disconnect, before.client_disconnect + 1);
+    }
+
+    #[test]
+    fn test_realtime_windows_aggregate_and_expire() {
+        let stats = RealtimeStats::new();
+        for latency in [100, 200, 300, 400, 1000] {
+            stats.record_at(10, Some(Duration::from_millis(latency)), 50, 20);
+        }
+        stats.record_at(10, None, 0, 0);
+        // 4 分钟前的请求只计入 5m/15m 窗口
+        stats.record_at(10 + 60, Some(Duration::from_millis(50)), 0, 0);
+
+        let windows = stats.windows_at(10 + 4 * 60);
+        assert_eq!(windows[0].requests, 0);
+        assert_eq!(windows[0].p50_latency_ms, None);
+
+        let five = &windows[1];
+        assert_eq!(five.requests, 7);
+        assert_eq!(five.errors, 1);
+        assert_eq!(five.p50_latency_ms, Some(200));
+        assert_eq!(five.p95_latency_ms, Some(1000));
+        assert!((five.error_rate - 1.0 / 7.0).abs() < 1e-9);
+        assert!((five.output_tokens_per_sec - 100.0 / 251.0).abs() < 1e-9);
+
+        // 15 分钟后第 10 秒的桶被复用，旧数据不再计入（第 70 秒的请求仍在窗口内）
+        stats.record_at(10 + REALTIME_HISTORY_SECS, Some(Duration::from_millis(10)), 0, 0);
+        let windows = stats.windows_at(10 + REALTIME_HISTORY_SECS);
+        assert_eq!(windows[2].requests, 2);
+        assert_eq!(windows[2].errors, 0);
+    }
+}
@@ -0,0 +1,1284 @@
+use serde::{Deserialize, Serialize, Deserializer};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// 机器码备份信息
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MachineIdBackup {
+    pub machine_id: String,
+    pub backup_time: String,
+}
+
+// 自定义反序列化：支持旧的字符串格式和新的结构体格式
+impl<'de> Deserialize<'de> for MachineIdBackup {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::{self, MapAccess, Visitor};
+        use std::fmt;
+
+        struct MachineIdBackupVisitor;
+
+        impl<'de> Visitor<'de> for MachineIdBackupVisitor {
+            type Value = MachineIdBackup;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a string or a struct with machineId and backupTime")
+            }
+
+            // 旧格式：直接是字符串
+            fn visit_str<E>(self, value: &str) -> Result<MachineIdBackup, E>
+            where
+                E: de::Error,
+            {
+                Ok(MachineIdBackup {
+                    machine_id: value.to_string(),
+                    backup_time: "未知".to_string(),
+                })
+            }
+
+            // 新格式：结构体
+            fn visit_map<M>(self, mut map: M) -> Result<MachineIdBackup, M::Error>
+            where
+                M: MapAccess<'de>,
+            {
+                let mut machine_id = None;
+                let mut backup_time = None;
+
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "machineId" => machine_id = Some(map.next_value()?),
+                        "backupTime" => backup_time = Some(map.next_value()?),
+                        _ => { let _ = map.next_value::<serde_json::Value>(); }
+                    }
+                }
+
+                Ok(MachineIdBackup {
+                    machine_id: machine_id.ok_or_else(|| de::Error::missing_field("machineId"))?,
+                    backup_time: backup_time.unwrap_or_else(|| "未知".to_string()),
+                })
+            }
+        }
+
+        deserializer.deserialize_any(MachineIdBackupVisitor)
+    }
+}
+
+/// 机器码备份历史最多保留的条数
+pub const MAX_MACHINE_ID_BACKUPS: usize = 10;
+
+/// KNA 应用配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Config {
+    #[serde(default = "default_host")]
+    pub host: String,
+
+    /// 监听地址列表（如 `["127.0.0.1", "::1"]`，为空时只监听 `host`）
+    #[serde(default)]
+    pub listen_hosts: Vec<String>,
+
+    #[serde(default = "default_port")]
+    pub port: u16,
+
+    /// 反代服务独立端口（可选，默认 8991）
+    #[serde(default = "default_proxy_port")]
+    pub proxy_port: u16,
+
+    #[serde(default = "default_region")]
+    pub region: String,
+
+    #[serde(default = "default_kiro_version")]
+    pub kiro_version: String,
+
+    #[serde(default)]
+    pub api_key: Option<String>,
+
+    /// Admin API 密钥（为空时启动时自动生成并保存）
+    #[serde(default)]
+    pub admin_api_key: Option<String>,
+
+    #[serde(default = "default_system_version")]
+    pub system_version: String,
+
+    #[serde(default = "default_node_version")]
+    pub node_version: String,
+
+    /// 锁定的模型名称（可选，仅影响客户端操作）
+    ///
+    /// 只写入 Kiro 客户端 settings.json 的模型选择，反代请求（`/v1/messages`）不受影响，始终使用请求中的模型
+    #[serde(default)]
+    pub locked_model: Option<String>,
+
+    /// 锁定模型作用的 Kiro Profile ID（`default` 或 `User/profiles` 下的目录名；为空时使用第一个包含 settings.json 的 Profile）
+    #[serde(default)]
+    pub kiro_profiles: Vec<String>,
+
+    /// 机器码备份（可选，用于恢复；始终为最近一次备份）
+    #[serde(default)]
+    pub machine_id_backup: Option<MachineIdBackup>,
+
+    /// 机器码备份历史（按时间先后，最多保留 `MAX_MACHINE_ID_BACKUPS` 条）
+    #[serde(default)]
+    pub machine_id_backups: Vec<MachineIdBackup>,
+
+    /// 分组列表（id -> 名称映射）
+    #[serde(default = "default_groups")]
+    pub groups: Vec<GroupConfig>,
+
+    /// 反代使用的分组 ID（为空表示使用所有分组）
+    #[serde(default)]
+    pub active_group_id: Option<String>,
+
+    /// 备用分组：活跃分组的凭证全部耗尽（余额用尽或已禁用）时自动转移到该分组
+    #[serde(default)]
+    pub fallback_group_id: Option<String>,
+
+    /// 分组反代实例：每个分组独立监听一个端口，与主反代服务同时运行
+    #[serde(default)]
+    pub group_listeners: Vec<GroupListener>,
+
+    /// 反代服务是否自动启动
+    #[serde(default)]
+    pub proxy_auto_start: bool,
+
+    /// 开机自启（仅桌面应用，启动后最小化到系统托盘）
+    #[serde(default)]
+    pub autostart: bool,
+
+    /// 是否启用自动刷新 Token
+    #[serde(default)]
+    pub auto_refresh_enabled: bool,
+
+    /// 自动刷新间隔（分钟），默认 10 分钟
+    #[serde(default = "default_auto_refresh_interval")]
+    pub auto_refresh_interval_minutes: u32,
+
+    /// 维护时间窗口（本地时间），后台刷新等任务仅在窗口内执行；为空表示不限制
+    #[serde(default)]
+    pub maintenance_windows: Vec<MaintenanceWindow>,
+
+    /// 多租户 API Key 列表（apiKey 仍作为不受限的主密钥）
+    #[serde(default)]
+    pub api_keys: Vec<ApiKeyConfig>,
+
+    /// 反代请求限流（按客户端 IP / API Key）
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+
+    /// 反代请求规模限制（请求体大小、消息数、工具数）
+    #[serde(default)]
+    pub request_limits: RequestLimitsConfig,
+
+    /// 上游对话请求超时（连接、首字节、总时长、流式空闲）
+    #[serde(default)]
+    pub upstream_timeouts: UpstreamTimeoutConfig,
+
+    /// 凭证自动禁用策略（按错误类型分别设置阈值和计数窗口）
+    #[serde(default)]
+    pub failure_policy: FailurePolicyConfig,
+
+    /// 会话粘性路由：同一会话（metadata.user_id 中的 session）固定使用同一凭证，仅在失败时切换
+    #[serde(default)]
+    pub session_affinity_enabled: bool,
+
+    /// max_tokens 超出模型最大输出或剩余上下文时自动压低（关闭时超出最大输出直接报错）
+    #[serde(default)]
+    pub auto_clamp_max_tokens: bool,
+
+    /// 对话历史超出上下文窗口时的压缩策略（默认关闭，直接报错）
+    #[serde(default)]
+    pub history_compaction: HistoryCompaction,
+
+    /// 流式响应断线续传窗口（秒，0 表示关闭）
+    ///
+    /// 开启后流式事件带递增 ID，客户端在流结束后该时间内携带 `Last-Event-ID` 重新请求，
+    /// 可从断点继续接收，而不会丢失已生成的部分响应
+    #[serde(default)]
+    pub stream_resume_secs: u64,
+
+    /// 凭证路由策略（默认按优先级）
+    #[serde(default)]
+    pub routing_strategy: RoutingStrategy,
+
+    /// 响应水印（标明网关版本和凭证分组）
+    #[serde(default)]
+    pub watermark: WatermarkConfig,
+
+    /// 停止反代时等待进行中流式响应结束的宽限期（秒），超时后强制中断
+    #[serde(default = "default_proxy_drain_timeout")]
+    pub proxy_drain_timeout_secs: u64,
+
+    /// 获取可用凭证的总超时（秒），超时返回 credential_unavailable；0 表示不限制
+    #[serde(default = "default_credential_acquire_timeout")]
+    pub credential_acquire_timeout_secs: u64,
+
+    /// 单个凭证同时处理的上游请求数上限，超出的请求进入排队；0 表示不限制
+    #[serde(default)]
+    pub max_concurrent_per_credential: usize,
+
+    /// 等待凭证空闲槽位的请求数上限（全局），队列满时直接拒绝
+    #[serde(default = "default_request_queue_size")]
+    pub request_queue_size: usize,
+
+    /// 请求排队的最长等待时间（秒），超时返回 overloaded；0 表示不限制
+    #[serde(default = "default_request_queue_timeout")]
+    pub request_queue_timeout_secs: u64,
+
+    /// 凭证健康检查（独立于自动刷新的定期探测）
+    #[serde(default)]
+    pub health_check: HealthCheckConfig,
+
+    /// 额度告警与每日用量报告
+    #[serde(default)]
+    pub alerts: AlertConfig,
+
+    /// 凭证生命周期 Webhook（自动禁用、故障转移、刷新连续失败）
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
+
+    /// HTTPS 监听（Admin 与反代服务共用，修改后需重启）
+    #[serde(default)]
+    pub tls: TlsConfig,
+
+    /// 局域网共享模式（反代监听 0.0.0.0，按 IP / 设备令牌放行）
+    #[serde(default)]
+    pub lan_access: LanAccessConfig,
+
+    /// 跨域（CORS）策略，Admin 与反代服务共用（修改后需重启）
+    #[serde(default)]
+    pub cors: CorsConfig,
+
+    /// 日志文件（JSON Lines，按天和大小轮转）
+    #[serde(default)]
+    pub log_file: LogFileConfig,
+
+    /// 非流式请求的幂等键缓存（`Idempotency-Key`）
+    #[serde(default)]
+    pub idempotency: IdempotencyConfig,
+
+    /// 相同请求的响应缓存（默认关闭，适合反复发送相同提示的测试场景）
+    #[serde(default)]
+    pub response_cache: ResponseCacheConfig,
+
+    /// `/v1/models` 返回的模型目录（可增删或停用，无需发版）
+    #[serde(default = "default_models")]
+    pub models: Vec<ModelEntry>,
+
+    /// 模型名映射表（按顺序匹配，如 gpt-4o -> claude-sonnet-4-5）
+    #[serde(default)]
+    pub model_mappings: Vec<ModelMapping>,
+
+    /// 导入凭证时的自动分组规则（按顺序匹配，首条命中生效）
+    #[serde(default)]
+    pub group_rules: Vec<GroupRule>,
+
+    /// 凭证轮换计划（本地时间，按时段切换活跃分组或当前凭证）
+    #[serde(default)]
+    pub rotation_schedule: Vec<RotationRule>,
+
+    /// 全局请求改写（对所有 /v1/messages 与 Gemini 请求生效）
+    #[serde(default)]
+    pub request_transform: RequestTransform,
+
+    /// 出站内容脱敏（请求发往 Kiro 前替换匹配的敏感内容）
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+
+    /// 响应后处理（文本到达客户端前执行改写和去重规则）
+    #[serde(default)]
+    pub post_process: PostProcessConfig,
+
+    /// 对话记录存储（默认关闭，保存完整的请求消息和响应用于审计和调试）
+    #[serde(default)]
+    pub transcripts: TranscriptConfig,
+}
+
+/// 凭证路由策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RoutingStrategy {
+    /// 沿用当前凭证，不可用时切换到 ID 最小的可用凭证
+    #[default]
+    Priority,
+    /// 每次选择剩余额度最多的凭证，额度由后台定期刷新，使各账户均匀消耗
+    LeastUsage,
+}
+
+/// 对话历史压缩策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HistoryCompaction {
+    /// 不压缩，输入超出上下文窗口时返回错误
+    #[default]
+    Off,
+    /// 按轮次移除最早的对话
+    DropOldest,
+    /// 移除最早的对话，并以被移除消息的摘录代替
+    Summarize,
+}
+
+/// 限流配置（各项为空表示不限制）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 每个客户端 IP 每分钟请求数
+    #[serde(default)]
+    pub per_ip_rpm: Option<u32>,
+    /// 每个 API Key 每分钟请求数
+    #[serde(default)]
+    pub per_key_rpm: Option<u32>,
+    /// 每个客户端 IP 每分钟输入 tokens（按请求体大小估算）
+    #[serde(default)]
+    pub per_ip_tpm: Option<u32>,
+    /// 每个 API Key 每分钟输入 tokens（按请求体大小估算）
+    #[serde(default)]
+    pub per_key_tpm: Option<u32>,
+}
+
+/// 请求规模限制，防止超大请求（如大量 base64 图片）占满内存
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestLimitsConfig {
+    /// 请求体大小上限（MB），默认 32；修改后重启反代服务生效
+    #[serde(default = "default_max_body_mb")]
+    pub max_body_mb: u64,
+    /// 单个请求的消息数上限，默认 10000；0 表示不限制
+    #[serde(default = "default_max_messages")]
+    pub max_messages: usize,
+    /// 单个请求的工具数上限，默认 500；0 表示不限制
+    #[serde(default = "default_max_tools")]
+    pub max_tools: usize,
+}
+
+impl Default for RequestLimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_body_mb: default_max_body_mb(),
+            max_messages: default_max_messages(),
+            max_tools: default_max_tools(),
+        }
+    }
+}
+
+impl RequestLimitsConfig {
+    /// 请求体大小上限（字节）
+    pub fn max_body_bytes(&self) -> usize {
+        (self.max_body_mb.max(1) as usize).saturating_mul(1024 * 1024)
+    }
+}
+
+fn default_max_body_mb() -> u64 {
+    32
+}
+
+fn default_max_messages() -> usize {
+    10_000
+}
+
+fn default_max_tools() -> usize {
+    500
+}
+
+/// 上游对话请求超时（秒），修改后重启反代服务生效
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpstreamTimeoutConfig {
+    /// 建立连接超时，默认 30
+    #[serde(default = "default_connect_timeout")]
+    pub connect_secs: u64,
+    /// 发出请求到收到响应头的超时，默认 180；超时后换凭证重试
+    #[serde(default = "default_first_byte_timeout")]
+    pub first_byte_secs: u64,
+    /// 单次请求（含读取完整响应）的总超时，默认 720
+    #[serde(default = "default_total_timeout")]
+    pub total_secs: u64,
+    /// 流式响应两次收到数据之间的最长间隔，默认 180；超时后向客户端发送错误事件并结束流，0 表示不限制
+    #[serde(default = "default_idle_timeout")]
+    pub idle_secs: u64,
+}
+
+impl Default for UpstreamTimeoutConfig {
+    fn default() -> Self {
+        Self {
+            connect_secs: default_connect_timeout(),
+            first_byte_secs: default_first_byte_timeout(),
+            total_secs: default_total_timeout(),
+            idle_secs: default_idle_timeout(),
+        }
+    }
+}
+
+impl UpstreamTimeoutConfig {
+    /// 首字节超时（0 表示不限制）
+    pub fn first_byte(&self) -> Option<Duration> {
+        (self.first_byte_secs > 0).then(|| Duration::from_secs(self.first_byte_secs))
+    }
+
+    /// 流式空闲超时（0 表示不限制）
+    pub fn idle(&self) -> Option<Duration> {
+        (self.idle_secs > 0).then(|| Duration::from_secs(self.idle_secs))
+    }
+}
+
+/// 凭证自动禁用策略，修改后重启反代服务生效
+///
+/// 各类错误分别计数，某一类在计数窗口内达到阈值时禁用凭证；调用成功后全部清零。
+/// 账户暂停、refresh token 失效等错误始终立即禁用，额度用尽和 429 限流另有处理，均不受此配置影响
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FailurePolicyConfig {
+    /// 401/403 凭证或权限错误，默认 3 次
+    #[serde(default = "default_counted_failure_rule")]
+    pub auth: FailureRule,
+    /// 408/5xx 上游瞬态错误，默认不计数
+    #[serde(default)]
+    pub server: FailureRule,
+    /// 网络错误和首字节超时，默认不计数（网络抖动不应禁用凭证）
+    #[serde(default)]
+    pub network: FailureRule,
+    /// 其他错误（如 MCP 调用失败），默认 3 次
+    #[serde(default = "default_counted_failure_rule")]
+    pub other: FailureRule,
+}
+
+impl Default for FailurePolicyConfig {
+    fn default() -> Self {
+        Self {
+            auth: default_counted_failure_rule(),
+            server: FailureRule::default(),
+            network: FailureRule::default(),
+            other: default_counted_failure_rule(),
+        }
+    }
+}
+
+/// 单类错误的禁用阈值
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FailureRule {
+    /// 达到该次数时禁用凭证，0 表示不计数
+    #[serde(default)]
+    pub threshold: u32,
+    /// 计数窗口（分钟），更早的失败不再计入；0 表示不过期（直到调用成功才清零）
+    #[serde(default)]
+    pub decay_mins: u64,
+}
+
+impl FailureRule {
+    /// 计数窗口（None 表示不过期）
+    pub fn decay(&self) -> Option<Duration> {
+        (self.decay_mins > 0).then(|| Duration::from_secs(self.decay_mins * 60))
+    }
+}
+
+fn default_counted_failure_rule() -> FailureRule {
+    FailureRule {
+        threshold: 3,
+        decay_mins: 0,
+    }
+}
+
+fn default_connect_timeout() -> u64 {
+    30
+}
+
+fn default_first_byte_timeout() -> u64 {
+    180
+}
+
+fn default_total_timeout() -> u64 {
+    720
+}
+
+fn default_idle_timeout() -> u64 {
+    180
+}
+
+/// 响应水印配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatermarkConfig {
+    /// 是否添加 X-Kiro-Gateway 响应头
+    #[serde(default)]
+    pub enabled: bool,
+    /// 流式响应末尾是否追加同内容的 SSE 注释行
+    #[serde(default)]
+    pub sse_comment: bool,
+}
+
+/// 模型目录条目
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelEntry {
+    pub id: String,
+    /// 显示名称（为空时使用 id）
+    #[serde(default)]
+    pub display_name: String,
+    /// 最大输出 tokens（为空时按模型能力表）
+    #[serde(default)]
+    pub max_tokens: Option<i32>,
+    /// 发布时间（Unix 秒）
+    #[serde(default)]
+    pub created: i64,
+    /// 停用的模型不出现在模型列表中
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+/// 模型名映射（from 以 `*` 结尾时按前缀匹配，忽略大小写）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelMapping {
+    pub from: String,
+    pub to: String,
+}
+
+/// 自动分组规则匹配的凭证属性
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GroupRuleField {
+    /// 邮箱域名（如 corp.com，忽略大小写）
+    EmailDomain,
+    /// 订阅类型（包含匹配，如 FREE 命中 "KIRO FREE"）
+    Subscription,
+    /// 认证方式（social / idc）
+    AuthMethod,
+}
+
+/// 自动分组规则：凭证属性匹配 pattern 时放入 group_id 分组
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupRule {
+    pub field: GroupRuleField,
+    pub pattern: String,
+    pub group_id: String,
+}
+
+/// 凭证健康检查配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthCheckConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 检查间隔（分钟），默认 15 分钟
+    #[serde(default = "default_health_check_interval")]
+    pub interval_minutes: u32,
+}
+
+impl Default for HealthCheckConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_minutes: default_health_check_interval(),
+        }
+    }
+}
+
+fn default_health_check_interval() -> u32 {
+    15
+}
+
+/// 告警 Webhook 消息格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WebhookFormat {
+    /// 原样 POST 告警 JSON
+    #[default]
+    Generic,
+    /// Slack Incoming Webhook（`{"text": ...}`）
+    Slack,
+    /// Discord Webhook（`{"content": ...}`）
+    Discord,
+}
+
+/// 凭证生命周期事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WebhookEventKind {
+    /// 凭证被自动禁用（账户暂停 / 连续失败）
+    CredentialDisabled,
+    /// 故障转移到其他凭证
+    Failover,
+    /// Token 连续刷新失败
+    RefreshFailed,
+    /// 活跃分组耗尽，转移到备用分组（或恢复）
+    GroupFailover,
+}
+
+/// 生命周期 Webhook 配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookConfig {
+    pub url: String,
+    /// 消息格式
+    #[serde(default)]
+    pub format: WebhookFormat,
+    /// 订阅的事件，为空时订阅全部
+    #[serde(default)]
+    pub events: Vec<WebhookEventKind>,
+}
+
+impl WebhookConfig {
+    /// 是否订阅了指定事件
+    pub fn subscribes(&self, event: WebhookEventKind) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+}
+
+/// 额度告警配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertConfig {
+    /// 是否启用告警（日志、桌面通知、Webhook）
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 剩余额度低于上限的百分比时告警，默认 10%
+    #[serde(default = "default_low_quota_percent")]
+    pub low_quota_percent: f64,
+    /// 告警 Webhook 地址（可选）
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    /// Webhook 消息格式
+    #[serde(default)]
+    pub webhook_format: WebhookFormat,
+    /// 是否发送每日用量报告
+    #[serde(default)]
+    pub daily_report_enabled: bool,
+    /// 每日报告发送时间（本地时间的小时，0-23），默认 9 点
+    #[serde(default = "default_daily_report_hour")]
+    pub daily_report_hour: u32,
+}
+
+/// HTTPS 监听配置
+///
+/// 未指定证书路径时使用配置目录下的 `tls/cert.pem` 与 `tls/key.pem`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TlsConfig {
+    /// 是否启用 HTTPS
+    #[serde(default)]
+    pub enabled: bool,
+    /// 证书文件路径（PEM，可包含证书链）
+    #[serde(default)]
+    pub cert_path: Option<String>,
+    /// 私钥文件路径（PEM）
+    #[serde(default)]
+    pub key_path: Option<String>,
+    /// 证书不存在时自动生成自签名证书
+    #[serde(default)]
+    pub self_signed: bool,
+}
+
+/// 跨域（CORS）配置
+///
+/// 默认允许任意来源（兼容旧版本）；局域网部署时建议只列出实际使用的前端来源
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CorsConfig {
+    /// 允许的来源（如 `http://192.168.1.20:3000`），`*` 表示任意来源，为空时拒绝所有跨域请求
+    #[serde(default = "default_cors_origins")]
+    pub allowed_origins: Vec<String>,
+    /// 是否允许携带凭据（Cookie / Authorization），开启时不能使用 `*`
+    #[serde(default)]
+    pub allow_credentials: bool,
+    /// 预检结果缓存时间（秒，0 表示不缓存）
+    #[serde(default = "default_cors_max_age_secs")]
+    pub max_age_secs: u64,
+}
+
+fn default_cors_origins() -> Vec<String> {
+    vec!["*".to_string()]
+}
+
+fn default_cors_max_age_secs() -> u64 {
+    600
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: default_cors_origins(),
+            allow_credentials: false,
+            max_age_secs: default_cors_max_age_secs(),
+        }
+    }
+}
+
+/// 局域网共享配置
+///
+/// 启用后反代服务监听所有网卡，默认拒绝：只有本机、白名单 IP（支持 CIDR）
+/// 和携带已登记设备令牌（`x-device-token` 请求头）的客户端可以访问
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanAccessConfig {
+    /// 是否启用局域网共享
+    #[serde(default)]
+    pub enabled: bool,
+    /// 允许访问的 IP 或网段（如 192.168.1.20、192.168.1.0/24）
+    #[serde(default)]
+    pub allowed_ips: Vec<String>,
+    /// 已登记的设备
+    #[serde(default)]
+    pub devices: Vec<LanDevice>,
+}
+
+/// 局域网设备
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanDevice {
+    /// 设备名称
+    pub name: String,
+    /// 设备令牌（为空时由服务端生成）
+    #[serde(default)]
+    pub token: String,
+}
+
+/// 日志文件配置
+///
+/// 日志写入配置目录下的 `logs/`，每天一个文件，超过大小上限时追加序号另起新文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogFileConfig {
+    /// 是否写入日志文件
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 单个文件大小上限（MB），默认 20
+    #[serde(default = "default_log_max_file_size_mb")]
+    pub max_file_size_mb: u64,
+    /// 日志保留天数（0 表示不清理），默认 14 天
+    #[serde(default = "default_log_retention_days")]
+    pub retention_days: u32,
+}
+
+impl Default for LogFileConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_file_size_mb: default_log_max_file_size_mb(),
+            retention_days: default_log_retention_days(),
+        }
+    }
+}
+
+fn default_log_max_file_size_mb() -> u64 {
+    20
+}
+
+fn default_log_retention_days() -> u32 {
+    14
+}
+
+/// 幂等键缓存配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdempotencyConfig {
+    /// 是否按幂等键缓存非流式响应（默认关闭）
+    #[serde(default)]
+    pub enabled: bool,
+    /// 缓存保留时间（秒），默认 10 分钟
+    #[serde(default = "default_idempotency_ttl_secs")]
+    pub ttl_secs: u64,
+    /// 最多缓存的响应数，默认 1000
+    #[serde(default = "default_idempotency_max_entries")]
+    pub max_entries: usize,
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: default_idempotency_ttl_secs(),
+            max_entries: default_idempotency_max_entries(),
+        }
+    }
+}
+
+fn default_idempotency_ttl_secs() -> u64 {
+    600
+}
+
+fn default_idempotency_max_entries() -> usize {
+    1000
+}
+
+/// 响应缓存配置（LRU + TTL）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResponseCacheConfig {
+    /// 是否缓存相同请求的非流式响应
+    #[serde(default)]
+    pub enabled: bool,
+    /// 缓存保留时间（秒），默认 5 分钟
+    #[serde(default = "default_response_cache_ttl_secs")]
+    pub ttl_secs: u64,
+    /// 最多缓存的响应数（超出时淘汰最久未使用的），默认 500
+    #[serde(default = "default_response_cache_max_entries")]
+    pub max_entries: usize,
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: default_response_cache_ttl_secs(),
+            max_entries: default_response_cache_max_entries(),
+        }
+    }
+}
+
+fn default_response_cache_ttl_secs() -> u64 {
+    300
+}
+
+fn default_response_cache_max_entries() -> usize {
+    500
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            low_quota_percent: default_low_quota_percent(),
+            webhook_url: None,
+            webhook_format: WebhookFormat::default(),
+            daily_report_enabled: false,
+            daily_report_hour: default_daily_report_hour(),
+        }
+    }
+}
+
+fn default_low_quota_percent() -> f64 {
+    10.0
+}
+
+fn default_daily_report_hour() -> u32 {
+    9
+}
+
+/// 租户 API Key 配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyConfig {
+    pub id: String,
+    pub name: String,
+    pub key: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 允许使用的模型（为空表示不限制，按完整名称匹配，`*` 为通配符，如 "claude-sonnet-4-5*"）
+    #[serde(default)]
+    pub allowed_models: Vec<String>,
+    /// 每分钟请求数上限
+    #[serde(default)]
+    pub rate_limit_rpm: Option<u32>,
+    /// 每月 token 配额（输入 + 输出）
+    #[serde(default)]
+    pub monthly_token_quota: Option<u64>,
+    /// 该 Key 的请求改写（在全局改写之后执行）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_transform: Option<RequestTransform>,
+}
+
+/// 请求改写：注入系统提示、移除工具、限制 max_tokens，用于统一执行组织级约束
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestTransform {
+    /// 插入到系统提示最前面的文本
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prepend: Option<String>,
+    /// 追加到系统提示末尾的文本
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_append: Option<String>,
+    /// 移除的工具名（精确匹配）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub strip_tools: Vec<String>,
+    /// max_tokens 上限（超出时压到上限）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens_cap: Option<i32>,
+}
+
+impl RequestTransform {
+    /// 是否没有任何改写
+    pub fn is_empty(&self) -> bool {
+        self.system_prepend.as_deref().is_none_or(|s| s.trim().is_empty())
+            && self.system_append.as_deref().is_none_or(|s| s.trim().is_empty())
+            && self.strip_tools.is_empty()
+            && self.max_tokens_cap.is_none()
+    }
+}
+
+/// 出站内容脱敏配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedactionConfig {
+    /// 脱敏规则（按顺序执行）
+    #[serde(default)]
+    pub rules: Vec<RedactionRule>,
+    /// 是否在响应头中返回各规则的命中次数
+    #[serde(default = "default_true")]
+    pub report_header: bool,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            report_header: true,
+        }
+    }
+}
+
+/// 脱敏规则：关键词（逐字匹配，忽略大小写）或正则表达式
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedactionRule {
+    /// 规则名（出现在响应头的命中报告中，只能包含字母、数字、`-` 和 `_`）
+    pub name: String,
+    pub pattern: String,
+    /// `pattern` 是否为正则表达式
+    #[serde(default)]
+    pub regex: bool,
+    /// 替换文本（默认 `[REDACTED:规则名]`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replacement: Option<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+/// 响应后处理配置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostProcessConfig {
+    /// 改写规则（按顺序执行，逐行匹配）
+    #[serde(default)]
+    pub rules: Vec<PostProcessRule>,
+    /// 是否删除与上一非空行完全相同的行
+    #[serde(default)]
+    pub dedupe_lines: bool,
+}
+
+/// 响应改写规则：正则替换，替换后为空的行整行删除
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostProcessRule {
+    pub name: String,
+    /// 正则表达式（逐行匹配，`^` / `$` 为行首行尾）
+    pub pattern: String,
+    /// 替换文本（支持 `$1` 等捕获组引用，默认删除匹配内容）
+    #[serde(default)]
+    pub replacement: String,
+    /// 只作用于回复开头（尚未输出可见文本时），用于清除开头注入的元数据
+    #[serde(default)]
+    pub leading: bool,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+/// 对话记录存储配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptConfig {
+    /// 是否保存对话记录
+    #[serde(default)]
+    pub enabled: bool,
+    /// 保留天数（0 表示不按时间清理），默认 30 天
+    #[serde(default = "default_transcript_retention_days")]
+    pub retention_days: u32,
+    /// 记录文件总大小上限（MB，0 表示不限制），超出时删除最早的文件，默认 500 MB
+    #[serde(default = "default_transcript_max_size_mb")]
+    pub max_size_mb: u64,
+}
+
+impl Default for TranscriptConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retention_days: default_transcript_retention_days(),
+            max_size_mb: default_transcript_max_size_mb(),
+        }
+    }
+}
+
+fn default_transcript_retention_days() -> u32 {
+    30
+}
+
+fn default_transcript_max_size_mb() -> u64 {
+    500
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// 维护时间窗口（格式 HH:MM，支持跨午夜，如 23:00 - 06:00）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceWindow {
+    pub start: String,
+    pub end: String,
+}
+
+/// 凭证轮换规则：在 [start, end) 时段内切换到指定分组和/或凭证（格式 HH:MM，支持跨午夜）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RotationRule {
+    pub start: String,
+    pub end: String,
+    /// 时段内的活跃分组
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_id: Option<String>,
+    /// 时段内优先使用的凭证（优先级路由下生效）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential_id: Option<u64>,
+}
+
+/// 分组配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupConfig {
+    pub id: String,
+    pub name: String,
+    /// 月度预算（None 表示不限制）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<GroupBudget>,
+}
+
+/// 分组月度预算，token 与请求数任一达到上限即视为超出
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupBudget {
+    /// 每月 token 上限（输入 + 输出）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monthly_tokens: Option<u64>,
+    /// 每月请求数上限
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monthly_requests: Option<u64>,
+    /// 超出预算后的处理方式
+    #[serde(default)]
+    pub policy: BudgetPolicy,
+}
+
+/// 分组超出预算后的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BudgetPolicy {
+    /// 拒绝请求（429）
+    #[default]
+    Reject,
+    /// 作为活跃分组时转移到备用分组；无可用备用分组或固定分组时仍拒绝
+    Fallback,
+}
+
+/// 分组反代实例（分组 → 端口）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupListener {
+    pub group_id: String,
+    pub port: u16,
+    /// 是否运行（关闭后保留映射）
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_models() -> Vec<ModelEntry> {
+    let model = |id: &str, display_name: &str, created: i64| ModelEntry {
+        id: id.to_string(),
+        display_name: display_name.to_string(),
+        max_tokens: None,
+        created,
+        enabled: true,
+    };
+    vec![
+        model("claude-sonnet-4-5-20250929", "Claude Sonnet 4.5", 1727568000),
+        model("claude-opus-4-5-20251101", "Claude Opus 4.5", 1730419200),
+        model("claude-haiku-4-5-20251001", "Claude Haiku 4.5", 1727740800),
+    ]
+}
+
+fn default_groups() -> Vec<GroupConfig> {
+    vec![GroupConfig {
+        id: "default".to_string(),
+        name: "默认分组".to_string(),
+        budget: None,
+    }]
+}
+
+fn default_host() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_port() -> u16 {
+    8990
+}
+
+fn default_proxy_port() -> u16 {
+    8991
+}
+
+fn default_region() -> String {
+    "us-east-1".to_string()
+}
+
+fn default_kiro_version() -> String {
+    "0.8.0".to_string()
+}
+
+fn default_system_version() -> String {
+    const SYSTEM_VERSIONS: &[&str] = &["darwin#24.6.0", "win32#10.0.22631"];
+    SYSTEM_VERSIONS[fastrand::usize(..SYSTEM_VERSIONS.len())].to_string()
+}
+
+fn default_node_version() -> String {
+    "22.21.1".to_string()
+}
+
+fn default_auto_refresh_interval() -> u32 {
+    10 // 默认 10 分钟
+}
+
+fn default_proxy_drain_timeout() -> u64 {
+    30
+}
+
+fn default_credential_acquire_timeout() -> u64 {
+    20
+}
+
+fn default_request_queue_size() -> usize {
+    64
+}
+
+fn default_request_queue_timeout() -> u64 {
+    60
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            host: default_host(),
+            listen_hosts: Vec::new(),
+            port: default_port(),
+            proxy_port: default_proxy_port(),
+            region: default_region(),
+            kiro_version: default_kiro_version(),
+            api_key: None,
+            admin_api_key: None,
+            system_version: default_system_version(),
+            node_version: default_node_version(),
+            locked_model: None,
+            kiro_profiles: Vec::new(),
+            machine_id_backup: None,
+            machine_id_backups: Vec::new(),
+            groups: default_groups(),
+            active_group_id: None,
+            fallback_group_id: None,
+            group_listeners: Vec::new(),
+            proxy_auto_start: false,
+            autostart: false,
+            auto_refresh_enabled: false,
+            auto_refresh_interval_minutes: default_auto_refresh_interval(),
+            maintenance_windows: Vec::new(),
+            api_keys: Vec::new(),
+            rate_limit: RateLimitConfig::default(),
+            request_limits: RequestLimitsConfig::default(),
+            upstream_timeouts: UpstreamTimeoutConfig::default(),
+            failure_policy: FailurePolicyConfig::default(),
+            session_affinity_enabled: false,
+            auto_clamp_max_tokens: false,
+            history_compaction: HistoryCompaction::default(),
+            stream_resume_secs: 0,
+            routing_strategy: RoutingStrategy::default(),
+            watermark: WatermarkConfig::default(),
+            proxy_drain_timeout_secs: default_proxy_drain_timeout(),
+            credential_acquire_timeout_secs: default_credential_acquire_timeout(),
+            max_concurrent_per_credential: 0,
+            request_queue_size: default_request_queue_size(),
+            request_queue_timeout_secs: default_request_queue_timeout(),
+            health_check: HealthCheckConfig::default(),
+            alerts: AlertConfig::default(),
+            webhooks: Vec::new(),
+            tls: TlsConfig::default(),
+            lan_access: LanAccessConfig::default(),
+            cors: CorsConfig::default(),
+            log_file: LogFileConfig::default(),
+            idempotency: IdempotencyConfig::default(),
+            response_cache: ResponseCacheConfig::default(),
+            models: default_models(),
+            model_mappings: Vec::new(),
+            group_rules: Vec::new(),
+            rotation_schedule: Vec::new(),
+            request_transform: RequestTransform::default(),
+            redaction: RedactionConfig::default(),
+            post_process: PostProcessConfig::default(),
+            transcripts: TranscriptConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    /// 获取默认配置文件路径
+    pub fn default_config_path() -> &'static str {
+        "config.json"
+    }
+
+    /// 机器码备份历史（最新的在前；旧版本只保存了单个备份时返回该备份）
+    pub fn machine_id_backup_history(&self) -> Vec<MachineIdBackup> {
+        if self.machine_id_backups.is_empty() {
+            return self.machine_id_backup.iter().cloned().collect();
+        }
+        self.machine_id_backups.iter().rev().cloned().collect()
+    }
+
+    /// 记录一次机器码备份（超出上限时丢弃最早的备份）
+    pub fn push_machine_id_backup(&mut self, machine_id: String) {
+        if self.machine_id_backups.is_empty() {
+            self.machine_id_backups.extend(self.machine_id_backup.take());
+        }
+        let backup = MachineIdBackup {
+            machine_id,
+            backup_time: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        };
+        self.machine_id_backups.push(backup.clone());
+        let excess = self.machine_id_backups.len().saturating_sub(MAX_MACHINE_ID_BACKUPS);
+        self.machine_id_backups.drain(..excess);
+        self.machine_id_backup = Some(backup);
+    }
+
+    /// 从文件加载配置
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            // 配置文件不存在，返回默认配置
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(path)?;
+        let config: Config = serde_json::from_str(&content)?;
+        Ok(config)
+    }
+
+    /// 从文件加载配置，如果不存在则创建默认配置文件
+    pub fn load_or_create<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            let default_config = Self::default();
+            default_config.save(path)?;
+            tracing::info!("已创建默认配置文件: {:?}", path);
+            return Ok(default_config);
+        }
+        Self::load(path)
+    }
+
+    /// 保存配置到文件
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+}
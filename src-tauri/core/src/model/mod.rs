@@ -1,4 +1,3 @@
 //! 应用配置模型
 
-pub mod arg;
 pub mod config;
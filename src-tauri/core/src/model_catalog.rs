@@ -0,0 +1,189 @@
+//! 模型目录
+//!
+//! `/v1/models` 返回的模型列表来自配置中的 `models`，默认包含当前支持的 Claude 模型。
+//! 管理员可以在 Kiro 支持新模型时直接添加条目，或停用不希望客户端看到的模型，无需发版。
+
+use lazy_static::lazy_static;
+use parking_lot::RwLock;
+
+use crate::anthropic::limits::model_capability;
+use crate::anthropic::types::Model;
+use crate::model::config::ModelEntry;
+
+/// 校验单个条目
+fn validate_entry(entry: &ModelEntry) -> Result<(), String> {
+    if entry.id.trim().is_empty() {
+        return Err("模型 ID 不能为空".to_string());
+    }
+    if entry.max_tokens.is_some_and(|v| v <= 0) {
+        return Err(format!("模型 {} 的 maxTokens 必须大于 0", entry.id));
+    }
+    Ok(())
+}
+
+/// 校验模型目录：条目合法且 ID 不重复（忽略大小写）
+pub fn validate(models: &[ModelEntry]) -> Result<(), String> {
+    let mut seen = std::collections::HashSet::new();
+    for entry in models {
+        validate_entry(entry)?;
+        if !seen.insert(entry.id.trim().to_lowercase()) {
+            return Err(format!("模型 ID 重复: {}", entry.id));
+        }
+    }
+    Ok(())
+}
+
+/// 模型目录
+pub struct ModelCatalog {
+    models: RwLock<Vec<ModelEntry>>,
+}
+
+impl ModelCatalog {
+    pub fn new() -> Self {
+        Self {
+            models: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// 替换模型目录
+    pub fn set_models(&self, models: Vec<ModelEntry>) {
+        *self.models.write() = models;
+    }
+
+    /// 启用的模型列表（按配置顺序）
+    pub fn list(&self) -> Vec<Model> {
+        self.models
+            .read()
+            .iter()
+            .filter(|entry| entry.enabled)
+            .map(to_model)
+            .collect()
+    }
+
+    /// 按 ID 查找启用的模型（忽略大小写）
+    pub fn get(&self, id: &str) -> Option<Model> {
+        self.models
+            .read()
+            .iter()
+            .find(|entry| entry.enabled && entry.id.eq_ignore_ascii_case(id))
+            .map(to_model)
+    }
+
+    /// 分页查询（`after_id` 取该模型之后的一页，`before_id` 取之前的一页），返回 (当前页, 是否还有更多)
+    ///
+    /// 游标对应的模型不存在时返回空页
+    pub fn page(&self, limit: usize, after_id: Option<&str>, before_id: Option<&str>) -> (Vec<Model>, bool) {
+        let all = self.list();
+        let position = |id: &str| all.iter().position(|m| m.id.eq_ignore_ascii_case(id));
+        let (start, end) = if let Some(after) = after_id {
+            let start = position(after).map_or(all.len(), |i| i + 1);
+            (start, (start + limit).min(all.len()))
+        } else if let Some(before) = before_id {
+            let end = position(before).unwrap_or(0);
+            (end.saturating_sub(limit), end)
+        } else {
+            (0, limit.min(all.len()))
+        };
+
+        let has_more = if before_id.is_some() { start > 0 } else { end < all.len() };
+        (all[start..end].to_vec(), has_more)
+    }
+}
+
+fn to_model(entry: &ModelEntry) -> Model {
+    Model {
+        id: entry.id.clone(),
+        object: "model".to_string(),
+        created: entry.created,
+        created_at: chrono::DateTime::from_timestamp(entry.created, 0)
+            .unwrap_or_default()
+            .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        owned_by: "anthropic".to_string(),
+        display_name: if entry.display_name.trim().is_empty() {
+            entry.id.clone()
+        } else {
+            entry.display_name.clone()
+        },
+        model_type: "chat".to_string(),
+        max_tokens: entry
+            .max_tokens
+            .or_else(|| model_capability(&entry.id).map(|c| c.max_output_tokens))
+            .unwrap_or(0),
+    }
+}
+
+// 全局模型目录
+lazy_static! {
+    pub static ref MODEL_CATALOG: ModelCatalog = ModelCatalog::new();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, enabled: bool) -> ModelEntry {
+        ModelEntry {
+            id: id.to_string(),
+            display_name: String::new(),
+            max_tokens: None,
+            created: 0,
+            enabled,
+        }
+    }
+
+    #[test]
+    fn test_list_hides_disabled_and_fills_defaults() {
+        let catalog = ModelCatalog::new();
+        catalog.set_models(vec![
+            entry("claude-sonnet-4-5-20250929", true),
+            entry("claude-opus-4-5-20251101", false),
+            ModelEntry {
+                max_tokens: Some(32_000),
+                display_name: "Claude Sonnet 4.6".to_string(),
+                ..entry("claude-sonnet-4-6", true)
+            },
+        ]);
+        let models = catalog.list();
+        let ids: Vec<&str> = models.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["claude-sonnet-4-5-20250929", "claude-sonnet-4-6"]);
+        assert_eq!(models[0].display_name, "claude-sonnet-4-5-20250929");
+        assert_eq!(models[0].max_tokens, 64_000);
+        assert_eq!(models[1].display_name, "Claude Sonnet 4.6");
+        assert_eq!(models[1].max_tokens, 32_000);
+    }
+
+    #[test]
+    fn test_get_and_page() {
+        let catalog = ModelCatalog::new();
+        catalog.set_models(vec![
+            entry("model-a", true),
+            entry("model-b", true),
+            entry("model-hidden", false),
+            ModelEntry {
+                created: 1_759_104_000,
+                ..entry("model-c", true)
+            },
+        ]);
+
+        let model = catalog.get("MODEL-C").unwrap();
+        assert_eq!(model.id, "model-c");
+        assert_eq!(model.created_at, "2025-09-29T00:00:00Z");
+        assert!(catalog.get("model-hidden").is_none());
+
+        let ids = |(models, has_more): (Vec<Model>, bool)| {
+            (models.into_iter().map(|m| m.id).collect::<Vec<_>>(), has_more)
+        };
+        assert_eq!(ids(catalog.page(2, None, None)), (vec!["model-a".to_string(), "model-b".to_string()], true));
+        assert_eq!(ids(catalog.page(2, Some("model-b"), None)), (vec!["model-c".to_string()], false));
+        assert_eq!(ids(catalog.page(1, None, Some("model-c"))), (vec!["model-b".to_string()], true));
+        assert_eq!(ids(catalog.page(5, Some("unknown"), None)), (Vec::new(), false));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&[entry("claude-sonnet-4-6", true)]).is_ok());
+        assert!(validate(&[entry(" ", true)]).is_err());
+        assert!(validate(&[ModelEntry { max_tokens: Some(0), ..entry("a", true) }]).is_err());
+        assert!(validate(&[entry("a", true), entry("A", false)]).is_err());
+    }
+}
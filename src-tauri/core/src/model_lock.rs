@@ -0,0 +1,561 @@
+//! 模型锁定监控器
+//! 监听 Kiro 的 settings.json 变更（文件事件，不可用时回退为轮询），当检测到模型被修改时自动恢复为锁定的模型
+//!
+//! 锁定只作用于 Kiro 客户端，不改写经网关转发的请求模型。Kiro 支持多个 Profile（`User/profiles/<id>`），
+//! 可通过 `kiroProfiles` 选择锁定哪些 Profile；未选择时沿用旧行为，使用第一个包含 settings.json 的 Profile。
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use chrono::{DateTime, Utc};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+use tokio::sync::{Notify, mpsc};
+use tokio::time::{interval, Duration};
+
+/// 默认 Profile 的 ID（`User/settings.json`）
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Kiro Profile
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KiroProfile {
+    /// Profile ID（`default` 或 `User/profiles` 下的目录名）
+    pub id: String,
+    /// 显示名称（读取不到时与 ID 相同）
+    pub name: String,
+    pub settings_path: String,
+    /// 当前选择的模型
+    pub model: Option<String>,
+    /// 是否已选择锁定
+    pub selected: bool,
+}
+
+/// 获取 Kiro User 目录
+fn get_kiro_user_dir() -> Option<PathBuf> {
+    get_kiro_base_path().map(|base| base.join("User"))
+}
+
+/// 获取 Kiro 基础目录
+fn get_kiro_base_path() -> Option<PathBuf> {
+    #[cfg(windows)]
+    {
+        std::env::var("APPDATA")
+            .ok()
+            .map(|appdata| PathBuf::from(appdata).join("Kiro"))
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        dirs::home_dir()
+            .map(|home| home.join("Library/Application Support/Kiro"))
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        dirs::home_dir()
+            .map(|home| home.join(".config/Kiro"))
+    }
+
+    #[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+    {
+        None
+    }
+}
+
+/// Profile 的 settings.json 路径
+fn profile_settings_path(user_dir: &Path, id: &str) -> PathBuf {
+    if id == DEFAULT_PROFILE {
+        user_dir.join("settings.json")
+    } else {
+        user_dir.join("profiles").join(id).join("settings.json")
+    }
+}
+
+/// 读取 Profile 显示名称（`globalStorage/storage.json` 的 `userDataProfiles`）
+fn read_profile_names(user_dir: &Path) -> HashMap<String, String> {
+    let Ok(content) = fs::read_to_string(user_dir.join("globalStorage").join("storage.json")) else {
+        return HashMap::new();
+    };
+    let Ok(storage) = serde_json::from_str::<serde_json::Value>(&content) else {
+        return HashMap::new();
+    };
+    storage["userDataProfiles"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|p| Some((p["location"].as_str()?.to_string(), p["name"].as_str()?.to_string())))
+        .collect()
+}
+
+/// 列出目录下的所有 Profile（默认 Profile 在前，其余按目录名排序）
+fn list_profiles_in(user_dir: &Path, selected: &[String]) -> Vec<KiroProfile> {
+    let mut ids = vec![DEFAULT_PROFILE.to_string()];
+    if let Ok(entries) = fs::read_dir(user_dir.join("profiles")) {
+        let mut dirs: Vec<String> = entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_dir())
+            .filter_map(|e| e.file_name().to_str().map(str::to_string))
+            .collect();
+        dirs.sort();
+        ids.extend(dirs);
+    }
+
+    let names = read_profile_names(user_dir);
+    ids.into_iter()
+        .map(|id| {
+            let path = profile_settings_path(user_dir, &id);
+            KiroProfile {
+                name: match id.as_str() {
+                    DEFAULT_PROFILE => "Default".to_string(),
+                    _ => names.get(&id).cloned().unwrap_or_else(|| id.clone()),
+                },
+                settings_path: path.display().to_string(),
+                model: get_kiro_model(&path),
+                selected: selected.contains(&id),
+                id,
+            }
+        })
+        .collect()
+}
+
+/// 未选择 Profile 时使用的 settings.json：优先第一个包含 settings.json 的 Profile，否则为默认 Profile
+fn auto_settings_path(user_dir: &Path) -> PathBuf {
+    list_profiles_in(user_dir, &[])
+        .into_iter()
+        .skip(1)
+        .map(|p| PathBuf::from(p.settings_path))
+        .find(|path| path.exists())
+        .inspect(|path| tracing::debug!("使用 profile 配置: {:?}", path))
+        .unwrap_or_else(|| profile_settings_path(user_dir, DEFAULT_PROFILE))
+}
+
+/// 设置 Kiro 的模型选项
+fn set_kiro_model(settings_path: &Path, model: &str) -> Result<(), String> {
+    // 读取现有配置
+    let mut settings: serde_json::Value = if settings_path.exists() {
+        let content = fs::read_to_string(settings_path)
+            .map_err(|e| format!("读取配置失败: {}", e))?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("解析配置失败: {}", e))?
+    } else {
+        // 创建目录
+        if let Some(parent) = settings_path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("创建目录失败: {}", e))?;
+        }
+        serde_json::json!({})
+    };
+
+    // 更新模型设置
+    settings["kiroAgent.modelSelection"] = serde_json::json!(model);
+
+    // 写回配置
+    let content = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("序列化配置失败: {}", e))?;
+    fs::write(settings_path, content)
+        .map_err(|e| format!("写入配置失败: {}", e))?;
+
+    Ok(())
+}
+
+/// 获取当前 Kiro 模型设置
+fn get_kiro_model(settings_path: &Path) -> Option<String> {
+    if !settings_path.exists() {
+        return None;
+    }
+
+    let content = fs::read_to_string(settings_path).ok()?;
+    let settings: serde_json::Value = serde_json::from_str(&content).ok()?;
+
+    settings.get("kiroAgent.modelSelection")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+}
+
+/// 文件监听不可用时的轮询间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 文件变更防抖时间（编辑器保存时会连续触发多次事件）
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// 监控方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WatchMode {
+    /// 未启动
+    Stopped,
+    /// 文件系统事件
+    Events,
+    /// 轮询（文件监听不可用时回退）
+    Polling,
+}
+
+/// 模型锁定监控状态
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelLockStatus {
+    pub locked_model: Option<String>,
+    pub mode: WatchMode,
+    /// 选择锁定的 Profile（为空时自动选择）
+    pub profiles: Vec<String>,
+    /// 监控的 Kiro settings.json 路径
+    pub settings_paths: Vec<String>,
+    /// 文件监听失败的原因（回退为轮询时）
+    pub watcher_error: Option<String>,
+    /// 最近一次写入锁定模型的时间
+    pub last_enforced_at: Option<DateTime<Utc>>,
+    /// 检测到模型被修改并恢复的次数
+    pub revert_count: u64,
+}
+
+struct WatchState {
+    mode: WatchMode,
+    watcher_error: Option<String>,
+    last_enforced_at: Option<DateTime<Utc>>,
+    revert_count: u64,
+}
+
+/// 监控任务与 Admin API 共享的状态
+struct Shared {
+    /// 锁定的模型名称
+    locked_model: RwLock<Option<String>>,
+    /// 选择锁定的 Profile
+    profiles: RwLock<Vec<String>>,
+    state: Mutex<WatchState>,
+    /// Profile 选择变化时通知监控任务重新监听
+    reconfigure: Notify,
+}
+
+impl Shared {
+    fn set_mode(&self, mode: WatchMode, watcher_error: Option<String>) {
+        let mut state = self.state.lock();
+        state.mode = mode;
+        state.watcher_error = watcher_error;
+    }
+
+    /// 当前需要锁定的 settings.json
+    fn settings_paths(&self) -> Vec<PathBuf> {
+        let Some(user_dir) = get_kiro_user_dir() else {
+            return Vec::new();
+        };
+        let profiles = self.profiles.read();
+        if profiles.is_empty() {
+            return vec![auto_settings_path(&user_dir)];
+        }
+        profiles.iter().map(|id| profile_settings_path(&user_dir, id)).collect()
+    }
+
+    /// 将锁定的模型写入所有选择的 Profile
+    fn apply(&self) {
+        let Some(model) = self.locked_model.read().clone() else {
+            return;
+        };
+        let mut applied = false;
+        for path in self.settings_paths() {
+            match set_kiro_model(&path, &model) {
+                Ok(()) => {
+                    tracing::info!("模型已锁定并应用到 Kiro: {} ({:?})", model, path);
+                    applied = true;
+                }
+                Err(e) => tracing::error!("设置 Kiro 模型失败: {} ({:?})", e, path),
+            }
+        }
+        if applied {
+            self.state.lock().last_enforced_at = Some(Utc::now());
+        }
+    }
+
+    /// 模型被修改时恢复为锁定的模型（写入后触发的事件读到的值相同，不会循环）
+    fn enforce(&self) {
+        let Some(locked_model_name) = self.locked_model.read().clone() else {
+            return;
+        };
+        for path in self.settings_paths() {
+            let Some(current_model) = get_kiro_model(&path) else {
+                continue;
+            };
+            if current_model == locked_model_name {
+                continue;
+            }
+            tracing::info!("检测到模型被修改: {} -> 恢复为: {} ({:?})", current_model, locked_model_name, path);
+            match set_kiro_model(&path, &locked_model_name) {
+                Ok(()) => {
+                    let mut state = self.state.lock();
+                    state.revert_count += 1;
+                    state.last_enforced_at = Some(Utc::now());
+                }
+                Err(e) => tracing::error!("恢复锁定模型失败: {}", e),
+            }
+        }
+    }
+}
+
+/// 模型锁定监控器
+///
+/// 优先监听 settings.json 所在目录的文件事件（防抖后检查），监听失败或中断时回退为轮询
+pub struct ModelLockWatcher {
+    shared: Arc<Shared>,
+    /// 是否正在运行
+    is_running: Arc<AtomicBool>,
+}
+
+impl ModelLockWatcher {
+    pub fn new() -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                locked_model: RwLock::new(None),
+                profiles: RwLock::new(Vec::new()),
+                state: Mutex::new(WatchState {
+                    mode: WatchMode::Stopped,
+                    watcher_error: None,
+                    last_enforced_at: None,
+                    revert_count: 0,
+                }),
+                reconfigure: Notify::new(),
+            }),
+            is_running: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// 设置锁定的模型
+    pub fn set_locked_model(&self, model: Option<String>) {
+        tracing::info!("set_locked_model 被调用: {:?}", model);
+        if model.is_none() {
+            tracing::info!("模型锁定已取消");
+        }
+        *self.shared.locked_model.write() = model;
+        // 立即应用模型设置
+        self.shared.apply();
+    }
+
+    /// 获取锁定的模型
+    pub fn get_locked_model(&self) -> Option<String> {
+        self.shared.locked_model.read().clone()
+    }
+
+    /// 选择锁定的 Profile（为空时自动选择），已锁定模型时立即写入
+    pub fn set_profiles(&self, profiles: Vec<String>) {
+        tracing::info!("模型锁定 Profile: {:?}", profiles);
+        *self.shared.profiles.write() = profiles;
+        self.shared.apply();
+        self.shared.reconfigure.notify_one();
+    }
+
+    /// 列出所有 Kiro Profile
+    pub fn profiles(&self) -> Vec<KiroProfile> {
+        let Some(user_dir) = get_kiro_user_dir() else {
+            return Vec::new();
+        };
+        list_profiles_in(&user_dir, &self.shared.profiles.read())
+    }
+
+    /// 获取监控状态
+    pub fn status(&self) -> ModelLockStatus {
+        let settings_paths = self
+            .shared
+            .settings_paths()
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect();
+        let state = self.shared.state.lock();
+        ModelLockStatus {
+            locked_model: self.get_locked_model(),
+            mode: state.mode,
+            profiles: self.shared.profiles.read().clone(),
+            settings_paths,
+            watcher_error: state.watcher_error.clone(),
+            last_enforced_at: state.last_enforced_at,
+            revert_count: state.revert_count,
+        }
+    }
+
+    /// 启动监控（在单独的任务中运行）
+    pub fn start(&self) {
+        if self.is_running.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let shared = Arc::clone(&self.shared);
+        let is_running = Arc::clone(&self.is_running);
+
+        tokio::spawn(async move {
+            // 启动前可能已被修改，先检查一次
+            shared.enforce();
+
+            let watcher_error = loop {
+                let (_watcher, rx) = match watch_settings(&shared.settings_paths()) {
+                    Ok(watch) => watch,
+                    Err(e) => break Some(e),
+                };
+                tracing::info!("模型锁定监控已启动（文件事件）");
+                shared.set_mode(WatchMode::Events, None);
+                match run_events(&shared, &is_running, rx).await {
+                    EventsExit::Stopped => break None,
+                    EventsExit::Reconfigure => continue,
+                    EventsExit::Failed(e) => break Some(e),
+                }
+            };
+            if let Some(error) = watcher_error {
+                tracing::warn!("模型锁定文件监听不可用，回退为轮询（{}秒）: {}", POLL_INTERVAL.as_secs(), error);
+                shared.set_mode(WatchMode::Polling, Some(error));
+                run_polling(&shared, &is_running).await;
+            }
+
+            shared.set_mode(WatchMode::Stopped, None);
+            tracing::info!("模型锁定监控已停止");
+        });
+    }
+
+    /// 停止监控
+    pub fn stop(&self) {
+        self.is_running.store(false, Ordering::SeqCst);
+    }
+}
+
+impl Default for ModelLockWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 监听 settings.json 所在目录（编辑器可能以替换文件的方式保存），只转发这些文件的写入事件
+fn watch_settings(paths: &[PathBuf]) -> Result<(RecommendedWatcher, mpsc::UnboundedReceiver<()>), String> {
+    if paths.is_empty() {
+        return Err("无法获取 Kiro 配置路径".to_string());
+    }
+    let targets = paths.to_vec();
+    let (tx, rx) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| match res {
+        Ok(event) => {
+            let relevant = (event.kind.is_create() || event.kind.is_modify())
+                && event.paths.iter().any(|p| targets.contains(p));
+            if relevant {
+                let _ = tx.send(());
+            }
+        }
+        Err(e) => tracing::warn!("模型锁定文件监听出错: {}", e),
+    })
+    .map_err(|e| format!("创建文件监听失败: {}", e))?;
+
+    let mut dirs: Vec<&Path> = paths.iter().filter_map(|p| p.parent()).collect();
+    dirs.sort();
+    dirs.dedup();
+    for dir in dirs {
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("监听 {} 失败: {}", dir.display(), e))?;
+    }
+    Ok((watcher, rx))
+}
+
+/// 文件事件模式的退出原因
+enum EventsExit {
+    Stopped,
+    /// Profile 选择变化，需要重新监听
+    Reconfigure,
+    Failed(String),
+}
+
+/// 文件事件模式
+async fn run_events(shared: &Shared, is_running: &AtomicBool, mut rx: mpsc::UnboundedReceiver<()>) -> EventsExit {
+    while is_running.load(Ordering::SeqCst) {
+        // 定期醒来检查停止标志
+        let received = tokio::select! {
+            _ = shared.reconfigure.notified() => return EventsExit::Reconfigure,
+            received = tokio::time::timeout(POLL_INTERVAL, rx.recv()) => received,
+        };
+        match received {
+            Err(_) => continue,
+            Ok(None) => return EventsExit::Failed("文件监听已中断".to_string()),
+            Ok(Some(())) => {
+                tokio::time::sleep(DEBOUNCE).await;
+                while rx.try_recv().is_ok() {}
+                shared.enforce();
+            }
+        }
+    }
+    EventsExit::Stopped
+}
+
+/// 轮询模式（每次重新计算 Profile 路径）
+async fn run_polling(shared: &Shared, is_running: &AtomicBool) {
+    let mut check_interval = interval(POLL_INTERVAL);
+    while is_running.load(Ordering::SeqCst) {
+        check_interval.tick().await;
+        shared.enforce();
+    }
+}
+
+// 全局单例
+lazy_static::lazy_static! {
+    pub static ref MODEL_LOCK_WATCHER: ModelLockWatcher = ModelLockWatcher::new();
+}
+
+/// 启动模型锁定监控
+pub fn start_model_lock_watcher() {
+    MODEL_LOCK_WATCHER.start();
+}
+
+/// 设置锁定的模型
+pub fn set_locked_model(model: Option<String>) {
+    MODEL_LOCK_WATCHER.set_locked_model(model);
+}
+
+/// 获取锁定的模型
+pub fn get_locked_model() -> Option<String> {
+    MODEL_LOCK_WATCHER.get_locked_model()
+}
+
+/// 获取模型锁定监控状态
+pub fn status() -> ModelLockStatus {
+    MODEL_LOCK_WATCHER.status()
+}
+
+/// 列出所有 Kiro Profile
+pub fn list_profiles() -> Vec<KiroProfile> {
+    MODEL_LOCK_WATCHER.profiles()
+}
+
+/// 选择锁定的 Profile
+pub fn set_profiles(profiles: Vec<String>) {
+    MODEL_LOCK_WATCHER.set_profiles(profiles);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_profiles_and_auto_path() {
+        let user_dir = std::env::temp_dir().join(format!("kiro-profiles-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(user_dir.join("profiles/-1a2b")).unwrap();
+        fs::create_dir_all(user_dir.join("profiles/-9f8e")).unwrap();
+        fs::create_dir_all(user_dir.join("globalStorage")).unwrap();
+        fs::write(
+            user_dir.join("globalStorage/storage.json"),
+            r#"{"userDataProfiles":[{"location":"-9f8e","name":"Work"}]}"#,
+        )
+        .unwrap();
+
+        // 没有任何 settings.json 时使用默认 Profile
+        assert_eq!(auto_settings_path(&user_dir), user_dir.join("settings.json"));
+
+        let work = profile_settings_path(&user_dir, "-9f8e");
+        set_kiro_model(&work, "claude-opus-4").unwrap();
+        assert_eq!(auto_settings_path(&user_dir), work);
+
+        let profiles = list_profiles_in(&user_dir, &["-9f8e".to_string()]);
+        fs::remove_dir_all(&user_dir).ok();
+
+        let ids: Vec<&str> = profiles.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, ["default", "-1a2b", "-9f8e"]);
+        assert_eq!(profiles[1].name, "-1a2b");
+        assert_eq!(profiles[2].name, "Work");
+        assert_eq!(profiles[2].model.as_deref(), Some("claude-opus-4"));
+        assert!(profiles[2].selected && !profiles[0].selected);
+    }
+}
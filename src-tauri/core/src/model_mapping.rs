@@ -0,0 +1,109 @@
+//! 模型名映射
+//!
+//! 将客户端传入的模型名（如 `gpt-4o`、`claude-3-5-sonnet-latest`）按配置的映射表重写为
+//! 转换器支持的模型，避免直接返回不支持的模型错误。映射在租户白名单检查之前执行。
+
+use lazy_static::lazy_static;
+use parking_lot::RwLock;
+
+use crate::anthropic::converter::map_model;
+use crate::model::config::ModelMapping;
+
+/// 检查映射规则是否匹配（忽略大小写，`from` 以 `*` 结尾时按前缀匹配）
+fn matches(pattern: &str, model: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    let model = model.to_lowercase();
+    match pattern.strip_suffix('*') {
+        Some(prefix) => model.starts_with(prefix),
+        None => model == pattern,
+    }
+}
+
+/// 校验映射表：来源不能为空或重复，目标必须是转换器支持的模型
+pub fn validate(mappings: &[ModelMapping]) -> Result<(), String> {
+    let mut seen = std::collections::HashSet::new();
+    for mapping in mappings {
+        let from = mapping.from.trim();
+        if from.is_empty() || from == "*" {
+            return Err("映射来源不能为空".to_string());
+        }
+        if !seen.insert(from.to_lowercase()) {
+            return Err(format!("映射来源重复: {}", from));
+        }
+        if map_model(&mapping.to).is_none() {
+            return Err(format!("映射目标不是支持的模型: {}", mapping.to));
+        }
+    }
+    Ok(())
+}
+
+/// 模型映射表（按配置顺序匹配，第一条命中的规则生效）
+pub struct ModelMapper {
+    rules: RwLock<Vec<ModelMapping>>,
+}
+
+impl ModelMapper {
+    pub fn new() -> Self {
+        Self {
+            rules: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// 替换映射表
+    pub fn set_rules(&self, rules: Vec<ModelMapping>) {
+        *self.rules.write() = rules;
+    }
+
+    /// 查找映射后的模型名，未命中返回 None
+    pub fn resolve(&self, model: &str) -> Option<String> {
+        self.rules
+            .read()
+            .iter()
+            .find(|rule| matches(rule.from.trim(), model))
+            .map(|rule| rule.to.clone())
+    }
+}
+
+// 全局模型映射表
+lazy_static! {
+    pub static ref MODEL_MAPPER: ModelMapper = ModelMapper::new();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping(from: &str, to: &str) -> ModelMapping {
+        ModelMapping {
+            from: from.to_string(),
+            to: to.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_resolve_exact_and_prefix() {
+        let mapper = ModelMapper::new();
+        mapper.set_rules(vec![
+            mapping("GPT-4o", "claude-sonnet-4-5"),
+            mapping("gpt-4o-mini", "claude-haiku-4-5"),
+            mapping("gpt-*", "claude-opus-4-5"),
+        ]);
+        assert_eq!(mapper.resolve("gpt-4o").as_deref(), Some("claude-sonnet-4-5"));
+        // 按顺序匹配，前缀规则排在后面
+        assert_eq!(mapper.resolve("gpt-4o-mini").as_deref(), Some("claude-haiku-4-5"));
+        assert_eq!(mapper.resolve("gpt-4.1").as_deref(), Some("claude-opus-4-5"));
+        assert_eq!(mapper.resolve("claude-sonnet-4-5"), None);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&[mapping("gpt-4o", "claude-sonnet-4-5")]).is_ok());
+        assert!(validate(&[mapping("gpt-4o", "gpt-5")]).is_err());
+        assert!(validate(&[mapping(" ", "claude-sonnet-4-5")]).is_err());
+        assert!(validate(&[
+            mapping("gpt-4o", "claude-sonnet-4-5"),
+            mapping("GPT-4O", "claude-opus-4-5"),
+        ])
+        .is_err());
+    }
+}
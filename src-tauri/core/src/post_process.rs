@@ -0,0 +1,314 @@
+//! 响应后处理
+//!
+//! Kiro 偶尔会在回复中注入 agent 模式的残留内容（开头的元数据行、重复的句子等）。
+//! 后处理按 `postProcess` 配置在文本到达客户端之前逐行处理：
+//! - 改写规则：正则替换，按顺序执行；`leading` 规则只作用于回复开头（尚未输出可见文本时）
+//! - 去重：丢弃与上一非空行完全相同的行（代码块内和过短的行不参与）
+//!
+//! 被规则清空的行连同换行符一起删除。流式响应按行缓冲，超过 [`MAX_PENDING_BYTES`]
+//! 仍未换行时先按已有内容输出；thinking 内容和工具调用不受影响。
+
+use std::sync::Arc;
+
+use lazy_static::lazy_static;
+use parking_lot::RwLock;
+use regex::Regex;
+
+use crate::model::config::{PostProcessConfig, PostProcessRule};
+
+/// 单行最多缓冲的字节数，超出后不等换行直接输出
+pub const MAX_PENDING_BYTES: usize = 4096;
+
+/// 参与去重的最短行长度（字符数），避免误删 `}`、`---` 等短行
+const DEDUPE_MIN_CHARS: usize = 16;
+
+/// 编译后的规则
+struct CompiledRule {
+    regex: Regex,
+    replacement: String,
+    leading: bool,
+}
+
+fn compile(rule: &PostProcessRule) -> Result<CompiledRule, String> {
+    let name = rule.name.trim();
+    if name.is_empty() {
+        return Err("后处理规则名不能为空".to_string());
+    }
+    if rule.pattern.is_empty() {
+        return Err(format!("后处理规则 '{}' 的正则表达式不能为空", name));
+    }
+    let regex = Regex::new(&rule.pattern).map_err(|e| format!("后处理规则 '{}' 的正则表达式无效: {}", name, e))?;
+    if regex.is_match("") {
+        return Err(format!("后处理规则 '{}' 会匹配空字符串", name));
+    }
+    Ok(CompiledRule {
+        regex,
+        replacement: rule.replacement.clone(),
+        leading: rule.leading,
+    })
+}
+
+/// 校验后处理配置
+pub fn validate(config: &PostProcessConfig) -> Result<(), String> {
+    for rule in &config.rules {
+        compile(rule)?;
+    }
+    Ok(())
+}
+
+/// 生效中的规则快照
+#[derive(Default)]
+struct Pipeline {
+    rules: Vec<CompiledRule>,
+    dedupe_lines: bool,
+}
+
+impl Pipeline {
+    fn is_empty(&self) -> bool {
+        self.rules.is_empty() && !self.dedupe_lines
+    }
+}
+
+/// 响应后处理器（配置热更新）
+pub struct PostProcessor {
+    pipeline: RwLock<Arc<Pipeline>>,
+}
+
+impl PostProcessor {
+    pub fn new() -> Self {
+        Self {
+            pipeline: RwLock::new(Arc::new(Pipeline::default())),
+        }
+    }
+
+    /// 替换后处理配置（对之后开始的响应生效，无效规则记录警告后跳过）
+    pub fn set_config(&self, config: &PostProcessConfig) {
+        let rules = config
+            .rules
+            .iter()
+            .filter(|r| r.enabled)
+            .filter_map(|rule| {
+                compile(rule)
+                    .inspect_err(|e| tracing::warn!("已跳过无效的后处理规则: {}", e))
+                    .ok()
+            })
+            .collect();
+        *self.pipeline.write() = Arc::new(Pipeline {
+            rules,
+            dedupe_lines: config.dedupe_lines,
+        });
+    }
+
+    /// 为一次响应创建处理会话（期间配置变更不影响该响应）
+    pub fn session(&self) -> PostProcessSession {
+        PostProcessSession {
+            pipeline: self.pipeline.read().clone(),
+            pending: String::new(),
+            mid_line: false,
+            emitted: false,
+            dropped_leading: false,
+            last_line: None,
+            in_code_fence: false,
+        }
+    }
+
+    /// 处理完整文本（非流式响应）
+    pub fn apply(&self, text: &str) -> String {
+        let mut session = self.session();
+        if !session.is_active() {
+            return text.to_string();
+        }
+        let mut output = session.push(text);
+        output.push_str(&session.flush());
+        output
+    }
+}
+
+impl Default for PostProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 一次响应的后处理状态（按行缓冲）
+pub struct PostProcessSession {
+    pipeline: Arc<Pipeline>,
+    /// 尚未换行的文本
+    pending: String,
+    /// 当前行的前半部分已因过长提前输出
+    mid_line: bool,
+    /// 是否已输出可见文本（之后不再执行 leading 规则）
+    emitted: bool,
+    /// 开头是否有行被删除（删除后紧跟的空行一并丢弃）
+    dropped_leading: bool,
+    /// 上一非空行（用于去重）
+    last_line: Option<String>,
+    in_code_fence: bool,
+}
+
+impl PostProcessSession {
+    /// 是否配置了任何后处理（未配置时调用方可直接透传）
+    pub fn is_active(&self) -> bool {
+        !self.pipeline.is_empty()
+    }
+
+    /// 追加一段文本，返回已处理完成的部分（不含末尾未换行的内容）
+    pub fn push(&mut self, text: &str) -> String {
+        self.pending.push_str(text);
+        let mut output = String::new();
+
+        while let Some(pos) = self.pending.find('\n') {
+            let line: String = self.pending.drain(..=pos).collect();
+            let line = &line[..line.len() - 1];
+            if std::mem::take(&mut self.mid_line) {
+                output.push_str(&self.rewrite(line));
+                output.push('\n');
+            } else if let Some(line) = self.process_line(line) {
+                output.push_str(&line);
+                output.push('\n');
+            }
+        }
+
+        if self.pending.len() > MAX_PENDING_BYTES {
+            let segment = std::mem::take(&mut self.pending);
+            let segment = self.rewrite(&segment);
+            self.note_emitted(&segment);
+            self.mid_line = true;
+            output.push_str(&segment);
+        }
+        output
+    }
+
+    /// 响应结束（或插入工具调用前），输出剩余未换行的内容
+    pub fn flush(&mut self) -> String {
+        let line = std::mem::take(&mut self.pending);
+        if line.is_empty() {
+            return String::new();
+        }
+        if std::mem::take(&mut self.mid_line) {
+            return self.rewrite(&line);
+        }
+        self.process_line(&line).unwrap_or_default()
+    }
+
+    /// 处理一个完整的行，返回 None 表示删除该行
+    fn process_line(&mut self, line: &str) -> Option<String> {
+        let rewritten = self.rewrite(line);
+        let trimmed = rewritten.trim();
+
+        if trimmed.is_empty() {
+            // 被规则清空的行，或开头删除内容后残留的空行
+            if !line.trim().is_empty() || (!self.emitted && self.dropped_leading) {
+                if !self.emitted {
+                    self.dropped_leading = true;
+                }
+                return None;
+            }
+            return Some(rewritten);
+        }
+
+        if trimmed.starts_with("```") {
+            self.in_code_fence = !self.in_code_fence;
+        } else if self.pipeline.dedupe_lines
+            && !self.in_code_fence
+            && trimmed.chars().count() >= DEDUPE_MIN_CHARS
+            && self.last_line.as_deref() == Some(trimmed)
+        {
+            tracing::debug!("后处理删除重复行: {}", trimmed);
+            return None;
+        }
+
+        self.last_line = Some(trimmed.to_string());
+        self.note_emitted(&rewritten);
+        Some(rewritten)
+    }
+
+    /// 依次执行改写规则
+    fn rewrite(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for rule in &self.pipeline.rules {
+            if rule.leading && self.emitted {
+                continue;
+            }
+            if rule.regex.is_match(&text) {
+                text = rule.regex.replace_all(&text, rule.replacement.as_str()).into_owned();
+            }
+        }
+        text
+    }
+
+    fn note_emitted(&mut self, text: &str) {
+        if !text.trim().is_empty() {
+            self.emitted = true;
+        }
+    }
+}
+
+// 全局响应后处理器
+lazy_static! {
+    pub static ref POST_PROCESSOR: PostProcessor = PostProcessor::new();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(name: &str, pattern: &str, replacement: &str, leading: bool) -> PostProcessRule {
+        PostProcessRule {
+            name: name.to_string(),
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+            leading,
+            enabled: true,
+        }
+    }
+
+    fn processor(rules: Vec<PostProcessRule>, dedupe_lines: bool) -> PostProcessor {
+        let processor = PostProcessor::new();
+        processor.set_config(&PostProcessConfig { rules, dedupe_lines });
+        processor
+    }
+
+    #[test]
+    fn test_strips_leading_metadata_and_duplicates() {
+        let processor = processor(
+            vec![
+                rule("agent-meta", r"^\[agent:[^\]]*\]$", "", true),
+                rule("kiro-name", r"\bKiro\b", "Claude", false),
+            ],
+            true,
+        );
+        let input = "[agent:spec-mode]\n\nI'm Kiro, let me check the file.\nI'm Kiro, let me check the file.\n\
+                     ```\nfoo();\nfoo();\n```\n[agent:spec-mode]";
+        let expected = "I'm Claude, let me check the file.\n```\nfoo();\nfoo();\n```\n[agent:spec-mode]";
+        assert_eq!(processor.apply(input), expected);
+
+        // 流式分块结果与整体处理一致
+        let mut session = processor.session();
+        let mut output = String::new();
+        for chunk in input.as_bytes().chunks(5) {
+            output.push_str(&session.push(std::str::from_utf8(chunk).unwrap()));
+        }
+        output.push_str(&session.flush());
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_inactive_and_long_lines() {
+        assert!(!PostProcessor::new().session().is_active());
+        assert_eq!(PostProcessor::new().apply("a\n\nb"), "a\n\nb");
+
+        let processor = processor(vec![rule("x", "x", "y", false)], false);
+        let mut session = processor.session();
+        let long = "x".repeat(MAX_PENDING_BYTES + 1);
+        assert_eq!(session.push(&long), "y".repeat(MAX_PENDING_BYTES + 1));
+        assert_eq!(session.push("x\nx"), "y\n");
+        assert_eq!(session.flush(), "y");
+
+        let invalid = |r: PostProcessRule| validate(&PostProcessConfig { rules: vec![r], dedupe_lines: false }).is_err();
+        assert!(invalid(rule("", "x", "", false)));
+        assert!(invalid(rule("broken", "(", "", false)));
+        assert!(invalid(rule("matches-empty", "a*", "", false)));
+        assert!(!invalid(rule("ok", "a+", "", false)));
+    }
+}
@@ -0,0 +1,660 @@
+//! 反代服务生命周期
+//!
+//! 统一描述反代服务的运行状态，Admin API、Anthropic 中间件与 Tauri 命令共享同一实例：
+//!
+//! ```text
+//! Stopped ──start──▶ Starting ──监听成功──▶ Running ──stop──▶ Draining ──连接排空/宽限期到──▶ Stopped
+//!    ▲                  │
+//!    └──────start────── Crashed ◀──────────监听失败/运行错误──────────┘
+//! ```
+//!
+//! Draining 期间不再接收新请求，进行中的流式响应可在宽限期（`proxyDrainTimeoutSecs`）内自然结束；
+//! 宽限期到后切换为 Stopped，仍未结束的流检测到状态变化后自行中断。
+//!
+//! 单端口模式下反代与 Admin API 共用监听器，启停只切换是否接收新请求（软启停），
+//! 不经过 Starting/Draining。
+//!
+//! 分组反代实例（`groupListeners`）各自是一个独立监听器的 [`ProxyLifecycle`]，固定使用一个分组的凭证，
+//! 由 [`GroupProxies`] 按配置统一启停，可与主反代服务同时运行。
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::Serialize;
+use tokio::sync::watch;
+
+use crate::events::{AdminEvent, EVENT_BUS};
+use crate::kiro_server::{AdminContext, run_proxy_only_server};
+use crate::logs::LOG_COLLECTOR;
+use crate::metrics::STREAM_METRICS;
+use crate::model::config::{Config, GroupListener};
+
+/// 启动时等待监听结果的最长时间
+const START_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 默认的排空宽限期（未从配置读取时使用）
+const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 重启时在宽限期之外额外等待监听器退出的时间（流检测到中断并关闭连接）
+const RESTART_STOP_MARGIN: Duration = Duration::from_secs(5);
+
+/// 反代服务状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProxyState {
+    /// 已停止
+    Stopped,
+    /// 正在绑定端口
+    Starting,
+    /// 正常运行，接收新请求
+    Running,
+    /// 已请求停止，等待进行中的请求结束
+    Draining,
+    /// 启动或运行过程中出错退出
+    Crashed,
+}
+
+impl ProxyState {
+    /// 是否接收新请求
+    pub fn accepts_requests(self) -> bool {
+        self == ProxyState::Running
+    }
+
+    /// 是否仍有监听器或正在启动/排空
+    pub fn is_active(self) -> bool {
+        matches!(self, ProxyState::Starting | ProxyState::Running | ProxyState::Draining)
+    }
+}
+
+/// 生命周期快照
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxySnapshot {
+    pub state: ProxyState,
+    /// 进入当前状态的时间
+    pub since: DateTime<Utc>,
+    /// 最近一次崩溃的错误信息
+    pub last_error: Option<String>,
+    /// 实际绑定的端口（端口被占用时可能与配置不同）
+    pub port: Option<u16>,
+    /// 实际绑定的监听地址（多地址监听时包含全部）
+    pub addresses: Vec<String>,
+    /// Draining 时强制中断剩余流的截止时间
+    pub drain_deadline: Option<DateTime<Utc>>,
+    /// 分组反代实例固定使用的分组（主反代服务为 None）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_id: Option<String>,
+}
+
+struct Inner {
+    state_tx: watch::Sender<ProxySnapshot>,
+    /// 单端口模式：与 Admin API 共用监听器，仅软启停
+    shared_listener: bool,
+    /// 独立监听器的停止信号
+    shutdown_tx: Mutex<Option<watch::Sender<bool>>>,
+    /// 启动独立监听器所需的上下文（双端口模式下由 Admin 服务注册）
+    context: Mutex<Option<Arc<AdminContext>>>,
+    /// 排空宽限期（每次启动时从配置读取）
+    drain_timeout: Mutex<Duration>,
+    /// 分组反代实例的分组与端口（主反代服务为 None）
+    group_listener: Option<GroupListener>,
+}
+
+/// 反代服务生命周期状态机
+#[derive(Clone)]
+pub struct ProxyLifecycle {
+    inner: Arc<Inner>,
+}
+
+impl ProxyLifecycle {
+    fn with_state(state: ProxyState, shared_listener: bool, group_listener: Option<GroupListener>) -> Self {
+        let (state_tx, _) = watch::channel(ProxySnapshot {
+            state,
+            since: Utc::now(),
+            last_error: None,
+            port: None,
+            addresses: Vec::new(),
+            drain_deadline: None,
+            group_id: group_listener.as_ref().map(|l| l.group_id.clone()),
+        });
+        Self {
+            inner: Arc::new(Inner {
+                state_tx,
+                shared_listener,
+                shutdown_tx: Mutex::new(None),
+                context: Mutex::new(None),
+                drain_timeout: Mutex::new(DEFAULT_DRAIN_TIMEOUT),
+                group_listener,
+            }),
+        }
+    }
+
+    /// 双端口模式：独立监听器，初始为 Stopped，需先 `attach_context`
+    pub fn new() -> Self {
+        Self::with_state(ProxyState::Stopped, false, None)
+    }
+
+    /// 单端口模式：与 Admin API 共用监听器，初始为 Running
+    pub fn shared_listener() -> Self {
+        Self::with_state(ProxyState::Running, true, None)
+    }
+
+    /// 分组反代实例：独立监听器，固定使用 `listener` 指定的分组和端口
+    pub fn for_group(listener: GroupListener) -> Self {
+        Self::with_state(ProxyState::Stopped, false, Some(listener))
+    }
+
+    /// 分组反代实例的分组与端口（主反代服务返回 None）
+    pub fn group_listener(&self) -> Option<&GroupListener> {
+        self.inner.group_listener.as_ref()
+    }
+
+    /// 注册启动独立监听器所需的上下文
+    pub fn attach_context(&self, ctx: Arc<AdminContext>) {
+        *self.inner.context.lock() = Some(ctx);
+    }
+
+    /// 是否管理独立监听器（双端口模式）
+    pub fn is_standalone(&self) -> bool {
+        !self.inner.shared_listener
+    }
+
+    pub fn snapshot(&self) -> ProxySnapshot {
+        self.inner.state_tx.borrow().clone()
+    }
+
+    pub fn state(&self) -> ProxyState {
+        self.inner.state_tx.borrow().state
+    }
+
+    /// 订阅状态变化
+    pub fn subscribe(&self) -> watch::Receiver<ProxySnapshot> {
+        self.inner.state_tx.subscribe()
+    }
+
+    /// 当前的排空宽限期
+    pub fn drain_timeout(&self) -> Duration {
+        *self.inner.drain_timeout.lock()
+    }
+
+    /// 是否接收新请求
+    pub fn accepts_requests(&self) -> bool {
+        self.state().accepts_requests()
+    }
+
+    /// 进行中的流式响应是否应立即中断
+    ///
+    /// Draining 期间允许已有请求自然结束，宽限期到后状态变为 Stopped
+    pub fn should_abort_streams(&self) -> bool {
+        matches!(self.state(), ProxyState::Stopped | ProxyState::Crashed)
+    }
+
+    /// 仅当当前状态属于 `from` 时切换到 `to` 并执行 `update`，返回是否发生切换
+    fn transition_with(
+        &self,
+        from: &[ProxyState],
+        to: ProxyState,
+        update: impl FnOnce(&mut ProxySnapshot),
+    ) -> bool {
+        let changed = self.inner.state_tx.send_if_modified(|snapshot| {
+            if !from.contains(&snapshot.state) {
+                return false;
+            }
+            snapshot.state = to;
+            snapshot.since = Utc::now();
+            update(snapshot);
+            true
+        });
+        if changed {
+            EVENT_BUS.publish(AdminEvent::Proxy { status: self.snapshot() });
+        }
+        changed
+    }
+
+    fn transition(&self, from: &[ProxyState], to: ProxyState) -> bool {
+        self.transition_with(from, to, |_| {})
+    }
+
+    /// Starting → Running（独立监听器已绑定到 `port` 上的 `addresses`）
+    pub(crate) fn mark_running(&self, port: u16, addresses: Vec<String>) -> bool {
+        self.transition_with(&[ProxyState::Starting], ProxyState::Running, |s| {
+            s.port = Some(port);
+            s.addresses = addresses;
+        })
+    }
+
+    /// 记录共用监听器的端口与地址（单端口模式）
+    pub(crate) fn set_bound_port(&self, port: u16, addresses: Vec<String>) {
+        self.inner.state_tx.send_modify(|s| {
+            s.port = Some(port);
+            s.addresses = addresses;
+        });
+    }
+
+    /// 监听器正常退出
+    pub(crate) fn mark_stopped(&self) -> bool {
+        self.inner.shutdown_tx.lock().take();
+        self.transition_with(
+            &[ProxyState::Starting, ProxyState::Running, ProxyState::Draining],
+            ProxyState::Stopped,
+            |s| {
+                s.port = None;
+                s.addresses.clear();
+                s.drain_deadline = None;
+            },
+        )
+    }
+
+    /// 监听器出错退出
+    pub(crate) fn mark_crashed(&self, error: impl Into<String>) -> bool {
+        self.inner.shutdown_tx.lock().take();
+        let error = error.into();
+        self.transition_with(
+            &[ProxyState::Starting, ProxyState::Running, ProxyState::Draining],
+            ProxyState::Crashed,
+            |s| {
+                s.port = None;
+                s.addresses.clear();
+                s.drain_deadline = None;
+                s.last_error = Some(error);
+            },
+        )
+    }
+
+    /// Stopped/Crashed → Starting，清除上次的错误信息
+    fn begin_start(&self) -> bool {
+        self.transition_with(
+            &[ProxyState::Stopped, ProxyState::Crashed],
+            ProxyState::Starting,
+            |s| s.last_error = None,
+        )
+    }
+
+    /// 等待监听器完全退出（Stopped/Crashed），超时返回 false
+    pub async fn wait_stopped(&self, timeout: Duration) -> bool {
+        let mut rx = self.subscribe();
+        let settled = tokio::time::timeout(timeout, rx.wait_for(|s| !s.state.is_active())).await;
+        settled.is_ok()
+    }
+
+    /// 启动反代服务
+    ///
+    /// 已处于活动状态时直接返回当前快照；Draining 期间拒绝启动
+    pub async fn start(&self) -> anyhow::Result<ProxySnapshot> {
+        let current = self.state();
+        if current == ProxyState::Draining {
+            anyhow::bail!("反代服务正在停止，请稍后再试");
+        }
+        if current.is_active() {
+            return Ok(self.snapshot());
+        }
+
+        if self.inner.shared_listener {
+            self.transition_with(
+                &[ProxyState::Stopped, ProxyState::Crashed],
+                ProxyState::Running,
+                |s| s.last_error = None,
+            );
+            return Ok(self.snapshot());
+        }
+
+        let Some(ctx) = self.inner.context.lock().clone() else {
+            anyhow::bail!("Admin 服务尚未就绪");
+        };
+        if !self.begin_start() {
+            // 并发启动：由另一调用方负责
+            return Ok(self.snapshot());
+        }
+
+        let (tx, rx) = watch::channel(false);
+        *self.inner.shutdown_tx.lock() = Some(tx);
+
+        let config = ctx.config.lock().clone();
+        let drain_timeout = Duration::from_secs(config.proxy_drain_timeout_secs);
+        *self.inner.drain_timeout.lock() = drain_timeout;
+        let token_manager = ctx.token_manager.clone();
+        let api_key = ctx.api_key.clone();
+        let tls = ctx.tls.clone();
+        let lifecycle = self.clone();
+        let mut drain_rx = rx.clone();
+        tokio::spawn(async move {
+            let server = run_proxy_only_server(config, token_manager, api_key, tls, rx, lifecycle.clone());
+            tokio::pin!(server);
+
+            // 收到停止信号后开始计时，宽限期到仍未排空则强制停止
+            let drain_expired = async {
+                if drain_rx.wait_for(|stop| *stop).await.is_err() {
+                    std::future::pending::<()>().await;
+                }
+                tokio::time::sleep(drain_timeout).await;
+            };
+
+            let result = tokio::select! {
+                result = &mut server => result,
+                _ = drain_expired => {
+                    let active = STREAM_METRICS.active_streams();
+                    tracing::warn!(
+                        "[反代服务] 排空超时（{} 秒），强制中断 {} 个进行中的流",
+                        drain_timeout.as_secs(),
+                        active
+                    );
+                    LOG_COLLECTOR.add_log(
+                        "WARN",
+                        &format!("⏱️ 反代服务排空超时，强制中断 {} 个进行中的流", active),
+                    );
+                    // 切换为 Stopped 后流式响应自行中断，监听任务随之退出
+                    lifecycle.mark_stopped();
+                    server.await
+                }
+            };
+
+            match result {
+                Ok(()) => {
+                    lifecycle.mark_stopped();
+                    tracing::info!("[反代服务] 已停止");
+                }
+                Err(e) => {
+                    tracing::error!("[反代服务] 运行错误: {}", e);
+                    lifecycle.mark_crashed(e.to_string());
+                }
+            }
+        });
+
+        let mut rx = self.subscribe();
+        let settled = tokio::time::timeout(
+            START_TIMEOUT,
+            rx.wait_for(|s| s.state != ProxyState::Starting),
+        )
+        .await;
+        if settled.is_err() {
+            tracing::warn!("[反代服务] 等待监听超时，仍在启动中");
+        }
+
+        let snapshot = self.snapshot();
+        if snapshot.state == ProxyState::Crashed {
+            anyhow::bail!(snapshot.last_error.unwrap_or_else(|| "未知错误".to_string()));
+        }
+        Ok(snapshot)
+    }
+
+    /// 停止反代服务，返回是否发生状态变化
+    ///
+    /// 独立监听器进入 Draining，排空或宽限期到后由服务任务切换为 Stopped；
+    /// 单端口模式立即切换为 Stopped。Crashed 状态会被清理为 Stopped。
+    pub fn stop(&self) -> bool {
+        if self.state() == ProxyState::Crashed {
+            return self.transition(&[ProxyState::Crashed], ProxyState::Stopped);
+        }
+
+        let shutdown_tx = self.inner.shutdown_tx.lock().take();
+        match shutdown_tx {
+            Some(tx) => {
+                let deadline = chrono::Duration::from_std(self.drain_timeout())
+                    .ok()
+                    .map(|timeout| Utc::now() + timeout);
+                let changed = self.transition_with(
+                    &[ProxyState::Starting, ProxyState::Running],
+                    ProxyState::Draining,
+                    |s| s.drain_deadline = deadline,
+                );
+                let _ = tx.send(true);
+                changed
+            }
+            None => self.transition(
+                &[ProxyState::Starting, ProxyState::Running],
+                ProxyState::Stopped,
+            ),
+        }
+    }
+
+    /// 重启反代服务
+    ///
+    /// 先停止并等待进行中的请求排空，再执行 `before_start`（如切换分组），最后重新启动。
+    /// 宽限期过后监听器仍未退出时不会启动新实例
+    pub async fn restart(&self, before_start: impl FnOnce()) -> anyhow::Result<ProxySnapshot> {
+        self.stop();
+        let timeout = self.drain_timeout() + RESTART_STOP_MARGIN;
+        if !self.wait_stopped(timeout).await {
+            anyhow::bail!(
+                "等待进行中的请求结束超时（{} 秒），请稍后再试",
+                timeout.as_secs()
+            );
+        }
+        before_start();
+        self.start().await
+    }
+}
+
+impl Default for ProxyLifecycle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 分组反代实例管理器
+///
+/// 每个启用的 [`GroupListener`] 对应一个独立监听器的 [`ProxyLifecycle`]，配置变化时通过
+/// [`GroupProxies::sync`] 启动新增实例、停止被删除或修改的实例
+pub struct GroupProxies {
+    instances: Mutex<HashMap<String, ProxyLifecycle>>,
+    context: Mutex<Option<Arc<AdminContext>>>,
+}
+
+impl GroupProxies {
+    pub fn new() -> Self {
+        Self {
+            instances: Mutex::new(HashMap::new()),
+            context: Mutex::new(None),
+        }
+    }
+
+    /// 注册启动监听器所需的上下文
+    pub fn attach_context(&self, ctx: Arc<AdminContext>) {
+        *self.context.lock() = Some(ctx);
+    }
+
+    /// 所有实例的状态（按分组 ID 排序）
+    pub fn snapshots(&self) -> Vec<ProxySnapshot> {
+        let mut snapshots: Vec<ProxySnapshot> =
+            self.instances.lock().values().map(|p| p.snapshot()).collect();
+        snapshots.sort_by(|a, b| a.group_id.cmp(&b.group_id));
+        snapshots
+    }
+
+    /// 按配置同步实例，返回启动失败的 (分组 ID, 错误信息)
+    ///
+    /// 分组或端口未变化的运行中实例保持不动；被删除、禁用或修改的实例进入 Draining 后移除
+    pub async fn sync(&self, listeners: &[GroupListener]) -> Vec<(String, String)> {
+        let enabled: Vec<&GroupListener> = listeners.iter().filter(|l| l.enabled).collect();
+
+        let to_start: Vec<GroupListener> = {
+            let mut instances = self.instances.lock();
+            instances.retain(|group_id, proxy| {
+                let keep = proxy.state().is_active()
+                    && enabled
+                        .iter()
+                        .any(|l| &l.group_id == group_id && Some(*l) == proxy.group_listener());
+                if !keep {
+                    proxy.stop();
+                }
+                keep
+            });
+            enabled
+                .iter()
+                .filter(|l| !instances.contains_key(&l.group_id))
+                .map(|l| (*l).clone())
+                .collect()
+        };
+
+        let mut errors = Vec::new();
+        if to_start.is_empty() {
+            return errors;
+        }
+        let Some(ctx) = self.context.lock().clone() else {
+            return to_start
+                .into_iter()
+                .map(|l| (l.group_id, "Admin 服务尚未就绪".to_string()))
+                .collect();
+        };
+
+        for listener in to_start {
+            let group_id = listener.group_id.clone();
+            let proxy = ProxyLifecycle::for_group(listener);
+            proxy.attach_context(ctx.clone());
+            self.instances.lock().insert(group_id.clone(), proxy.clone());
+            if let Err(e) = proxy.start().await {
+                tracing::error!("[分组反代] 分组 {} 启动失败: {}", group_id, e);
+                errors.push((group_id, e.to_string()));
+            }
+        }
+        errors
+    }
+}
+
+impl Default for GroupProxies {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+lazy_static::lazy_static! {
+    /// 全局分组反代实例管理器
+    pub static ref GROUP_PROXIES: GroupProxies = GroupProxies::new();
+}
+
+/// 校验分组反代配置：分组必须存在，端口不能重复，也不能与 Admin / 主反代端口冲突
+pub fn validate_group_listeners(listeners: &[GroupListener], config: &Config) -> Result<(), String> {
+    let mut ports = std::collections::HashSet::new();
+    let mut groups = std::collections::HashSet::new();
+    for listener in listeners {
+        if !config.groups.iter().any(|g| g.id == listener.group_id) {
+            return Err(format!("分组不存在: {}", listener.group_id));
+        }
+        if !groups.insert(listener.group_id.as_str()) {
+            return Err(format!("分组 {} 只能配置一个反代实例", listener.group_id));
+        }
+        if listener.port == 0 || listener.port == config.port || listener.port == config.proxy_port {
+            return Err(format!("端口 {} 不可用（与 Admin 或主反代端口冲突）", listener.port));
+        }
+        if !ports.insert(listener.port) {
+            return Err(format!("端口 {} 重复", listener.port));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_shared_listener_soft_toggle() {
+        let lifecycle = ProxyLifecycle::shared_listener();
+        assert!(lifecycle.accepts_requests());
+
+        assert!(lifecycle.stop());
+        assert_eq!(lifecycle.state(), ProxyState::Stopped);
+        assert!(lifecycle.should_abort_streams());
+        assert!(!lifecycle.stop());
+
+        let snapshot = lifecycle.start().await.unwrap();
+        assert_eq!(snapshot.state, ProxyState::Running);
+    }
+
+    #[tokio::test]
+    async fn test_restart_runs_hook_between_stop_and_start() {
+        let lifecycle = ProxyLifecycle::shared_listener();
+        let mut hook_state = None;
+        let snapshot = lifecycle
+            .restart(|| hook_state = Some(lifecycle.state()))
+            .await
+            .unwrap();
+        assert_eq!(hook_state, Some(ProxyState::Stopped));
+        assert_eq!(snapshot.state, ProxyState::Running);
+    }
+
+    #[tokio::test]
+    async fn test_standalone_requires_context() {
+        let lifecycle = ProxyLifecycle::new();
+        assert_eq!(lifecycle.state(), ProxyState::Stopped);
+        assert!(lifecycle.start().await.is_err());
+        assert_eq!(lifecycle.state(), ProxyState::Stopped);
+    }
+
+    #[test]
+    fn test_draining_until_listener_exits() {
+        let lifecycle = ProxyLifecycle::new();
+        let (tx, rx) = watch::channel(false);
+        assert!(lifecycle.begin_start());
+        *lifecycle.inner.shutdown_tx.lock() = Some(tx);
+        assert!(lifecycle.mark_running(8991, vec!["127.0.0.1:8991".to_string(), "[::1]:8991".to_string()]));
+        assert_eq!(lifecycle.snapshot().port, Some(8991));
+        assert_eq!(lifecycle.snapshot().addresses.len(), 2);
+
+        assert!(lifecycle.stop());
+        assert_eq!(lifecycle.state(), ProxyState::Draining);
+        let deadline = lifecycle.snapshot().drain_deadline.expect("draining sets a deadline");
+        assert!(deadline > lifecycle.snapshot().since);
+        assert!(!lifecycle.accepts_requests());
+        assert!(!lifecycle.should_abort_streams());
+        assert!(*rx.borrow());
+
+        assert!(lifecycle.mark_stopped());
+        assert_eq!(lifecycle.state(), ProxyState::Stopped);
+        assert_eq!(lifecycle.snapshot().port, None);
+        assert!(lifecycle.snapshot().addresses.is_empty());
+        assert_eq!(lifecycle.snapshot().drain_deadline, None);
+    }
+
+    #[tokio::test]
+    async fn test_group_proxies_require_context_and_valid_ports() {
+        let config = Config::default();
+        let listener = GroupListener {
+            group_id: "default".to_string(),
+            port: 9001,
+            enabled: true,
+        };
+        assert!(validate_group_listeners(std::slice::from_ref(&listener), &config).is_ok());
+
+        let unknown_group = GroupListener {
+            group_id: "missing".to_string(),
+            ..listener.clone()
+        };
+        assert!(validate_group_listeners(&[unknown_group], &config).is_err());
+        let main_port = GroupListener {
+            port: config.proxy_port,
+            ..listener.clone()
+        };
+        assert!(validate_group_listeners(&[main_port], &config).is_err());
+
+        let proxies = GroupProxies::new();
+        let errors = proxies.sync(std::slice::from_ref(&listener)).await;
+        assert_eq!(errors.len(), 1);
+        assert!(proxies.snapshots().is_empty());
+
+        let disabled = GroupListener {
+            enabled: false,
+            ..listener
+        };
+        assert!(proxies.sync(&[disabled]).await.is_empty());
+    }
+
+    #[test]
+    fn test_crash_records_error() {
+        let lifecycle = ProxyLifecycle::new();
+        lifecycle.begin_start();
+        assert!(lifecycle.mark_crashed("bind failed"));
+
+        let snapshot = lifecycle.snapshot();
+        assert_eq!(snapshot.state, ProxyState::Crashed);
+        assert_eq!(snapshot.last_error.as_deref(), Some("bind failed"));
+
+        // 崩溃后停止只清理状态，保留错误信息供排查
+        assert!(lifecycle.stop());
+        assert_eq!(lifecycle.state(), ProxyState::Stopped);
+        assert_eq!(lifecycle.snapshot().last_error.as_deref(), Some("bind failed"));
+    }
+}
@@ -0,0 +1,289 @@
+//! 反代请求限流
+//!
+//! 按客户端 IP 和 API Key 分别维护令牌桶（RPM 与 TPM），在认证之前拦截，
+//! 避免单个异常客户端的大量请求通过失败计数把所有凭证拖到禁用状态。
+//! Key 桶只为有效的 Key（主密钥或已启用的租户 Key）按 Key ID 创建，无效 Key 只受 IP 限流，
+//! 随后由认证拒绝，客户端无法通过随机 Key 让桶无限增长。
+//! TPM 在读取请求体之前按 Content-Length / 4 估算输入 tokens。
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, State},
+    http::{Request, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use parking_lot::Mutex;
+
+use crate::anthropic::types::ErrorResponse;
+use crate::api_keys::API_KEY_REGISTRY;
+use crate::common::auth;
+use crate::model::config::RateLimitConfig;
+
+/// 清理间隔（桶空闲一分钟即补满，补满的桶与新建的桶等价，清理时直接移除）
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 令牌桶：容量为每分钟额度，按秒匀速补充
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(per_minute: u32, now: Instant) -> Self {
+        Self {
+            capacity: per_minute as f64,
+            tokens: per_minute as f64,
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.capacity / 60.0).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    /// 需要等待多久才能取出 cost 个令牌（0 表示可以立即取出）
+    fn wait_time(&self, cost: f64) -> Duration {
+        // 单次消耗超过容量时按桶满处理，避免大请求永远无法通过
+        let cost = cost.min(self.capacity);
+        if self.tokens >= cost {
+            return Duration::ZERO;
+        }
+        let rate = self.capacity / 60.0;
+        Duration::from_secs_f64((cost - self.tokens) / rate)
+    }
+
+    fn take(&mut self, cost: f64) {
+        self.tokens -= cost.min(self.capacity);
+    }
+
+    fn is_full(&self) -> bool {
+        self.tokens >= self.capacity
+    }
+}
+
+#[derive(Default)]
+struct Buckets {
+    rpm: HashMap<String, TokenBucket>,
+    tpm: HashMap<String, TokenBucket>,
+    last_cleanup: Option<Instant>,
+}
+
+/// 限流器
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    /// 反代主密钥（用于识别有效 Key）
+    api_key: String,
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig, api_key: impl Into<String>) -> Self {
+        Self {
+            config,
+            api_key: api_key.into(),
+            buckets: Mutex::new(Buckets::default()),
+        }
+    }
+
+    /// 将客户端提供的 Key 解析为 Key ID（无效 Key 返回 None）
+    fn key_id(&self, key: &str) -> Option<String> {
+        if auth::constant_time_eq(key, &self.api_key) {
+            return Some("main".to_string());
+        }
+        API_KEY_REGISTRY
+            .resolve(key)
+            .map(|tenant| format!("tenant:{}", tenant.id))
+    }
+
+    /// 检查一次请求，所有相关桶都有余量时一次性扣除；否则返回需要等待的时间
+    ///
+    /// `key_id` 必须是已验证的 Key ID（见 [`Self::key_id`]）
+    fn check(&self, ip: Option<&str>, key_id: Option<&str>, est_tokens: u64) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock();
+        Self::cleanup(&mut buckets, now);
+
+        // (桶 key, 每分钟额度, 是否为 TPM 桶, 消耗)
+        let mut checks: Vec<(String, u32, bool, f64)> = Vec::new();
+        if let Some(ip) = ip {
+            if let Some(rpm) = self.config.per_ip_rpm {
+                checks.push((format!("ip:{}", ip), rpm, false, 1.0));
+            }
+            if let Some(tpm) = self.config.per_ip_tpm {
+                checks.push((format!("ip:{}", ip), tpm, true, est_tokens as f64));
+            }
+        }
+        if let Some(key) = key_id {
+            if let Some(rpm) = self.config.per_key_rpm {
+                checks.push((format!("key:{}", key), rpm, false, 1.0));
+            }
+            if let Some(tpm) = self.config.per_key_tpm {
+                checks.push((format!("key:{}", key), tpm, true, est_tokens as f64));
+            }
+        }
+
+        let mut wait = Duration::ZERO;
+        for (key, limit, is_tpm, cost) in &checks {
+            let map = if *is_tpm { &mut buckets.tpm } else { &mut buckets.rpm };
+            let bucket = map
+                .entry(key.clone())
+                .or_insert_with(|| TokenBucket::new(*limit, now));
+            bucket.refill(now);
+            wait = wait.max(bucket.wait_time(*cost));
+        }
+        if !wait.is_zero() {
+            return Err(wait);
+        }
+
+        for (key, _, is_tpm, cost) in &checks {
+            let map = if *is_tpm { &mut buckets.tpm } else { &mut buckets.rpm };
+            if let Some(bucket) = map.get_mut(key) {
+                bucket.take(*cost);
+            }
+        }
+        Ok(())
+    }
+
+    fn cleanup(buckets: &mut Buckets, now: Instant) {
+        let due = buckets
+            .last_cleanup
+            .is_none_or(|t| now.duration_since(t) >= CLEANUP_INTERVAL);
+        if !due {
+            return;
+        }
+        let still_used = |_: &String, b: &mut TokenBucket| {
+            b.refill(now);
+            !b.is_full()
+        };
+        buckets.rpm.retain(still_used);
+        buckets.tpm.retain(still_used);
+        buckets.last_cleanup = Some(now);
+    }
+}
+
+/// 限流中间件
+pub async fn rate_limit_middleware(
+    State(limiter): State<Arc<RateLimiter>>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    if !limiter.config.enabled {
+        return next.run(request).await;
+    }
+
+    let ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_string());
+    let key_id = auth::extract_api_key(&request).and_then(|key| limiter.key_id(&key));
+    let est_tokens = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok())
+        .map(|len| len / 4)
+        .unwrap_or(0);
+
+    match limiter.check(ip.as_deref(), key_id.as_deref(), est_tokens) {
+        Ok(()) => next.run(request).await,
+        Err(wait) => {
+            let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+            tracing::warn!(
+                "请求被限流: ip={}, retry_after={}s",
+                ip.as_deref().unwrap_or("unknown"),
+                retry_after
+            );
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after.to_string())],
+                Json(ErrorResponse::new(
+                    "rate_limit_error",
+                    format!("Rate limit exceeded, retry after {} seconds", retry_after),
+                )),
+            )
+                .into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(per_ip_rpm: Option<u32>, per_key_tpm: Option<u32>) -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            enabled: true,
+            per_ip_rpm,
+            per_key_rpm: None,
+            per_ip_tpm: None,
+            per_key_tpm,
+        }, "sk-main")
+    }
+
+    #[test]
+    fn test_per_ip_rpm() {
+        let limiter = limiter(Some(2), None);
+        assert!(limiter.check(Some("1.1.1.1"), None, 0).is_ok());
+        assert!(limiter.check(Some("1.1.1.1"), None, 0).is_ok());
+        let wait = limiter.check(Some("1.1.1.1"), None, 0).unwrap_err();
+        assert!(wait.as_secs_f64() > 0.0 && wait.as_secs_f64() <= 30.0);
+        // 其他 IP 不受影响
+        assert!(limiter.check(Some("2.2.2.2"), None, 0).is_ok());
+    }
+
+    #[test]
+    fn test_per_key_tpm() {
+        let limiter = limiter(None, Some(1000));
+        assert!(limiter.check(None, Some("k"), 800).is_ok());
+        assert!(limiter.check(None, Some("k"), 800).is_err());
+        assert!(limiter.check(None, Some("other"), 800).is_ok());
+    }
+
+    #[test]
+    fn test_rejected_request_does_not_consume() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            enabled: true,
+            per_ip_rpm: Some(10),
+            per_key_rpm: Some(1),
+            per_ip_tpm: None,
+            per_key_tpm: None,
+        }, "sk-main");
+        assert!(limiter.check(Some("ip"), Some("k"), 0).is_ok());
+        assert!(limiter.check(Some("ip"), Some("k"), 0).is_err());
+        // Key 桶被拒绝时 IP 桶不应被扣除
+        for _ in 0..9 {
+            assert!(limiter.check(Some("ip"), None, 0).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_unknown_keys_get_no_bucket_and_idle_buckets_evicted() {
+        let limiter = limiter(Some(2), Some(1000));
+        assert_eq!(limiter.key_id("sk-main").as_deref(), Some("main"));
+        assert!(limiter.key_id("sk-random").is_none());
+
+        for i in 0..100 {
+            let key_id = limiter.key_id(&format!("sk-random-{}", i));
+            let _ = limiter.check(None, key_id.as_deref(), 10);
+        }
+        assert!(limiter.buckets.lock().tpm.is_empty());
+
+        assert!(limiter.check(Some("1.1.1.1"), Some("main"), 10).is_ok());
+        let mut buckets = limiter.buckets.lock();
+        assert_eq!((buckets.rpm.len(), buckets.tpm.len()), (1, 1));
+        // 补满后的桶在下一次清理时移除
+        buckets.last_cleanup = None;
+        RateLimiter::cleanup(&mut buckets, Instant::now() + CLEANUP_INTERVAL);
+        assert!(buckets.rpm.is_empty() && buckets.tpm.is_empty());
+    }
+}
@@ -0,0 +1,277 @@
+//! 出站内容脱敏
+//!
+//! 请求在转换为 Kiro 请求之前按 `redaction` 配置的规则替换敏感内容（内部主机名、API Key 等），
+//! 覆盖系统提示、消息内容（含工具调用参数和工具结果）以及工具描述。规则为关键词（忽略大小写）
+//! 或正则表达式，按顺序执行。脱敏发生在请求改写之后、日志记录和响应缓存键计算之前，
+//! 命中报告通过 `x-kiro-gateway-redactions` 响应头返回（只含规则名和次数，不含原文）。
+
+use axum::http::HeaderValue;
+use axum::response::Response;
+use lazy_static::lazy_static;
+use parking_lot::RwLock;
+use regex::Regex;
+use serde_json::Value;
+
+use crate::anthropic::types::MessagesRequest;
+use crate::model::config::{RedactionConfig, RedactionRule};
+
+/// 命中报告响应头
+pub const REDACTIONS_HEADER: &str = "x-kiro-gateway-redactions";
+
+/// 内容块（带 `type` 的对象）中不参与脱敏的字段（类型、ID、工具名、图片数据等结构性内容）
+const SKIPPED_KEYS: &[&str] = &["type", "id", "tool_use_id", "name", "media_type", "data", "signature"];
+
+/// 编译后的规则
+struct CompiledRule {
+    name: String,
+    regex: Regex,
+    replacement: String,
+}
+
+fn compile(rule: &RedactionRule) -> Result<CompiledRule, String> {
+    let name = rule.name.trim();
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(format!("脱敏规则名无效: '{}'（只能包含字母、数字、- 和 _）", rule.name));
+    }
+    if rule.pattern.is_empty() {
+        return Err(format!("脱敏规则 '{}' 的匹配内容不能为空", name));
+    }
+    let pattern = if rule.regex {
+        rule.pattern.clone()
+    } else {
+        format!("(?i){}", regex::escape(&rule.pattern))
+    };
+    let regex = Regex::new(&pattern).map_err(|e| format!("脱敏规则 '{}' 的正则表达式无效: {}", name, e))?;
+    if regex.is_match("") {
+        return Err(format!("脱敏规则 '{}' 会匹配空字符串", name));
+    }
+    Ok(CompiledRule {
+        name: name.to_string(),
+        regex,
+        replacement: rule
+            .replacement
+            .clone()
+            .unwrap_or_else(|| format!("[REDACTED:{}]", name)),
+    })
+}
+
+/// 校验脱敏配置
+pub fn validate(config: &RedactionConfig) -> Result<(), String> {
+    for rule in &config.rules {
+        compile(rule)?;
+    }
+    Ok(())
+}
+
+/// 一次请求的脱敏结果：按规则顺序记录命中次数（未命中的规则不出现）
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RedactionReport {
+    hits: Vec<(String, usize)>,
+    report_header: bool,
+}
+
+impl RedactionReport {
+    pub fn is_empty(&self) -> bool {
+        self.hits.is_empty()
+    }
+
+    fn add(&mut self, name: &str, count: usize) {
+        match self.hits.iter_mut().find(|(n, _)| n == name) {
+            Some((_, total)) => *total += count,
+            None => self.hits.push((name.to_string(), count)),
+        }
+    }
+
+    /// 命中报告，如 `internal-host=2, api-key=1`
+    pub fn summary(&self) -> String {
+        self.hits
+            .iter()
+            .map(|(name, count)| format!("{}={}", name, count))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// 在响应头中附带命中报告（未命中或关闭报告时原样返回）
+    pub fn mark(&self, mut response: Response) -> Response {
+        if self.report_header && !self.is_empty() {
+            if let Ok(value) = HeaderValue::from_str(&self.summary()) {
+                response.headers_mut().insert(REDACTIONS_HEADER, value);
+            }
+        }
+        response
+    }
+}
+
+/// 内容脱敏器（配置热更新）
+pub struct Redactor {
+    rules: RwLock<Vec<CompiledRule>>,
+    report_header: RwLock<bool>,
+}
+
+impl Redactor {
+    pub fn new() -> Self {
+        Self {
+            rules: RwLock::new(Vec::new()),
+            report_header: RwLock::new(true),
+        }
+    }
+
+    /// 替换脱敏配置（立即生效，无效规则记录警告后跳过）
+    pub fn set_config(&self, config: &RedactionConfig) {
+        let rules = config
+            .rules
+            .iter()
+            .filter(|r| r.enabled)
+            .filter_map(|rule| {
+                compile(rule)
+                    .inspect_err(|e| tracing::warn!("已跳过无效的脱敏规则: {}", e))
+                    .ok()
+            })
+            .collect();
+        *self.rules.write() = rules;
+        *self.report_header.write() = config.report_header;
+    }
+
+    /// 对请求执行脱敏，返回命中报告
+    pub fn apply(&self, request: &mut MessagesRequest) -> RedactionReport {
+        let mut report = RedactionReport {
+            report_header: *self.report_header.read(),
+            ..Default::default()
+        };
+        let rules = self.rules.read();
+        if rules.is_empty() {
+            return report;
+        }
+
+        let mut redact = |text: &mut String| {
+            for rule in rules.iter() {
+                let count = rule.regex.find_iter(text).count();
+                if count > 0 {
+                    *text = rule.regex.replace_all(text, rule.replacement.as_str()).into_owned();
+                    report.add(&rule.name, count);
+                }
+            }
+        };
+
+        for system in request.system.iter_mut().flatten() {
+            redact(&mut system.text);
+        }
+        for message in &mut request.messages {
+            redact_value(&mut message.content, &mut redact);
+        }
+        for tool in request.tools.iter_mut().flatten() {
+            redact(&mut tool.description);
+        }
+
+        if !report.is_empty() {
+            tracing::info!("🛡️ 出站内容脱敏: {}", report.summary());
+        }
+        report
+    }
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 递归脱敏 JSON 中的字符串（跳过结构性字段）
+fn redact_value(value: &mut Value, redact: &mut impl FnMut(&mut String)) {
+    match value {
+        Value::String(text) => redact(text),
+        Value::Array(items) => {
+            for item in items {
+                redact_value(item, redact);
+            }
+        }
+        Value::Object(map) => {
+            let is_block = map.contains_key("type");
+            for (key, item) in map.iter_mut() {
+                if !(is_block && SKIPPED_KEYS.contains(&key.as_str())) {
+                    redact_value(item, redact);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+// 全局内容脱敏器
+lazy_static! {
+    pub static ref REDACTOR: Redactor = Redactor::new();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(name: &str, pattern: &str, regex: bool) -> RedactionRule {
+        RedactionRule {
+            name: name.to_string(),
+            pattern: pattern.to_string(),
+            regex,
+            replacement: None,
+            enabled: true,
+        }
+    }
+
+    fn request() -> MessagesRequest {
+        serde_json::from_value(serde_json::json!({
+            "model": "claude-sonnet-4",
+            "max_tokens": 1024,
+            "system": [{ "type": "text", "text": "Deploy to build.corp.internal" }],
+            "tools": [{ "name": "Bash", "description": "Runs on BUILD.corp.internal", "input_schema": {} }],
+            "messages": [
+                { "role": "user", "content": "key sk-abc123def456 and build.corp.internal" },
+                { "role": "assistant", "content": [
+                    { "type": "tool_use", "id": "t1", "name": "Bash", "input": { "command": "curl build.corp.internal", "name": "build.corp.internal" } }
+                ]},
+                { "role": "user", "content": [
+                    { "type": "tool_result", "tool_use_id": "t1", "content": [{ "type": "text", "text": "token=sk-zzz999" }] }
+                ]}
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_redacts_keywords_and_regex() {
+        let redactor = Redactor::new();
+        redactor.set_config(&RedactionConfig {
+            rules: vec![
+                rule("internal-host", "build.corp.internal", false),
+                rule("api-key", r"sk-[A-Za-z0-9]{6,}", true),
+            ],
+            report_header: true,
+        });
+
+        let mut req = request();
+        let report = redactor.apply(&mut req);
+        assert_eq!(report.summary(), "internal-host=5, api-key=2");
+
+        assert_eq!(req.system.as_ref().unwrap()[0].text, "Deploy to [REDACTED:internal-host]");
+        assert_eq!(req.tools.as_ref().unwrap()[0].description, "Runs on [REDACTED:internal-host]");
+        assert_eq!(req.messages[0].content, "key [REDACTED:api-key] and [REDACTED:internal-host]");
+        assert_eq!(req.messages[1].content[0]["input"]["command"], "curl [REDACTED:internal-host]");
+        assert_eq!(req.messages[1].content[0]["input"]["name"], "[REDACTED:internal-host]");
+        assert_eq!(req.messages[1].content[0]["name"], "Bash");
+        assert_eq!(req.messages[2].content[0]["content"][0]["text"], "token=[REDACTED:api-key]");
+
+        let response = report.mark(Response::new(axum::body::Body::empty()));
+        assert_eq!(response.headers()[REDACTIONS_HEADER], "internal-host=5, api-key=2");
+    }
+
+    #[test]
+    fn test_no_rules_and_validation() {
+        let mut req = request();
+        assert!(Redactor::new().apply(&mut req).is_empty());
+
+        let invalid = |r: RedactionRule| validate(&RedactionConfig { rules: vec![r], report_header: true }).is_err();
+        assert!(invalid(rule("bad name", "x", false)));
+        assert!(invalid(rule("empty", "", false)));
+        assert!(invalid(rule("broken", "(", true)));
+        assert!(invalid(rule("matches-empty", "a*", true)));
+        assert!(!invalid(rule("ok", "a+", true)));
+    }
+}
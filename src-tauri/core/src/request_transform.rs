@@ -0,0 +1,169 @@
+//! 请求改写
+//!
+//! 在转换为 Kiro 请求之前按配置改写 Anthropic 请求：在系统提示前后注入文本、移除指定工具、
+//! 限制 max_tokens，使组织级约束无需修改每个客户端。全局改写先执行，API Key 的改写随后执行。
+//! 改写发生在模型映射和白名单检查之后、响应缓存键计算之前。
+
+use lazy_static::lazy_static;
+use parking_lot::RwLock;
+
+use crate::anthropic::types::{MessagesRequest, SystemMessage};
+use crate::model::config::RequestTransform;
+
+/// 校验改写配置
+pub fn validate(transform: &RequestTransform) -> Result<(), String> {
+    if transform.max_tokens_cap.is_some_and(|cap| cap <= 0) {
+        return Err("max_tokens 上限必须大于 0".to_string());
+    }
+    if transform.strip_tools.iter().any(|name| name.trim().is_empty()) {
+        return Err("移除的工具名不能为空".to_string());
+    }
+    Ok(())
+}
+
+/// 对请求执行一次改写
+pub fn apply(request: &mut MessagesRequest, transform: &RequestTransform) {
+    if let Some(text) = transform.system_prepend.as_deref().filter(|s| !s.trim().is_empty()) {
+        request
+            .system
+            .get_or_insert_with(Vec::new)
+            .insert(0, SystemMessage { text: text.to_string() });
+    }
+    if let Some(text) = transform.system_append.as_deref().filter(|s| !s.trim().is_empty()) {
+        request
+            .system
+            .get_or_insert_with(Vec::new)
+            .push(SystemMessage { text: text.to_string() });
+    }
+
+    if !transform.strip_tools.is_empty() {
+        if let Some(tools) = request.tools.as_mut() {
+            let before = tools.len();
+            tools.retain(|tool| !transform.strip_tools.contains(&tool.name));
+            if tools.len() < before {
+                tracing::debug!("请求改写：移除了 {} 个工具", before - tools.len());
+            }
+            if tools.is_empty() {
+                request.tools = None;
+            }
+        }
+        // 强制调用的工具已被移除时取消强制
+        let forced_stripped = request
+            .tool_choice
+            .as_ref()
+            .and_then(|choice| choice.get("name"))
+            .and_then(|name| name.as_str())
+            .is_some_and(|name| transform.strip_tools.iter().any(|t| t == name));
+        if forced_stripped || request.tools.is_none() {
+            request.tool_choice = None;
+        }
+    }
+
+    if let Some(cap) = transform.max_tokens_cap {
+        if request.max_tokens > cap {
+            tracing::debug!("请求改写：max_tokens {} -> {}", request.max_tokens, cap);
+            request.max_tokens = cap;
+        }
+    }
+}
+
+/// 请求改写器（全局配置 + 调用方传入的 API Key 配置）
+pub struct RequestTransformer {
+    global: RwLock<RequestTransform>,
+}
+
+impl RequestTransformer {
+    pub fn new() -> Self {
+        Self {
+            global: RwLock::new(RequestTransform::default()),
+        }
+    }
+
+    /// 替换全局改写配置（立即生效）
+    pub fn set_global(&self, transform: RequestTransform) {
+        *self.global.write() = transform;
+    }
+
+    /// 依次执行全局改写和 API Key 的改写
+    pub fn apply(&self, request: &mut MessagesRequest, tenant: Option<&RequestTransform>) {
+        {
+            let global = self.global.read();
+            if !global.is_empty() {
+                apply(request, &global);
+            }
+        }
+        if let Some(transform) = tenant.filter(|t| !t.is_empty()) {
+            apply(request, transform);
+        }
+    }
+}
+
+// 全局请求改写器
+lazy_static! {
+    pub static ref REQUEST_TRANSFORMER: RequestTransformer = RequestTransformer::new();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request() -> MessagesRequest {
+        serde_json::from_value(serde_json::json!({
+            "model": "claude-sonnet-4",
+            "max_tokens": 8192,
+            "system": [{ "type": "text", "text": "client prompt" }],
+            "tools": [
+                { "name": "Bash", "description": "run", "input_schema": {} },
+                { "name": "Read", "description": "read", "input_schema": {} }
+            ],
+            "tool_choice": { "type": "tool", "name": "Bash" },
+            "messages": [{ "role": "user", "content": "Hello" }]
+        }))
+        .unwrap()
+    }
+
+    fn system_texts(request: &MessagesRequest) -> Vec<&str> {
+        request
+            .system
+            .as_ref()
+            .map(|s| s.iter().map(|m| m.text.as_str()).collect())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_global_then_tenant_transform() {
+        let transformer = RequestTransformer::new();
+        transformer.set_global(RequestTransform {
+            system_prepend: Some("org policy".to_string()),
+            max_tokens_cap: Some(4096),
+            ..Default::default()
+        });
+        let tenant = RequestTransform {
+            system_append: Some("team note".to_string()),
+            strip_tools: vec!["Bash".to_string()],
+            max_tokens_cap: Some(1024),
+            ..Default::default()
+        };
+
+        let mut req = request();
+        transformer.apply(&mut req, Some(&tenant));
+
+        assert_eq!(system_texts(&req), vec!["org policy", "client prompt", "team note"]);
+        let tools: Vec<&str> = req.tools.as_ref().unwrap().iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(tools, vec!["Read"]);
+        assert!(req.tool_choice.is_none());
+        assert_eq!(req.max_tokens, 1024);
+    }
+
+    #[test]
+    fn test_empty_transform_is_noop_and_validation() {
+        let mut req = request();
+        RequestTransformer::new().apply(&mut req, Some(&RequestTransform::default()));
+        assert_eq!(system_texts(&req), vec!["client prompt"]);
+        assert_eq!(req.max_tokens, 8192);
+        assert!(req.tool_choice.is_some());
+
+        assert!(validate(&RequestTransform { max_tokens_cap: Some(0), ..Default::default() }).is_err());
+        assert!(validate(&RequestTransform { strip_tools: vec![" ".to_string()], ..Default::default() }).is_err());
+    }
+}
@@ -0,0 +1,131 @@
+//! 凭证轮换计划
+//!
+//! 按本地时间段切换活跃分组或当前凭证（如账户 A 00:00-12:00、账户 B 12:00-24:00），
+//! 使各账户的用量与其每日额度重置时间对齐。后台任务每分钟检查一次，仅在命中的规则
+//! 发生变化时切换；不在任何时段内时保持当前选择不变。
+
+use chrono::NaiveTime;
+use lazy_static::lazy_static;
+use parking_lot::{Mutex, RwLock};
+
+use crate::maintenance::parse_hhmm;
+use crate::model::config::RotationRule;
+
+/// 校验轮换计划，返回第一个错误的描述（分组/凭证是否存在由调用方检查）
+pub fn validate(rules: &[RotationRule]) -> Result<(), String> {
+    for rule in rules {
+        let start = rule.start.trim();
+        // 24:00 视为当天结束
+        let end = rule.end.trim();
+        if parse_hhmm(start).is_none() || (end != "24:00" && parse_hhmm(end).is_none()) {
+            return Err(format!("轮换时段格式错误: {} - {}（应为 HH:MM）", rule.start, rule.end));
+        }
+        if start == end {
+            return Err(format!("轮换时段起止时间不能相同: {}", rule.start));
+        }
+        if rule.group_id.is_none() && rule.credential_id.is_none() {
+            return Err(format!("轮换时段 {} - {} 未指定分组或凭证", rule.start, rule.end));
+        }
+    }
+    Ok(())
+}
+
+/// 查找给定时间命中的第一条规则
+pub fn matching_rule(rules: &[RotationRule], now: NaiveTime) -> Option<usize> {
+    rules.iter().position(|rule| match rule.end.trim() {
+        // 到当天结束：只比较起始时间
+        "24:00" => parse_hhmm(&rule.start).is_some_and(|start| now >= start),
+        end => crate::maintenance::window_contains(&rule.start, end, now),
+    })
+}
+
+/// 轮换计划调度器
+pub struct RotationScheduler {
+    rules: RwLock<Vec<RotationRule>>,
+    /// 最近一次应用的规则下标（替换计划后清空，下一轮立即重新应用）
+    applied: Mutex<Option<usize>>,
+}
+
+impl RotationScheduler {
+    pub fn new() -> Self {
+        Self {
+            rules: RwLock::new(Vec::new()),
+            applied: Mutex::new(None),
+        }
+    }
+
+    /// 替换轮换计划（下一轮检查时生效）
+    pub fn set_rules(&self, rules: Vec<RotationRule>) {
+        *self.rules.write() = rules;
+        *self.applied.lock() = None;
+    }
+
+    /// 返回需要应用的规则：命中的规则与上次应用的不同时返回，否则返回 None
+    pub fn due(&self, now: NaiveTime) -> Option<RotationRule> {
+        let rules = self.rules.read();
+        let index = matching_rule(&rules, now)?;
+        let mut applied = self.applied.lock();
+        if *applied == Some(index) {
+            return None;
+        }
+        *applied = Some(index);
+        Some(rules[index].clone())
+    }
+}
+
+// 全局轮换计划
+lazy_static! {
+    pub static ref ROTATION: RotationScheduler = RotationScheduler::new();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(start: &str, end: &str, group: &str) -> RotationRule {
+        RotationRule {
+            start: start.to_string(),
+            end: end.to_string(),
+            group_id: Some(group.to_string()),
+            credential_id: None,
+        }
+    }
+
+    fn t(s: &str) -> NaiveTime {
+        parse_hhmm(s).unwrap()
+    }
+
+    #[test]
+    fn test_matching_rule_with_day_end() {
+        let rules = vec![rule("00:00", "12:00", "a"), rule("12:00", "24:00", "b")];
+        assert!(validate(&rules).is_ok());
+        assert_eq!(matching_rule(&rules, t("00:00")), Some(0));
+        assert_eq!(matching_rule(&rules, t("11:59")), Some(0));
+        assert_eq!(matching_rule(&rules, t("12:00")), Some(1));
+        assert_eq!(matching_rule(&rules, t("23:59")), Some(1));
+    }
+
+    #[test]
+    fn test_due_only_when_rule_changes() {
+        let scheduler = RotationScheduler::new();
+        scheduler.set_rules(vec![rule("08:00", "20:00", "day"), rule("20:00", "08:00", "night")]);
+
+        assert_eq!(scheduler.due(t("09:00")).unwrap().group_id.as_deref(), Some("day"));
+        assert!(scheduler.due(t("10:00")).is_none());
+        assert_eq!(scheduler.due(t("21:00")).unwrap().group_id.as_deref(), Some("night"));
+        assert!(scheduler.due(t("03:00")).is_none());
+
+        // 替换计划后重新应用
+        scheduler.set_rules(vec![rule("00:00", "24:00", "all")]);
+        assert_eq!(scheduler.due(t("03:00")).unwrap().group_id.as_deref(), Some("all"));
+    }
+
+    #[test]
+    fn test_validate_rejects_invalid_rules() {
+        assert!(validate(&[rule("25:00", "03:00", "a")]).is_err());
+        assert!(validate(&[rule("12:00", "12:00", "a")]).is_err());
+        let mut empty = rule("01:00", "03:00", "a");
+        empty.group_id = None;
+        assert!(validate(&[empty]).is_err());
+    }
+}
@@ -0,0 +1,202 @@
+//! HTTPS 监听
+//!
+//! Admin 与反代服务默认只监听 HTTP。需要监听非本机地址时可以启用 TLS：
+//! 使用配置的证书，或在首次启动时生成自签名证书（覆盖 localhost、127.0.0.1 和监听地址）。
+//! 加密使用 rustls + ring，不依赖系统 OpenSSL。
+
+use std::future::Future;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum_server::tls_rustls::RustlsConfig;
+
+use crate::model::config::TlsConfig;
+
+/// 收到停止信号后等待连接关闭的最长时间
+const TLS_SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
+
+/// 解析证书与私钥路径（未配置时使用配置目录下的 tls/ 子目录）
+fn resolve_paths(tls: &TlsConfig, config_dir: &Path) -> (PathBuf, PathBuf) {
+    let default_dir = config_dir.join("tls");
+    let cert = tls
+        .cert_path
+        .as_deref()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| default_dir.join("cert.pem"));
+    let key = tls
+        .key_path
+        .as_deref()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| default_dir.join("key.pem"));
+    (cert, key)
+}
+
+/// 生成自签名证书并写入文件
+fn generate_self_signed(host: &str, cert_path: &Path, key_path: &Path) -> anyhow::Result<()> {
+    let mut names = vec!["localhost".to_string(), "127.0.0.1".to_string(), "::1".to_string()];
+    if !host.is_empty() && host != "0.0.0.0" && host != "::" && !names.iter().any(|n| n == host) {
+        names.push(host.to_string());
+    }
+
+    let certified = rcgen::generate_simple_self_signed(names)?;
+    for path in [cert_path, key_path] {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+    std::fs::write(cert_path, certified.cert.pem())?;
+    std::fs::write(key_path, certified.key_pair.serialize_pem())?;
+    restrict_permissions(key_path);
+    Ok(())
+}
+
+/// 私钥文件仅当前用户可读
+#[cfg(unix)]
+fn restrict_permissions(path: &Path) {
+    use std::os::unix::fs::PermissionsExt;
+    let _ = std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600));
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &Path) {}
+
+/// 从 PEM 构建 rustls 服务端配置
+fn server_config(cert_pem: &[u8], key_pem: &[u8]) -> anyhow::Result<rustls::ServerConfig> {
+    let certs = rustls_pemfile::certs(&mut &cert_pem[..]).collect::<Result<Vec<_>, _>>()?;
+    if certs.is_empty() {
+        anyhow::bail!("证书文件中没有证书");
+    }
+    let key = rustls_pemfile::private_key(&mut &key_pem[..])?
+        .ok_or_else(|| anyhow::anyhow!("私钥文件中没有私钥"))?;
+
+    let mut config = rustls::ServerConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()?
+    .with_no_client_auth()
+    .with_single_cert(certs, key)?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(config)
+}
+
+/// 按配置加载 TLS，未启用时返回 None
+///
+/// 证书不存在且开启了 `selfSigned` 时自动生成
+pub fn load(tls: &TlsConfig, host: &str, config_dir: &Path) -> anyhow::Result<Option<RustlsConfig>> {
+    if !tls.enabled {
+        return Ok(None);
+    }
+
+    let (cert_path, key_path) = resolve_paths(tls, config_dir);
+    if !cert_path.exists() || !key_path.exists() {
+        if !tls.self_signed {
+            anyhow::bail!(
+                "TLS 证书不存在: {} / {}（可开启 selfSigned 自动生成）",
+                cert_path.display(),
+                key_path.display()
+            );
+        }
+        generate_self_signed(host, &cert_path, &key_path)?;
+        tracing::warn!("已生成自签名证书: {}（客户端需要信任该证书）", cert_path.display());
+    }
+
+    let cert_pem = std::fs::read(&cert_path)
+        .map_err(|e| anyhow::anyhow!("读取证书失败 {}: {}", cert_path.display(), e))?;
+    let key_pem = std::fs::read(&key_path)
+        .map_err(|e| anyhow::anyhow!("读取私钥失败 {}: {}", key_path.display(), e))?;
+    let config = server_config(&cert_pem, &key_pem)?;
+    tracing::info!("已启用 HTTPS，证书: {}", cert_path.display());
+    Ok(Some(RustlsConfig::from_config(Arc::new(config))))
+}
+
+/// 监听协议名（用于日志）
+pub fn scheme(tls: &Option<RustlsConfig>) -> &'static str {
+    if tls.is_some() { "https" } else { "http" }
+}
+
+/// 在监听器上启动服务，配置了 TLS 时使用 HTTPS
+///
+/// `shutdown` 完成后停止接受新连接并等待已有连接结束
+pub async fn serve(
+    listener: tokio::net::TcpListener,
+    app: axum::Router,
+    tls: Option<RustlsConfig>,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> anyhow::Result<()> {
+    let service = app.into_make_service_with_connect_info::<SocketAddr>();
+
+    let Some(tls) = tls else {
+        axum::serve(listener, service).with_graceful_shutdown(shutdown).await?;
+        return Ok(());
+    };
+
+    let handle = axum_server::Handle::new();
+    let shutdown_handle = handle.clone();
+    tokio::spawn(async move {
+        shutdown.await;
+        shutdown_handle.graceful_shutdown(Some(TLS_SHUTDOWN_GRACE));
+    });
+
+    axum_server::from_tcp_rustls(listener.into_std()?, tls)
+        .handle(handle)
+        .serve(service)
+        .await?;
+    Ok(())
+}
+
+/// 在多个监听器上启动同一服务，`shutdown` 完成后全部停止
+pub async fn serve_all(
+    listeners: Vec<tokio::net::TcpListener>,
+    app: axum::Router,
+    tls: Option<RustlsConfig>,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> anyhow::Result<()> {
+    let (tx, rx) = tokio::sync::watch::channel(false);
+    tokio::spawn(async move {
+        shutdown.await;
+        let _ = tx.send(true);
+    });
+
+    let servers = listeners.into_iter().map(|listener| {
+        let mut rx = rx.clone();
+        serve(listener, app.clone(), tls.clone(), async move {
+            let _ = rx.wait_for(|stop| *stop).await;
+        })
+    });
+    futures::future::try_join_all(servers).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generates_self_signed_certificate_on_first_load() {
+        let dir = std::env::temp_dir().join(format!("kiro-tls-{}", uuid::Uuid::new_v4()));
+        let disabled = TlsConfig::default();
+        assert!(load(&disabled, "127.0.0.1", &dir).unwrap().is_none());
+
+        let missing = TlsConfig {
+            enabled: true,
+            ..TlsConfig::default()
+        };
+        assert!(load(&missing, "127.0.0.1", &dir).is_err());
+
+        let self_signed = TlsConfig {
+            self_signed: true,
+            ..missing
+        };
+        assert!(load(&self_signed, "192.168.1.10", &dir).unwrap().is_some());
+        assert!(dir.join("tls/cert.pem").exists());
+
+        // 再次加载复用已生成的证书
+        let cert = std::fs::read(dir.join("tls/cert.pem")).unwrap();
+        assert!(load(&self_signed, "192.168.1.10", &dir).unwrap().is_some());
+        assert_eq!(std::fs::read(dir.join("tls/cert.pem")).unwrap(), cert);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -6,6 +6,9 @@
 //! - 非西文字符：每个计 4.5 个字符单位
 //! - 西文字符：每个计 1 个字符单位
 //! - 4 个字符单位 = 1 token（四舍五入）
+//!
+//! 启用 `tokenizer` 特性后改用 BPE 分词器（cl100k_base）精确计数，
+//! 结果与 Anthropic 的统计更接近；分词器加载失败时回退到上述启发式估算。
 
 use crate::anthropic::types::{
     CountTokensRequest, CountTokensResponse, Message, SystemMessage, Tool,
@@ -66,15 +69,47 @@ fn is_non_western_char(c: char) -> bool {
     )
 }
 
+/// 获取 BPE 分词器（首次调用时加载，加载失败只记录一次警告）
+#[cfg(feature = "tokenizer")]
+fn bpe() -> Option<&'static tiktoken_rs::CoreBPE> {
+    static BPE: OnceLock<Option<tiktoken_rs::CoreBPE>> = OnceLock::new();
+    BPE.get_or_init(|| {
+        tiktoken_rs::cl100k_base()
+            .map_err(|e| tracing::warn!("加载 BPE 分词器失败，回退到启发式估算: {}", e))
+            .ok()
+    })
+    .as_ref()
+}
+
+/// 使用 BPE 分词器精确计数
+///
+/// 未启用 `tokenizer` 特性或分词器不可用时返回 None，由调用方回退到启发式估算
+pub(crate) fn bpe_count(text: &str) -> Option<u64> {
+    #[cfg(feature = "tokenizer")]
+    {
+        bpe().map(|bpe| bpe.encode_ordinary(text).len() as u64)
+    }
+    #[cfg(not(feature = "tokenizer"))]
+    {
+        let _ = text;
+        None
+    }
+}
+
 /// 计算文本的 token 数量
 ///
+/// 优先使用 BPE 分词器，不可用时回退到启发式估算
+pub fn count_tokens(text: &str) -> u64 {
+    bpe_count(text).unwrap_or_else(|| count_tokens_heuristic(text))
+}
+
+/// 按字符启发式估算 token 数量
+///
 /// # 计算规则
 /// - 非西文字符：每个计 4.5 个字符单位
 /// - 西文字符：每个计 1 个字符单位
 /// - 4 个字符单位 = 1 token（四舍五入）
-/// ```
-pub fn count_tokens(text: &str) -> u64 {
-    // println!("text: {}", text);
+fn count_tokens_heuristic(text: &str) -> u64 {
 
     let char_units: f64 = text
         .chars()
@@ -197,15 +232,7 @@ fn count_all_tokens_local(
 
     // 用户消息
     for msg in &messages {
-        if let serde_json::Value::String(s) = &msg.content {
-            total += count_tokens(s);
-        } else if let serde_json::Value::Array(arr) = &msg.content {
-            for item in arr {
-                if let Some(text) = item.get("text").and_then(|v| v.as_str()) {
-                    total += count_tokens(text);
-                }
-            }
-        }
+        total += count_message_tokens(msg);
     }
 
     // 工具定义
@@ -221,6 +248,19 @@ fn count_all_tokens_local(
     total.max(1)
 }
 
+/// 本地计算单条消息的 tokens（字符串内容或数组中的 text 块）
+pub(crate) fn count_message_tokens(msg: &Message) -> u64 {
+    match &msg.content {
+        serde_json::Value::String(s) => count_tokens(s),
+        serde_json::Value::Array(arr) => arr
+            .iter()
+            .filter_map(|item| item.get("text").and_then(|v| v.as_str()))
+            .map(count_tokens)
+            .sum(),
+        _ => 0,
+    }
+}
+
 /// 估算输出 tokens
 pub(crate) fn estimate_output_tokens(content: &[serde_json::Value]) -> i32 {
     let mut total = 0;
@@ -240,3 +280,21 @@ pub(crate) fn estimate_output_tokens(content: &[serde_json::Value]) -> i32 {
 
     total.max(1)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_tokens_uses_bpe_when_available() {
+        let text = "Hello, world! 你好，世界";
+        assert_eq!(bpe_count(text).is_some(), cfg!(feature = "tokenizer"));
+        match bpe_count(text) {
+            Some(tokens) => {
+                assert_eq!(count_tokens(text), tokens);
+                assert_eq!(bpe_count("Hello"), Some(1));
+            }
+            None => assert_eq!(count_tokens(text), count_tokens_heuristic(text)),
+        }
+    }
+}
@@ -0,0 +1,568 @@
+//! 对话记录存储（可选）
+//!
+//! 启用 `transcripts.enabled` 后，`/v1/messages` 的每次成功请求（脱敏后的请求消息 + 最终响应）
+//! 按天追加写入配置目录下的 `transcripts/YYYY-MM-DD.jsonl`（UTC 日期），并记录所属的租户 API Key，
+//! 用于审计和提示词调试。流式响应在流结束后由 SSE 事件还原为完整消息再写入（客户端中途断开的不记录）。
+//! 超过 `retentionDays` 的文件和超出 `maxSizeMb` 的最早文件会被删除。
+//! `GET /api/admin/transcripts` 按 API Key、模型、关键词和时间检索。
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+
+use axum::body::Body;
+use axum::response::Response;
+use bytes::Bytes;
+use chrono::{DateTime, NaiveDate, Utc};
+use futures::{StreamExt, stream};
+use lazy_static::lazy_static;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::anthropic::types::MessagesRequest;
+use crate::model::config::TranscriptConfig;
+
+/// 检索时匹配主密钥（非租户）请求的 API Key 过滤值
+pub const MAIN_KEY_FILTER: &str = "main";
+
+/// 预览文本的最大字符数
+const PREVIEW_CHARS: usize = 100;
+
+/// 一条对话记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Transcript {
+    pub id: String,
+    pub timestamp: DateTime<Utc>,
+    /// 租户 API Key ID（主密钥的请求为 None）
+    pub api_key_id: Option<String>,
+    pub model: String,
+    pub stream: bool,
+    /// 从收到请求到响应结束的耗时（毫秒）
+    pub duration_ms: u64,
+    /// 请求内容（system / messages / tools）
+    pub request: Value,
+    /// 最终响应（Anthropic Message 格式）
+    pub response: Value,
+}
+
+/// 检索结果中的记录摘要
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptSummary {
+    pub id: String,
+    pub timestamp: DateTime<Utc>,
+    pub api_key_id: Option<String>,
+    pub model: String,
+    pub stream: bool,
+    pub duration_ms: u64,
+    pub message_count: usize,
+    /// 最后一条用户消息的预览
+    pub preview: String,
+    pub stop_reason: Option<String>,
+}
+
+impl Transcript {
+    fn summary(&self) -> TranscriptSummary {
+        let messages = self.request["messages"].as_array();
+        let preview = messages
+            .and_then(|messages| messages.iter().rev().find(|m| m["role"] == "user"))
+            .map(|m| preview(&m["content"]))
+            .unwrap_or_default();
+        TranscriptSummary {
+            id: self.id.clone(),
+            timestamp: self.timestamp,
+            api_key_id: self.api_key_id.clone(),
+            model: self.model.clone(),
+            stream: self.stream,
+            duration_ms: self.duration_ms,
+            message_count: messages.map_or(0, Vec::len),
+            preview,
+            stop_reason: self.response["stop_reason"].as_str().map(str::to_string),
+        }
+    }
+}
+
+/// 消息内容的文本预览
+fn preview(content: &Value) -> String {
+    let text = match content {
+        Value::String(text) => text.as_str(),
+        Value::Array(blocks) => blocks
+            .iter()
+            .find_map(|b| b["text"].as_str())
+            .unwrap_or_default(),
+        _ => "",
+    };
+    if text.chars().count() > PREVIEW_CHARS {
+        format!("{}...", text.chars().take(PREVIEW_CHARS).collect::<String>())
+    } else {
+        text.to_string()
+    }
+}
+
+/// 检索条件
+#[derive(Debug, Clone, Default)]
+pub struct TranscriptFilter {
+    /// 租户 API Key ID，`main` 表示主密钥
+    pub api_key_id: Option<String>,
+    /// 模型名（包含匹配，忽略大小写）
+    pub model: Option<String>,
+    /// 关键词（在请求和响应全文中查找，忽略大小写）
+    pub query: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub limit: usize,
+}
+
+impl TranscriptFilter {
+    fn matches(&self, transcript: &Transcript) -> bool {
+        let key_matches = match self.api_key_id.as_deref() {
+            None => true,
+            Some(MAIN_KEY_FILTER) => transcript.api_key_id.is_none(),
+            Some(id) => transcript.api_key_id.as_deref() == Some(id),
+        };
+        key_matches
+            && self
+                .model
+                .as_ref()
+                .is_none_or(|m| transcript.model.to_lowercase().contains(&m.to_lowercase()))
+            && self.since.is_none_or(|since| transcript.timestamp >= since)
+            && self.until.is_none_or(|until| transcript.timestamp <= until)
+    }
+}
+
+/// 请求开始时的快照，响应结束后补全并写入
+pub struct PendingTranscript {
+    api_key_id: Option<String>,
+    model: String,
+    stream: bool,
+    request: Value,
+    started_at: Instant,
+}
+
+impl PendingTranscript {
+    fn finish(self, response: Value) {
+        let transcript = Transcript {
+            id: uuid::Uuid::new_v4().simple().to_string(),
+            timestamp: Utc::now(),
+            api_key_id: self.api_key_id,
+            model: self.model,
+            stream: self.stream,
+            duration_ms: self.started_at.elapsed().as_millis() as u64,
+            request: self.request,
+            response,
+        };
+        TRANSCRIPTS.append(&transcript);
+    }
+
+    /// 记录成功的响应后原样返回（流式响应在流结束后记录）
+    pub async fn record(self, response: Response) -> Response {
+        if !response.status().is_success() {
+            return response;
+        }
+
+        let (parts, body) = response.into_parts();
+        if !self.stream {
+            return match axum::body::to_bytes(body, usize::MAX).await {
+                Ok(bytes) => {
+                    match serde_json::from_slice(&bytes) {
+                        Ok(message) => self.finish(message),
+                        Err(e) => tracing::warn!("解析响应失败，跳过对话记录: {}", e),
+                    }
+                    Response::from_parts(parts, Body::from(bytes))
+                }
+                Err(e) => {
+                    tracing::warn!("读取响应失败，跳过对话记录: {}", e);
+                    Response::from_parts(parts, Body::empty())
+                }
+            };
+        }
+
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let sink = buffer.clone();
+        let data = body.into_data_stream().inspect(move |chunk| {
+            if let Ok(bytes) = chunk {
+                sink.lock().extend_from_slice(bytes);
+            }
+        });
+        // 流正常结束后还原完整消息并写入（客户端断开时流被丢弃，不会执行）
+        let finish = stream::once(async move {
+            let sse = String::from_utf8_lossy(&buffer.lock()).into_owned();
+            self.finish(assemble_stream(&sse));
+            None::<Result<Bytes, axum::Error>>
+        })
+        .filter_map(std::future::ready);
+        Response::from_parts(parts, Body::from_stream(data.chain(finish)))
+    }
+}
+
+/// 由 SSE 事件还原完整的 Anthropic Message
+fn assemble_stream(sse: &str) -> Value {
+    let mut message = json!({});
+    let mut content: Vec<Value> = Vec::new();
+    let mut partial_json: Vec<(usize, String)> = Vec::new();
+
+    for data in sse.lines().filter_map(|line| line.strip_prefix("data:")) {
+        let Ok(event) = serde_json::from_str::<Value>(data.trim()) else {
+            continue;
+        };
+        let index = event["index"].as_u64().map(|i| i as usize);
+        match event["type"].as_str().unwrap_or_default() {
+            "message_start" => message = event["message"].clone(),
+            "content_block_start" => {
+                if let Some(index) = index {
+                    if content.len() <= index {
+                        content.resize(index + 1, Value::Null);
+                    }
+                    content[index] = event["content_block"].clone();
+                }
+            }
+            "content_block_delta" => {
+                let Some(block) = index.and_then(|i| content.get_mut(i)) else {
+                    continue;
+                };
+                let delta = &event["delta"];
+                let (field, value) = match delta["type"].as_str().unwrap_or_default() {
+                    "text_delta" => ("text", &delta["text"]),
+                    "thinking_delta" => ("thinking", &delta["thinking"]),
+                    "signature_delta" => ("signature", &delta["signature"]),
+                    "input_json_delta" => {
+                        let index = index.unwrap_or_default();
+                        let partial = delta["partial_json"].as_str().unwrap_or_default();
+                        match partial_json.iter_mut().find(|(i, _)| *i == index) {
+                            Some((_, json)) => json.push_str(partial),
+                            None => partial_json.push((index, partial.to_string())),
+                        }
+                        continue;
+                    }
+                    _ => continue,
+                };
+                let existing = block[field].as_str().unwrap_or_default().to_string();
+                block[field] = Value::String(existing + value.as_str().unwrap_or_default());
+            }
+            "message_delta" => {
+                if let Some(delta) = event["delta"].as_object() {
+                    for (key, value) in delta {
+                        message[key] = value.clone();
+                    }
+                }
+                if let Some(usage) = event["usage"].as_object() {
+                    for (key, value) in usage {
+                        message["usage"][key] = value.clone();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for (index, json) in partial_json {
+        if let Some(block) = content.get_mut(index) {
+            block["input"] = serde_json::from_str(&json).unwrap_or(Value::String(json));
+        }
+    }
+    content.retain(|block| !block.is_null());
+    message["content"] = Value::Array(content);
+    message
+}
+
+/// 对话记录存储
+pub struct TranscriptStore {
+    dir: RwLock<Option<PathBuf>>,
+    config: RwLock<TranscriptConfig>,
+    /// 串行化文件写入和清理
+    write_lock: Mutex<()>,
+}
+
+impl TranscriptStore {
+    pub fn new() -> Self {
+        Self {
+            dir: RwLock::new(None),
+            config: RwLock::new(TranscriptConfig::default()),
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn set_dir(&self, dir: Option<PathBuf>) {
+        *self.dir.write() = dir;
+    }
+
+    /// 替换配置（立即生效，已保存的记录不受影响）
+    pub fn set_config(&self, config: TranscriptConfig) {
+        *self.config.write() = config;
+    }
+
+    /// 请求开始时创建记录快照（未启用时返回 None）
+    pub fn begin(&self, api_key_id: Option<&str>, request: &MessagesRequest) -> Option<PendingTranscript> {
+        if !self.config.read().enabled || self.dir.read().is_none() {
+            return None;
+        }
+        Some(PendingTranscript {
+            api_key_id: api_key_id.map(str::to_string),
+            model: request.model.clone(),
+            stream: request.stream,
+            request: json!({
+                "system": request.system,
+                "messages": request.messages,
+                "tools": request.tools,
+            }),
+            started_at: Instant::now(),
+        })
+    }
+
+    fn append(&self, transcript: &Transcript) {
+        use std::io::Write;
+
+        let Some(dir) = self.dir.read().clone() else {
+            return;
+        };
+        let _guard = self.write_lock.lock();
+        let path = dir.join(format!("{}.jsonl", transcript.timestamp.format("%Y-%m-%d")));
+        let written = std::fs::create_dir_all(&dir)
+            .and_then(|_| serde_json::to_string(transcript).map_err(std::io::Error::from))
+            .and_then(|line| {
+                let mut file = std::fs::OpenOptions::new().create(true).append(true).open(&path)?;
+                writeln!(file, "{}", line)
+            });
+        if let Err(e) = written {
+            tracing::warn!("写入对话记录失败: {}", e);
+        }
+    }
+
+    /// 记录文件（按日期升序）
+    fn files(&self) -> Vec<(NaiveDate, PathBuf)> {
+        let Some(dir) = self.dir.read().clone() else {
+            return Vec::new();
+        };
+        let mut files: Vec<_> = std::fs::read_dir(&dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                let date = file_date(&path)?;
+                Some((date, path))
+            })
+            .collect();
+        files.sort();
+        files
+    }
+
+    /// 按条件检索（按时间倒序）
+    pub fn search(&self, filter: &TranscriptFilter) -> Vec<TranscriptSummary> {
+        let query = filter.query.as_ref().map(|q| q.to_lowercase()).filter(|q| !q.is_empty());
+        let since_date = filter.since.map(|since| since.date_naive());
+        let mut results = Vec::new();
+
+        for (date, path) in self.files().into_iter().rev() {
+            if since_date.is_some_and(|since| date < since) {
+                break;
+            }
+            let Ok(content) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            for line in content.lines().rev().filter(|l| !l.trim().is_empty()) {
+                // 关键词先在原始行中粗筛，避免解析所有记录
+                if query.as_ref().is_some_and(|q| !line.to_lowercase().contains(q.as_str())) {
+                    continue;
+                }
+                let Ok(transcript) = serde_json::from_str::<Transcript>(line) else {
+                    continue;
+                };
+                if filter.matches(&transcript) {
+                    results.push(transcript.summary());
+                    if results.len() >= filter.limit {
+                        return results;
+                    }
+                }
+            }
+        }
+        results
+    }
+
+    /// 按 ID 读取完整记录
+    pub fn get(&self, id: &str) -> Option<Transcript> {
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let needle = format!("\"id\":\"{}\"", id);
+        self.files().into_iter().rev().find_map(|(_, path)| {
+            let content = std::fs::read_to_string(&path).ok()?;
+            content
+                .lines()
+                .filter(|line| line.contains(&needle))
+                .find_map(|line| serde_json::from_str::<Transcript>(line).ok().filter(|t| t.id == id))
+        })
+    }
+
+    /// 删除所有记录文件，返回删除的文件数
+    pub fn purge(&self) -> usize {
+        let _guard = self.write_lock.lock();
+        self.files()
+            .into_iter()
+            .filter(|(_, path)| std::fs::remove_file(path).is_ok())
+            .count()
+    }
+
+    /// 按保留天数和总大小上限清理旧文件，返回删除的文件数
+    pub fn enforce_retention(&self, today: NaiveDate) -> usize {
+        let config = self.config.read().clone();
+        let _guard = self.write_lock.lock();
+        let mut files: Vec<(NaiveDate, PathBuf, u64)> = self
+            .files()
+            .into_iter()
+            .map(|(date, path)| {
+                let size = std::fs::metadata(&path).map_or(0, |m| m.len());
+                (date, path, size)
+            })
+            .collect();
+
+        let mut expired = Vec::new();
+        if config.retention_days > 0 {
+            let cutoff = today - chrono::Duration::days(config.retention_days as i64);
+            let keep_from = files.partition_point(|(date, _, _)| *date < cutoff);
+            expired.extend(files.drain(..keep_from));
+        }
+        if config.max_size_mb > 0 {
+            let limit = config.max_size_mb * 1024 * 1024;
+            let mut total: u64 = files.iter().map(|(_, _, size)| size).sum();
+            // 至少保留最新的文件
+            while total > limit && files.len() > 1 {
+                let file = files.remove(0);
+                total -= file.2;
+                expired.push(file);
+            }
+        }
+
+        let removed = expired
+            .iter()
+            .filter(|(_, path, _)| match std::fs::remove_file(path) {
+                Ok(()) => true,
+                Err(e) => {
+                    tracing::warn!("删除过期对话记录失败: {}: {}", path.display(), e);
+                    false
+                }
+            })
+            .count();
+        if removed > 0 {
+            tracing::info!("已清理 {} 个过期的对话记录文件", removed);
+        }
+        removed
+    }
+}
+
+impl Default for TranscriptStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 从 `YYYY-MM-DD.jsonl` 文件名解析日期
+fn file_date(path: &Path) -> Option<NaiveDate> {
+    if path.extension()? != "jsonl" {
+        return None;
+    }
+    NaiveDate::parse_from_str(path.file_stem()?.to_str()?, "%Y-%m-%d").ok()
+}
+
+lazy_static! {
+    /// 全局对话记录存储
+    pub static ref TRANSCRIPTS: TranscriptStore = TranscriptStore::new();
+}
+
+/// 初始化全局对话记录存储，记录目录位于配置文件所在目录
+pub fn init(config_path: &Path, config: TranscriptConfig) {
+    TRANSCRIPTS.set_dir(config_path.parent().map(|dir| dir.join("transcripts")));
+    TRANSCRIPTS.set_config(config);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transcript(id: &str, timestamp: &str, api_key_id: Option<&str>, text: &str) -> Transcript {
+        Transcript {
+            id: id.to_string(),
+            timestamp: timestamp.parse().unwrap(),
+            api_key_id: api_key_id.map(str::to_string),
+            model: "claude-sonnet-4".to_string(),
+            stream: false,
+            duration_ms: 10,
+            request: json!({ "messages": [{ "role": "user", "content": text }] }),
+            response: json!({ "content": [{ "type": "text", "text": "ok" }], "stop_reason": "end_turn" }),
+        }
+    }
+
+    #[test]
+    fn test_search_get_and_retention() {
+        let dir = std::env::temp_dir().join(format!("kiro-transcripts-{}", uuid::Uuid::new_v4().simple()));
+        let store = TranscriptStore::new();
+        store.set_dir(Some(dir.clone()));
+        store.append(&transcript("a1", "2025-01-01T10:00:00Z", None, "deploy the build"));
+        store.append(&transcript("b2", "2025-01-03T10:00:00Z", Some("team-a"), "fix the Deploy script"));
+        store.append(&transcript("c3", "2025-01-03T11:00:00Z", Some("team-b"), "hello"));
+
+        let search = |filter: TranscriptFilter| -> Vec<String> {
+            store
+                .search(&TranscriptFilter { limit: 10, ..filter })
+                .into_iter()
+                .map(|s| s.id)
+                .collect()
+        };
+        assert_eq!(search(TranscriptFilter::default()), vec!["c3", "b2", "a1"]);
+        let deploy = TranscriptFilter {
+            query: Some("DEPLOY".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(search(deploy), vec!["b2", "a1"]);
+        let main_key = TranscriptFilter {
+            api_key_id: Some(MAIN_KEY_FILTER.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(search(main_key), vec!["a1"]);
+        let since = TranscriptFilter {
+            since: Some("2025-01-03T10:30:00Z".parse().unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(search(since), vec!["c3"]);
+
+        let found = store.get("b2").unwrap();
+        assert_eq!(found.summary().preview, "fix the Deploy script");
+        assert!(store.get("ffff").is_none());
+
+        store.set_config(TranscriptConfig {
+            enabled: true,
+            retention_days: 2,
+            max_size_mb: 0,
+        });
+        assert_eq!(store.enforce_retention("2025-01-04".parse().unwrap()), 1);
+        assert!(store.get("a1").is_none());
+        assert_eq!(store.purge(), 1);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_assemble_stream() {
+        let sse = concat!(
+            "event: message_start\n",
+            "data: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_1\",\"role\":\"assistant\",\"content\":[],\"usage\":{\"input_tokens\":5,\"output_tokens\":1}}}\n\n",
+            ": failover-trace {}\n\n",
+            "event: content_block_start\n",
+            "data: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hel\"}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"lo\"}}\n\n",
+            "data: {\"type\":\"content_block_start\",\"index\":1,\"content_block\":{\"type\":\"tool_use\",\"id\":\"t1\",\"name\":\"Read\",\"input\":{}}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"{\\\"path\\\": \"}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"\\\"a.rs\\\"}\"}}\n\n",
+            "data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"tool_use\",\"stop_sequence\":null},\"usage\":{\"input_tokens\":7,\"output_tokens\":12}}\n\n",
+            "data: {\"type\":\"message_stop\"}\n\n",
+        );
+        let message = assemble_stream(sse);
+        assert_eq!(message["id"], "msg_1");
+        assert_eq!(message["stop_reason"], "tool_use");
+        assert_eq!(message["usage"]["output_tokens"], 12);
+        assert_eq!(message["content"][0]["text"], "Hello");
+        assert_eq!(message["content"][1]["input"], json!({ "path": "a.rs" }));
+    }
+}
@@ -0,0 +1,207 @@
+//! 凭证额度快照时间线
+//!
+//! 每次从 getUsageLimits 更新凭证余额缓存时记录一个快照（已用、上限、剩余），
+//! 按凭证保存为时间序列供 Admin UI 绘制消耗趋势。快照持久化到配置目录下的
+//! `usage_history.json`。
+
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+
+/// 每个凭证最多保留的快照数（10 分钟刷新间隔下约两周）
+const MAX_SNAPSHOTS_PER_CREDENTIAL: usize = 2016;
+
+/// 两个快照的最小间隔，间隔内的新数据覆盖上一个快照，避免手动查询时堆积密集的点
+const MIN_SNAPSHOT_SPACING_SECS: i64 = 60;
+
+/// 快照文件写盘的最小间隔
+const HISTORY_SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 单个额度快照
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageSnapshot {
+    pub timestamp: DateTime<Utc>,
+    pub current_usage: f64,
+    pub usage_limit: f64,
+    pub remaining: f64,
+}
+
+#[derive(Default)]
+struct HistoryState {
+    series: HashMap<u64, VecDeque<UsageSnapshot>>,
+    dirty: bool,
+    last_saved: Option<Instant>,
+}
+
+/// 额度快照存储
+pub struct UsageHistory {
+    path: RwLock<Option<PathBuf>>,
+    state: Mutex<HistoryState>,
+}
+
+impl UsageHistory {
+    pub fn new() -> Self {
+        Self {
+            path: RwLock::new(None),
+            state: Mutex::new(HistoryState::default()),
+        }
+    }
+
+    /// 从文件加载历史快照（文件不存在或损坏时从空开始）
+    pub fn load(&self, path: Option<PathBuf>) {
+        let series = path
+            .as_ref()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|content| {
+                serde_json::from_str::<HashMap<u64, VecDeque<UsageSnapshot>>>(&content)
+                    .map_err(|e| tracing::warn!("解析额度快照文件失败，将重新记录: {}", e))
+                    .ok()
+            })
+            .unwrap_or_default();
+
+        *self.path.write() = path;
+        let mut state = self.state.lock();
+        state.series = series;
+        state.dirty = false;
+    }
+
+    /// 记录一个快照
+    pub fn record(&self, id: u64, current_usage: f64, usage_limit: f64) {
+        self.record_at(id, Utc::now(), current_usage, usage_limit);
+        self.save_if_due();
+    }
+
+    fn record_at(&self, id: u64, timestamp: DateTime<Utc>, current_usage: f64, usage_limit: f64) {
+        let snapshot = UsageSnapshot {
+            timestamp,
+            current_usage,
+            usage_limit,
+            remaining: (usage_limit - current_usage).max(0.0),
+        };
+
+        let mut state = self.state.lock();
+        let series = state.series.entry(id).or_default();
+        let too_close = series
+            .back()
+            .is_some_and(|last| (timestamp - last.timestamp).num_seconds() < MIN_SNAPSHOT_SPACING_SECS);
+        if too_close {
+            series.pop_back();
+        }
+        series.push_back(snapshot);
+        while series.len() > MAX_SNAPSHOTS_PER_CREDENTIAL {
+            series.pop_front();
+        }
+        state.dirty = true;
+    }
+
+    /// 获取凭证的快照序列（按时间升序），可选只返回指定时间之后的部分
+    pub fn history(&self, id: u64, since: Option<DateTime<Utc>>) -> Vec<UsageSnapshot> {
+        let state = self.state.lock();
+        state
+            .series
+            .get(&id)
+            .map(|series| {
+                series
+                    .iter()
+                    .filter(|s| since.is_none_or(|since| s.timestamp >= since))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// 删除凭证的快照
+    pub fn remove(&self, id: u64) {
+        let mut state = self.state.lock();
+        if state.series.remove(&id).is_some() {
+            state.dirty = true;
+            self.save_locked(&mut state);
+        }
+    }
+
+    /// 立即写盘（批量刷新结束时调用）
+    pub fn flush(&self) {
+        let mut state = self.state.lock();
+        self.save_locked(&mut state);
+    }
+
+    fn save_if_due(&self) {
+        let mut state = self.state.lock();
+        let due = state
+            .last_saved
+            .is_none_or(|t| t.elapsed() >= HISTORY_SAVE_INTERVAL);
+        if due {
+            self.save_locked(&mut state);
+        }
+    }
+
+    fn save_locked(&self, state: &mut HistoryState) {
+        if !state.dirty {
+            return;
+        }
+        let Some(path) = self.path.read().clone() else {
+            return;
+        };
+        match serde_json::to_string(&state.series) {
+            Ok(json) => {
+                if let Err(e) = std::fs::write(&path, json) {
+                    tracing::warn!("保存额度快照失败: {}", e);
+                    return;
+                }
+                state.dirty = false;
+                state.last_saved = Some(Instant::now());
+            }
+            Err(e) => tracing::warn!("序列化额度快照失败: {}", e),
+        }
+    }
+}
+
+impl Default for UsageHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+lazy_static! {
+    /// 全局额度快照存储
+    pub static ref USAGE_HISTORY: UsageHistory = UsageHistory::new();
+}
+
+/// 初始化全局快照存储，快照文件与配置文件位于同一目录
+pub fn init(config_path: &std::path::Path) {
+    let path = config_path.parent().map(|dir| dir.join("usage_history.json"));
+    USAGE_HISTORY.load(path);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_coalesces_close_snapshots_and_filters_since() {
+        let history = UsageHistory::new();
+        let t0 = Utc::now() - chrono::Duration::hours(2);
+
+        history.record_at(1, t0, 10.0, 100.0);
+        history.record_at(1, t0 + chrono::Duration::seconds(30), 12.0, 100.0);
+        history.record_at(1, t0 + chrono::Duration::hours(1), 40.0, 100.0);
+
+        let all = history.history(1, None);
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].current_usage, 12.0);
+        assert_eq!(all[1].remaining, 60.0);
+
+        let recent = history.history(1, Some(t0 + chrono::Duration::minutes(30)));
+        assert_eq!(recent.len(), 1);
+        assert!(history.history(2, None).is_empty());
+
+        history.remove(1);
+        assert!(history.history(1, None).is_empty());
+    }
+}
@@ -0,0 +1,92 @@
+//! 响应水印
+//!
+//! 在反代响应上附加 `X-Kiro-Gateway` 头（以及可选的 SSE 末尾注释），标明网关版本和
+//! 处理该请求的凭证分组（不暴露凭证 ID），便于多层网关串联时定位是哪一层产生的响应。
+
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{HeaderValue, Request, header},
+    middleware::Next,
+    response::Response,
+};
+use bytes::Bytes;
+use futures::{StreamExt, stream};
+
+use crate::model::config::WatermarkConfig;
+
+/// 水印响应头
+pub const WATERMARK_HEADER: &str = "x-kiro-gateway";
+
+/// 处理请求所用的凭证分组，由 handler 放入响应 extensions
+#[derive(Debug, Clone)]
+pub struct ResponseGroup(pub String);
+
+/// 生成水印内容，如 `kiro-gateway/1.2.0; group=default`
+fn watermark_text(group: Option<&str>) -> String {
+    let mut text = format!("{}/{}", crate::PRODUCT_NAME, env!("CARGO_PKG_VERSION"));
+    if let Some(group) = group {
+        text.push_str("; group=");
+        text.push_str(group);
+    }
+    text
+}
+
+/// 水印中间件
+pub async fn watermark_middleware(
+    State(config): State<Arc<WatermarkConfig>>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let response = next.run(request).await;
+    if !config.enabled {
+        return response;
+    }
+
+    let group = response
+        .extensions()
+        .get::<ResponseGroup>()
+        .map(|ResponseGroup(g)| g.clone());
+    let text = watermark_text(group.as_deref());
+
+    let (mut parts, body) = response.into_parts();
+    // 分组名含非 ASCII 字符时无法放入头部，退化为只带版本
+    let value = HeaderValue::from_str(&text)
+        .or_else(|_| HeaderValue::from_str(&watermark_text(None)));
+    if let Ok(value) = value {
+        parts.headers.insert(WATERMARK_HEADER, value);
+    }
+
+    let is_event_stream = parts
+        .headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream"));
+    if !(config.sse_comment && is_event_stream) {
+        return Response::from_parts(parts, body);
+    }
+
+    // SSE 注释行会被客户端忽略，只在抓包/日志中可见
+    let comment = Bytes::from(format!(": {}\n\n", text.replace('\n', " ")));
+    let body = body
+        .into_data_stream()
+        .chain(stream::once(async move { Ok::<_, axum::Error>(comment) }));
+    Response::from_parts(parts, Body::from_stream(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watermark_text() {
+        let version = env!("CARGO_PKG_VERSION");
+        assert_eq!(watermark_text(None), format!("kiro-gateway/{}", version));
+        assert_eq!(
+            watermark_text(Some("team-a")),
+            format!("kiro-gateway/{}; group=team-a", version)
+        );
+    }
+}
@@ -0,0 +1,261 @@
+//! 凭证生命周期 Webhook
+//!
+//! 凭证被自动禁用（账户暂停 / 连续失败）、发生故障转移、Token 连续刷新失败时，
+//! 向配置的 Webhook 推送通知，运维无需盯着 Admin UI。每个 Webhook 可以只订阅部分事件，
+//! 并按 Slack / Discord / 通用 JSON 格式发送；投递失败时指数退避重试。
+//!
+//! 额度告警（[`crate::alerts`]）的 Webhook 也通过这里投递。
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use parking_lot::RwLock;
+use serde::Serialize;
+
+use crate::model::config::{WebhookConfig, WebhookEventKind, WebhookFormat};
+
+/// Webhook 请求超时（秒）
+const WEBHOOK_TIMEOUT_SECS: u64 = 10;
+
+/// 最大投递次数（含首次）
+const MAX_DELIVERY_ATTEMPTS: u32 = 4;
+
+/// 首次重试的退避时间，之后每次翻倍
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(2);
+
+/// 凭证生命周期事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookEvent {
+    pub event: WebhookEventKind,
+    pub title: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub credential_id: Option<u64>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl WebhookEvent {
+    /// 凭证被自动禁用
+    pub fn credential_disabled(id: u64, reason: &str) -> Self {
+        Self::new(
+            WebhookEventKind::CredentialDisabled,
+            "凭证已被自动禁用".to_string(),
+            format!("凭证 #{} 已被自动禁用：{}", id, reason),
+            Some(id),
+        )
+    }
+
+    /// 故障转移到其他凭证
+    pub fn failover(from: u64, to: u64, reason: &str) -> Self {
+        Self::new(
+            WebhookEventKind::Failover,
+            "凭证故障转移".to_string(),
+            format!("凭证 #{} {}，已切换到凭证 #{}", from, reason, to),
+            Some(from),
+        )
+    }
+
+    /// Token 连续刷新失败
+    pub fn refresh_failed(id: u64, failures: u32, error: &str) -> Self {
+        Self::new(
+            WebhookEventKind::RefreshFailed,
+            "Token 刷新连续失败".to_string(),
+            format!("凭证 #{} Token 已连续刷新失败 {} 次：{}", id, failures, error),
+            Some(id),
+        )
+    }
+
+    /// 活跃分组耗尽，转移到备用分组
+    pub fn group_failover(from: &str, to: &str) -> Self {
+        Self::new(
+            WebhookEventKind::GroupFailover,
+            "分组故障转移".to_string(),
+            format!("分组 '{}' 内的凭证已全部耗尽或禁用，已转移到备用分组 '{}'", from, to),
+            None,
+        )
+    }
+
+    /// 活跃分组恢复可用，不再使用备用分组
+    pub fn group_recovered(group: &str) -> Self {
+        Self::new(
+            WebhookEventKind::GroupFailover,
+            "分组已恢复".to_string(),
+            format!("分组 '{}' 重新有可用凭证，已停止使用备用分组", group),
+            None,
+        )
+    }
+
+    fn new(event: WebhookEventKind, title: String, message: String, credential_id: Option<u64>) -> Self {
+        Self {
+            event,
+            title,
+            message,
+            credential_id,
+            timestamp: Utc::now(),
+        }
+    }
+}
+
+/// 按格式构建请求体：Slack / Discord 发送标题 + 正文，通用格式原样发送 `payload`
+pub fn render(format: WebhookFormat, title: &str, message: &str, payload: serde_json::Value) -> serde_json::Value {
+    match format {
+        WebhookFormat::Generic => payload,
+        WebhookFormat::Slack => serde_json::json!({
+            "text": format!("*{}*\n{}", title, message)
+        }),
+        WebhookFormat::Discord => serde_json::json!({
+            "content": format!("**{}**\n{}", title, message)
+        }),
+    }
+}
+
+/// 发送一次 Webhook 请求
+async fn post(url: &str, body: &serde_json::Value) -> anyhow::Result<()> {
+    let client = crate::http_client::build_client(None, WEBHOOK_TIMEOUT_SECS)?;
+    let response = client.post(url).json(body).send().await?;
+    if !response.status().is_success() {
+        anyhow::bail!("Webhook 返回 {}", response.status());
+    }
+    Ok(())
+}
+
+/// 投递 Webhook，失败时指数退避重试，返回最后一次错误
+pub async fn deliver(url: &str, body: &serde_json::Value) -> anyhow::Result<()> {
+    let mut attempt = 1;
+    loop {
+        match post(url, body).await {
+            Ok(()) => return Ok(()),
+            Err(e) if attempt >= MAX_DELIVERY_ATTEMPTS => return Err(e),
+            Err(e) => {
+                let delay = RETRY_BASE_DELAY * 2u32.pow(attempt - 1);
+                tracing::debug!(
+                    "Webhook 投递失败（{}/{}），{} 秒后重试: {}",
+                    attempt,
+                    MAX_DELIVERY_ATTEMPTS,
+                    delay.as_secs(),
+                    e
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// 在后台投递 Webhook（不在 Tokio 运行时内时丢弃）
+pub fn spawn_delivery(url: String, body: serde_json::Value) {
+    let Ok(handle) = tokio::runtime::Handle::try_current() else {
+        return;
+    };
+    handle.spawn(async move {
+        if let Err(e) = deliver(&url, &body).await {
+            tracing::warn!("Webhook 投递失败（已重试 {} 次）: {}", MAX_DELIVERY_ATTEMPTS - 1, e);
+        }
+    });
+}
+
+/// 发送测试消息（不重试，便于立即看到结果）
+pub async fn send_test(webhook: &WebhookConfig) -> anyhow::Result<()> {
+    let title = "Webhook 测试";
+    let message = format!("来自 {} 的测试消息", crate::PRODUCT_NAME);
+    let payload = serde_json::json!({
+        "event": "test",
+        "title": title,
+        "message": message,
+        "timestamp": Utc::now(),
+    });
+    post(webhook.url.trim(), &render(webhook.format, title, &message, payload)).await
+}
+
+/// 校验 Webhook 配置
+pub fn validate(webhooks: &[WebhookConfig]) -> Result<(), String> {
+    for webhook in webhooks {
+        let url = webhook.url.trim();
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(format!("Webhook 地址必须以 http:// 或 https:// 开头: {}", url));
+        }
+    }
+    Ok(())
+}
+
+/// 生命周期事件分发器
+pub struct WebhookDispatcher {
+    webhooks: RwLock<Vec<WebhookConfig>>,
+}
+
+impl WebhookDispatcher {
+    pub fn new() -> Self {
+        Self {
+            webhooks: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// 替换 Webhook 配置（立即生效）
+    pub fn set_webhooks(&self, webhooks: Vec<WebhookConfig>) {
+        *self.webhooks.write() = webhooks;
+    }
+
+    /// 向订阅了该事件的 Webhook 推送
+    pub fn notify(&self, event: WebhookEvent) {
+        let payload = serde_json::json!(event);
+        for webhook in self.webhooks.read().iter().filter(|w| w.subscribes(event.event)) {
+            let body = render(webhook.format, &event.title, &event.message, payload.clone());
+            spawn_delivery(webhook.url.clone(), body);
+        }
+    }
+}
+
+impl Default for WebhookDispatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+lazy_static! {
+    /// 全局 Webhook 分发器
+    pub static ref WEBHOOKS: WebhookDispatcher = WebhookDispatcher::new();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_formats() {
+        let event = WebhookEvent::failover(1, 2, "连续失败");
+        let payload = serde_json::json!(event);
+        assert_eq!(payload["event"], "failover");
+        assert_eq!(payload["credentialId"], 1);
+
+        let slack = render(WebhookFormat::Slack, &event.title, &event.message, payload.clone());
+        assert_eq!(slack["text"], "*凭证故障转移*\n凭证 #1 连续失败，已切换到凭证 #2");
+        let discord = render(WebhookFormat::Discord, "t", "m", payload.clone());
+        assert_eq!(discord["content"], "**t**\nm");
+        assert_eq!(render(WebhookFormat::Generic, "t", "m", payload.clone()), payload);
+    }
+
+    #[test]
+    fn test_subscription_filter_and_validation() {
+        let all = WebhookConfig {
+            url: "https://example.com/hook".to_string(),
+            format: WebhookFormat::Generic,
+            events: Vec::new(),
+        };
+        let failover_only = WebhookConfig {
+            events: vec![WebhookEventKind::Failover],
+            ..all.clone()
+        };
+        assert!(all.subscribes(WebhookEventKind::RefreshFailed));
+        assert!(failover_only.subscribes(WebhookEventKind::Failover));
+        assert!(!failover_only.subscribes(WebhookEventKind::CredentialDisabled));
+
+        assert!(validate(&[all]).is_ok());
+        let invalid = WebhookConfig {
+            url: "example.com".to_string(),
+            ..failover_only
+        };
+        assert!(validate(&[invalid]).is_err());
+    }
+}
@@ -1,110 +0,0 @@
-//! Anthropic API 中间件
-
-use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
-
-use axum::{
-    body::Body,
-    extract::State,
-    http::{Request, StatusCode},
-    middleware::Next,
-    response::{IntoResponse, Json, Response},
-};
-
-use crate::common::auth;
-use crate::kiro::provider::KiroProvider;
-
-use super::types::ErrorResponse;
-
-/// 应用共享状态
-#[derive(Clone)]
-pub struct AppState {
-    /// API 密钥
-    pub api_key: String,
-    /// Kiro Provider（可选，用于实际 API 调用）
-    /// 内部使用 MultiTokenManager，已支持线程安全的多凭证管理
-    pub kiro_provider: Option<Arc<KiroProvider>>,
-    /// Profile ARN（可选，用于请求）
-    pub profile_arn: Option<String>,
-    /// 代理服务是否启用
-    pub proxy_enabled: Arc<AtomicBool>,
-}
-
-impl AppState {
-    /// 创建新的应用状态
-    pub fn new(api_key: impl Into<String>) -> Self {
-        Self {
-            api_key: api_key.into(),
-            kiro_provider: None,
-            profile_arn: None,
-            proxy_enabled: Arc::new(AtomicBool::new(true)),
-        }
-    }
-
-    /// 设置 KiroProvider
-    pub fn with_kiro_provider(mut self, provider: KiroProvider) -> Self {
-        self.kiro_provider = Some(Arc::new(provider));
-        self
-    }
-
-    /// 设置 Profile ARN
-    pub fn with_profile_arn(mut self, arn: impl Into<String>) -> Self {
-        self.profile_arn = Some(arn.into());
-        self
-    }
-    
-    /// 设置代理启用状态
-    pub fn with_proxy_enabled(mut self, enabled: Arc<AtomicBool>) -> Self {
-        self.proxy_enabled = enabled;
-        self
-    }
-    
-    /// 检查代理是否启用
-    pub fn is_proxy_enabled(&self) -> bool {
-        self.proxy_enabled.load(Ordering::SeqCst)
-    }
-}
-
-/// API Key 认证中间件
-pub async fn auth_middleware(
-    State(state): State<AppState>,
-    request: Request<Body>,
-    next: Next,
-) -> Response {
-    // 首先检查代理服务是否启用
-    if !state.is_proxy_enabled() {
-        return (
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse::new(
-                "service_unavailable".to_string(),
-                "Proxy service is currently disabled".to_string(),
-            ))
-        ).into_response();
-    }
-    
-    match auth::extract_api_key(&request) {
-        Some(key) if auth::constant_time_eq(&key, &state.api_key) => next.run(request).await,
-        _ => {
-            let error = ErrorResponse::authentication_error();
-            (StatusCode::UNAUTHORIZED, Json(error)).into_response()
-        }
-    }
-}
-
-/// CORS 中间件层
-///
-/// **安全说明**：当前配置允许所有来源（Any），这是为了支持公开 API 服务。
-/// 如果需要更严格的安全控制，请根据实际需求配置具体的允许来源、方法和头信息。
-///
-/// # 配置说明
-/// - `allow_origin(Any)`: 允许任何来源的请求
-/// - `allow_methods(Any)`: 允许任何 HTTP 方法
-/// - `allow_headers(Any)`: 允许任何请求头
-pub fn cors_layer() -> tower_http::cors::CorsLayer {
-    use tower_http::cors::{Any, CorsLayer};
-
-    CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any)
-}
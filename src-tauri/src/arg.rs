@@ -0,0 +1,93 @@
+use clap::{Parser, Subcommand};
+
+/// Anthropic <-> Kiro API 客户端
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+pub struct Args {
+    /// 配置文件路径
+    #[arg(short, long, global = true)]
+    pub config: Option<String>,
+
+    /// 凭证文件路径
+    #[arg(long, global = true)]
+    pub credentials: Option<String>,
+
+    /// 配置档案名（使用 `~/.kiro-gateway/profiles/<名称>/` 下独立的配置和凭证文件）
+    #[arg(long, global = true, env = "KIRO_GATEWAY_PROFILE")]
+    pub profile: Option<String>,
+
+    /// 启动时隐藏主窗口到系统托盘（开机自启使用，仅桌面应用）
+    #[arg(long)]
+    pub minimized: bool,
+
+    /// 子命令（不指定时启动桌面应用；无 GUI 构建时启动服务）
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// 命令行子命令
+///
+/// 凭证和配置命令直接读写配置文件，运行中的网关需重启后生效
+/// （运行中请使用 Admin API，避免网关回写时覆盖命令行的修改）
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// 以无头模式运行服务（Admin API + 反代），Ctrl+C 退出
+    Serve,
+    /// 凭证管理
+    #[command(subcommand)]
+    Credentials(CredentialsCommand),
+    /// 配置管理
+    #[command(subcommand)]
+    Config(ConfigCommand),
+}
+
+/// 凭证管理子命令
+#[derive(Subcommand, Debug)]
+pub enum CredentialsCommand {
+    /// 添加凭证（刷新 Token 验证有效后写入凭证文件）
+    Add {
+        /// Refresh Token（传 `-` 时从标准输入读取，避免出现在进程列表中）
+        #[arg(long)]
+        refresh_token: String,
+        /// 认证方式（social / idc / builder-id）
+        #[arg(long, default_value = "social")]
+        auth_method: String,
+        /// OIDC Client ID（IdC 认证需要）
+        #[arg(long)]
+        client_id: Option<String>,
+        /// OIDC Client Secret（IdC 认证需要）
+        #[arg(long)]
+        client_secret: Option<String>,
+        /// 分组 ID
+        #[arg(long)]
+        group: Option<String>,
+    },
+    /// 列出凭证
+    List {
+        /// 以 JSON 输出
+        #[arg(long)]
+        json: bool,
+    },
+    /// 删除凭证
+    Remove {
+        /// 凭证 ID
+        id: u64,
+    },
+    /// 刷新 Token 验证凭证（不指定 ID 时验证全部），任一失败时退出码为 1
+    Validate {
+        /// 凭证 ID
+        id: Option<u64>,
+    },
+}
+
+/// 配置管理子命令
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// 设置配置项，如 `config set port 9000`、`config set rateLimit.perKeyRpm 60`
+    Set {
+        /// 配置项（camelCase，嵌套字段用 `.` 分隔）
+        key: String,
+        /// 值（按 JSON 解析，解析失败时视为字符串）
+        value: String,
+    },
+}
@@ -0,0 +1,181 @@
+//! 命令行管理命令
+//!
+//! 不经过 HTTP Admin API 和 GUI，直接读写配置文件和凭证文件，便于脚本和 CI 使用。
+
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::Context;
+use kiro_gateway_core::kiro::model::credentials::{CredentialsConfig, KiroCredentials};
+use kiro_gateway_core::kiro::token_manager::MultiTokenManager;
+use kiro_gateway_core::model::config::Config;
+
+use crate::arg::{ConfigCommand, CredentialsCommand};
+
+/// 执行凭证管理命令
+pub async fn run_credentials(
+    command: CredentialsCommand,
+    config_path: &Path,
+    credentials_path: &Path,
+) -> anyhow::Result<()> {
+    let manager = load_manager(config_path, credentials_path)?;
+
+    match command {
+        CredentialsCommand::Add {
+            refresh_token,
+            auth_method,
+            client_id,
+            client_secret,
+            group,
+        } => {
+            let refresh_token = if refresh_token == "-" {
+                let mut input = String::new();
+                std::io::stdin().read_to_string(&mut input).context("读取标准输入失败")?;
+                input.trim().to_string()
+            } else {
+                refresh_token
+            };
+            let credential = KiroCredentials {
+                refresh_token: Some(refresh_token),
+                auth_method: Some(auth_method),
+                client_id,
+                client_secret,
+                group_id: group.unwrap_or_else(|| "default".to_string()),
+                status: "normal".to_string(),
+                ..Default::default()
+            };
+            let id = manager.add_credential(credential).await?;
+            println!("已添加凭证 #{}", id);
+        }
+        CredentialsCommand::List { json } => {
+            let snapshot = manager.snapshot();
+            if json {
+                let entries: Vec<serde_json::Value> = snapshot
+                    .entries
+                    .iter()
+                    .map(|e| {
+                        serde_json::json!({
+                            "id": e.id,
+                            "disabled": e.disabled,
+                            "authMethod": e.auth_method,
+                            "groupId": e.group_id,
+                            "status": e.status,
+                            "email": e.email,
+                            "subscriptionTitle": e.subscription_title,
+                            "remaining": e.remaining,
+                            "expiresAt": e.expires_at,
+                        })
+                    })
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&entries)?);
+            } else {
+                println!("{:<6}{:<10}{:<12}{:<10}{:<10}邮箱", "ID", "认证方式", "分组", "状态", "剩余额度");
+                for e in &snapshot.entries {
+                    let status = if e.disabled { "disabled" } else { e.status.as_str() };
+                    println!(
+                        "{:<6}{:<10}{:<12}{:<10}{:<10}{}",
+                        e.id,
+                        e.auth_method.as_deref().unwrap_or("social"),
+                        e.group_id,
+                        status,
+                        e.remaining.map(|r| format!("{:.1}", r)).unwrap_or_else(|| "-".to_string()),
+                        e.email.as_deref().unwrap_or("-"),
+                    );
+                }
+                println!("共 {} 个凭证，可用 {} 个", snapshot.total, snapshot.available);
+            }
+        }
+        CredentialsCommand::Remove { id } => {
+            manager.delete_credential(id)?;
+            println!("已删除凭证 #{}", id);
+        }
+        CredentialsCommand::Validate { id } => {
+            let ids: Vec<u64> = match id {
+                Some(id) => vec![id],
+                None => manager.snapshot().entries.iter().map(|e| e.id).collect(),
+            };
+            let mut failed = 0;
+            for id in &ids {
+                // 刷新成功的 Token 会回写到凭证文件
+                match manager.refresh_token_for(*id).await {
+                    Ok(()) => println!("凭证 #{}: 有效", id),
+                    Err(e) => {
+                        failed += 1;
+                        println!("凭证 #{}: 无效 - {}", id, e);
+                    }
+                }
+            }
+            if failed > 0 {
+                anyhow::bail!("{} / {} 个凭证验证失败", failed, ids.len());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 加载凭证管理器（旧的单对象格式会在首次写入时转换为数组格式）
+fn load_manager(config_path: &Path, credentials_path: &Path) -> anyhow::Result<MultiTokenManager> {
+    let config = Config::load(config_path).context("加载配置失败")?;
+    let credentials = CredentialsConfig::load_or_create(credentials_path).context("加载凭证失败")?;
+    MultiTokenManager::new(
+        config,
+        credentials.into_sorted_credentials(),
+        None,
+        Some(credentials_path.to_path_buf()),
+        true,
+    )
+}
+
+/// 执行配置管理命令
+pub fn run_config(command: ConfigCommand, config_path: &Path) -> anyhow::Result<()> {
+    match command {
+        ConfigCommand::Set { key, value } => {
+            let config = Config::load(config_path).context("加载配置失败")?;
+            let value = serde_json::from_str(&value).unwrap_or(serde_json::Value::String(value));
+            let updated = set_config_value(&config, &key, value)?;
+            updated.save(config_path).context("保存配置失败")?;
+            println!("已设置 {}", key);
+        }
+    }
+    Ok(())
+}
+
+/// 按 `.` 分隔的路径设置配置项，并校验结果仍是合法配置
+fn set_config_value(config: &Config, key: &str, value: serde_json::Value) -> anyhow::Result<Config> {
+    let mut root = serde_json::to_value(config)?;
+    let parts: Vec<&str> = key.split('.').collect();
+    if parts.iter().any(|p| p.is_empty()) {
+        anyhow::bail!("配置项格式错误: {}", key);
+    }
+
+    let (last, parents) = parts.split_last().expect("路径非空");
+    let mut node = &mut root;
+    for part in parents {
+        let object = node
+            .as_object_mut()
+            .with_context(|| format!("配置项 {} 不是对象", part))?;
+        node = object
+            .entry(part.to_string())
+            .or_insert_with(|| serde_json::Value::Object(Default::default()));
+    }
+    node.as_object_mut()
+        .with_context(|| format!("无法设置配置项: {}", key))?
+        .insert(last.to_string(), value.clone());
+
+    let updated: Config = serde_json::from_value(root).with_context(|| format!("配置项 {} 的值无效", key))?;
+
+    // 未知的配置项会在反序列化时被忽略，写回后比对确认已生效
+    let saved = serde_json::to_value(&updated)?;
+    let actual = parts.iter().try_fold(&saved, |node, part| node.get(part));
+    let applied = match (actual, &value) {
+        // 置空的可选项不会被序列化
+        (None, serde_json::Value::Null) => true,
+        (Some(a), v) if a.is_number() && v.is_number() => a.as_f64() == v.as_f64(),
+        (Some(a), v) => a == v,
+        (None, _) => false,
+    };
+    if !applied {
+        anyhow::bail!("未知配置项或值不受支持: {}", key);
+    }
+    Ok(updated)
+}
@@ -0,0 +1,413 @@
+//! 桌面 GUI（Tauri）
+//!
+//! 仅在启用 `gui` feature 时编译，无头部署使用 `--no-default-features` 构建
+
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use tauri::{Emitter, Manager, WindowEvent};
+use tauri::menu::{Menu, MenuItem};
+use tauri::tray::{TrayIcon, TrayIconBuilder, MouseButton, MouseButtonState, TrayIconEvent};
+use tauri_plugin_autostart::{MacosLauncher, ManagerExt};
+use tauri_plugin_notification::NotificationExt;
+
+use kiro_gateway_core::admin_ui;
+use kiro_gateway_core::events::{AdminEvent, EVENT_BUS, ServerEvent};
+use kiro_gateway_core::instance::InstanceLock;
+use kiro_gateway_core::model::config::Config;
+use kiro_gateway_core::proxy_lifecycle::ProxyLifecycle;
+
+/// 服务器状态
+#[derive(Clone)]
+struct ServerState {
+    config_path: String,
+    credentials_path: String,
+    /// 反代服务生命周期（与 Admin API 共享）
+    proxy: ProxyLifecycle,
+    /// 服务线程最近一次事件（线程意外退出时仍能反映真实状态）
+    last_server_event: Arc<Mutex<Option<ServerEvent>>>,
+}
+
+// ============ Tauri Commands ============
+
+/// 获取反代服务状态
+#[tauri::command]
+async fn get_server_status(state: tauri::State<'_, ServerState>) -> Result<serde_json::Value, String> {
+    let snapshot = state.proxy.snapshot();
+    
+    // 读取配置获取监听地址
+    let config = match kiro_gateway_core::model::config::Config::load(&state.config_path) {
+        Ok(c) => c,
+        Err(e) => return Err(format!("读取配置失败: {}", e)),
+    };
+    let server = state.last_server_event.lock().clone();
+    // 端口被占用时会顺延，优先返回实际绑定的端口
+    let admin_port = match &server {
+        Some(ServerEvent::Started { port, .. }) => Some(*port),
+        _ => None,
+    };
+    
+    Ok(serde_json::json!({
+        "isRunning": snapshot.state.accepts_requests(),
+        "state": snapshot.state,
+        "since": snapshot.since.to_rfc3339(),
+        "lastError": snapshot.last_error,
+        "host": config.host,
+        "port": snapshot.port.unwrap_or(config.proxy_port),
+        "configuredPort": config.proxy_port,
+        "boundPort": snapshot.port,
+        "boundAddresses": snapshot.addresses,
+        "adminPort": admin_port,
+        "drainDeadline": snapshot.drain_deadline.map(|d| d.to_rfc3339()),
+        "server": server
+    }))
+}
+
+/// 启动反代服务
+#[tauri::command]
+async fn start_proxy_server(state: tauri::State<'_, ServerState>) -> Result<String, String> {
+    if state.proxy.state().accepts_requests() {
+        return Err("服务器已在运行中".to_string());
+    }
+    
+    state.proxy.start().await.map_err(|e| format!("启动失败: {}", e))?;
+    
+    Ok("服务器已启动".to_string())
+}
+
+/// 停止反代服务
+#[tauri::command]
+async fn stop_proxy_server(state: tauri::State<'_, ServerState>) -> Result<String, String> {
+    if !state.proxy.stop() {
+        return Err("服务器未运行".to_string());
+    }
+    
+    Ok("服务器已停止".to_string())
+}
+
+/// 打开外部 URL
+#[tauri::command]
+fn open_url(url: String) -> Result<(), String> {
+    open::that(&url).map_err(|e| format!("打开链接失败: {}", e))
+}
+
+/// 在浏览器中打开 Admin UI（附带一次性认证令牌）
+#[tauri::command]
+fn open_admin_ui() -> Result<(), String> {
+    let url = admin_ui::launch_url().ok_or("Admin API 尚未启动")?;
+    open::that(&url).map_err(|e| format!("打开 Admin UI 失败: {}", e))
+}
+
+/// 领取 Admin UI 会话令牌（内嵌窗口调用 Admin API 时使用）
+#[tauri::command]
+fn get_admin_session() -> String {
+    admin_ui::issue_session()
+}
+
+/// 保存文件（弹出文件保存对话框）
+#[tauri::command]
+async fn save_file(content: String, default_name: String, filter_name: String, filter_extensions: Vec<String>) -> Result<bool, String> {
+    use std::io::Write;
+    
+    let extensions: Vec<&str> = filter_extensions.iter().map(|s| s.as_str()).collect();
+    
+    let file_handle = rfd::AsyncFileDialog::new()
+        .set_title("保存文件")
+        .set_file_name(&default_name)
+        .add_filter(&filter_name, &extensions)
+        .save_file()
+        .await;
+    
+    match file_handle {
+        Some(handle) => {
+            let path = handle.path();
+            std::fs::File::create(path)
+                .and_then(|mut file| file.write_all(content.as_bytes()))
+                .map_err(|e| format!("保存文件失败: {}", e))?;
+            Ok(true)
+        }
+        None => Ok(false) // 用户取消
+    }
+}
+
+/// 获取数据目录路径
+#[tauri::command]
+fn get_data_dir() -> String {
+    crate::get_config_dir().to_string_lossy().to_string()
+}
+
+/// 打开数据目录
+#[tauri::command]
+fn open_data_dir() -> Result<(), String> {
+    let dir = crate::get_config_dir();
+    open::that(&dir).map_err(|e| format!("打开目录失败: {}", e))
+}
+
+/// 提取 panic 信息
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        format!("panic: {}", s)
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        format!("panic: {}", s)
+    } else {
+        "panic".to_string()
+    }
+}
+
+/// 开机自启参数：启动后隐藏到系统托盘
+const AUTOSTART_ARGS: &[&str] = &["--minimized"];
+
+/// 开机自启参数（沿用当前配置档案）
+fn autostart_args() -> Vec<&'static str> {
+    let mut args = AUTOSTART_ARGS.to_vec();
+    if let Some(profile) = crate::PROFILE.get().and_then(Option::as_deref) {
+        args.extend(["--profile", profile]);
+    }
+    args
+}
+
+/// 按配置注册或移除开机自启项（Linux 为 ~/.config/autostart 下的 .desktop 文件）
+fn sync_autostart(app: &tauri::AppHandle, enabled: bool) {
+    let launcher = app.autolaunch();
+    let result = match launcher.is_enabled() {
+        Ok(current) if current == enabled => return,
+        _ if enabled => launcher.enable(),
+        _ => launcher.disable(),
+    };
+    match result {
+        Ok(()) => tracing::info!("开机自启已{}", if enabled { "启用" } else { "关闭" }),
+        Err(e) => tracing::warn!("设置开机自启失败: {}", e),
+    }
+}
+
+/// 当前会话是否可以显示系统托盘
+///
+/// Linux 的托盘依赖 StatusNotifier（appindicator）和 D-Bus 会话总线，
+/// 无图形会话或无会话总线时（如通过 SSH 启动）跳过托盘
+#[cfg(target_os = "linux")]
+fn tray_supported() -> bool {
+    let has_env = |name: &str| std::env::var_os(name).is_some_and(|v| !v.is_empty());
+    let has_display = has_env("DISPLAY") || has_env("WAYLAND_DISPLAY");
+    let has_session_bus = has_env("DBUS_SESSION_BUS_ADDRESS")
+        || std::env::var_os("XDG_RUNTIME_DIR")
+            .is_some_and(|dir| std::path::Path::new(&dir).join("bus").exists());
+    has_display && has_session_bus
+}
+
+#[cfg(not(target_os = "linux"))]
+fn tray_supported() -> bool {
+    true
+}
+
+/// 显示并聚焦主窗口
+fn show_main_window(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// 创建系统托盘，不可用时返回 None（窗口关闭时直接退出而不是隐藏）
+///
+/// 缺少 appindicator 库时 Tauri 会在创建托盘时 panic，这里一并捕获
+fn create_tray(app: &tauri::App) -> Option<TrayIcon> {
+    if !tray_supported() {
+        tracing::warn!("当前会话不支持系统托盘，已跳过托盘创建");
+        return None;
+    }
+
+    let build = || -> tauri::Result<TrayIcon> {
+        let show_item = MenuItem::with_id(app, "show", "显示窗口", true, None::<&str>)?;
+        let quit_item = MenuItem::with_id(app, "quit", "退出", true, None::<&str>)?;
+        let menu = Menu::with_items(app, &[&show_item, &quit_item])?;
+
+        TrayIconBuilder::new()
+            .icon(app.default_window_icon().unwrap().clone())
+            .menu(&menu)
+            .tooltip("Kiro Gateway")
+            .on_menu_event(|app, event| {
+                match event.id.as_ref() {
+                    "show" => show_main_window(app),
+                    "quit" => app.exit(0),
+                    _ => {}
+                }
+            })
+            // 左键单击时显示窗口（Linux appindicator 不产生点击事件，通过菜单显示）
+            .on_tray_icon_event(|tray, event| {
+                if let TrayIconEvent::Click {
+                    button: MouseButton::Left,
+                    button_state: MouseButtonState::Up,
+                    ..
+                } = event
+                {
+                    show_main_window(tray.app_handle());
+                }
+            })
+            .build(app)
+    };
+
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(build)) {
+        Ok(Ok(tray)) => Some(tray),
+        Ok(Err(e)) => {
+            tracing::warn!("创建系统托盘失败: {}", e);
+            None
+        }
+        Err(panic) => {
+            tracing::warn!("创建系统托盘失败（{}），请安装 libayatana-appindicator3", panic_message(panic.as_ref()));
+            None
+        }
+    }
+}
+
+/// 订阅事件总线：记录服务线程状态，并将服务/反代状态变化推送给前端窗口，告警弹出桌面通知，
+/// 开机自启设置变化时同步自启项
+fn forward_server_events(app: tauri::AppHandle, last_server_event: Arc<Mutex<Option<ServerEvent>>>) {
+    let mut rx = EVENT_BUS.subscribe();
+    tauri::async_runtime::spawn(async move {
+        use tokio::sync::broadcast::error::RecvError;
+        loop {
+            match rx.recv().await {
+                Ok(AdminEvent::Server { event }) => {
+                    *last_server_event.lock() = Some(event.clone());
+                    let _ = app.emit("server-event", &event);
+                }
+                // 只转发主反代服务的状态，分组反代实例由 Admin UI 单独查询
+                Ok(AdminEvent::Proxy { status }) if status.group_id.is_none() => {
+                    let _ = app.emit("proxy-status", &status);
+                }
+                Ok(AdminEvent::Autostart { enabled }) => sync_autostart(&app, enabled),
+                Ok(AdminEvent::Alert { alert }) => {
+                    if let Err(e) = app
+                        .notification()
+                        .builder()
+                        .title(&alert.title)
+                        .body(&alert.message)
+                        .show()
+                    {
+                        tracing::warn!("发送桌面通知失败: {}", e);
+                    }
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+/// 启动 Tauri 应用
+///
+/// `minimized` 为 true 时（开机自启）启动后不显示主窗口，托盘不可用时仍然显示。
+/// 持有 `instance` 期间再次启动应用会唤醒本实例的主窗口
+pub fn run(config_path: String, credentials_path: String, minimized: bool, instance: InstanceLock) {
+    // 创建服务器状态（不自动启动）
+    let server_state = ServerState {
+        config_path,
+        credentials_path,
+        proxy: ProxyLifecycle::new(),
+        last_server_event: Arc::new(Mutex::new(None)),
+    };
+
+    // Run Tauri Application
+    tauri::Builder::default()
+        .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_autostart::init(
+            MacosLauncher::LaunchAgent,
+            Some(autostart_args()),
+        ))
+        .manage(server_state)
+        .invoke_handler(tauri::generate_handler![
+            get_server_status,
+            start_proxy_server,
+            stop_proxy_server,
+            open_url,
+            open_admin_ui,
+            get_admin_session,
+            save_file,
+            get_data_dir,
+            open_data_dir,
+        ])
+        .setup(move |app| {
+            let window = app.get_webview_window("main").unwrap();
+            
+            // Optional: Open DevTools in debug mode
+            #[cfg(debug_assertions)]
+            window.open_devtools();
+            
+            // 创建系统托盘（不可用时窗口关闭即退出）
+            let has_tray = match create_tray(app) {
+                Some(tray) => {
+                    app.manage(tray);
+                    true
+                }
+                None => false,
+            };
+            if minimized && has_tray {
+                let _ = window.hide();
+            }
+
+            // 自动启动 Admin API 服务器（不包含反代）
+            let server_state: tauri::State<ServerState> = app.state();
+            let config_path = server_state.config_path.clone();
+            let credentials_path = server_state.credentials_path.clone();
+            let proxy = server_state.proxy.clone();
+
+            // 按配置同步开机自启项（配置文件被外部修改或首次启用时）
+            match Config::load(&config_path) {
+                Ok(config) => sync_autostart(app.handle(), config.autostart),
+                Err(e) => tracing::warn!("读取配置失败，跳过开机自启同步: {}", e),
+            }
+
+            // 浏览器访问的 Admin UI 使用打包进可执行文件的前端资源
+            let resolver = app.asset_resolver();
+            admin_ui::set_asset_source(Arc::new(move |path| {
+                resolver.get(path.to_string()).map(|asset| admin_ui::Asset {
+                    bytes: asset.bytes,
+                    mime_type: asset.mime_type,
+                })
+            }));
+
+            // 先订阅事件总线，确保不漏掉服务线程的启动事件
+            forward_server_events(app.handle().clone(), server_state.last_server_event.clone());
+            tauri::async_runtime::spawn(instance.reporter().track_ports());
+
+            // 再次启动应用时唤醒本实例的主窗口
+            let handle = app.handle().clone();
+            if let Err(e) = instance.listen_for_activation(move || show_main_window(&handle)) {
+                tracing::warn!("监听单实例唤醒请求失败: {}", e);
+            }
+            app.manage(instance);
+            
+            std::thread::spawn(move || {
+                let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| -> anyhow::Result<()> {
+                    let rt = tokio::runtime::Builder::new_multi_thread()
+                        .enable_all()
+                        .build()?;
+                    rt.block_on(kiro_gateway_core::kiro_server::run_admin_server(config_path, credentials_path, proxy))
+                }));
+                let error = match result {
+                    Ok(Ok(())) => {
+                        EVENT_BUS.server_event(ServerEvent::Stopped);
+                        return;
+                    }
+                    Ok(Err(e)) => e.to_string(),
+                    Err(panic) => panic_message(panic.as_ref()),
+                };
+                EVENT_BUS.server_event(ServerEvent::Crashed { error });
+            });
+            
+            Ok(())
+        })
+        .on_window_event(|window, event| {
+            // 拦截关闭事件，改为隐藏到托盘（没有托盘时正常关闭，避免窗口无法找回）
+            if let WindowEvent::CloseRequested { api, .. } = event {
+                if window.app_handle().try_state::<TrayIcon>().is_some() {
+                    let _ = window.hide();
+                    api.prevent_close();
+                }
+            }
+        })
+        .run(tauri::generate_context!("tauri.conf.json"))
+        .expect("error while running tauri application");
+}
@@ -3,25 +3,18 @@
     windows_subsystem = "windows"
 )]
 
-mod admin;
-mod anthropic;
-mod common;
-mod http_client;
-mod kiro;
-mod logs;
-mod model;
-pub mod token;
-mod kiro_server;
-mod model_lock;
+mod arg;
+mod cli;
+#[cfg(feature = "gui")]
+mod gui;
 
 use clap::Parser;
 use std::path::PathBuf;
-use std::sync::Arc;
-use model::arg::Args;
-use tauri::{Manager, WindowEvent};
-use tauri::menu::{Menu, MenuItem};
-use tauri::tray::{TrayIconBuilder, MouseButton, MouseButtonState, TrayIconEvent};
-use tokio::sync::{Mutex, watch};
+use std::sync::OnceLock;
+use arg::{Args, Command};
+use kiro_gateway_core::instance::InstanceLock;
+use kiro_gateway_core::{events, kiro_server};
+use kiro_gateway_core::log_level;
 
 #[derive(Parser, Debug)]
 struct MainArgs {
@@ -29,19 +22,32 @@ struct MainArgs {
     server_args: Args,
 }
 
-/// 服务器状态
-#[derive(Clone)]
-struct ServerState {
-    config_path: String,
-    credentials_path: String,
-    /// 服务器停止信号发送端
-    shutdown_tx: Arc<Mutex<Option<watch::Sender<bool>>>>,
-    /// 服务器运行状态
-    is_running: Arc<Mutex<bool>>,
+/// 当前使用的配置档案（`--profile` / `KIRO_GATEWAY_PROFILE`）
+static PROFILE: OnceLock<Option<String>> = OnceLock::new();
+
+/// 校验配置档案名（只能包含字母、数字、`-` 和 `_`，避免路径穿越）
+fn validate_profile(name: &str) -> Result<(), String> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(format!("配置档案名无效: '{}'（只能包含字母、数字、- 和 _）", name));
+    }
+    Ok(())
 }
 
-/// 获取配置文件目录（使用用户目录下的 .kiro-gateway 文件夹）
+/// 获取配置文件目录（指定配置档案时为 `profiles/<名称>` 子目录）
 fn get_config_dir() -> PathBuf {
+    let config_dir = base_config_dir();
+    let Some(profile) = PROFILE.get().and_then(Option::as_deref) else {
+        return config_dir;
+    };
+    let profile_dir = config_dir.join("profiles").join(profile);
+    if let Err(e) = std::fs::create_dir_all(&profile_dir) {
+        eprintln!("Warning: Failed to create profile directory: {}", e);
+    }
+    profile_dir
+}
+
+/// 获取默认配置目录（使用用户目录下的 .kiro-gateway 文件夹）
+fn base_config_dir() -> PathBuf {
     // 使用用户目录下的 .kiro-gateway 文件夹
     if let Some(home_dir) = dirs::home_dir() {
         let config_dir = home_dir.join(".kiro-gateway");
@@ -94,147 +100,22 @@ fn ensure_credentials_file(path: &PathBuf) {
     }
 }
 
-// ============ Tauri Commands ============
-
-/// 获取服务器状态
-#[tauri::command]
-async fn get_server_status(state: tauri::State<'_, ServerState>) -> Result<serde_json::Value, String> {
-    let is_running = *state.is_running.lock().await;
-    
-    // 读取配置获取监听地址
-    let config = match model::config::Config::load(&state.config_path) {
-        Ok(c) => c,
-        Err(e) => return Err(format!("读取配置失败: {}", e)),
-    };
-    
-    Ok(serde_json::json!({
-        "isRunning": is_running,
-        "host": config.host,
-        "port": config.port
-    }))
-}
-
-/// 启动服务器
-#[tauri::command]
-async fn start_proxy_server(state: tauri::State<'_, ServerState>) -> Result<String, String> {
-    let mut is_running = state.is_running.lock().await;
-    
-    if *is_running {
-        return Err("服务器已在运行中".to_string());
-    }
-    
-    let config_path = state.config_path.clone();
-    let credentials_path = state.credentials_path.clone();
-    let shutdown_tx = state.shutdown_tx.clone();
-    let is_running_flag = state.is_running.clone();
-    
-    // 创建新的 shutdown channel
-    let (tx, rx) = watch::channel(false);
-    {
-        let mut shutdown = shutdown_tx.lock().await;
-        *shutdown = Some(tx);
-    }
-    
-    // 标记为运行中
-    *is_running = true;
-    
-    // 在新线程中启动服务器
-    std::thread::spawn(move || {
-        let rt = tokio::runtime::Builder::new_multi_thread()
-            .enable_all()
-            .build()
-            .unwrap();
-            
-        rt.block_on(async {
-            if let Err(e) = kiro_server::run_server(config_path, credentials_path, rx).await {
-                eprintln!("Server Error: {}", e);
-            }
-            
-            // 服务器停止后更新状态
-            let mut running = is_running_flag.lock().await;
-            *running = false;
-        });
-    });
-    
-    Ok("服务器已启动".to_string())
-}
-
-/// 停止服务器
-#[tauri::command]
-async fn stop_proxy_server(state: tauri::State<'_, ServerState>) -> Result<String, String> {
-    let mut is_running = state.is_running.lock().await;
-    
-    if !*is_running {
-        return Err("服务器未运行".to_string());
-    }
-    
-    // 发送停止信号
-    let shutdown_tx = state.shutdown_tx.lock().await;
-    if let Some(tx) = shutdown_tx.as_ref() {
-        tx.send(true).map_err(|e| format!("发送停止信号失败: {}", e))?;
-    }
-    
-    *is_running = false;
-    
-    Ok("服务器已停止".to_string())
-}
-
-/// 打开外部 URL
-#[tauri::command]
-fn open_url(url: String) -> Result<(), String> {
-    open::that(&url).map_err(|e| format!("打开链接失败: {}", e))
-}
-
-/// 保存文件（弹出文件保存对话框）
-#[tauri::command]
-async fn save_file(content: String, default_name: String, filter_name: String, filter_extensions: Vec<String>) -> Result<bool, String> {
-    use std::io::Write;
-    
-    let extensions: Vec<&str> = filter_extensions.iter().map(|s| s.as_str()).collect();
-    
-    let file_handle = rfd::AsyncFileDialog::new()
-        .set_title("保存文件")
-        .set_file_name(&default_name)
-        .add_filter(&filter_name, &extensions)
-        .save_file()
-        .await;
-    
-    match file_handle {
-        Some(handle) => {
-            let path = handle.path();
-            std::fs::File::create(path)
-                .and_then(|mut file| file.write_all(content.as_bytes()))
-                .map_err(|e| format!("保存文件失败: {}", e))?;
-            Ok(true)
-        }
-        None => Ok(false) // 用户取消
-    }
-}
-
-/// 获取数据目录路径
-#[tauri::command]
-fn get_data_dir() -> String {
-    get_config_dir().to_string_lossy().to_string()
-}
-
-/// 打开数据目录
-#[tauri::command]
-fn open_data_dir() -> Result<(), String> {
-    let dir = get_config_dir();
-    open::that(&dir).map_err(|e| format!("打开目录失败: {}", e))
-}
-
 fn main() {
-    // 初始化日志
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::from_default_env()
-                .add_directive(tracing::Level::INFO.into()),
-        )
-        .init();
+    // 初始化日志（过滤器可通过 Admin API 在运行时调整）
+    log_level::init();
 
     // Parse args to get config paths
     let args = MainArgs::parse();
+
+    // 配置档案：各档案使用独立的配置目录（单实例锁按目录区分，不同档案可同时运行）
+    let profile = args.server_args.profile.as_deref().map(str::trim).filter(|p| !p.is_empty());
+    if let Some(profile) = profile {
+        if let Err(e) = validate_profile(profile) {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    }
+    PROFILE.get_or_init(|| profile.map(str::to_string));
     
     // 获取配置文件目录
     let config_dir = get_config_dir();
@@ -251,112 +132,101 @@ fn main() {
     // 确保配置文件存在
     ensure_config_file(&config_path);
     ensure_credentials_file(&credentials_path);
+
+    // 管理命令：执行后直接退出
+    match args.server_args.command {
+        Some(Command::Credentials(command)) => {
+            let rt = tokio::runtime::Builder::new_multi_thread()
+                .enable_all()
+                .build()
+                .expect("创建 Tokio 运行时失败");
+            exit_with(rt.block_on(cli::run_credentials(command, &config_path, &credentials_path)));
+        }
+        Some(Command::Config(command)) => exit_with(cli::run_config(command, &config_path)),
+        Some(Command::Serve) | None => {}
+    }
     
     println!("=== Kiro Gateway ===");
+    if let Some(profile) = profile {
+        println!("Profile: {}", profile);
+    }
     println!("Config: {}", config_path.display());
     println!("Credentials: {}", credentials_path.display());
 
     let config_path_str = config_path.to_string_lossy().to_string();
     let credentials_path_str = credentials_path.to_string_lossy().to_string();
+    let headless = matches!(args.server_args.command, Some(Command::Serve)) || cfg!(not(feature = "gui"));
+    let instance = acquire_instance_lock(&config_path, headless);
+
+    #[cfg(feature = "gui")]
+    if !headless {
+        gui::run(config_path_str, credentials_path_str, args.server_args.minimized, instance);
+        return;
+    }
+
+    run_headless(config_path_str, credentials_path_str, instance);
+}
 
-    // 创建服务器状态（不自动启动）
-    let server_state = ServerState {
-        config_path: config_path_str,
-        credentials_path: credentials_path_str,
-        shutdown_tx: Arc::new(Mutex::new(None)),
-        is_running: Arc::new(Mutex::new(false)),
+/// 获取单实例锁：同一配置目录已有实例运行时，GUI 模式唤醒已有窗口后退出，无头模式报错退出
+fn acquire_instance_lock(config_path: &std::path::Path, headless: bool) -> InstanceLock {
+    let dir = config_path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or_else(|| std::path::Path::new("."));
+    let existing = match InstanceLock::acquire(dir) {
+        Ok(Ok(lock)) => return lock,
+        Ok(Err(existing)) => existing,
+        Err(e) => exit_with(Err(e.context("获取单实例锁失败"))),
     };
 
-    // Run Tauri Application
-    tauri::Builder::default()
-        .plugin(tauri_plugin_shell::init())
-        .manage(server_state)
-        .invoke_handler(tauri::generate_handler![
-            get_server_status,
-            start_proxy_server,
-            stop_proxy_server,
-            open_url,
-            save_file,
-            get_data_dir,
-            open_data_dir,
-        ])
-        .setup(|app| {
-            let window = app.get_webview_window("main").unwrap();
-            
-            // Optional: Open DevTools in debug mode
-            #[cfg(debug_assertions)]
-            window.open_devtools();
-            
-            // 创建系统托盘菜单
-            let show_item = MenuItem::with_id(app, "show", "显示窗口", true, None::<&str>)?;
-            let quit_item = MenuItem::with_id(app, "quit", "退出", true, None::<&str>)?;
-            let menu = Menu::with_items(app, &[&show_item, &quit_item])?;
-            
-            // 创建系统托盘
-            let tray = TrayIconBuilder::new()
-                .icon(app.default_window_icon().unwrap().clone())
-                .menu(&menu)
-                .tooltip("Kiro Gateway")
-                .on_menu_event(|app, event| {
-                    match event.id.as_ref() {
-                        "show" => {
-                            if let Some(window) = app.get_webview_window("main") {
-                                let _ = window.show();
-                                let _ = window.set_focus();
-                            }
-                        }
-                        "quit" => {
-                            app.exit(0);
-                        }
-                        _ => {}
-                    }
-                })
-                .on_tray_icon_event(|tray, event| {
-                    // 左键单击时显示窗口
-                    if let TrayIconEvent::Click {
-                        button: MouseButton::Left,
-                        button_state: MouseButtonState::Up,
-                        ..
-                    } = event
-                    {
-                        if let Some(window) = tray.app_handle().get_webview_window("main") {
-                            let _ = window.show();
-                            let _ = window.set_focus();
-                        }
-                    }
-                })
-                .build(app)?;
-            
-            // 保存托盘引用
-            app.manage(tray);
-            
-            // 自动启动 Admin API 服务器（不包含反代）
-            let server_state: tauri::State<ServerState> = app.state();
-            let config_path = server_state.config_path.clone();
-            let credentials_path = server_state.credentials_path.clone();
-            
-            std::thread::spawn(move || {
-                let rt = tokio::runtime::Builder::new_multi_thread()
-                    .enable_all()
-                    .build()
-                    .unwrap();
-                    
-                rt.block_on(async {
-                    if let Err(e) = kiro_server::run_admin_server(config_path, credentials_path).await {
-                        eprintln!("Admin Server Error: {}", e);
-                    }
-                });
-            });
-            
-            Ok(())
-        })
-        .on_window_event(|window, event| {
-            // 拦截关闭事件，改为隐藏到托盘
-            if let WindowEvent::CloseRequested { api, .. } = event {
-                let _ = window.hide();
-                api.prevent_close();
+    let info = existing.info.clone().unwrap_or_default();
+    let port = |port: Option<u16>| port.map_or_else(|| "未启动".to_string(), |p| p.to_string());
+    println!(
+        "Kiro Gateway 已在运行（PID {}，Admin 端口 {}，反代端口 {}）",
+        info.pid,
+        port(info.admin_port),
+        port(info.proxy_port)
+    );
+    if headless {
+        exit_with(Err(anyhow::anyhow!("同一配置目录只能运行一个实例: {}", dir.display())));
+    }
+    if let Err(e) = existing.activate() {
+        eprintln!("唤醒已运行的实例失败: {}", e);
+    }
+    std::process::exit(0);
+}
+
+/// 输出错误并以对应退出码结束进程
+fn exit_with(result: anyhow::Result<()>) -> ! {
+    match result {
+        Ok(()) => std::process::exit(0),
+        Err(e) => {
+            eprintln!("错误: {:#}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// 无头模式：直接运行单端口服务（Admin API + 反代），Ctrl+C 退出
+fn run_headless(config_path: String, credentials_path: String, instance: InstanceLock) {
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("创建 Tokio 运行时失败");
+
+    rt.block_on(async {
+        tokio::spawn(instance.reporter().track_ports());
+        let (tx, rx) = tokio::sync::watch::channel(false);
+        tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                tracing::info!("收到退出信号，正在停止服务...");
+                let _ = tx.send(true);
             }
-        })
-        .run(tauri::generate_context!("tauri.conf.json"))
-        .expect("error while running tauri application");
+        });
+
+        if let Err(e) = kiro_server::run_server(config_path, credentials_path, rx).await {
+            events::EVENT_BUS.server_event(events::ServerEvent::Crashed { error: e.to_string() });
+            std::process::exit(1);
+        }
+    });
 }
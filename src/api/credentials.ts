@@ -1,23 +1,62 @@
 import axios from "axios";
+import { storage } from "@/lib/storage";
 import type {
   CredentialsStatusResponse,
+  CredentialStatusItem,
   BalanceResponse,
+  UsageHistoryResponse,
   SuccessResponse,
   SetDisabledRequest,
   AddCredentialRequest,
   AddCredentialResponse,
+  UpdateCredentialRequest,
 } from "@/types/api";
 
+// 由网关 /ui/ 提供的浏览器 Admin UI 直接访问同源的 Admin API
+const servedByGateway = window.location.pathname.startsWith("/ui/");
+
 // 创建 axios 实例
 const api = axios.create({
-  baseURL:
-    import.meta.env.VITE_API_BASE_URL || "http://127.0.0.1:8990/api/admin",
+  baseURL: servedByGateway
+    ? `${window.location.origin}/api/admin`
+    : import.meta.env.VITE_API_BASE_URL || "http://127.0.0.1:8990/api/admin",
   headers: {
     "Content-Type": "application/json",
   },
 });
 
-// 不再需要 API Key 认证
+// 桌面内嵌窗口通过 Tauri 命令领取会话令牌
+const tauri = (window as any).__TAURI__;
+
+async function ensureAdminUiSession(): Promise<string | null> {
+  const session = storage.getAdminUiSession();
+  if (session || !tauri) {
+    return session;
+  }
+  const token: string = await tauri.core.invoke("get_admin_session");
+  storage.setAdminUiSession(token);
+  return token;
+}
+
+// 附带 Admin API Key（Admin UI 优先使用会话令牌，未设置时不发送）
+api.interceptors.request.use(async (config) => {
+  const apiKey = (await ensureAdminUiSession()) || storage.getApiKey();
+  if (apiKey) {
+    config.headers["x-api-key"] = apiKey;
+  }
+  return config;
+});
+
+// 内嵌窗口的会话令牌过期后重新领取并重试一次
+api.interceptors.response.use(undefined, async (error) => {
+  const config = error.config;
+  if (tauri && error.response?.status === 401 && config && !config._sessionRetried) {
+    config._sessionRetried = true;
+    storage.removeAdminUiSession();
+    return api.request(config);
+  }
+  return Promise.reject(error);
+});
 
 // 获取所有凭证状态
 export async function getCredentials(): Promise<CredentialsStatusResponse> {
@@ -25,6 +64,17 @@ export async function getCredentials(): Promise<CredentialsStatusResponse> {
   return data;
 }
 
+// 获取单个凭证的明文 Token（需要 Admin API Key）
+export async function revealCredential(
+  id: number
+): Promise<CredentialStatusItem | undefined> {
+  const { data } = await api.get<CredentialsStatusResponse>("/credentials", {
+    params: { reveal: true },
+    headers: { "x-reveal-key": storage.getApiKey() ?? "" },
+  });
+  return data.credentials.find((c) => c.id === id);
+}
+
 // 设置凭证禁用状态
 export async function setCredentialDisabled(
   id: number,
@@ -45,6 +95,16 @@ export async function resetCredentialFailure(
   return data;
 }
 
+// 为凭证重新生成随机 machineId
+export async function regenerateCredentialMachineId(
+  id: number
+): Promise<SuccessResponse & { machineId: string }> {
+  const { data } = await api.post<SuccessResponse & { machineId: string }>(
+    `/credentials/${id}/machine-id`
+  );
+  return data;
+}
+
 // 获取凭证余额
 export async function getCredentialBalance(
   id: number
@@ -53,6 +113,18 @@ export async function getCredentialBalance(
   return data;
 }
 
+// 获取凭证额度快照时间线（hours 为空时返回全部）
+export async function getCredentialUsageHistory(
+  id: number,
+  hours?: number
+): Promise<UsageHistoryResponse> {
+  const { data } = await api.get<UsageHistoryResponse>(
+    `/credentials/${id}/usage-history`,
+    { params: hours ? { hours } : undefined }
+  );
+  return data;
+}
+
 // 刷新单个凭证（刷新 Token + 更新余额）
 export interface RefreshCredentialResponse {
   id: number;
@@ -107,6 +179,15 @@ export async function addCredential(
   return data;
 }
 
+// 编辑凭证（修改认证信息或代理后重新验证）
+export async function updateCredential(
+  id: number,
+  req: UpdateCredentialRequest
+): Promise<SuccessResponse> {
+  const { data } = await api.put<SuccessResponse>(`/credentials/${id}`, req);
+  return data;
+}
+
 // 删除凭证
 export async function deleteCredential(id: number): Promise<SuccessResponse> {
   const { data } = await api.delete<SuccessResponse>(`/credentials/${id}`);
@@ -129,6 +210,17 @@ export interface ImportCredentialsResponse {
   skippedCount: number;
   credentialIds: number[];
   skippedReasons: string[];
+  // 逐项结果（含自动分组情况）
+  items: ImportItemReport[];
+}
+
+export interface ImportItemReport {
+  index: number;
+  success: boolean;
+  credentialId: number | null;
+  groupId: string | null;
+  matchedRule: string | null;
+  error: string | null;
 }
 
 export async function importCredentials(
@@ -143,6 +235,61 @@ export async function importCredentials(
   return data;
 }
 
+// 从粘贴文本导入凭证：每行一个 refreshToken，或 CSV（token, authMethod, clientId, clientSecret, group）
+export type ImportTextFormat = "auto" | "lines" | "csv";
+
+export async function importCredentialsText(
+  text: string,
+  format: ImportTextFormat = "auto",
+  groupId?: string
+): Promise<ImportCredentialsResponse> {
+  const { data } = await api.post<ImportCredentialsResponse>(
+    "/credentials/import-text",
+    {
+      text,
+      format,
+      groupId,
+    }
+  );
+  return data;
+}
+
+// 批量验证凭证（不添加）
+export interface ValidateResultItem {
+  index: number;
+  valid: boolean;
+  authMethod: string;
+  expiresAt: string | null;
+  email: string | null;
+  subscriptionTitle: string | null;
+  usageLimit: number | null;
+  remaining: number | null;
+  // 已添加的重复凭证 ID
+  existingId: number | null;
+  // 上游轮换后的 Refresh Token，导入时应使用该值
+  rotatedRefreshToken: string | null;
+  error: string | null;
+}
+
+export interface ValidateCredentialsResponse {
+  validCount: number;
+  invalidCount: number;
+  total: number;
+  results: ValidateResultItem[];
+}
+
+export async function validateCredentials(
+  credentials: Omit<ImportCredentialItem, "groupId">[]
+): Promise<ValidateCredentialsResponse> {
+  const { data } = await api.post<ValidateCredentialsResponse>(
+    "/credentials/validate",
+    {
+      credentials,
+    }
+  );
+  return data;
+}
+
 // 日志相关 API
 export interface LogEntry {
   timestamp: string;
@@ -164,6 +311,7 @@ export interface LogEntry {
     hasToolUse: boolean;
     responsePreview: string;
   };
+  groupId?: string | null;
 }
 
 export interface LogsResponse {
@@ -171,19 +319,144 @@ export interface LogsResponse {
   total: number;
 }
 
-export async function getLogs(): Promise<LogsResponse> {
-  const { data } = await api.get<LogsResponse>("/logs");
+// groupId 指定时只返回该分组的请求/响应日志
+export async function getLogs(groupId?: string): Promise<LogsResponse> {
+  const { data } = await api.get<LogsResponse>("/logs", {
+    params: groupId ? { groupId } : undefined,
+  });
+  return data;
+}
+
+// 日志级别响应
+export interface LogLevelResponse {
+  filter: string | null;
+}
+
+// 获取当前日志过滤器
+export async function getLogLevel(): Promise<LogLevelResponse> {
+  const { data } = await api.get<LogLevelResponse>("/log-level");
+  return data;
+}
+
+// 运行时调整日志级别（modules 路径相对本 crate，如 { "kiro::provider": "debug" }）
+export async function setLogLevel(
+  level: string,
+  modules: Record<string, string> = {}
+): Promise<LogLevelResponse> {
+  const { data } = await api.post<LogLevelResponse>("/log-level", { level, modules });
   return data;
 }
 
+// 服务核心事件
+export type ServerEvent =
+  | { kind: "started"; host: string; port: number }
+  | { kind: "stopped" }
+  | { kind: "crashed"; error: string };
+
+// 额度告警 / 每日用量报告
+export interface Alert {
+  kind: "lowQuota" | "groupExhausted" | "dailyReport";
+  title: string;
+  message: string;
+  credentialId?: number;
+  groupId?: string;
+  timestamp: string;
+}
+
+// 实时事件（GET /events，SSE）
+export type AdminEvent =
+  | { type: "log"; entry: LogEntry }
+  | {
+      type: "credential";
+      id: number;
+      change: "added" | "deleted" | "updated" | "enabled" | "disabled" | "suspended" | "coolingDown";
+    }
+  | {
+      type: "proxy";
+      status: {
+        state: ProxyState;
+        since: string;
+        lastError: string | null;
+        port: number | null;
+        // 分组反代实例的分组（主反代服务没有该字段）
+        groupId?: string;
+      };
+    }
+  | { type: "server"; event: ServerEvent }
+  | { type: "alert"; alert: Alert }
+  | { type: "lagged"; skipped: number };
+
+// 订阅实时事件，返回取消订阅函数
+export function subscribeAdminEvents(onEvent: (event: AdminEvent) => void): () => void {
+  const source = new EventSource(`${api.defaults.baseURL}/events`);
+  source.onmessage = (e) => onEvent(JSON.parse(e.data) as AdminEvent);
+  return () => source.close();
+}
+
 export async function clearLogs(): Promise<SuccessResponse> {
   const { data } = await api.post<SuccessResponse>("/logs/clear");
   return data;
 }
 
+// 日志文件（按天轮转的 JSON Lines）
+export interface LogDayInfo {
+  date: string;
+  files: number;
+  size: number;
+}
+
+export interface LogFilesResponse {
+  directory: string | null;
+  days: LogDayInfo[];
+}
+
+export async function getLogFiles(): Promise<LogFilesResponse> {
+  const { data } = await api.get<LogFilesResponse>("/logs/files");
+  return data;
+}
+
+// 下载某一天的日志归档（.jsonl.gz）
+export async function downloadLogArchive(date: string): Promise<Blob> {
+  const { data } = await api.get<Blob>(`/logs/archive/${date}`, {
+    responseType: "blob",
+  });
+  return data;
+}
+
+// 导出加密的网关状态备份（.kgbackup）
+export async function exportBackup(password: string): Promise<Blob> {
+  const { data } = await api.post<Blob>("/backup/export", { password }, {
+    responseType: "blob",
+  });
+  return data;
+}
+
+export interface ImportBackupResponse {
+  success: boolean;
+  message: string;
+  credentials: number;
+  files: string[];
+  createdAt: string;
+}
+
+// 导入备份（data 为备份文件的 Base64 内容）
+export async function importBackup(password: string, data: string): Promise<ImportBackupResponse> {
+  const { data: result } = await api.post<ImportBackupResponse>("/backup/import", { password, data });
+  return result;
+}
+
 // 配置相关 API
+export interface CorsConfig {
+  // 允许的来源，"*" 表示任意来源，为空时拒绝所有跨域请求
+  allowedOrigins: string[];
+  allowCredentials: boolean;
+  maxAgeSecs: number;
+}
+
 export interface ConfigResponse {
   host: string;
+  // 监听地址列表（为空时只监听 host）
+  listenHosts: string[];
   port: number;
   proxyPort: number;
   apiKey: string | null;
@@ -192,10 +465,13 @@ export interface ConfigResponse {
   autoRefreshIntervalMinutes: number;
   lockedModel: string | null;
   machineIdBackup: string | null;
+  cors: CorsConfig;
+  autostart: boolean;
 }
 
 export interface UpdateConfigRequest {
   host?: string;
+  listenHosts?: string[];
   port?: number;
   proxyPort?: number;
   apiKey?: string;
@@ -204,6 +480,8 @@ export interface UpdateConfigRequest {
   autoRefreshIntervalMinutes?: number;
   lockedModel?: string;
   machineIdBackup?: string;
+  cors?: CorsConfig;
+  autostart?: boolean;
 }
 
 export async function getConfig(): Promise<ConfigResponse> {
@@ -218,6 +496,27 @@ export async function updateConfig(
   return data;
 }
 
+// 实际生效的配置（内存配置 + 运行时状态，密钥已脱敏）
+export interface EffectiveConfigResponse {
+  config: Record<string, unknown>;
+  differsFromFile: string[];
+  runtime: {
+    activeGroupId: string | null;
+    standaloneProxy: boolean;
+    proxyState: ProxyState;
+    proxyBoundPort: number | null;
+    logFilter: string | null;
+    maintenanceAllowed: boolean;
+    lockedModel: string | null;
+  };
+  env: Record<string, string>;
+}
+
+export async function getEffectiveConfig(): Promise<EffectiveConfigResponse> {
+  const { data } = await api.get<EffectiveConfigResponse>("/config/effective");
+  return data;
+}
+
 // ============ 批量操作 API ============
 
 export interface BatchDeleteRequest {
@@ -240,9 +539,33 @@ export async function batchDeleteCredentials(
   return data;
 }
 
+export interface DedupeGroup {
+  email: string | null;
+  keptId: number;
+  removedIds: number[];
+  // 保留的 Token 来自哪条凭证
+  tokenFromId: number;
+}
+
+export interface DedupeResponse extends SuccessResponse {
+  dryRun: boolean;
+  removed: number;
+  groups: DedupeGroup[];
+}
+
+// 合并指向同一账号的重复凭证（dryRun 时仅预览）
+export async function dedupeCredentials(dryRun = false): Promise<DedupeResponse> {
+  const { data } = await api.post<DedupeResponse>("/credentials/dedupe", null, {
+    params: { dryRun },
+  });
+  return data;
+}
+
 export interface ExportCredentialsRequest {
   ids: number[];
   exportType?: "full" | "tokens_only";
+  // 设置后返回加密凭证包（data 字段，Base64），忽略 exportType
+  password?: string;
 }
 
 export interface ExportCredentialsResponse {
@@ -251,6 +574,7 @@ export interface ExportCredentialsResponse {
   count: number;
   credentials?: unknown[];
   ids?: number[];
+  data?: string;
 }
 
 export async function exportCredentials(
@@ -264,6 +588,31 @@ export async function exportCredentials(
   return data;
 }
 
+// 导出口令加密的凭证包（返回 Base64），用于在网关实例之间分享凭证
+export async function exportCredentialsEncrypted(
+  ids: number[],
+  password: string
+): Promise<ExportCredentialsResponse> {
+  const { data } = await api.post<ExportCredentialsResponse>(
+    "/credentials/export",
+    { ids, password } as ExportCredentialsRequest
+  );
+  return data;
+}
+
+// 导入加密凭证包（data 为 Base64 内容）
+export async function importCredentialsEncrypted(
+  password: string,
+  data: string,
+  groupId?: string
+): Promise<ImportCredentialsResponse> {
+  const { data: result } = await api.post<ImportCredentialsResponse>(
+    "/credentials/import-encrypted",
+    { password, data, groupId }
+  );
+  return result;
+}
+
 // ============ 机器码管理 API ============
 
 export interface MachineIdBackup {
@@ -274,6 +623,18 @@ export interface MachineIdBackup {
 export interface MachineIdResponse {
   machineId: string | null;
   machineIdBackup: MachineIdBackup | null;
+  // 备份历史（最新的在前）
+  machineIdBackups: MachineIdBackup[];
+}
+
+// 重置/恢复机器码的预览结果（dryRun）
+export interface MachineIdPreview {
+  dryRun: true;
+  operation: "reset" | "restore";
+  currentMachineId: string | null;
+  newMachineId: string;
+  confirmToken: string;
+  expiresInSecs: number;
 }
 
 export async function getMachineId(): Promise<MachineIdResponse> {
@@ -286,13 +647,32 @@ export async function backupMachineId(): Promise<SuccessResponse> {
   return data;
 }
 
-export async function restoreMachineId(): Promise<SuccessResponse> {
-  const { data } = await api.post<SuccessResponse>("/machine-id/restore");
+// 预览恢复机器码（index 为备份序号，0 为最近一次）
+export async function previewRestoreMachineId(index = 0): Promise<MachineIdPreview> {
+  const { data } = await api.post<MachineIdPreview>("/machine-id/restore", null, {
+    params: { dryRun: true, index },
+  });
   return data;
 }
 
-export async function resetMachineId(): Promise<SuccessResponse> {
-  const { data } = await api.post<SuccessResponse>("/machine-id/reset");
+export async function restoreMachineId(confirmToken: string, index = 0): Promise<SuccessResponse> {
+  const { data } = await api.post<SuccessResponse>("/machine-id/restore", null, {
+    params: { confirmToken, index },
+  });
+  return data;
+}
+
+export async function previewResetMachineId(): Promise<MachineIdPreview> {
+  const { data } = await api.post<MachineIdPreview>("/machine-id/reset", null, {
+    params: { dryRun: true },
+  });
+  return data;
+}
+
+export async function resetMachineId(confirmToken: string): Promise<SuccessResponse> {
+  const { data } = await api.post<SuccessResponse>("/machine-id/reset", null, {
+    params: { confirmToken },
+  });
   return data;
 }
 
@@ -314,6 +694,49 @@ export async function setLockedModel(
   return data;
 }
 
+export interface ModelLockStatus {
+  lockedModel: string | null;
+  mode: "stopped" | "events" | "polling";
+  profiles: string[];
+  settingsPaths: string[];
+  watcherError: string | null;
+  lastEnforcedAt: string | null;
+  revertCount: number;
+}
+
+export async function getModelLockStatus(): Promise<ModelLockStatus> {
+  const { data } = await api.get<ModelLockStatus>("/config/model/status");
+  return data;
+}
+
+export interface KiroProfile {
+  id: string;
+  name: string;
+  settingsPath: string;
+  model: string | null;
+  selected: boolean;
+}
+
+export interface KiroProfilesResponse {
+  profiles: KiroProfile[];
+  selected: string[];
+  settingsPaths: string[];
+}
+
+export async function getKiroProfiles(): Promise<KiroProfilesResponse> {
+  const { data } = await api.get<KiroProfilesResponse>("/kiro/profiles");
+  return data;
+}
+
+export async function setKiroProfiles(
+  profiles: string[]
+): Promise<KiroProfilesResponse> {
+  const { data } = await api.post<KiroProfilesResponse>("/kiro/profiles", {
+    profiles,
+  });
+  return data;
+}
+
 // ============ 本地账号 API ============
 
 export interface LocalCredentialResponse {
@@ -337,6 +760,41 @@ export async function importLocalCredential(): Promise<AddCredentialResponse> {
   return data;
 }
 
+export interface DiscoveredCredential {
+  file: string;
+  authMethod: string;
+  provider: string | null;
+  startUrl: string | null;
+  region: string | null;
+  expiresAt: string | null;
+  refreshTokenPreview: string;
+  hasClientRegistration: boolean;
+  alreadyImported: boolean;
+}
+
+export interface DiscoverCredentialsResponse {
+  directory: string;
+  credentials: DiscoveredCredential[];
+}
+
+export async function discoverCredentials(): Promise<DiscoverCredentialsResponse> {
+  const { data } = await api.get<DiscoverCredentialsResponse>(
+    "/credentials/discover"
+  );
+  return data;
+}
+
+export async function importDiscoveredCredentials(
+  files: string[],
+  groupId?: string
+): Promise<ImportCredentialsResponse> {
+  const { data } = await api.post<ImportCredentialsResponse>(
+    "/credentials/discover/import",
+    { files, groupId }
+  );
+  return data;
+}
+
 export async function switchToCredential(
   id: number
 ): Promise<SuccessResponse> {
@@ -362,15 +820,40 @@ export async function switchToNextCredential(): Promise<SwitchNextResponse> {
 
 // ============ 分组管理 ============
 
+// 分组超出预算后的处理方式
+export type BudgetPolicy = "reject" | "fallback";
+
+// 分组月度预算（token 与请求数任一达到上限即超出）
+export interface GroupBudget {
+  monthlyTokens?: number;
+  monthlyRequests?: number;
+  policy: BudgetPolicy;
+}
+
+// 分组本月用量及预算状态
+export interface GroupBudgetStatus {
+  month: string;
+  requestCount: number;
+  inputTokens: number;
+  outputTokens: number;
+  exceeded: boolean;
+}
+
 export interface GroupInfo {
   id: string;
   name: string;
   credentialCount: number;
+  budget: GroupBudget | null;
+  budgetStatus: GroupBudgetStatus;
 }
 
 export interface GroupsResponse {
   groups: GroupInfo[];
   activeGroupId: string | null;
+  // 活跃分组耗尽时自动转移到的备用分组
+  fallbackGroupId: string | null;
+  // 当前已转移到的备用分组（活跃分组正常时为 null）
+  failoverGroupId: string | null;
 }
 
 // 获取所有分组
@@ -397,6 +880,12 @@ export async function setActiveGroup(groupId: string | null): Promise<SuccessRes
   return data;
 }
 
+// 设置备用分组（null 表示不转移）
+export async function setFallbackGroup(groupId: string | null): Promise<SuccessResponse> {
+  const { data } = await api.post<SuccessResponse>("/groups/fallback", { groupId });
+  return data;
+}
+
 // 设置凭证分组
 export async function setCredentialGroup(id: number, groupId: string): Promise<SuccessResponse> {
   const { data } = await api.post<SuccessResponse>(`/credentials/${id}/group`, { groupId });
@@ -409,12 +898,31 @@ export async function renameGroup(id: string, name: string): Promise<SuccessResp
   return data;
 }
 
+// 设置分组月度预算（null 取消限制）
+export async function setGroupBudget(id: string, budget: GroupBudget | null): Promise<SuccessResponse> {
+  const { data } = await api.put<SuccessResponse>(`/groups/${id}/budget`, { budget });
+  return data;
+}
+
+// 反代服务生命周期状态
+export type ProxyState = "stopped" | "starting" | "running" | "draining" | "crashed";
+
 // 代理服务状态响应
 export interface ProxyStatusResponse {
   running: boolean;
+  state: ProxyState;
+  since: string;
+  lastError: string | null;
   host: string;
   port: number;
+  boundPort: number | null;
+  // 实际绑定的全部监听地址（如 "127.0.0.1:8991"、"[::1]:8991"）
+  boundAddresses: string[];
   activeGroupId: string | null;
+  // 进行中的流式响应数量（draining 时即排空进度）
+  activeStreams: number;
+  // draining 时强制中断剩余流的截止时间
+  drainDeadline: string | null;
 }
 
 // 获取代理服务状态
@@ -423,9 +931,58 @@ export async function getProxyStatus(): Promise<ProxyStatusResponse> {
   return data;
 }
 
-// 设置代理服务启用状态
-export async function setProxyEnabled(enabled: boolean): Promise<SuccessResponse> {
-  const { data } = await api.post<SuccessResponse>("/proxy/enabled", { enabled });
+// 反代服务操作
+export type ProxyAction = "start" | "stop" | "restart";
+
+// 反代服务操作响应（附带操作后的状态）
+export interface ProxyActionResponse extends ProxyStatusResponse {
+  message: string;
+}
+
+// 启动/停止/重启反代服务；groupId 缺省保持当前分组，null 表示全部
+export async function proxyAction(
+  action: ProxyAction,
+  groupId?: string | null
+): Promise<ProxyActionResponse> {
+  const body = groupId === undefined ? { action } : { action, groupId };
+  const { data } = await api.post<ProxyActionResponse>("/proxy", body);
+  return data;
+}
+
+// 分组反代实例（每个分组独立端口，与主反代服务同时运行）
+export interface GroupListener {
+  groupId: string;
+  port: number;
+  enabled: boolean;
+}
+
+export interface GroupProxySnapshot {
+  state: ProxyState;
+  since: string;
+  lastError: string | null;
+  port: number | null;
+  drainDeadline: string | null;
+  groupId: string;
+}
+
+export interface GroupProxiesResponse {
+  listeners: GroupListener[];
+  instances: GroupProxySnapshot[];
+  // 本次同步中启动失败的实例
+  errors?: { groupId: string; error: string }[];
+}
+
+export async function getGroupProxies(): Promise<GroupProxiesResponse> {
+  const { data } = await api.get<GroupProxiesResponse>("/group-proxies");
+  return data;
+}
+
+export async function setGroupProxies(
+  listeners: GroupListener[]
+): Promise<GroupProxiesResponse> {
+  const { data } = await api.put<GroupProxiesResponse>("/group-proxies", {
+    listeners,
+  });
   return data;
 }
 
@@ -441,6 +998,431 @@ export async function getVersion(): Promise<VersionResponse> {
   return data;
 }
 
+// 运行指标响应（流式响应按结束原因计数）
+export interface MetricsResponse {
+  streamTerminations: {
+    completed: number;
+    upstreamError: number;
+    clientDisconnect: number;
+    proxyDisabled: number;
+    idleTimeout: number;
+  };
+}
+
+// 获取运行指标
+export async function getMetrics(): Promise<MetricsResponse> {
+  const { data } = await api.get<MetricsResponse>("/metrics");
+  return data;
+}
+
+// 实时统计（最近 1m/5m/15m 窗口）
+export interface WindowStats {
+  window: "1m" | "5m" | "15m";
+  windowSecs: number;
+  requests: number;
+  errors: number;
+  requestsPerSec: number;
+  inputTokensPerSec: number;
+  outputTokensPerSec: number;
+  p50LatencyMs: number | null;
+  p95LatencyMs: number | null;
+  errorRate: number;
+}
+
+export interface RealtimeStatsResponse {
+  windows: WindowStats[];
+  activeStreams: number;
+}
+
+// 获取实时吞吐与延迟
+export async function getRealtimeStats(): Promise<RealtimeStatsResponse> {
+  const { data } = await api.get<RealtimeStatsResponse>("/stats/realtime");
+  return data;
+}
+
+// 模型目录（/v1/models 返回启用的条目）
+export interface ModelEntry {
+  id: string;
+  displayName: string;
+  // 为空时按模型能力表
+  maxTokens: number | null;
+  created: number;
+  enabled: boolean;
+}
+
+export interface UpdateModelRequest {
+  displayName?: string;
+  // 0 表示按模型能力表
+  maxTokens?: number;
+  created?: number;
+  enabled?: boolean;
+}
+
+export async function getModelCatalog(): Promise<ModelEntry[]> {
+  const { data } = await api.get<{ models: ModelEntry[] }>("/models");
+  return data.models;
+}
+
+export async function addModel(model: ModelEntry): Promise<SuccessResponse> {
+  const { data } = await api.post<SuccessResponse>("/models", model);
+  return data;
+}
+
+export async function updateModel(id: string, req: UpdateModelRequest): Promise<SuccessResponse> {
+  const { data } = await api.put<SuccessResponse>(`/models/${encodeURIComponent(id)}`, req);
+  return data;
+}
+
+export async function deleteModel(id: string): Promise<SuccessResponse> {
+  const { data } = await api.delete<SuccessResponse>(`/models/${encodeURIComponent(id)}`);
+  return data;
+}
+
+// Admin 修改操作审计记录
+export interface AuditEntry {
+  timestamp: string;
+  // 内嵌 Admin UI 或脱敏后的 Admin API Key
+  actor: string;
+  clientIp: string | null;
+  method: string;
+  // 相对 /api/admin 的路径
+  path: string;
+  action: string;
+  status: number;
+}
+
+// 查询审计日志（按时间倒序）
+export async function getAuditLog(params?: { limit?: number; since?: string }): Promise<AuditEntry[]> {
+  const { data } = await api.get<{ entries: AuditEntry[] }>("/audit", { params });
+  return data.entries;
+}
+
+// 模型映射（from 以 * 结尾时按前缀匹配）
+export interface ModelMapping {
+  from: string;
+  to: string;
+}
+
+export async function getModelMappings(): Promise<ModelMapping[]> {
+  const { data } = await api.get<{ mappings: ModelMapping[] }>("/model-mappings");
+  return data.mappings;
+}
+
+export async function setModelMappings(mappings: ModelMapping[]): Promise<SuccessResponse> {
+  const { data } = await api.put<SuccessResponse>("/model-mappings", { mappings });
+  return data;
+}
+
+// 额度告警配置
+export interface AlertConfig {
+  enabled: boolean;
+  lowQuotaPercent: number;
+  webhookUrl: string | null;
+  webhookFormat: WebhookFormat;
+  dailyReportEnabled: boolean;
+  dailyReportHour: number;
+}
+
+export async function getAlertConfig(): Promise<AlertConfig> {
+  const { data } = await api.get<AlertConfig>("/alerts");
+  return data;
+}
+
+export async function setAlertConfig(config: AlertConfig): Promise<SuccessResponse> {
+  const { data } = await api.put<SuccessResponse>("/alerts", config);
+  return data;
+}
+
+// 凭证生命周期 Webhook（events 为空时订阅全部）
+export type WebhookFormat = "generic" | "slack" | "discord";
+export type WebhookEventKind = "credentialDisabled" | "failover" | "refreshFailed" | "groupFailover";
+
+export interface WebhookConfig {
+  url: string;
+  format: WebhookFormat;
+  events: WebhookEventKind[];
+}
+
+export interface WebhookTestResult {
+  url: string;
+  success: boolean;
+  error?: string;
+}
+
+export async function getWebhooks(): Promise<WebhookConfig[]> {
+  const { data } = await api.get<{ webhooks: WebhookConfig[] }>("/webhooks");
+  return data.webhooks;
+}
+
+export async function setWebhooks(webhooks: WebhookConfig[]): Promise<SuccessResponse> {
+  const { data } = await api.put<SuccessResponse>("/webhooks", { webhooks });
+  return data;
+}
+
+// 发送测试消息；不传 url 时测试全部已配置的 Webhook
+export async function testWebhooks(
+  target?: { url: string; format?: WebhookFormat }
+): Promise<WebhookTestResult[]> {
+  const { data } = await api.post<{ results: WebhookTestResult[] }>("/webhooks/test", target ?? {});
+  return data.results;
+}
+
+// 局域网共享：默认拒绝，本机、白名单 IP/网段、携带 x-device-token 的设备可访问反代
+export interface LanDevice {
+  name: string;
+  // 为空时由服务端生成
+  token: string;
+}
+
+export interface LanAccessConfig {
+  enabled: boolean;
+  allowedIps: string[];
+  devices: LanDevice[];
+}
+
+export interface RejectedConnection {
+  ip: string;
+  path: string;
+  reason: string;
+  timestamp: string;
+}
+
+export interface AccessControlResponse {
+  config: LanAccessConfig;
+  rejections: RejectedConnection[];
+}
+
+export async function getAccessControl(): Promise<AccessControlResponse> {
+  const { data } = await api.get<AccessControlResponse>("/access");
+  return data;
+}
+
+export async function setAccessControl(
+  config: LanAccessConfig
+): Promise<AccessControlResponse> {
+  const { data } = await api.put<AccessControlResponse>("/access", config);
+  return data;
+}
+
+export async function clearAccessRejections(): Promise<SuccessResponse> {
+  const { data } = await api.post<SuccessResponse>("/access/rejections/clear");
+  return data;
+}
+
+// 响应缓存：相同的非流式请求直接返回缓存的响应（LRU + TTL，默认关闭）
+export interface ResponseCacheConfig {
+  enabled: boolean;
+  ttlSecs: number;
+  maxEntries: number;
+}
+
+export interface ResponseCacheStats {
+  entries: number;
+  hits: number;
+  misses: number;
+  hitRate: number;
+}
+
+export interface ResponseCacheResponse {
+  config: ResponseCacheConfig;
+  stats: ResponseCacheStats;
+}
+
+export async function getResponseCache(): Promise<ResponseCacheResponse> {
+  const { data } = await api.get<ResponseCacheResponse>("/response-cache");
+  return data;
+}
+
+export async function setResponseCache(
+  config: ResponseCacheConfig
+): Promise<ResponseCacheResponse> {
+  const { data } = await api.put<ResponseCacheResponse>("/response-cache", config);
+  return data;
+}
+
+export async function purgeResponseCache(): Promise<SuccessResponse> {
+  const { data } = await api.delete<SuccessResponse>("/response-cache");
+  return data;
+}
+
+// 请求改写（全局，租户 API Key 可单独配置）
+export interface RequestTransform {
+  systemPrepend?: string;
+  systemAppend?: string;
+  stripTools?: string[];
+  maxTokensCap?: number;
+}
+
+export async function getRequestTransform(): Promise<RequestTransform> {
+  const { data } = await api.get<RequestTransform>("/request-transform");
+  return data;
+}
+
+export async function setRequestTransform(transform: RequestTransform): Promise<SuccessResponse> {
+  const { data } = await api.put<SuccessResponse>("/request-transform", transform);
+  return data;
+}
+
+// 出站内容脱敏（请求发往 Kiro 前替换敏感内容）
+export interface RedactionRule {
+  name: string;
+  pattern: string;
+  // pattern 为正则表达式（否则按关键词忽略大小写匹配）
+  regex?: boolean;
+  // 默认 [REDACTED:规则名]
+  replacement?: string;
+  enabled?: boolean;
+}
+
+export interface RedactionConfig {
+  rules: RedactionRule[];
+  // 是否通过 x-kiro-gateway-redactions 响应头返回命中次数
+  reportHeader: boolean;
+}
+
+export async function getRedaction(): Promise<RedactionConfig> {
+  const { data } = await api.get<RedactionConfig>("/redaction");
+  return data;
+}
+
+export async function setRedaction(config: RedactionConfig): Promise<SuccessResponse> {
+  const { data } = await api.put<SuccessResponse>("/redaction", config);
+  return data;
+}
+
+// 响应后处理（文本到达客户端前逐行改写、去重）
+export interface PostProcessRule {
+  name: string;
+  // 正则表达式，逐行匹配
+  pattern: string;
+  // 默认删除匹配内容，替换后为空的行整行删除
+  replacement?: string;
+  // 只作用于回复开头
+  leading?: boolean;
+  enabled?: boolean;
+}
+
+export interface PostProcessConfig {
+  rules: PostProcessRule[];
+  // 删除与上一非空行完全相同的行
+  dedupeLines: boolean;
+}
+
+export async function getPostProcess(): Promise<PostProcessConfig> {
+  const { data } = await api.get<PostProcessConfig>("/post-process");
+  return data;
+}
+
+export async function setPostProcess(config: PostProcessConfig): Promise<SuccessResponse> {
+  const { data } = await api.put<SuccessResponse>("/post-process", config);
+  return data;
+}
+
+// 对话记录（默认关闭，保存请求消息和最终响应）
+export interface TranscriptConfig {
+  enabled: boolean;
+  // 保留天数（0 表示不按时间清理）
+  retentionDays: number;
+  // 记录文件总大小上限（MB，0 表示不限制）
+  maxSizeMb: number;
+}
+
+export interface TranscriptSummary {
+  id: string;
+  timestamp: string;
+  apiKeyId: string | null;
+  model: string;
+  stream: boolean;
+  durationMs: number;
+  messageCount: number;
+  preview: string;
+  stopReason: string | null;
+}
+
+export interface Transcript {
+  id: string;
+  timestamp: string;
+  apiKeyId: string | null;
+  model: string;
+  stream: boolean;
+  durationMs: number;
+  request: { system?: unknown; messages: unknown[]; tools?: unknown[] };
+  response: Record<string, unknown>;
+}
+
+export async function searchTranscripts(params?: {
+  // 租户 API Key ID，main 表示主密钥
+  apiKeyId?: string;
+  model?: string;
+  q?: string;
+  since?: string;
+  until?: string;
+  limit?: number;
+}): Promise<TranscriptSummary[]> {
+  const { data } = await api.get<{ entries: TranscriptSummary[] }>("/transcripts", { params });
+  return data.entries;
+}
+
+export async function getTranscript(id: string): Promise<Transcript> {
+  const { data } = await api.get<Transcript>(`/transcripts/${id}`);
+  return data;
+}
+
+export async function purgeTranscripts(): Promise<SuccessResponse> {
+  const { data } = await api.delete<SuccessResponse>("/transcripts");
+  return data;
+}
+
+export async function getTranscriptConfig(): Promise<TranscriptConfig> {
+  const { data } = await api.get<TranscriptConfig>("/transcripts/config");
+  return data;
+}
+
+export async function setTranscriptConfig(config: TranscriptConfig): Promise<TranscriptConfig> {
+  const { data } = await api.put<TranscriptConfig>("/transcripts/config", config);
+  return data;
+}
+
+// 导入自动分组规则（按顺序匹配，首条命中生效）
+export interface GroupRule {
+  field: "emailDomain" | "subscription" | "authMethod";
+  pattern: string;
+  groupId: string;
+}
+
+export async function getGroupRules(): Promise<GroupRule[]> {
+  const { data } = await api.get<{ rules: GroupRule[] }>("/group-rules");
+  return data.rules;
+}
+
+export async function setGroupRules(rules: GroupRule[]): Promise<SuccessResponse> {
+  const { data } = await api.put<SuccessResponse>("/group-rules", { rules });
+  return data;
+}
+
+// 凭证轮换计划（本地时间段 → 分组 / 凭证，end 可为 24:00）
+export interface RotationRule {
+  start: string;
+  end: string;
+  groupId?: string;
+  credentialId?: number;
+}
+
+export interface RotationScheduleResponse {
+  rules: RotationRule[];
+  activeIndex: number | null;
+}
+
+export async function getRotationSchedule(): Promise<RotationScheduleResponse> {
+  const { data } = await api.get<RotationScheduleResponse>("/rotation-schedule");
+  return data;
+}
+
+export async function setRotationSchedule(rules: RotationRule[]): Promise<SuccessResponse> {
+  const { data } = await api.put<SuccessResponse>("/rotation-schedule", { rules });
+  return data;
+}
+
 // GitHub Release 信息
 export interface GitHubRelease {
   tag_name: string;
@@ -500,3 +1482,16 @@ function compareVersions(v1: string, v2: string): number {
   }
   return 0;
 }
+
+// 轮换 Admin API Key（旧密钥立即失效）
+export async function rotateAdminApiKey(): Promise<{
+  success: boolean;
+  message: string;
+  adminApiKey: string;
+}> {
+  const { data } = await api.post("/admin-key/rotate");
+  if (data.adminApiKey) {
+    storage.setApiKey(data.adminApiKey);
+  }
+  return data;
+}
@@ -9,6 +9,7 @@ import {
 import { Progress } from '@/components/ui/progress'
 import { Copy, Check } from 'lucide-react'
 import { CredentialStatusItem } from '@/types/api'
+import { revealCredential } from '@/api/credentials'
 
 interface BalanceDialogProps {
   credential: CredentialStatusItem | null
@@ -36,6 +37,17 @@ export function BalanceDialog({ credential, open, onOpenChange }: BalanceDialogP
     setTimeout(() => setCopied(null), 1500)
   }
 
+  // 凭证列表中的 Token 已脱敏，复制前获取明文
+  const withRevealed = async (fn: (revealed: CredentialStatusItem) => void) => {
+    if (!credential) return
+    try {
+      const revealed = await revealCredential(credential.id)
+      if (revealed) fn(revealed)
+    } catch {
+      toast.error('获取明文 Token 失败，请检查 Admin API Key')
+    }
+  }
+
   const maskToken = (token: string | null) => {
     if (!token || token.length < 20) return token || '-'
     return token.slice(0, 8) + '...' + token.slice(-8)
@@ -113,7 +125,7 @@ export function BalanceDialog({ credential, open, onOpenChange }: BalanceDialogP
                 <span className="font-mono flex-1 truncate mx-2">{maskToken(credential.refreshToken)}</span>
                 {credential.refreshToken && (
                   <button 
-                    onClick={() => handleCopy(credential.refreshToken!, 'refresh')}
+                    onClick={() => withRevealed((c) => handleCopy(c.refreshToken!, 'refresh'))}
                     className="text-muted-foreground hover:text-primary p-1"
                   >
                     {copied === 'refresh' ? <Check className="h-3 w-3 text-green-500" /> : <Copy className="h-3 w-3" />}
@@ -127,7 +139,7 @@ export function BalanceDialog({ credential, open, onOpenChange }: BalanceDialogP
                 <span className="font-mono flex-1 truncate mx-2">{maskToken(credential.accessToken)}</span>
                 {credential.accessToken && (
                   <button 
-                    onClick={() => handleCopy(credential.accessToken!, 'access')}
+                    onClick={() => withRevealed((c) => handleCopy(c.accessToken!, 'access'))}
                     className="text-muted-foreground hover:text-primary p-1"
                   >
                     {copied === 'access' ? <Check className="h-3 w-3 text-green-500" /> : <Copy className="h-3 w-3" />}
@@ -155,17 +167,17 @@ export function BalanceDialog({ credential, open, onOpenChange }: BalanceDialogP
             {/* 复制 JSON 按钮 */}
             <div className="pt-3 border-t">
               <button
-                onClick={() => {
+                onClick={() => withRevealed((c) => {
                   const json = JSON.stringify({
-                    accessToken: credential.accessToken || '',
-                    refreshToken: credential.refreshToken || '',
-                    profileArn: credential.profileArn || '',
-                    expiresAt: credential.expiresAt || '',
+                    accessToken: c.accessToken || '',
+                    refreshToken: c.refreshToken || '',
+                    profileArn: c.profileArn || '',
+                    expiresAt: c.expiresAt || '',
                     authMethod: 'social',
                     provider: 'Google',
                   }, null, 2)
                   handleCopy(json, 'json')
-                }}
+                })}
                 className="w-full flex items-center justify-center gap-2 px-3 py-2 text-sm bg-muted hover:bg-muted/80 rounded-md transition-colors"
               >
                 {copied === 'json' ? (
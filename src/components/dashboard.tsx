@@ -1393,9 +1393,9 @@ export function Dashboard(_props: DashboardProps) {
                               if (proxyToggling || isDisabled) return
                               setProxyToggling(true)
                               try {
-                                const { setProxyEnabled: setProxyEnabledApi } = await import('@/api/credentials')
-                                await setProxyEnabledApi(!proxyRunning)
-                                setProxyRunning(!proxyRunning)
+                                const { proxyAction } = await import('@/api/credentials')
+                                const result = await proxyAction(proxyRunning ? 'stop' : 'start')
+                                setProxyRunning(result.running)
                                 if (!proxyRunning) {
                                   refetch()
                                 }
@@ -1878,6 +1878,35 @@ export function Dashboard(_props: DashboardProps) {
                   </div>
                 </CardContent>
               </Card>
+
+              <Card>
+                <CardHeader className="pb-3">
+                  <CardTitle className="text-sm flex items-center gap-2">
+                    <Globe className="h-4 w-4" />
+                    浏览器访问
+                  </CardTitle>
+                </CardHeader>
+                <CardContent className="space-y-3">
+                  <div className="text-xs text-muted-foreground">
+                    在浏览器中打开管理页面（自动登录，无需输入 Admin API Key）
+                  </div>
+                  <Button
+                    variant="outline"
+                    size="sm"
+                    onClick={async () => {
+                      try {
+                        const { invoke } = (window as any).__TAURI__.core
+                        await invoke('open_admin_ui')
+                      } catch (e) {
+                        console.error('打开 Admin UI 失败:', e)
+                        toast.error(`打开 Admin UI 失败: ${e}`)
+                      }
+                    }}
+                  >
+                    在浏览器中打开
+                  </Button>
+                </CardContent>
+              </Card>
             </div>
           )}
 
@@ -1944,13 +1973,15 @@ export function Dashboard(_props: DashboardProps) {
         description="确定要重置机器码吗？这将生成新的设备标识。"
         onConfirm={async () => {
           try {
-            const { resetMachineId } = await import('@/api/credentials')
-            const result = await resetMachineId()
+            const { previewResetMachineId, resetMachineId, getMachineId } = await import('@/api/credentials')
+            // 先预览获取确认令牌，再执行写入（写入前当前机器码会自动备份）
+            const preview = await previewResetMachineId()
+            const result = await resetMachineId(preview.confirmToken)
             toast.success(result.message)
             // 刷新机器码显示
-            const { getMachineId } = await import('@/api/credentials')
             const machineIdResult = await getMachineId()
             setCurrentMachineId(machineIdResult.machineId || '')
+            setBackupMachineId(machineIdResult.machineIdBackup || null)
           } catch (e: any) {
             toast.error(e.response?.data?.error?.message || '重置失败')
           }
@@ -1967,13 +1998,15 @@ export function Dashboard(_props: DashboardProps) {
         description="确定要恢复备份的机器码吗？当前机器码将被替换。"
         onConfirm={async () => {
           try {
-            const { restoreMachineId } = await import('@/api/credentials')
-            const result = await restoreMachineId()
+            const { previewRestoreMachineId, restoreMachineId, getMachineId } = await import('@/api/credentials')
+            // 先预览获取确认令牌，再执行写入（写入前当前机器码会自动备份）
+            const preview = await previewRestoreMachineId()
+            const result = await restoreMachineId(preview.confirmToken)
             toast.success(result.message)
             // 刷新机器码显示
-            const { getMachineId } = await import('@/api/credentials')
             const machineIdResult = await getMachineId()
             setCurrentMachineId(machineIdResult.machineId || '')
+            setBackupMachineId(machineIdResult.machineIdBackup || null)
           } catch (e: any) {
             toast.error(e.response?.data?.error?.message || '恢复失败')
           }
@@ -1,7 +1,12 @@
 const API_KEY_STORAGE_KEY = 'adminApiKey'
+// Admin UI 的会话令牌（浏览器中由网关注入页面，桌面内嵌窗口通过 Tauri 命令领取）
+const ADMIN_UI_SESSION_KEY = 'kiroAdminSession'
 
 export const storage = {
   getApiKey: () => localStorage.getItem(API_KEY_STORAGE_KEY),
   setApiKey: (key: string) => localStorage.setItem(API_KEY_STORAGE_KEY, key),
   removeApiKey: () => localStorage.removeItem(API_KEY_STORAGE_KEY),
+  getAdminUiSession: () => sessionStorage.getItem(ADMIN_UI_SESSION_KEY),
+  setAdminUiSession: (token: string) => sessionStorage.setItem(ADMIN_UI_SESSION_KEY, token),
+  removeAdminUiSession: () => sessionStorage.removeItem(ADMIN_UI_SESSION_KEY),
 }
@@ -15,6 +15,8 @@ export interface ParsedError {
   detail?: string
   /** 错误类型 */
   type?: string
+  /** 稳定的机器可读错误码（如 KG1001_CREDENTIAL_NOT_FOUND） */
+  code?: string
 }
 
 /**
@@ -43,6 +45,7 @@ export function parseError(error: unknown): ParsedError {
   if (errorObj && typeof errorObj.message === 'string') {
     const message = errorObj.message
     const type = typeof errorObj.type === 'string' ? errorObj.type : undefined
+    const code = typeof errorObj.code === 'string' ? errorObj.code : undefined
 
     // 解析嵌套的错误信息（如：上游服务错误: 权限不足: 403 {...}）
     const parsed = parseNestedErrorMessage(message)
@@ -51,6 +54,7 @@ export function parseError(error: unknown): ParsedError {
       title: parsed.title,
       detail: parsed.detail,
       type,
+      code,
     }
   }
 
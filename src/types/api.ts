@@ -12,6 +12,7 @@ export interface CredentialStatusItem {
   id: number
   disabled: boolean
   failureCount: number
+  cooldownSecs: number | null
   isCurrent: boolean
   expiresAt: string | null
   authMethod: string | null
@@ -31,6 +32,43 @@ export interface CredentialStatusItem {
   status: 'normal' | 'invalid' | 'expired'
   // 分组 ID
   groupId: string
+  // 凭证单独使用的代理地址（null 表示使用全局代理）
+  proxyUrl: string | null
+  // 凭证单独使用的客户端指纹（null 表示使用全局配置）
+  kiroVersion: string | null
+  systemVersion: string | null
+  nodeVersion: string | null
+  // machineId 盐值
+  machineIdSalt: string | null
+  // 最近一次健康检查结果
+  lastHealthCheck: HealthCheckResult | null
+  // 额度用尽被禁用时，预计自动启用的时间
+  quotaResetAt: string | null
+  // 最近 1 小时 / 24 小时的请求成功率和平均延迟
+  requestStats: CredentialRequestStats
+}
+
+// 单个时间窗口的请求统计
+export interface RequestWindowStats {
+  requests: number
+  failures: number
+  // 成功率（0-1），无请求时为 null
+  successRate: number | null
+  // 成功请求的平均延迟（毫秒）
+  avgLatencyMs: number | null
+}
+
+export interface CredentialRequestStats {
+  lastHour: RequestWindowStats
+  lastDay: RequestWindowStats
+}
+
+// 凭证健康检查结果
+export interface HealthCheckResult {
+  checkedAt: string
+  latencyMs: number
+  healthy: boolean
+  error: string | null
 }
 
 // 余额响应
@@ -51,6 +89,20 @@ export interface BalanceResponse {
   expiresAt: string | null
 }
 
+// 额度快照
+export interface UsageSnapshot {
+  timestamp: string
+  currentUsage: number
+  usageLimit: number
+  remaining: number
+}
+
+// 额度历史响应
+export interface UsageHistoryResponse {
+  id: number
+  points: UsageSnapshot[]
+}
+
 // 成功响应
 export interface SuccessResponse {
   success: boolean
@@ -62,6 +114,8 @@ export interface AdminErrorResponse {
   error: {
     type: string
     message: string
+    /** 稳定的机器可读错误码，如 KG1001_CREDENTIAL_NOT_FOUND */
+    code: string
   }
 }
 
@@ -78,6 +132,22 @@ export interface AddCredentialRequest {
   clientSecret?: string
 }
 
+// 编辑凭证请求（未提供的字段保持不变，空字符串表示清除）
+export interface UpdateCredentialRequest {
+  authMethod?: 'social' | 'idc' | 'builder-id'
+  clientId?: string
+  clientSecret?: string
+  proxyUrl?: string
+  proxyUsername?: string
+  proxyPassword?: string
+  kiroVersion?: string
+  systemVersion?: string
+  nodeVersion?: string
+  machineIdSalt?: string
+  groupId?: string
+  refreshToken?: string
+}
+
 // 添加凭证响应
 export interface AddCredentialResponse {
   success: boolean